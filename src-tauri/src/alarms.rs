@@ -0,0 +1,144 @@
+use crate::camera_plugin::CameraAlarm;
+use crate::events::AppEvent;
+use crate::AppState;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+use tokio::sync::mpsc;
+
+// Cameras repeat an active alarm while it lasts; an event ends this long after the last one
+// unless the camera reports the end itself
+const ALARM_END_SECS: u64 = 10;
+// Wait before reconnecting after the camera's event stream drops
+const RECONNECT_DELAY_SECS: u64 = 15;
+
+/// Follow a camera's own event stream if its plugin has one (vendor plugins such as Hikvision)
+pub fn start(app_handle: &AppHandle, camera_id: i32, camera_type: &str) {
+    let state = app_handle.state::<AppState>();
+    let supported = state.plugin_manager.get_plugin(camera_type).is_some_and(|p| p.supports_alarms());
+    if supported {
+        state.alarm_streams.start(camera_id, watch_camera(app_handle.clone(), camera_id));
+    }
+}
+
+pub fn stop(state: &AppState, camera_id: i32) {
+    if state.alarm_streams.stop(camera_id) {
+        println!("[Alarms] Stopped event stream of camera {}", camera_id);
+    }
+}
+
+/// Start event streams for every camera whose plugin supports them (app startup)
+pub fn start_all(app_handle: &AppHandle) -> Result<(), String> {
+    let state = app_handle.state::<AppState>();
+    let conn = crate::db::open(&state.db_path).map_err(|e| e.to_string())?;
+
+    let cameras = {
+        let mut stmt = conn.prepare("SELECT id, type FROM cameras WHERE archived_at IS NULL").map_err(|e| e.to_string())?;
+        let rows = stmt.query_map([], |row| Ok((row.get::<_, i32>(0)?, row.get::<_, String>(1)?)))
+            .map_err(|e| e.to_string())?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())?;
+        rows
+    };
+
+    for (camera_id, camera_type) in cameras {
+        start(app_handle, camera_id, &camera_type);
+    }
+    Ok(())
+}
+
+async fn watch_camera(app_handle: AppHandle, camera_id: i32) {
+    let state = app_handle.state::<AppState>();
+
+    loop {
+        if let Err(e) = run_stream(&state, camera_id).await {
+            eprintln!("[Alarms] Camera {}: {}", camera_id, e);
+            state.pipelines.error(camera_id, &format!("Event stream: {}", e));
+        }
+        tokio::time::sleep(Duration::from_secs(RECONNECT_DELAY_SECS)).await;
+    }
+}
+
+// One connection to the camera's event stream. The plugin hands alarms over through a
+// channel so they can be stored while it keeps reading.
+async fn run_stream(state: &AppState, camera_id: i32) -> Result<(), String> {
+    let camera = crate::stream::load_camera(&state.db_path, camera_id)?;
+    let plugin = state.plugin_manager.get_plugin(&camera.camera_type)
+        .ok_or_else(|| format!("No plugin for camera type '{}'", camera.camera_type))?;
+
+    let (sender, mut receiver) = mpsc::unbounded_channel();
+    let on_alarm = move |alarm: CameraAlarm| {
+        let _ = sender.send(alarm);
+    };
+    let watch = plugin.watch_alarms(&camera, &on_alarm);
+    tokio::pin!(watch);
+
+    // kind -> (open timeline event, last time the camera reported it active)
+    let mut open: HashMap<String, (i64, Instant)> = HashMap::new();
+    let mut tick = tokio::time::interval(Duration::from_secs(1));
+
+    let result = loop {
+        tokio::select! {
+            result = &mut watch => break result,
+            Some(alarm) = receiver.recv() => {
+                if let Err(e) = handle_alarm(state, camera_id, alarm, &mut open) {
+                    eprintln!("[Alarms] Failed to store alarm of camera {}: {}", camera_id, e);
+                }
+            }
+            _ = tick.tick() => {
+                let ended: Vec<String> = open.iter()
+                    .filter(|(_, (_, last_seen))| last_seen.elapsed() >= Duration::from_secs(ALARM_END_SECS))
+                    .map(|(kind, _)| kind.clone())
+                    .collect();
+                for kind in ended {
+                    if let Some((event_id, _)) = open.remove(&kind) {
+                        end_event(&state.db_path, event_id)?;
+                    }
+                }
+            }
+        }
+    };
+
+    for (event_id, _) in open.into_values() {
+        end_event(&state.db_path, event_id)?;
+    }
+    result
+}
+
+fn handle_alarm(state: &AppState, camera_id: i32, alarm: CameraAlarm, open: &mut HashMap<String, (i64, Instant)>) -> Result<(), String> {
+    if !alarm.active {
+        if let Some((event_id, _)) = open.remove(&alarm.kind) {
+            end_event(&state.db_path, event_id)?;
+        }
+        return Ok(());
+    }
+
+    if let Some((_, last_seen)) = open.get_mut(&alarm.kind) {
+        *last_seen = Instant::now();
+        return Ok(());
+    }
+
+    let mut details = alarm.details;
+    if let Some(channel) = alarm.channel {
+        details["channel"] = serde_json::json!(channel);
+    }
+    let event_id = {
+        let conn = crate::db::open(&state.db_path).map_err(|e| e.to_string())?;
+        crate::timeline::insert_event(&conn, camera_id, &alarm.kind, details)?
+    };
+    open.insert(alarm.kind.clone(), (event_id, Instant::now()));
+
+    println!("[Alarms] Camera {} reported {}", camera_id, alarm.kind);
+    if alarm.kind == "motion" {
+        // Camera-side motion detection counts as certain motion
+        state.events.emit(AppEvent::MotionDetected { camera_id, event_id, score: 1.0 });
+    } else {
+        state.events.emit(AppEvent::CameraAlarm { camera_id, event_id, kind: alarm.kind });
+    }
+    Ok(())
+}
+
+fn end_event(db_path: &str, event_id: i64) -> Result<(), String> {
+    let conn = crate::db::open(db_path).map_err(|e| e.to_string())?;
+    crate::timeline::end_event(&conn, event_id)
+}
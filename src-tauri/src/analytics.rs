@@ -0,0 +1,304 @@
+use crate::events::AppEvent;
+use crate::models::{AnalyticsSettings, ObjectDetectorSettings};
+use crate::object_detector::{category, ObjectDetector};
+use crate::AppState;
+use chrono::Utc;
+use rusqlite::Connection;
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+use tokio::io::AsyncReadExt;
+
+// Frames are scaled to this width before detection (models resize to ~640 anyway)
+const SAMPLE_WIDTH: u32 = 640;
+// How often an idle sampler checks whether the camera's stream has started
+const STREAM_POLL_SECS: u64 = 10;
+// Frames with detections are stored under the recordings' thumbnails directory
+const DETECTION_DIR: &str = "thumbnails/detections";
+
+// Detector shared by all cameras, keyed by the settings it was built from, so a model is
+// loaded once rather than per camera
+static DETECTOR: Mutex<Option<(String, Arc<dyn ObjectDetector>)>> = Mutex::new(None);
+
+/// Backends compiled into this build
+pub fn available_backends() -> Vec<String> {
+    let mut backends = vec!["http".to_string()];
+    if cfg!(feature = "onnx") {
+        backends.push("onnx".to_string());
+    }
+    backends
+}
+
+pub fn load_analytics_settings(conn: &Connection) -> Result<AnalyticsSettings, String> {
+    conn.query_row(
+        "SELECT backend, model_path, endpoint_url, sample_fps FROM analytics_settings WHERE id = 1",
+        [],
+        |row| Ok(AnalyticsSettings {
+            id: 1,
+            backend: row.get(0)?,
+            modelPath: row.get(1)?,
+            endpointUrl: row.get(2)?,
+            sampleFps: row.get(3)?,
+            availableBackends: available_backends(),
+        }),
+    ).map_err(|e| e.to_string())
+}
+
+fn build_detector(settings: &AnalyticsSettings) -> Result<Arc<dyn ObjectDetector>, String> {
+    match settings.backend.as_str() {
+        "http" => {
+            let endpoint = settings.endpointUrl.as_deref()
+                .filter(|url| !url.trim().is_empty())
+                .ok_or("No detection server URL configured")?;
+            Ok(Arc::new(crate::detectors::HttpDetector::new(endpoint)?))
+        }
+        #[cfg(feature = "onnx")]
+        "onnx" => {
+            let model_path = settings.modelPath.as_deref()
+                .filter(|path| !path.trim().is_empty())
+                .ok_or("No ONNX model configured")?;
+            Ok(Arc::new(crate::detectors::OnnxDetector::new(model_path)?))
+        }
+        #[cfg(not(feature = "onnx"))]
+        "onnx" => Err("This build has no ONNX support (build with the \"onnx\" feature)".to_string()),
+        other => Err(format!("Unknown detection backend '{}'", other)),
+    }
+}
+
+fn shared_detector(settings: &AnalyticsSettings) -> Result<Arc<dyn ObjectDetector>, String> {
+    let key = format!("{}|{:?}|{:?}", settings.backend, settings.modelPath, settings.endpointUrl);
+    let mut cached = DETECTOR.lock().map_err(|e| e.to_string())?;
+
+    if let Some((cached_key, detector)) = cached.as_ref() {
+        if *cached_key == key {
+            return Ok(detector.clone());
+        }
+    }
+
+    let detector = build_detector(settings)?;
+    *cached = Some((key, detector.clone()));
+    Ok(detector)
+}
+
+/// Settings of one camera; cameras without a row get the defaults (disabled)
+pub fn load_settings(conn: &Connection, camera_id: i32) -> Result<ObjectDetectorSettings, String> {
+    let settings = conn.query_row(
+        "SELECT enabled, min_confidence, classes, cooldown_secs FROM object_detectors WHERE camera_id = ?1",
+        [camera_id],
+        |row| Ok(ObjectDetectorSettings {
+            cameraId: camera_id,
+            enabled: row.get(0)?,
+            minConfidence: row.get(1)?,
+            classes: serde_json::from_str(&row.get::<_, String>(2)?).unwrap_or_default(),
+            cooldownSecs: row.get(3)?,
+            running: false,
+        }),
+    );
+
+    match settings {
+        Ok(settings) => Ok(settings),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(ObjectDetectorSettings {
+            cameraId: camera_id,
+            enabled: false,
+            minConfidence: 0.5,
+            classes: vec!["person".to_string(), "vehicle".to_string(), "animal".to_string()],
+            cooldownSecs: 10,
+            running: false,
+        }),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Start (or restart with fresh settings) object detection for a camera
+pub fn start(app_handle: &AppHandle, camera_id: i32) {
+    let state = app_handle.state::<AppState>();
+    state.object_detectors.start(camera_id, analyse_camera(app_handle.clone(), camera_id));
+}
+
+pub fn stop(state: &AppState, camera_id: i32) {
+    if state.object_detectors.stop(camera_id) {
+        println!("[Analytics] Stopped object detection for camera {}", camera_id);
+    }
+}
+
+/// Start detection for every camera with object detection enabled (app startup, backend change)
+pub fn start_enabled(app_handle: &AppHandle) -> Result<(), String> {
+    let state = app_handle.state::<AppState>();
+    let conn = crate::db::open(&state.db_path).map_err(|e| e.to_string())?;
+
+    let camera_ids = {
+        let mut stmt = conn.prepare("SELECT camera_id FROM object_detectors WHERE enabled = 1 AND camera_id IN (SELECT id FROM cameras WHERE archived_at IS NULL)")
+            .map_err(|e| e.to_string())?;
+        let ids = stmt.query_map([], |row| row.get::<_, i32>(0))
+            .map_err(|e| e.to_string())?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())?;
+        ids
+    };
+
+    for camera_id in camera_ids {
+        start(app_handle, camera_id);
+    }
+    Ok(())
+}
+
+async fn analyse_camera(app_handle: AppHandle, camera_id: i32) {
+    let state = app_handle.state::<AppState>();
+    println!("[Analytics] Object detection enabled for camera {}", camera_id);
+
+    loop {
+        let settings = match crate::db::open(&state.db_path).map_err(|e| e.to_string())
+            .and_then(|conn| load_settings(&conn, camera_id))
+        {
+            Ok(settings) => settings,
+            Err(e) => {
+                eprintln!("[Analytics] Failed to load settings of camera {}: {}", camera_id, e);
+                break;
+            }
+        };
+        if !settings.enabled {
+            break;
+        }
+
+        if let Err(e) = run_sampler(&state, &settings).await {
+            eprintln!("[Analytics] Camera {}: {}", camera_id, e);
+            state.pipelines.error(camera_id, &format!("Object detection: {}", e));
+        }
+
+        tokio::time::sleep(Duration::from_secs(STREAM_POLL_SECS)).await;
+    }
+
+    println!("[Analytics] Object detection for camera {} finished", camera_id);
+}
+
+// Sample JPEG frames from the camera's live HLS stream while it runs and pass them to the detector
+async fn run_sampler(state: &AppState, settings: &ObjectDetectorSettings) -> Result<(), String> {
+    let camera_id = settings.cameraId;
+    let playlist = state.stream_dir.join(camera_id.to_string()).join("index.m3u8");
+    if !playlist.exists() {
+        // Only active streams are analysed; check again later
+        return Ok(());
+    }
+
+    let analytics = {
+        let conn = crate::db::open(&state.db_path).map_err(|e| e.to_string())?;
+        load_analytics_settings(&conn)?
+    };
+    let detector = shared_detector(&analytics)?;
+
+    let mut cmd = tokio::process::Command::new("ffmpeg");
+    cmd.args(["-hide_banner", "-loglevel", "error", "-live_start_index", "-1", "-i"])
+        .arg(&playlist)
+        .args(["-an", "-vf"])
+        .arg(format!("fps={},scale={}:-2", analytics.sampleFps, SAMPLE_WIDTH))
+        .args(state.pipelines.run_marker_args())
+        .args(["-f", "image2pipe", "-c:v", "mjpeg", "-q:v", "5", "pipe:1"])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .kill_on_drop(true);
+
+    #[cfg(target_os = "windows")]
+    {
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        cmd.creation_flags(CREATE_NO_WINDOW);
+    }
+
+    let mut child = cmd.spawn().map_err(|e| format!("Failed to start ffmpeg: {}", e))?;
+    let _process = state.pipelines.adopt_tokio_process(&child);
+    let mut stdout = child.stdout.take().ok_or("Failed to capture ffmpeg output")?;
+    println!("[Analytics] Sampling camera {} at {} fps with the {} backend", camera_id, analytics.sampleFps, detector.name());
+
+    let cooldown = Duration::from_secs(settings.cooldownSecs.max(0) as u64);
+    let mut last_event: Option<Instant> = None;
+    let mut buffer: Vec<u8> = Vec::new();
+    let mut chunk = vec![0u8; 64 * 1024];
+
+    loop {
+        let read = stdout.read(&mut chunk).await.map_err(|e| e.to_string())?;
+        if read == 0 {
+            break;
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+
+        // image2pipe writes whole JPEGs back to back; each ends with the EOI marker
+        while let Some(end) = buffer.windows(2).position(|w| w == [0xFF, 0xD9]) {
+            let frame: Vec<u8> = buffer.drain(..end + 2).collect();
+            if last_event.is_some_and(|at| at.elapsed() < cooldown) {
+                continue;
+            }
+            if analyse_frame(state, settings, detector.as_ref(), &frame).await? {
+                last_event = Some(Instant::now());
+            }
+        }
+    }
+
+    // Stream stopped (or restarted); the caller waits for it to come back
+    let _ = child.wait().await;
+    Ok(())
+}
+
+// Returns whether the frame produced an event
+async fn analyse_frame(
+    state: &AppState,
+    settings: &ObjectDetectorSettings,
+    detector: &dyn ObjectDetector,
+    frame: &[u8],
+) -> Result<bool, String> {
+    let camera_id = settings.cameraId;
+    let objects: Vec<serde_json::Value> = detector.detect(frame).await?
+        .into_iter()
+        .filter(|d| d.confidence >= settings.minConfidence as f32)
+        .filter_map(|d| {
+            let category = category(&d.label).filter(|c| settings.classes.iter().any(|class| class == c))?;
+            Some(serde_json::json!({
+                "label": d.label,
+                "category": category,
+                "confidence": d.confidence,
+                "box": d.bbox,
+            }))
+        })
+        .collect();
+
+    if objects.is_empty() {
+        return Ok(false);
+    }
+
+    // Keep the frame and its boxes next to the recording thumbnails
+    let captured_at = Utc::now();
+    let stem = format!(
+        "{}/{}/{}",
+        DETECTION_DIR,
+        camera_id,
+        captured_at.with_timezone(&crate::settings::timezone()).format("%Y%m%d_%H%M%S_%3f")
+    );
+    let image = format!("{}.jpg", stem);
+    let image_path = state.recording_dir.join(&image);
+    if let Some(parent) = image_path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    std::fs::write(&image_path, frame).map_err(|e| e.to_string())?;
+
+    let details = serde_json::json!({
+        "backend": detector.name(),
+        "image": image,
+        "objects": objects,
+    });
+    std::fs::write(state.recording_dir.join(format!("{}.json", stem)), details.to_string())
+        .map_err(|e| e.to_string())?;
+
+    let mut labels: Vec<String> = objects.iter()
+        .filter_map(|o| o["category"].as_str().map(|c| c.to_string()))
+        .collect();
+    labels.sort();
+    labels.dedup();
+
+    let event_id = {
+        let conn = crate::db::open(&state.db_path).map_err(|e| e.to_string())?;
+        crate::timeline::insert_event(&conn, camera_id, "object", details)?
+    };
+
+    println!("[Analytics] Camera {}: detected {}", camera_id, labels.join(", "));
+    state.events.emit(AppEvent::ObjectDetected { camera_id, event_id, labels, image });
+    Ok(true)
+}
@@ -0,0 +1,379 @@
+use crate::events::{AppEvent, EventBus};
+use crate::models::{ArchiveJob, ArchiveSettings};
+use bytes::Bytes;
+use http_body::Frame;
+use http_body_util::StreamBody;
+use rusqlite::Connection;
+use sha1::{Digest, Sha1};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tokio::fs::File;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::sync::mpsc;
+
+const CHUNK_SIZE: usize = 1024 * 1024;
+// Finished jobs kept around so the UI can show recent results
+const MAX_FINISHED_JOBS: usize = 50;
+
+pub fn load_settings(conn: &Connection) -> Result<ArchiveSettings, String> {
+    conn.query_row(
+        "SELECT id, target_type, target, webdav_user, webdav_pass, auto_archive FROM archive_settings WHERE id = 1",
+        [],
+        |row| {
+            Ok(ArchiveSettings {
+                id: row.get(0)?,
+                targetType: row.get(1)?,
+                target: row.get(2)?,
+                webdavUser: row.get(3)?,
+                webdavPass: row.get(4)?,
+                autoArchive: row.get(5)?,
+            })
+        },
+    ).map_err(|e| e.to_string())
+}
+
+/// Transfer queue for archiving recordings (stored in AppState).
+/// Jobs run one at a time on a background task and report `archive-progress` events.
+#[derive(Clone)]
+pub struct ArchiveQueue {
+    sender: mpsc::UnboundedSender<i32>,
+    worker: Arc<ArchiveWorker>,
+}
+
+struct ArchiveWorker {
+    db_path: String,
+    recording_dir: PathBuf,
+    http: reqwest::Client,
+    progress: ProgressReporter,
+}
+
+// Shared job table plus event emission, cloned into upload streams
+#[derive(Clone)]
+struct ProgressReporter {
+    events: EventBus,
+    jobs: Arc<Mutex<Vec<ArchiveJob>>>,
+}
+
+impl ProgressReporter {
+    fn update<F: FnOnce(&mut ArchiveJob)>(&self, recording_id: i32, f: F) {
+        let job = {
+            let Ok(mut jobs) = self.jobs.lock() else { return };
+            let Some(job) = jobs.iter_mut().rev().find(|j| j.recording_id == recording_id) else { return };
+            f(job);
+            job.clone()
+        };
+
+        self.events.emit(AppEvent::ArchiveProgress(job));
+    }
+
+    // Only emit when the whole-percent value changes to keep event volume down
+    fn bytes(&self, recording_id: i32, bytes_done: u64) {
+        let changed = {
+            let Ok(mut jobs) = self.jobs.lock() else { return };
+            let Some(job) = jobs.iter_mut().rev().find(|j| j.recording_id == recording_id) else { return };
+            let percent = |done: u64| (done * 100).checked_div(job.bytes_total).unwrap_or(100);
+            let changed = percent(job.bytes_done) != percent(bytes_done);
+            job.bytes_done = bytes_done;
+            changed
+        };
+
+        if changed {
+            self.update(recording_id, |_| {});
+        }
+    }
+}
+
+impl ArchiveQueue {
+    /// Create the queue and start its worker task
+    pub fn start(db_path: String, recording_dir: PathBuf, events: EventBus) -> Result<Self, String> {
+        // No overall timeout: large recordings can take a long time to transfer
+        let http = reqwest::Client::builder()
+            .connect_timeout(std::time::Duration::from_secs(10))
+            .build()
+            .map_err(|e| e.to_string())?;
+
+        let worker = Arc::new(ArchiveWorker {
+            db_path,
+            recording_dir,
+            http,
+            progress: ProgressReporter { events, jobs: Arc::new(Mutex::new(Vec::new())) },
+        });
+
+        let (sender, mut receiver) = mpsc::unbounded_channel::<i32>();
+        let loop_worker = worker.clone();
+        tauri::async_runtime::spawn(async move {
+            while let Some(recording_id) = receiver.recv().await {
+                loop_worker.run_job(recording_id).await;
+            }
+        });
+
+        Ok(Self { sender, worker })
+    }
+
+    /// Queue a recording for archiving
+    pub fn enqueue(&self, recording_id: i32) -> Result<(), String> {
+        let (filename, is_finished) = {
+            let conn = crate::db::open(&self.worker.db_path).map_err(|e| e.to_string())?;
+            conn.query_row(
+                "SELECT filename, is_finished FROM recordings WHERE id = ?1",
+                [recording_id],
+                |row| Ok((row.get::<_, String>(0)?, row.get::<_, bool>(1)?)),
+            ).map_err(|e| e.to_string())?
+        };
+
+        if !is_finished {
+            return Err("Recording is still in progress".to_string());
+        }
+
+        {
+            let mut jobs = self.worker.progress.jobs.lock().map_err(|e| e.to_string())?;
+            let pending = jobs.iter().any(|j| {
+                j.recording_id == recording_id && !matches!(j.status.as_str(), "done" | "failed")
+            });
+            if pending {
+                return Err("Recording is already queued for archiving".to_string());
+            }
+
+            // Drop the oldest finished jobs
+            while jobs.len() >= MAX_FINISHED_JOBS {
+                match jobs.iter().position(|j| matches!(j.status.as_str(), "done" | "failed")) {
+                    Some(index) => { jobs.remove(index); }
+                    None => break,
+                }
+            }
+
+            jobs.push(ArchiveJob {
+                recording_id,
+                filename,
+                status: "queued".to_string(),
+                bytes_done: 0,
+                bytes_total: 0,
+                location: None,
+                error: None,
+            });
+        }
+
+        self.sender.send(recording_id).map_err(|e| e.to_string())?;
+        println!("[Archive] Queued recording {}", recording_id);
+        Ok(())
+    }
+
+    /// Current and recent jobs
+    pub fn snapshot(&self) -> Vec<ArchiveJob> {
+        self.worker.progress.jobs.lock().map(|jobs| jobs.clone()).unwrap_or_default()
+    }
+
+    /// Archive a recording inline if auto-archive is enabled and it has no archived copy yet.
+    /// Called before a recording is deleted; returns true if it was archived now.
+    pub async fn archive_before_delete(&self, recording_id: i32) -> Result<bool, String> {
+        let (settings, archived) = {
+            let conn = crate::db::open(&self.worker.db_path).map_err(|e| e.to_string())?;
+            let archived: Option<String> = conn.query_row(
+                "SELECT archive_location FROM recordings WHERE id = ?1",
+                [recording_id],
+                |row| row.get(0),
+            ).map_err(|e| e.to_string())?;
+            (load_settings(&conn)?, archived.is_some())
+        };
+
+        if !settings.autoArchive || archived {
+            return Ok(false);
+        }
+
+        self.worker.archive(recording_id).await?;
+        Ok(true)
+    }
+}
+
+impl ArchiveWorker {
+    async fn run_job(&self, recording_id: i32) {
+        match self.archive(recording_id).await {
+            Ok(location) => {
+                println!("[Archive] Recording {} archived to {}", recording_id, location);
+                self.progress.update(recording_id, |job| {
+                    job.status = "done".to_string();
+                    job.location = Some(location);
+                });
+            }
+            Err(e) => {
+                eprintln!("[Archive] Failed to archive recording {}: {}", recording_id, e);
+                self.progress.update(recording_id, |job| {
+                    job.status = "failed".to_string();
+                    job.error = Some(e);
+                });
+            }
+        }
+    }
+
+    async fn archive(&self, recording_id: i32) -> Result<String, String> {
+        let (settings, filename, source) = {
+            let conn = crate::db::open(&self.db_path).map_err(|e| e.to_string())?;
+            let filename: String = conn.query_row(
+                "SELECT filename FROM recordings WHERE id = ?1",
+                [recording_id],
+                |row| row.get(0),
+            ).map_err(|e| e.to_string())?;
+            let source = crate::storage::recording_path(&conn, &self.recording_dir, recording_id)?;
+            (load_settings(&conn)?, filename, source)
+        };
+
+        let target = settings.target.clone()
+            .filter(|t| !t.trim().is_empty())
+            .ok_or("No archive target configured")?;
+
+        let total = tokio::fs::metadata(&source).await
+            .map_err(|e| format!("Recording file {} is not readable: {}", filename, e))?
+            .len();
+
+        self.progress.update(recording_id, |job| {
+            job.status = "transferring".to_string();
+            job.bytes_total = total;
+            job.bytes_done = 0;
+        });
+
+        let (location, checksum) = match settings.targetType.as_str() {
+            "local" => self.copy_local(recording_id, &source, Path::new(&target), &filename).await?,
+            "webdav" => self.upload_webdav(recording_id, &source, &target, &filename, &settings).await?,
+            other => return Err(format!("Unknown archive target type '{}'", other)),
+        };
+
+        let conn = crate::db::open(&self.db_path).map_err(|e| e.to_string())?;
+        conn.execute(
+            "UPDATE recordings SET archive_location = ?1, archived_at = ?2, archive_checksum = ?3 WHERE id = ?4",
+            rusqlite::params![location, chrono::Utc::now().to_rfc3339(), checksum, recording_id],
+        ).map_err(|e| e.to_string())?;
+
+        Ok(location)
+    }
+
+    // Copy to a directory (local disk or a mounted SMB/NFS share), then re-read the copy to verify it
+    async fn copy_local(&self, recording_id: i32, source: &Path, dir: &Path, filename: &str) -> Result<(String, String), String> {
+        tokio::fs::create_dir_all(dir).await
+            .map_err(|e| format!("Failed to create archive directory: {}", e))?;
+
+        let dest = dir.join(filename);
+        let partial = dir.join(format!("{}.part", filename));
+
+        let mut input = File::open(source).await.map_err(|e| e.to_string())?;
+        let mut output = File::create(&partial).await.map_err(|e| e.to_string())?;
+        let mut hasher = Sha1::new();
+        let mut buf = vec![0u8; CHUNK_SIZE];
+        let mut done = 0u64;
+
+        loop {
+            let n = input.read(&mut buf).await.map_err(|e| e.to_string())?;
+            if n == 0 {
+                break;
+            }
+            hasher.update(&buf[..n]);
+            output.write_all(&buf[..n]).await.map_err(|e| format!("Failed to write archive copy: {}", e))?;
+            done += n as u64;
+            self.progress.bytes(recording_id, done);
+        }
+
+        output.flush().await.map_err(|e| e.to_string())?;
+        output.sync_all().await.map_err(|e| e.to_string())?;
+        drop(output);
+
+        let checksum = to_hex(&hasher.finalize());
+
+        self.progress.update(recording_id, |job| job.status = "verifying".to_string());
+        let written = hash_file(&partial).await?;
+        if written != checksum {
+            let _ = tokio::fs::remove_file(&partial).await;
+            return Err("Checksum mismatch after copying to the archive".to_string());
+        }
+
+        tokio::fs::rename(&partial, &dest).await.map_err(|e| e.to_string())?;
+        Ok((dest.to_string_lossy().to_string(), checksum))
+    }
+
+    // PUT to a WebDAV collection, then download the copy again to verify it
+    async fn upload_webdav(
+        &self,
+        recording_id: i32,
+        source: &Path,
+        collection_url: &str,
+        filename: &str,
+        settings: &ArchiveSettings,
+    ) -> Result<(String, String), String> {
+        let url = format!("{}/{}", collection_url.trim_end_matches('/'), urlencoding::encode(filename));
+        let checksum = hash_file(source).await?;
+
+        let file = File::open(source).await.map_err(|e| e.to_string())?;
+        let total = file.metadata().await.map_err(|e| e.to_string())?.len();
+        let progress = self.progress.clone();
+
+        // Stream the file instead of loading whole recordings into memory
+        let stream = futures::stream::unfold((Some(file), 0u64), move |(file, done)| {
+            let progress = progress.clone();
+            async move {
+                let mut file = file?;
+                let mut buf = vec![0u8; CHUNK_SIZE];
+                match file.read(&mut buf).await {
+                    Ok(0) => None,
+                    Ok(n) => {
+                        buf.truncate(n);
+                        let done = done + n as u64;
+                        progress.bytes(recording_id, done);
+                        Some((Ok(Frame::data(Bytes::from(buf))), (Some(file), done)))
+                    }
+                    Err(e) => Some((Err(e), (None, done))),
+                }
+            }
+        });
+
+        let mut request = self.http.put(&url)
+            .header(reqwest::header::CONTENT_LENGTH, total)
+            .body(reqwest::Body::wrap(StreamBody::new(stream)));
+        if let Some(user) = settings.webdavUser.as_deref().filter(|u| !u.is_empty()) {
+            request = request.basic_auth(user, settings.webdavPass.as_deref());
+        }
+
+        let res = request.send().await.map_err(|e| format!("WebDAV upload failed: {}", e))?;
+        if !res.status().is_success() {
+            return Err(format!("WebDAV upload failed with HTTP status {}", res.status()));
+        }
+
+        self.progress.update(recording_id, |job| job.status = "verifying".to_string());
+
+        let mut request = self.http.get(&url);
+        if let Some(user) = settings.webdavUser.as_deref().filter(|u| !u.is_empty()) {
+            request = request.basic_auth(user, settings.webdavPass.as_deref());
+        }
+        let mut res = request.send().await.map_err(|e| format!("WebDAV verification failed: {}", e))?;
+        if !res.status().is_success() {
+            return Err(format!("WebDAV verification failed with HTTP status {}", res.status()));
+        }
+
+        let mut hasher = Sha1::new();
+        while let Some(chunk) = res.chunk().await.map_err(|e| e.to_string())? {
+            hasher.update(&chunk);
+        }
+        if to_hex(&hasher.finalize()) != checksum {
+            return Err("Checksum mismatch after uploading to WebDAV".to_string());
+        }
+
+        Ok((url, checksum))
+    }
+}
+
+async fn hash_file(path: &Path) -> Result<String, String> {
+    let mut file = File::open(path).await.map_err(|e| e.to_string())?;
+    let mut hasher = Sha1::new();
+    let mut buf = vec![0u8; CHUNK_SIZE];
+
+    loop {
+        let n = file.read(&mut buf).await.map_err(|e| e.to_string())?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+
+    Ok(to_hex(&hasher.finalize()))
+}
+
+pub(crate) fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
@@ -0,0 +1,196 @@
+use crate::events::AppEvent;
+use crate::models::{AudioMonitorSettings, RecordingMeta, RecordingTrigger};
+use crate::AppState;
+use rusqlite::Connection;
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+use tokio::io::{AsyncBufReadExt, BufReader};
+
+// Sound must stay below the threshold this long before a new loud interval can start
+const QUIET_SECS: f64 = 0.5;
+// Wait before reconnecting after the analysing FFmpeg exits (camera offline, no audio track, ...)
+const RESTART_DELAY_SECS: u64 = 30;
+
+/// Start (or restart with fresh settings) monitoring a camera
+pub fn start(app_handle: &AppHandle, camera_id: i32) {
+    let state = app_handle.state::<AppState>();
+    state.audio_monitors.start(camera_id, monitor_camera(app_handle.clone(), camera_id));
+}
+
+pub fn stop(state: &AppState, camera_id: i32) {
+    if state.audio_monitors.stop(camera_id) {
+        println!("[Audio] Stopped monitoring camera {}", camera_id);
+    }
+}
+
+/// Settings of one camera; cameras without a row get the defaults (disabled)
+pub fn load_settings(conn: &Connection, camera_id: i32) -> Result<AudioMonitorSettings, String> {
+    let settings = conn.query_row(
+        "SELECT enabled, threshold_db, cooldown_secs, record_on_alert, record_minutes
+         FROM audio_monitors WHERE camera_id = ?1",
+        [camera_id],
+        |row| Ok(AudioMonitorSettings {
+            cameraId: camera_id,
+            enabled: row.get(0)?,
+            thresholdDb: row.get(1)?,
+            cooldownSecs: row.get(2)?,
+            recordOnAlert: row.get(3)?,
+            recordMinutes: row.get(4)?,
+            running: false,
+        }),
+    );
+
+    match settings {
+        Ok(settings) => Ok(settings),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(AudioMonitorSettings {
+            cameraId: camera_id,
+            enabled: false,
+            thresholdDb: -20.0,
+            cooldownSecs: 60,
+            recordOnAlert: false,
+            recordMinutes: 5,
+            running: false,
+        }),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Start monitors for every camera with audio monitoring enabled (app startup)
+pub fn start_enabled(app_handle: &AppHandle) -> Result<(), String> {
+    let state = app_handle.state::<AppState>();
+    let conn = crate::db::open(&state.db_path).map_err(|e| e.to_string())?;
+
+    let camera_ids = {
+        let mut stmt = conn.prepare("SELECT camera_id FROM audio_monitors WHERE enabled = 1 AND camera_id IN (SELECT id FROM cameras WHERE archived_at IS NULL)")
+            .map_err(|e| e.to_string())?;
+        let ids = stmt.query_map([], |row| row.get::<_, i32>(0))
+            .map_err(|e| e.to_string())?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())?;
+        ids
+    };
+
+    for camera_id in camera_ids {
+        start(app_handle, camera_id);
+    }
+    Ok(())
+}
+
+async fn monitor_camera(app_handle: AppHandle, camera_id: i32) {
+    let state = app_handle.state::<AppState>();
+    println!("[Audio] Monitoring camera {}", camera_id);
+
+    loop {
+        let settings = match crate::db::open(&state.db_path).map_err(|e| e.to_string())
+            .and_then(|conn| load_settings(&conn, camera_id))
+        {
+            Ok(settings) => settings,
+            Err(e) => {
+                eprintln!("[Audio] Failed to load settings of camera {}: {}", camera_id, e);
+                break;
+            }
+        };
+        if !settings.enabled {
+            break;
+        }
+
+        if let Err(e) = run_detector(&state, &settings).await {
+            eprintln!("[Audio] Camera {}: {}", camera_id, e);
+            state.pipelines.error(camera_id, &format!("Audio monitor: {}", e));
+        }
+
+        tokio::time::sleep(Duration::from_secs(RESTART_DELAY_SECS)).await;
+    }
+
+    println!("[Audio] Monitor for camera {} finished", camera_id);
+}
+
+// Run FFmpeg's silencedetect with the threshold as its noise floor: "silence_end" marks sound
+// rising above the threshold and "silence_start" marks it falling back below
+async fn run_detector(state: &AppState, settings: &AudioMonitorSettings) -> Result<(), String> {
+    let camera_id = settings.cameraId;
+    let camera = crate::stream::load_camera(&state.db_path, camera_id)?;
+    if crate::stream::is_local_device(&camera.camera_type) {
+        return Err("Audio monitoring needs a network camera stream".to_string());
+    }
+    let source = crate::stream::get_rtsp_url(&state.onvif_http, &camera).await?;
+
+    let mut cmd = tokio::process::Command::new("ffmpeg");
+    cmd.args(["-hide_banner", "-nostats"])
+        .args(crate::stream::network_input_args(&camera, &source))
+        .args(["-i", &source, "-vn", "-af"])
+        .arg(format!("silencedetect=noise={}dB:d={}", settings.thresholdDb, QUIET_SECS))
+        .args(state.pipelines.run_marker_args())
+        .args(["-f", "null", "-"])
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+
+    #[cfg(target_os = "windows")]
+    {
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        cmd.creation_flags(CREATE_NO_WINDOW);
+    }
+
+    let mut child = cmd.spawn().map_err(|e| format!("Failed to start ffmpeg: {}", e))?;
+    let _process = state.pipelines.adopt_tokio_process(&child);
+    let stderr = child.stderr.take().ok_or("Failed to capture ffmpeg output")?;
+    let mut lines = BufReader::new(stderr).lines();
+
+    let cooldown = Duration::from_secs(settings.cooldownSecs.max(0) as u64);
+    let mut last_alert: Option<Instant> = None;
+    let mut open_event: Option<i64> = None;
+    let mut last_output = String::new();
+
+    while let Some(line) = lines.next_line().await.map_err(|e| e.to_string())? {
+        if line.contains("silence_end:") {
+            if last_alert.is_some_and(|at| at.elapsed() < cooldown) {
+                continue;
+            }
+            last_alert = Some(Instant::now());
+            open_event = Some(raise_alert(state, settings).await?);
+        } else if line.contains("silence_start:") {
+            if let Some(event_id) = open_event.take() {
+                end_event(&state.db_path, event_id)?;
+            }
+        } else if !line.trim().is_empty() {
+            last_output = line;
+        }
+    }
+
+    if let Some(event_id) = open_event {
+        end_event(&state.db_path, event_id)?;
+    }
+
+    let status = child.wait().await.map_err(|e| e.to_string())?;
+    Err(format!("FFmpeg exited with {}: {}", status, crate::redact::redact(last_output.trim())))
+}
+
+// Store the alert as a timeline event, notify the UI and optionally start a recording
+async fn raise_alert(state: &AppState, settings: &AudioMonitorSettings) -> Result<i64, String> {
+    let camera_id = settings.cameraId;
+    let event_id = {
+        let conn = crate::db::open(&state.db_path).map_err(|e| e.to_string())?;
+        crate::timeline::insert_event(&conn, camera_id, "audio", serde_json::json!({ "thresholdDb": settings.thresholdDb }))?
+    };
+
+    println!("[Audio] Sound above {} dB on camera {}", settings.thresholdDb, camera_id);
+    state.events.emit(AppEvent::AudioDetected { camera_id, event_id, threshold_db: settings.thresholdDb });
+
+    let already_recording = state.recording_processes.lock().map_err(|e| e.to_string())?.contains_key(&camera_id);
+    if settings.recordOnAlert && !already_recording {
+        let meta = RecordingMeta::new(RecordingTrigger::Audio);
+        match crate::stream::start_recording_with_options_direct(state, camera_id, None, meta).await {
+            Ok(recording_id) => crate::stream::schedule_auto_stop(state, camera_id, recording_id, settings.recordMinutes),
+            Err(e) => eprintln!("[Audio] Failed to start recording for camera {}: {}", camera_id, e),
+        }
+    }
+
+    Ok(event_id)
+}
+
+fn end_event(db_path: &str, event_id: i64) -> Result<(), String> {
+    let conn = crate::db::open(db_path).map_err(|e| e.to_string())?;
+    crate::timeline::end_event(&conn, event_id)
+}
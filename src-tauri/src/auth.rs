@@ -0,0 +1,320 @@
+use crate::models::{AppUser, AuthSession};
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use axum::extract::State;
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::post;
+use axum::{Json, Router};
+use chrono::{DateTime, Duration, Utc};
+use rusqlite::{Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+const SESSION_HOURS: i64 = 12;
+const MIN_PASSWORD_LEN: usize = 8;
+
+/// App roles, each including the ones below it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    // Watch live streams and recordings
+    Viewer,
+    // Also record, snapshot and move PTZ cameras
+    Operator,
+    // Also manage cameras, schedules, users and settings
+    Admin,
+}
+
+impl Role {
+    pub fn parse(value: &str) -> Result<Role, String> {
+        match value {
+            "viewer" => Ok(Role::Viewer),
+            "operator" => Ok(Role::Operator),
+            "admin" => Ok(Role::Admin),
+            other => Err(format!("Unknown role: {}", other)),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Viewer => "viewer",
+            Role::Operator => "operator",
+            Role::Admin => "admin",
+        }
+    }
+}
+
+/// Role a Tauri command needs when login is required; None for the login commands themselves.
+/// Commands not listed here are admin-only.
+pub fn required_role(command: &str) -> Option<Role> {
+    match command {
+        "login" | "logout" | "get_auth_status" | "ping" => None,
+
+        "get_cameras" | "get_archived_cameras" | "get_camera_groups" | "start_stream" | "stop_stream" | "get_timeshift_info"
+        | "get_recordings" | "get_recording_sessions" | "get_recording_gaps" | "get_recording_timeline"
+        | "get_events" | "start_playback_session" | "start_recording_session_playback"
+        | "stop_playback_session" | "get_snapshots"
+        | "get_recording_schedules" | "get_schedule_history" | "get_recording_cameras"
+        | "get_pipeline_status" | "generate_report" | "get_camera_capabilities" | "check_ptz_capabilities"
+        | "get_archive_queue" | "get_upload_queue" | "get_jobs" | "get_server_token" | "get_server_port"
+        | "get_app_settings" | "change_password" | "get_resource_usage"
+        | "get_quality_presets" | "get_load_budget" => Some(Role::Viewer),
+
+        "start_recording" | "stop_recording" | "pause_recording" | "resume_recording"
+        | "capture_snapshot" | "capture_frame" | "trigger_camera" | "move_ptz" | "stop_ptz"
+        | "update_recording_meta" | "set_recording_protected" | "export_recordings" | "export_recording_session"
+        | "export_comparison" | "verify_recordings" | "repair_recording" | "archive_recording" | "upload_recording"
+        | "submit_job" | "cancel_job" | "revoke_stream_session" | "create_preview" => Some(Role::Operator),
+
+        _ => Some(Role::Admin),
+    }
+}
+
+pub fn is_required(conn: &Connection) -> Result<bool, String> {
+    Ok(crate::settings::get(conn, crate::settings::AUTH_REQUIRED)?.as_bool().unwrap_or(false))
+}
+
+/// Session token of the app window's login. The window is the only Tauri IPC client,
+/// so commands are checked against this one session.
+#[derive(Clone, Default)]
+pub struct UiSession(Arc<Mutex<Option<String>>>);
+
+impl UiSession {
+    pub fn token(&self) -> Option<String> {
+        self.0.lock().ok().and_then(|token| token.clone())
+    }
+
+    pub fn set(&self, token: Option<String>) {
+        if let Ok(mut current) = self.0.lock() {
+            *current = token;
+        }
+    }
+}
+
+/// Check the window's session before a command runs
+pub fn authorize_command(db_path: &str, session: &UiSession, command: &str) -> Result<(), String> {
+    let Some(role) = required_role(command) else { return Ok(()) };
+    let conn = crate::db::open(db_path).map_err(|e| e.to_string())?;
+    if !is_required(&conn)? {
+        return Ok(());
+    }
+
+    let user = match session.token() {
+        Some(token) => session_user(&conn, &token)?,
+        None => None,
+    };
+    match user {
+        Some(user) if user.role >= role => Ok(()),
+        Some(_) => Err(format!("Permission denied: {} requires the {} role", command, role.as_str())),
+        None => Err("Login required".to_string()),
+    }
+}
+
+/// HTTP API check: the per-launch server token (handed to the app window) or a session token
+/// whose user has `role`. Any logged-in window user can read the server token, so while login
+/// is required it only grants viewer routes.
+pub fn authorize_request(db_path: &str, server_token: &str, headers: &HeaderMap, query: &HashMap<String, String>, role: Role) -> bool {
+    let Ok(conn) = crate::db::open(db_path) else { return false };
+    if crate::download::is_authorized(server_token, headers, query)
+        && (role == Role::Viewer || !is_required(&conn).unwrap_or(true))
+    {
+        return true;
+    }
+    let Some(token) = provided_token(headers, query) else { return false };
+    session_user(&conn, token)
+        .ok()
+        .flatten()
+        .is_some_and(|user| user.role >= role)
+}
+
+pub(crate) fn provided_token<'a>(headers: &'a HeaderMap, query: &'a HashMap<String, String>) -> Option<&'a str> {
+    headers.get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .or(query.get("token").map(|t| t.as_str()))
+}
+
+pub fn hash_password(password: &str) -> Result<String, String> {
+    if password.chars().count() < MIN_PASSWORD_LEN {
+        return Err(format!("Password must be at least {} characters", MIN_PASSWORD_LEN));
+    }
+    let salt = SaltString::encode_b64(&rand::random::<[u8; 16]>()).map_err(|e| e.to_string())?;
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| e.to_string())
+}
+
+fn verify_password(password: &str, hash: &str) -> bool {
+    PasswordHash::new(hash).is_ok_and(|hash| Argon2::default().verify_password(password.as_bytes(), &hash).is_ok())
+}
+
+// Sessions are stored by token hash, so the database alone doesn't let anyone in
+fn token_hash(token: &str) -> String {
+    Sha256::digest(token.as_bytes()).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn user_from_row(row: &rusqlite::Row) -> rusqlite::Result<AppUser> {
+    let role: String = row.get(2)?;
+    Ok(AppUser {
+        id: row.get(0)?,
+        username: row.get(1)?,
+        role: Role::parse(&role).unwrap_or(Role::Viewer),
+        created_at: row.get::<_, String>(3)?.parse().unwrap_or_else(|_| Utc::now()),
+        last_login_at: row.get::<_, Option<String>>(4)?.and_then(|t| t.parse().ok()),
+    })
+}
+
+pub fn list_users(conn: &Connection) -> Result<Vec<AppUser>, String> {
+    let mut stmt = conn.prepare("SELECT id, username, role, created_at, last_login_at FROM app_users ORDER BY username")
+        .map_err(|e| e.to_string())?;
+    let users = stmt.query_map([], user_from_row).map_err(|e| e.to_string())?;
+    users.collect::<Result<_, _>>().map_err(|e| e.to_string())
+}
+
+pub fn load_user(conn: &Connection, id: i32) -> Result<AppUser, String> {
+    conn.query_row(
+        "SELECT id, username, role, created_at, last_login_at FROM app_users WHERE id = ?1",
+        [id],
+        user_from_row,
+    ).optional().map_err(|e| e.to_string())?.ok_or_else(|| "User not found".to_string())
+}
+
+/// User of an unexpired session
+pub fn session_user(conn: &Connection, token: &str) -> Result<Option<AppUser>, String> {
+    conn.query_row(
+        "SELECT u.id, u.username, u.role, u.created_at, u.last_login_at
+         FROM app_sessions s JOIN app_users u ON s.user_id = u.id
+         WHERE s.token_hash = ?1 AND s.expires_at > ?2",
+        rusqlite::params![token_hash(token), Utc::now().to_rfc3339()],
+        user_from_row,
+    ).optional().map_err(|e| e.to_string())
+}
+
+/// Check credentials and open a session
+pub fn login(conn: &Connection, username: &str, password: &str) -> Result<AuthSession, String> {
+    let row: Option<(i32, String)> = conn.query_row(
+        "SELECT id, password_hash FROM app_users WHERE username = ?1",
+        [username.trim()],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    ).optional().map_err(|e| e.to_string())?;
+
+    let Some((user_id, _)) = row.filter(|(_, hash)| verify_password(password, hash)) else {
+        println!("[Auth] Failed login for '{}'", username.trim());
+        return Err("Invalid username or password".to_string());
+    };
+
+    let now = Utc::now();
+    conn.execute("DELETE FROM app_sessions WHERE expires_at <= ?1", [now.to_rfc3339()])
+        .map_err(|e| e.to_string())?;
+
+    let token = format!("{}{}", uuid::Uuid::new_v4().simple(), uuid::Uuid::new_v4().simple());
+    let expires_at: DateTime<Utc> = now + Duration::hours(SESSION_HOURS);
+    conn.execute(
+        "INSERT INTO app_sessions (token_hash, user_id, created_at, expires_at) VALUES (?1, ?2, ?3, ?4)",
+        rusqlite::params![token_hash(&token), user_id, now.to_rfc3339(), expires_at.to_rfc3339()],
+    ).map_err(|e| e.to_string())?;
+    conn.execute("UPDATE app_users SET last_login_at = ?1 WHERE id = ?2", rusqlite::params![now.to_rfc3339(), user_id])
+        .map_err(|e| e.to_string())?;
+
+    let user = load_user(conn, user_id)?;
+    println!("[Auth] '{}' logged in as {}", user.username, user.role.as_str());
+    Ok(AuthSession { token, user, expires_at })
+}
+
+/// Check a user's current password, e.g. before changing it
+pub fn check_password(conn: &Connection, user_id: i32, password: &str) -> Result<(), String> {
+    let hash: String = conn.query_row("SELECT password_hash FROM app_users WHERE id = ?1", [user_id], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+    if !verify_password(password, &hash) {
+        return Err("Current password is incorrect".to_string());
+    }
+    Ok(())
+}
+
+pub fn logout(conn: &Connection, token: &str) -> Result<(), String> {
+    conn.execute("DELETE FROM app_sessions WHERE token_hash = ?1", [token_hash(token)])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+pub fn admin_count(conn: &Connection) -> Result<i64, String> {
+    conn.query_row("SELECT COUNT(*) FROM app_users WHERE role = 'admin'", [], |row| row.get(0))
+        .map_err(|e| e.to_string())
+}
+
+/// Removing or demoting `user_id` must leave an admin while login is required
+pub fn check_keeps_admin(conn: &Connection, user_id: i32) -> Result<(), String> {
+    let user = load_user(conn, user_id)?;
+    if user.role == Role::Admin && admin_count(conn)? <= 1 && is_required(conn)? {
+        return Err("The last admin can't be removed while login is required".to_string());
+    }
+    Ok(())
+}
+
+#[derive(Deserialize)]
+struct LoginRequest {
+    username: String,
+    password: String,
+}
+
+/// `POST /api/auth/login` and `/api/auth/logout` for HTTP API clients; the session token then
+/// goes in "Authorization: Bearer" (or `?token=`) like the server token
+pub fn router(db_path: String) -> Router {
+    Router::new()
+        .route("/api/auth/login", post(login_handler))
+        .route("/api/auth/logout", post(logout_handler))
+        .with_state(db_path)
+}
+
+async fn login_handler(State(db_path): State<String>, Json(request): Json<LoginRequest>) -> Response {
+    let result = tokio::task::spawn_blocking(move || {
+        let conn = crate::db::open(&db_path).map_err(|e| e.to_string())?;
+        login(&conn, &request.username, &request.password)
+    }).await.map_err(|e| e.to_string()).and_then(|result| result);
+
+    match result {
+        Ok(session) => Json(session).into_response(),
+        Err(e) => (StatusCode::UNAUTHORIZED, e).into_response(),
+    }
+}
+
+async fn logout_handler(
+    State(db_path): State<String>,
+    axum::extract::Query(query): axum::extract::Query<HashMap<String, String>>,
+    headers: HeaderMap,
+) -> Response {
+    let Some(token) = provided_token(&headers, &query) else {
+        return (StatusCode::UNAUTHORIZED, "Missing token").into_response();
+    };
+    match crate::db::open(&db_path).map_err(|e| e.to_string()).and_then(|conn| logout(&conn, token)) {
+        Ok(()) => StatusCode::NO_CONTENT.into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::test_db::TestDb;
+
+    #[test]
+    fn server_token_only_grants_viewer_routes_while_login_is_required() {
+        let mut db = TestDb::new("auth-server-token");
+        let db_path = db.db_path();
+        let query = HashMap::from([("token".to_string(), "server-token".to_string())]);
+        let allowed = |role| authorize_request(&db_path, "server-token", &HeaderMap::new(), &query, role);
+
+        assert!(allowed(Role::Viewer));
+        assert!(allowed(Role::Operator));
+
+        let changes = serde_json::json!({ crate::settings::AUTH_REQUIRED: true });
+        crate::settings::update(&mut db.conn, changes.as_object().unwrap()).unwrap();
+        assert!(allowed(Role::Viewer));
+        assert!(!allowed(Role::Operator));
+        assert!(!allowed(Role::Admin));
+    }
+}
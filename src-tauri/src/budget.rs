@@ -0,0 +1,259 @@
+use crate::events::AppEvent;
+use crate::models::{Camera, CameraLoad, LoadBudgetStatus};
+use crate::AppState;
+use rusqlite::{Connection, OptionalExtension};
+use std::collections::HashSet;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+/// Priorities of a camera under the load budget, lowest first; streams of "low" cameras are
+/// downgraded first and "high" ones last. Cameras without one are "normal".
+pub const PRIORITY_LEVELS: [&str; 3] = ["low", "normal", "high"];
+
+/// Downgrade steps of a live stream: 1 halves the frame rate, 2 also halves the resolution
+pub const MAX_DOWNGRADE: u8 = 2;
+
+const CHECK_INTERVAL_SECS: u64 = 15;
+// Downgraded streams are restored only once the load fits under this share of the budget,
+// so they don't flap at the limit
+const RESTORE_MARGIN: f64 = 0.8;
+// Assumed for cameras whose video mode isn't known, which is most network cameras
+const ASSUMED_WIDTH: i32 = 1920;
+const ASSUMED_HEIGHT: i32 = 1080;
+const ASSUMED_FPS: i32 = 15;
+const MIN_FPS: i32 = 2;
+
+// A camera that is streaming, recording or both
+struct ActiveCamera {
+    camera: Camera,
+    priority: Option<String>,
+    streaming: bool,
+    recording: bool,
+}
+
+// A live stream the governor may downgrade
+struct Stream {
+    camera_id: i32,
+    rank: usize,
+    // load at each downgrade level
+    loads: [f64; MAX_DOWNGRADE as usize + 1],
+    level: u8,
+}
+
+/// Every few seconds, compare the encoding load of the running streams and recordings with
+/// `encoder.loadBudget` and downgrade or restore live streams, restarting them to apply it.
+/// Recordings count towards the load but are never downgraded.
+pub fn spawn_load_governor(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(Duration::from_secs(CHECK_INTERVAL_SECS)).await;
+
+            let state = app_handle.state::<AppState>();
+            let changed = match adjust(&state) {
+                Ok(changed) => changed,
+                Err(e) => {
+                    eprintln!("[Budget] Check failed: {}", e);
+                    continue;
+                }
+            };
+            for camera_id in changed {
+                if let Err(e) = crate::stream_watchdog::restart_stream(&app_handle, camera_id).await {
+                    eprintln!("[Budget] Restarting the stream of camera {} failed: {}", camera_id, e);
+                }
+            }
+        }
+    });
+}
+
+/// Priority of the camera under the load budget, None for "normal"
+pub fn priority_for(conn: &Connection, camera_id: i32) -> Result<Option<String>, String> {
+    conn.query_row("SELECT priority FROM cameras WHERE id = ?1", [camera_id], |row| row.get(0))
+        .optional()
+        .map(Option::flatten)
+        .map_err(|e| e.to_string())
+}
+
+/// Budget, total load and each active camera's share of it
+pub fn status(state: &AppState) -> Result<LoadBudgetStatus, String> {
+    let (budget, active) = active_cameras(state)?;
+    let cameras: Vec<CameraLoad> = active.into_iter().map(|ActiveCamera { camera, priority, streaming, recording }| {
+        let downgrade = state.pipelines.stream_downgrade(camera.id);
+        CameraLoad {
+            cameraId: camera.id,
+            priority: priority.unwrap_or_else(|| "normal".to_string()),
+            streamLoad: streaming.then(|| stream_load(&camera, downgrade)),
+            recordingLoad: recording.then(|| stream_load(&camera, 0)),
+            streamDowngrade: downgrade,
+        }
+    }).collect();
+    Ok(LoadBudgetStatus {
+        budget,
+        load: cameras.iter().map(|camera| camera.streamLoad.unwrap_or(0.0) + camera.recordingLoad.unwrap_or(0.0)).sum(),
+        cameras,
+    })
+}
+
+/// Encoding load of a camera's stream at a downgrade level, in megapixels per second
+pub fn stream_load(camera: &Camera, level: u8) -> f64 {
+    let (width, height) = match (camera.video_width, camera.video_height) {
+        (Some(width), Some(height)) => (width, height),
+        _ => (ASSUMED_WIDTH, ASSUMED_HEIGHT),
+    };
+    let fps = downgraded_fps(camera.video_fps, level).or(camera.video_fps).unwrap_or(ASSUMED_FPS);
+    let area = if level >= 2 { 0.25 } else { 1.0 };
+    width as f64 * height as f64 * area * fps as f64 / 1_000_000.0
+}
+
+/// Frame rate of a downgraded stream, None when it isn't downgraded
+pub fn downgraded_fps(camera_fps: Option<i32>, level: u8) -> Option<i32> {
+    (level >= 1).then(|| (camera_fps.unwrap_or(ASSUMED_FPS) / 2).max(MIN_FPS))
+}
+
+/// Scale filter of a downgraded stream: half the height (kept even) from level 2 on
+pub fn downgraded_scale(level: u8) -> Option<&'static str> {
+    (level >= 2).then_some("scale=-2:trunc(ih/4)*2")
+}
+
+// Set the downgrade level of every live stream; returns the cameras whose stream must restart
+fn adjust(state: &AppState) -> Result<Vec<i32>, String> {
+    let (budget, active) = active_cameras(state)?;
+    let mut fixed = 0.0;
+    let mut streams = Vec::new();
+    for ActiveCamera { camera, priority, streaming, recording } in &active {
+        if *recording {
+            fixed += stream_load(camera, 0);
+        }
+        if *streaming {
+            let rank = PRIORITY_LEVELS.iter()
+                .position(|level| Some(*level) == priority.as_deref())
+                .unwrap_or(1);
+            streams.push(Stream {
+                camera_id: camera.id,
+                rank,
+                loads: [0, 1, 2].map(|level| stream_load(camera, level)),
+                level: state.pipelines.stream_downgrade(camera.id),
+            });
+        }
+    }
+
+    let levels = match budget {
+        0 => vec![0; streams.len()],
+        budget => plan(budget as f64, fixed, &streams),
+    };
+    let mut changed = Vec::new();
+    for (stream, level) in streams.iter().zip(levels) {
+        if level == stream.level {
+            continue;
+        }
+        if level > stream.level {
+            println!("[Budget] Load over the budget of {} Mpx/s, downgrading the stream of camera {} to level {}", budget, stream.camera_id, level);
+        } else {
+            println!("[Budget] Restoring the stream of camera {} to level {}", stream.camera_id, level);
+        }
+        state.pipelines.set_stream_downgrade(stream.camera_id, level);
+        state.events.emit(AppEvent::StreamDowngraded { camera_id: stream.camera_id, level });
+        changed.push(stream.camera_id);
+    }
+
+    // Streams that stopped start at full quality next time
+    let streaming: HashSet<i32> = streams.iter().map(|stream| stream.camera_id).collect();
+    state.pipelines.reset_stream_downgrades(&streaming);
+    Ok(changed)
+}
+
+// Budget in Mpx/s (0 for none) and the active cameras with whether they stream and record
+fn active_cameras(state: &AppState) -> Result<(i32, Vec<ActiveCamera>), String> {
+    let conn = crate::db::open(&state.db_path).map_err(|e| e.to_string())?;
+    let budget = crate::settings::get_i32(&conn, crate::settings::ENCODER_LOAD_BUDGET)?;
+    let streaming: HashSet<i32> = state.processes.lock().map_err(|e| e.to_string())?.keys().copied().collect();
+    let recording: HashSet<i32> = state.recording_processes.lock().map_err(|e| e.to_string())?.keys().copied().collect();
+
+    let mut ids: Vec<i32> = streaming.union(&recording).copied().collect();
+    ids.sort_unstable();
+    let mut cameras = Vec::new();
+    for id in ids {
+        // Deleted while running
+        let Ok(camera) = crate::stream::load_camera(&state.db_path, id) else { continue };
+        cameras.push(ActiveCamera {
+            priority: priority_for(&conn, id)?,
+            streaming: streaming.contains(&id),
+            recording: recording.contains(&id),
+            camera,
+        });
+    }
+    Ok((budget, cameras))
+}
+
+// New level of each stream: downgraded as soon as the load is over the budget, restored only
+// once it fits under RESTORE_MARGIN of it
+fn plan(budget: f64, fixed: f64, streams: &[Stream]) -> Vec<u8> {
+    let needed = levels_within(budget, fixed, streams);
+    let relaxed = levels_within(budget * RESTORE_MARGIN, fixed, streams);
+    streams.iter().enumerate()
+        .map(|(index, stream)| needed[index].max(stream.level.min(relaxed[index])))
+        .collect()
+}
+
+// Lowest levels keeping the total load within `budget`. Streams are downgraded a step at a
+// time: lowest priority first, every stream of a priority one step before any takes a second,
+// and the busiest first among equals. Stays over budget when every stream is fully downgraded.
+fn levels_within(budget: f64, fixed: f64, streams: &[Stream]) -> Vec<u8> {
+    let mut levels = vec![0u8; streams.len()];
+    let load = |index: usize, levels: &[u8]| streams[index].loads[levels[index] as usize];
+    loop {
+        let total: f64 = fixed + (0..streams.len()).map(|index| load(index, &levels)).sum::<f64>();
+        if total <= budget {
+            break;
+        }
+        let next = (0..streams.len())
+            .filter(|&index| levels[index] < MAX_DOWNGRADE)
+            .min_by(|&a, &b| {
+                streams[a].rank.cmp(&streams[b].rank)
+                    .then(levels[a].cmp(&levels[b]))
+                    .then(load(b, &levels).total_cmp(&load(a, &levels)))
+            });
+        let Some(index) = next else { break };
+        levels[index] += 1;
+    }
+    levels
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stream(camera_id: i32, rank: usize, load: f64, level: u8) -> Stream {
+        Stream { camera_id, rank, loads: [load, load / 2.0, load / 8.0], level }
+    }
+
+    #[test]
+    fn lowest_priority_streams_are_downgraded_first() {
+        let streams = [stream(1, 2, 60.0, 0), stream(2, 0, 30.0, 0), stream(3, 0, 40.0, 0)];
+        // 130 over 120: the busier low-priority stream loses half its frame rate
+        assert_eq!(levels_within(120.0, 0.0, &streams), [0, 0, 1]);
+        // Both low-priority streams at half rate before either loses resolution
+        assert_eq!(levels_within(100.0, 0.0, &streams), [0, 1, 1]);
+        assert_eq!(levels_within(90.0, 0.0, &streams), [0, 1, 2]);
+        // Recordings count too; high priority goes last and the budget may stay exceeded
+        assert_eq!(levels_within(10.0, 50.0, &streams), [2, 2, 2]);
+    }
+
+    #[test]
+    fn downgraded_streams_are_restored_with_a_margin() {
+        // 60 + 40 fits 105, but not 80% of it: stream 2 stays at half rate
+        assert_eq!(plan(105.0, 0.0, &[stream(1, 1, 60.0, 0), stream(2, 0, 40.0, 1)]), [0, 1]);
+        assert_eq!(plan(130.0, 0.0, &[stream(1, 1, 60.0, 0), stream(2, 0, 40.0, 2)]), [0, 0]);
+        // Over the budget: downgraded right away
+        assert_eq!(plan(90.0, 0.0, &[stream(1, 1, 60.0, 0), stream(2, 0, 40.0, 0)]), [0, 1]);
+    }
+
+    #[test]
+    fn downgrades_halve_frame_rate_then_resolution() {
+        assert_eq!(downgraded_fps(Some(25), 0), None);
+        assert_eq!(downgraded_fps(Some(25), 1), Some(12));
+        assert_eq!(downgraded_fps(None, 2), Some(7));
+        assert_eq!(downgraded_fps(Some(3), 1), Some(MIN_FPS));
+        assert_eq!(downgraded_scale(1), None);
+        assert!(downgraded_scale(2).is_some());
+    }
+}
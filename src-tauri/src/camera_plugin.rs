@@ -1,6 +1,8 @@
 use crate::models::Camera;
 use async_trait::async_trait;
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
 
 /// Information about a discovered camera
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -20,6 +22,171 @@ pub struct CameraInfo {
     pub video_width: Option<i32>,         // e.g., 1280
     pub video_height: Option<i32>,        // e.g., 720
     pub video_fps: Option<i32>,           // e.g., 30
+    // Identity used to recognise one device found by several plugins
+    #[serde(default)]
+    pub xaddr: Option<String>,            // ONVIF device service URL
+    #[serde(default)]
+    pub mac: Option<String>,              // lowercase, colon separated
+    #[serde(default)]
+    pub serial: Option<String>,
+    // Plugins that found the device; the entry itself comes from the first one
+    #[serde(default)]
+    pub sources: Vec<String>,
+    // data:image/jpeg;base64 URL, set when the device hands out snapshots without credentials
+    #[serde(default)]
+    pub thumbnail: Option<String>,
+    // Camera already in the cameras table for this device
+    #[serde(default)]
+    pub existing_camera_id: Option<i32>,
+}
+
+impl CameraInfo {
+    /// Local capture devices are only found by one plugin and have no network endpoint
+    pub fn is_local(&self) -> bool {
+        self.host.is_empty() || self.host == "localhost"
+    }
+
+    /// Whether two discovery results describe the same physical device: matched on MAC
+    /// address, then serial number, then network endpoint (host)
+    pub fn same_device(&self, other: &CameraInfo) -> bool {
+        if let (Some(a), Some(b)) = (&self.mac, &other.mac) {
+            return a == b;
+        }
+        if let (Some(a), Some(b)) = (&self.serial, &other.serial) {
+            return a == b;
+        }
+        if self.is_local() || other.is_local() {
+            return self.camera_type == other.camera_type
+                && self.device_path == other.device_path
+                && self.device_id == other.device_id
+                && self.device_index == other.device_index;
+        }
+        self.host.eq_ignore_ascii_case(&other.host)
+    }
+
+    /// Fold another result for the same device into this one
+    pub fn merge(&mut self, other: CameraInfo) {
+        for source in other.sources {
+            if !self.sources.contains(&source) {
+                self.sources.push(source);
+            }
+        }
+        self.xaddr = self.xaddr.take().or(other.xaddr);
+        self.mac = self.mac.take().or(other.mac);
+        self.serial = self.serial.take().or(other.serial);
+        self.thumbnail = self.thumbnail.take().or(other.thumbnail);
+    }
+}
+
+/// MAC address in the form used by CameraInfo ("ac:cc:8e:12:34:56") from 12 hex digits
+/// with or without separators
+pub fn normalize_mac(text: &str) -> Option<String> {
+    let digits: String = text.chars().filter(|c| !matches!(c, ':' | '-' | '.')).collect();
+    if digits.len() != 12 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let digits = digits.to_ascii_lowercase();
+    let octets: Vec<&str> = (0..12).step_by(2).map(|i| &digits[i..i + 2]).collect();
+    Some(octets.join(":"))
+}
+
+/// Add a discovery result to a list, merging it into the entry for the same device if there
+/// is one. Returns the index of the entry and whether it is new.
+pub fn merge_discovered(cameras: &mut Vec<CameraInfo>, camera: CameraInfo) -> (usize, bool) {
+    match cameras.iter().position(|c| c.same_device(&camera)) {
+        Some(index) => {
+            cameras[index].merge(camera);
+            (index, false)
+        }
+        None => {
+            cameras.push(camera);
+            (cameras.len() - 1, true)
+        }
+    }
+}
+
+/// Handle for an incremental discovery scan
+/// Plugins report cameras through it as they are found and check it for cancellation.
+/// A device reported again (by another plugin, or with a thumbnail) is passed to `on_found`
+/// again with its merged entry.
+#[derive(Clone)]
+pub struct DiscoveryScan {
+    cancelled: Arc<AtomicBool>,
+    on_found: Arc<dyn Fn(CameraInfo) + Send + Sync>,
+    found: Arc<Mutex<Vec<CameraInfo>>>,
+    // Fetches thumbnails of new devices in the background when set
+    thumbnails: Option<Arc<PluginManager>>,
+}
+
+impl DiscoveryScan {
+    pub fn new<F>(on_found: F) -> Self
+    where
+        F: Fn(CameraInfo) + Send + Sync + 'static,
+    {
+        Self {
+            cancelled: Arc::new(AtomicBool::new(false)),
+            on_found: Arc::new(on_found),
+            found: Arc::new(Mutex::new(Vec::new())),
+            thumbnails: None,
+        }
+    }
+
+    /// Fetch a thumbnail for each new device through its plugin; entries are reported
+    /// again once their thumbnail arrives, possibly after the scan has finished
+    pub fn with_thumbnails(mut self, plugin_manager: Arc<PluginManager>) -> Self {
+        self.thumbnails = Some(plugin_manager);
+        self
+    }
+
+    /// Report a camera as soon as it is found
+    pub fn report(&self, mut camera: CameraInfo) {
+        if camera.sources.is_empty() {
+            camera.sources.push(camera.camera_type.clone());
+        }
+
+        let (entry, is_new) = {
+            let Ok(mut found) = self.found.lock() else { return };
+            let (index, is_new) = merge_discovered(&mut found, camera);
+            (found[index].clone(), is_new)
+        };
+
+        if is_new && entry.thumbnail.is_none() {
+            if let Some(plugin_manager) = self.thumbnails.clone() {
+                let scan = self.clone();
+                let camera = entry.clone();
+                tauri::async_runtime::spawn(async move {
+                    if let Some(thumbnail) = plugin_manager.discovery_thumbnail(&camera).await {
+                        scan.attach_thumbnail(&camera, thumbnail);
+                    }
+                });
+            }
+        }
+        (self.on_found)(entry);
+    }
+
+    fn attach_thumbnail(&self, camera: &CameraInfo, thumbnail: String) {
+        let entry = {
+            let Ok(mut found) = self.found.lock() else { return };
+            let Some(entry) = found.iter_mut().find(|c| c.same_device(camera)) else { return };
+            entry.thumbnail = Some(thumbnail);
+            entry.clone()
+        };
+        (self.on_found)(entry);
+    }
+
+    /// Ask all plugins taking part in the scan to stop early
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// Cancellation flag for scanners that poll it directly
+    pub fn cancel_flag(&self) -> &AtomicBool {
+        &self.cancelled
+    }
 }
 
 /// PTZ movement direction
@@ -33,6 +200,19 @@ pub enum PtzDirection {
     ZoomOut,
 }
 
+/// Event reported by a camera's own analytics (vendor alarm/event streams)
+#[derive(Debug, Clone)]
+pub struct CameraAlarm {
+    /// Normalised kind used as the timeline event type: "motion", "line_crossing",
+    /// "intrusion", "tampering", "io", "video_loss", or the vendor's name for anything else
+    pub kind: String,
+    /// false when the camera reports the condition has ended
+    pub active: bool,
+    pub channel: Option<u32>,
+    /// Vendor fields kept with the event
+    pub details: serde_json::Value,
+}
+
 /// Recording options
 #[derive(Debug, Clone)]
 pub struct RecordingOptions {
@@ -50,6 +230,22 @@ pub trait CameraPlugin: Send + Sync {
     /// Discover cameras of this type on the network/system
     async fn discover(&self) -> Result<Vec<CameraInfo>, String>;
 
+    /// Discover cameras, reporting each one through the scan as it is found
+    /// Plugins with a slow scan should override this to stream results and honour cancellation
+    async fn discover_incremental(&self, scan: &DiscoveryScan) -> Result<Vec<CameraInfo>, String> {
+        let cameras = self.discover().await?;
+        for camera in &cameras {
+            scan.report(camera.clone());
+        }
+        Ok(cameras)
+    }
+
+    /// JPEG of a discovered device fetched without credentials, for the discovery list.
+    /// Devices that need credentials (or can't be sampled cheaply) return None.
+    async fn discovery_thumbnail(&self, _camera: &CameraInfo) -> Option<Vec<u8>> {
+        None
+    }
+
     /// Get the stream URL for a camera
     /// For ONVIF: RTSP URL
     /// For UVC: device path (e.g., /dev/video0)
@@ -60,6 +256,12 @@ pub trait CameraPlugin: Send + Sync {
         false
     }
 
+    /// PTZ axes a particular camera offers (None if it has none). Only asked when
+    /// supports_ptz() returns true; plugins whose cameras all have full PTZ keep the default.
+    async fn ptz_capabilities(&self, _camera: &Camera) -> Option<crate::models::PTZCapabilitiesDetails> {
+        Some(crate::models::PTZCapabilitiesDetails { hasPanTilt: true, hasZoom: true })
+    }
+
     /// Check if this plugin supports time synchronization
     fn supports_time_sync(&self) -> bool {
         false
@@ -94,6 +296,40 @@ pub trait CameraPlugin: Send + Sync {
         Err("Time sync not supported by this plugin".to_string())
     }
 
+    /// Check if this plugin can report camera-side events through watch_alarms
+    fn supports_alarms(&self) -> bool {
+        false
+    }
+
+    /// Follow the camera's event stream, passing each alarm to `on_alarm`, until the
+    /// connection ends (only if supports_alarms() returns true)
+    async fn watch_alarms(
+        &self,
+        _camera: &Camera,
+        _on_alarm: &(dyn Fn(CameraAlarm) + Send + Sync),
+    ) -> Result<(), String> {
+        Err("Alarm events not supported by this plugin".to_string())
+    }
+
+    /// Settings the plugin accepts, as a JSON Schema subset (see plugin_config::validate);
+    /// None if the plugin has no settings
+    fn config_schema(&self) -> Option<serde_json::Value> {
+        None
+    }
+
+    /// Check a config before it is stored; the default checks it against config_schema()
+    fn validate_config(&self, config: &serde_json::Value) -> Result<(), String> {
+        match self.config_schema() {
+            Some(schema) => crate::plugin_config::validate(&schema, config),
+            None => Err(format!("Plugin '{}' has no settings", self.plugin_type())),
+        }
+    }
+
+    /// Apply a validated config with the schema defaults filled in (at startup and on change)
+    fn configure(&self, _config: &serde_json::Value) -> Result<(), String> {
+        Ok(())
+    }
+
     /// Get ONVIF profiles (only for ONVIF cameras)
     async fn get_profiles(&self, _camera: &Camera) -> Result<Vec<(String, String)>, String> {
         Err("Profiles not supported by this plugin".to_string())
@@ -124,11 +360,20 @@ impl PluginManager {
         self.plugins.get(camera_type)
     }
 
+    // Plugins in discovery order. ONVIF goes first so a device that several plugins find is
+    // listed as an ONVIF camera; the rest follow in a stable order.
+    fn discovery_order(&self) -> Vec<(&String, &dyn CameraPlugin)> {
+        let mut plugins: Vec<_> = self.plugins.iter().map(|(plugin_type, plugin)| (plugin_type, plugin.as_ref())).collect();
+        plugins.sort_by_key(|(plugin_type, _)| (plugin_type.as_str() != "onvif", plugin_type.as_str()));
+        plugins
+    }
+
     /// Discover all cameras from all plugins
+    /// A device found by several plugins is listed once, with every plugin in its sources
     pub async fn discover_all(&self) -> Result<Vec<CameraInfo>, String> {
         let mut all_cameras = Vec::new();
 
-        for (plugin_type, plugin) in &self.plugins {
+        for (plugin_type, plugin) in self.discovery_order() {
             println!("[PluginManager] Discovering cameras from plugin: {}", plugin_type);
             match plugin.discover().await {
                 Ok(cameras) => {
@@ -137,7 +382,10 @@ impl PluginManager {
                         plugin_type,
                         cameras.len()
                     );
-                    all_cameras.extend(cameras);
+                    for mut camera in cameras {
+                        camera.sources = vec![plugin_type.clone()];
+                        merge_discovered(&mut all_cameras, camera);
+                    }
                 }
                 Err(e) => {
                     println!(
@@ -151,6 +399,53 @@ impl PluginManager {
         Ok(all_cameras)
     }
 
+    /// Discover cameras from all plugins, streaming results through the scan
+    /// Plugins not yet started are skipped once the scan is cancelled
+    pub async fn discover_all_incremental(&self, scan: &DiscoveryScan) -> Vec<CameraInfo> {
+        let mut all_cameras = Vec::new();
+
+        for (plugin_type, plugin) in self.discovery_order() {
+            if scan.is_cancelled() {
+                break;
+            }
+
+            println!("[PluginManager] Discovering cameras from plugin: {}", plugin_type);
+            match plugin.discover_incremental(scan).await {
+                Ok(cameras) => {
+                    println!(
+                        "[PluginManager] Plugin '{}' found {} camera(s)",
+                        plugin_type,
+                        cameras.len()
+                    );
+                    for mut camera in cameras {
+                        camera.sources = vec![plugin_type.clone()];
+                        merge_discovered(&mut all_cameras, camera);
+                    }
+                }
+                Err(e) => {
+                    println!(
+                        "[PluginManager] Plugin '{}' discovery failed: {}",
+                        plugin_type, e
+                    );
+                }
+            }
+        }
+
+        all_cameras
+    }
+
+    /// Thumbnail of a discovered device as a data URL, from the first plugin that found it
+    /// and can supply one
+    pub async fn discovery_thumbnail(&self, camera: &CameraInfo) -> Option<String> {
+        for source in &camera.sources {
+            let Some(plugin) = self.plugins.get(source) else { continue };
+            if let Some(jpeg) = plugin.discovery_thumbnail(camera).await {
+                return crate::snapshots::thumbnail_data_url(&jpeg).await;
+            }
+        }
+        None
+    }
+
     /// Get list of registered plugin types
     pub fn get_plugin_types(&self) -> Vec<String> {
         self.plugins.keys().cloned().collect()
@@ -0,0 +1,36 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use tauri::async_runtime::JoinHandle;
+
+/// Background analysis tasks (audio, motion, ...) keyed by camera, at most one per camera
+#[derive(Clone, Default)]
+pub struct CameraTasks {
+    tasks: Arc<Mutex<HashMap<i32, JoinHandle<()>>>>,
+}
+
+impl CameraTasks {
+    /// Run `task` for the camera, replacing (aborting) the one already running
+    pub fn start<F>(&self, camera_id: i32, task: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let handle = tauri::async_runtime::spawn(task);
+        if let Ok(mut tasks) = self.tasks.lock() {
+            if let Some(previous) = tasks.insert(camera_id, handle) {
+                previous.abort();
+            }
+        }
+    }
+
+    /// Abort the camera's task; dropping it also kills any FFmpeg child it owned.
+    /// Returns whether a task was running.
+    pub fn stop(&self, camera_id: i32) -> bool {
+        let task = self.tasks.lock().ok().and_then(|mut tasks| tasks.remove(&camera_id));
+        task.map(|task| task.abort()).is_some()
+    }
+
+    pub fn is_running(&self, camera_id: i32) -> bool {
+        self.tasks.lock().is_ok_and(|tasks| tasks.contains_key(&camera_id))
+    }
+}
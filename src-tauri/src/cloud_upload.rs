@@ -0,0 +1,510 @@
+use crate::events::{AppEvent, EventBus};
+use crate::models::{CloudUploadSettings, UploadJob};
+use crate::s3::S3Target;
+use bytes::Bytes;
+use chrono::Utc;
+use http_body::Frame;
+use http_body_util::StreamBody;
+use rusqlite::Connection;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::fs::File;
+use tokio::io::AsyncReadExt;
+use tokio::sync::mpsc;
+
+const CHUNK_SIZE: usize = 256 * 1024;
+// Finished jobs kept around so the UI can show recent results
+const MAX_FINISHED_JOBS: usize = 50;
+// Retry delay doubles after each failed attempt, up to the maximum
+const RETRY_BASE_DELAY_SECS: u64 = 10;
+const RETRY_MAX_DELAY_SECS: u64 = 900;
+// How often uploaded objects are checked against the retention period
+const EXPIRY_CHECK_INTERVAL_SECS: u64 = 3600;
+
+pub fn load_settings(conn: &Connection) -> Result<CloudUploadSettings, String> {
+    let retention_days = crate::settings::get_opt_i32(conn, crate::settings::CLOUD_RETENTION_DAYS)?;
+    conn.query_row(
+        "SELECT id, enabled, endpoint, region, bucket, access_key, secret_key, prefix, path_style,
+                include_thumbnails, bandwidth_limit_kbps, max_retries
+         FROM cloud_upload_settings WHERE id = 1",
+        [],
+        |row| {
+            Ok(CloudUploadSettings {
+                id: row.get(0)?,
+                enabled: row.get(1)?,
+                endpoint: row.get(2)?,
+                region: row.get(3)?,
+                bucket: row.get(4)?,
+                accessKey: row.get(5)?,
+                secretKey: row.get(6)?,
+                prefix: row.get(7)?,
+                pathStyle: row.get(8)?,
+                includeThumbnails: row.get(9)?,
+                retentionDays: retention_days,
+                bandwidthLimitKbps: row.get(10)?,
+                maxRetries: row.get(11)?,
+            })
+        },
+    ).map_err(|e| e.to_string())
+}
+
+/// Whether recordings of a camera are uploaded: the camera's own policy if set, otherwise the global default
+pub fn upload_enabled_for(conn: &Connection, camera_id: i32) -> Result<bool, String> {
+    let policy: Option<bool> = conn.query_row(
+        "SELECT enabled FROM camera_upload_policies WHERE camera_id = ?1",
+        [camera_id],
+        |row| row.get(0),
+    ).ok();
+
+    match policy {
+        Some(enabled) => Ok(enabled),
+        None => Ok(load_settings(conn)?.enabled),
+    }
+}
+
+fn build_target(settings: &CloudUploadSettings) -> Result<S3Target, String> {
+    let required = |value: &Option<String>, name: &str| {
+        value.clone()
+            .filter(|v| !v.trim().is_empty())
+            .ok_or(format!("Cloud upload {} is not configured", name))
+    };
+
+    S3Target::new(
+        &required(&settings.endpoint, "endpoint")?,
+        &settings.region,
+        &required(&settings.bucket, "bucket")?,
+        &required(&settings.accessKey, "access key")?,
+        &required(&settings.secretKey, "secret key")?,
+        settings.pathStyle,
+    )
+}
+
+/// Upload queue for copying finished recordings to S3-compatible storage (stored in AppState).
+/// Jobs run one at a time, are retried with exponential backoff and report `upload-progress` events.
+#[derive(Clone)]
+pub struct UploadQueue {
+    worker: Arc<UploadWorker>,
+}
+
+struct UploadWorker {
+    db_path: String,
+    recording_dir: PathBuf,
+    http: reqwest::Client,
+    sender: mpsc::UnboundedSender<i32>,
+    progress: UploadProgress,
+}
+
+#[derive(Clone)]
+struct UploadProgress {
+    events: EventBus,
+    jobs: Arc<Mutex<Vec<UploadJob>>>,
+}
+
+impl UploadProgress {
+    fn update<F: FnOnce(&mut UploadJob)>(&self, recording_id: i32, f: F) {
+        let job = {
+            let Ok(mut jobs) = self.jobs.lock() else { return };
+            let Some(job) = jobs.iter_mut().rev().find(|j| j.recording_id == recording_id) else { return };
+            f(job);
+            job.clone()
+        };
+
+        self.events.emit(AppEvent::UploadProgress(job));
+    }
+
+    // Only emit when the whole-percent value changes to keep event volume down
+    fn bytes(&self, recording_id: i32, bytes_done: u64) {
+        let changed = {
+            let Ok(mut jobs) = self.jobs.lock() else { return };
+            let Some(job) = jobs.iter_mut().rev().find(|j| j.recording_id == recording_id) else { return };
+            let percent = |done: u64| (done * 100).checked_div(job.bytes_total).unwrap_or(100);
+            let changed = percent(job.bytes_done) != percent(bytes_done);
+            job.bytes_done = bytes_done;
+            changed
+        };
+
+        if changed {
+            self.update(recording_id, |_| {});
+        }
+    }
+}
+
+impl UploadQueue {
+    /// Create the queue and start its worker and expiry tasks
+    pub fn start(db_path: String, recording_dir: PathBuf, events: EventBus) -> Result<Self, String> {
+        // No overall timeout: large recordings can take a long time to upload
+        let http = reqwest::Client::builder()
+            .connect_timeout(Duration::from_secs(10))
+            .build()
+            .map_err(|e| e.to_string())?;
+
+        let (sender, mut receiver) = mpsc::unbounded_channel::<i32>();
+        let worker = Arc::new(UploadWorker {
+            db_path,
+            recording_dir,
+            http,
+            sender,
+            progress: UploadProgress { events, jobs: Arc::new(Mutex::new(Vec::new())) },
+        });
+
+        let loop_worker = worker.clone();
+        tauri::async_runtime::spawn(async move {
+            while let Some(recording_id) = receiver.recv().await {
+                loop_worker.run_job(recording_id).await;
+            }
+        });
+
+        let expiry_worker = worker.clone();
+        tauri::async_runtime::spawn(async move {
+            loop {
+                if let Err(e) = expiry_worker.expire_uploads().await {
+                    eprintln!("[Upload] Failed to expire old uploads: {}", e);
+                }
+                tokio::time::sleep(Duration::from_secs(EXPIRY_CHECK_INTERVAL_SECS)).await;
+            }
+        });
+
+        Ok(Self { worker })
+    }
+
+    /// Queue a recording for upload regardless of the camera's policy
+    pub fn enqueue(&self, recording_id: i32) -> Result<(), String> {
+        let (filename, is_finished) = {
+            let conn = crate::db::open(&self.worker.db_path).map_err(|e| e.to_string())?;
+            conn.query_row(
+                "SELECT filename, is_finished FROM recordings WHERE id = ?1",
+                [recording_id],
+                |row| Ok((row.get::<_, String>(0)?, row.get::<_, bool>(1)?)),
+            ).map_err(|e| e.to_string())?
+        };
+
+        if !is_finished {
+            return Err("Recording is still in progress".to_string());
+        }
+
+        {
+            let mut jobs = self.worker.progress.jobs.lock().map_err(|e| e.to_string())?;
+            let pending = jobs.iter().any(|j| {
+                j.recording_id == recording_id && !matches!(j.status.as_str(), "done" | "failed")
+            });
+            if pending {
+                return Err("Recording is already queued for upload".to_string());
+            }
+
+            // Drop the oldest finished jobs
+            while jobs.len() >= MAX_FINISHED_JOBS {
+                match jobs.iter().position(|j| matches!(j.status.as_str(), "done" | "failed")) {
+                    Some(index) => { jobs.remove(index); }
+                    None => break,
+                }
+            }
+
+            jobs.push(UploadJob {
+                recording_id,
+                filename,
+                status: "queued".to_string(),
+                attempts: 0,
+                bytes_done: 0,
+                bytes_total: 0,
+                key: None,
+                next_retry_at: None,
+                error: None,
+            });
+        }
+
+        self.worker.sender.send(recording_id).map_err(|e| e.to_string())?;
+        println!("[Upload] Queued recording {}", recording_id);
+        Ok(())
+    }
+
+    /// Queue a just-finalized recording if uploads are enabled for its camera
+    pub fn enqueue_finished(&self, recording_id: i32, camera_id: i32) {
+        let enabled = crate::db::open(&self.worker.db_path)
+            .map_err(|e| e.to_string())
+            .and_then(|conn| upload_enabled_for(&conn, camera_id));
+
+        match enabled {
+            Ok(true) => {
+                if let Err(e) = self.enqueue(recording_id) {
+                    eprintln!("[Upload] Failed to queue recording {}: {}", recording_id, e);
+                }
+            }
+            Ok(false) => {}
+            Err(e) => eprintln!("[Upload] Failed to read upload policy for camera {}: {}", camera_id, e),
+        }
+    }
+
+    /// Current and recent jobs
+    pub fn snapshot(&self) -> Vec<UploadJob> {
+        self.worker.progress.jobs.lock().map(|jobs| jobs.clone()).unwrap_or_default()
+    }
+}
+
+impl UploadWorker {
+    async fn run_job(&self, recording_id: i32) {
+        let result = self.upload(recording_id).await;
+
+        let (attempts, max_retries) = {
+            let attempts = self.progress.jobs.lock().ok()
+                .and_then(|jobs| jobs.iter().rev().find(|j| j.recording_id == recording_id).map(|j| j.attempts + 1))
+                .unwrap_or(1);
+            let max_retries = crate::db::open(&self.db_path).ok()
+                .and_then(|conn| load_settings(&conn).ok())
+                .map(|s| s.maxRetries.max(0) as u32)
+                .unwrap_or(0);
+            (attempts, max_retries)
+        };
+
+        match result {
+            Ok(key) => {
+                println!("[Upload] Recording {} uploaded as {}", recording_id, key);
+                self.progress.update(recording_id, |job| {
+                    job.status = "done".to_string();
+                    job.attempts = attempts;
+                    job.key = Some(key);
+                    job.next_retry_at = None;
+                    job.error = None;
+                });
+            }
+            Err(e) if attempts <= max_retries => {
+                let delay = RETRY_BASE_DELAY_SECS.saturating_mul(1 << (attempts - 1).min(16)).min(RETRY_MAX_DELAY_SECS);
+                eprintln!(
+                    "[Upload] Attempt {} for recording {} failed: {} (retrying in {}s)",
+                    attempts, recording_id, e, delay
+                );
+                self.progress.update(recording_id, |job| {
+                    job.status = "retrying".to_string();
+                    job.attempts = attempts;
+                    job.next_retry_at = Some(Utc::now() + chrono::Duration::seconds(delay as i64));
+                    job.error = Some(e);
+                });
+
+                let sender = self.sender.clone();
+                tauri::async_runtime::spawn(async move {
+                    tokio::time::sleep(Duration::from_secs(delay)).await;
+                    let _ = sender.send(recording_id);
+                });
+            }
+            Err(e) => {
+                eprintln!("[Upload] Giving up on recording {} after {} attempts: {}", recording_id, attempts, e);
+                self.progress.update(recording_id, |job| {
+                    job.status = "failed".to_string();
+                    job.attempts = attempts;
+                    job.next_retry_at = None;
+                    job.error = Some(e);
+                });
+            }
+        }
+    }
+
+    async fn upload(&self, recording_id: i32) -> Result<String, String> {
+        let (settings, camera_id, filename, thumbnail, source) = {
+            let conn = crate::db::open(&self.db_path).map_err(|e| e.to_string())?;
+            let (camera_id, filename, thumbnail) = conn.query_row(
+                "SELECT camera_id, filename, thumbnail FROM recordings WHERE id = ?1",
+                [recording_id],
+                |row| Ok((row.get::<_, i32>(0)?, row.get::<_, String>(1)?, row.get::<_, Option<String>>(2)?)),
+            ).map_err(|e| e.to_string())?;
+            let source = crate::storage::recording_path(&conn, &self.recording_dir, recording_id)?;
+            (load_settings(&conn)?, camera_id, filename, thumbnail, source)
+        };
+
+        let target = build_target(&settings)?;
+        let total = tokio::fs::metadata(&source).await
+            .map_err(|e| format!("Recording file {} is not readable: {}", filename, e))?
+            .len();
+
+        self.progress.update(recording_id, |job| {
+            job.status = "uploading".to_string();
+            job.bytes_total = total;
+            job.bytes_done = 0;
+            job.next_retry_at = None;
+        });
+
+        let key = format!("{}{}/{}", settings.prefix, camera_id, filename);
+        let limit = settings.bandwidthLimitKbps.filter(|kbps| *kbps > 0).map(|kbps| kbps as u64 * 1000 / 8);
+        self.put_file(&target, &key, &source, crate::stream::recording_content_type(&filename), Some(recording_id), limit).await?;
+
+        let mut thumbnail_key = None;
+        if settings.includeThumbnails {
+            if let Some(thumbnail) = thumbnail {
+                let path = self.recording_dir.join("thumbnails").join(&thumbnail);
+                if path.exists() {
+                    let key = format!("{}{}/thumbnails/{}", settings.prefix, camera_id, thumbnail);
+                    self.put_file(&target, &key, &path, "image/jpeg", None, limit).await?;
+                    thumbnail_key = Some(key);
+                }
+            }
+        }
+
+        let conn = crate::db::open(&self.db_path).map_err(|e| e.to_string())?;
+        conn.execute(
+            "UPDATE recordings SET upload_key = ?1, uploaded_at = ?2, upload_thumbnail_key = ?3 WHERE id = ?4",
+            rusqlite::params![key, Utc::now().to_rfc3339(), thumbnail_key, recording_id],
+        ).map_err(|e| e.to_string())?;
+
+        Ok(key)
+    }
+
+    // Stream a file to the bucket, throttled to `bytes_per_sec` if set.
+    // The body's SHA-256 is signed, so S3 rejects the upload if anything arrives corrupted.
+    async fn put_file(
+        &self,
+        target: &S3Target,
+        key: &str,
+        path: &Path,
+        content_type: &str,
+        progress_id: Option<i32>,
+        bytes_per_sec: Option<u64>,
+    ) -> Result<(), String> {
+        let payload_sha256 = hash_file(path).await?;
+        let url = target.object_url(key)?;
+
+        let file = File::open(path).await.map_err(|e| e.to_string())?;
+        let total = file.metadata().await.map_err(|e| e.to_string())?.len();
+        let progress = self.progress.clone();
+        let started = Instant::now();
+
+        let stream = futures::stream::unfold((Some(file), 0u64), move |(file, done)| {
+            let progress = progress.clone();
+            async move {
+                let mut file = file?;
+                let mut buf = vec![0u8; CHUNK_SIZE];
+                match file.read(&mut buf).await {
+                    Ok(0) => None,
+                    Ok(n) => {
+                        buf.truncate(n);
+                        let done = done + n as u64;
+                        if let Some(bytes_per_sec) = bytes_per_sec {
+                            let due = Duration::from_secs_f64(done as f64 / bytes_per_sec as f64);
+                            if let Some(wait) = due.checked_sub(started.elapsed()) {
+                                tokio::time::sleep(wait).await;
+                            }
+                        }
+                        if let Some(recording_id) = progress_id {
+                            progress.bytes(recording_id, done);
+                        }
+                        Some((Ok(Frame::data(Bytes::from(buf))), (Some(file), done)))
+                    }
+                    Err(e) => Some((Err(e), (None, done))),
+                }
+            }
+        });
+
+        let mut request = self.http.put(url.clone())
+            .header(reqwest::header::CONTENT_LENGTH, total)
+            .header(reqwest::header::CONTENT_TYPE, content_type)
+            .body(reqwest::Body::wrap(StreamBody::new(stream)));
+        for (name, value) in target.sign("PUT", &url, &payload_sha256, Utc::now()) {
+            request = request.header(name, value);
+        }
+
+        let res = request.send().await.map_err(|e| format!("S3 upload failed: {}", e))?;
+        if !res.status().is_success() {
+            let status = res.status();
+            let body = res.text().await.unwrap_or_default();
+            return Err(format!("S3 upload failed with HTTP status {}: {}", status, body.trim()));
+        }
+
+        Ok(())
+    }
+
+    // Delete uploaded objects older than the retention period and clear their upload columns
+    async fn expire_uploads(&self) -> Result<(), String> {
+        let (settings, expired) = {
+            let conn = crate::db::open(&self.db_path).map_err(|e| e.to_string())?;
+            let settings = load_settings(&conn)?;
+            let Some(days) = settings.retentionDays.filter(|d| *d > 0) else { return Ok(()) };
+
+            let cutoff = (Utc::now() - chrono::Duration::days(days as i64)).to_rfc3339();
+            (settings, expired_uploads(&conn, &cutoff)?)
+        };
+
+        if expired.is_empty() {
+            return Ok(());
+        }
+
+        let target = build_target(&settings)?;
+        for (recording_id, key, thumbnail_key) in expired {
+            let result = async {
+                target.delete_object(&self.http, &key).await?;
+                if let Some(thumbnail_key) = thumbnail_key {
+                    target.delete_object(&self.http, &thumbnail_key).await?;
+                }
+                let conn = crate::db::open(&self.db_path).map_err(|e| e.to_string())?;
+                conn.execute(
+                    "UPDATE recordings SET upload_key = NULL, uploaded_at = NULL, upload_thumbnail_key = NULL WHERE id = ?1",
+                    [recording_id],
+                ).map_err(|e| e.to_string())?;
+                Ok::<(), String>(())
+            }.await;
+
+            match result {
+                Ok(()) => println!("[Upload] Expired uploaded copy of recording {}", recording_id),
+                Err(e) => eprintln!("[Upload] Failed to expire upload of recording {}: {}", recording_id, e),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// Recordings uploaded before `cutoff` with their object and thumbnail keys. Uploads of
+// protected recordings are kept like the recordings themselves.
+fn expired_uploads(conn: &Connection, cutoff: &str) -> Result<Vec<(i32, String, Option<String>)>, String> {
+    let mut stmt = conn.prepare(
+        "SELECT id, upload_key, upload_thumbnail_key FROM recordings
+         WHERE upload_key IS NOT NULL AND uploaded_at < ?1 AND is_protected = 0"
+    ).map_err(|e| e.to_string())?;
+    let expired = stmt.query_map([cutoff], |row| {
+        Ok((row.get::<_, i32>(0)?, row.get::<_, String>(1)?, row.get::<_, Option<String>>(2)?))
+    }).map_err(|e| e.to_string())?
+        .filter_map(|row| row.ok())
+        .collect();
+    Ok(expired)
+}
+
+async fn hash_file(path: &Path) -> Result<String, String> {
+    let mut file = File::open(path).await.map_err(|e| e.to_string())?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; CHUNK_SIZE];
+
+    loop {
+        let n = file.read(&mut buf).await.map_err(|e| e.to_string())?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+
+    Ok(crate::archive::to_hex(&hasher.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::test_db::TestDb;
+
+    #[test]
+    fn uploads_of_protected_recordings_dont_expire() {
+        let db = TestDb::new("upload-expiry");
+        let camera_id = db.add_camera("Gate");
+        for (filename, uploaded_at, is_protected) in [
+            ("old.mp4", "2024-05-01T00:00:00+00:00", false),
+            ("protected.mp4", "2024-05-01T00:00:00+00:00", true),
+            ("recent.mp4", "2024-06-01T00:00:00+00:00", false),
+        ] {
+            db.conn.execute(
+                "INSERT INTO recordings (camera_id, filename, start_time, is_finished, upload_key, uploaded_at, is_protected)
+                 VALUES (?1, ?2, ?3, 1, ?2, ?3, ?4)",
+                rusqlite::params![camera_id, filename, uploaded_at, is_protected],
+            ).unwrap();
+        }
+
+        let expired = expired_uploads(&db.conn, "2024-05-15T00:00:00+00:00").unwrap();
+        let keys: Vec<&str> = expired.iter().map(|(_, key, _)| key.as_str()).collect();
+        assert_eq!(keys, ["old.mp4"]);
+    }
+}
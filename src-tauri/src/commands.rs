@@ -1,56 +1,29 @@
 use tauri::State;
-use crate::models::{Camera, NewCamera, Recording, PTZCapabilities, PTZMovement, PTZResult, CameraTimeInfo, TimeSyncResult, CameraCapabilities, EncoderSettings, UpdateEncoderSettings, RecordingSchedule, NewRecordingSchedule, UpdateRecordingSchedule};
+use crate::models::{Camera, NewCamera, CameraImport, SkippedCamera, CameraGroup, NewCameraGroup, DeleteCameraOptions, InputTuning, VideoFilters, OnvifTrace, CameraDiagnostics, AppUser, NewAppUser, UpdateAppUser, AuthSession, AuthStatus, OnvifUser, OnvifUserChange, NetworkConfig, NetworkChange, NetworkChangeResult, ProbedDevice, QuickAddedCamera, PluginConfig, RpiCameraInfo, Recording, RecordingSession, RecordingFilter, RecordingGap, RecordingMeta, VerifyReport, AudioMonitorSettings, UpdateAudioMonitorSettings, MotionDetectorSettings, UpdateMotionDetectorSettings, AnalyticsSettings, UpdateAnalyticsSettings, ObjectDetectorSettings, UpdateObjectDetectorSettings, ServerSettings, UpdateServerSettings, HlsSettings, UpdateHlsSettings, CameraHlsSettings, TimeshiftInfo, RecordingSettings, UpdateRecordingSettings, TriggerRequest, TriggerResult, TriggerSettings, UpdateTriggerSettings, MqttSettings, UpdateMqttSettings, BatchResult, BatchFailure, PlaybackSession, PipelineStatus, PipelineCleanup, BackendHeartbeat, DiagnosticsBundle, ResourceUsage, RecordingTimeline, CameraEvent, ArchiveJob, ArchiveSettings, UpdateArchiveSettings, CloudUploadSettings, UpdateCloudUploadSettings, CameraUploadPolicy, CameraRecordingFormat, CameraPrebuffer, StorageLocation, NewStorageLocation, CameraStorage, UploadJob, BackgroundJob, RecordingTrigger, UpdateRecordingMeta, PTZCapabilities, PTZMovement, PTZResult, TlsCertificate, CameraTimeInfo, CameraClockStatus, CameraClockReport, TimeSyncResult, CameraCapabilities, EncoderSettings, UpdateEncoderSettings, RecordingSchedule, NewRecordingSchedule, UpdateRecordingSchedule, NewWeeklySchedule, UpdateWeeklySchedule, NewSnapshotSchedule, UpdateSnapshotSchedule, Snapshot, CapturedFrame, SchedulerSettings, UpdateSchedulerSettings, ScheduleRun, ActivityReport, ReportRange, Watermark};
 use crate::AppState;
+use crate::camera_plugin::DiscoveryScan;
+use crate::events::{AppEvent, EventBus};
 use crate::gpu_detector::{detect_gpu_capabilities, GpuCapabilities};
-use rusqlite::Connection;
+use crate::messages::AppMessage;
+use crate::repository::{CameraGroupRepo, CameraRepo, RecordingRepo, ScheduleRepo};
+use rusqlite::{Connection, OptionalExtension};
 use chrono::{Utc, DateTime};
 use tokio_cron_scheduler::Job;
-use chrono_tz::Asia::Tokyo;
-use std::sync::Arc;
+use serde_json::json;
 
 fn get_conn(state: &State<AppState>) -> Result<Connection, String> {
-    Connection::open(&state.db_path).map_err(|e| e.to_string())
+    crate::db::open(&state.db_path).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
 pub async fn get_cameras(state: State<'_, AppState>) -> Result<Vec<Camera>, String> {
-    let conn = get_conn(&state)?;
-    let mut stmt = conn.prepare(
-        "SELECT id, name, type, host, port, user, pass, xaddr, stream_path,
-                device_path, device_id, device_index,
-                video_format, video_width, video_height, video_fps,
-                created_at, updated_at
-         FROM cameras"
-    ).map_err(|e| e.to_string())?;
-
-    let cameras_iter = stmt.query_map([], |row| {
-        Ok(Camera {
-            id: row.get(0)?,
-            name: row.get(1)?,
-            camera_type: row.get(2)?,
-            host: row.get(3)?,
-            port: row.get(4)?,
-            user: row.get(5)?,
-            pass: row.get(6)?,
-            xaddr: row.get(7)?,
-            stream_path: row.get(8)?,
-            device_path: row.get(9)?,
-            device_id: row.get(10)?,
-            device_index: row.get(11)?,
-            video_format: row.get(12)?,
-            video_width: row.get(13)?,
-            video_height: row.get(14)?,
-            video_fps: row.get(15)?,
-            created_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(16)?).unwrap_or(Utc::now().into()).with_timezone(&Utc),
-            updated_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(17)?).unwrap_or(Utc::now().into()).with_timezone(&Utc),
-        })
-    }).map_err(|e| e.to_string())?;
+    CameraRepo::list(&get_conn(&state)?, false)
+}
 
-    let mut cameras = Vec::new();
-    for camera in cameras_iter {
-        cameras.push(camera.map_err(|e| e.to_string())?);
-    }
-    Ok(cameras)
+/// Cameras deleted with the archive option; their recordings are still listed
+#[tauri::command]
+pub async fn get_archived_cameras(state: State<'_, AppState>) -> Result<Vec<Camera>, String> {
+    CameraRepo::list(&get_conn(&state)?, true)
 }
 
 #[tauri::command]
@@ -58,14 +31,27 @@ pub async fn add_camera(state: State<'_, AppState>, camera: NewCamera) -> Result
     println!("[AddCamera] Received camera: name='{}', type='{}', device_path={:?}",
              camera.name, camera.camera_type, camera.device_path);
 
+    let rtsp_transport = camera.rtsp_transport.clone().unwrap_or_else(|| "tcp".to_string());
+    check_rtsp_transport(&rtsp_transport)?;
+    if let Some(seconds) = camera.stream_timeout_secs {
+        check_stream_timeout(seconds)?;
+    }
+    if let Some(pem) = &camera.tls_cert_pem {
+        crate::onvif::check_certificate(pem)?;
+    }
+
     let conn = get_conn(&state)?;
     let now = Utc::now().to_rfc3339();
+    // New cameras go to the end of the list
+    let sort_order: i32 = conn.query_row("SELECT COALESCE(MAX(sort_order), 0) + 1 FROM cameras", [], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
     conn.execute(
         "INSERT INTO cameras (name, type, host, port, user, pass, xaddr, stream_path,
                              device_path, device_id, device_index,
                              video_format, video_width, video_height, video_fps,
-                             created_at, updated_at)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17)",
+                             tls_verify, rtsp_transport, stream_timeout_secs, created_at, updated_at, tls_cert_pem, sort_order,
+                             profile_token)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22, ?23)",
         &[
             &camera.name as &dyn rusqlite::ToSql,
             &camera.camera_type,
@@ -82,13 +68,21 @@ pub async fn add_camera(state: State<'_, AppState>, camera: NewCamera) -> Result
             &camera.video_width,
             &camera.video_height,
             &camera.video_fps,
+            &camera.tls_verify,
+            &rtsp_transport,
+            &camera.stream_timeout_secs,
             &now,
             &now,
+            &camera.tls_cert_pem,
+            &sort_order,
+            &camera.profile_token,
         ] as &[&dyn rusqlite::ToSql],
     ).map_err(|e| e.to_string())?;
 
     let id = conn.last_insert_rowid() as i32;
-    
+    state.mqtt.publish_camera(id, &camera.name, &camera.camera_type);
+    crate::alarms::start(&state.app_handle, id, &camera.camera_type);
+
     // Return the created camera (fetch it back or construct it)
     // Constructing is faster
     Ok(Camera {
@@ -108,404 +102,3284 @@ pub async fn add_camera(state: State<'_, AppState>, camera: NewCamera) -> Result
         video_width: camera.video_width,
         video_height: camera.video_height,
         video_fps: camera.video_fps,
+        tls_verify: camera.tls_verify,
+        tls_cert_pem: camera.tls_cert_pem,
+        rtsp_transport,
+        stream_timeout_secs: camera.stream_timeout_secs,
+        input_tuning: Default::default(),
+        extra_input_args: Vec::new(),
+        extra_output_args: Vec::new(),
+        quality_preset: None,
+        video_filters: Default::default(),
+        time_sync_strategy: None,
+        profile_token: camera.profile_token,
+        sort_order,
+        is_favorite: false,
         created_at: Utc::now(),
         updated_at: Utc::now(),
     })
 }
 
+/// Add a multichannel ONVIF device (encoder, NVR) as one camera per channel, all sharing its
+/// endpoint and credentials. `profile_tokens` picks channels from probe_onvif_device; None
+/// adds every channel the device lists.
 #[tauri::command]
-pub async fn delete_camera(state: State<'_, AppState>, id: i32) -> Result<(), String> {
-    let conn = get_conn(&state)?;
-    conn.execute("DELETE FROM cameras WHERE id = ?1", [id]).map_err(|e| e.to_string())?;
-    Ok(())
-}
-
-#[tauri::command]
-pub async fn discover_cameras(state: State<'_, AppState>) -> Result<Vec<crate::camera_plugin::CameraInfo>, String> {
-    println!("[Discovery] Discovering cameras from all plugins...");
-
-    // Use plugin manager to discover cameras from all plugins
-    let plugin_cameras = state.plugin_manager.discover_all().await?;
+pub async fn add_camera_channels(
+    state: State<'_, AppState>,
+    camera: NewCamera,
+    profile_tokens: Option<Vec<String>>,
+) -> Result<Vec<Camera>, String> {
+    if camera.camera_type != "onvif" {
+        return Err("Channels can only be added for ONVIF cameras".to_string());
+    }
+    let xaddr = camera.xaddr.clone().ok_or("No xAddr available for ONVIF camera")?;
+    let channels = crate::onvif::fetch_channels(
+        state.onvif_http.for_tls(camera.tls_verify),
+        &xaddr,
+        camera.user.as_deref().unwrap_or_default(),
+        camera.pass.as_deref().unwrap_or_default(),
+    ).await?;
+
+    let selected: Vec<_> = match &profile_tokens {
+        Some(tokens) => {
+            if let Some(unknown) = tokens.iter().find(|token| !channels.iter().any(|c| &c.profile_token == *token)) {
+                return Err(format!("The device has no channel with profile '{}'", unknown));
+            }
+            channels.iter().filter(|channel| tokens.contains(&channel.profile_token)).collect()
+        }
+        None => channels.iter().collect(),
+    };
+    if selected.is_empty() {
+        return Err("The device lists no video channels".to_string());
+    }
 
-    println!("[Discovery] Found {} camera(s) total", plugin_cameras.len());
+    let mut cameras = Vec::new();
+    for (index, channel) in selected.iter().enumerate() {
+        let mut channel_camera = camera.clone();
+        channel_camera.profile_token = Some(channel.profile_token.clone());
+        if selected.len() > 1 {
+            let label = if channel.profile_name.is_empty() { format!("Channel {}", index + 1) } else { channel.profile_name.clone() };
+            channel_camera.name = format!("{} - {}", camera.name, label);
+        }
+        cameras.push(add_camera(state.clone(), channel_camera).await?);
+    }
 
-    Ok(plugin_cameras)
+    println!("[AddCamera] Added {} channel(s) of {}", cameras.len(), crate::redact::redact(&xaddr));
+    Ok(cameras)
 }
 
+/// Create an RTSP camera from its stream URL alone: host, port, path and credentials come
+/// from the URL, resolution and frame rate from probing the stream. The camera is only saved
+/// when the stream answers. `name` defaults to the camera's host.
 #[tauri::command]
-pub async fn start_stream(state: State<'_, AppState>, id: i32) -> Result<serde_json::Value, String> {
-    // Get camera details
-    let cameras = get_cameras(state.clone()).await?;
-    let camera = cameras.into_iter().find(|c| c.id == id).ok_or("Camera not found")?;
-    
-    // Start FFmpeg process via stream module
-    match crate::stream::start_stream(state.clone(), camera).await {
-        Ok(stream_path_relative) => {
-            let port = state.server_port;
-            Ok(serde_json::json!({ "streamUrl": format!("http://localhost:{}/{}", port, stream_path_relative) }))
-        },
-        Err(e) => {
-            eprintln!("[Error] Failed to start stream for camera {}: {}", id, e);
-            Err(e)
-        }
+pub async fn add_camera_from_url(
+    state: State<'_, AppState>,
+    rtsp_url: String,
+    name: Option<String>,
+) -> Result<QuickAddedCamera, String> {
+    let name = name.map(|name| name.trim().to_string()).filter(|name| !name.is_empty());
+    let mut camera = crate::nvr_config::camera_from_url(name.as_deref().unwrap_or_default(), &rtsp_url)?;
+    if camera.name.is_empty() {
+        camera.name = camera.host.clone();
     }
-}
 
-#[tauri::command]
-pub async fn stop_stream(state: State<'_, AppState>, id: i32) -> Result<serde_json::Value, String> {
-    crate::stream::stop_stream(state, id).await.map_err(|e| e.to_string())?;
-    Ok(serde_json::json!({ "success": true }))
+    let probe = crate::stream::probe_stream(rtsp_url.trim()).await?;
+    camera.video_width = probe.width;
+    camera.video_height = probe.height;
+    camera.video_fps = probe.fps;
+
+    let camera = add_camera(state, camera).await?;
+    println!(
+        "[AddCamera] Added {} from its stream URL: {} {}x{} at {} fps",
+        camera.name,
+        probe.video_codec.as_deref().unwrap_or("unknown codec"),
+        probe.width.unwrap_or(0),
+        probe.height.unwrap_or(0),
+        probe.fps.unwrap_or(0),
+    );
+    Ok(QuickAddedCamera { camera, probe })
 }
 
+/// Create cameras from a Frigate config, a Shinobi monitor export or an exported camera list.
+/// Cameras named like an existing one are skipped; a dry run only reports what would be created.
 #[tauri::command]
-pub async fn start_recording(state: State<'_, AppState>, id: i32) -> Result<serde_json::Value, String> {
-    let cameras = get_cameras(state.clone()).await?;
-    let camera = cameras.into_iter().find(|c| c.id == id).ok_or("Camera not found")?;
-
-    // For UVC cameras: stop streaming if active (device can only be accessed by one process)
-    if camera.camera_type == "uvc" {
-        let was_streaming = {
-            let processes = state.processes.lock().map_err(|e| e.to_string())?;
-            processes.contains_key(&id)
-        };
-
-        if was_streaming {
-            println!("[Recording] UVC camera {} is streaming, stopping stream before recording", id);
+pub async fn import_cameras(state: State<'_, AppState>, path: String, dry_run: bool) -> Result<CameraImport, String> {
+    let text = std::fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    let parsed = crate::nvr_config::parse_import(&text)?;
+    let mut names: std::collections::HashSet<String> = {
+        let conn = get_conn(&state)?;
+        [false, true].into_iter()
+            .map(|archived| CameraRepo::list(&conn, archived))
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .flatten()
+            .map(|camera| camera.name.to_lowercase())
+            .collect()
+    };
 
-            // Stop current stream
-            if let Err(e) = crate::stream::stop_stream(state.clone(), id).await {
-                println!("[Recording] Warning: Failed to stop stream: {}", e);
+    let mut import = CameraImport { source: parsed.source.to_string(), cameras: Vec::new(), skipped: parsed.skipped, created: Vec::new() };
+    for camera in parsed.cameras {
+        if !names.insert(camera.name.to_lowercase()) {
+            import.skipped.push(SkippedCamera { name: camera.name, reason: "A camera with this name already exists".to_string() });
+            continue;
+        }
+        if !dry_run {
+            match add_camera(state.clone(), camera.clone()).await {
+                Ok(created) => import.created.push(created.id),
+                Err(reason) => {
+                    import.skipped.push(SkippedCamera { name: camera.name, reason });
+                    continue;
+                }
             }
-
-            // Wait for cleanup
-            tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
-
-            println!("[Recording] Stream stopped, starting recording for camera {}", id);
         }
+        import.cameras.push(camera);
     }
 
-    crate::stream::start_recording(state, camera).await.map_err(|e| e.to_string())?;
-    Ok(serde_json::json!({ "success": true }))
+    println!("[Import] {} cameras from {} config {} ({} skipped){}",
+             import.cameras.len(), import.source, path, import.skipped.len(), if dry_run { ", dry run" } else { "" });
+    Ok(import)
 }
 
+/// Write the camera list (archived cameras excluded) to `path` in the portable format, as YAML
+/// for .yaml/.yml files and JSON otherwise; returns the number of cameras
 #[tauri::command]
-pub async fn stop_recording(
-    state: State<'_, AppState>,
-    app_handle: tauri::AppHandle,
-    id: i32
-) -> Result<serde_json::Value, String> {
-    crate::stream::stop_recording(state, app_handle, id).await.map_err(|e| e.to_string())?;
-    Ok(serde_json::json!({ "success": true }))
+pub async fn export_cameras(state: State<'_, AppState>, path: String, include_credentials: bool) -> Result<usize, String> {
+    let cameras = CameraRepo::list(&get_conn(&state)?, false)?;
+    let yaml = std::path::Path::new(&path).extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("yaml") || extension.eq_ignore_ascii_case("yml"));
+    let text = crate::nvr_config::export_cameras(&cameras, yaml, include_credentials)?;
+    std::fs::write(&path, text).map_err(|e| format!("Failed to write {}: {}", path, e))?;
+    println!("[Export] {} cameras written to {}", cameras.len(), path);
+    Ok(cameras.len())
 }
 
 #[tauri::command]
-pub async fn get_recordings(state: State<'_, AppState>) -> Result<Vec<Recording>, String> {
+pub async fn delete_camera(state: State<'_, AppState>, id: i32, options: Option<DeleteCameraOptions>) -> Result<(), String> {
+    let options = options.unwrap_or_default();
     let conn = get_conn(&state)?;
-    let mut stmt = conn.prepare(
-        "SELECT r.id, r.camera_id, r.filename, r.thumbnail, r.start_time, r.end_time, r.is_finished, c.name 
-         FROM recordings r 
-         LEFT JOIN cameras c ON r.camera_id = c.id 
-         ORDER BY r.start_time DESC"
-    ).map_err(|e| e.to_string())?;
-    
-    let recordings_iter = stmt.query_map([], |row| {
-        Ok(Recording {
-            id: row.get(0)?,
-            camera_id: row.get(1)?,
-            filename: row.get(2)?,
-            thumbnail: row.get(3)?,
-            start_time: DateTime::parse_from_rfc3339(&row.get::<_, String>(4)?).unwrap_or(Utc::now().into()).with_timezone(&Utc),
-            end_time: row.get::<_, Option<String>>(5)?.map(|t| DateTime::parse_from_rfc3339(&t).unwrap_or(Utc::now().into()).with_timezone(&Utc)),
-            is_finished: row.get(6)?,
-            camera_name: row.get(7)?,
-        })
-    }).map_err(|e| e.to_string())?;
 
-    let mut recordings = Vec::new();
-    for r in recordings_iter {
-        recordings.push(r.map_err(|e| e.to_string())?);
+    let recording_ids: Vec<i32> = {
+        let mut stmt = conn.prepare("SELECT id FROM recordings WHERE camera_id = ?1").map_err(|e| e.to_string())?;
+        let ids = stmt.query_map([id], |row| row.get(0)).map_err(|e| e.to_string())?;
+        ids.collect::<Result<_, _>>().map_err(|e| e.to_string())?
+    };
+    // Recordings can't outlive their camera row
+    if !options.archive && !options.deleteRecordings && !recording_ids.is_empty() {
+        return Err(format!(
+            "Camera has {} recording(s); archive the camera to keep them or delete them with it",
+            recording_ids.len()
+        ));
+    }
+    // Protected recordings are never deleted along with their camera
+    if options.deleteRecordings {
+        let protected: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM recordings WHERE camera_id = ?1 AND is_protected = 1",
+            [id],
+            |row| row.get(0),
+        ).map_err(|e| e.to_string())?;
+        if protected > 0 {
+            return Err(format!(
+                "Camera has {} protected recording(s); unprotect them or archive the camera to keep them",
+                protected
+            ));
+        }
     }
-    Ok(recordings)
-}
 
-#[tauri::command]
-pub async fn delete_recording(state: State<'_, AppState>, id: i32) -> Result<(), String> {
-    let conn = get_conn(&state)?;
-    
-    // Get filename to delete
-    let filename: String = conn.query_row(
-        "SELECT filename FROM recordings WHERE id = ?1",
+    // Forget cached ONVIF service endpoints for this camera
+    let xaddr: Option<String> = conn.query_row(
+        "SELECT xaddr FROM cameras WHERE id = ?1",
         [id],
         |row| row.get(0)
-    ).map_err(|e| e.to_string())?;
+    ).unwrap_or(None);
+    if let Some(xaddr) = xaddr {
+        crate::onvif::invalidate_services(&xaddr);
+    }
 
-    // Delete file from filesystem
-    let file_path = state.recording_dir.join(&filename);
-    if file_path.exists() {
-        std::fs::remove_file(file_path).map_err(|e| e.to_string())?;
+    crate::audio_monitor::stop(&state, id);
+    crate::motion::stop(&state, id);
+    crate::prebuffer::stop(&state, id);
+    crate::analytics::stop(&state, id);
+    crate::alarms::stop(&state, id);
+    state.mqtt.remove_camera(id);
+
+    // Finish a running recording properly before the stream goes
+    let recording = state.recording_processes.lock().map_err(|e| e.to_string())?.contains_key(&id);
+    if recording {
+        if let Err(e) = crate::stream::stop_recording_direct(&state, id).await {
+            eprintln!("[DeleteCamera] Failed to stop recording of camera {}: {}", id, e);
+        }
     }
+    crate::stream::stop_stream(state.clone(), id).await?;
 
-    conn.execute("DELETE FROM recordings WHERE id = ?1", [id]).map_err(|e| e.to_string())?;
+    let schedule_ids: Vec<i32> = {
+        let mut stmt = conn.prepare("SELECT id FROM recording_schedules WHERE camera_id = ?1").map_err(|e| e.to_string())?;
+        let ids = stmt.query_map([id], |row| row.get(0)).map_err(|e| e.to_string())?;
+        ids.collect::<Result<_, _>>().map_err(|e| e.to_string())?
+    };
+    {
+        let scheduler = state.scheduler.lock().await;
+        for schedule_id in &schedule_ids {
+            let _ = scheduler.remove_schedule(*schedule_id).await;
+        }
+    }
+    conn.execute("DELETE FROM recording_schedules WHERE camera_id = ?1", [id]).map_err(|e| e.to_string())?;
+
+    if options.deleteRecordings {
+        for recording_id in &recording_ids {
+            delete_recording_with_conn(&conn, &state.recording_dir, &state.archive_queue, *recording_id, false)?;
+        }
+        let snapshot_ids: Vec<i64> = {
+            let mut stmt = conn.prepare("SELECT id FROM snapshots WHERE camera_id = ?1").map_err(|e| e.to_string())?;
+            let ids = stmt.query_map([id], |row| row.get(0)).map_err(|e| e.to_string())?;
+            ids.collect::<Result<_, _>>().map_err(|e| e.to_string())?
+        };
+        for snapshot_id in snapshot_ids {
+            crate::snapshots::delete_snapshot(&state.db_path, &state.recording_dir, snapshot_id)?;
+        }
+        conn.execute("DELETE FROM recording_gaps WHERE camera_id = ?1", [id]).map_err(|e| e.to_string())?;
+        println!("[DeleteCamera] Deleted {} recording(s) of camera {}", recording_ids.len(), id);
+    }
+
+    if options.archive {
+        conn.execute("UPDATE cameras SET archived_at = ?1 WHERE id = ?2", rusqlite::params![Utc::now().to_rfc3339(), id])
+            .map_err(|e| e.to_string())?;
+        println!("[DeleteCamera] Archived camera {}", id);
+        return Ok(());
+    }
+
+    // Settings, events and the other per-camera rows go with it (ON DELETE CASCADE)
+    conn.execute("DELETE FROM cameras WHERE id = ?1", [id]).map_err(|e| e.to_string())?;
+    println!("[DeleteCamera] Deleted camera {}", id);
     Ok(())
 }
 
-// Time synchronization commands
+/// Bring an archived camera back into the camera list and restart its background workers
 #[tauri::command]
-pub async fn get_camera_time(state: State<'_, AppState>, id: i32) -> Result<CameraTimeInfo, String> {
-    let cameras = get_cameras(state.clone()).await?;
-    let camera = cameras.into_iter().find(|c| c.id == id).ok_or("Camera not found")?;
+pub async fn restore_camera(state: State<'_, AppState>, id: i32) -> Result<(), String> {
+    let conn = get_conn(&state)?;
+    let (name, camera_type): (String, String) = conn.query_row(
+        "SELECT name, type FROM cameras WHERE id = ?1 AND archived_at IS NOT NULL",
+        [id],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    ).optional().map_err(|e| e.to_string())?.ok_or("Archived camera not found")?;
 
-    if camera.camera_type != "onvif" {
-        return Err("Time synchronization is only supported for ONVIF cameras".to_string());
+    conn.execute("UPDATE cameras SET archived_at = NULL, updated_at = ?1 WHERE id = ?2", rusqlite::params![Utc::now().to_rfc3339(), id])
+        .map_err(|e| e.to_string())?;
+
+    state.mqtt.publish_camera(id, &name, &camera_type);
+    crate::alarms::start(&state.app_handle, id, &camera_type);
+    if crate::audio_monitor::load_settings(&conn, id)?.enabled {
+        crate::audio_monitor::start(&state.app_handle, id);
+    }
+    if crate::motion::load_settings(&conn, id)?.enabled {
+        crate::motion::start(&state.app_handle, id);
+    }
+    if crate::prebuffer::load_seconds(&conn, id)?.is_some() {
+        crate::prebuffer::start(&state.app_handle, id);
+    }
+    if crate::analytics::load_settings(&conn, id)?.enabled {
+        crate::analytics::start(&state.app_handle, id);
     }
 
-    let camera_datetime = crate::onvif::get_system_date_time(&camera).await?;
-    let server_time = Utc::now();
+    println!("[RestoreCamera] Restored camera {}", id);
+    Ok(())
+}
 
-    Ok(CameraTimeInfo {
-        cameraTime: serde_json::json!({
-            "year": camera_datetime.year,
-            "month": camera_datetime.month,
-            "day": camera_datetime.day,
-            "hour": camera_datetime.hour,
-            "minute": camera_datetime.minute,
-            "second": camera_datetime.second,
-        }),
-        serverTime: server_time.to_rfc3339(),
-    })
+#[tauri::command]
+pub async fn get_camera_groups(state: State<'_, AppState>) -> Result<Vec<CameraGroup>, String> {
+    CameraGroupRepo::list(&get_conn(&state)?)
 }
 
 #[tauri::command]
-pub async fn sync_camera_time(state: State<'_, AppState>, id: i32) -> Result<TimeSyncResult, String> {
-    let cameras = get_cameras(state.clone()).await?;
-    let camera = cameras.into_iter().find(|c| c.id == id).ok_or("Camera not found")?;
+pub async fn add_camera_group(state: State<'_, AppState>, group: NewCameraGroup) -> Result<CameraGroup, String> {
+    let mut conn = get_conn(&state)?;
+    let name = check_camera_group(&group)?;
+    let id = CameraGroupRepo::save(&mut conn, None, name, &group.camera_ids)?;
+    CameraGroupRepo::get(&conn, id)
+}
 
-    if camera.camera_type != "onvif" {
-        return Err("Time synchronization is only supported for ONVIF cameras".to_string());
-    }
+/// Rename a group and replace its members; schedules targeting it use the new members from
+/// their next run
+#[tauri::command]
+pub async fn update_camera_group(state: State<'_, AppState>, id: i64, group: NewCameraGroup) -> Result<CameraGroup, String> {
+    let mut conn = get_conn(&state)?;
+    let name = check_camera_group(&group)?;
+    CameraGroupRepo::save(&mut conn, Some(id), name, &group.camera_ids)?;
+    CameraGroupRepo::get(&conn, id)
+}
 
-    // Check if streaming is currently active
-    let was_streaming = {
-        let processes = state.processes.lock().map_err(|e| e.to_string())?;
-        processes.contains_key(&id)
-    };
+/// Delete a group that no schedule targets
+#[tauri::command]
+pub async fn delete_camera_group(state: State<'_, AppState>, id: i64) -> Result<(), String> {
+    let conn = get_conn(&state)?;
+    let schedules: i64 = conn.query_row("SELECT COUNT(*) FROM recording_schedules WHERE group_id = ?1", [id], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+    if schedules > 0 {
+        return Err(format!("Camera group is used by {} schedule(s); delete or change them first", schedules));
+    }
+    conn.execute("DELETE FROM camera_groups WHERE id = ?1", [id]).map_err(|e| e.to_string())?;
+    Ok(())
+}
 
-    // Get current camera time before sync
-    let before_datetime = crate::onvif::get_system_date_time(&camera).await?;
+fn check_camera_group(group: &NewCameraGroup) -> Result<&str, String> {
+    let name = group.name.trim();
+    if name.is_empty() {
+        return Err("Camera group name must not be empty".to_string());
+    }
+    Ok(name)
+}
 
-    // Get server time
-    let server_time = Utc::now();
+/// Store the order of the camera list and grid. Cameras missing from `ids` keep their
+/// relative order after the listed ones.
+#[tauri::command]
+pub async fn reorder_cameras(state: State<'_, AppState>, ids: Vec<i32>) -> Result<(), String> {
+    let mut conn = get_conn(&state)?;
+    let current: Vec<i32> = {
+        let mut stmt = conn.prepare("SELECT id FROM cameras ORDER BY sort_order, id").map_err(|e| e.to_string())?;
+        let ids = stmt.query_map([], |row| row.get(0)).map_err(|e| e.to_string())?;
+        ids.collect::<Result<_, _>>().map_err(|e| e.to_string())?
+    };
+    if let Some(unknown) = ids.iter().find(|id| !current.contains(id)) {
+        return Err(format!("Camera {} not found", unknown));
+    }
 
-    // Convert server time to ONVIF format
-    let new_datetime = crate::onvif::ONVIFDateTime::from_chrono(&server_time);
+    let mut order: Vec<i32> = Vec::with_capacity(current.len());
+    for id in ids.into_iter().chain(current) {
+        if !order.contains(&id) {
+            order.push(id);
+        }
+    }
 
-    // Set camera time
-    crate::onvif::set_system_date_time(&camera, &new_datetime).await?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    for (position, id) in order.iter().enumerate() {
+        tx.execute("UPDATE cameras SET sort_order = ?1 WHERE id = ?2", rusqlite::params![position as i32 + 1, id])
+            .map_err(|e| e.to_string())?;
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(())
+}
 
-    // Wait a moment for the camera to process the time change
-    tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+#[tauri::command]
+pub async fn set_favorite(state: State<'_, AppState>, id: i32, favorite: bool) -> Result<(), String> {
+    let conn = get_conn(&state)?;
+    let updated = conn.execute(
+        "UPDATE cameras SET is_favorite = ?1, updated_at = ?2 WHERE id = ?3",
+        rusqlite::params![favorite, Utc::now().to_rfc3339(), id],
+    ).map_err(|e| e.to_string())?;
+    if updated == 0 {
+        return Err("Camera not found".to_string());
+    }
+    Ok(())
+}
 
-    // Verify by reading the time again
-    let after_datetime = match crate::onvif::get_system_date_time(&camera).await {
-        Ok(dt) => Some(dt),
-        Err(e) => {
-            println!("[TimeSync] Warning: Could not verify time after sync: {}", e);
-            None
-        }
-    };
+// Set existing_camera_id on discovery results that are already in the cameras table
+fn mark_registered(conn: &Connection, cameras: &mut [crate::camera_plugin::CameraInfo]) -> Result<(), String> {
+    type Row = (i32, String, String, Option<String>, Option<String>, Option<u32>);
+    let mut stmt = conn.prepare("SELECT id, type, host, device_path, device_id, device_index FROM cameras WHERE archived_at IS NULL")
+        .map_err(|e| e.to_string())?;
+    let registered = stmt.query_map([], |row| {
+        Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?, row.get(5)?))
+    })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<Row>, _>>()
+        .map_err(|e| e.to_string())?;
 
-    // Restart streaming if it was active before time sync
-    if was_streaming {
-        println!("[TimeSync] Restarting stream for camera {} after time sync", id);
+    for camera in cameras.iter_mut() {
+        camera.existing_camera_id = registered.iter()
+            .find(|(_, camera_type, host, device_path, device_id, device_index)| {
+                if camera.is_local() {
+                    *camera_type == camera.camera_type
+                        && *device_path == camera.device_path
+                        && *device_id == camera.device_id
+                        && *device_index == camera.device_index
+                } else {
+                    host.eq_ignore_ascii_case(&camera.host)
+                }
+            })
+            .map(|(id, ..)| *id);
+    }
+    Ok(())
+}
 
-        // Stop current stream
-        if let Err(e) = crate::stream::stop_stream(state.clone(), id).await {
-            println!("[TimeSync] Warning: Failed to stop stream: {}", e);
-        }
+#[tauri::command]
+pub async fn discover_cameras(state: State<'_, AppState>) -> Result<Vec<crate::camera_plugin::CameraInfo>, String> {
+    println!("[Discovery] Discovering cameras from all plugins...");
 
-        // Wait for cleanup
-        tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+    // Use plugin manager to discover cameras from all plugins (one entry per device)
+    let mut plugin_cameras = state.plugin_manager.discover_all().await?;
 
-        // Restart stream
-        if let Err(e) = crate::stream::start_stream(state.clone(), camera.clone()).await {
-            println!("[TimeSync] Warning: Failed to restart stream: {}", e);
-        } else {
-            println!("[TimeSync] Stream restarted successfully for camera {}", id);
-        }
+    mark_registered(&get_conn(&state)?, &mut plugin_cameras)?;
+    let thumbnails = futures::future::join_all(
+        plugin_cameras.iter().map(|camera| state.plugin_manager.discovery_thumbnail(camera))
+    ).await;
+    for (camera, thumbnail) in plugin_cameras.iter_mut().zip(thumbnails) {
+        camera.thumbnail = thumbnail;
     }
 
-    // Calculate time difference
-    let before_chrono = before_datetime.to_chrono().ok_or("Invalid camera time format")?;
-    let time_diff = server_time.signed_duration_since(before_chrono);
-    let diff_seconds = time_diff.num_seconds();
+    println!("[Discovery] Found {} camera(s) total", plugin_cameras.len());
 
-    // Check if verification shows the time was actually set
-    let message = if let Some(after_dt) = after_datetime {
-        let after_chrono = after_dt.to_chrono().ok_or("Invalid camera time format")?;
-        let final_diff = Utc::now().signed_duration_since(after_chrono).num_seconds();
+    Ok(plugin_cameras)
+}
 
-        if final_diff.abs() < 5 {
-            format!("Camera time synchronized successfully (adjusted by {}s, verified)", diff_seconds)
-        } else {
-            format!("Camera time may not have been set correctly (before diff: {}s, after diff: {}s)", diff_seconds, final_diff)
+/// Probe an ONVIF device at a known address (when WS-Discovery can't reach it). Without a
+/// port, the HTTP ports of the discovery settings (or the common ONVIF ports) are tried with
+/// the discovery timeout and retries.
+#[tauri::command]
+pub async fn probe_onvif_device(
+    state: State<'_, AppState>,
+    host: String,
+    port: Option<u16>,
+    user: Option<String>,
+    pass: Option<String>,
+) -> Result<ProbedDevice, String> {
+    if host.trim().is_empty() {
+        return Err("Host is required".to_string());
+    }
+
+    let port = match port {
+        Some(port) => port,
+        None => {
+            let config = crate::plugin_config::load(&get_conn(&state)?, "onvif")?;
+            let options = crate::onvif::DiscoveryOptions::from_config(&config);
+            let http_ports: Vec<u16> = options.ports.iter().copied()
+                .filter(|&port| port != crate::onvif::WS_DISCOVERY_PORT)
+                .collect();
+            crate::onvif::find_device_port(&state.onvif_http, &host, &http_ports, &options).await?
         }
-    } else if diff_seconds.abs() < 2 {
-        format!("Camera time is already synchronized (difference: {}s)", diff_seconds)
-    } else {
-        format!("Camera time command sent (adjusted by {}s, verification unavailable)", diff_seconds)
     };
 
-    println!("[TimeSync] Camera {} - {}", id, message);
-
-    Ok(TimeSyncResult {
-        success: true,
-        beforeTime: serde_json::json!({
-            "year": before_datetime.year,
-            "month": before_datetime.month,
-            "day": before_datetime.day,
-            "hour": before_datetime.hour,
-            "minute": before_datetime.minute,
-            "second": before_datetime.second,
-        }),
-        serverTime: server_time.to_rfc3339(),
-        message,
-        error: None,
-    })
+    crate::onvif::probe_device(
+        &state.onvif_http,
+        &host,
+        port,
+        user.as_deref().unwrap_or_default(),
+        pass.as_deref().unwrap_or_default(),
+    ).await
 }
 
+/// Probe a Hikvision camera over ISAPI (firmwares with ONVIF disabled or broken)
 #[tauri::command]
-pub async fn check_ptz_capabilities(state: State<'_, AppState>, id: i32) -> Result<PTZCapabilities, String> {
-    let cameras = get_cameras(state.clone()).await?;
-    let camera = cameras.into_iter().find(|c| c.id == id).ok_or("Camera not found")?;
-
-    if camera.camera_type != "onvif" {
-        return Ok(PTZCapabilities { supported: false, capabilities: None });
+pub async fn probe_hikvision_device(
+    state: State<'_, AppState>,
+    host: String,
+    port: u16,
+    user: Option<String>,
+    pass: Option<String>,
+    tls_verify: Option<bool>,
+) -> Result<ProbedDevice, String> {
+    if host.trim().is_empty() {
+        return Err("Host is required".to_string());
     }
 
-    match crate::onvif::get_ptz_service_url(&camera).await {
-        Ok(_) => Ok(PTZCapabilities { 
-            supported: true, 
-            capabilities: Some(crate::models::PTZCapabilitiesDetails { hasPanTilt: true, hasZoom: true }) 
-        }),
-        Err(_) => Ok(PTZCapabilities { supported: false, capabilities: None })
-    }
+    crate::plugins::hikvision_plugin::probe(
+        state.onvif_http.for_tls(tls_verify.unwrap_or(true)),
+        &host,
+        port,
+        user.as_deref().unwrap_or_default(),
+        pass.as_deref().unwrap_or_default(),
+    ).await
 }
 
+/// Probe a Dahua or Amcrest camera over its CGI API
 #[tauri::command]
-pub async fn move_ptz(state: State<'_, AppState>, id: i32, movement: PTZMovement) -> Result<PTZResult, String> {
-    let cameras = get_cameras(state.clone()).await?;
-    let camera = cameras.into_iter().find(|c| c.id == id).ok_or("Camera not found")?;
+pub async fn probe_dahua_device(
+    state: State<'_, AppState>,
+    host: String,
+    port: u16,
+    user: Option<String>,
+    pass: Option<String>,
+    tls_verify: Option<bool>,
+) -> Result<ProbedDevice, String> {
+    if host.trim().is_empty() {
+        return Err("Host is required".to_string());
+    }
+
+    crate::plugins::dahua_plugin::probe(
+        state.onvif_http.for_tls(tls_verify.unwrap_or(true)),
+        &host,
+        port,
+        user.as_deref().unwrap_or_default(),
+        pass.as_deref().unwrap_or_default(),
+    ).await
+}
+
+/// Probe an Axis camera over VAPIX (when its ONVIF users are restricted or missing)
+#[tauri::command]
+pub async fn probe_axis_device(
+    state: State<'_, AppState>,
+    host: String,
+    port: u16,
+    user: Option<String>,
+    pass: Option<String>,
+    tls_verify: Option<bool>,
+) -> Result<ProbedDevice, String> {
+    if host.trim().is_empty() {
+        return Err("Host is required".to_string());
+    }
+
+    crate::plugins::axis_plugin::probe(
+        state.onvif_http.for_tls(tls_verify.unwrap_or(true)),
+        &host,
+        port,
+        user.as_deref().unwrap_or_default(),
+        pass.as_deref().unwrap_or_default(),
+    ).await
+}
+
+/// VAPIX parameters of an Axis camera, e.g. group "root.Image" or "root.Properties"
+#[tauri::command]
+pub async fn get_axis_parameters(
+    state: State<'_, AppState>,
+    id: i32,
+    group: String,
+) -> Result<std::collections::HashMap<String, String>, String> {
+    let camera = crate::stream::load_camera(&state.db_path, id)?;
+    if camera.camera_type != "axis" {
+        return Err("This is only supported for Axis cameras".to_string());
+    }
+    crate::plugins::axis_plugin::parameters(&state.onvif_http.for_camera(&camera), &camera, &group).await
+}
+
+/// Raspberry Pi CSI cameras with their sensor modes, for choosing a resolution and frame rate
+#[tauri::command]
+pub async fn list_rpi_cameras() -> Result<Vec<RpiCameraInfo>, String> {
+    tokio::task::spawn_blocking(crate::plugins::rpi_camera_plugin::list_cameras)
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Capture resolution and frame rate of a local camera (UVC or Pi CSI).
+/// A running stream picks the new mode up when it is restarted.
+#[tauri::command]
+pub async fn set_camera_video_mode(
+    state: State<'_, AppState>,
+    id: i32,
+    width: i32,
+    height: i32,
+    fps: i32,
+) -> Result<(), String> {
+    if width <= 0 || height <= 0 || fps <= 0 {
+        return Err("Width, height and frame rate must be positive".to_string());
+    }
+
+    let conn = get_conn(&state)?;
+    let camera_type: String = conn.query_row("SELECT type FROM cameras WHERE id = ?1", [id], |row| row.get(0))
+        .map_err(|_| "Camera not found".to_string())?;
+    if !crate::stream::is_local_device(&camera_type) {
+        return Err("Video modes can only be set for UVC and Pi cameras".to_string());
+    }
+
+    conn.execute(
+        "UPDATE cameras SET video_width = ?1, video_height = ?2, video_fps = ?3, updated_at = CURRENT_TIMESTAMP WHERE id = ?4",
+        rusqlite::params![width, height, fps, id],
+    ).map_err(|e| e.to_string())?;
+
+    println!("[Camera] Camera {} video mode set to {}x{} @ {} fps", id, width, height, fps);
+    Ok(())
+}
+
+fn check_rtsp_transport(transport: &str) -> Result<(), String> {
+    if !crate::stream::RTSP_TRANSPORTS.contains(&transport) {
+        return Err(format!(
+            "Unknown RTSP transport '{}' (expected one of {})",
+            transport,
+            crate::stream::RTSP_TRANSPORTS.join(", ")
+        ));
+    }
+    Ok(())
+}
+
+/// Choose how FFmpeg receives a network camera's RTSP stream. Takes effect the next time
+/// the stream or a recording starts.
+#[tauri::command]
+pub async fn set_camera_rtsp_transport(state: State<'_, AppState>, id: i32, transport: String) -> Result<(), String> {
+    check_rtsp_transport(&transport)?;
+
+    let conn = get_conn(&state)?;
+    let camera_type: String = conn.query_row("SELECT type FROM cameras WHERE id = ?1", [id], |row| row.get(0))
+        .map_err(|_| "Camera not found".to_string())?;
+    if crate::stream::is_local_device(&camera_type) {
+        return Err("UVC and Pi cameras aren't streamed over RTSP".to_string());
+    }
+
+    conn.execute(
+        "UPDATE cameras SET rtsp_transport = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = ?2",
+        rusqlite::params![transport, id],
+    ).map_err(|e| e.to_string())?;
+
+    println!("[Camera] Camera {} RTSP transport set to {}", id, transport);
+    Ok(())
+}
+
+fn check_stream_timeout(seconds: i32) -> Result<(), String> {
+    if !(crate::stream::MIN_STREAM_START_TIMEOUT_SECS..=crate::stream::MAX_STREAM_START_TIMEOUT_SECS).contains(&seconds) {
+        return Err(format!(
+            "Stream start timeout must be between {} and {} seconds",
+            crate::stream::MIN_STREAM_START_TIMEOUT_SECS,
+            crate::stream::MAX_STREAM_START_TIMEOUT_SECS,
+        ));
+    }
+    Ok(())
+}
+
+/// Certificate the camera's HTTPS endpoint presents, to review and pin with set_camera_tls
+#[tauri::command]
+pub async fn get_camera_certificate(state: State<'_, AppState>, id: i32) -> Result<TlsCertificate, String> {
+    let camera = crate::stream::load_camera(&state.db_path, id)?;
+    let url = match camera.xaddr.as_deref() {
+        Some(xaddr) if xaddr.starts_with("https://") => xaddr.to_string(),
+        _ => format!("https://{}:{}/", camera.host, camera.port),
+    };
+    crate::onvif::fetch_certificate(&url).await
+}
+
+/// TLS trust of a camera: verify its certificate against the system roots, or pin
+/// `cert_pem` so that only that certificate is accepted
+#[tauri::command]
+pub async fn set_camera_tls(state: State<'_, AppState>, id: i32, verify: bool, cert_pem: Option<String>) -> Result<(), String> {
+    let cert_pem = cert_pem.filter(|pem| !pem.trim().is_empty());
+    if let Some(pem) = &cert_pem {
+        crate::onvif::check_certificate(pem)?;
+    }
+
+    let conn = get_conn(&state)?;
+    let updated = conn.execute(
+        "UPDATE cameras SET tls_verify = ?1, tls_cert_pem = ?2, updated_at = CURRENT_TIMESTAMP WHERE id = ?3",
+        rusqlite::params![verify || cert_pem.is_some(), cert_pem, id],
+    ).map_err(|e| e.to_string())?;
+    if updated == 0 {
+        return Err("Camera not found".to_string());
+    }
+
+    println!("[Camera] Camera {} TLS set to verify={}, pinned={}", id, verify, cert_pem.is_some());
+    Ok(())
+}
+
+/// How long a stream of the camera may take to come up before start_stream gives up;
+/// None restores the default
+#[tauri::command]
+pub async fn set_camera_stream_timeout(state: State<'_, AppState>, id: i32, seconds: Option<i32>) -> Result<(), String> {
+    if let Some(seconds) = seconds {
+        check_stream_timeout(seconds)?;
+    }
+
+    let conn = get_conn(&state)?;
+    let updated = conn.execute(
+        "UPDATE cameras SET stream_timeout_secs = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = ?2",
+        rusqlite::params![seconds, id],
+    ).map_err(|e| e.to_string())?;
+    if updated == 0 {
+        return Err("Camera not found".to_string());
+    }
+
+    println!("[Camera] Camera {} stream start timeout set to {:?}", id, seconds);
+    Ok(())
+}
+
+/// Priority of a camera's live stream under encoder.loadBudget: "low" streams are downgraded
+/// first, "high" ones last; None restores "normal". Applies at the next budget check.
+#[tauri::command]
+pub async fn set_camera_priority(state: State<'_, AppState>, id: i32, priority: Option<String>) -> Result<(), String> {
+    if let Some(priority) = priority.as_deref() {
+        if !crate::budget::PRIORITY_LEVELS.contains(&priority) {
+            return Err(format!(
+                "Unknown priority '{}', expected one of {}",
+                priority,
+                crate::budget::PRIORITY_LEVELS.join(", ")
+            ));
+        }
+    }
+
+    let conn = get_conn(&state)?;
+    let updated = conn.execute(
+        "UPDATE cameras SET priority = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = ?2",
+        rusqlite::params![priority, id],
+    ).map_err(|e| e.to_string())?;
+    if updated == 0 {
+        return Err("Camera not found".to_string());
+    }
+
+    println!("[Camera] Camera {} priority set to {}", id, priority.as_deref().unwrap_or("normal"));
+    Ok(())
+}
+
+/// Encoding load of the running streams and recordings against encoder.loadBudget, with
+/// each camera's share and downgrade
+#[tauri::command]
+pub async fn get_load_budget(state: State<'_, AppState>) -> Result<crate::models::LoadBudgetStatus, String> {
+    crate::budget::status(&state)
+}
+
+fn check_input_tuning(tuning: &InputTuning) -> Result<(), String> {
+    for (name, value) in [("Socket timeout", tuning.socket_timeout_ms), ("Read/write timeout", tuning.rw_timeout_ms)] {
+        if value.is_some_and(|ms| !(1000..=600_000).contains(&ms)) {
+            return Err(format!("{} must be between 1000 and 600000 ms", name));
+        }
+    }
+    if tuning.reconnect_delay_max_secs.is_some_and(|secs| !(1..=600).contains(&secs)) {
+        return Err("Maximum reconnect delay must be between 1 and 600 seconds".to_string());
+    }
+    if tuning.probesize.is_some_and(|bytes| !(32..=50_000_000).contains(&bytes)) {
+        return Err("Probe size must be between 32 bytes and 50 MB".to_string());
+    }
+    if tuning.analyzeduration_ms.is_some_and(|ms| !(0..=60_000).contains(&ms)) {
+        return Err("Analyze duration must be between 0 and 60000 ms".to_string());
+    }
+    if let Some(user_agent) = &tuning.user_agent {
+        if user_agent.len() > 256 || user_agent.chars().any(char::is_control) {
+            return Err("User agent must be a single line of at most 256 characters".to_string());
+        }
+    }
+    Ok(())
+}
+
+/// FFmpeg input tuning of a network camera's stream (socket and read timeouts, HTTP reconnects,
+/// stream probing, user agent). Takes effect the next time the stream or a recording starts.
+#[tauri::command]
+pub async fn set_camera_input_tuning(state: State<'_, AppState>, id: i32, tuning: InputTuning) -> Result<(), String> {
+    let tuning = InputTuning {
+        user_agent: tuning.user_agent.as_deref().map(str::trim).filter(|agent| !agent.is_empty()).map(String::from),
+        ..tuning
+    };
+    check_input_tuning(&tuning)?;
+
+    let conn = get_conn(&state)?;
+    let camera_type: String = conn.query_row("SELECT type FROM cameras WHERE id = ?1", [id], |row| row.get(0))
+        .map_err(|_| "Camera not found".to_string())?;
+    if crate::stream::is_local_device(&camera_type) {
+        return Err("UVC and Pi cameras aren't streamed over the network".to_string());
+    }
+
+    conn.execute(
+        "UPDATE cameras SET input_socket_timeout_ms = ?1, input_rw_timeout_ms = ?2, input_reconnect = ?3,
+             input_reconnect_delay_max_secs = ?4, input_probesize = ?5, input_analyzeduration_ms = ?6,
+             input_user_agent = ?7, updated_at = CURRENT_TIMESTAMP
+         WHERE id = ?8",
+        rusqlite::params![
+            tuning.socket_timeout_ms, tuning.rw_timeout_ms, tuning.reconnect, tuning.reconnect_delay_max_secs,
+            tuning.probesize, tuning.analyzeduration_ms, tuning.user_agent, id,
+        ],
+    ).map_err(|e| e.to_string())?;
+
+    println!("[Camera] Camera {} input tuning set to {:?}", id, tuning);
+    Ok(())
+}
+
+/// Advanced: FFmpeg arguments added before the camera's input and before the output of its
+/// live stream and recordings, to work around camera quirks. Takes effect the next time the
+/// stream or a recording starts.
+#[tauri::command]
+pub async fn set_camera_ffmpeg_args(state: State<'_, AppState>, id: i32, input_args: Vec<String>, output_args: Vec<String>) -> Result<(), String> {
+    crate::stream::check_extra_ffmpeg_args(&input_args)?;
+    crate::stream::check_extra_ffmpeg_args(&output_args)?;
+
+    let conn = get_conn(&state)?;
+    let camera_type: String = conn.query_row("SELECT type FROM cameras WHERE id = ?1", [id], |row| row.get(0))
+        .map_err(|_| "Camera not found".to_string())?;
+    if crate::stream::is_local_device(&camera_type) {
+        return Err("Custom FFmpeg arguments are only supported for network cameras".to_string());
+    }
+
+    // Empty lists are stored as NULL, like a camera that never had custom arguments
+    let to_json = |args: &[String]| -> Result<Option<String>, String> {
+        if args.is_empty() {
+            return Ok(None);
+        }
+        serde_json::to_string(args).map(Some).map_err(|e| e.to_string())
+    };
+    conn.execute(
+        "UPDATE cameras SET extra_input_args = ?1, extra_output_args = ?2, updated_at = CURRENT_TIMESTAMP WHERE id = ?3",
+        rusqlite::params![to_json(&input_args)?, to_json(&output_args)?, id],
+    ).map_err(|e| e.to_string())?;
+
+    println!(
+        "[Camera] Camera {} custom FFmpeg arguments set: input [{}], output [{}]",
+        id,
+        crate::redact::redact(&input_args.join(" ")),
+        crate::redact::redact(&output_args.join(" "))
+    );
+    Ok(())
+}
+
+/// Deinterlacing, rotation and flips of a camera's video, applied to its live stream, recordings
+/// and snapshots. Takes effect the next time the stream or a recording starts.
+#[tauri::command]
+pub async fn set_camera_video_filters(state: State<'_, AppState>, id: i32, filters: VideoFilters) -> Result<(), String> {
+    if ![0, 90, 180, 270].contains(&filters.rotation) {
+        return Err("Rotation must be 0, 90, 180 or 270 degrees".to_string());
+    }
+
+    let conn = get_conn(&state)?;
+    let updated = conn.execute(
+        "UPDATE cameras SET video_deinterlace = ?1, video_rotation = ?2, video_hflip = ?3, video_vflip = ?4,
+             updated_at = CURRENT_TIMESTAMP
+         WHERE id = ?5",
+        rusqlite::params![filters.deinterlace, filters.rotation, filters.hflip, filters.vflip, id],
+    ).map_err(|e| e.to_string())?;
+    if updated == 0 {
+        return Err("Camera not found".to_string());
+    }
+
+    println!("[Camera] Camera {} video filters set to {:?}", id, filters);
+    Ok(())
+}
+
+/// Quality presets a camera or the encoder.qualityPreset setting can name
+#[tauri::command]
+pub async fn get_quality_presets() -> Result<Vec<&'static crate::encoder::QualityPreset>, String> {
+    Ok(crate::encoder::QUALITY_PRESETS.iter().collect())
+}
+
+/// Give a camera its own quality preset, or None to follow the global one. "custom" uses the
+/// individual encoder and HLS settings even when a global preset is set. Takes effect the next
+/// time the stream or a recording starts.
+#[tauri::command]
+pub async fn set_camera_quality_preset(state: State<'_, AppState>, id: i32, preset: Option<String>) -> Result<(), String> {
+    if let Some(preset) = preset.as_deref() {
+        if !crate::encoder::QUALITY_PRESET_NAMES.contains(&preset) {
+            return Err(format!(
+                "Unknown quality preset '{}', expected one of {}",
+                preset,
+                crate::encoder::QUALITY_PRESET_NAMES.join(", ")
+            ));
+        }
+    }
+
+    let conn = get_conn(&state)?;
+    let updated = conn.execute(
+        "UPDATE cameras SET quality_preset = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = ?2",
+        rusqlite::params![preset, id],
+    ).map_err(|e| e.to_string())?;
+    if updated == 0 {
+        return Err("Camera not found".to_string());
+    }
+
+    println!("[Camera] Camera {} quality preset set to {}", id, preset.as_deref().unwrap_or("global"));
+    Ok(())
+}
+
+/// How time sync sets the camera's time zone: "utc", "camera" (keep its own) or "host" (this
+/// app's general.timezone); None restores "utc". Only ONVIF cameras have a time zone to set.
+#[tauri::command]
+pub async fn set_camera_time_sync_strategy(state: State<'_, AppState>, id: i32, strategy: Option<String>) -> Result<(), String> {
+    if let Some(strategy) = strategy.as_deref() {
+        if !crate::onvif::TIME_SYNC_STRATEGIES.contains(&strategy) {
+            return Err(format!(
+                "Unknown time sync strategy '{}', expected one of {}",
+                strategy,
+                crate::onvif::TIME_SYNC_STRATEGIES.join(", ")
+            ));
+        }
+    }
+
+    let conn = get_conn(&state)?;
+    let updated = conn.execute(
+        "UPDATE cameras SET time_sync_strategy = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = ?2",
+        rusqlite::params![strategy, id],
+    ).map_err(|e| e.to_string())?;
+    if updated == 0 {
+        return Err("Camera not found".to_string());
+    }
+
+    println!("[Camera] Camera {} time sync strategy set to {}", id, strategy.as_deref().unwrap_or("utc"));
+    Ok(())
+}
+
+/// Log the camera's full ONVIF SOAP requests and responses (credentials masked) to a trace
+/// file, for reporting problems with a specific camera model
+#[tauri::command]
+pub async fn set_onvif_trace(state: State<'_, AppState>, id: i32, enabled: bool) -> Result<(), String> {
+    let conn = get_conn(&state)?;
+    crate::onvif_trace::set_enabled(&conn, &state.db_path, id, enabled)
+}
+
+/// Whether tracing is on and the trace collected so far
+#[tauri::command]
+pub async fn get_onvif_trace(state: State<'_, AppState>, camera_id: i32) -> Result<OnvifTrace, String> {
+    let conn = get_conn(&state)?;
+    Ok(OnvifTrace {
+        enabled: crate::onvif_trace::is_enabled(&conn, camera_id)?,
+        trace: crate::onvif_trace::read(&state.db_path, camera_id)?,
+    })
+}
+
+/// Exercise the camera's main ONVIF operations and report which ones pass, with the raw faults
+/// of those that don't, to tell a camera problem from an app problem
+#[tauri::command]
+pub async fn run_camera_diagnostics(state: State<'_, AppState>, id: i32) -> Result<CameraDiagnostics, String> {
+    let camera = get_onvif_camera(&state, id).await?;
+    crate::onvif_diagnostics::run(&state.onvif_http, &camera).await
+}
+
+/// Settings of a camera plugin and the schema describing them
+#[tauri::command]
+pub async fn get_plugin_config(state: State<'_, AppState>, plugin_type: String) -> Result<PluginConfig, String> {
+    let plugin = state.plugin_manager.get_plugin(&plugin_type)
+        .ok_or_else(|| format!("Unknown plugin '{}'", plugin_type))?;
+    let schema = plugin.config_schema();
+
+    let conn = get_conn(&state)?;
+    let stored = crate::plugin_config::load(&conn, &plugin_type)?;
+    let config = match &schema {
+        Some(schema) => crate::plugin_config::with_defaults(schema, &stored),
+        None => stored,
+    };
+
+    Ok(PluginConfig { pluginType: plugin_type, schema, config })
+}
+
+/// Replace a plugin's settings after the plugin has validated them, and apply them
+#[tauri::command]
+pub async fn set_plugin_config(state: State<'_, AppState>, plugin_type: String, config: serde_json::Value) -> Result<PluginConfig, String> {
+    {
+        let plugin = state.plugin_manager.get_plugin(&plugin_type)
+            .ok_or_else(|| format!("Unknown plugin '{}'", plugin_type))?;
+        let schema = plugin.config_schema().ok_or_else(|| format!("Plugin '{}' has no settings", plugin_type))?;
+        plugin.validate_config(&config)?;
+
+        let conn = get_conn(&state)?;
+        crate::plugin_config::save(&conn, &plugin_type, &config)?;
+        plugin.configure(&crate::plugin_config::with_defaults(&schema, &config))?;
+    }
+
+    println!("[Plugins] Updated settings of plugin '{}'", plugin_type);
+    get_plugin_config(state, plugin_type).await
+}
+
+/// Start a discovery scan in the background and return its scan id
+/// Cameras are emitted as `device-discovered` events while the scan runs,
+/// followed by a single `discovery-finished` event. A device is emitted again when
+/// another plugin finds it or its thumbnail arrives (which can be after the scan finished).
+#[tauri::command]
+pub async fn start_discovery(state: State<'_, AppState>) -> Result<String, String> {
+    let scan_id = uuid::Uuid::new_v4().to_string();
+
+    let events = state.events.clone();
+    let event_scan_id = scan_id.clone();
+    let db_path = state.db_path.clone();
+    let scan = DiscoveryScan::new(move |mut camera| {
+        if let Ok(conn) = crate::db::open(&db_path) {
+            if let Err(e) = mark_registered(&conn, std::slice::from_mut(&mut camera)) {
+                eprintln!("[Discovery] Failed to check registered cameras: {}", e);
+            }
+        }
+        events.emit(AppEvent::DeviceDiscovered { scan_id: event_scan_id.clone(), camera: Box::new(camera) });
+    })
+    .with_thumbnails(state.plugin_manager.clone());
+
+    state.discovery_scans.lock().map_err(|e| e.to_string())?.insert(scan_id.clone(), scan.clone());
+    println!("[Discovery] Scan {} started", scan_id);
+
+    let plugin_manager = state.plugin_manager.clone();
+    let discovery_scans = state.discovery_scans.clone();
+    let events = state.events.clone();
+    let task_scan_id = scan_id.clone();
+
+    tauri::async_runtime::spawn(async move {
+        let cameras = plugin_manager.discover_all_incremental(&scan).await;
+
+        if let Ok(mut scans) = discovery_scans.lock() {
+            scans.remove(&task_scan_id);
+        }
+
+        let cancelled = scan.is_cancelled();
+        println!(
+            "[Discovery] Scan {} {} with {} camera(s)",
+            task_scan_id,
+            if cancelled { "cancelled" } else { "finished" },
+            cameras.len()
+        );
+
+        events.emit(AppEvent::DiscoveryFinished { scan_id: task_scan_id, found: cameras.len(), cancelled });
+    });
+
+    Ok(scan_id)
+}
+
+#[tauri::command]
+pub async fn cancel_discovery(state: State<'_, AppState>, scan_id: String) -> Result<(), String> {
+    let scans = state.discovery_scans.lock().map_err(|e| e.to_string())?;
+    let scan = scans.get(&scan_id).ok_or("Discovery scan not found or already finished")?;
+
+    println!("[Discovery] Cancelling scan {}", scan_id);
+    scan.cancel();
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn start_stream(state: State<'_, AppState>, id: i32) -> Result<serde_json::Value, crate::stream::StreamStartError> {
+    // Get camera details
+    let cameras = get_cameras(state.clone()).await?;
+    let camera = cameras.into_iter().find(|c| c.id == id).ok_or_else(|| "Camera not found".to_string())?;
+    
+    // Start FFmpeg process via stream module
+    match crate::stream::start_stream(state.clone(), camera).await {
+        Ok(stream_file) => {
+            // Every caller gets its own session, revocable without affecting other viewers
+            let session = state.stream_tokens.open_session(id);
+            // With both packagings the DASH manifest sits next to the playlist
+            let packaging = crate::hls::packaging_for(&get_conn(&state)?, id)?;
+            let dash_url = (packaging != "hls")
+                .then(|| state.stream_tokens.session_url(state.server_port, &session, crate::hls::MANIFEST_NAME));
+            Ok(serde_json::json!({
+                "streamUrl": state.stream_tokens.session_url(state.server_port, &session, stream_file),
+                "dashUrl": dash_url,
+            }))
+        },
+        Err(e) => {
+            eprintln!("[Error] Failed to start stream for camera {}: {}", id, e);
+            state.pipelines.error(id, &e.message);
+            Err(e)
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn stop_stream(state: State<'_, AppState>, id: i32) -> Result<serde_json::Value, String> {
+    crate::stream::stop_stream(state, id).await.map_err(|e| e.to_string())?;
+    Ok(serde_json::json!({ "success": true }))
+}
+
+/// End one live stream session (the id in its URL); the stream keeps running for other sessions
+#[tauri::command]
+pub fn revoke_stream_session(state: State<'_, AppState>, session: String) -> Result<(), String> {
+    if !state.stream_tokens.revoke_session(&session) {
+        return Err("Stream session not found".to_string());
+    }
+    println!("[Stream] Revoked stream session {}", session);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn start_recording(
+    state: State<'_, AppState>,
+    id: i32,
+    tags: Option<Vec<String>>,
+    notes: Option<String>,
+    max_duration_minutes: Option<i32>
+) -> Result<serde_json::Value, String> {
+    // Explicit 0 records without a limit; None uses the configured default
+    let max_duration = match max_duration_minutes {
+        Some(minutes) if minutes < 0 => return Err("Max duration must not be negative".to_string()),
+        Some(minutes) => Some(minutes),
+        None => crate::stream::load_default_max_duration(&get_conn(&state)?)?,
+    }.filter(|minutes| *minutes > 0);
+
+    let cameras = get_cameras(state.clone()).await?;
+    let camera = cameras.into_iter().find(|c| c.id == id).ok_or("Camera not found")?;
+
+    // For local cameras (UVC, Pi CSI): stop streaming if active (device can only be accessed by one process)
+    if crate::stream::is_local_device(&camera.camera_type) {
+        let was_streaming = {
+            let processes = state.processes.lock().map_err(|e| e.to_string())?;
+            processes.contains_key(&id)
+        };
+
+        if was_streaming {
+            println!("[Recording] Local camera {} is streaming, stopping stream before recording", id);
+
+            // Stop current stream
+            if let Err(e) = crate::stream::stop_stream(state.clone(), id).await {
+                println!("[Recording] Warning: Failed to stop stream: {}", e);
+            }
+
+            // Wait for cleanup
+            tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+
+            println!("[Recording] Stream stopped, starting recording for camera {}", id);
+        }
+    }
+
+    let meta = RecordingMeta {
+        trigger: RecordingTrigger::Manual,
+        tags: normalize_tags(tags.unwrap_or_default()),
+        notes: notes.filter(|n| !n.trim().is_empty()),
+        session_id: None,
+    };
+
+    let recording_id = crate::stream::start_recording_with_options(state.clone(), camera.id, None, meta).await?;
+    if let Some(minutes) = max_duration {
+        crate::stream::schedule_auto_stop(&state, camera.id, recording_id, minutes);
+    }
+    Ok(serde_json::json!({ "success": true }))
+}
+
+#[tauri::command]
+pub async fn stop_recording(
+    state: State<'_, AppState>,
+    id: i32
+) -> Result<serde_json::Value, String> {
+    crate::stream::stop_recording(state, id).await.map_err(|e| e.to_string())?;
+    Ok(serde_json::json!({ "success": true }))
+}
+
+#[tauri::command]
+pub async fn get_audio_monitor(state: State<'_, AppState>, camera_id: i32) -> Result<AudioMonitorSettings, String> {
+    let conn = get_conn(&state)?;
+    let mut settings = crate::audio_monitor::load_settings(&conn, camera_id)?;
+    settings.running = state.audio_monitors.is_running(camera_id);
+    Ok(settings)
+}
+
+/// Save a camera's audio alert settings and start, restart or stop its monitor to match
+#[tauri::command]
+pub async fn set_audio_monitor(
+    state: State<'_, AppState>,
+    camera_id: i32,
+    settings: UpdateAudioMonitorSettings,
+) -> Result<AudioMonitorSettings, String> {
+    {
+        let conn = get_conn(&state)?;
+        let camera_type: String = conn.query_row("SELECT type FROM cameras WHERE id = ?1", [camera_id], |row| row.get(0))
+            .map_err(|_| "Camera not found".to_string())?;
+
+        let mut current = crate::audio_monitor::load_settings(&conn, camera_id)?;
+        if let Some(enabled) = settings.enabled {
+            if enabled && crate::stream::is_local_device(&camera_type) {
+                return Err("Audio monitoring needs a network camera stream".to_string());
+            }
+            current.enabled = enabled;
+        }
+        if let Some(threshold) = settings.thresholdDb {
+            if !(-90.0..=0.0).contains(&threshold) {
+                return Err("Threshold must be between -90 and 0 dB".to_string());
+            }
+            current.thresholdDb = threshold;
+        }
+        if let Some(cooldown) = settings.cooldownSecs {
+            if cooldown < 0 {
+                return Err("Cooldown must not be negative".to_string());
+            }
+            current.cooldownSecs = cooldown;
+        }
+        if let Some(record) = settings.recordOnAlert {
+            current.recordOnAlert = record;
+        }
+        if let Some(minutes) = settings.recordMinutes {
+            if minutes < 1 {
+                return Err("Alert recordings must be at least 1 minute long".to_string());
+            }
+            current.recordMinutes = minutes;
+        }
+
+        conn.execute(
+            "INSERT OR REPLACE INTO audio_monitors
+                (camera_id, enabled, threshold_db, cooldown_secs, record_on_alert, record_minutes)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            rusqlite::params![
+                camera_id,
+                current.enabled,
+                current.thresholdDb,
+                current.cooldownSecs,
+                current.recordOnAlert,
+                current.recordMinutes,
+            ],
+        ).map_err(|e| e.to_string())?;
+
+        if current.enabled {
+            crate::audio_monitor::start(&state.app_handle, camera_id);
+        } else {
+            crate::audio_monitor::stop(&state, camera_id);
+        }
+    }
+
+    get_audio_monitor(state, camera_id).await
+}
+
+#[tauri::command]
+pub async fn get_motion_detector(state: State<'_, AppState>, camera_id: i32) -> Result<MotionDetectorSettings, String> {
+    let conn = get_conn(&state)?;
+    let mut settings = crate::motion::load_settings(&conn, camera_id)?;
+    settings.running = state.motion_detectors.is_running(camera_id);
+    Ok(settings)
+}
+
+/// Save a camera's motion detection settings and start, restart or stop detection to match
+#[tauri::command]
+pub async fn set_motion_detector(
+    state: State<'_, AppState>,
+    camera_id: i32,
+    settings: UpdateMotionDetectorSettings,
+) -> Result<MotionDetectorSettings, String> {
+    {
+        let conn = get_conn(&state)?;
+        let camera_type: String = conn.query_row("SELECT type FROM cameras WHERE id = ?1", [camera_id], |row| row.get(0))
+            .map_err(|_| "Camera not found".to_string())?;
+
+        let mut current = crate::motion::load_settings(&conn, camera_id)?;
+        if let Some(enabled) = settings.enabled {
+            current.enabled = enabled;
+        }
+        if let Some(sensitivity) = settings.sensitivity {
+            if !(1..=100).contains(&sensitivity) {
+                return Err("Sensitivity must be between 1 and 100".to_string());
+            }
+            current.sensitivity = sensitivity;
+        }
+        if let Some(roi) = settings.roi {
+            crate::motion::validate_roi(&roi)?;
+            let whole_frame = roi.x == 0.0 && roi.y == 0.0 && roi.width == 1.0 && roi.height == 1.0;
+            current.roi = (!whole_frame).then_some(roi);
+        }
+        if let Some(cooldown) = settings.cooldownSecs {
+            if cooldown < 0 {
+                return Err("Cooldown must not be negative".to_string());
+            }
+            current.cooldownSecs = cooldown;
+        }
+        if let Some(record) = settings.recordOnMotion {
+            // The local device is held by the live stream the detector reads from
+            if record && crate::stream::is_local_device(&camera_type) {
+                return Err("UVC and Pi cameras can't record on motion while their stream is analysed".to_string());
+            }
+            current.recordOnMotion = record;
+        }
+        if let Some(minutes) = settings.recordMinutes {
+            if minutes < 1 {
+                return Err("Motion recordings must be at least 1 minute long".to_string());
+            }
+            current.recordMinutes = minutes;
+        }
+
+        let roi = current.roi.as_ref();
+        conn.execute(
+            "INSERT OR REPLACE INTO motion_detectors
+                (camera_id, enabled, sensitivity, roi_x, roi_y, roi_width, roi_height,
+                 cooldown_secs, record_on_motion, record_minutes)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            rusqlite::params![
+                camera_id,
+                current.enabled,
+                current.sensitivity,
+                roi.map(|r| r.x),
+                roi.map(|r| r.y),
+                roi.map(|r| r.width),
+                roi.map(|r| r.height),
+                current.cooldownSecs,
+                current.recordOnMotion,
+                current.recordMinutes,
+            ],
+        ).map_err(|e| e.to_string())?;
+
+        if current.enabled {
+            crate::motion::start(&state.app_handle, camera_id);
+        } else {
+            crate::motion::stop(&state, camera_id);
+        }
+    }
+
+    get_motion_detector(state, camera_id).await
+}
+
+#[tauri::command]
+pub async fn get_analytics_settings(state: State<'_, AppState>) -> Result<AnalyticsSettings, String> {
+    let conn = get_conn(&state)?;
+    crate::analytics::load_analytics_settings(&conn)
+}
+
+/// Change the detection backend; running detectors restart with it
+#[tauri::command]
+pub async fn update_analytics_settings(
+    state: State<'_, AppState>,
+    settings: UpdateAnalyticsSettings,
+) -> Result<AnalyticsSettings, String> {
+    {
+        let conn = get_conn(&state)?;
+        if let Some(backend) = settings.backend {
+            if !crate::analytics::available_backends().contains(&backend) {
+                return Err(format!("Detection backend '{}' is not available in this build", backend));
+            }
+            conn.execute("UPDATE analytics_settings SET backend = ?1 WHERE id = 1", [backend])
+                .map_err(|e| e.to_string())?;
+        }
+        if let Some(model_path) = settings.modelPath {
+            let model_path = Some(model_path.trim().to_string()).filter(|p| !p.is_empty());
+            conn.execute("UPDATE analytics_settings SET model_path = ?1 WHERE id = 1", [model_path])
+                .map_err(|e| e.to_string())?;
+        }
+        if let Some(endpoint_url) = settings.endpointUrl {
+            let endpoint_url = Some(endpoint_url.trim().to_string()).filter(|u| !u.is_empty());
+            conn.execute("UPDATE analytics_settings SET endpoint_url = ?1 WHERE id = 1", [endpoint_url])
+                .map_err(|e| e.to_string())?;
+        }
+        if let Some(fps) = settings.sampleFps {
+            if !(0.1..=2.0).contains(&fps) {
+                return Err("Sample rate must be between 0.1 and 2 fps".to_string());
+            }
+            conn.execute("UPDATE analytics_settings SET sample_fps = ?1 WHERE id = 1", [fps])
+                .map_err(|e| e.to_string())?;
+        }
+    }
+
+    crate::analytics::start_enabled(&state.app_handle)?;
+    get_analytics_settings(state).await
+}
+
+#[tauri::command]
+pub async fn get_object_detector(state: State<'_, AppState>, camera_id: i32) -> Result<ObjectDetectorSettings, String> {
+    let conn = get_conn(&state)?;
+    let mut settings = crate::analytics::load_settings(&conn, camera_id)?;
+    settings.running = state.object_detectors.is_running(camera_id);
+    Ok(settings)
+}
+
+/// Save a camera's object detection settings and start, restart or stop detection to match
+#[tauri::command]
+pub async fn set_object_detector(
+    state: State<'_, AppState>,
+    camera_id: i32,
+    settings: UpdateObjectDetectorSettings,
+) -> Result<ObjectDetectorSettings, String> {
+    {
+        let conn = get_conn(&state)?;
+        conn.query_row("SELECT id FROM cameras WHERE id = ?1", [camera_id], |row| row.get::<_, i32>(0))
+            .map_err(|_| "Camera not found".to_string())?;
+
+        let mut current = crate::analytics::load_settings(&conn, camera_id)?;
+        if let Some(enabled) = settings.enabled {
+            current.enabled = enabled;
+        }
+        if let Some(confidence) = settings.minConfidence {
+            if !(0.0..=1.0).contains(&confidence) {
+                return Err("Confidence threshold must be between 0 and 1".to_string());
+            }
+            current.minConfidence = confidence;
+        }
+        if let Some(classes) = settings.classes {
+            if let Some(unknown) = classes.iter().find(|c| !["person", "vehicle", "animal"].contains(&c.as_str())) {
+                return Err(format!("Unknown object class '{}' (expected person, vehicle or animal)", unknown));
+            }
+            current.classes = classes;
+        }
+        if let Some(cooldown) = settings.cooldownSecs {
+            if cooldown < 0 {
+                return Err("Cooldown must not be negative".to_string());
+            }
+            current.cooldownSecs = cooldown;
+        }
+
+        conn.execute(
+            "INSERT OR REPLACE INTO object_detectors (camera_id, enabled, min_confidence, classes, cooldown_secs)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            rusqlite::params![
+                camera_id,
+                current.enabled,
+                current.minConfidence,
+                serde_json::to_string(&current.classes).map_err(|e| e.to_string())?,
+                current.cooldownSecs,
+            ],
+        ).map_err(|e| e.to_string())?;
+
+        if current.enabled {
+            crate::analytics::start(&state.app_handle, camera_id);
+        } else {
+            crate::analytics::stop(&state, camera_id);
+        }
+    }
+
+    get_object_detector(state, camera_id).await
+}
+
+/// Finalize the current segment and leave a gap until resume_recording; returns the session id
+#[tauri::command]
+pub async fn pause_recording(state: State<'_, AppState>, camera_id: i32) -> Result<i64, String> {
+    crate::stream::pause_recording(&state, camera_id).await
+}
+
+/// Continue a paused recording in a new segment; returns the new recording id
+#[tauri::command]
+pub async fn resume_recording(state: State<'_, AppState>, camera_id: i32) -> Result<i64, String> {
+    crate::stream::resume_recording(&state, camera_id).await
+}
+
+#[tauri::command]
+pub async fn get_recording_gaps(state: State<'_, AppState>, session_id: i64) -> Result<Vec<RecordingGap>, String> {
+    let conn = get_conn(&state)?;
+    crate::stream::list_recording_gaps(&conn, session_id)
+}
+
+// Trim tags and drop empties/duplicates, keeping the user's order
+fn normalize_tags(tags: Vec<String>) -> Vec<String> {
+    let mut normalized: Vec<String> = Vec::new();
+    for tag in tags {
+        let tag = tag.trim().to_string();
+        if !tag.is_empty() && !normalized.contains(&tag) {
+            normalized.push(tag);
+        }
+    }
+    normalized
+}
+
+#[tauri::command]
+pub async fn get_recordings(state: State<'_, AppState>, filter: Option<RecordingFilter>) -> Result<Vec<Recording>, String> {
+    let conn = get_conn(&state)?;
+    RecordingRepo::query(&conn, filter.unwrap_or_default())
+}
+
+/// Recordings grouped by session, newest first: one entry per recording the user started,
+/// however often it was paused. The filter selects segments; a session lists the ones matching.
+#[tauri::command]
+pub async fn get_recording_sessions(state: State<'_, AppState>, filter: Option<RecordingFilter>) -> Result<Vec<RecordingSession>, String> {
+    let conn = get_conn(&state)?;
+    let recordings = RecordingRepo::query(&conn, filter.unwrap_or_default())?;
+
+    let mut sessions: Vec<RecordingSession> = Vec::new();
+    for recording in recordings {
+        let session_id = recording.session_id.unwrap_or(recording.id as i64);
+        if let Some(session) = sessions.iter_mut().find(|s| s.id == session_id) {
+            session.segments.insert(0, recording);
+            continue;
+        }
+
+        let (trigger, started_at, ended_at): (String, String, Option<String>) = conn.query_row(
+            "SELECT trigger, started_at, ended_at FROM recording_sessions WHERE id = ?1",
+            [session_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        ).optional().map_err(|e| e.to_string())?
+            .unwrap_or_else(|| (recording.trigger.clone(), recording.start_time.to_rfc3339(), None));
+        let parse = |t: &str| DateTime::parse_from_rfc3339(t).map(|t| t.with_timezone(&Utc));
+
+        sessions.push(RecordingSession {
+            id: session_id,
+            camera_id: recording.camera_id,
+            camera_name: recording.camera_name.clone(),
+            trigger,
+            started_at: parse(&started_at).unwrap_or(recording.start_time),
+            ended_at: ended_at.and_then(|t| parse(&t).ok()),
+            segment_count: 0,
+            total_duration_secs: 0.0,
+            total_size: 0,
+            is_protected: false,
+            segments: vec![recording],
+        });
+    }
+
+    for session in &mut sessions {
+        session.segment_count = session.segments.len();
+        session.is_protected = session.segments.iter().any(|s| s.is_protected);
+        session.total_size = session.segments.iter().filter_map(|s| s.file_size).sum();
+        session.total_duration_secs = session.segments.iter()
+            .filter_map(|s| s.end_time.map(|end| (end - s.start_time).num_milliseconds() as f64 / 1000.0))
+            .sum();
+    }
+    Ok(sessions)
+}
+
+#[tauri::command]
+pub async fn update_recording_meta(state: State<'_, AppState>, id: i32, updates: UpdateRecordingMeta) -> Result<(), String> {
+    let conn = get_conn(&state)?;
+
+    if let Some(tags) = updates.tags {
+        let tags = serde_json::to_string(&normalize_tags(tags)).map_err(|e| e.to_string())?;
+        conn.execute("UPDATE recordings SET tags = ?1 WHERE id = ?2", rusqlite::params![tags, id])
+            .map_err(|e| e.to_string())?;
+    }
+    if let Some(notes) = updates.notes {
+        let notes = Some(notes).filter(|n| !n.trim().is_empty());
+        conn.execute("UPDATE recordings SET notes = ?1 WHERE id = ?2", rusqlite::params![notes, id])
+            .map_err(|e| e.to_string())?;
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn set_recording_protected(state: State<'_, AppState>, id: i32, protected: bool) -> Result<(), String> {
+    let conn = get_conn(&state)?;
+    let affected = conn.execute(
+        "UPDATE recordings SET is_protected = ?1 WHERE id = ?2",
+        rusqlite::params![protected, id],
+    ).map_err(|e| e.to_string())?;
+
+    if affected == 0 {
+        return Err("Recording not found".to_string());
+    }
+
+    println!("[Recording] Recording {} {}", id, if protected { "protected" } else { "unprotected" });
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn delete_recording(state: State<'_, AppState>, id: i32, force: Option<bool>) -> Result<(), String> {
+    let conn = get_conn(&state)?;
+    delete_recording_with_conn(&conn, &state.recording_dir, &state.archive_queue, id, force.unwrap_or(false))
+}
+
+// Remove a recording's file and row (shared by single, batch, session and camera delete). With
+// auto-archive on, a recording without an archived copy is archived first and kept if that fails.
+fn delete_recording_with_conn(
+    conn: &Connection,
+    recording_dir: &std::path::Path,
+    archive: &crate::archive::ArchiveQueue,
+    id: i32,
+    force: bool,
+) -> Result<(), String> {
+    let (is_protected, camera_id): (bool, i32) = conn.query_row(
+        "SELECT is_protected, camera_id FROM recordings WHERE id = ?1",
+        [id],
+        |row| Ok((row.get(0)?, row.get(1)?))
+    ).map_err(|e| e.to_string())?;
+
+    if is_protected && !force {
+        return Err("Recording is protected; unprotect it or delete with force".to_string());
+    }
+
+    // Callers hold a connection, which can't be kept across an await
+    let archived = tokio::task::block_in_place(|| {
+        tokio::runtime::Handle::current().block_on(archive.archive_before_delete(id))
+    }).map_err(|e| format!("Archiving before deletion failed: {}", e))?;
+    if archived {
+        println!("[Archive] Recording {} archived before deletion", id);
+    }
+
+    // Delete file from filesystem
+    let file_path = crate::storage::recording_path(conn, recording_dir, id)?;
+    if file_path.exists() {
+        std::fs::remove_file(file_path).map_err(|e| e.to_string())?;
+    }
+
+    conn.execute("DELETE FROM recordings WHERE id = ?1", [id]).map_err(|e| e.to_string())?;
+    crate::stream::prune_recording_sessions(conn, camera_id)?;
+    Ok(())
+}
+
+// Segments of a recording session as (id, filename, is_finished, is_protected), oldest first
+fn session_segments(conn: &Connection, session_id: i64) -> Result<Vec<(i32, String, bool, bool)>, String> {
+    let mut stmt = conn.prepare(
+        "SELECT id, filename, is_finished, is_protected FROM recordings WHERE session_id = ?1 ORDER BY start_time"
+    ).map_err(|e| e.to_string())?;
+    let segments = stmt.query_map([session_id], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    if segments.is_empty() {
+        return Err(format!("Recording session {} not found", session_id));
+    }
+    Ok(segments)
+}
+
+/// Delete every segment of a recording session; nothing is deleted if one is protected
+/// (unless forced) or still being recorded
+#[tauri::command]
+pub async fn delete_recording_session(state: State<'_, AppState>, session_id: i64, force: Option<bool>) -> Result<(), String> {
+    let conn = get_conn(&state)?;
+    let segments = session_segments(&conn, session_id)?;
+
+    if segments.iter().any(|(_, _, is_finished, _)| !is_finished) {
+        return Err("Recording session is still being recorded; stop it first".to_string());
+    }
+    if !force.unwrap_or(false) && segments.iter().any(|(_, _, _, is_protected)| *is_protected) {
+        return Err("Recording session has protected segments; unprotect them or delete with force".to_string());
+    }
+
+    for (id, _, _, _) in &segments {
+        delete_recording_with_conn(&conn, &state.recording_dir, &state.archive_queue, *id, true)?;
+    }
+    println!("[Recording] Deleted recording session {} ({} segment(s))", session_id, segments.len());
+    Ok(())
+}
+
+/// Export a recording session as one file, its segments joined in order (pauses are left
+/// out), optionally with a watermark burned in. Returns the path of the exported file.
+#[tauri::command]
+pub async fn export_recording_session(
+    state: State<'_, AppState>,
+    session_id: i64,
+    dest_dir: String,
+    faststart: Option<bool>,
+    watermark: Option<Watermark>,
+) -> Result<String, String> {
+    if let Some(watermark) = &watermark {
+        crate::watermark::check(watermark)?;
+    }
+    let conn = get_conn(&state)?;
+    let segments = session_segments(&conn, session_id)?;
+    if segments.iter().any(|(_, _, is_finished, _)| !is_finished) {
+        return Err("Recording session is still being recorded; stop it first".to_string());
+    }
+
+    let dest_dir = std::path::PathBuf::from(dest_dir);
+    std::fs::create_dir_all(&dest_dir).map_err(|e| format!("Failed to create export directory: {}", e))?;
+
+    let mut sources = Vec::new();
+    for (id, filename, _, _) in &segments {
+        let source = crate::storage::recording_path(&conn, &state.recording_dir, *id)?;
+        if !source.exists() {
+            return Err(format!("Recording file {} is missing", filename));
+        }
+        sources.push(source);
+    }
+
+    // Named after the first segment, i.e. the start of the session
+    let first = std::path::Path::new(&segments[0].1);
+    let target = match faststart.unwrap_or(false) {
+        true => dest_dir.join(first.with_extension("mp4")),
+        false => dest_dir.join(first),
+    };
+    if target.exists() {
+        return Err(format!("{} already exists in the export directory", target.file_name().unwrap_or_default().to_string_lossy()));
+    }
+
+    let concat_target = target.clone();
+    tauri::async_runtime::spawn_blocking(move || match watermark {
+        // Joined next to the export first, as the watermark input
+        Some(watermark) => {
+            let joined = concat_target.with_file_name(format!(
+                ".{}.joined.{}",
+                uuid::Uuid::new_v4(),
+                concat_target.extension().unwrap_or_default().to_string_lossy()
+            ));
+            let result = crate::stream::concat_recordings(&sources, &joined)
+                .and_then(|()| crate::stream::export_watermarked(&joined, &concat_target, &watermark));
+            let _ = std::fs::remove_file(&joined);
+            result
+        }
+        None => crate::stream::concat_recordings(&sources, &concat_target),
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+
+    println!("[Recording] Exported recording session {} to {:?}", session_id, target);
+    Ok(target.to_string_lossy().to_string())
+}
+
+/// Export two recordings side by side as one MP4, both aligned to the same wall-clock range so
+/// two views of an incident can be compared. Returns the path of the exported file.
+#[tauri::command]
+pub async fn export_comparison(
+    state: State<'_, AppState>,
+    recording_a: i32,
+    recording_b: i32,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    dest_dir: String,
+    watermark: Option<Watermark>,
+) -> Result<String, String> {
+    if let Some(watermark) = &watermark {
+        crate::watermark::check(watermark)?;
+    }
+    if to <= from {
+        return Err("The end of the range must be after its start".to_string());
+    }
+    if recording_a == recording_b {
+        return Err("Choose two different recordings".to_string());
+    }
+
+    let conn = get_conn(&state)?;
+    // File of a recording with its seek and padding for the range
+    let input = |id: i32| -> Result<(std::path::PathBuf, f64, f64), String> {
+        let (filename, start, end, is_finished): (String, String, Option<String>, bool) = conn.query_row(
+            "SELECT filename, start_time, end_time, is_finished FROM recordings WHERE id = ?1",
+            [id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+        ).map_err(|e| format!("Recording {} not found: {}", id, e))?;
+        let parse = |t: &str| DateTime::parse_from_rfc3339(t).map(|t| t.with_timezone(&Utc)).map_err(|e| e.to_string());
+        let start = parse(&start)?;
+        let end = end.as_deref().map(parse).transpose()?;
+
+        if !is_finished {
+            return Err(format!("Recording {} is still being recorded; stop it first", filename));
+        }
+        if end.is_some_and(|end| end <= from) || start >= to {
+            return Err(format!("Recording {} doesn't cover the selected range", filename));
+        }
+
+        let source = crate::storage::recording_path(&conn, &state.recording_dir, id)?;
+        if !source.exists() {
+            return Err(format!("Recording file {} is missing", filename));
+        }
+        let (seek, pad) = crate::stream::comparison_timing(start, from);
+        Ok((source, seek, pad))
+    };
+    let inputs = [input(recording_a)?, input(recording_b)?];
+
+    let dest_dir = std::path::PathBuf::from(dest_dir);
+    std::fs::create_dir_all(&dest_dir).map_err(|e| format!("Failed to create export directory: {}", e))?;
+
+    let target = dest_dir.join(format!(
+        "comparison_{}_{}_{}.mp4",
+        recording_a,
+        recording_b,
+        from.with_timezone(&crate::settings::timezone()).format("%Y-%m-%d_%H-%M-%S")
+    ));
+    if target.exists() {
+        return Err(format!("{} already exists in the export directory", target.file_name().unwrap_or_default().to_string_lossy()));
+    }
+
+    let duration = (to - from).num_milliseconds() as f64 / 1000.0;
+    let export_target = target.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        crate::stream::export_side_by_side(&inputs, duration, watermark.as_ref(), &export_target)
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+
+    println!("[Recording] Exported comparison of recordings {} and {} to {:?}", recording_a, recording_b, target);
+    Ok(target.to_string_lossy().to_string())
+}
+
+// Emit `recordings-batch-progress` after each item of a batch operation
+fn emit_batch_progress(events: &EventBus, operation: &str, id: i32, completed: usize, total: usize, error: Option<&str>) {
+    events.emit(AppEvent::RecordingsBatchProgress {
+        operation: operation.to_string(),
+        id,
+        completed,
+        total,
+        error: error.map(|e| e.to_string()),
+    });
+}
+
+// Run `op` for every id on a blocking thread with a single DB connection,
+// collecting per-item failures instead of stopping at the first one
+async fn run_recordings_batch<F>(
+    state: &State<'_, AppState>,
+    operation: &'static str,
+    ids: Vec<i32>,
+    op: F,
+) -> Result<BatchResult, String>
+where
+    F: Fn(&Connection, i32) -> Result<(), String> + Send + 'static,
+{
+    let db_path = state.db_path.clone();
+    let events = state.events.clone();
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let conn = crate::db::open(&db_path).map_err(|e| e.to_string())?;
+        let total = ids.len();
+        let mut result = BatchResult { succeeded: Vec::new(), failed: Vec::new() };
+
+        for (index, id) in ids.into_iter().enumerate() {
+            match op(&conn, id) {
+                Ok(()) => {
+                    result.succeeded.push(id);
+                    emit_batch_progress(&events, operation, id, index + 1, total, None);
+                }
+                Err(error) => {
+                    eprintln!("[Recording] Batch {} failed for recording {}: {}", operation, id, error);
+                    emit_batch_progress(&events, operation, id, index + 1, total, Some(&error));
+                    result.failed.push(BatchFailure { id, error });
+                }
+            }
+        }
+
+        println!(
+            "[Recording] Batch {}: {} succeeded, {} failed",
+            operation, result.succeeded.len(), result.failed.len()
+        );
+        Ok(result)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+pub async fn delete_recordings(state: State<'_, AppState>, ids: Vec<i32>, force: Option<bool>) -> Result<BatchResult, String> {
+    let recording_dir = state.recording_dir.clone();
+    let archive = state.archive_queue.clone();
+    let force = force.unwrap_or(false);
+
+    run_recordings_batch(&state, "delete", ids, move |conn, id| {
+        delete_recording_with_conn(conn, &recording_dir, &archive, id, force)
+    }).await
+}
+
+/// Copy recordings to `dest_dir`. With a watermark they are re-encoded with it burned in;
+/// the recordings themselves are never changed.
+#[tauri::command]
+pub async fn export_recordings(
+    state: State<'_, AppState>,
+    ids: Vec<i32>,
+    dest_dir: String,
+    faststart: Option<bool>,
+    watermark: Option<Watermark>,
+) -> Result<BatchResult, String> {
+    if let Some(watermark) = &watermark {
+        crate::watermark::check(watermark)?;
+    }
+    let dest_dir = std::path::PathBuf::from(dest_dir);
+    let faststart = faststart.unwrap_or(false);
+    std::fs::create_dir_all(&dest_dir).map_err(|e| format!("Failed to create export directory: {}", e))?;
+
+    let recording_dir = state.recording_dir.clone();
+
+    run_recordings_batch(&state, "export", ids, move |conn, id| {
+        let filename: String = conn.query_row(
+            "SELECT filename FROM recordings WHERE id = ?1",
+            [id],
+            |row| row.get(0)
+        ).map_err(|e| e.to_string())?;
+
+        let source = crate::storage::recording_path(conn, &recording_dir, id)?;
+        if !source.exists() {
+            return Err(format!("Recording file {} is missing", filename));
+        }
+
+        // Fragmented MP4 and MKV recordings become a regular MP4 with the index up front
+        let target = match faststart {
+            true => dest_dir.join(std::path::Path::new(&filename).with_extension("mp4")),
+            false => dest_dir.join(&filename),
+        };
+        if target.exists() {
+            return Err(format!("{} already exists in the export directory", target.file_name().unwrap_or_default().to_string_lossy()));
+        }
+
+        if let Some(watermark) = &watermark {
+            return crate::stream::export_watermarked(&source, &target, watermark);
+        }
+        if faststart {
+            return crate::stream::remux_faststart(&source, &target);
+        }
+        std::fs::copy(&source, &target).map_err(|e| format!("Failed to copy {}: {}", filename, e))?;
+        Ok(())
+    }).await
+}
+
+#[tauri::command]
+pub async fn start_playback_session(
+    state: State<'_, AppState>,
+    recording_id: i32,
+    start_secs: Option<f64>
+) -> Result<PlaybackSession, String> {
+    crate::playback::start_playback_session(state, recording_id, start_secs).await
+}
+
+#[tauri::command]
+pub async fn start_recording_session_playback(
+    state: State<'_, AppState>,
+    session_id: i64,
+    start_secs: Option<f64>
+) -> Result<PlaybackSession, String> {
+    crate::playback::start_recording_session_playback(state, session_id, start_secs).await
+}
+
+#[tauri::command]
+pub async fn stop_playback_session(state: State<'_, AppState>, session_id: String) -> Result<(), String> {
+    crate::playback::stop_playback_session(&state, &session_id)
+}
+
+#[tauri::command]
+pub async fn get_recording_timeline(
+    state: State<'_, AppState>,
+    camera_id: i32,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>
+) -> Result<RecordingTimeline, String> {
+    let conn = get_conn(&state)?;
+    crate::timeline::build_timeline(&conn, &state.recording_dir, camera_id, from, to)
+}
+
+/// Activity report (recordings, failures, offline cameras, storage) for a time range;
+/// the reports.digest setting sends the same report daily or weekly
+#[tauri::command]
+pub async fn generate_report(state: State<'_, AppState>, range: ReportRange) -> Result<ActivityReport, String> {
+    let conn = get_conn(&state)?;
+    crate::report::generate_report(&conn, &state.recording_dir, range.from, range.to)
+}
+
+/// Events for the events browser, newest first; each carries the recording and offset to play
+#[tauri::command]
+pub async fn get_events(
+    state: State<'_, AppState>,
+    camera_id: Option<i32>,
+    event_type: Option<String>,
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+    limit: Option<u32>,
+) -> Result<Vec<CameraEvent>, String> {
+    let conn = get_conn(&state)?;
+    let event_type = event_type.filter(|t| !t.is_empty());
+    crate::timeline::list_events(&conn, camera_id, event_type.as_deref(), from, to, limit.unwrap_or(200).min(1000))
+}
+
+// Time synchronization commands
+#[tauri::command]
+pub async fn get_camera_time(state: State<'_, AppState>, id: i32) -> Result<CameraTimeInfo, AppMessage> {
+    let cameras = get_cameras(state.clone()).await?;
+    let camera = cameras.into_iter().find(|c| c.id == id).ok_or_else(|| AppMessage::new("camera.not_found", json!({ "id": id })))?;
+
+    let camera_datetime = read_camera_time(&state, &camera).await?;
+    let server_time = Utc::now();
+
+    Ok(CameraTimeInfo {
+        cameraTime: serde_json::json!({
+            "year": camera_datetime.year,
+            "month": camera_datetime.month,
+            "day": camera_datetime.day,
+            "hour": camera_datetime.hour,
+            "minute": camera_datetime.minute,
+            "second": camera_datetime.second,
+        }),
+        serverTime: server_time.to_rfc3339(),
+        timeZone: camera_datetime.time_zone.as_ref().map(|zone| zone.tz.clone()),
+        daylightSavings: camera_datetime.time_zone.is_some_and(|zone| zone.daylight_savings),
+    })
+}
+
+// Cameras queried at once by get_all_camera_times
+const CLOCK_QUERY_CONCURRENCY: usize = 8;
+// Drift a camera clock may have before it counts as off; matches the sync verification
+const CLOCK_DRIFT_TOLERANCE_SECS: i64 = 5;
+
+#[tauri::command]
+pub async fn get_all_camera_times(state: State<'_, AppState>) -> Result<CameraClockReport, String> {
+    use futures::stream::{self, StreamExt};
+
+    // Cameras without time support (e.g. plain RTSP) have no clock to report
+    let cameras: Vec<Camera> = get_cameras(state.clone()).await?
+        .into_iter()
+        .filter(|camera| time_plugin(&state, camera).is_ok())
+        .collect();
+
+    let state_ref = &state;
+    let mut statuses: Vec<CameraClockStatus> = stream::iter(cameras)
+        .map(|camera| async move {
+            let result = read_camera_time(state_ref, &camera).await
+                .and_then(|time| time.to_chrono().ok_or_else(|| "Invalid camera time format".to_string()));
+            match result {
+                Ok(camera_time) => CameraClockStatus {
+                    cameraId: camera.id,
+                    cameraName: camera.name,
+                    reachable: true,
+                    cameraTime: Some(camera_time.to_rfc3339()),
+                    driftSecs: Some(camera_time.signed_duration_since(Utc::now()).num_seconds()),
+                    error: None,
+                },
+                Err(e) => CameraClockStatus {
+                    cameraId: camera.id,
+                    cameraName: camera.name,
+                    reachable: false,
+                    cameraTime: None,
+                    driftSecs: None,
+                    error: Some(e),
+                },
+            }
+        })
+        .buffer_unordered(CLOCK_QUERY_CONCURRENCY)
+        .collect()
+        .await;
+    statuses.sort_by_key(|status| status.cameraId);
+
+    let drifts: Vec<i64> = statuses.iter().filter_map(|status| status.driftSecs).collect();
+    let reachable_count = drifts.len();
+    println!("[TimeSync] Queried {} camera clocks, {} reachable", statuses.len(), reachable_count);
+
+    Ok(CameraClockReport {
+        serverTime: Utc::now().to_rfc3339(),
+        reachableCount: reachable_count,
+        unreachableCount: statuses.len() - reachable_count,
+        driftedCount: drifts.iter().filter(|drift| drift.abs() >= CLOCK_DRIFT_TOLERANCE_SECS).count(),
+        maxDriftSecs: drifts.iter().copied().max_by_key(|drift| drift.abs()),
+        cameras: statuses,
+    })
+}
+
+#[tauri::command]
+pub async fn sync_camera_time(state: State<'_, AppState>, id: i32) -> Result<TimeSyncResult, AppMessage> {
+    let cameras = get_cameras(state.clone()).await?;
+    let camera = cameras.into_iter().find(|c| c.id == id).ok_or_else(|| AppMessage::new("camera.not_found", json!({ "id": id })))?;
+
+    // Fail early for camera types without time synchronization
+    time_plugin(&state, &camera)?;
+
+    // Check if streaming is currently active
+    let was_streaming = {
+        let processes = state.processes.lock().map_err(|e| e.to_string())?;
+        processes.contains_key(&id)
+    };
+
+    // Get current camera time before sync
+    let before_datetime = read_camera_time(&state, &camera).await?;
+
+    // Get server time
+    let server_time = Utc::now();
+
+    // Convert server time to ONVIF format, in the time zone the camera's strategy asks for
+    let mut new_datetime = crate::onvif::ONVIFDateTime::from_chrono(&server_time);
+    new_datetime.time_zone = sync_time_zone(&camera, &before_datetime, server_time);
+
+    // Set camera time
+    let changed_at = std::time::SystemTime::now();
+    write_camera_time(&state, &camera, &new_datetime).await?;
+
+    // Wait a moment for the camera to process the time change
+    tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+
+    // Verify by reading the time again
+    let after_datetime = match read_camera_time(&state, &camera).await {
+        Ok(dt) => Some(dt),
+        Err(e) => {
+            println!("[TimeSync] Warning: Could not verify time after sync: {}", e);
+            None
+        }
+    };
+
+    // Some cameras drop the RTSP session when their clock jumps; only then is the stream restarted
+    let stream_restarted = if was_streaming {
+        match crate::stream_watchdog::recover_after_clock_change(&state.app_handle, id, changed_at).await {
+            Ok(restarted) => restarted,
+            Err(e) => {
+                println!("[TimeSync] Warning: Failed to restart stream: {}", e);
+                true
+            }
+        }
+    } else {
+        false
+    };
+
+    // Calculate time difference
+    let invalid_time = || AppMessage::new("time_sync.invalid_camera_time", json!({}));
+    let before_chrono = before_datetime.to_chrono().ok_or_else(invalid_time)?;
+    let time_diff = server_time.signed_duration_since(before_chrono);
+    let diff_seconds = time_diff.num_seconds();
+
+    // Check if verification shows the time was actually set
+    let message = if let Some(after_dt) = after_datetime {
+        let after_chrono = after_dt.to_chrono().ok_or_else(invalid_time)?;
+        let final_diff = Utc::now().signed_duration_since(after_chrono).num_seconds();
+
+        if final_diff.abs() < 5 {
+            AppMessage::new("time_sync.verified", json!({ "adjustedSecs": diff_seconds }))
+        } else {
+            AppMessage::new("time_sync.not_applied", json!({ "beforeDiffSecs": diff_seconds, "afterDiffSecs": final_diff }))
+        }
+    } else if diff_seconds.abs() < 2 {
+        AppMessage::new("time_sync.already_synced", json!({ "diffSecs": diff_seconds }))
+    } else {
+        AppMessage::new("time_sync.unverified", json!({ "adjustedSecs": diff_seconds }))
+    };
+
+    println!("[TimeSync] Camera {} - {}", id, message);
+
+    Ok(TimeSyncResult {
+        success: true,
+        beforeTime: serde_json::json!({
+            "year": before_datetime.year,
+            "month": before_datetime.month,
+            "day": before_datetime.day,
+            "hour": before_datetime.hour,
+            "minute": before_datetime.minute,
+            "second": before_datetime.second,
+        }),
+        serverTime: server_time.to_rfc3339(),
+        timeZone: new_datetime.time_zone.map(|zone| zone.tz),
+        streamRestarted: stream_restarted,
+        message,
+        error: None,
+    })
+}
+
+// ONVIF cameras talk to the device service directly; other types go through their plugin
+fn time_plugin<'a>(state: &'a State<'_, AppState>, camera: &Camera) -> Result<Option<&'a dyn crate::camera_plugin::CameraPlugin>, AppMessage> {
+    if camera.camera_type == "onvif" {
+        return Ok(None);
+    }
+    match state.plugin_manager.get_plugin(&camera.camera_type) {
+        Some(plugin) if plugin.supports_time_sync() => Ok(Some(plugin.as_ref())),
+        _ => Err(AppMessage::new("time_sync.unsupported", json!({ "cameraType": camera.camera_type }))),
+    }
+}
+
+// Time zone to set along with the time; plugins only take the time, so only ONVIF cameras get one
+fn sync_time_zone(camera: &Camera, before: &crate::onvif::ONVIFDateTime, now: DateTime<Utc>) -> Option<crate::onvif::CameraTimeZone> {
+    if camera.camera_type != "onvif" {
+        return None;
+    }
+    crate::onvif::sync_time_zone(camera.time_sync_strategy.as_deref(), before, now)
+}
+
+async fn read_camera_time(state: &State<'_, AppState>, camera: &Camera) -> Result<crate::onvif::ONVIFDateTime, String> {
+    match time_plugin(state, camera).map_err(|e| e.message)? {
+        Some(plugin) => Ok(crate::onvif::ONVIFDateTime::from_chrono(&plugin.get_camera_time(camera).await?)),
+        None => crate::onvif::get_system_date_time(&state.onvif_http, camera).await,
+    }
+}
+
+async fn write_camera_time(state: &State<'_, AppState>, camera: &Camera, datetime: &crate::onvif::ONVIFDateTime) -> Result<(), String> {
+    match time_plugin(state, camera).map_err(|e| e.message)? {
+        Some(plugin) => plugin.set_camera_time(camera, datetime.to_chrono().ok_or("Invalid time")?).await,
+        None => crate::onvif::set_system_date_time(&state.onvif_http, camera, datetime).await,
+    }
+}
+
+// Plugin PTZ for non-ONVIF cameras that support it
+fn ptz_plugin<'a>(state: &'a State<'_, AppState>, camera: &Camera) -> Option<&'a dyn crate::camera_plugin::CameraPlugin> {
+    state.plugin_manager.get_plugin(&camera.camera_type)
+        .filter(|plugin| camera.camera_type != "onvif" && plugin.supports_ptz())
+        .map(|plugin| plugin.as_ref())
+}
+
+#[tauri::command]
+pub async fn check_ptz_capabilities(state: State<'_, AppState>, id: i32) -> Result<PTZCapabilities, String> {
+    let cameras = get_cameras(state.clone()).await?;
+    let camera = cameras.into_iter().find(|c| c.id == id).ok_or("Camera not found")?;
+
+    if let Some(plugin) = ptz_plugin(&state, &camera) {
+        let capabilities = plugin.ptz_capabilities(&camera).await;
+        return Ok(PTZCapabilities { supported: capabilities.is_some(), capabilities });
+    }
+    if camera.camera_type != "onvif" {
+        return Ok(PTZCapabilities { supported: false, capabilities: None });
+    }
+
+    match crate::onvif::get_ptz_service_url(&state.onvif_http, &camera).await {
+        Ok(_) => Ok(PTZCapabilities { 
+            supported: true, 
+            capabilities: Some(crate::models::PTZCapabilitiesDetails { hasPanTilt: true, hasZoom: true }) 
+        }),
+        Err(_) => Ok(PTZCapabilities { supported: false, capabilities: None })
+    }
+}
+
+#[tauri::command]
+pub async fn move_ptz(state: State<'_, AppState>, id: i32, movement: PTZMovement) -> Result<PTZResult, String> {
+    let cameras = get_cameras(state.clone()).await?;
+    let camera = cameras.into_iter().find(|c| c.id == id).ok_or("Camera not found")?;
+
+    let x = movement.x.unwrap_or(0.0);
+    let y = movement.y.unwrap_or(0.0);
+    let zoom = movement.zoom.unwrap_or(0.0);
+
+    if let Some(plugin) = ptz_plugin(&state, &camera) {
+        // Plugins move in one direction at a time; follow the strongest axis
+        use crate::camera_plugin::PtzDirection;
+        let direction = if zoom.abs() >= x.abs() && zoom.abs() >= y.abs() && zoom != 0.0 {
+            if zoom > 0.0 { PtzDirection::ZoomIn } else { PtzDirection::ZoomOut }
+        } else if x.abs() >= y.abs() && x != 0.0 {
+            if x > 0.0 { PtzDirection::Right } else { PtzDirection::Left }
+        } else if y != 0.0 {
+            if y > 0.0 { PtzDirection::Up } else { PtzDirection::Down }
+        } else {
+            plugin.ptz_stop(&camera).await?;
+            return Ok(PTZResult { success: true, message: AppMessage::new("ptz.stopped", json!({})) });
+        };
+        plugin.ptz_move(&camera, direction, 0).await?;
+        return Ok(PTZResult { success: true, message: AppMessage::new("ptz.moving", json!({})) });
+    }
+
+    if camera.camera_type != "onvif" {
+        return Err("Not an ONVIF camera".to_string());
+    }
+
+    crate::onvif::continuous_move(&state.onvif_http, &camera, x, y, zoom).await?;
+    Ok(PTZResult { success: true, message: AppMessage::new("ptz.moving", json!({})) })
+}
+
+#[tauri::command]
+pub async fn stop_ptz(state: State<'_, AppState>, id: i32) -> Result<PTZResult, String> {
+    let cameras = get_cameras(state.clone()).await?;
+    let camera = cameras.into_iter().find(|c| c.id == id).ok_or("Camera not found")?;
+
+    if let Some(plugin) = ptz_plugin(&state, &camera) {
+        plugin.ptz_stop(&camera).await?;
+        return Ok(PTZResult { success: true, message: AppMessage::new("ptz.stopped", json!({})) });
+    }
+    if camera.camera_type != "onvif" {
+         return Err("Not an ONVIF camera".to_string());
+    }
+
+    crate::onvif::stop_move(&state.onvif_http, &camera).await?;
+    Ok(PTZResult { success: true, message: AppMessage::new("ptz.stopped", json!({})) })
+}
+
+// Camera-side user management (ONVIF)
+async fn get_onvif_camera(state: &State<'_, AppState>, id: i32) -> Result<Camera, String> {
+    let cameras = get_cameras(state.clone()).await?;
+    let camera = cameras.into_iter().find(|c| c.id == id).ok_or("Camera not found")?;
+
+    if camera.camera_type != "onvif" {
+        return Err("This is only supported for ONVIF cameras".to_string());
+    }
+
+    Ok(camera)
+}
+
+fn store_camera_credentials(state: &State<AppState>, id: i32, user: &str, pass: &str) -> Result<(), String> {
+    let conn = get_conn(state)?;
+    conn.execute(
+        "UPDATE cameras SET user = ?1, pass = ?2, updated_at = CURRENT_TIMESTAMP WHERE id = ?3",
+        rusqlite::params![user, pass, id],
+    ).map_err(|e| e.to_string())?;
+
+    println!("[Users] Stored credentials for camera {} updated to user '{}'", id, user);
+    Ok(())
+}
+
+// How long a camera may take to answer at its new address (longer when it reboots)
+const NETWORK_RECHECK_SECS: u64 = 60;
+const NETWORK_RECHECK_AFTER_REBOOT_SECS: u64 = 180;
+const NETWORK_RECHECK_INTERVAL_SECS: u64 = 5;
+
+#[tauri::command]
+pub async fn get_camera_network(state: State<'_, AppState>, id: i32) -> Result<NetworkConfig, String> {
+    let camera = get_onvif_camera(&state, id).await?;
+    crate::onvif::get_network_config(&state.onvif_http, &camera).await
+}
+
+/// Apply new network settings to a camera. DNS and gateway go first while the camera is still
+/// reachable at its old address; after the interface change (and a reboot if the camera asks
+/// for one) the stored host and xAddr follow a static address and the camera is probed there.
+#[tauri::command]
+pub async fn set_camera_network(state: State<'_, AppState>, id: i32, change: NetworkChange) -> Result<NetworkChangeResult, String> {
+    let camera = get_onvif_camera(&state, id).await?;
+    let old_xaddr = camera.xaddr.clone().ok_or("No xAddr available for ONVIF camera")?;
+
+    let address = if change.dhcp {
+        None
+    } else {
+        let address = change.ipv4_address.as_deref().map(str::trim)
+            .ok_or("An IPv4 address is required for a static configuration")?;
+        address.parse::<std::net::Ipv4Addr>().map_err(|_| format!("Invalid IPv4 address '{}'", address))?;
+        let prefix_length = change.prefix_length.ok_or("A prefix length is required for a static configuration")?;
+        if !(1..=32).contains(&prefix_length) {
+            return Err("Prefix length must be between 1 and 32".to_string());
+        }
+        Some((address.to_string(), prefix_length))
+    };
+    let gateway = change.gateway.as_deref().map(str::trim).filter(|g| !g.is_empty());
+    if let Some(gateway) = gateway {
+        gateway.parse::<std::net::Ipv4Addr>().map_err(|_| format!("Invalid gateway address '{}'", gateway))?;
+    }
+    let dns_servers: Vec<String> = change.dns_servers.unwrap_or_default().iter()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect();
+    for server in &dns_servers {
+        server.parse::<std::net::Ipv4Addr>().map_err(|_| format!("Invalid DNS server address '{}'", server))?;
+    }
+
+    if let Some(from_dhcp) = change.dns_from_dhcp.or((!dns_servers.is_empty()).then_some(false)) {
+        crate::onvif::set_dns(&state.onvif_http, &camera, from_dhcp, &dns_servers).await?;
+    }
+    if let Some(gateway) = gateway {
+        crate::onvif::set_default_gateway(&state.onvif_http, &camera, gateway).await?;
+    }
+
+    let reboot_needed = crate::onvif::set_network_interface(
+        &state.onvif_http,
+        &camera,
+        &change.interface_token,
+        change.dhcp,
+        address.as_ref().map(|(address, prefix_length)| (address.as_str(), *prefix_length)),
+    ).await?;
+    if reboot_needed {
+        crate::onvif::system_reboot(&state.onvif_http, &camera).await?;
+    }
+
+    // A DHCP lease can't be predicted, so only a static address moves the stored host
+    let new_xaddr = match &address {
+        Some((address, _)) if *address != camera.host => {
+            let new_xaddr = crate::onvif::move_xaddr(&old_xaddr, address)?;
+            let conn = get_conn(&state)?;
+            conn.execute(
+                "UPDATE cameras SET host = ?1, xaddr = ?2, updated_at = CURRENT_TIMESTAMP WHERE id = ?3",
+                rusqlite::params![address, new_xaddr, id],
+            ).map_err(|e| e.to_string())?;
+            crate::onvif::invalidate_services(&old_xaddr);
+            crate::onvif::reset_clock_skew(&old_xaddr);
+            println!("[Network] Camera {} moved from {} to {}", id, camera.host, address);
+            new_xaddr
+        }
+        _ => old_xaddr,
+    };
+
+    let timeout = if reboot_needed { NETWORK_RECHECK_AFTER_REBOOT_SECS } else { NETWORK_RECHECK_SECS };
+    let user = camera.user.clone().unwrap_or_default();
+    let pass = camera.pass.clone().unwrap_or_default();
+    let started = std::time::Instant::now();
+    let mut reachable = false;
+    while started.elapsed().as_secs() < timeout {
+        tokio::time::sleep(tokio::time::Duration::from_secs(NETWORK_RECHECK_INTERVAL_SECS)).await;
+        if crate::onvif::probe_device(&state.onvif_http, &new_xaddr, camera.port as u16, &user, &pass).await.is_ok() {
+            reachable = true;
+            break;
+        }
+    }
+
+    let camera = get_onvif_camera(&state, id).await?;
+    let message = match (reachable, change.dhcp) {
+        (true, _) => AppMessage::new("network.reachable", json!({ "host": camera.host })),
+        (false, true) => AppMessage::new("network.dhcp_moved", json!({})),
+        (false, false) => AppMessage::new("network.unreachable", json!({ "host": camera.host, "timeoutSecs": timeout })),
+    };
+    println!("[Network] Camera {}: {}", id, message);
+
+    Ok(NetworkChangeResult { camera, rebooted: reboot_needed, reachable, message })
+}
+
+#[tauri::command]
+pub async fn get_camera_users(state: State<'_, AppState>, id: i32) -> Result<Vec<OnvifUser>, String> {
+    let camera = get_onvif_camera(&state, id).await?;
+    crate::onvif::get_users(&state.onvif_http, &camera).await
+}
+
+#[tauri::command]
+pub async fn create_camera_user(state: State<'_, AppState>, id: i32, user: OnvifUserChange) -> Result<Camera, String> {
+    let camera = get_onvif_camera(&state, id).await?;
+    let password = user.password.as_deref().filter(|p| !p.is_empty()).ok_or("Password is required for a new user")?;
+
+    crate::onvif::create_user(&state.onvif_http, &camera, &user.username, password, &user.user_level).await?;
+
+    if user.use_for_app {
+        store_camera_credentials(&state, id, &user.username, password)?;
+    }
+
+    get_onvif_camera(&state, id).await
+}
+
+#[tauri::command]
+pub async fn update_camera_user(state: State<'_, AppState>, id: i32, user: OnvifUserChange) -> Result<Camera, String> {
+    let camera = get_onvif_camera(&state, id).await?;
+    let password = user.password.as_deref().filter(|p| !p.is_empty());
+    let is_app_user = camera.user.as_deref() == Some(user.username.as_str());
+
+    if user.use_for_app && !is_app_user && password.is_none() {
+        return Err("A new password is required to switch the app to this user".to_string());
+    }
+
+    crate::onvif::set_user(&state.onvif_http, &camera, &user.username, password, &user.user_level).await?;
+
+    // Keep the app connected after rotating the password of the account it uses
+    if let Some(password) = password {
+        if is_app_user || user.use_for_app {
+            store_camera_credentials(&state, id, &user.username, password)?;
+        }
+    }
+
+    get_onvif_camera(&state, id).await
+}
+
+#[tauri::command]
+pub async fn delete_camera_user(state: State<'_, AppState>, id: i32, username: String) -> Result<(), String> {
+    let camera = get_onvif_camera(&state, id).await?;
+
+    if camera.user.as_deref() == Some(username.as_str()) {
+        return Err("Cannot delete the user the app is connecting with".to_string());
+    }
+
+    crate::onvif::delete_user(&state.onvif_http, &camera, &username).await
+}
+
+#[tauri::command]
+pub async fn get_camera_capabilities(_id: i32) -> Result<CameraCapabilities, String> {
+     Ok(CameraCapabilities {
+        streaming: true,
+        recording: true,
+        thumbnails: false,
+        ptz: true, // Optimistically true, or check dynamically
+        discovery: false,
+        timeSync: false,
+        remoteAccess: false,
+    })
+}
+
+// ============= GPU & Encoder Commands =============
+
+#[tauri::command]
+pub async fn detect_gpu() -> Result<GpuCapabilities, String> {
+    println!("[GPU] Detecting GPU capabilities...");
+    detect_gpu_capabilities().await
+}
+
+#[tauri::command]
+pub async fn get_encoder_settings(state: State<'_, AppState>) -> Result<EncoderSettings, String> {
+    let conn = get_conn(&state)?;
+
+    let mut stmt = conn.prepare(
+        "SELECT id, encoder_mode, gpu_encoder, cpu_encoder, preset, quality FROM encoder_settings WHERE id = 1"
+    ).map_err(|e| e.to_string())?;
+
+    let settings = stmt.query_row([], |row| {
+        Ok(EncoderSettings {
+            id: row.get(0)?,
+            encoderMode: row.get(1)?,
+            gpuEncoder: row.get(2)?,
+            cpuEncoder: row.get(3)?,
+            preset: row.get(4)?,
+            quality: row.get(5)?,
+        })
+    }).map_err(|e| e.to_string())?;
+
+    Ok(settings)
+}
+
+#[tauri::command]
+pub async fn update_encoder_settings(
+    state: State<'_, AppState>,
+    settings: UpdateEncoderSettings,
+) -> Result<EncoderSettings, String> {
+    let conn = get_conn(&state)?;
+
+    // Use separate UPDATE statements for each field
+    if let Some(mode) = &settings.encoderMode {
+        conn.execute("UPDATE encoder_settings SET encoder_mode = ?1 WHERE id = 1", [mode])
+            .map_err(|e| e.to_string())?;
+    }
+    if let Some(gpu_enc) = &settings.gpuEncoder {
+        conn.execute("UPDATE encoder_settings SET gpu_encoder = ?1 WHERE id = 1", [gpu_enc])
+            .map_err(|e| e.to_string())?;
+    }
+    if let Some(cpu_enc) = &settings.cpuEncoder {
+        conn.execute("UPDATE encoder_settings SET cpu_encoder = ?1 WHERE id = 1", [cpu_enc])
+            .map_err(|e| e.to_string())?;
+    }
+    if let Some(p) = &settings.preset {
+        conn.execute("UPDATE encoder_settings SET preset = ?1 WHERE id = 1", [p])
+            .map_err(|e| e.to_string())?;
+    }
+    if let Some(q) = settings.quality {
+        conn.execute("UPDATE encoder_settings SET quality = ?1 WHERE id = 1", [q])
+            .map_err(|e| e.to_string())?;
+    }
+
+    if settings.encoderMode.is_none()
+        && settings.gpuEncoder.is_none()
+        && settings.cpuEncoder.is_none()
+        && settings.preset.is_none()
+        && settings.quality.is_none() {
+        return Err("No fields to update".to_string());
+    }
+
+    // Drop connection before await
+    drop(conn);
+    // A new encoder choice gets a chance even on cameras that fell back to the CPU
+    state.pipelines.clear_cpu_fallbacks();
+
+    // Return updated settings
+    get_encoder_settings(state).await
+}
+
+/// Check recordings on disk against the library. `deep` (default) also reads each container with ffprobe.
+#[tauri::command]
+pub async fn verify_recordings(state: State<'_, AppState>, deep: Option<bool>) -> Result<VerifyReport, String> {
+    let db_path = state.db_path.clone();
+    let recording_dir = state.recording_dir.clone();
+    let deep = deep.unwrap_or(true);
+
+    tauri::async_runtime::spawn_blocking(move || {
+        crate::integrity::verify_recordings(&db_path, &recording_dir, deep)
+    }).await.map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+pub async fn repair_recording(state: State<'_, AppState>, id: i32) -> Result<(), String> {
+    let db_path = state.db_path.clone();
+    let recording_dir = state.recording_dir.clone();
+
+    tauri::async_runtime::spawn_blocking(move || {
+        crate::integrity::repair_recording(&db_path, &recording_dir, id)
+    }).await.map_err(|e| e.to_string())?
+}
+
+// ========== Server Commands ==========
+
+/// Token for authenticated endpoints such as `/recordings/{id}/download?token=...`
+#[tauri::command]
+pub async fn get_server_token(state: State<'_, AppState>) -> Result<String, String> {
+    Ok(state.server_token.clone())
+}
+
+/// Log the app window in; its commands are then checked against the user's role
+#[tauri::command]
+pub async fn login(state: State<'_, AppState>, username: String, password: String) -> Result<AuthSession, String> {
+    let conn = get_conn(&state)?;
+    let session = crate::auth::login(&conn, &username, &password)?;
+    state.auth.set(Some(session.token.clone()));
+    Ok(session)
+}
+
+#[tauri::command]
+pub async fn logout(state: State<'_, AppState>) -> Result<(), String> {
+    if let Some(token) = state.auth.token() {
+        crate::auth::logout(&get_conn(&state)?, &token)?;
+    }
+    state.auth.set(None);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_auth_status(state: State<'_, AppState>) -> Result<AuthStatus, String> {
+    let conn = get_conn(&state)?;
+    let user = match state.auth.token() {
+        Some(token) => crate::auth::session_user(&conn, &token)?,
+        None => None,
+    };
+    Ok(AuthStatus {
+        required: crate::auth::is_required(&conn)?,
+        has_admin: crate::auth::admin_count(&conn)? > 0,
+        user,
+    })
+}
+
+/// Change the password of the logged-in user
+#[tauri::command]
+pub async fn change_password(state: State<'_, AppState>, current_password: String, new_password: String) -> Result<(), String> {
+    let conn = get_conn(&state)?;
+    let token = state.auth.token().ok_or("Not logged in")?;
+    let user = crate::auth::session_user(&conn, &token)?.ok_or("Not logged in")?;
+    crate::auth::check_password(&conn, user.id, &current_password)?;
+
+    conn.execute(
+        "UPDATE app_users SET password_hash = ?1 WHERE id = ?2",
+        rusqlite::params![crate::auth::hash_password(&new_password)?, user.id],
+    ).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_app_users(state: State<'_, AppState>) -> Result<Vec<AppUser>, String> {
+    crate::auth::list_users(&get_conn(&state)?)
+}
+
+#[tauri::command]
+pub async fn add_app_user(state: State<'_, AppState>, user: NewAppUser) -> Result<AppUser, String> {
+    let username = user.username.trim();
+    if username.is_empty() {
+        return Err("Username must not be empty".to_string());
+    }
+    let conn = get_conn(&state)?;
+    conn.execute(
+        "INSERT INTO app_users (username, password_hash, role, created_at) VALUES (?1, ?2, ?3, ?4)",
+        rusqlite::params![username, crate::auth::hash_password(&user.password)?, user.role.as_str(), Utc::now().to_rfc3339()],
+    ).map_err(|e| match e {
+        rusqlite::Error::SqliteFailure(err, _) if err.code == rusqlite::ErrorCode::ConstraintViolation => {
+            format!("User '{}' already exists", username)
+        }
+        e => e.to_string(),
+    })?;
+    println!("[Auth] Added user '{}' ({})", username, user.role.as_str());
+    crate::auth::load_user(&conn, conn.last_insert_rowid() as i32)
+}
+
+/// Change a user's role or password; a new password ends the user's sessions
+#[tauri::command]
+pub async fn update_app_user(state: State<'_, AppState>, id: i32, changes: UpdateAppUser) -> Result<AppUser, String> {
+    let conn = get_conn(&state)?;
+    if let Some(role) = changes.role {
+        if role != crate::auth::Role::Admin {
+            crate::auth::check_keeps_admin(&conn, id)?;
+        }
+        conn.execute("UPDATE app_users SET role = ?1 WHERE id = ?2", rusqlite::params![role.as_str(), id])
+            .map_err(|e| e.to_string())?;
+    }
+    if let Some(password) = &changes.password {
+        conn.execute(
+            "UPDATE app_users SET password_hash = ?1 WHERE id = ?2",
+            rusqlite::params![crate::auth::hash_password(password)?, id],
+        ).map_err(|e| e.to_string())?;
+        conn.execute("DELETE FROM app_sessions WHERE user_id = ?1", [id]).map_err(|e| e.to_string())?;
+    }
+    crate::auth::load_user(&conn, id)
+}
+
+#[tauri::command]
+pub async fn delete_app_user(state: State<'_, AppState>, id: i32) -> Result<(), String> {
+    let conn = get_conn(&state)?;
+    crate::auth::check_keeps_admin(&conn, id)?;
+    // Sessions go with the user (ON DELETE CASCADE)
+    conn.execute("DELETE FROM app_users WHERE id = ?1", [id]).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Port the local HTTP server listens on (server.port as of app start)
+#[tauri::command]
+pub async fn get_server_port(state: State<'_, AppState>) -> Result<u16, String> {
+    Ok(state.server_port)
+}
+
+#[tauri::command]
+pub async fn get_server_settings(state: State<'_, AppState>) -> Result<ServerSettings, String> {
+    let conn = get_conn(&state)?;
+    Ok(ServerSettings {
+        id: 1,
+        downloadLimitKbps: crate::download::load_download_limit(&conn)?,
+    })
+}
+
+#[tauri::command]
+pub async fn update_server_settings(
+    state: State<'_, AppState>,
+    settings: UpdateServerSettings,
+) -> Result<ServerSettings, String> {
+    {
+        let mut conn = get_conn(&state)?;
+        let changes: serde_json::Map<String, serde_json::Value> = settings.downloadLimitKbps
+            .map(|kbps| (crate::settings::SERVER_DOWNLOAD_LIMIT.to_string(), serde_json::Value::from(kbps)))
+            .into_iter()
+            .collect();
+        update_app_settings_with_conn(&state, &mut conn, &changes)?;
+    }
+
+    get_server_settings(state).await
+}
+
+/// All app_settings values keyed by name ("server.port", "hls.segmentSecs", ...)
+#[tauri::command]
+pub async fn get_app_settings(state: State<'_, AppState>) -> Result<serde_json::Map<String, serde_json::Value>, String> {
+    crate::settings::load_all(&get_conn(&state)?)
+}
+
+/// Change any number of settings at once; nothing is stored if one of them is invalid
+#[tauri::command]
+pub async fn update_app_settings(
+    state: State<'_, AppState>,
+    changes: serde_json::Map<String, serde_json::Value>,
+) -> Result<serde_json::Map<String, serde_json::Value>, String> {
+    let mut conn = get_conn(&state)?;
+    update_app_settings_with_conn(&state, &mut conn, &changes)?;
+    crate::settings::load_all(&conn)
+}
+
+// Store settings and tell the UI which ones changed
+fn update_app_settings_with_conn(
+    state: &State<'_, AppState>,
+    conn: &mut Connection,
+    changes: &serde_json::Map<String, serde_json::Value>,
+) -> Result<(), String> {
+    // Turning login on without an admin would lock everyone out
+    if changes.get(crate::settings::AUTH_REQUIRED).and_then(|value| value.as_bool()) == Some(true)
+        && crate::auth::admin_count(conn)? == 0
+    {
+        return Err("Add an admin user before requiring login".to_string());
+    }
+    let change = crate::settings::update(conn, changes)?;
+    if change.keys.is_empty() {
+        return Ok(());
+    }
+    // Reconnect with the new settings (or just disconnect when disabled)
+    if change.keys.iter().any(|key| key.starts_with("mqtt.")) {
+        crate::mqtt::start(&state.app_handle)?;
+    }
+    state.events.emit(AppEvent::SettingsChanged { keys: change.keys, restart_required: change.restart_required });
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_hls_settings(state: State<'_, AppState>) -> Result<HlsSettings, String> {
+    let conn = get_conn(&state)?;
+    crate::hls::load_settings(&conn)
+}
+
+/// Live stream HLS output; takes effect the next time a stream starts
+#[tauri::command]
+pub async fn update_hls_settings(
+    state: State<'_, AppState>,
+    settings: UpdateHlsSettings,
+) -> Result<HlsSettings, String> {
+    use crate::settings::{HLS_DELETE_THRESHOLD, HLS_LIST_SIZE, HLS_SEGMENT_DIR, HLS_SEGMENT_SECS, HLS_STALL_SEGMENTS, HLS_TIMESHIFT_MINUTES};
+
+    let mut conn = get_conn(&state)?;
+    let changes: serde_json::Map<String, serde_json::Value> = [
+        (HLS_SEGMENT_SECS, settings.segmentSecs.map(serde_json::Value::from)),
+        (HLS_LIST_SIZE, settings.listSize.map(serde_json::Value::from)),
+        (HLS_DELETE_THRESHOLD, settings.deleteThreshold.map(serde_json::Value::from)),
+        (HLS_SEGMENT_DIR, settings.segmentDir.map(serde_json::Value::from)),
+        (HLS_TIMESHIFT_MINUTES, settings.timeshiftMinutes.map(serde_json::Value::from)),
+        (HLS_STALL_SEGMENTS, settings.stallSegments.map(serde_json::Value::from)),
+    ]
+        .into_iter()
+        .filter_map(|(key, value)| Some((key.to_string(), value?)))
+        .collect();
+    update_app_settings_with_conn(&state, &mut conn, &changes)?;
+
+    crate::hls::load_settings(&conn)
+}
+
+#[tauri::command]
+pub async fn get_camera_hls_settings(state: State<'_, AppState>, camera_id: i32) -> Result<Option<CameraHlsSettings>, String> {
+    let conn = get_conn(&state)?;
+    crate::hls::load_camera_settings(&conn, camera_id)
+}
+
+/// Override HLS output for one camera; fields left empty follow the global settings, and a
+/// camera without any override is removed from camera_hls_settings
+#[tauri::command]
+pub async fn set_camera_hls_settings(state: State<'_, AppState>, settings: CameraHlsSettings) -> Result<(), String> {
+    if let Some(secs) = settings.segment_secs {
+        crate::hls::check_range("Segment duration", secs, crate::hls::SEGMENT_SECS_RANGE)?;
+    }
+    if let Some(size) = settings.list_size {
+        crate::hls::check_range("Playlist size", size, crate::hls::LIST_SIZE_RANGE)?;
+    }
+    if let Some(threshold) = settings.delete_threshold {
+        crate::hls::check_range("Delete threshold", threshold, crate::hls::DELETE_THRESHOLD_RANGE)?;
+    }
+    if let Some(minutes) = settings.timeshift_minutes {
+        crate::hls::check_range("Timeshift window", minutes, crate::hls::TIMESHIFT_MINUTES_RANGE)?;
+    }
+    let segment_dir = settings.segment_dir.as_deref().map(str::trim).filter(|dir| !dir.is_empty());
+    if let Some(dir) = segment_dir {
+        crate::hls::check_segment_dir(dir)?;
+    }
+    if let Some(packaging) = &settings.packaging {
+        if !crate::hls::PACKAGINGS.contains(&packaging.as_str()) {
+            return Err(format!("Unknown stream packaging {}; use one of {}", packaging, crate::hls::PACKAGINGS.join(", ")));
+        }
+    }
 
-    if camera.camera_type != "onvif" {
-        return Err("Not an ONVIF camera".to_string());
+    let conn = get_conn(&state)?;
+    if settings.segment_secs.is_none() && settings.list_size.is_none() && settings.delete_threshold.is_none()
+        && segment_dir.is_none() && settings.timeshift_minutes.is_none() && settings.packaging.is_none()
+    {
+        conn.execute("DELETE FROM camera_hls_settings WHERE camera_id = ?1", [settings.camera_id])
+            .map_err(|e| e.to_string())?;
+        return Ok(());
     }
 
-    let x = movement.x.unwrap_or(0.0);
-    let y = movement.y.unwrap_or(0.0);
-    let zoom = movement.zoom.unwrap_or(0.0);
+    conn.execute(
+        "INSERT INTO camera_hls_settings (camera_id, segment_secs, list_size, delete_threshold, segment_dir, timeshift_minutes, packaging)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+         ON CONFLICT(camera_id) DO UPDATE SET segment_secs = excluded.segment_secs, list_size = excluded.list_size,
+             delete_threshold = excluded.delete_threshold, segment_dir = excluded.segment_dir,
+             timeshift_minutes = excluded.timeshift_minutes, packaging = excluded.packaging",
+        rusqlite::params![settings.camera_id, settings.segment_secs, settings.list_size, settings.delete_threshold, segment_dir, settings.timeshift_minutes, settings.packaging],
+    ).map_err(|e| e.to_string())?;
 
-    crate::onvif::continuous_move(&camera, x, y, zoom).await?;
-    Ok(PTZResult { success: true, message: "Moving".to_string() })
+    Ok(())
 }
 
+/// Live rewind window of a camera's stream, for pausing and scrubbing back in the player
 #[tauri::command]
-pub async fn stop_ptz(state: State<'_, AppState>, id: i32) -> Result<PTZResult, String> {
-    let cameras = get_cameras(state.clone()).await?;
-    let camera = cameras.into_iter().find(|c| c.id == id).ok_or("Camera not found")?;
+pub async fn get_timeshift_info(state: State<'_, AppState>, camera_id: i32) -> Result<TimeshiftInfo, String> {
+    let conn = get_conn(&state)?;
+    crate::hls::timeshift_info(&state, &conn, camera_id)
+}
 
-    if camera.camera_type != "onvif" {
-         return Err("Not an ONVIF camera".to_string());
+#[tauri::command]
+pub async fn get_recording_settings(state: State<'_, AppState>) -> Result<RecordingSettings, String> {
+    let conn = get_conn(&state)?;
+    Ok(RecordingSettings {
+        id: 1,
+        defaultMaxDurationMinutes: crate::stream::load_default_max_duration(&conn)?,
+    })
+}
+
+#[tauri::command]
+pub async fn update_recording_settings(
+    state: State<'_, AppState>,
+    settings: UpdateRecordingSettings,
+) -> Result<RecordingSettings, String> {
+    {
+        let conn = get_conn(&state)?;
+        if let Some(minutes) = settings.defaultMaxDurationMinutes {
+            if minutes < 0 {
+                return Err("Max duration must not be negative".to_string());
+            }
+            conn.execute(
+                "UPDATE recording_settings SET default_max_duration_minutes = ?1 WHERE id = 1",
+                [(minutes > 0).then_some(minutes)],
+            ).map_err(|e| e.to_string())?;
+        }
     }
 
-    crate::onvif::stop_move(&camera).await?;
-    Ok(PTZResult { success: true, message: "Stopped".to_string() })
+    get_recording_settings(state).await
 }
 
+// ========== Trigger Commands ==========
+
 #[tauri::command]
-pub async fn get_camera_capabilities(_id: i32) -> Result<CameraCapabilities, String> {
-     Ok(CameraCapabilities {
-        streaming: true,
-        recording: true,
-        thumbnails: false,
-        ptz: true, // Optimistically true, or check dynamically
-        discovery: false,
-        timeSync: false,
-        remoteAccess: false,
-    })
+pub async fn trigger_camera(
+    state: State<'_, AppState>,
+    camera_id: i32,
+    action: Option<String>,
+    reason: Option<String>,
+    duration_minutes: Option<i32>,
+) -> Result<TriggerResult, String> {
+    let request = TriggerRequest { action, reason, durationMinutes: duration_minutes };
+    crate::triggers::fire(&state, camera_id, request, "app").await
 }
 
-// ============= GPU & Encoder Commands =============
+#[tauri::command]
+pub async fn get_trigger_settings(state: State<'_, AppState>) -> Result<TriggerSettings, String> {
+    let conn = get_conn(&state)?;
+    crate::triggers::load_settings(&conn)
+}
 
 #[tauri::command]
-pub async fn detect_gpu() -> Result<GpuCapabilities, String> {
-    println!("[GPU] Detecting GPU capabilities...");
-    detect_gpu_capabilities().await
+pub async fn update_trigger_settings(
+    state: State<'_, AppState>,
+    settings: UpdateTriggerSettings,
+) -> Result<TriggerSettings, String> {
+    use crate::settings::{TRIGGERS_ALLOW_REMOTE, TRIGGERS_DEFAULT_RECORD_MINUTES, TRIGGERS_REMOTE_PORT, TRIGGERS_TOKEN};
+
+    let mut conn = get_conn(&state)?;
+    let token = (settings.regenerateToken == Some(true)).then(crate::settings::new_token);
+    let changes: serde_json::Map<String, serde_json::Value> = [
+        (TRIGGERS_ALLOW_REMOTE, settings.allowRemote.map(serde_json::Value::from)),
+        (TRIGGERS_REMOTE_PORT, settings.remotePort.map(serde_json::Value::from)),
+        (TRIGGERS_DEFAULT_RECORD_MINUTES, settings.defaultRecordMinutes.map(serde_json::Value::from)),
+        (TRIGGERS_TOKEN, token.map(serde_json::Value::from)),
+    ]
+        .into_iter()
+        .filter_map(|(key, value)| Some((key.to_string(), value?)))
+        .collect();
+    update_app_settings_with_conn(&state, &mut conn, &changes)?;
+
+    crate::triggers::load_settings(&conn)
 }
 
+// ========== MQTT Commands ==========
+
 #[tauri::command]
-pub async fn get_encoder_settings(state: State<'_, AppState>) -> Result<EncoderSettings, String> {
+pub async fn get_mqtt_settings(state: State<'_, AppState>) -> Result<MqttSettings, String> {
     let conn = get_conn(&state)?;
+    let mut settings = crate::mqtt::load_settings(&conn)?;
+    settings.connected = state.mqtt.is_connected();
+    Ok(settings)
+}
 
-    let mut stmt = conn.prepare(
-        "SELECT id, encoder_mode, gpu_encoder, cpu_encoder, preset, quality FROM encoder_settings WHERE id = 1"
+#[tauri::command]
+pub async fn update_mqtt_settings(
+    state: State<'_, AppState>,
+    settings: UpdateMqttSettings,
+) -> Result<MqttSettings, String> {
+    use crate::settings::{MQTT_BASE_TOPIC, MQTT_CLIENT_ID, MQTT_DISCOVERY_PREFIX, MQTT_ENABLED, MQTT_HOST, MQTT_PASSWORD, MQTT_PORT, MQTT_USERNAME};
+
+    {
+        let mut conn = get_conn(&state)?;
+        // Empty strings clear the optional fields
+        let changes: serde_json::Map<String, serde_json::Value> = [
+            (MQTT_ENABLED, settings.enabled.map(serde_json::Value::from)),
+            (MQTT_HOST, settings.host.map(serde_json::Value::from)),
+            (MQTT_PORT, settings.port.map(serde_json::Value::from)),
+            (MQTT_USERNAME, settings.username.map(serde_json::Value::from)),
+            (MQTT_PASSWORD, settings.password.map(serde_json::Value::from)),
+            (MQTT_CLIENT_ID, settings.clientId.map(serde_json::Value::from)),
+            (MQTT_DISCOVERY_PREFIX, settings.discoveryPrefix.map(serde_json::Value::from)),
+            (MQTT_BASE_TOPIC, settings.baseTopic.map(serde_json::Value::from)),
+        ]
+            .into_iter()
+            .filter_map(|(key, value)| Some((key.to_string(), value?)))
+            .collect();
+        update_app_settings_with_conn(&state, &mut conn, &changes)?;
+    }
+
+    get_mqtt_settings(state).await
+}
+
+// ========== Archive Commands ==========
+
+#[tauri::command]
+pub async fn archive_recording(state: State<'_, AppState>, id: i32) -> Result<(), String> {
+    state.archive_queue.enqueue(id)
+}
+
+#[tauri::command]
+pub async fn get_archive_queue(state: State<'_, AppState>) -> Result<Vec<ArchiveJob>, String> {
+    Ok(state.archive_queue.snapshot())
+}
+
+#[tauri::command]
+pub async fn get_archive_settings(state: State<'_, AppState>) -> Result<ArchiveSettings, String> {
+    let conn = get_conn(&state)?;
+    crate::archive::load_settings(&conn)
+}
+
+#[tauri::command]
+pub async fn update_archive_settings(
+    state: State<'_, AppState>,
+    settings: UpdateArchiveSettings,
+) -> Result<ArchiveSettings, String> {
+    let conn = get_conn(&state)?;
+
+    if let Some(target_type) = &settings.targetType {
+        if target_type != "local" && target_type != "webdav" {
+            return Err(format!("Invalid archive target type '{}' (expected local or webdav)", target_type));
+        }
+        conn.execute("UPDATE archive_settings SET target_type = ?1 WHERE id = 1", [target_type])
+            .map_err(|e| e.to_string())?;
+    }
+    if let Some(target) = &settings.target {
+        conn.execute("UPDATE archive_settings SET target = ?1 WHERE id = 1", [target])
+            .map_err(|e| e.to_string())?;
+    }
+    if let Some(user) = &settings.webdavUser {
+        conn.execute("UPDATE archive_settings SET webdav_user = ?1 WHERE id = 1", [user])
+            .map_err(|e| e.to_string())?;
+    }
+    if let Some(pass) = &settings.webdavPass {
+        conn.execute("UPDATE archive_settings SET webdav_pass = ?1 WHERE id = 1", [pass])
+            .map_err(|e| e.to_string())?;
+    }
+    if let Some(auto_archive) = settings.autoArchive {
+        conn.execute("UPDATE archive_settings SET auto_archive = ?1 WHERE id = 1", [auto_archive])
+            .map_err(|e| e.to_string())?;
+    }
+
+    crate::archive::load_settings(&conn)
+}
+
+// ========== Cloud Upload Commands ==========
+
+#[tauri::command]
+pub async fn upload_recording(state: State<'_, AppState>, id: i32) -> Result<(), String> {
+    state.upload_queue.enqueue(id)
+}
+
+#[tauri::command]
+pub async fn get_upload_queue(state: State<'_, AppState>) -> Result<Vec<UploadJob>, String> {
+    Ok(state.upload_queue.snapshot())
+}
+
+// ========== Job Commands ==========
+
+/// Queue a background job of one of jobs::JOB_KINDS; progress arrives as job-progress events
+#[tauri::command]
+pub async fn submit_job(state: State<'_, AppState>, kind: String, params: Option<serde_json::Value>) -> Result<BackgroundJob, String> {
+    state.job_queue.submit(&kind, params.unwrap_or(serde_json::Value::Null))
+}
+
+/// Queue a looping preview of a recording, `offset` seconds in (0 by default), as WebP or GIF;
+/// the recording's `preview` is set and `recording-updated` sent when it is ready
+#[tauri::command]
+pub async fn create_preview(
+    state: State<'_, AppState>,
+    recording_id: i32,
+    offset: Option<f64>,
+    format: Option<String>,
+) -> Result<BackgroundJob, String> {
+    let format = format.unwrap_or_else(|| crate::previews::PREVIEW_FORMATS[0].to_string());
+    if !crate::previews::PREVIEW_FORMATS.contains(&format.as_str()) {
+        return Err(format!("Unknown preview format '{}' (expected one of {})", format, crate::previews::PREVIEW_FORMATS.join(", ")));
+    }
+    let finished: bool = get_conn(&state)?.query_row(
+        "SELECT is_finished AND failure IS NULL FROM recordings WHERE id = ?1",
+        [recording_id],
+        |row| row.get(0),
+    ).map_err(|_| format!("Recording {} not found", recording_id))?;
+    if !finished {
+        return Err(format!("Recording {} has no finished file to preview", recording_id));
+    }
+    state.job_queue.submit("create_preview", serde_json::json!({
+        "recording_id": recording_id,
+        "offset": offset.unwrap_or(0.0),
+        "format": format,
+    }))
+}
+
+/// Recent jobs, newest first
+#[tauri::command]
+pub async fn get_jobs(state: State<'_, AppState>, limit: Option<u32>) -> Result<Vec<BackgroundJob>, String> {
+    let conn = get_conn(&state)?;
+    crate::jobs::list_jobs(&conn, limit.unwrap_or(50))
+}
+
+#[tauri::command]
+pub async fn cancel_job(state: State<'_, AppState>, id: i64) -> Result<(), String> {
+    state.job_queue.cancel(id)
+}
+
+#[tauri::command]
+pub async fn get_cloud_upload_settings(state: State<'_, AppState>) -> Result<CloudUploadSettings, String> {
+    let conn = get_conn(&state)?;
+    crate::cloud_upload::load_settings(&conn)
+}
+
+#[tauri::command]
+pub async fn update_cloud_upload_settings(
+    state: State<'_, AppState>,
+    settings: UpdateCloudUploadSettings,
+) -> Result<CloudUploadSettings, String> {
+    let mut conn = get_conn(&state)?;
+
+    if let Some(endpoint) = &settings.endpoint {
+        if !endpoint.is_empty() {
+            let url = url::Url::parse(endpoint).map_err(|e| format!("Invalid S3 endpoint: {}", e))?;
+            if url.scheme() != "http" && url.scheme() != "https" {
+                return Err("S3 endpoint must be an http or https URL".to_string());
+            }
+        }
+        conn.execute("UPDATE cloud_upload_settings SET endpoint = ?1 WHERE id = 1", [endpoint])
+            .map_err(|e| e.to_string())?;
+    }
+    if let Some(enabled) = settings.enabled {
+        conn.execute("UPDATE cloud_upload_settings SET enabled = ?1 WHERE id = 1", [enabled])
+            .map_err(|e| e.to_string())?;
+    }
+    if let Some(region) = &settings.region {
+        conn.execute("UPDATE cloud_upload_settings SET region = ?1 WHERE id = 1", [region])
+            .map_err(|e| e.to_string())?;
+    }
+    if let Some(bucket) = &settings.bucket {
+        conn.execute("UPDATE cloud_upload_settings SET bucket = ?1 WHERE id = 1", [bucket])
+            .map_err(|e| e.to_string())?;
+    }
+    if let Some(access_key) = &settings.accessKey {
+        conn.execute("UPDATE cloud_upload_settings SET access_key = ?1 WHERE id = 1", [access_key])
+            .map_err(|e| e.to_string())?;
+    }
+    if let Some(secret_key) = &settings.secretKey {
+        conn.execute("UPDATE cloud_upload_settings SET secret_key = ?1 WHERE id = 1", [secret_key])
+            .map_err(|e| e.to_string())?;
+    }
+    if let Some(prefix) = &settings.prefix {
+        // Keys are built as <prefix><camera_id>/<filename>
+        let prefix = prefix.trim_start_matches('/');
+        let prefix = if prefix.is_empty() || prefix.ends_with('/') { prefix.to_string() } else { format!("{}/", prefix) };
+        conn.execute("UPDATE cloud_upload_settings SET prefix = ?1 WHERE id = 1", [prefix])
+            .map_err(|e| e.to_string())?;
+    }
+    if let Some(path_style) = settings.pathStyle {
+        conn.execute("UPDATE cloud_upload_settings SET path_style = ?1 WHERE id = 1", [path_style])
+            .map_err(|e| e.to_string())?;
+    }
+    if let Some(include_thumbnails) = settings.includeThumbnails {
+        conn.execute("UPDATE cloud_upload_settings SET include_thumbnails = ?1 WHERE id = 1", [include_thumbnails])
+            .map_err(|e| e.to_string())?;
+    }
+    if let Some(days) = settings.retentionDays {
+        let changes = serde_json::Map::from_iter([(crate::settings::CLOUD_RETENTION_DAYS.to_string(), days.into())]);
+        update_app_settings_with_conn(&state, &mut conn, &changes)?;
+    }
+    if let Some(kbps) = settings.bandwidthLimitKbps {
+        if kbps < 0 {
+            return Err("Bandwidth limit must not be negative".to_string());
+        }
+        conn.execute("UPDATE cloud_upload_settings SET bandwidth_limit_kbps = ?1 WHERE id = 1", [(kbps > 0).then_some(kbps)])
+            .map_err(|e| e.to_string())?;
+    }
+    if let Some(max_retries) = settings.maxRetries {
+        if !(0..=20).contains(&max_retries) {
+            return Err("Max retries must be between 0 and 20".to_string());
+        }
+        conn.execute("UPDATE cloud_upload_settings SET max_retries = ?1 WHERE id = 1", [max_retries])
+            .map_err(|e| e.to_string())?;
+    }
+
+    crate::cloud_upload::load_settings(&conn)
+}
+
+#[tauri::command]
+pub async fn get_camera_upload_policies(state: State<'_, AppState>) -> Result<Vec<CameraUploadPolicy>, String> {
+    let conn = get_conn(&state)?;
+    let mut stmt = conn.prepare("SELECT camera_id, enabled FROM camera_upload_policies")
+        .map_err(|e| e.to_string())?;
+
+    let policies = stmt.query_map([], |row| {
+        Ok(CameraUploadPolicy { camera_id: row.get(0)?, enabled: row.get(1)? })
+    }).map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok(policies)
+}
+
+/// Set a camera's upload policy; `None` makes it follow the global setting again
+#[tauri::command]
+pub async fn set_camera_upload_policy(
+    state: State<'_, AppState>,
+    camera_id: i32,
+    enabled: Option<bool>,
+) -> Result<(), String> {
+    let conn = get_conn(&state)?;
+
+    match enabled {
+        Some(enabled) => {
+            conn.execute(
+                "INSERT INTO camera_upload_policies (camera_id, enabled) VALUES (?1, ?2)
+                 ON CONFLICT(camera_id) DO UPDATE SET enabled = excluded.enabled",
+                rusqlite::params![camera_id, enabled],
+            ).map_err(|e| e.to_string())?;
+        }
+        None => {
+            conn.execute("DELETE FROM camera_upload_policies WHERE camera_id = ?1", [camera_id])
+                .map_err(|e| e.to_string())?;
+        }
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_camera_recording_formats(state: State<'_, AppState>) -> Result<Vec<CameraRecordingFormat>, String> {
+    let conn = get_conn(&state)?;
+    let mut stmt = conn.prepare("SELECT camera_id, container FROM camera_recording_formats")
+        .map_err(|e| e.to_string())?;
+
+    let formats = stmt.query_map([], |row| {
+        Ok(CameraRecordingFormat { camera_id: row.get(0)?, container: row.get(1)? })
+    }).map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok(formats)
+}
+
+/// Choose the container a camera records to; `None` goes back to MP4. Takes effect the next
+/// time a recording starts.
+#[tauri::command]
+pub async fn set_camera_recording_format(
+    state: State<'_, AppState>,
+    camera_id: i32,
+    container: Option<String>,
+) -> Result<(), String> {
+    let conn = get_conn(&state)?;
+
+    match container {
+        Some(container) => {
+            if !crate::stream::RECORDING_CONTAINERS.contains(&container.as_str()) {
+                return Err(format!(
+                    "Unknown recording container '{}' (expected one of {})",
+                    container,
+                    crate::stream::RECORDING_CONTAINERS.join(", ")
+                ));
+            }
+            conn.execute(
+                "INSERT INTO camera_recording_formats (camera_id, container) VALUES (?1, ?2)
+                 ON CONFLICT(camera_id) DO UPDATE SET container = excluded.container",
+                rusqlite::params![camera_id, container],
+            ).map_err(|e| e.to_string())?;
+        }
+        None => {
+            conn.execute("DELETE FROM camera_recording_formats WHERE camera_id = ?1", [camera_id])
+                .map_err(|e| e.to_string())?;
+        }
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_camera_prebuffers(state: State<'_, AppState>) -> Result<Vec<CameraPrebuffer>, String> {
+    let conn = get_conn(&state)?;
+    crate::prebuffer::load_all(&conn)
+}
+
+/// Keep the last `seconds` of a camera's stream in a ring buffer, put in front of every
+/// recording that starts on it; `None` turns the buffer off. Network cameras only.
+#[tauri::command]
+pub async fn set_camera_prebuffer(
+    state: State<'_, AppState>,
+    camera_id: i32,
+    seconds: Option<i32>,
+) -> Result<(), String> {
+    let conn = get_conn(&state)?;
+
+    match seconds {
+        Some(seconds) => {
+            let (min, max) = crate::prebuffer::PREBUFFER_SECS_RANGE;
+            if !(min..=max).contains(&seconds) {
+                return Err(format!("Pre-recording buffer must be between {} and {} seconds", min, max));
+            }
+            let camera = crate::stream::load_camera(&state.db_path, camera_id)?;
+            if crate::stream::is_local_device(&camera.camera_type) {
+                return Err("UVC and Pi cameras can't be buffered while they stream".to_string());
+            }
+            conn.execute(
+                "INSERT INTO camera_prebuffers (camera_id, seconds) VALUES (?1, ?2)
+                 ON CONFLICT(camera_id) DO UPDATE SET seconds = excluded.seconds",
+                rusqlite::params![camera_id, seconds],
+            ).map_err(|e| e.to_string())?;
+            crate::prebuffer::start(&state.app_handle, camera_id);
+        }
+        None => {
+            conn.execute("DELETE FROM camera_prebuffers WHERE camera_id = ?1", [camera_id])
+                .map_err(|e| e.to_string())?;
+            crate::prebuffer::stop(&state, camera_id);
+        }
+    }
+
+    Ok(())
+}
+
+// ========== Storage Location Commands ==========
+
+/// Configured storage locations in failover order, with their current free space
+#[tauri::command]
+pub async fn get_storage_locations(state: State<'_, AppState>) -> Result<Vec<StorageLocation>, String> {
+    let conn = get_conn(&state)?;
+    Ok(crate::storage::with_status(crate::storage::list_locations(&conn)?))
+}
+
+/// Add a recording directory; it must already exist. Returns its id.
+#[tauri::command]
+pub async fn add_storage_location(state: State<'_, AppState>, location: NewStorageLocation) -> Result<i64, String> {
+    crate::storage::validate(&location)?;
+    let conn = get_conn(&state)?;
+    conn.execute(
+        "INSERT INTO storage_locations (name, path, priority, min_free_mb, enabled) VALUES (?1, ?2, ?3, ?4, ?5)",
+        rusqlite::params![location.name.trim(), location.path, location.priority, location.min_free_mb, location.enabled],
     ).map_err(|e| e.to_string())?;
 
-    let settings = stmt.query_row([], |row| {
-        Ok(EncoderSettings {
-            id: row.get(0)?,
-            encoderMode: row.get(1)?,
-            gpuEncoder: row.get(2)?,
-            cpuEncoder: row.get(3)?,
-            preset: row.get(4)?,
-            quality: row.get(5)?,
-        })
-    }).map_err(|e| e.to_string())?;
+    let id = conn.last_insert_rowid();
+    println!("[Storage] Added storage location {} at {}", id, location.path);
+    Ok(id)
+}
+
+/// Change a location; a new path must hold the files already recorded there
+#[tauri::command]
+pub async fn update_storage_location(state: State<'_, AppState>, id: i64, location: NewStorageLocation) -> Result<(), String> {
+    crate::storage::validate(&location)?;
+    let conn = get_conn(&state)?;
+    let affected = conn.execute(
+        "UPDATE storage_locations SET name = ?1, path = ?2, priority = ?3, min_free_mb = ?4, enabled = ?5 WHERE id = ?6",
+        rusqlite::params![location.name.trim(), location.path, location.priority, location.min_free_mb, location.enabled, id],
+    ).map_err(|e| e.to_string())?;
+
+    if affected == 0 {
+        return Err("Storage location not found".to_string());
+    }
+    Ok(())
+}
+
+/// Remove a location that holds no recordings; cameras assigned to it go back to the
+/// built-in recordings directory
+#[tauri::command]
+pub async fn delete_storage_location(state: State<'_, AppState>, id: i64) -> Result<(), String> {
+    let conn = get_conn(&state)?;
+    let recordings: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM recordings WHERE location_id = ?1",
+        [id],
+        |row| row.get(0),
+    ).map_err(|e| e.to_string())?;
+    if recordings > 0 {
+        return Err(format!("{} recording(s) are stored in this location; delete them first or disable it instead", recordings));
+    }
+
+    conn.execute("DELETE FROM storage_locations WHERE id = ?1", [id]).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_camera_storage(state: State<'_, AppState>) -> Result<Vec<CameraStorage>, String> {
+    let conn = get_conn(&state)?;
+    let mut stmt = conn.prepare("SELECT camera_id, location_id FROM camera_storage")
+        .map_err(|e| e.to_string())?;
 
-    Ok(settings)
+    let assignments = stmt.query_map([], |row| {
+        Ok(CameraStorage { camera_id: row.get(0)?, location_id: row.get(1)? })
+    }).map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok(assignments)
 }
 
+/// Choose the location a camera records to first; `None` goes back to the built-in
+/// recordings directory. Takes effect the next time a recording starts.
 #[tauri::command]
-pub async fn update_encoder_settings(
+pub async fn set_camera_storage(
     state: State<'_, AppState>,
-    settings: UpdateEncoderSettings,
-) -> Result<EncoderSettings, String> {
+    camera_id: i32,
+    location_id: Option<i64>,
+) -> Result<(), String> {
     let conn = get_conn(&state)?;
 
-    // Use separate UPDATE statements for each field
-    if let Some(mode) = &settings.encoderMode {
-        conn.execute("UPDATE encoder_settings SET encoder_mode = ?1 WHERE id = 1", [mode])
-            .map_err(|e| e.to_string())?;
-    }
-    if let Some(gpu_enc) = &settings.gpuEncoder {
-        conn.execute("UPDATE encoder_settings SET gpu_encoder = ?1 WHERE id = 1", [gpu_enc])
-            .map_err(|e| e.to_string())?;
-    }
-    if let Some(cpu_enc) = &settings.cpuEncoder {
-        conn.execute("UPDATE encoder_settings SET cpu_encoder = ?1 WHERE id = 1", [cpu_enc])
-            .map_err(|e| e.to_string())?;
-    }
-    if let Some(p) = &settings.preset {
-        conn.execute("UPDATE encoder_settings SET preset = ?1 WHERE id = 1", [p])
-            .map_err(|e| e.to_string())?;
-    }
-    if let Some(q) = settings.quality {
-        conn.execute("UPDATE encoder_settings SET quality = ?1 WHERE id = 1", [q])
-            .map_err(|e| e.to_string())?;
-    }
-
-    if settings.encoderMode.is_none()
-        && settings.gpuEncoder.is_none()
-        && settings.cpuEncoder.is_none()
-        && settings.preset.is_none()
-        && settings.quality.is_none() {
-        return Err("No fields to update".to_string());
+    match location_id {
+        Some(location_id) => {
+            conn.execute(
+                "INSERT INTO camera_storage (camera_id, location_id) VALUES (?1, ?2)
+                 ON CONFLICT(camera_id) DO UPDATE SET location_id = excluded.location_id",
+                rusqlite::params![camera_id, location_id],
+            ).map_err(|e| e.to_string())?;
+        }
+        None => {
+            conn.execute("DELETE FROM camera_storage WHERE camera_id = ?1", [camera_id])
+                .map_err(|e| e.to_string())?;
+        }
     }
 
-    // Drop connection before await
-    drop(conn);
-
-    // Return updated settings
-    get_encoder_settings(state).await
+    Ok(())
 }
 
 // ========== Recording Schedule Commands ==========
@@ -518,8 +3392,8 @@ fn validate_cron_expression(expr: &str) -> Result<String, String> {
         expr.to_string()
     };
 
-    // Validate using the same parser as the scheduler (tokio-cron-scheduler with Tokyo timezone)
-    Job::new_async_tz(normalized_expr.as_str(), Tokyo, |_uuid, _lock| {
+    // Validate using the same parser as the scheduler (tokio-cron-scheduler in the configured time zone)
+    Job::new_async_tz(normalized_expr.as_str(), crate::settings::timezone(), |_uuid, _lock| {
         Box::pin(async move {
             // Validation only - this job is never executed
         })
@@ -528,73 +3402,20 @@ fn validate_cron_expression(expr: &str) -> Result<String, String> {
     .map_err(|e| format!("Invalid cron expression: {}", e))
 }
 
-// Calculate next run time for a cron expression (returns None if disabled or no future runs)
-fn calculate_next_run(cron_expr: &str, is_enabled: bool) -> Option<String> {
-    use croner::Cron;
-
-    if !is_enabled {
-        return None;
-    }
-
-    // Parse the cron expression using croner
-    // cron_expr is in 6-field format: "second minute hour day month dow"
-    let cron = Cron::new(cron_expr)
-        .with_seconds_optional()
-        .parse()
-        .ok()?;
-
-    // Get current time in JST
-    let now = Utc::now().with_timezone(&Tokyo);
-
-    // Find next occurrence
-    cron.find_next_occurrence(&now, false)
-        .ok()
-        .map(|next| {
-            // Convert to JST and format as ISO 8601
-            let jst_time = next.with_timezone(&Tokyo);
-            jst_time.to_rfc3339()
-        })
-}
-
 #[tauri::command]
 pub async fn get_recording_schedules(
     state: State<'_, AppState>
 ) -> Result<Vec<RecordingSchedule>, String> {
-    let conn = get_conn(&state)?;
-
-    let mut stmt = conn.prepare(
-        "SELECT s.id, s.camera_id, s.name, s.cron_expression, s.duration_minutes, s.fps, s.is_enabled,
-                s.created_at, s.updated_at, c.name as camera_name
-         FROM recording_schedules s
-         LEFT JOIN cameras c ON s.camera_id = c.id
-         ORDER BY s.created_at DESC"
-    ).map_err(|e| e.to_string())?;
-
-    let schedules_iter = stmt.query_map([], |row| {
-        let cron_expression: String = row.get(3)?;
-        let is_enabled: bool = row.get(6)?;
-
-        Ok(RecordingSchedule {
-            id: row.get(0)?,
-            camera_id: row.get(1)?,
-            name: row.get(2)?,
-            cron_expression: cron_expression.clone(),
-            duration_minutes: row.get(4)?,
-            fps: row.get(5)?,
-            is_enabled,
-            created_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(7)?).unwrap_or(Utc::now().into()).with_timezone(&Utc),
-            updated_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(8)?).unwrap_or(Utc::now().into()).with_timezone(&Utc),
-            camera_name: row.get(9)?,
-            next_run: calculate_next_run(&cron_expression, is_enabled),
-        })
-    }).map_err(|e| e.to_string())?;
+    ScheduleRepo::list(&get_conn(&state)?)
+}
 
-    let mut schedules = Vec::new();
-    for schedule in schedules_iter {
-        schedules.push(schedule.map_err(|e| e.to_string())?);
+// A recording schedule runs on either one camera or a camera group
+fn check_schedule_target(conn: &Connection, camera_id: Option<i32>, group_id: Option<i64>) -> Result<(), String> {
+    match (camera_id, group_id) {
+        (Some(_), None) => Ok(()),
+        (None, Some(group_id)) => CameraGroupRepo::get(conn, group_id).map(|_| ()),
+        _ => Err("A schedule needs either a camera or a camera group".to_string()),
     }
-
-    Ok(schedules)
 }
 
 #[tauri::command]
@@ -606,12 +3427,14 @@ pub async fn add_recording_schedule(
     let normalized_cron = validate_cron_expression(&schedule.cron_expression)?;
 
     let conn = get_conn(&state)?;
+    check_schedule_target(&conn, schedule.camera_id, schedule.group_id)?;
 
     conn.execute(
-        "INSERT INTO recording_schedules (camera_id, name, cron_expression, duration_minutes, fps, is_enabled)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        "INSERT INTO recording_schedules (camera_id, group_id, name, cron_expression, duration_minutes, fps, is_enabled)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
         (
             &schedule.camera_id,
+            &schedule.group_id,
             &schedule.name,
             &normalized_cron,
             &schedule.duration_minutes,
@@ -623,55 +3446,15 @@ pub async fn add_recording_schedule(
     let id = conn.last_insert_rowid() as i32;
 
     // Get the created schedule
-    let created_schedule = {
-        let mut stmt = conn.prepare(
-            "SELECT s.id, s.camera_id, s.name, s.cron_expression, s.duration_minutes, s.fps, s.is_enabled,
-                    s.created_at, s.updated_at, c.name as camera_name
-             FROM recording_schedules s
-             LEFT JOIN cameras c ON s.camera_id = c.id
-             WHERE s.id = ?1"
-        ).map_err(|e| e.to_string())?;
-
-        stmt.query_row([id], |row| {
-            let cron_expression: String = row.get(3)?;
-            let is_enabled: bool = row.get(6)?;
-
-            Ok(RecordingSchedule {
-                id: row.get(0)?,
-                camera_id: row.get(1)?,
-                name: row.get(2)?,
-                cron_expression: cron_expression.clone(),
-                duration_minutes: row.get(4)?,
-                fps: row.get(5)?,
-                is_enabled,
-                created_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(7)?).unwrap_or(Utc::now().into()).with_timezone(&Utc),
-                updated_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(8)?).unwrap_or(Utc::now().into()).with_timezone(&Utc),
-                camera_name: row.get(9)?,
-                next_run: calculate_next_run(&cron_expression, is_enabled),
-            })
-        }).map_err(|e| e.to_string())?
-    };
+    let created_schedule = ScheduleRepo::get(&conn, id)?;
 
     // Drop connection before async operations
     drop(conn);
 
     // Add to scheduler if enabled
     if created_schedule.is_enabled {
-        let state_arc = Arc::new(AppState {
-            db_path: state.db_path.clone(),
-            server_port: state.server_port,
-            stream_dir: state.stream_dir.clone(),
-            recording_dir: state.recording_dir.clone(),
-            processes: state.processes.clone(),
-            recording_processes: state.recording_processes.clone(),
-            scheduler: state.scheduler.clone(),
-            active_scheduled_recordings: state.active_scheduled_recordings.clone(),
-            app_handle: state.app_handle.clone(),
-            plugin_manager: state.plugin_manager.clone(),
-        });
-
         let scheduler = state.scheduler.lock().await;
-        scheduler.add_schedule(created_schedule.clone(), state_arc).await?;
+        scheduler.add_schedule(created_schedule.clone(), state.inner().clone()).await?;
     }
 
     println!("[Schedule] Created schedule '{}' (ID: {})", created_schedule.name, created_schedule.id);
@@ -695,12 +3478,19 @@ pub async fn update_recording_schedule(
     let conn = get_conn(&state)?;
 
     // Check if schedule exists and get current state
-    let old_enabled: bool = conn.query_row(
-        "SELECT is_enabled FROM recording_schedules WHERE id = ?1",
+    let (old_enabled, schedule_type, action): (bool, String, String) = conn.query_row(
+        "SELECT is_enabled, schedule_type, action FROM recording_schedules WHERE id = ?1",
         [id],
-        |row| row.get(0)
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?))
     ).map_err(|e| format!("Schedule not found: {}", e))?;
 
+    if schedule_type == "weekly" && normalized_cron.is_none() && updates.duration_minutes.is_some() {
+        return Err("Weekly schedules take their durations from their time blocks".to_string());
+    }
+    if action == "snapshot" && (updates.duration_minutes.is_some() || updates.fps.is_some()) {
+        return Err("Snapshot schedules have no duration or frame rate".to_string());
+    }
+
     // Build dynamic UPDATE query
     {
         let mut set_clauses = Vec::new();
@@ -711,8 +3501,11 @@ pub async fn update_recording_schedule(
             params.push(Box::new(name.clone()));
         }
         if let Some(ref cron_expr) = normalized_cron {
+            // Setting a cron expression turns a weekly schedule back into a cron schedule
             set_clauses.push("cron_expression = ?");
             params.push(Box::new(cron_expr.clone()));
+            set_clauses.push("schedule_type = 'cron'");
+            set_clauses.push("weekly_blocks = NULL");
         }
         if let Some(duration) = updates.duration_minutes {
             set_clauses.push("duration_minutes = ?");
@@ -748,53 +3541,13 @@ pub async fn update_recording_schedule(
     } // params is dropped here before any .await
 
     // Get updated schedule
-    let updated_schedule = {
-        let mut stmt = conn.prepare(
-            "SELECT s.id, s.camera_id, s.name, s.cron_expression, s.duration_minutes, s.fps, s.is_enabled,
-                    s.created_at, s.updated_at, c.name as camera_name
-             FROM recording_schedules s
-             LEFT JOIN cameras c ON s.camera_id = c.id
-             WHERE s.id = ?1"
-        ).map_err(|e| e.to_string())?;
-
-        stmt.query_row([id], |row| {
-            let cron_expression: String = row.get(3)?;
-            let is_enabled: bool = row.get(6)?;
-
-            Ok(RecordingSchedule {
-                id: row.get(0)?,
-                camera_id: row.get(1)?,
-                name: row.get(2)?,
-                cron_expression: cron_expression.clone(),
-                duration_minutes: row.get(4)?,
-                fps: row.get(5)?,
-                is_enabled,
-                created_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(7)?).unwrap_or(Utc::now().into()).with_timezone(&Utc),
-                updated_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(8)?).unwrap_or(Utc::now().into()).with_timezone(&Utc),
-                camera_name: row.get(9)?,
-                next_run: calculate_next_run(&cron_expression, is_enabled),
-            })
-        }).map_err(|e| e.to_string())?
-    };
+    let updated_schedule = ScheduleRepo::get(&conn, id)?;
 
     // Drop connection before async operations
     drop(conn);
 
     // Handle scheduler updates
     if updates.is_enabled.is_some() || updates.cron_expression.is_some() || updates.duration_minutes.is_some() {
-        let state_arc = Arc::new(AppState {
-            db_path: state.db_path.clone(),
-            server_port: state.server_port,
-            stream_dir: state.stream_dir.clone(),
-            recording_dir: state.recording_dir.clone(),
-            processes: state.processes.clone(),
-            recording_processes: state.recording_processes.clone(),
-            scheduler: state.scheduler.clone(),
-            active_scheduled_recordings: state.active_scheduled_recordings.clone(),
-            app_handle: state.app_handle.clone(),
-            plugin_manager: state.plugin_manager.clone(),
-        });
-
         let scheduler = state.scheduler.lock().await;
 
         // Remove old job if exists
@@ -804,7 +3557,7 @@ pub async fn update_recording_schedule(
 
         // Add new job if enabled
         if updated_schedule.is_enabled {
-            scheduler.add_schedule(updated_schedule.clone(), state_arc).await?;
+            scheduler.add_schedule(updated_schedule.clone(), state.inner().clone()).await?;
         }
     }
 
@@ -813,6 +3566,255 @@ pub async fn update_recording_schedule(
     Ok(updated_schedule)
 }
 
+/// Create a schedule from day-of-week time blocks instead of a cron expression
+#[tauri::command]
+pub async fn add_weekly_schedule(
+    state: State<'_, AppState>,
+    schedule: NewWeeklySchedule
+) -> Result<RecordingSchedule, String> {
+    let triggers = crate::weekly_schedule::compile(&schedule.blocks)?;
+    for (cron_expr, _) in &triggers {
+        validate_cron_expression(cron_expr)?;
+    }
+    let (first_cron, first_duration) = triggers[0].clone();
+    let blocks_json = serde_json::to_string(&schedule.blocks).map_err(|e| e.to_string())?;
+
+    let conn = get_conn(&state)?;
+    check_schedule_target(&conn, schedule.camera_id, schedule.group_id)?;
+
+    conn.execute(
+        "INSERT INTO recording_schedules (camera_id, group_id, name, cron_expression, duration_minutes, fps, is_enabled, schedule_type, weekly_blocks)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, 'weekly', ?8)",
+        rusqlite::params![
+            schedule.camera_id,
+            schedule.group_id,
+            schedule.name,
+            first_cron,
+            first_duration,
+            schedule.fps,
+            schedule.is_enabled,
+            blocks_json,
+        ],
+    ).map_err(|e| e.to_string())?;
+
+    let id = conn.last_insert_rowid() as i32;
+    drop(conn);
+
+    // Reuse the regular update path to load the row and register the jobs
+    let created_schedule = update_recording_schedule(
+        state,
+        id,
+        UpdateRecordingSchedule {
+            name: None,
+            cron_expression: None,
+            duration_minutes: None,
+            fps: None,
+            is_enabled: Some(schedule.is_enabled),
+        }
+    ).await?;
+
+    println!("[Schedule] Created weekly schedule '{}' (ID: {})", created_schedule.name, created_schedule.id);
+
+    Ok(created_schedule)
+}
+
+/// Update a weekly schedule; setting blocks also converts a cron schedule into a weekly one
+#[tauri::command]
+pub async fn update_weekly_schedule(
+    state: State<'_, AppState>,
+    id: i32,
+    updates: UpdateWeeklySchedule
+) -> Result<RecordingSchedule, String> {
+    let is_enabled = {
+        let conn = get_conn(&state)?;
+        let current_enabled: bool = conn.query_row(
+            "SELECT is_enabled FROM recording_schedules WHERE id = ?1",
+            [id],
+            |row| row.get(0)
+        ).map_err(|e| format!("Schedule not found: {}", e))?;
+
+        if let Some(blocks) = &updates.blocks {
+            let triggers = crate::weekly_schedule::compile(blocks)?;
+            for (cron_expr, _) in &triggers {
+                validate_cron_expression(cron_expr)?;
+            }
+            let (first_cron, first_duration) = triggers[0].clone();
+            let blocks_json = serde_json::to_string(blocks).map_err(|e| e.to_string())?;
+
+            conn.execute(
+                "UPDATE recording_schedules
+                 SET schedule_type = 'weekly', weekly_blocks = ?1, cron_expression = ?2, duration_minutes = ?3
+                 WHERE id = ?4",
+                rusqlite::params![blocks_json, first_cron, first_duration, id],
+            ).map_err(|e| e.to_string())?;
+        }
+
+        updates.is_enabled.unwrap_or(current_enabled)
+    };
+
+    // Passing is_enabled makes the update path re-register the scheduler jobs
+    update_recording_schedule(
+        state,
+        id,
+        UpdateRecordingSchedule {
+            name: updates.name,
+            cron_expression: None,
+            duration_minutes: None,
+            fps: updates.fps,
+            is_enabled: Some(is_enabled),
+        }
+    ).await
+}
+
+// Cron expression for a snapshot schedule, given either a cron expression or an interval
+fn snapshot_cron(cron_expression: Option<&str>, interval_minutes: Option<i32>) -> Result<Option<String>, String> {
+    match (cron_expression, interval_minutes) {
+        (Some(_), Some(_)) => Err("Give either a cron expression or an interval, not both".to_string()),
+        (Some(expr), None) => validate_cron_expression(expr).map(Some),
+        (None, Some(minutes)) => crate::snapshots::interval_to_cron(minutes).map(Some),
+        (None, None) => Ok(None),
+    }
+}
+
+/// Create a schedule that captures a still image on each trigger instead of recording
+#[tauri::command]
+pub async fn add_snapshot_schedule(
+    state: State<'_, AppState>,
+    schedule: NewSnapshotSchedule
+) -> Result<RecordingSchedule, String> {
+    let cron_expr = snapshot_cron(schedule.cron_expression.as_deref(), schedule.interval_minutes)?
+        .ok_or_else(|| "A snapshot schedule needs a cron expression or an interval".to_string())?;
+    if schedule.retention_days.is_some_and(|days| days < 0) {
+        return Err("Retention days must not be negative".to_string());
+    }
+
+    let conn = get_conn(&state)?;
+
+    conn.execute(
+        "INSERT INTO recording_schedules (camera_id, name, cron_expression, duration_minutes, is_enabled, action, retention_days)
+         VALUES (?1, ?2, ?3, 0, ?4, 'snapshot', ?5)",
+        rusqlite::params![
+            schedule.camera_id,
+            schedule.name,
+            cron_expr,
+            schedule.is_enabled,
+            schedule.retention_days.filter(|days| *days > 0),
+        ],
+    ).map_err(|e| e.to_string())?;
+
+    let id = conn.last_insert_rowid() as i32;
+    drop(conn);
+
+    // Reuse the regular update path to load the row and register the job
+    let created_schedule = update_recording_schedule(
+        state,
+        id,
+        UpdateRecordingSchedule {
+            name: None,
+            cron_expression: None,
+            duration_minutes: None,
+            fps: None,
+            is_enabled: Some(schedule.is_enabled),
+        }
+    ).await?;
+
+    println!("[Schedule] Created snapshot schedule '{}' (ID: {})", created_schedule.name, created_schedule.id);
+
+    Ok(created_schedule)
+}
+
+#[tauri::command]
+pub async fn update_snapshot_schedule(
+    state: State<'_, AppState>,
+    id: i32,
+    updates: UpdateSnapshotSchedule
+) -> Result<RecordingSchedule, String> {
+    let is_enabled = {
+        let conn = get_conn(&state)?;
+        let (current_enabled, action): (bool, String) = conn.query_row(
+            "SELECT is_enabled, action FROM recording_schedules WHERE id = ?1",
+            [id],
+            |row| Ok((row.get(0)?, row.get(1)?))
+        ).map_err(|e| format!("Schedule not found: {}", e))?;
+
+        if action != "snapshot" {
+            return Err("Not a snapshot schedule".to_string());
+        }
+
+        if let Some(cron_expr) = snapshot_cron(updates.cron_expression.as_deref(), updates.interval_minutes)? {
+            conn.execute(
+                "UPDATE recording_schedules SET cron_expression = ?1 WHERE id = ?2",
+                rusqlite::params![cron_expr, id],
+            ).map_err(|e| e.to_string())?;
+        }
+        if let Some(days) = updates.retention_days {
+            if days < 0 {
+                return Err("Retention days must not be negative".to_string());
+            }
+            conn.execute(
+                "UPDATE recording_schedules SET retention_days = ?1 WHERE id = ?2",
+                rusqlite::params![(days > 0).then_some(days), id],
+            ).map_err(|e| e.to_string())?;
+        }
+
+        updates.is_enabled.unwrap_or(current_enabled)
+    };
+
+    // Passing is_enabled makes the update path re-register the scheduler job
+    update_recording_schedule(
+        state,
+        id,
+        UpdateRecordingSchedule {
+            name: updates.name,
+            cron_expression: None,
+            duration_minutes: None,
+            fps: None,
+            is_enabled: Some(is_enabled),
+        }
+    ).await
+}
+
+/// Capture a still image now (not tied to a schedule, so never pruned by retention)
+#[tauri::command]
+pub async fn capture_snapshot(
+    state: State<'_, AppState>,
+    camera_id: i32
+) -> Result<Snapshot, String> {
+    crate::snapshots::capture_snapshot(&state.db_path, &state.onvif_http, &state.recording_dir, camera_id, None).await
+}
+
+/// Save the frame the camera currently shows, preferably from its running live stream, to
+/// `dest` (.png/.jpg) or to the snapshots library as `format` ("jpeg" or "png")
+#[tauri::command]
+pub async fn capture_frame(
+    state: State<'_, AppState>,
+    camera_id: i32,
+    dest: Option<String>,
+    format: Option<String>,
+) -> Result<CapturedFrame, String> {
+    let dest = dest.filter(|dest| !dest.trim().is_empty()).map(std::path::PathBuf::from);
+    crate::snapshots::capture_frame(&state, camera_id, dest.as_deref(), format.as_deref()).await
+}
+
+#[tauri::command]
+pub async fn get_snapshots(
+    state: State<'_, AppState>,
+    camera_id: Option<i32>,
+    schedule_id: Option<i32>,
+    limit: Option<u32>,
+) -> Result<Vec<Snapshot>, String> {
+    let conn = get_conn(&state)?;
+    crate::snapshots::list_snapshots(&conn, camera_id, schedule_id, limit.unwrap_or(500))
+}
+
+#[tauri::command]
+pub async fn delete_snapshot(
+    state: State<'_, AppState>,
+    id: i64
+) -> Result<(), String> {
+    crate::snapshots::delete_snapshot(&state.db_path, &state.recording_dir, id)
+}
+
 #[tauri::command]
 pub async fn delete_recording_schedule(
     state: State<'_, AppState>,
@@ -856,6 +3858,48 @@ pub async fn toggle_schedule(
     ).await
 }
 
+/// Per-camera streaming/recording state (uptime, encoder, file size, last error)
+#[tauri::command]
+pub async fn get_pipeline_status(state: State<'_, AppState>) -> Result<Vec<PipelineStatus>, String> {
+    crate::pipeline::pipeline_status(&state)
+}
+
+/// Kill the stream and recording FFmpeg of one camera, or of all cameras, and clean up their
+/// stream directories and unfinished recordings; for pipelines the stop commands can't stop
+#[tauri::command]
+pub async fn force_cleanup(state: State<'_, AppState>, camera_id: Option<i32>) -> Result<Vec<PipelineCleanup>, String> {
+    let state = state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || crate::stream_cleanup::force_cleanup(&state, camera_id))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Zip logs, settings (secrets masked), database, GPU, FFmpeg and camera details into
+/// `dest_path` for attaching to a bug report
+#[tauri::command]
+pub async fn export_diagnostics(state: State<'_, AppState>, dest_path: String) -> Result<DiagnosticsBundle, String> {
+    let gpu = detect_gpu_capabilities().await;
+    let state = state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || crate::diagnostics::export(&state, gpu, std::path::Path::new(&dest_path)))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Liveness check for the UI: answers with the same health summary as the backend-heartbeat event
+#[tauri::command]
+pub async fn ping(state: State<'_, AppState>) -> Result<BackendHeartbeat, String> {
+    let state = state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || crate::heartbeat::heartbeat(&state))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// CPU, memory and GPU load now, with the share of each pipeline FFmpeg
+#[tauri::command]
+pub async fn get_resource_usage(state: State<'_, AppState>) -> Result<ResourceUsage, String> {
+    crate::resource_monitor::sample(&state).await
+}
+
 #[tauri::command]
 pub async fn get_recording_cameras(
     state: State<'_, AppState>
@@ -867,3 +3911,136 @@ pub async fn get_recording_cameras(
     let camera_ids: Vec<i32> = processes.keys().copied().collect();
     Ok(camera_ids)
 }
+
+#[tauri::command]
+pub async fn get_scheduler_settings(state: State<'_, AppState>) -> Result<SchedulerSettings, String> {
+    let conn = get_conn(&state)?;
+    crate::scheduler::load_settings(&conn)
+}
+
+#[tauri::command]
+pub async fn update_scheduler_settings(
+    state: State<'_, AppState>,
+    settings: UpdateSchedulerSettings,
+) -> Result<SchedulerSettings, String> {
+    let conn = get_conn(&state)?;
+
+    if let Some(max) = settings.maxConcurrentRecordings {
+        if max < 0 {
+            return Err("Max concurrent recordings must not be negative".to_string());
+        }
+        conn.execute(
+            "UPDATE scheduler_settings SET max_concurrent_recordings = ?1 WHERE id = 1",
+            [(max > 0).then_some(max)],
+        ).map_err(|e| e.to_string())?;
+    }
+    if let Some(minutes) = settings.maxDeferMinutes {
+        if minutes < 0 {
+            return Err("Max defer minutes must not be negative".to_string());
+        }
+        conn.execute("UPDATE scheduler_settings SET max_defer_minutes = ?1 WHERE id = 1", [minutes])
+            .map_err(|e| e.to_string())?;
+    }
+    if let Some(seconds) = settings.staggerSeconds {
+        if !(0..=300).contains(&seconds) {
+            return Err("Stagger seconds must be between 0 and 300".to_string());
+        }
+        conn.execute("UPDATE scheduler_settings SET stagger_seconds = ?1 WHERE id = 1", [seconds])
+            .map_err(|e| e.to_string())?;
+    }
+    if let Some(enabled) = settings.schedulesEnabled {
+        crate::scheduler::set_schedules_enabled(&conn, enabled)?;
+    }
+
+    crate::scheduler::load_settings(&conn)
+}
+
+/// Pause every schedule, e.g. during maintenance; kept across restarts until resumed
+#[tauri::command]
+pub async fn pause_schedules(state: State<'_, AppState>) -> Result<SchedulerSettings, String> {
+    let conn = get_conn(&state)?;
+    crate::scheduler::set_schedules_enabled(&conn, false)?;
+    crate::scheduler::load_settings(&conn)
+}
+
+#[tauri::command]
+pub async fn resume_schedules(state: State<'_, AppState>) -> Result<SchedulerSettings, String> {
+    let conn = get_conn(&state)?;
+    crate::scheduler::set_schedules_enabled(&conn, true)?;
+    crate::scheduler::load_settings(&conn)
+}
+
+/// Recent executions, newest first, optionally for a single schedule
+#[tauri::command]
+pub async fn get_schedule_history(
+    state: State<'_, AppState>,
+    schedule_id: Option<i32>,
+    limit: Option<u32>,
+) -> Result<Vec<ScheduleRun>, String> {
+    let conn = get_conn(&state)?;
+    let parse_time = |t: Option<String>| {
+        t.and_then(|t| DateTime::parse_from_rfc3339(&t).ok()).map(|t| t.with_timezone(&Utc))
+    };
+
+    let mut stmt = conn.prepare(
+        "SELECT id, schedule_id, camera_id, scheduled_at, started_at, finished_at, status, detail, planned_stop_at
+         FROM schedule_runs
+         WHERE ?1 IS NULL OR schedule_id = ?1
+         ORDER BY scheduled_at DESC
+         LIMIT ?2"
+    ).map_err(|e| e.to_string())?;
+
+    let runs = stmt.query_map(rusqlite::params![schedule_id, limit.unwrap_or(100)], |row| {
+        Ok(ScheduleRun {
+            id: row.get(0)?,
+            schedule_id: row.get(1)?,
+            camera_id: row.get(2)?,
+            scheduled_at: parse_time(row.get(3)?).unwrap_or_else(Utc::now),
+            started_at: parse_time(row.get(4)?),
+            finished_at: parse_time(row.get(5)?),
+            planned_stop_at: parse_time(row.get(8)?),
+            status: row.get(6)?,
+            detail: row.get(7)?,
+        })
+    }).map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok(runs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::test_db::TestDb;
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn deleted_recordings_are_archived_first() {
+        let db = TestDb::new("archive-before-delete");
+        let (recording_dir, archive_dir) = (db.dir().join("recordings"), db.dir().join("archive"));
+        std::fs::create_dir_all(&recording_dir).unwrap();
+        let conn = &db.conn;
+        let camera_id = db.add_camera("Gate");
+        conn.execute(
+            "INSERT INTO recordings (camera_id, filename, start_time, end_time, is_finished)
+             VALUES (?1, 'rec_1.mp4', '2024-05-01T00:00:00Z', '2024-05-01T00:10:00Z', 1)",
+            [camera_id],
+        ).unwrap();
+        let recording_id = conn.last_insert_rowid() as i32;
+        std::fs::write(recording_dir.join("rec_1.mp4"), b"recording").unwrap();
+        conn.execute(
+            "UPDATE archive_settings SET target = ?1, auto_archive = 1 WHERE id = 1",
+            [archive_dir.to_string_lossy()],
+        ).unwrap();
+
+        let archive = crate::archive::ArchiveQueue::start(
+            db.db_path(),
+            recording_dir.clone(),
+            EventBus::detached(),
+        ).unwrap();
+        delete_recording_with_conn(conn, &recording_dir, &archive, recording_id, false).unwrap();
+
+        assert!(!recording_dir.join("rec_1.mp4").exists());
+        assert_eq!(std::fs::read(archive_dir.join("rec_1.mp4")).unwrap(), b"recording");
+    }
+}
@@ -3,6 +3,14 @@ use std::path::Path;
 use std::fs;
 use crate::gpu_detector;
 
+/// Open the database with foreign key enforcement on; SQLite keeps it off unless each
+/// connection asks for it, which leaves every ON DELETE CASCADE inert
+pub fn open<P: AsRef<Path>>(path: P) -> Result<Connection> {
+    let conn = Connection::open(path)?;
+    conn.pragma_update(None, "foreign_keys", true)?;
+    Ok(conn)
+}
+
 pub fn init_db<P: AsRef<Path>>(path: P) -> Result<()> {
     if let Some(parent) = path.as_ref().parent() {
         fs::create_dir_all(parent).ok();
@@ -24,6 +32,11 @@ pub fn init_db<P: AsRef<Path>>(path: P) -> Result<()> {
             device_path TEXT,
             device_id TEXT,
             device_index INTEGER,
+            tls_verify BOOLEAN NOT NULL DEFAULT 0,
+            rtsp_transport TEXT NOT NULL DEFAULT 'tcp',
+            stream_timeout_secs INTEGER,
+            tls_cert_pem TEXT,
+            archived_at TEXT,
             created_at TEXT DEFAULT CURRENT_TIMESTAMP,
             updated_at TEXT DEFAULT CURRENT_TIMESTAMP
         )",
@@ -39,6 +52,21 @@ pub fn init_db<P: AsRef<Path>>(path: P) -> Result<()> {
             start_time TEXT NOT NULL,
             end_time TEXT,
             is_finished BOOLEAN DEFAULT 0,
+            trigger TEXT NOT NULL DEFAULT 'manual',
+            tags TEXT NOT NULL DEFAULT '[]',
+            notes TEXT,
+            is_protected BOOLEAN NOT NULL DEFAULT 0,
+            archive_location TEXT,
+            archived_at TEXT,
+            archive_checksum TEXT,
+            upload_key TEXT,
+            uploaded_at TEXT,
+            upload_thumbnail_key TEXT,
+            file_size INTEGER,
+            integrity TEXT,
+            integrity_detail TEXT,
+            verified_at TEXT,
+            session_id INTEGER,
             FOREIGN KEY(camera_id) REFERENCES cameras(id) ON DELETE CASCADE
         )",
         [],
@@ -63,23 +91,703 @@ pub fn init_db<P: AsRef<Path>>(path: P) -> Result<()> {
         [],
     )?;
 
-    // Create recording schedules table
+    // Named sets of cameras a schedule can target as a whole
     conn.execute(
-        "CREATE TABLE IF NOT EXISTS recording_schedules (
+        "CREATE TABLE IF NOT EXISTS camera_groups (
             id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS camera_group_members (
+            group_id INTEGER NOT NULL,
             camera_id INTEGER NOT NULL,
+            PRIMARY KEY(group_id, camera_id),
+            FOREIGN KEY(group_id) REFERENCES camera_groups(id) ON DELETE CASCADE,
+            FOREIGN KEY(camera_id) REFERENCES cameras(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    // Create recording schedules table
+    conn.execute(&format!("CREATE TABLE IF NOT EXISTS recording_schedules ({})", RECORDING_SCHEDULES_COLUMNS), [])?;
+
+    // Still images captured by snapshot schedules (schedule_id NULL for manual captures).
+    // Files live under recordings/snapshots/<camera_id>/
+    conn.execute(&format!("CREATE TABLE IF NOT EXISTS snapshots ({})", SNAPSHOTS_COLUMNS), [])?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_snapshots_camera_captured ON snapshots(camera_id, captured_at)",
+        [],
+    )?;
+
+    // Scheduler limits (single row); NULL max_concurrent_recordings means unlimited
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS scheduler_settings (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            max_concurrent_recordings INTEGER,
+            max_defer_minutes INTEGER NOT NULL DEFAULT 10,
+            stagger_seconds INTEGER NOT NULL DEFAULT 5
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "INSERT OR IGNORE INTO scheduler_settings (id) VALUES (1)",
+        [],
+    )?;
+
+    // One row per schedule execution
+    // status: pending, queued, recording, completed, deferred, failed, interrupted
+    // Runs still pending/queued/recording at startup were cut short by a restart
+    conn.execute(&format!("CREATE TABLE IF NOT EXISTS schedule_runs ({})", SCHEDULE_RUNS_COLUMNS), [])?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_schedule_runs_schedule ON schedule_runs(schedule_id, scheduled_at)",
+        [],
+    )?;
+
+    // Archive target settings (single row, like encoder_settings)
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS archive_settings (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            target_type TEXT NOT NULL DEFAULT 'local',
+            target TEXT,
+            webdav_user TEXT,
+            webdav_pass TEXT,
+            auto_archive BOOLEAN NOT NULL DEFAULT 0
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "INSERT OR IGNORE INTO archive_settings (id, target_type) VALUES (1, 'local')",
+        [],
+    )?;
+
+    // S3-compatible upload settings (single row)
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS cloud_upload_settings (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            enabled BOOLEAN NOT NULL DEFAULT 0,
+            endpoint TEXT,
+            region TEXT NOT NULL DEFAULT 'us-east-1',
+            bucket TEXT,
+            access_key TEXT,
+            secret_key TEXT,
+            prefix TEXT NOT NULL DEFAULT 'recordings/',
+            path_style BOOLEAN NOT NULL DEFAULT 1,
+            include_thumbnails BOOLEAN NOT NULL DEFAULT 1,
+            retention_days INTEGER,
+            bandwidth_limit_kbps INTEGER,
+            max_retries INTEGER NOT NULL DEFAULT 5
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "INSERT OR IGNORE INTO cloud_upload_settings (id) VALUES (1)",
+        [],
+    )?;
+
+    // Cameras without a row here follow cloud_upload_settings.enabled
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS camera_upload_policies (
+            camera_id INTEGER PRIMARY KEY,
+            enabled BOOLEAN NOT NULL,
+            FOREIGN KEY(camera_id) REFERENCES cameras(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    // Cameras without a row here record to "mp4" (see stream::RECORDING_CONTAINERS)
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS camera_recording_formats (
+            camera_id INTEGER PRIMARY KEY,
+            container TEXT NOT NULL,
+            FOREIGN KEY(camera_id) REFERENCES cameras(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    // Extra recording directories (see storage), tried in priority order when one is full
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS storage_locations (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
             name TEXT NOT NULL,
-            cron_expression TEXT NOT NULL,
-            duration_minutes INTEGER NOT NULL,
-            fps INTEGER,
-            is_enabled BOOLEAN DEFAULT 1,
+            path TEXT NOT NULL UNIQUE,
+            priority INTEGER NOT NULL DEFAULT 0,
+            min_free_mb INTEGER NOT NULL DEFAULT 1024,
+            enabled BOOLEAN NOT NULL DEFAULT 1
+        )",
+        [],
+    )?;
+
+    // Cameras without a row here record to the built-in recordings directory
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS camera_storage (
+            camera_id INTEGER PRIMARY KEY,
+            location_id INTEGER NOT NULL,
+            FOREIGN KEY(camera_id) REFERENCES cameras(id) ON DELETE CASCADE,
+            FOREIGN KEY(location_id) REFERENCES storage_locations(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    // Pre-recording ring buffers (see prebuffer); cameras without a row have none
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS camera_prebuffers (
+            camera_id INTEGER PRIMARY KEY,
+            seconds INTEGER NOT NULL,
+            FOREIGN KEY(camera_id) REFERENCES cameras(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    // Background jobs (see jobs); params is the JSON the job was submitted with
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS jobs (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            kind TEXT NOT NULL,
+            params TEXT NOT NULL,
+            status TEXT NOT NULL,
+            progress REAL NOT NULL DEFAULT 0,
+            message TEXT,
+            error TEXT,
+            created_at TEXT NOT NULL,
+            started_at TEXT,
+            finished_at TEXT
+        )",
+        [],
+    )?;
+
+    // App login accounts and their sessions (see auth)
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS app_users (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            username TEXT NOT NULL UNIQUE COLLATE NOCASE,
+            password_hash TEXT NOT NULL,
+            role TEXT NOT NULL DEFAULT 'viewer',
+            created_at TEXT NOT NULL,
+            last_login_at TEXT
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS app_sessions (
+            token_hash TEXT PRIMARY KEY,
+            user_id INTEGER NOT NULL,
+            created_at TEXT NOT NULL,
+            expires_at TEXT NOT NULL,
+            FOREIGN KEY(user_id) REFERENCES app_users(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    // Key/value settings (JSON values) managed by the settings module
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS app_settings (
+            key TEXT PRIMARY KEY,
+            value TEXT NOT NULL,
+            updated_at TEXT NOT NULL
+        )",
+        [],
+    )?;
+
+    // Live stream HLS output (single row); superseded by app_settings, which copies it once
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS hls_settings (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            segment_secs INTEGER NOT NULL DEFAULT 2,
+            list_size INTEGER NOT NULL DEFAULT 15,
+            delete_threshold INTEGER NOT NULL DEFAULT 3,
+            segment_dir TEXT,
+            timeshift_minutes INTEGER NOT NULL DEFAULT 0
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "INSERT OR IGNORE INTO hls_settings (id) VALUES (1)",
+        [],
+    )?;
+
+    // NULL columns follow hls_settings
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS camera_hls_settings (
+            camera_id INTEGER PRIMARY KEY,
+            segment_secs INTEGER,
+            list_size INTEGER,
+            delete_threshold INTEGER,
+            segment_dir TEXT,
+            timeshift_minutes INTEGER,
+            packaging TEXT,
+            FOREIGN KEY(camera_id) REFERENCES cameras(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    // Cameras whose ONVIF SOAP requests are written to a trace file
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS onvif_trace_cameras (
+            camera_id INTEGER PRIMARY KEY,
+            enabled_at TEXT NOT NULL,
+            FOREIGN KEY(camera_id) REFERENCES cameras(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    // Local HTTP server settings (single row); superseded by app_settings, which copies it once
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS server_settings (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            download_limit_kbps INTEGER
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "INSERT OR IGNORE INTO server_settings (id) VALUES (1)",
+        [],
+    )?;
+
+    // Audio level alerts per camera
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS audio_monitors (
+            camera_id INTEGER PRIMARY KEY,
+            enabled BOOLEAN NOT NULL DEFAULT 0,
+            threshold_db REAL NOT NULL DEFAULT -20,
+            cooldown_secs INTEGER NOT NULL DEFAULT 60,
+            record_on_alert BOOLEAN NOT NULL DEFAULT 0,
+            record_minutes INTEGER NOT NULL DEFAULT 5,
+            FOREIGN KEY(camera_id) REFERENCES cameras(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    // FFmpeg motion detection per camera (ROI columns are NULL for the whole frame)
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS motion_detectors (
+            camera_id INTEGER PRIMARY KEY,
+            enabled BOOLEAN NOT NULL DEFAULT 0,
+            sensitivity INTEGER NOT NULL DEFAULT 50,
+            roi_x REAL,
+            roi_y REAL,
+            roi_width REAL,
+            roi_height REAL,
+            cooldown_secs INTEGER NOT NULL DEFAULT 30,
+            record_on_motion BOOLEAN NOT NULL DEFAULT 0,
+            record_minutes INTEGER NOT NULL DEFAULT 5,
+            FOREIGN KEY(camera_id) REFERENCES cameras(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    // Object detection backend (single row)
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS analytics_settings (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            backend TEXT NOT NULL DEFAULT 'http',
+            model_path TEXT,
+            endpoint_url TEXT,
+            sample_fps REAL NOT NULL DEFAULT 1
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "INSERT OR IGNORE INTO analytics_settings (id) VALUES (1)",
+        [],
+    )?;
+
+    // Object detection per camera (classes is a JSON array of categories)
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS object_detectors (
+            camera_id INTEGER PRIMARY KEY,
+            enabled BOOLEAN NOT NULL DEFAULT 0,
+            min_confidence REAL NOT NULL DEFAULT 0.5,
+            classes TEXT NOT NULL DEFAULT '[\"person\",\"vehicle\",\"animal\"]',
+            cooldown_secs INTEGER NOT NULL DEFAULT 10,
+            FOREIGN KEY(camera_id) REFERENCES cameras(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    // A recording as the user started it; its files (recordings.session_id) are the segments
+    // split off by pause/resume. ended_at is NULL while a segment is being written.
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS recording_sessions (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            camera_id INTEGER NOT NULL,
+            trigger TEXT NOT NULL DEFAULT 'manual',
+            started_at TEXT NOT NULL,
+            ended_at TEXT,
+            FOREIGN KEY(camera_id) REFERENCES cameras(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    // Paused intervals of recording sessions (resumed_at is NULL while paused)
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS recording_gaps (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            session_id INTEGER NOT NULL,
+            camera_id INTEGER NOT NULL,
+            paused_at TEXT NOT NULL,
+            resumed_at TEXT,
+            FOREIGN KEY(camera_id) REFERENCES cameras(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_recording_gaps_session ON recording_gaps(session_id)",
+        [],
+    )?;
+
+    // Manual recording settings (single row)
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS recording_settings (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            default_max_duration_minutes INTEGER
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "INSERT OR IGNORE INTO recording_settings (id) VALUES (1)",
+        [],
+    )?;
+
+    // External trigger endpoint (single row); superseded by app_settings, which copies it once
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS trigger_settings (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            token TEXT NOT NULL,
+            allow_remote BOOLEAN NOT NULL DEFAULT 0,
+            remote_port INTEGER NOT NULL DEFAULT 3334,
+            default_record_minutes INTEGER NOT NULL DEFAULT 5
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "INSERT OR IGNORE INTO trigger_settings (id, token) VALUES (1, lower(hex(randomblob(16))))",
+        [],
+    )?;
+
+    // MQTT broker for Home Assistant discovery (single row); superseded by app_settings, which copies it once
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS mqtt_settings (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            enabled BOOLEAN NOT NULL DEFAULT 0,
+            host TEXT,
+            port INTEGER NOT NULL DEFAULT 1883,
+            username TEXT,
+            password TEXT,
+            client_id TEXT NOT NULL DEFAULT 'onvif-viewer',
+            discovery_prefix TEXT NOT NULL DEFAULT 'homeassistant',
+            base_topic TEXT NOT NULL DEFAULT 'onvif_viewer'
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "INSERT OR IGNORE INTO mqtt_settings (id) VALUES (1)",
+        [],
+    )?;
+
+    // Settings of camera plugins (JSON, checked against each plugin's schema)
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS plugin_settings (
+            plugin_type TEXT PRIMARY KEY,
+            config TEXT NOT NULL DEFAULT '{}',
+            updated_at TEXT DEFAULT CURRENT_TIMESTAMP
+        )",
+        [],
+    )?;
+
+    // Camera events (motion, tampering, ...) shown as timeline markers
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS events (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            camera_id INTEGER NOT NULL,
+            event_type TEXT NOT NULL,
+            start_time TEXT NOT NULL,
+            end_time TEXT,
+            details TEXT,
             created_at TEXT DEFAULT CURRENT_TIMESTAMP,
-            updated_at TEXT DEFAULT CURRENT_TIMESTAMP,
             FOREIGN KEY(camera_id) REFERENCES cameras(id) ON DELETE CASCADE
         )",
         [],
     )?;
 
+    // Indexes for per-camera time range queries (timeline)
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_recordings_camera_start ON recordings(camera_id, start_time)",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_events_camera_start ON events(camera_id, start_time)",
+        [],
+    )?;
+    // Events browser across all cameras
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_events_start ON events(start_time)",
+        [],
+    )?;
+
+    // Recording failures and dropped live streams as they happen, for activity reports
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS camera_incidents (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            camera_id INTEGER NOT NULL,
+            kind TEXT NOT NULL,
+            detail TEXT,
+            occurred_at TEXT NOT NULL,
+            FOREIGN KEY(camera_id) REFERENCES cameras(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_camera_incidents_occurred ON camera_incidents(occurred_at)",
+        [],
+    )?;
+
+    // Digests sent by report::spawn_digest; one row per period and range, so none is sent twice
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS report_deliveries (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            period TEXT NOT NULL,
+            range_start TEXT NOT NULL,
+            range_end TEXT NOT NULL,
+            sent_at TEXT NOT NULL,
+            error TEXT,
+            UNIQUE(period, range_end)
+        )",
+        [],
+    )?;
+
+    // Columns added after the initial release
+    add_column_if_missing(&conn, "cameras", "tls_verify", "BOOLEAN NOT NULL DEFAULT 0")?;
+    add_column_if_missing(&conn, "cameras", "rtsp_transport", "TEXT NOT NULL DEFAULT 'tcp'")?;
+    add_column_if_missing(&conn, "cameras", "stream_timeout_secs", "INTEGER")?;
+    add_column_if_missing(&conn, "cameras", "tls_cert_pem", "TEXT")?;
+    // FFmpeg input tuning of network streams (InputTuning)
+    add_column_if_missing(&conn, "cameras", "input_socket_timeout_ms", "INTEGER")?;
+    add_column_if_missing(&conn, "cameras", "input_rw_timeout_ms", "INTEGER")?;
+    add_column_if_missing(&conn, "cameras", "input_reconnect", "BOOLEAN NOT NULL DEFAULT 0")?;
+    add_column_if_missing(&conn, "cameras", "input_reconnect_delay_max_secs", "INTEGER")?;
+    add_column_if_missing(&conn, "cameras", "input_probesize", "INTEGER")?;
+    add_column_if_missing(&conn, "cameras", "input_analyzeduration_ms", "INTEGER")?;
+    add_column_if_missing(&conn, "cameras", "input_user_agent", "TEXT")?;
+    // Custom FFmpeg arguments, JSON arrays
+    add_column_if_missing(&conn, "cameras", "extra_input_args", "TEXT")?;
+    add_column_if_missing(&conn, "cameras", "extra_output_args", "TEXT")?;
+    add_column_if_missing(&conn, "cameras", "quality_preset", "TEXT")?;
+    // Video filters (VideoFilters)
+    add_column_if_missing(&conn, "cameras", "video_deinterlace", "BOOLEAN NOT NULL DEFAULT 0")?;
+    add_column_if_missing(&conn, "cameras", "video_rotation", "INTEGER NOT NULL DEFAULT 0")?;
+    add_column_if_missing(&conn, "cameras", "video_hflip", "BOOLEAN NOT NULL DEFAULT 0")?;
+    add_column_if_missing(&conn, "cameras", "video_vflip", "BOOLEAN NOT NULL DEFAULT 0")?;
+    add_column_if_missing(&conn, "cameras", "time_sync_strategy", "TEXT")?;
+    add_column_if_missing(&conn, "cameras", "profile_token", "TEXT")?;
+    // UVC/Pi capture settings, detected when the device is added
+    add_column_if_missing(&conn, "cameras", "video_format", "TEXT")?;
+    add_column_if_missing(&conn, "cameras", "video_width", "INTEGER")?;
+    add_column_if_missing(&conn, "cameras", "video_height", "INTEGER")?;
+    add_column_if_missing(&conn, "cameras", "video_fps", "INTEGER")?;
+    // Set when a camera is archived (soft-deleted): hidden from the camera list, recordings kept
+    add_column_if_missing(&conn, "cameras", "archived_at", "TEXT")?;
+    // Load budget priority (budget::PRIORITY_LEVELS), NULL for "normal"
+    add_column_if_missing(&conn, "cameras", "priority", "TEXT")?;
+    add_column_if_missing(&conn, "cameras", "sort_order", "INTEGER NOT NULL DEFAULT 0")?;
+    add_column_if_missing(&conn, "cameras", "is_favorite", "BOOLEAN NOT NULL DEFAULT 0")?;
+    add_column_if_missing(&conn, "hls_settings", "timeshift_minutes", "INTEGER NOT NULL DEFAULT 0")?;
+    add_column_if_missing(&conn, "camera_hls_settings", "timeshift_minutes", "INTEGER")?;
+    add_column_if_missing(&conn, "camera_hls_settings", "packaging", "TEXT")?;
+    add_column_if_missing(&conn, "recordings", "trigger", "TEXT NOT NULL DEFAULT 'manual'")?;
+    // JSON array of strings
+    add_column_if_missing(&conn, "recordings", "tags", "TEXT NOT NULL DEFAULT '[]'")?;
+    add_column_if_missing(&conn, "recordings", "notes", "TEXT")?;
+    add_column_if_missing(&conn, "recordings", "is_protected", "BOOLEAN NOT NULL DEFAULT 0")?;
+    add_column_if_missing(&conn, "recordings", "archive_location", "TEXT")?;
+    add_column_if_missing(&conn, "recordings", "archived_at", "TEXT")?;
+    add_column_if_missing(&conn, "recordings", "archive_checksum", "TEXT")?;
+    add_column_if_missing(&conn, "recordings", "upload_key", "TEXT")?;
+    add_column_if_missing(&conn, "recordings", "uploaded_at", "TEXT")?;
+    add_column_if_missing(&conn, "recordings", "upload_thumbnail_key", "TEXT")?;
+    add_column_if_missing(&conn, "recordings", "file_size", "INTEGER")?;
+    add_column_if_missing(&conn, "recordings", "integrity", "TEXT")?;
+    add_column_if_missing(&conn, "recordings", "integrity_detail", "TEXT")?;
+    add_column_if_missing(&conn, "recordings", "verified_at", "TEXT")?;
+    add_column_if_missing(&conn, "recordings", "session_id", "INTEGER")?;
+    // Storage location the filename is relative to; NULL for the built-in recordings directory
+    add_column_if_missing(&conn, "recordings", "location_id", "INTEGER")?;
+    // Why the recording FFmpeg died right after starting; such recordings have no file
+    add_column_if_missing(&conn, "recordings", "failure", "TEXT")?;
+    // Animated previews in the thumbnails directory (see previews)
+    add_column_if_missing(&conn, "recordings", "preview", "TEXT")?;
+    add_column_if_missing(&conn, "events", "preview", "TEXT")?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_recordings_session ON recordings(session_id)",
+        [],
+    )?;
+    // Seconds of the session's max duration left when it was paused; NULL without one
+    add_column_if_missing(&conn, "recording_gaps", "remaining_secs", "INTEGER")?;
+    add_column_if_missing(&conn, "schedule_runs", "planned_stop_at", "TEXT")?;
+    add_column_if_missing(&conn, "recording_schedules", "schedule_type", "TEXT NOT NULL DEFAULT 'cron'")?;
+    // JSON array of WeeklyBlock
+    add_column_if_missing(&conn, "recording_schedules", "weekly_blocks", "TEXT")?;
+    // "record" or "snapshot"; retention_days only applies to snapshot schedules
+    add_column_if_missing(&conn, "recording_schedules", "action", "TEXT NOT NULL DEFAULT 'record'")?;
+    add_column_if_missing(&conn, "recording_schedules", "retention_days", "INTEGER")?;
+    // Global switch for all schedules (pause_schedules / resume_schedules)
+    add_column_if_missing(&conn, "scheduler_settings", "schedules_enabled", "BOOLEAN NOT NULL DEFAULT 1")?;
+
+    // Foreign keys added after the initial release
+    add_foreign_key_if_missing(&conn, "schedule_runs", "camera_id", SCHEDULE_RUNS_COLUMNS)?;
+    add_foreign_key_if_missing(&conn, "snapshots", "schedule_id", SNAPSHOTS_COLUMNS)?;
+    // Also drops NOT NULL from camera_id, which group schedules leave empty
+    add_foreign_key_if_missing(&conn, "recording_schedules", "group_id", RECORDING_SCHEDULES_COLUMNS)?;
+
+    backfill_recording_sessions(&conn)?;
+
+    Ok(())
+}
+
+// Give recordings made before recording_sessions existed a session: a recording that was never
+// paused becomes a session of its own, numbered like the recording, which is also the number
+// paused sessions already used (the id of their first segment)
+fn backfill_recording_sessions(conn: &Connection) -> Result<()> {
+    conn.execute("UPDATE recordings SET session_id = id WHERE session_id IS NULL", [])?;
+    let added = conn.execute(
+        "INSERT INTO recording_sessions (id, camera_id, trigger, started_at, ended_at)
+         SELECT r.session_id, MIN(r.camera_id), MIN(r.trigger), MIN(r.start_time),
+                CASE WHEN MIN(r.is_finished) = 1 THEN MAX(r.end_time) END
+         FROM recordings r
+         WHERE NOT EXISTS (SELECT 1 FROM recording_sessions s WHERE s.id = r.session_id)
+         GROUP BY r.session_id",
+        [],
+    )?;
+
+    if added > 0 {
+        println!("[DB] Created {} recording session(s) for existing recordings", added);
+    }
+    Ok(())
+}
+
+// A schedule targets either one camera or a camera group
+const RECORDING_SCHEDULES_COLUMNS: &str = "
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    camera_id INTEGER,
+    group_id INTEGER,
+    name TEXT NOT NULL,
+    cron_expression TEXT NOT NULL,
+    duration_minutes INTEGER NOT NULL,
+    fps INTEGER,
+    is_enabled BOOLEAN DEFAULT 1,
+    created_at TEXT DEFAULT CURRENT_TIMESTAMP,
+    updated_at TEXT DEFAULT CURRENT_TIMESTAMP,
+    schedule_type TEXT NOT NULL DEFAULT 'cron',
+    weekly_blocks TEXT,
+    action TEXT NOT NULL DEFAULT 'record',
+    retention_days INTEGER,
+    FOREIGN KEY(camera_id) REFERENCES cameras(id) ON DELETE CASCADE,
+    FOREIGN KEY(group_id) REFERENCES camera_groups(id) ON DELETE CASCADE
+";
+
+const SNAPSHOTS_COLUMNS: &str = "
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    camera_id INTEGER NOT NULL,
+    schedule_id INTEGER,
+    filename TEXT NOT NULL,
+    captured_at TEXT NOT NULL,
+    file_size INTEGER,
+    FOREIGN KEY(camera_id) REFERENCES cameras(id) ON DELETE CASCADE,
+    FOREIGN KEY(schedule_id) REFERENCES recording_schedules(id) ON DELETE SET NULL
+";
+
+const SCHEDULE_RUNS_COLUMNS: &str = "
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    schedule_id INTEGER NOT NULL,
+    camera_id INTEGER NOT NULL,
+    scheduled_at TEXT NOT NULL,
+    planned_stop_at TEXT,
+    started_at TEXT,
+    finished_at TEXT,
+    status TEXT NOT NULL,
+    detail TEXT,
+    FOREIGN KEY(schedule_id) REFERENCES recording_schedules(id) ON DELETE CASCADE,
+    FOREIGN KEY(camera_id) REFERENCES cameras(id) ON DELETE CASCADE
+";
+
+// SQLite can't add a constraint to an existing table, so a table from an older version without
+// the foreign key on `column` is rebuilt from `columns` (its current definition). Orphaned rows
+// are copied as they are; check_integrity reports them.
+fn add_foreign_key_if_missing(conn: &Connection, table: &str, column: &str, columns: &str) -> Result<()> {
+    let mut stmt = conn.prepare(&format!("PRAGMA foreign_key_list({})", table))?;
+    let exists = stmt
+        .query_map([], |row| row.get::<_, String>(3))?
+        .filter_map(|from| from.ok())
+        .any(|from| from == column);
+    if exists {
+        return Ok(());
+    }
+    println!("[DB] Adding foreign key on {}.{}", table, column);
+
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let names = stmt
+        .query_map([], |row| row.get::<_, String>(1))?
+        .collect::<Result<Vec<_>>>()?
+        .join(", ");
+    let mut stmt = conn.prepare("SELECT sql FROM sqlite_master WHERE type = 'index' AND tbl_name = ?1 AND sql IS NOT NULL")?;
+    let indexes = stmt
+        .query_map([table], |row| row.get::<_, String>(0))?
+        .collect::<Result<Vec<_>>>()?;
+
+    // Enforcement has to be off while the old table is dropped
+    conn.pragma_update(None, "foreign_keys", false)?;
+    conn.execute_batch(&format!(
+        "BEGIN;
+         CREATE TABLE {table}_rebuild ({columns});
+         INSERT INTO {table}_rebuild ({names}) SELECT {names} FROM {table};
+         DROP TABLE {table};
+         ALTER TABLE {table}_rebuild RENAME TO {table};
+         {indexes}
+         COMMIT;",
+        indexes = indexes.iter().map(|sql| format!("{};", sql)).collect::<String>(),
+    ))
+}
+
+/// Log rows whose parent row is gone (left by versions that didn't enforce foreign keys)
+pub fn check_integrity<P: AsRef<Path>>(path: P) -> Result<usize> {
+    let conn = open(path)?;
+    let mut stmt = conn.prepare("PRAGMA foreign_key_check")?;
+    // (table, parent table) -> row count
+    let mut orphans = std::collections::BTreeMap::<(String, String), usize>::new();
+    for violation in stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(2)?)))? {
+        *orphans.entry(violation?).or_default() += 1;
+    }
+
+    for ((table, parent), count) in &orphans {
+        println!("[DB] Integrity: {} row(s) in {} reference missing {} rows", count, table, parent);
+    }
+    Ok(orphans.values().sum())
+}
+
+// Add a column to an existing table (databases created by older versions)
+fn add_column_if_missing(conn: &Connection, table: &str, column: &str, definition: &str) -> Result<()> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let exists = stmt
+        .query_map([], |row| row.get::<_, String>(1))?
+        .filter_map(|name| name.ok())
+        .any(|name| name == column);
+
+    if !exists {
+        conn.execute(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition), [])?;
+    }
+
     Ok(())
 }
 
@@ -95,7 +803,7 @@ pub async fn init_gpu_encoder_settings<P: AsRef<Path>>(path: P) -> Result<(), St
     if let Some(preferred_encoder) = capabilities.preferredEncoder {
         println!("[Init] Found GPU encoder: {}", preferred_encoder);
 
-        let conn = Connection::open(path)
+        let conn = open(path)
             .map_err(|e| format!("Failed to open database: {}", e))?;
 
         // Update the encoder settings only if gpu_encoder is NULL
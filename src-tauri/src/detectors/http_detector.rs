@@ -0,0 +1,89 @@
+use crate::object_detector::{jpeg_dimensions, BoundingBox, Detection, ObjectDetector};
+use async_trait::async_trait;
+use serde::Deserialize;
+use std::time::Duration;
+
+// Inference servers on a CPU can take a while per frame
+const REQUEST_TIMEOUT_SECS: u64 = 10;
+
+#[derive(Deserialize)]
+struct DetectionResponse {
+    success: bool,
+    #[serde(default)]
+    predictions: Vec<Prediction>,
+    error: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Prediction {
+    label: String,
+    confidence: f32,
+    x_min: f32,
+    y_min: f32,
+    x_max: f32,
+    y_max: f32,
+}
+
+/// Detection server with the DeepStack-style `/v1/vision/detection` API
+/// (DeepStack, CodeProject.AI Server, ...)
+pub struct HttpDetector {
+    client: reqwest::Client,
+    endpoint: String,
+}
+
+impl HttpDetector {
+    /// `endpoint` is the full detection URL, e.g. `http://localhost:32168/v1/vision/detection`
+    pub fn new(endpoint: &str) -> Result<Self, String> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(REQUEST_TIMEOUT_SECS))
+            .build()
+            .map_err(|e| e.to_string())?;
+
+        Ok(Self { client, endpoint: endpoint.to_string() })
+    }
+}
+
+#[async_trait]
+impl ObjectDetector for HttpDetector {
+    fn name(&self) -> &'static str {
+        "http"
+    }
+
+    async fn detect(&self, jpeg: &[u8]) -> Result<Vec<Detection>, String> {
+        let (width, height) = jpeg_dimensions(jpeg).ok_or("Frame is not a valid JPEG")?;
+
+        let part = reqwest::multipart::Part::bytes(jpeg.to_vec())
+            .file_name("frame.jpg")
+            .mime_str("image/jpeg")
+            .map_err(|e| e.to_string())?;
+        let form = reqwest::multipart::Form::new().part("image", part);
+
+        let response: DetectionResponse = self.client.post(&self.endpoint)
+            .multipart(form)
+            .send()
+            .await
+            .map_err(|e| format!("Detection request failed: {}", e))?
+            .error_for_status()
+            .map_err(|e| format!("Detection server error: {}", e))?
+            .json()
+            .await
+            .map_err(|e| format!("Invalid detection response: {}", e))?;
+
+        if !response.success {
+            return Err(response.error.unwrap_or_else(|| "Detection server reported a failure".to_string()));
+        }
+
+        // Pixel coordinates to fractions of the frame
+        let (w, h) = (width as f32, height as f32);
+        Ok(response.predictions.into_iter().map(|p| Detection {
+            label: p.label,
+            confidence: p.confidence,
+            bbox: BoundingBox {
+                x: p.x_min / w,
+                y: p.y_min / h,
+                width: (p.x_max - p.x_min) / w,
+                height: (p.y_max - p.y_min) / h,
+            },
+        }).collect())
+    }
+}
@@ -0,0 +1,7 @@
+pub mod http_detector;
+#[cfg(feature = "onnx")]
+pub mod onnx_detector;
+
+pub use http_detector::HttpDetector;
+#[cfg(feature = "onnx")]
+pub use onnx_detector::OnnxDetector;
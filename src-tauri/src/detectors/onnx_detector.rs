@@ -0,0 +1,145 @@
+use crate::object_detector::{BoundingBox, Detection, ObjectDetector};
+use async_trait::async_trait;
+use image::imageops::FilterType;
+use ndarray::{Array4, Axis};
+use ort::session::builder::GraphOptimizationLevel;
+use ort::session::Session;
+use ort::value::TensorRef;
+use std::sync::{Arc, Mutex};
+
+// YOLOv8/YOLO11 exports take a square RGB image
+const INPUT_SIZE: u32 = 640;
+// Candidates below this are dropped before NMS; the per-camera threshold applies afterwards
+const MIN_SCORE: f32 = 0.25;
+const NMS_IOU: f32 = 0.45;
+
+const COCO_CLASSES: [&str; 80] = [
+    "person", "bicycle", "car", "motorcycle", "airplane", "bus", "train", "truck", "boat",
+    "traffic light", "fire hydrant", "stop sign", "parking meter", "bench", "bird", "cat", "dog",
+    "horse", "sheep", "cow", "elephant", "bear", "zebra", "giraffe", "backpack", "umbrella",
+    "handbag", "tie", "suitcase", "frisbee", "skis", "snowboard", "sports ball", "kite",
+    "baseball bat", "baseball glove", "skateboard", "surfboard", "tennis racket", "bottle",
+    "wine glass", "cup", "fork", "knife", "spoon", "bowl", "banana", "apple", "sandwich", "orange",
+    "broccoli", "carrot", "hot dog", "pizza", "donut", "cake", "chair", "couch", "potted plant",
+    "bed", "dining table", "toilet", "tv", "laptop", "mouse", "remote", "keyboard", "cell phone",
+    "microwave", "oven", "toaster", "sink", "refrigerator", "book", "clock", "vase", "scissors",
+    "teddy bear", "hair drier", "toothbrush",
+];
+
+/// Local YOLO model (COCO classes, exported to ONNX with a [1, 84, N] output) run with ONNX Runtime
+pub struct OnnxDetector {
+    // Running a session needs exclusive access; cameras take turns
+    session: Arc<Mutex<Session>>,
+}
+
+impl OnnxDetector {
+    pub fn new(model_path: &str) -> Result<Self, String> {
+        let load_error = |e: String| format!("Failed to load ONNX model {}: {}", model_path, e);
+        let session = Session::builder()
+            .map_err(|e| load_error(e.to_string()))?
+            .with_optimization_level(GraphOptimizationLevel::Level3)
+            .map_err(|e| load_error(e.to_string()))?
+            .commit_from_file(model_path)
+            .map_err(|e| load_error(e.to_string()))?;
+
+        println!("[Analytics] Loaded ONNX model {}", model_path);
+        Ok(Self { session: Arc::new(Mutex::new(session)) })
+    }
+}
+
+// Resize (stretched, so boxes map back as plain fractions) into a normalized NCHW tensor
+fn preprocess(jpeg: &[u8]) -> Result<Array4<f32>, String> {
+    let image = image::load_from_memory(jpeg)
+        .map_err(|e| format!("Failed to decode frame: {}", e))?
+        .resize_exact(INPUT_SIZE, INPUT_SIZE, FilterType::Triangle)
+        .to_rgb8();
+
+    let size = INPUT_SIZE as usize;
+    let mut input = Array4::<f32>::zeros((1, 3, size, size));
+    for (x, y, pixel) in image.enumerate_pixels() {
+        for channel in 0..3 {
+            input[[0, channel, y as usize, x as usize]] = pixel[channel] as f32 / 255.0;
+        }
+    }
+    Ok(input)
+}
+
+fn iou(a: &BoundingBox, b: &BoundingBox) -> f32 {
+    let left = a.x.max(b.x);
+    let top = a.y.max(b.y);
+    let right = (a.x + a.width).min(b.x + b.width);
+    let bottom = (a.y + a.height).min(b.y + b.height);
+    let intersection = (right - left).max(0.0) * (bottom - top).max(0.0);
+    let union = a.width * a.height + b.width * b.height - intersection;
+    if union > 0.0 { intersection / union } else { 0.0 }
+}
+
+// Greedy per-class non-maximum suppression
+fn non_max_suppression(mut candidates: Vec<Detection>) -> Vec<Detection> {
+    candidates.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+
+    let mut kept: Vec<Detection> = Vec::new();
+    for candidate in candidates {
+        let overlaps = kept.iter().any(|k| k.label == candidate.label && iou(&k.bbox, &candidate.bbox) > NMS_IOU);
+        if !overlaps {
+            kept.push(candidate);
+        }
+    }
+    kept
+}
+
+fn run_model(session: &Mutex<Session>, jpeg: &[u8]) -> Result<Vec<Detection>, String> {
+    let input = preprocess(jpeg)?;
+    let tensor = TensorRef::from_array_view(&input).map_err(|e| e.to_string())?;
+
+    let mut session = session.lock().map_err(|e| e.to_string())?;
+    let outputs = session.run(ort::inputs![tensor]).map_err(|e| format!("Inference failed: {}", e))?;
+    let output = outputs[0].try_extract_array::<f32>().map_err(|e| e.to_string())?;
+
+    // [1, 4 + classes, candidates] -> rows of (cx, cy, w, h, class scores...)
+    let output = output.index_axis(Axis(0), 0);
+    let size = INPUT_SIZE as f32;
+    let mut candidates = Vec::new();
+
+    for candidate in output.axis_iter(Axis(1)) {
+        let Some((class, score)) = candidate.iter().skip(4).copied().enumerate()
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+        else {
+            continue;
+        };
+        if score < MIN_SCORE {
+            continue;
+        }
+        let Some(label) = COCO_CLASSES.get(class) else { continue };
+
+        let (cx, cy, w, h) = (candidate[0], candidate[1], candidate[2], candidate[3]);
+        candidates.push(Detection {
+            label: label.to_string(),
+            confidence: score,
+            bbox: BoundingBox {
+                x: ((cx - w / 2.0) / size).max(0.0),
+                y: ((cy - h / 2.0) / size).max(0.0),
+                width: (w / size).min(1.0),
+                height: (h / size).min(1.0),
+            },
+        });
+    }
+
+    Ok(non_max_suppression(candidates))
+}
+
+#[async_trait]
+impl ObjectDetector for OnnxDetector {
+    fn name(&self) -> &'static str {
+        "onnx"
+    }
+
+    async fn detect(&self, jpeg: &[u8]) -> Result<Vec<Detection>, String> {
+        // Inference is CPU bound; keep it off the async workers
+        let session = self.session.clone();
+        let jpeg = jpeg.to_vec();
+        tokio::task::spawn_blocking(move || run_model(&session, &jpeg))
+            .await
+            .map_err(|e| e.to_string())?
+    }
+}
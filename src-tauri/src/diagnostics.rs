@@ -0,0 +1,234 @@
+use crate::gpu_detector::GpuCapabilities;
+use crate::models::DiagnosticsBundle;
+use crate::redact::redact;
+use crate::repository::CameraRepo;
+use crate::AppState;
+use chrono::Utc;
+use rusqlite::types::ValueRef;
+use rusqlite::Connection;
+use serde_json::{json, Map, Value};
+use std::io::Write;
+use std::path::Path;
+use zip::write::SimpleFileOptions;
+
+// Incidents and events included, newest first
+const RECENT_LOG_ROWS: usize = 500;
+// Settings tables; app_settings is read through settings::load_all
+const SETTINGS_TABLES: [&str; 12] = [
+    "encoder_settings", "scheduler_settings", "archive_settings", "cloud_upload_settings", "server_settings",
+    "hls_settings", "recording_settings", "trigger_settings", "mqtt_settings", "analytics_settings",
+    "plugin_settings", "storage_locations",
+];
+// Columns whose values are masked whatever they hold
+const SECRET_COLUMN_PARTS: [&str; 4] = ["pass", "secret", "token", "key"];
+
+/// Zip what a bug report needs into `dest_path`: app and database versions, the schema,
+/// settings with secrets masked, GPU detection, the FFmpeg version, a camera summary without
+/// credentials, pipeline state, recent incidents and events, and ONVIF traces. The app keeps no
+/// log file of its own; incidents and events are its persistent record of what went wrong.
+pub fn export(state: &AppState, gpu: Result<GpuCapabilities, String>, dest_path: &Path) -> Result<DiagnosticsBundle, String> {
+    let conn = crate::db::open(&state.db_path).map_err(|e| e.to_string())?;
+    let mut entries: Vec<(String, Vec<u8>)> = Vec::new();
+    let mut add_json = |name: &str, value: Value| -> Result<(), String> {
+        entries.push((name.to_string(), serde_json::to_vec_pretty(&value).map_err(|e| e.to_string())?));
+        Ok(())
+    };
+
+    add_json("app.json", json!({
+        "version": env!("CARGO_PKG_VERSION"),
+        "os": std::env::consts::OS,
+        "arch": std::env::consts::ARCH,
+        "createdAt": Utc::now(),
+        "timezone": crate::settings::timezone().name(),
+    }))?;
+    add_json("database/version.json", json!({
+        "sqlite": rusqlite::version(),
+        "userVersion": conn.query_row("PRAGMA user_version", [], |row| row.get::<_, i64>(0)).map_err(|e| e.to_string())?,
+        "tables": rows(&conn, "SELECT name, (SELECT COUNT(*) FROM pragma_table_info(name)) AS columns FROM sqlite_master WHERE type = 'table' ORDER BY name")?,
+    }))?;
+    add_json("settings.json", settings(&conn)?)?;
+    add_json("gpu.json", match gpu {
+        Ok(capabilities) => json!({ "capabilities": capabilities, "usage": crate::gpu_detector::sample_gpu_usage() }),
+        Err(e) => json!({ "error": e }),
+    })?;
+    add_json("cameras.json", cameras(&conn)?)?;
+    add_json("pipelines.json", pipelines(state)?)?;
+    add_json("logs/incidents.json", rows(&conn, &format!(
+        "SELECT camera_id, kind, detail, occurred_at FROM camera_incidents ORDER BY occurred_at DESC LIMIT {}",
+        RECENT_LOG_ROWS
+    ))?)?;
+    add_json("logs/events.json", rows(&conn, &format!(
+        "SELECT camera_id, event_type, start_time, end_time, details FROM events ORDER BY start_time DESC LIMIT {}",
+        RECENT_LOG_ROWS
+    ))?)?;
+
+    let schema: Vec<String> = rows(&conn, "SELECT sql FROM sqlite_master WHERE sql IS NOT NULL ORDER BY type DESC, name")?
+        .as_array()
+        .map(|rows| rows.iter().filter_map(|row| row["sql"].as_str().map(|sql| format!("{};\n", sql))).collect())
+        .unwrap_or_default();
+    entries.push(("database/schema.sql".to_string(), schema.concat().into_bytes()));
+    entries.push(("ffmpeg.txt".to_string(), ffmpeg_version().into_bytes()));
+    entries.extend(onvif_traces(&state.db_path));
+
+    write_zip(dest_path, &entries)?;
+    let bytes = std::fs::metadata(dest_path).map(|metadata| metadata.len()).unwrap_or(0);
+    println!("[Diagnostics] Bundle of {} files ({} bytes) written to {}", entries.len(), bytes, dest_path.display());
+    Ok(DiagnosticsBundle {
+        path: dest_path.to_string_lossy().to_string(),
+        files: entries.into_iter().map(|(name, _)| name).collect(),
+        bytes,
+    })
+}
+
+fn write_zip(path: &Path, entries: &[(String, Vec<u8>)]) -> Result<(), String> {
+    let file = std::fs::File::create(path).map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+    let mut zip = zip::ZipWriter::new(file);
+    let options = SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+    for (name, data) in entries {
+        zip.start_file(name.as_str(), options).map_err(|e| e.to_string())?;
+        zip.write_all(data).map_err(|e| e.to_string())?;
+    }
+    zip.finish().map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// App settings and every settings table, with secret columns masked and URLs redacted
+fn settings(conn: &Connection) -> Result<Value, String> {
+    let mut settings = Map::new();
+    settings.insert("app_settings".to_string(), mask_object(crate::settings::load_all(conn)?));
+    for table in SETTINGS_TABLES {
+        let mut table_rows = rows(conn, &format!("SELECT * FROM {}", table))?;
+        for row in table_rows.as_array_mut().into_iter().flatten() {
+            if let Value::Object(columns) = row {
+                for (column, value) in columns.iter_mut() {
+                    *value = mask(column, value.take());
+                }
+            }
+        }
+        settings.insert(table.to_string(), table_rows);
+    }
+    Ok(Value::Object(settings))
+}
+
+// Secret-looking names get "***"; JSON objects (e.g. plugin configs stored as text) are masked
+// field by field
+fn mask(name: &str, value: Value) -> Value {
+    let name = name.to_ascii_lowercase();
+    match value {
+        Value::Null => Value::Null,
+        _ if SECRET_COLUMN_PARTS.iter().any(|part| name.contains(part)) => json!("***"),
+        Value::String(text) => match serde_json::from_str::<Map<String, Value>>(&text) {
+            Ok(object) => mask_object(object),
+            Err(_) => Value::String(redact(&text)),
+        },
+        Value::Object(object) => mask_object(object),
+        value => value,
+    }
+}
+
+fn mask_object(object: Map<String, Value>) -> Value {
+    Value::Object(object.into_iter().map(|(key, value)| {
+        let value = mask(&key, value);
+        (key, value)
+    }).collect())
+}
+
+// Every camera, archived ones included, without credentials
+fn cameras(conn: &Connection) -> Result<Value, String> {
+    let mut summary = Vec::new();
+    for archived in [false, true] {
+        for camera in CameraRepo::list(conn, archived)? {
+            summary.push(json!({
+                "id": camera.id,
+                "name": camera.name,
+                "type": camera.camera_type,
+                "host": camera.host,
+                "port": camera.port,
+                "xaddr": camera.xaddr.as_deref().map(redact),
+                "streamPath": camera.stream_path.as_deref().map(redact),
+                "profileToken": camera.profile_token,
+                "rtspTransport": camera.rtsp_transport,
+                "hasCredentials": camera.user.is_some() || camera.pass.is_some(),
+                "video": { "width": camera.video_width, "height": camera.video_height, "fps": camera.video_fps },
+                "archived": archived,
+            }));
+        }
+    }
+    Ok(Value::Array(summary))
+}
+
+// Pipeline status without stream URLs, whose session paths grant access to the live streams
+fn pipelines(state: &AppState) -> Result<Value, String> {
+    let mut pipelines = serde_json::to_value(crate::pipeline::pipeline_status(state)?).map_err(|e| e.to_string())?;
+    for pipeline in pipelines.as_array_mut().into_iter().flatten() {
+        pipeline["streamUrl"] = Value::Null;
+    }
+    Ok(pipelines)
+}
+
+// Rows of a query as JSON objects keyed by column name
+fn rows(conn: &Connection, sql: &str) -> Result<Value, String> {
+    let mut stmt = conn.prepare(sql).map_err(|e| e.to_string())?;
+    let columns: Vec<String> = stmt.column_names().into_iter().map(String::from).collect();
+    let mut rows = stmt.query([]).map_err(|e| e.to_string())?;
+    let mut result = Vec::new();
+    while let Some(row) = rows.next().map_err(|e| e.to_string())? {
+        let mut object = Map::new();
+        for (index, column) in columns.iter().enumerate() {
+            let value = match row.get_ref(index).map_err(|e| e.to_string())? {
+                ValueRef::Null => Value::Null,
+                ValueRef::Integer(number) => json!(number),
+                ValueRef::Real(number) => json!(number),
+                ValueRef::Text(text) => json!(String::from_utf8_lossy(text)),
+                ValueRef::Blob(blob) => json!(format!("<{} bytes>", blob.len())),
+            };
+            object.insert(column.clone(), value);
+        }
+        result.push(Value::Object(object));
+    }
+    Ok(Value::Array(result))
+}
+
+fn ffmpeg_version() -> String {
+    let mut cmd = std::process::Command::new("ffmpeg");
+    cmd.arg("-version");
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        cmd.creation_flags(CREATE_NO_WINDOW);
+    }
+    match cmd.output() {
+        Ok(output) => String::from_utf8_lossy(&output.stdout).into_owned(),
+        Err(e) => format!("Failed to run ffmpeg: {}\n", e),
+    }
+}
+
+// ONVIF SOAP traces of traced cameras, already redacted when they were written
+fn onvif_traces(db_path: &str) -> Vec<(String, Vec<u8>)> {
+    let dir = Path::new(db_path).parent().unwrap_or(Path::new(".")).join("onvif-traces");
+    let Ok(files) = std::fs::read_dir(dir) else { return Vec::new() };
+    files
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_file())
+        .filter_map(|entry| {
+            let data = std::fs::read(entry.path()).ok()?;
+            Some((format!("logs/onvif-traces/{}", entry.file_name().to_string_lossy()), data))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn secrets_are_masked_by_column_name_and_urls_redacted() {
+        assert_eq!(mask("secret_key", json!("abc")), json!("***"));
+        assert_eq!(mask("Password", json!("abc")), json!("***"));
+        assert_eq!(mask("password", Value::Null), Value::Null);
+        assert_eq!(mask("reports.webhookUrl", json!("https://user:pw@example.com/hook")), json!("https://***@example.com/hook"));
+        assert_eq!(mask("port", json!(1883)), json!(1883));
+        assert_eq!(mask("config", json!(r#"{"user":"admin","pass":"x"}"#)), json!({"user": "admin", "pass": "***"}));
+    }
+}
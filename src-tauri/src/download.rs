@@ -0,0 +1,313 @@
+use axum::body::Body;
+use axum::extract::{Path as UrlPath, Query, Request, State};
+use axum::http::{header, HeaderMap, HeaderValue, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::Router;
+use bytes::Bytes;
+use chrono::DateTime;
+use rusqlite::Connection;
+use std::collections::HashMap;
+use std::io::SeekFrom;
+use std::path::{Component, Path, PathBuf};
+use std::time::{Duration, Instant};
+use tower_http::services::{ServeDir, ServeFile};
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+const CHUNK_SIZE: usize = 64 * 1024;
+
+#[derive(Clone)]
+pub struct DownloadState {
+    pub db_path: String,
+    pub recording_dir: PathBuf,
+    pub token: String,
+}
+
+/// `/recordings`: files of the recording dir (recordings, snapshots, thumbnails), then
+/// `/recordings/{id}/download` and the files of other storage locations. Every path needs a
+/// viewer, since in LAN mode the server is reachable from other hosts.
+pub fn router(state: DownloadState) -> Router {
+    // The routes are the fallback of the ServeDir, since axum doesn't allow a route next to
+    // a nested service on the same prefix
+    let routes = Router::new()
+        .route("/:id/download", get(download_recording))
+        .route("/locations/:location_id/*file", get(serve_location_file))
+        .with_state(state.clone());
+    Router::new()
+        .nest_service("/recordings", ServeDir::new(&state.recording_dir).fallback(routes))
+        .layer(axum::middleware::from_fn_with_state(state, guard))
+}
+
+async fn guard(
+    State(state): State<DownloadState>,
+    Query(query): Query<HashMap<String, String>>,
+    headers: HeaderMap,
+    request: Request,
+    next: Next,
+) -> Response {
+    if !crate::auth::authorize_request(&state.db_path, &state.token, &headers, &query, crate::auth::Role::Viewer) {
+        return error_response(StatusCode::UNAUTHORIZED, "Missing or invalid token");
+    }
+    next.run(request).await
+}
+
+pub fn load_download_limit(conn: &Connection) -> Result<Option<i32>, String> {
+    crate::settings::get_opt_i32(conn, crate::settings::SERVER_DOWNLOAD_LIMIT)
+}
+
+fn error_response(status: StatusCode, message: &str) -> Response {
+    (status, message.to_string()).into_response()
+}
+
+/// Token from "Authorization: Bearer <token>" or a `token` query parameter (for plain links)
+pub(crate) fn is_authorized(expected: &str, headers: &HeaderMap, query: &HashMap<String, String>) -> bool {
+    crate::auth::provided_token(headers, query).is_some_and(|token| {
+        token.len() == expected.len()
+            && token.bytes().zip(expected.bytes()).fold(0u8, |acc, (a, b)| acc | (a ^ b)) == 0
+    })
+}
+
+// Parse a single "bytes=" range; multi-range requests get the whole file
+fn parse_range(value: &str, size: u64) -> Option<Result<(u64, u64), ()>> {
+    let spec = value.strip_prefix("bytes=")?;
+    if spec.contains(',') {
+        return None;
+    }
+
+    let (start, end) = spec.split_once('-')?;
+    let range = match (start.trim(), end.trim()) {
+        ("", suffix) => {
+            let suffix: u64 = suffix.parse().ok()?;
+            if suffix == 0 || size == 0 {
+                return Some(Err(()));
+            }
+            (size.saturating_sub(suffix), size - 1)
+        }
+        (start, "") => (start.parse().ok()?, size.saturating_sub(1)),
+        (start, end) => (start.parse().ok()?, end.parse::<u64>().ok()?.min(size.saturating_sub(1))),
+    };
+
+    if range.0 > range.1 || range.0 >= size {
+        return Some(Err(()));
+    }
+    Some(Ok(range))
+}
+
+// "Front Door_2024-05-01_13-45-00.mp4"
+fn friendly_filename(camera_name: Option<&str>, start_time: &str, filename: &str) -> String {
+    let extension = std::path::Path::new(filename)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("mp4");
+    let camera: String = camera_name.unwrap_or("recording")
+        .chars()
+        .map(|c| if c.is_control() || "/\\:*?\"<>|".contains(c) { '_' } else { c })
+        .collect();
+
+    match DateTime::parse_from_rfc3339(start_time) {
+        Ok(start) => format!("{}_{}.{}", camera.trim(), start.with_timezone(&crate::settings::timezone()).format("%Y-%m-%d_%H-%M-%S"), extension),
+        Err(_) => filename.to_string(),
+    }
+}
+
+fn content_disposition(name: &str) -> String {
+    let fallback: String = name.chars().map(|c| if c.is_ascii() && c != '"' { c } else { '_' }).collect();
+    format!("attachment; filename=\"{}\"; filename*=UTF-8''{}", fallback, urlencoding::encode(name))
+}
+
+async fn download_recording(
+    State(state): State<DownloadState>,
+    UrlPath(id): UrlPath<i32>,
+    headers: HeaderMap,
+) -> Response {
+    let row = crate::db::open(&state.db_path).map_err(|e| e.to_string()).and_then(|conn| {
+        let row = conn.query_row(
+            "SELECT r.filename, r.start_time, r.is_finished, c.name, r.location_id
+             FROM recordings r LEFT JOIN cameras c ON r.camera_id = c.id
+             WHERE r.id = ?1",
+            [id],
+            |row| Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, bool>(2)?,
+                row.get::<_, Option<String>>(3)?,
+                row.get::<_, Option<i64>>(4)?,
+            )),
+        ).map_err(|e| e.to_string())?;
+        let path = crate::storage::resolve(&conn, &state.recording_dir, row.4, &row.0)?;
+        Ok((row, path, load_download_limit(&conn)?))
+    });
+
+    let ((filename, start_time, is_finished, camera_name, _), path, limit_kbps) = match row {
+        Ok(row) => row,
+        Err(_) => return error_response(StatusCode::NOT_FOUND, "Recording not found"),
+    };
+    if !is_finished {
+        return error_response(StatusCode::CONFLICT, "Recording is still in progress");
+    }
+
+    let mut file = match tokio::fs::File::open(&path).await {
+        Ok(file) => file,
+        Err(_) => return error_response(StatusCode::NOT_FOUND, "Recording file is missing"),
+    };
+    let size = match file.metadata().await {
+        Ok(metadata) => metadata.len(),
+        Err(e) => return error_response(StatusCode::INTERNAL_SERVER_ERROR, &e.to_string()),
+    };
+
+    let range = headers.get(header::RANGE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| parse_range(v, size));
+
+    let (status, start, length) = match range {
+        Some(Ok((start, end))) => (StatusCode::PARTIAL_CONTENT, start, end - start + 1),
+        Some(Err(())) => {
+            let mut response = error_response(StatusCode::RANGE_NOT_SATISFIABLE, "Range not satisfiable");
+            if let Ok(value) = HeaderValue::from_str(&format!("bytes */{}", size)) {
+                response.headers_mut().insert(header::CONTENT_RANGE, value);
+            }
+            return response;
+        }
+        None => (StatusCode::OK, 0, size),
+    };
+
+    if start > 0 {
+        if let Err(e) = file.seek(SeekFrom::Start(start)).await {
+            return error_response(StatusCode::INTERNAL_SERVER_ERROR, &e.to_string());
+        }
+    }
+
+    println!("[Download] Serving recording {} (bytes {}-{} of {})", id, start, (start + length).saturating_sub(1), size);
+
+    let bytes_per_sec = limit_kbps.filter(|kbps| *kbps > 0).map(|kbps| kbps as u64 * 1000 / 8);
+    let started = Instant::now();
+
+    let stream = futures::stream::unfold((Some(file), 0u64), move |(file, sent)| async move {
+        let mut file = file?;
+        if sent >= length {
+            return None;
+        }
+
+        let mut buf = vec![0u8; CHUNK_SIZE.min((length - sent) as usize)];
+        match file.read(&mut buf).await {
+            Ok(0) => None,
+            Ok(n) => {
+                buf.truncate(n);
+                let sent = sent + n as u64;
+                if let Some(bytes_per_sec) = bytes_per_sec {
+                    let due = Duration::from_secs_f64(sent as f64 / bytes_per_sec as f64);
+                    if let Some(wait) = due.checked_sub(started.elapsed()) {
+                        tokio::time::sleep(wait).await;
+                    }
+                }
+                Some((Ok::<_, std::io::Error>(Bytes::from(buf)), (Some(file), sent)))
+            }
+            Err(e) => Some((Err(e), (None, sent))),
+        }
+    });
+
+    let name = friendly_filename(camera_name.as_deref(), &start_time, &filename);
+    let content_type = crate::stream::recording_content_type(&filename);
+
+    let mut response = Response::new(Body::from_stream(stream));
+    *response.status_mut() = status;
+    let headers = response.headers_mut();
+    headers.insert(header::CONTENT_TYPE, HeaderValue::from_static(content_type));
+    headers.insert(header::ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+    headers.insert(header::CONTENT_LENGTH, HeaderValue::from(length));
+    if let Ok(value) = HeaderValue::from_str(&content_disposition(&name)) {
+        headers.insert(header::CONTENT_DISPOSITION, value);
+    }
+    if status == StatusCode::PARTIAL_CONTENT {
+        if let Ok(value) = HeaderValue::from_str(&format!("bytes {}-{}/{}", start, start + length - 1, size)) {
+            headers.insert(header::CONTENT_RANGE, value);
+        }
+    }
+
+    response
+}
+
+// Serve `/recordings/locations/{id}/{file}` from the location's directory, with range
+// requests like the built-in directory
+async fn serve_location_file(
+    State(state): State<DownloadState>,
+    UrlPath((location_id, file)): UrlPath<(i64, String)>,
+    request: Request,
+) -> Response {
+    let root = match crate::db::open(&state.db_path).map_err(|e| e.to_string())
+        .and_then(|conn| crate::storage::location_root(&conn, &state.recording_dir, Some(location_id)))
+    {
+        Ok(root) => root,
+        Err(_) => return StatusCode::NOT_FOUND.into_response(),
+    };
+    let Some(path) = location_file(&root, &file) else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+
+    match ServeFile::new(path).try_call(request).await {
+        Ok(response) => response.map(Body::new),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+// Existing file `file` of a location, None unless it is a plain relative path that stays inside
+// the location's directory once symlinks are resolved
+fn location_file(root: &Path, file: &str) -> Option<PathBuf> {
+    let relative = Path::new(file);
+    if !relative.components().all(|component| matches!(component, Component::Normal(_))) {
+        return None;
+    }
+    let root = root.canonicalize().ok()?;
+    let path = root.join(relative).canonicalize().ok()?;
+    path.starts_with(&root).then_some(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::test_db::TestDb;
+
+    #[tokio::test]
+    async fn recording_files_need_a_token() {
+        let db = TestDb::new("recording-files");
+        std::fs::write(db.dir().join("rec_1.mp4"), b"recording").unwrap();
+        let app = router(DownloadState {
+            db_path: db.db_path(),
+            recording_dir: db.dir().to_path_buf(),
+            token: "server-token".to_string(),
+        });
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/recordings/rec_1.mp4", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let status = |query: &str| reqwest::get(format!("{}{}", url, query));
+        assert_eq!(status("").await.unwrap().status().as_u16(), 401);
+        assert_eq!(status("?token=wrong").await.unwrap().status().as_u16(), 401);
+        assert_eq!(status("?token=server-token").await.unwrap().status().as_u16(), 200);
+    }
+
+    #[test]
+    fn location_files_stay_inside_the_location() {
+        let base = std::env::temp_dir().join(format!("onvif-viewer-location-{}", std::process::id()));
+        let root = base.join("location");
+        let _ = std::fs::remove_dir_all(&base);
+        std::fs::create_dir_all(root.join("cam1")).unwrap();
+        std::fs::write(root.join("cam1").join("rec.mp4"), b"").unwrap();
+        std::fs::write(base.join("secret.txt"), b"").unwrap();
+
+        assert!(location_file(&root, "cam1/rec.mp4").is_some());
+        assert!(location_file(&root, "cam1/missing.mp4").is_none());
+        assert!(location_file(&root, "../secret.txt").is_none());
+        assert!(location_file(&root, "cam1/../../secret.txt").is_none());
+        assert!(location_file(&root, base.join("secret.txt").to_str().unwrap()).is_none());
+        assert!(location_file(&root, "/etc/passwd").is_none());
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(base.join("secret.txt"), root.join("link.txt")).unwrap();
+            assert!(location_file(&root, "link.txt").is_none());
+        }
+
+        let _ = std::fs::remove_dir_all(&base);
+    }
+}
@@ -1,5 +1,184 @@
 use crate::models::EncoderSettings;
 use crate::gpu_detector::{GpuCapabilities, test_encoder};
+use rusqlite::{Connection, OptionalExtension};
+use serde::Serialize;
+
+/// Named set of encoding choices that fit together, so a camera can be set up by intent
+/// instead of tuning FFmpeg options one by one
+#[derive(Debug, Serialize)]
+pub struct QualityPreset {
+    pub name: &'static str,
+    pub label: &'static str,
+    pub description: &'static str,
+    // Live HLS segment length; a camera's own segment setting still wins
+    pub segment_secs: i32,
+    // Keyframe interval, a divisor of segment_secs so every segment starts on a keyframe
+    pub gop_secs: i32,
+    // x264/x265 speed preset of the CPU encoder
+    pub cpu_preset: &'static str,
+    // CRF of the CPU encoder, constant quality level of GPU encoders
+    pub quality: i32,
+    pub stream_kbps: u32,
+    pub record_kbps: u32,
+    pub stream_audio: bool,
+    pub record_audio: bool,
+}
+
+pub const QUALITY_PRESETS: [QualityPreset; 3] = [
+    QualityPreset {
+        name: "low_latency",
+        label: "Low latency",
+        description: "Short segments and fast encoding for live viewing; no audio",
+        segment_secs: 1,
+        gop_secs: 1,
+        cpu_preset: "ultrafast",
+        quality: 26,
+        stream_kbps: 2000,
+        record_kbps: 4000,
+        stream_audio: false,
+        record_audio: false,
+    },
+    QualityPreset {
+        name: "balanced",
+        label: "Balanced",
+        description: "Smooth live view with recordings in good quality and with audio",
+        segment_secs: 2,
+        gop_secs: 2,
+        cpu_preset: "veryfast",
+        quality: 23,
+        stream_kbps: 4000,
+        record_kbps: 8000,
+        stream_audio: false,
+        record_audio: true,
+    },
+    QualityPreset {
+        name: "archive",
+        label: "Archive quality",
+        description: "Highest quality recordings for evidence; more delay and CPU/GPU load",
+        segment_secs: 4,
+        gop_secs: 2,
+        cpu_preset: "medium",
+        quality: 20,
+        stream_kbps: 6000,
+        record_kbps: 12000,
+        stream_audio: true,
+        record_audio: true,
+    },
+];
+
+/// Values of the encoder.qualityPreset setting and of a camera's preset; "custom" uses the
+/// individual encoder and HLS settings
+pub const QUALITY_PRESET_NAMES: [&str; 4] = ["custom", "low_latency", "balanced", "archive"];
+
+pub fn quality_preset(name: &str) -> Option<&'static QualityPreset> {
+    QUALITY_PRESETS.iter().find(|preset| preset.name == name)
+}
+
+/// Preset in effect for a camera: its own, else the global one (None for "custom")
+pub fn quality_preset_for(conn: &Connection, camera_id: i32) -> Result<Option<&'static QualityPreset>, String> {
+    let camera_preset: Option<String> = conn.query_row(
+        "SELECT quality_preset FROM cameras WHERE id = ?1",
+        [camera_id],
+        |row| row.get(0),
+    ).optional().map_err(|e| e.to_string())?.flatten();
+    let name = match camera_preset {
+        Some(name) => name,
+        None => crate::settings::get_opt_string(conn, crate::settings::ENCODER_QUALITY_PRESET)?.unwrap_or_default(),
+    };
+    Ok(quality_preset(&name))
+}
+
+// Set an option's value, adding the option if it isn't there
+fn set_arg(args: &mut Vec<String>, option: &str, value: String) {
+    match args.iter().position(|arg| arg == option) {
+        Some(index) if index + 1 < args.len() => args[index + 1] = value,
+        _ => args.extend([option.to_string(), value]),
+    }
+}
+
+// Set an option's value only if the encoder uses it
+fn replace_arg(args: &mut [String], option: &str, value: &str) {
+    if let Some(index) = args.iter().position(|arg| arg == option) {
+        if let Some(arg) = args.get_mut(index + 1) {
+            *arg = value.to_string();
+        }
+    }
+}
+
+/// Bitrate of a lower rendition of a live stream
+pub fn rendition_kbps(height: i32) -> u32 {
+    match height {
+        ..=360 => 800,
+        361..=480 => 1200,
+        481..=720 => 2500,
+        _ => 4000,
+    }
+}
+
+/// Approximate bitrate of an encoder configuration, from its rate cap or target
+pub fn config_kbps(config: &EncoderConfig) -> Option<u32> {
+    let value = |option: &str| {
+        let index = config.args.iter().position(|arg| arg == option)?;
+        let value = config.args.get(index + 1)?;
+        match value.strip_suffix(['M', 'm']) {
+            Some(mbps) => mbps.parse::<u32>().ok().map(|mbps| mbps * 1000),
+            None => value.strip_suffix(['K', 'k']).unwrap_or(value).parse().ok(),
+        }
+    };
+    value("-maxrate").or_else(|| value("-b:v"))
+}
+
+/// Options of an extra live rendition: `base`'s encoder capped at `kbps`. Hardware device
+/// options are global and left to the first output, which already sets them.
+pub fn rendition_config(base: &EncoderConfig, kbps: u32) -> EncoderConfig {
+    let mut args = Vec::with_capacity(base.args.len());
+    let mut options = base.args.iter();
+    while let Some(arg) = options.next() {
+        if arg == "-init_hw_device" || arg == "-filter_hw_device" {
+            options.next();
+            continue;
+        }
+        args.push(arg.clone());
+    }
+    if base.is_gpu {
+        set_arg(&mut args, "-b:v", format!("{}k", kbps));
+    }
+    set_arg(&mut args, "-maxrate", format!("{}k", kbps));
+    set_arg(&mut args, "-bufsize", format!("{}k", kbps));
+    EncoderConfig { codec: base.codec.clone(), args, is_gpu: base.is_gpu }
+}
+
+// FFmpeg messages of a hardware encoder that broke (driver reset, NVENC session limit, device
+// gone), as opposed to input problems such as an unreachable camera
+const ENCODER_FAILURE_PATTERNS: [&str; 11] = [
+    "openencodesessionex failed",
+    "no capable devices found",
+    "cuda_error",
+    "cannot load libnvidia-encode",
+    "failed to initialise vaapi connection",
+    "failed to create a vaapi device",
+    "error creating a mfx session",
+    "device creation failed",
+    "error while opening encoder",
+    "error initializing output stream",
+    "generic error in an external library",
+];
+
+/// The stderr line showing that `codec` (a GPU encoder) failed, if any: a known hardware
+/// encoder error or an error FFmpeg logged for the encoder itself ("[h264_nvenc @ 0x...] ...
+/// failed"); the encoder's warnings don't count
+pub fn encoder_failure<'a>(stderr: &'a [String], codec: &str) -> Option<&'a str> {
+    let encoder_prefix = format!("[{} @", codec);
+    stderr.iter()
+        .rev()
+        .find(|line| {
+            let lower = line.to_lowercase();
+            let encoder_error = line.starts_with(&encoder_prefix)
+                && ["error", "fail", "cannot", "unable"].iter().any(|word| lower.contains(word));
+            encoder_error || ENCODER_FAILURE_PATTERNS.iter().any(|pattern| lower.contains(pattern))
+        })
+        .map(|line| line.as_str())
+}
 
 #[derive(Debug, Clone)]
 pub struct EncoderConfig {
@@ -11,6 +190,8 @@ pub struct EncoderConfig {
 pub struct EncoderSelector {
     pub capabilities: GpuCapabilities,
     pub settings: EncoderSettings,
+    // Overrides bitrate, GOP, speed preset, quality and audio when set
+    pub preset: Option<&'static QualityPreset>,
 }
 
 impl EncoderSelector {
@@ -18,9 +199,56 @@ impl EncoderSelector {
         EncoderSelector {
             capabilities,
             settings,
+            preset: None,
         }
     }
 
+    pub fn with_preset(mut self, preset: Option<&'static QualityPreset>) -> Self {
+        self.preset = preset;
+        self
+    }
+
+    /// Audio options of the live stream or a recording
+    pub fn audio_args(&self, streaming: bool) -> Vec<String> {
+        let audio = match self.preset {
+            Some(preset) if streaming => preset.stream_audio,
+            Some(preset) => preset.record_audio,
+            // Live streams drop audio for stability/latency, recordings keep it
+            None => !streaming,
+        };
+        let args: &[&str] = if audio { &["-c:a", "aac"] } else { &["-an"] };
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    // Rewrite a built configuration with the preset's rate control and keyframes
+    fn apply_preset(&self, mut config: EncoderConfig, streaming: bool, fps: Option<i32>) -> EncoderConfig {
+        let Some(preset) = self.preset else { return config };
+        let args = &mut config.args;
+
+        // Same shape as the defaults: CBR-like live streams, some headroom for recordings
+        let (kbps, maxrate, bufsize) = match streaming {
+            true => (preset.stream_kbps, preset.stream_kbps, preset.stream_kbps / 2),
+            false => (preset.record_kbps, preset.record_kbps * 5 / 4, preset.record_kbps),
+        };
+        set_arg(args, "-g", (fps.unwrap_or(30) * preset.gop_secs).to_string());
+        replace_arg(args, "-keyint_min", &(fps.unwrap_or(30) * preset.gop_secs).to_string());
+        set_arg(args, "-force_key_frames", format!("expr:gte(t,n_forced*{})", preset.gop_secs));
+        set_arg(args, "-maxrate", format!("{}k", maxrate));
+        set_arg(args, "-bufsize", format!("{}k", bufsize));
+
+        if config.is_gpu {
+            set_arg(args, "-b:v", format!("{}k", kbps));
+            for option in ["-cq", "-qp", "-global_quality"] {
+                replace_arg(args, option, &preset.quality.to_string());
+            }
+        } else {
+            // Constant quality capped at the preset's rate
+            set_arg(args, "-preset", preset.cpu_preset.to_string());
+            set_arg(args, "-crf", preset.quality.to_string());
+        }
+        config
+    }
+
     pub async fn select_encoder_for_streaming(&self, fps: Option<i32>) -> EncoderConfig {
         match self.settings.encoderMode.as_str() {
             "Auto" => {
@@ -173,14 +401,15 @@ impl EncoderSelector {
             }
         }
 
-        EncoderConfig {
+        let config = EncoderConfig {
             codec: encoder.to_string(),
             args,
             is_gpu: true,
-        }
+        };
+        self.apply_preset(config, true, fps)
     }
 
-    fn build_cpu_config_streaming(&self, fps: Option<i32>) -> EncoderConfig {
+    pub fn build_cpu_config_streaming(&self, fps: Option<i32>) -> EncoderConfig {
         // Calculate keyframe interval: fps * 2 for 2-second segments
         // Default to 60 if FPS not provided (for ONVIF cameras)
         let keyframe_interval = fps.map(|f| f * 2).unwrap_or(60).to_string();
@@ -197,11 +426,12 @@ impl EncoderSelector {
             "-force_key_frames".to_string(), "expr:gte(t,n_forced*2)".to_string(),
         ];
 
-        EncoderConfig {
+        let config = EncoderConfig {
             codec: self.settings.cpuEncoder.clone(),
             args,
             is_gpu: false,
-        }
+        };
+        self.apply_preset(config, true, fps)
     }
 
     fn build_gpu_config_recording(&self, encoder: &str) -> EncoderConfig {
@@ -280,23 +510,86 @@ impl EncoderSelector {
             }
         }
 
-        EncoderConfig {
+        let config = EncoderConfig {
             codec: encoder.to_string(),
             args,
             is_gpu: true,
-        }
+        };
+        self.apply_preset(config, false, None)
     }
 
-    fn build_cpu_config_recording(&self) -> EncoderConfig {
+    pub fn build_cpu_config_recording(&self) -> EncoderConfig {
         let args = vec![
             "-c:v".to_string(), self.settings.cpuEncoder.clone(),
             "-preset".to_string(), self.settings.preset.clone(),
         ];
 
-        EncoderConfig {
+        let config = EncoderConfig {
             codec: self.settings.cpuEncoder.clone(),
             args,
             is_gpu: false,
-        }
+        };
+        self.apply_preset(config, false, None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(text: &str) -> Vec<String> {
+        text.lines().map(|line| line.to_string()).collect()
+    }
+
+    #[test]
+    fn finds_hardware_encoder_failures() {
+        let stderr = lines("frame= 1200 fps= 15\n[h264_nvenc @ 0x5581] OpenEncodeSessionEx failed: out of memory (10)\nConversion failed!");
+        assert_eq!(encoder_failure(&stderr, "h264_nvenc"), Some("[h264_nvenc @ 0x5581] OpenEncodeSessionEx failed: out of memory (10)"));
+
+        let stderr = lines("[AVHWDeviceContext @ 0x55] Failed to initialise VAAPI connection: -1 (unknown libva error).");
+        assert!(encoder_failure(&stderr, "h264_vaapi").is_some());
+    }
+
+    #[test]
+    fn ignores_input_failures() {
+        let stderr = lines("[h264_nvenc @ 0x5581] Using preset p1, deprecated\nStream #0:0 -> #0:0 (h264 (native) -> h264 (h264_nvenc))\n[rtsp @ 0x5581] method DESCRIBE failed: 401 Unauthorized");
+        assert_eq!(encoder_failure(&stderr, "h264_nvenc"), None);
+    }
+
+    #[test]
+    fn quality_preset_overrides_cpu_options() {
+        let capabilities = GpuCapabilities {
+            availableEncoders: Vec::new(),
+            preferredEncoder: None,
+            gpuType: "none".to_string(),
+            gpuName: None,
+        };
+        let selector = EncoderSelector::new(capabilities, EncoderSettings::default())
+            .with_preset(quality_preset("low_latency"));
+        let args = selector.build_cpu_config_streaming(Some(15)).args;
+        let value = |option: &str| args.iter().position(|arg| arg == option).map(|index| args[index + 1].as_str());
+
+        assert_eq!(value("-preset"), Some("ultrafast"));
+        assert_eq!(value("-g"), Some("15"));
+        assert_eq!(value("-keyint_min"), Some("15"));
+        assert_eq!(value("-crf"), Some("26"));
+        assert_eq!(value("-maxrate"), Some("2000k"));
+        assert_eq!(args.iter().filter(|arg| *arg == "-g").count(), 1);
+        assert_eq!(selector.audio_args(true), vec!["-an"]);
+        assert_eq!(selector.with_preset(None).audio_args(false), vec!["-c:a", "aac"]);
+    }
+
+    #[test]
+    fn renditions_reuse_the_encoder_at_a_lower_rate() {
+        let base = EncoderConfig {
+            codec: "h264_qsv".to_string(),
+            args: lines("-init_hw_device\nqsv=hw\n-filter_hw_device\nhw\n-c:v\nh264_qsv\n-b:v\n4M\n-maxrate\n4M\n-bufsize\n2M"),
+            is_gpu: true,
+        };
+        assert_eq!(config_kbps(&base), Some(4000));
+
+        let rendition = rendition_config(&base, rendition_kbps(480));
+        assert_eq!(rendition.args, lines("-c:v\nh264_qsv\n-b:v\n1200k\n-maxrate\n1200k\n-bufsize\n1200k"));
+        assert_eq!(config_kbps(&rendition), Some(1200));
     }
 }
@@ -0,0 +1,302 @@
+use crate::camera_plugin::CameraInfo;
+use crate::models::{ActivityReport, ArchiveJob, BackendHeartbeat, BackgroundJob, ResourceUsage, Snapshot, UploadJob};
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Query, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::Router;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use tokio::sync::broadcast;
+
+// Events buffered per WebSocket client; a client further behind than this skips events
+const WS_CHANNEL_CAPACITY: usize = 256;
+// How often free space in the recordings directory is checked
+const DISK_CHECK_INTERVAL_SECS: u64 = 60;
+// DiskLow is sent when free space drops below this, and again only after it has recovered
+const DISK_LOW_BYTES: u64 = 2 * 1024 * 1024 * 1024;
+
+/// Everything the backend reports to the UI. Each variant has a fixed Tauri event name
+/// and a camelCase JSON payload; WebSocket clients receive `{"event": name, "payload": ...}`.
+#[derive(Debug, Clone)]
+pub enum AppEvent {
+    RecordingStarted { camera_id: i32, recording_id: i64 },
+    RecordingCompleted { camera_id: i32, recording_id: i32 },
+    RecordingUpdated { camera_id: i32, recording_id: i32 },
+    RecordingProgress { camera_id: i32, recording_id: i32, elapsed_secs: i64, bytes: u64 },
+    RecordingFailed { camera_id: i32, error: String },
+    RecordingPaused { camera_id: i32, session_id: i64 },
+    RecordingResumed { camera_id: i32, session_id: i64, recording_id: i64 },
+    StreamStarted { camera_id: i32 },
+    StreamDied { camera_id: i32, reason: String },
+    StreamDegraded { camera_id: i32, stale_secs: u64 },
+    EncoderFallback { camera_id: i32, encoder: String, reason: String },
+    StreamDowngraded { camera_id: i32, level: u8 },
+    CameraOnline { camera_id: i32 },
+    CameraOffline { camera_id: i32, reason: String },
+    ScheduleFired { schedule_id: i32, camera_id: i32, name: String },
+    ScheduleDeferred { schedule_id: i32, camera_id: i32, name: String, reason: String },
+    SnapshotCaptured(Snapshot),
+    AudioDetected { camera_id: i32, event_id: i64, threshold_db: f64 },
+    MotionDetected { camera_id: i32, event_id: i64, score: f64 },
+    ObjectDetected { camera_id: i32, event_id: i64, labels: Vec<String>, image: String },
+    TriggerReceived { camera_id: i32, event_id: i64, action: String, reason: String },
+    CameraAlarm { camera_id: i32, event_id: i64, kind: String },
+    DiskLow { path: String, available_bytes: u64, total_bytes: u64 },
+    DeviceDiscovered { scan_id: String, camera: Box<CameraInfo> },
+    DiscoveryFinished { scan_id: String, found: usize, cancelled: bool },
+    RecordingsBatchProgress { operation: String, id: i32, completed: usize, total: usize, error: Option<String> },
+    ArchiveProgress(ArchiveJob),
+    UploadProgress(UploadJob),
+    JobProgress(BackgroundJob),
+    SettingsChanged { keys: Vec<String>, restart_required: bool },
+    LibraryChanged { missing: Vec<i32>, restored: Vec<i32>, purged: Vec<i32> },
+    ReportReady { period: String, report: Box<ActivityReport> },
+    ResourceUsage(Box<ResourceUsage>),
+    BackendHeartbeat(BackendHeartbeat),
+}
+
+impl AppEvent {
+    pub fn name(&self) -> &'static str {
+        match self {
+            AppEvent::RecordingStarted { .. } => "recording-started",
+            AppEvent::RecordingCompleted { .. } => "recording-completed",
+            AppEvent::RecordingUpdated { .. } => "recording-updated",
+            AppEvent::RecordingProgress { .. } => "recording-progress",
+            AppEvent::RecordingFailed { .. } => "recording-failed",
+            AppEvent::RecordingPaused { .. } => "recording-paused",
+            AppEvent::RecordingResumed { .. } => "recording-resumed",
+            AppEvent::StreamStarted { .. } => "stream-started",
+            AppEvent::StreamDied { .. } => "stream-died",
+            AppEvent::StreamDegraded { .. } => "stream-degraded",
+            AppEvent::EncoderFallback { .. } => "encoder-fallback",
+            AppEvent::StreamDowngraded { .. } => "stream-downgraded",
+            AppEvent::CameraOnline { .. } => "camera-online",
+            AppEvent::CameraOffline { .. } => "camera-offline",
+            AppEvent::ScheduleFired { .. } => "schedule-fired",
+            AppEvent::ScheduleDeferred { .. } => "schedule-deferred",
+            AppEvent::SnapshotCaptured(_) => "snapshot-captured",
+            AppEvent::AudioDetected { .. } => "audio-detected",
+            AppEvent::MotionDetected { .. } => "motion-detected",
+            AppEvent::ObjectDetected { .. } => "object-detected",
+            AppEvent::TriggerReceived { .. } => "trigger-received",
+            AppEvent::CameraAlarm { .. } => "camera-alarm",
+            AppEvent::DiskLow { .. } => "disk-low",
+            AppEvent::DeviceDiscovered { .. } => "device-discovered",
+            AppEvent::DiscoveryFinished { .. } => "discovery-finished",
+            AppEvent::RecordingsBatchProgress { .. } => "recordings-batch-progress",
+            AppEvent::ArchiveProgress(_) => "archive-progress",
+            AppEvent::UploadProgress(_) => "upload-progress",
+            AppEvent::JobProgress(_) => "job-progress",
+            AppEvent::SettingsChanged { .. } => "settings-changed",
+            AppEvent::LibraryChanged { .. } => "library-changed",
+            AppEvent::ReportReady { .. } => "report-ready",
+            AppEvent::ResourceUsage(_) => "resource-usage",
+            AppEvent::BackendHeartbeat(_) => "backend-heartbeat",
+        }
+    }
+
+    pub fn payload(&self) -> Value {
+        match self {
+            AppEvent::RecordingStarted { camera_id, recording_id } => {
+                json!({ "cameraId": camera_id, "recordingId": recording_id })
+            }
+            AppEvent::RecordingCompleted { camera_id, recording_id } => {
+                json!({ "cameraId": camera_id, "recordingId": recording_id })
+            }
+            AppEvent::RecordingUpdated { camera_id, recording_id } => {
+                json!({ "cameraId": camera_id, "recordingId": recording_id })
+            }
+            AppEvent::RecordingProgress { camera_id, recording_id, elapsed_secs, bytes } => {
+                json!({ "cameraId": camera_id, "recordingId": recording_id, "elapsedSecs": elapsed_secs, "bytes": bytes })
+            }
+            AppEvent::RecordingFailed { camera_id, error } => json!({ "cameraId": camera_id, "error": error }),
+            AppEvent::RecordingPaused { camera_id, session_id } => {
+                json!({ "cameraId": camera_id, "sessionId": session_id })
+            }
+            AppEvent::RecordingResumed { camera_id, session_id, recording_id } => {
+                json!({ "cameraId": camera_id, "sessionId": session_id, "recordingId": recording_id })
+            }
+            AppEvent::StreamStarted { camera_id } => json!({ "cameraId": camera_id }),
+            AppEvent::StreamDied { camera_id, reason } => json!({ "cameraId": camera_id, "reason": reason }),
+            AppEvent::StreamDegraded { camera_id, stale_secs } => json!({ "cameraId": camera_id, "staleSecs": stale_secs }),
+            AppEvent::StreamDowngraded { camera_id, level } => json!({ "cameraId": camera_id, "level": level }),
+            AppEvent::EncoderFallback { camera_id, encoder, reason } => {
+                json!({ "cameraId": camera_id, "encoder": encoder, "reason": reason })
+            }
+            AppEvent::CameraOnline { camera_id } => json!({ "cameraId": camera_id }),
+            AppEvent::CameraOffline { camera_id, reason } => json!({ "cameraId": camera_id, "reason": reason }),
+            AppEvent::ScheduleFired { schedule_id, camera_id, name } => {
+                json!({ "scheduleId": schedule_id, "cameraId": camera_id, "name": name })
+            }
+            AppEvent::ScheduleDeferred { schedule_id, camera_id, name, reason } => {
+                json!({ "scheduleId": schedule_id, "cameraId": camera_id, "name": name, "reason": reason })
+            }
+            AppEvent::SnapshotCaptured(snapshot) => json!(snapshot),
+            AppEvent::AudioDetected { camera_id, event_id, threshold_db } => {
+                json!({ "cameraId": camera_id, "eventId": event_id, "thresholdDb": threshold_db })
+            }
+            AppEvent::MotionDetected { camera_id, event_id, score } => {
+                json!({ "cameraId": camera_id, "eventId": event_id, "score": score })
+            }
+            AppEvent::ObjectDetected { camera_id, event_id, labels, image } => {
+                json!({ "cameraId": camera_id, "eventId": event_id, "labels": labels, "image": image })
+            }
+            AppEvent::TriggerReceived { camera_id, event_id, action, reason } => {
+                json!({ "cameraId": camera_id, "eventId": event_id, "action": action, "reason": reason })
+            }
+            AppEvent::CameraAlarm { camera_id, event_id, kind } => {
+                json!({ "cameraId": camera_id, "eventId": event_id, "kind": kind })
+            }
+            AppEvent::DiskLow { path, available_bytes, total_bytes } => {
+                json!({ "path": path, "availableBytes": available_bytes, "totalBytes": total_bytes })
+            }
+            AppEvent::DeviceDiscovered { scan_id, camera } => json!({ "scanId": scan_id, "camera": camera }),
+            AppEvent::DiscoveryFinished { scan_id, found, cancelled } => {
+                json!({ "scanId": scan_id, "found": found, "cancelled": cancelled })
+            }
+            AppEvent::RecordingsBatchProgress { operation, id, completed, total, error } => {
+                json!({ "operation": operation, "id": id, "completed": completed, "total": total, "error": error })
+            }
+            AppEvent::ArchiveProgress(job) => json!(job),
+            AppEvent::UploadProgress(job) => json!(job),
+            AppEvent::JobProgress(job) => json!(job),
+            AppEvent::SettingsChanged { keys, restart_required } => {
+                json!({ "keys": keys, "restartRequired": restart_required })
+            }
+            AppEvent::LibraryChanged { missing, restored, purged } => {
+                json!({ "missing": missing, "restored": restored, "purged": purged })
+            }
+            AppEvent::ReportReady { period, report } => json!({ "period": period, "report": report }),
+            AppEvent::ResourceUsage(usage) => json!(usage),
+            AppEvent::BackendHeartbeat(beat) => json!(beat),
+        }
+    }
+}
+
+/// Single emit path for backend events: Tauri events for the app window plus a
+/// broadcast channel feeding `/events` WebSocket clients
+#[derive(Clone)]
+pub struct EventBus {
+    app_handle: Option<AppHandle>,
+    sender: broadcast::Sender<String>,
+}
+
+impl EventBus {
+    pub fn new(app_handle: AppHandle) -> Self {
+        let (sender, _) = broadcast::channel(WS_CHANNEL_CAPACITY);
+        Self { app_handle: Some(app_handle), sender }
+    }
+
+    /// Bus without an app window, for tests; events only reach subscribers
+    #[cfg(test)]
+    pub fn detached() -> Self {
+        let (sender, _) = broadcast::channel(WS_CHANNEL_CAPACITY);
+        Self { app_handle: None, sender }
+    }
+
+    pub fn emit(&self, event: AppEvent) {
+        let name = event.name();
+        let payload = event.payload();
+
+        if let Some(Err(e)) = self.app_handle.as_ref().map(|app_handle| app_handle.emit(name, &payload)) {
+            eprintln!("[Event] Warning: Failed to emit {} event: {}", name, e);
+        }
+
+        // Fails only when no WebSocket client is connected
+        let _ = self.sender.send(json!({ "event": name, "payload": payload }).to_string());
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<String> {
+        self.sender.subscribe()
+    }
+}
+
+#[derive(Clone)]
+struct EventsState {
+    bus: EventBus,
+    token: String,
+    db_path: String,
+}
+
+/// `/events` WebSocket endpoint, authenticated like downloads (`?token=` since
+/// browsers can't set headers on WebSocket requests)
+pub fn router(bus: EventBus, token: String, db_path: String) -> Router {
+    Router::new()
+        .route("/events", get(events_socket))
+        .with_state(EventsState { bus, token, db_path })
+}
+
+async fn events_socket(
+    State(state): State<EventsState>,
+    Query(query): Query<HashMap<String, String>>,
+    headers: HeaderMap,
+    ws: WebSocketUpgrade,
+) -> Response {
+    if !crate::auth::authorize_request(&state.db_path, &state.token, &headers, &query, crate::auth::Role::Viewer) {
+        return (StatusCode::UNAUTHORIZED, "Missing or invalid token").into_response();
+    }
+
+    let receiver = state.bus.subscribe();
+    ws.on_upgrade(move |socket| forward_events(socket, receiver))
+}
+
+async fn forward_events(mut socket: WebSocket, mut receiver: broadcast::Receiver<String>) {
+    println!("[Event] WebSocket client connected");
+
+    loop {
+        tokio::select! {
+            event = receiver.recv() => match event {
+                Ok(text) => {
+                    if socket.send(Message::Text(text)).await.is_err() {
+                        break;
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    eprintln!("[Event] WebSocket client fell behind, skipped {} events", skipped);
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+            // Incoming messages are ignored; this only notices the client going away
+            incoming = socket.recv() => match incoming {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+
+    println!("[Event] WebSocket client disconnected");
+}
+
+/// Periodically check free space where recordings are written and send DiskLow when it runs short
+pub fn spawn_disk_monitor(bus: EventBus, recording_dir: PathBuf) {
+    tauri::async_runtime::spawn(async move {
+        let mut low = false;
+
+        loop {
+            match (fs4::available_space(&recording_dir), fs4::total_space(&recording_dir)) {
+                (Ok(available), Ok(total)) => {
+                    let now_low = available < DISK_LOW_BYTES;
+                    if now_low && !low {
+                        println!("[Disk] Low free space for recordings: {} MB left", available / (1024 * 1024));
+                        bus.emit(AppEvent::DiskLow {
+                            path: recording_dir.to_string_lossy().to_string(),
+                            available_bytes: available,
+                            total_bytes: total,
+                        });
+                    }
+                    low = now_low;
+                }
+                (Err(e), _) | (_, Err(e)) => {
+                    eprintln!("[Disk] Failed to check free space of {:?}: {}", recording_dir, e);
+                }
+            }
+
+            tokio::time::sleep(Duration::from_secs(DISK_CHECK_INTERVAL_SECS)).await;
+        }
+    });
+}
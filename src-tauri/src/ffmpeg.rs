@@ -0,0 +1,383 @@
+use crate::encoder::EncoderConfig;
+use crate::models::{Camera, VideoFilters};
+use std::path::Path;
+use std::process::Command;
+
+// Windows-specific imports for hiding console window
+#[cfg(target_os = "windows")]
+use std::os::windows::process::CommandExt;
+
+/// Platform whose capture devices FFmpeg reads; a parameter rather than cfg blocks so the
+/// arguments of every platform can be built (and tested) anywhere
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Platform {
+    Linux,
+    Windows,
+    MacOs,
+}
+
+impl Platform {
+    pub fn current() -> Self {
+        if cfg!(target_os = "windows") {
+            Platform::Windows
+        } else if cfg!(target_os = "macos") {
+            Platform::MacOs
+        } else {
+            Platform::Linux
+        }
+    }
+}
+
+/// Where FFmpeg reads from
+#[derive(Debug, Clone, Copy)]
+pub enum InputSource<'a> {
+    /// UVC capture device: a v4l2 path, DirectShow device name or AVFoundation index
+    Device { camera: &'a Camera, device: &'a str },
+    /// H.264 piped from rpicam-vid on stdin
+    Pipe { camera: &'a Camera },
+    /// RTSP or HTTP(S) stream of a network camera
+    Network { camera: &'a Camera, url: &'a str },
+    /// A recording or other local file
+    File(&'a Path),
+    /// File list of the concat demuxer, one `file '<path>'` line per file to join
+    ConcatList(&'a Path),
+}
+
+impl<'a> InputSource<'a> {
+    /// Input of a camera by type; `source` is what `stream::get_rtsp_url` returned for it
+    pub fn camera(camera: &'a Camera, source: &'a str) -> Self {
+        match camera.camera_type.as_str() {
+            "uvc" => InputSource::Device { camera, device: source },
+            "rpicam" => InputSource::Pipe { camera },
+            _ => InputSource::Network { camera, url: source },
+        }
+    }
+}
+
+/// Arguments of an FFmpeg run, in the order FFmpeg needs them: input options and the input,
+/// then filters, encoder and output options, then the output. Methods append at the current
+/// position, so input options go before `input`.
+#[derive(Debug, Clone)]
+pub struct CommandBuilder {
+    platform: Platform,
+    low_latency: bool,
+    args: Vec<String>,
+}
+
+impl Default for CommandBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CommandBuilder {
+    /// Overwrites the output without asking
+    pub fn new() -> Self {
+        Self::for_platform(Platform::current())
+    }
+
+    pub fn for_platform(platform: Platform) -> Self {
+        CommandBuilder { platform, low_latency: false, args: vec!["-y".to_string()] }
+    }
+
+    /// Read live inputs with as little buffering as possible, for the live stream
+    pub fn low_latency(mut self) -> Self {
+        self.low_latency = true;
+        self
+    }
+
+    /// Start reading the next input `secs` seconds in
+    pub fn seek(self, secs: f64) -> Self {
+        self.args(["-ss".to_string(), format!("{:.3}", secs)])
+    }
+
+    pub fn input(self, source: InputSource) -> Self {
+        let args = match source {
+            InputSource::Device { camera, device } => device_input_args(self.platform, camera, device, self.low_latency),
+            InputSource::Pipe { camera } => crate::plugins::rpi_camera_plugin::input_args(camera),
+            InputSource::Network { camera, url } => {
+                let mut args = Vec::new();
+                if self.low_latency {
+                    args.extend(["-fflags".to_string(), "nobuffer".to_string()]);
+                }
+                args.extend(crate::stream::network_input_args(camera, url));
+                args.extend(["-i".to_string(), url.to_string()]);
+                args
+            }
+            InputSource::File(path) => vec!["-i".to_string(), path.to_string_lossy().to_string()],
+            InputSource::ConcatList(path) => ["-f", "concat", "-safe", "0", "-i"].into_iter()
+                .map(String::from)
+                .chain([path.to_string_lossy().to_string()])
+                .collect(),
+        };
+        self.args(args)
+    }
+
+    /// Output frame rate, unchanged when None
+    pub fn frame_rate(self, fps: Option<i32>) -> Self {
+        match fps {
+            Some(fps) => self.args(["-r".to_string(), fps.to_string()]),
+            None => self,
+        }
+    }
+
+    /// The camera's video filters, nothing when it has none
+    pub fn filters(self, filters: &VideoFilters) -> Self {
+        self.filters_with(filters, None)
+    }
+
+    /// The camera's video filters followed by `extra`, in one chain since FFmpeg only applies
+    /// the last -vf of an output
+    pub fn filters_with(self, filters: &VideoFilters, extra: Option<&str>) -> Self {
+        let chain: Vec<String> = crate::stream::video_filter_chain(filters).into_iter()
+            .chain(extra.map(String::from))
+            .collect();
+        match chain.is_empty() {
+            true => self,
+            false => self.video_filter(chain.join(",")),
+        }
+    }
+
+    pub fn video_filter(self, chain: impl Into<String>) -> Self {
+        self.args(["-vf".to_string(), chain.into()])
+    }
+
+    /// Filter graph over several inputs; its outputs are picked with -map
+    pub fn filter_complex(self, graph: impl Into<String>) -> Self {
+        self.args(["-filter_complex".to_string(), graph.into()])
+    }
+
+    /// Stop writing after `secs` seconds of output
+    pub fn duration(self, secs: f64) -> Self {
+        self.args(["-t".to_string(), format!("{:.3}", secs)])
+    }
+
+    pub fn encoder(self, config: &EncoderConfig) -> Self {
+        self.args(config.args.iter().cloned())
+    }
+
+    /// Output format of a recording container, see `stream::RECORDING_CONTAINERS`
+    pub fn container(self, container: &str) -> Self {
+        self.args(container_args(container))
+    }
+
+    /// Index at the front of an MP4 `target` so that it plays while still downloading;
+    /// nothing for other containers
+    pub fn faststart(self, target: &Path) -> Self {
+        match target.extension().is_some_and(|ext| ext == "mp4") {
+            true => self.args(["-movflags", "+faststart"]),
+            false => self,
+        }
+    }
+
+    pub fn output(self, path: &Path) -> Self {
+        self.arg(path.to_string_lossy())
+    }
+
+    pub fn arg(mut self, arg: impl Into<String>) -> Self {
+        self.args.push(arg.into());
+        self
+    }
+
+    pub fn args<I, S>(mut self, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.args.extend(args.into_iter().map(Into::into));
+        self
+    }
+
+    pub fn build(self) -> Vec<String> {
+        self.args
+    }
+
+    /// FFmpeg with the arguments, without a console window on Windows
+    pub fn command(self) -> Command {
+        let mut cmd = command();
+        cmd.args(self.args);
+        cmd
+    }
+}
+
+/// `ffmpeg` without arguments, without a console window on Windows
+pub fn command() -> Command {
+    #[allow(unused_mut)]
+    let mut cmd = Command::new("ffmpeg");
+    #[cfg(target_os = "windows")]
+    {
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        cmd.creation_flags(CREATE_NO_WINDOW);
+    }
+    cmd
+}
+
+// Capture device input with the format, resolution and frame rate detected for the camera.
+// Detection only exists for v4l2 so far.
+fn device_input_args(platform: Platform, camera: &Camera, device: &str, low_latency: bool) -> Vec<String> {
+    let mut args: Vec<String> = Vec::new();
+    match platform {
+        Platform::Linux => {
+            // Ignore MJPEG decode errors (APP field issues) and generate missing timestamps
+            args.extend(["-err_detect", "ignore_err"].map(String::from));
+            if low_latency {
+                args.extend(["-fflags", "nobuffer+genpts", "-flags", "low_delay"].map(String::from));
+            } else {
+                args.extend(["-fflags", "+genpts"].map(String::from));
+            }
+            args.extend(["-avoid_negative_ts", "make_zero"].map(String::from));
+            if let Some(format) = &camera.video_format {
+                args.extend(["-input_format".to_string(), format.clone()]);
+            }
+            if let (Some(width), Some(height)) = (camera.video_width, camera.video_height) {
+                args.extend(["-video_size".to_string(), format!("{}x{}", width, height)]);
+            }
+            if let Some(fps) = camera.video_fps {
+                args.extend(["-framerate".to_string(), fps.to_string()]);
+            }
+            args.extend(["-f".to_string(), "v4l2".to_string(), "-i".to_string(), device.to_string()]);
+        }
+        Platform::Windows | Platform::MacOs => {
+            if low_latency {
+                args.extend(["-fflags", "nobuffer", "-flags", "low_delay"].map(String::from));
+            }
+            let (format, input) = match platform {
+                Platform::Windows => ("dshow", format!("video={}", device)),
+                _ => ("avfoundation", device.to_string()),
+            };
+            args.extend(["-f".to_string(), format.to_string(), "-i".to_string(), input]);
+        }
+    }
+    args
+}
+
+// FFmpeg output format of a recording. Fragmented MP4 writes a moov up front and flushes a
+// fragment at every keyframe, so a killed FFmpeg leaves a playable file.
+fn container_args(container: &str) -> Vec<String> {
+    let args: &[&str] = match container {
+        "fmp4" => &["-f", "mp4", "-movflags", "+frag_keyframe+empty_moov+default_base_moof"],
+        "mkv" => &["-f", "matroska"],
+        _ => &["-f", "mpegts"],
+    };
+    args.iter().map(|arg| arg.to_string()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn camera(camera_type: &str) -> Camera {
+        serde_json::from_value(serde_json::json!({
+            "id": 1, "name": "Gate", "type": camera_type, "host": "10.0.0.5", "port": 554,
+            "tls_verify": false, "rtsp_transport": "tcp", "sort_order": 0, "is_favorite": false,
+            "created_at": "2024-05-01T00:00:00Z", "updated_at": "2024-05-01T00:00:00Z",
+        })).unwrap()
+    }
+
+    fn uvc() -> Camera {
+        let mut camera = camera("uvc");
+        camera.video_format = Some("mjpeg".to_string());
+        camera.video_width = Some(1280);
+        camera.video_height = Some(720);
+        camera.video_fps = Some(30);
+        camera
+    }
+
+    fn input(platform: Platform, low_latency: bool, camera: &Camera, source: &str) -> Vec<String> {
+        let builder = CommandBuilder::for_platform(platform);
+        let builder = if low_latency { builder.low_latency() } else { builder };
+        builder.input(InputSource::camera(camera, source)).build()
+    }
+
+    #[test]
+    fn uvc_inputs_per_platform() {
+        let camera = uvc();
+        assert_eq!(input(Platform::Linux, true, &camera, "/dev/video0"), [
+            "-y", "-err_detect", "ignore_err", "-fflags", "nobuffer+genpts", "-flags", "low_delay",
+            "-avoid_negative_ts", "make_zero", "-input_format", "mjpeg", "-video_size", "1280x720",
+            "-framerate", "30", "-f", "v4l2", "-i", "/dev/video0",
+        ]);
+        assert_eq!(input(Platform::Linux, false, &camera, "/dev/video0"), [
+            "-y", "-err_detect", "ignore_err", "-fflags", "+genpts", "-avoid_negative_ts", "make_zero",
+            "-input_format", "mjpeg", "-video_size", "1280x720", "-framerate", "30", "-f", "v4l2", "-i", "/dev/video0",
+        ]);
+        assert_eq!(input(Platform::Windows, true, &camera, "USB Camera"), [
+            "-y", "-fflags", "nobuffer", "-flags", "low_delay", "-f", "dshow", "-i", "video=USB Camera",
+        ]);
+        assert_eq!(input(Platform::Windows, false, &camera, "USB Camera"), ["-y", "-f", "dshow", "-i", "video=USB Camera"]);
+        assert_eq!(input(Platform::MacOs, true, &camera, "0"), [
+            "-y", "-fflags", "nobuffer", "-flags", "low_delay", "-f", "avfoundation", "-i", "0",
+        ]);
+        assert_eq!(input(Platform::MacOs, false, &camera, "0"), ["-y", "-f", "avfoundation", "-i", "0"]);
+    }
+
+    #[test]
+    fn undetected_uvc_settings_are_left_to_ffmpeg() {
+        assert_eq!(input(Platform::Linux, false, &camera("uvc"), "/dev/video2"), [
+            "-y", "-err_detect", "ignore_err", "-fflags", "+genpts", "-avoid_negative_ts", "make_zero",
+            "-f", "v4l2", "-i", "/dev/video2",
+        ]);
+    }
+
+    #[test]
+    fn network_and_pipe_inputs() {
+        let url = "rtsp://10.0.0.5:554/live";
+        for platform in [Platform::Linux, Platform::Windows, Platform::MacOs] {
+            assert_eq!(input(platform, true, &camera("onvif"), url), [
+                "-y", "-fflags", "nobuffer", "-rtsp_transport", "tcp", "-i", url,
+            ]);
+            assert_eq!(input(platform, false, &camera("rtsp"), url), ["-y", "-rtsp_transport", "tcp", "-i", url]);
+        }
+        let mut rpicam = camera("rpicam");
+        rpicam.video_fps = Some(25);
+        assert_eq!(input(Platform::Linux, false, &rpicam, "rpicam:0"), [
+            "-y", "-fflags", "+genpts", "-f", "h264", "-framerate", "25", "-i", "pipe:0",
+        ]);
+    }
+
+    #[test]
+    fn recording_command() {
+        let mut camera = camera("rtsp");
+        camera.video_filters = VideoFilters { rotation: 180, ..Default::default() };
+        let encoder = EncoderConfig {
+            codec: "libx264".to_string(),
+            args: ["-c:v", "libx264", "-preset", "veryfast"].map(String::from).to_vec(),
+            is_gpu: false,
+        };
+        let args = CommandBuilder::for_platform(Platform::Linux)
+            .input(InputSource::camera(&camera, "rtsp://10.0.0.5/live"))
+            .frame_rate(Some(15))
+            .filters_with(&camera.video_filters, Some("scale=-2:540"))
+            .encoder(&encoder)
+            .args(["-c:a", "aac"])
+            .container("fmp4")
+            .output(Path::new("/recordings/rec_1.mp4"))
+            .build();
+        assert_eq!(args, [
+            "-y", "-rtsp_transport", "tcp", "-i", "rtsp://10.0.0.5/live", "-r", "15", "-vf", "hflip,vflip,scale=-2:540",
+            "-c:v", "libx264", "-preset", "veryfast", "-c:a", "aac",
+            "-f", "mp4", "-movflags", "+frag_keyframe+empty_moov+default_base_moof", "/recordings/rec_1.mp4",
+        ]);
+    }
+
+    #[test]
+    fn file_commands() {
+        let remux = CommandBuilder::for_platform(Platform::Windows)
+            .input(InputSource::File(Path::new("temp_rec_1.ts")))
+            .args(["-c", "copy", "-movflags", "+faststart"])
+            .output(Path::new("rec_1.mp4"))
+            .build();
+        assert_eq!(remux, ["-y", "-i", "temp_rec_1.ts", "-c", "copy", "-movflags", "+faststart", "rec_1.mp4"]);
+
+        let thumbnail = CommandBuilder::for_platform(Platform::Linux)
+            .seek(2.0)
+            .input(InputSource::File(Path::new("rec_1.mp4")))
+            .args(["-vframes", "1"])
+            .video_filter("scale=320:-1")
+            .output(Path::new("rec_1.jpg"))
+            .build();
+        assert_eq!(thumbnail, ["-y", "-ss", "2.000", "-i", "rec_1.mp4", "-vframes", "1", "-vf", "scale=320:-1", "rec_1.jpg"]);
+        assert_eq!(CommandBuilder::new().container("mkv").build(), ["-y", "-f", "matroska"]);
+        assert_eq!(CommandBuilder::new().container("mp4").build(), ["-y", "-f", "mpegts"]);
+    }
+}
@@ -1,3 +1,4 @@
+use crate::models::GpuUsage;
 use serde::{Deserialize, Serialize};
 use std::process::Command;
 
@@ -366,3 +367,185 @@ pub async fn test_encoder(encoder: &str) -> bool {
         }
     }
 }
+
+/// Current load of every GPU a vendor tool can report on (nvidia-smi, intel_gpu_top, amdgpu
+/// sysfs). Blocking: intel_gpu_top needs about a second to take a sample.
+pub fn sample_gpu_usage() -> Vec<GpuUsage> {
+    let mut gpus = sample_nvidia_usage();
+    #[cfg(target_os = "linux")]
+    {
+        gpus.extend(sample_intel_usage());
+        gpus.extend(sample_amd_usage());
+    }
+    for (index, gpu) in gpus.iter_mut().enumerate() {
+        gpu.index = index as u32;
+    }
+    gpus
+}
+
+fn sample_nvidia_usage() -> Vec<GpuUsage> {
+    let mut cmd = Command::new("nvidia-smi");
+    cmd.args([
+        "--query-gpu=name,utilization.gpu,utilization.encoder,utilization.decoder,memory.used,memory.total,temperature.gpu",
+        "--format=csv,noheader,nounits",
+    ]);
+
+    // Hide console window on Windows
+    #[cfg(target_os = "windows")]
+    {
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        cmd.creation_flags(CREATE_NO_WINDOW);
+    }
+
+    match cmd.output() {
+        Ok(output) if output.status.success() => parse_nvidia_usage(&String::from_utf8_lossy(&output.stdout)),
+        _ => Vec::new(),
+    }
+}
+
+// One line per GPU; memory is in MiB and unsupported values read "[N/A]" or "[Not Supported]"
+fn parse_nvidia_usage(csv: &str) -> Vec<GpuUsage> {
+    const MIB: u64 = 1024 * 1024;
+    csv.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            let number = |index: usize| fields.get(index).and_then(|value| value.parse::<f32>().ok());
+            GpuUsage {
+                index: 0,
+                vendor: "NVIDIA".to_string(),
+                name: fields.first().filter(|name| !name.is_empty()).map(|name| name.to_string()),
+                utilization_percent: number(1),
+                encoder_percent: number(2),
+                decoder_percent: number(3),
+                memory_used_bytes: number(4).map(|mib| mib as u64 * MIB),
+                memory_total_bytes: number(5).map(|mib| mib as u64 * MIB),
+                temperature_c: number(6),
+            }
+        })
+        .collect()
+}
+
+// intel_gpu_top only streams samples, so read it for a moment and keep the last one
+#[cfg(target_os = "linux")]
+fn sample_intel_usage() -> Option<GpuUsage> {
+    let mut child = Command::new("intel_gpu_top")
+        .args(["-J", "-s", "500"])
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::null())
+        .spawn()
+        .ok()?;
+    std::thread::sleep(std::time::Duration::from_millis(1200));
+    let _ = child.kill();
+    let output = child.wait_with_output().ok()?;
+    parse_intel_gpu_top(&String::from_utf8_lossy(&output.stdout))
+}
+
+// Output is a JSON array that is never closed: "[ {sample}, {sample}, ..."
+fn parse_intel_gpu_top(text: &str) -> Option<GpuUsage> {
+    let mut rest = text.trim_start().strip_prefix('[')?;
+    let mut last = None;
+    loop {
+        rest = rest.trim_start().trim_start_matches(',');
+        let mut samples = serde_json::Deserializer::from_str(rest).into_iter::<serde_json::Value>();
+        match samples.next() {
+            Some(Ok(sample)) => {
+                let consumed = samples.byte_offset();
+                last = Some(sample);
+                rest = &rest[consumed..];
+            }
+            // End of the output or a sample cut off by the kill
+            _ => break,
+        }
+    }
+
+    let engines = last?.get("engines")?.as_object()?.clone();
+    let busy = |engine: fn(&str) -> bool| {
+        engines.iter()
+            .filter(|(name, _)| engine(name))
+            .filter_map(|(_, engine)| engine["busy"].as_f64())
+            .fold(None, |max: Option<f64>, busy| Some(max.map_or(busy, |max| max.max(busy))))
+    };
+    Some(GpuUsage {
+        index: 0,
+        vendor: "Intel".to_string(),
+        name: None,
+        utilization_percent: busy(|_| true).map(|busy| busy as f32),
+        // "Video/0" is the engine QSV encodes and decodes on ("VideoEnhance" is its scaler)
+        encoder_percent: busy(|name| name.starts_with("Video") && !name.starts_with("VideoEnhance")).map(|busy| busy as f32),
+        decoder_percent: None,
+        memory_used_bytes: None,
+        memory_total_bytes: None,
+        temperature_c: None,
+    })
+}
+
+// amdgpu exposes its load in sysfs: /sys/class/drm/cardN/device/gpu_busy_percent
+#[cfg(target_os = "linux")]
+fn sample_amd_usage() -> Vec<GpuUsage> {
+    let read = |path: &std::path::Path| std::fs::read_to_string(path).ok().map(|text| text.trim().to_string());
+    let Ok(cards) = std::fs::read_dir("/sys/class/drm") else { return Vec::new() };
+
+    let mut devices: Vec<_> = cards
+        .filter_map(|card| card.ok())
+        .filter(|card| {
+            let name = card.file_name().to_string_lossy().to_string();
+            // "card0", not connectors such as "card0-HDMI-A-1"
+            name.starts_with("card") && !name.contains('-')
+        })
+        .map(|card| card.path().join("device"))
+        .filter(|device| read(&device.join("vendor")).as_deref() == Some("0x1002"))
+        .collect();
+    devices.sort();
+
+    devices.into_iter()
+        .filter_map(|device| {
+            let utilization: f32 = read(&device.join("gpu_busy_percent"))?.parse().ok()?;
+            let bytes = |file: &str| read(&device.join(file)).and_then(|value| value.parse::<u64>().ok());
+            // hwmon reports millidegrees
+            let temperature = std::fs::read_dir(device.join("hwmon")).ok()
+                .and_then(|mut hwmons| hwmons.next())
+                .and_then(|hwmon| read(&hwmon.ok()?.path().join("temp1_input")))
+                .and_then(|value| value.parse::<f32>().ok())
+                .map(|millidegrees| millidegrees / 1000.0);
+            Some(GpuUsage {
+                index: 0,
+                vendor: "AMD".to_string(),
+                name: None,
+                utilization_percent: Some(utilization),
+                encoder_percent: None,
+                decoder_percent: None,
+                memory_used_bytes: bytes("mem_info_vram_used"),
+                memory_total_bytes: bytes("mem_info_vram_total"),
+                temperature_c: temperature,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_nvidia_smi_rows() {
+        let gpus = parse_nvidia_usage("NVIDIA GeForce RTX 3060, 41, 87, [N/A], 1024, 12288, 63\n");
+        assert_eq!(gpus.len(), 1);
+        assert_eq!(gpus[0].name.as_deref(), Some("NVIDIA GeForce RTX 3060"));
+        assert_eq!(gpus[0].encoder_percent, Some(87.0));
+        assert_eq!(gpus[0].decoder_percent, None);
+        assert_eq!(gpus[0].memory_total_bytes, Some(12288 * 1024 * 1024));
+    }
+
+    #[test]
+    fn keeps_the_last_complete_intel_gpu_top_sample() {
+        let text = r#"[
+{ "engines": { "Render/3D/0": { "busy": 10.0 }, "Video/0": { "busy": 20.0 } } },
+{ "engines": { "Render/3D/0": { "busy": 5.0 }, "Video/0": { "busy": 75.5 }, "VideoEnhance/0": { "busy": 1.0 } } },
+{ "engines": { "Render/3D/0": { "bu"#;
+        let gpu = parse_intel_gpu_top(text).unwrap();
+        assert_eq!(gpu.utilization_percent, Some(75.5));
+        assert_eq!(gpu.encoder_percent, Some(75.5));
+        assert!(parse_intel_gpu_top("").is_none());
+    }
+}
@@ -0,0 +1,99 @@
+use crate::events::AppEvent;
+use crate::models::BackendHeartbeat;
+use crate::AppState;
+use chrono::Utc;
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+
+// Seconds between backend-heartbeat events; the UI treats a few missed beats as a dead backend
+const HEARTBEAT_INTERVAL_SECS: u64 = 5;
+// Checking for FFmpeg starts a process, so its result is reused for this long
+const FFMPEG_CHECK_SECS: u64 = 60;
+
+static STARTED_AT: LazyLock<Instant> = LazyLock::new(Instant::now);
+static FFMPEG_CHECK: Mutex<Option<(Instant, bool)>> = Mutex::new(None);
+
+/// Send a `backend-heartbeat` event every few seconds for as long as the backend runs
+pub fn spawn_heartbeat(app_handle: AppHandle) {
+    LazyLock::force(&STARTED_AT);
+    tauri::async_runtime::spawn(async move {
+        let mut last_problems = Vec::new();
+        loop {
+            let state = app_handle.state::<AppState>().inner().clone();
+            let beat = match tauri::async_runtime::spawn_blocking(move || {
+                let beat = heartbeat(&state);
+                state.events.emit(AppEvent::BackendHeartbeat(beat.clone()));
+                beat
+            }).await {
+                Ok(beat) => beat,
+                Err(e) => {
+                    eprintln!("[Heartbeat] Check failed: {}", e);
+                    tokio::time::sleep(Duration::from_secs(HEARTBEAT_INTERVAL_SECS)).await;
+                    continue;
+                }
+            };
+            // Logged when they change, not on every beat
+            if beat.problems != last_problems {
+                if beat.problems.is_empty() {
+                    println!("[Heartbeat] Backend healthy again");
+                } else {
+                    println!("[Heartbeat] Backend degraded: {}", beat.problems.join("; "));
+                }
+                last_problems = beat.problems;
+            }
+            tokio::time::sleep(Duration::from_secs(HEARTBEAT_INTERVAL_SECS)).await;
+        }
+    });
+}
+
+/// Health of the backend now: running pipelines, database and FFmpeg. Blocks for a
+/// database query and, once a minute, an `ffmpeg -version` run.
+pub fn heartbeat(state: &AppState) -> BackendHeartbeat {
+    let db_error = crate::db::open(&state.db_path)
+        .map_err(|e| e.to_string())
+        .and_then(|conn| conn.query_row("SELECT 1", [], |_| Ok(())).map_err(|e| e.to_string()))
+        .err();
+    let ffmpeg_available = ffmpeg_available();
+
+    let mut problems = Vec::new();
+    if let Some(e) = &db_error {
+        problems.push(format!("Database unavailable: {}", e));
+    }
+    if !ffmpeg_available {
+        problems.push("FFmpeg not found".to_string());
+    }
+
+    BackendHeartbeat {
+        sentAt: Utc::now(),
+        intervalSecs: HEARTBEAT_INTERVAL_SECS,
+        uptimeSecs: STARTED_AT.elapsed().as_secs(),
+        activeStreams: state.processes.lock().map(|processes| processes.len()).unwrap_or(0),
+        activeRecordings: state.recording_processes.lock().map(|processes| processes.len()).unwrap_or(0),
+        activePlaybacks: state.playback_processes.lock().map(|processes| processes.len()).unwrap_or(0),
+        dbOk: db_error.is_none(),
+        dbError: db_error,
+        ffmpegAvailable: ffmpeg_available,
+        problems,
+    }
+}
+
+fn ffmpeg_available() -> bool {
+    let mut check = FFMPEG_CHECK.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some((at, available)) = *check {
+        if at.elapsed() < Duration::from_secs(FFMPEG_CHECK_SECS) {
+            return available;
+        }
+    }
+    let mut cmd = std::process::Command::new("ffmpeg");
+    cmd.arg("-version").stdout(std::process::Stdio::null()).stderr(std::process::Stdio::null());
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        cmd.creation_flags(CREATE_NO_WINDOW);
+    }
+    let available = cmd.status().is_ok_and(|status| status.success());
+    *check = Some((Instant::now(), available));
+    available
+}
@@ -0,0 +1,304 @@
+use crate::models::{CameraHlsSettings, HlsSettings, TimeshiftInfo};
+use crate::pipeline::PipelineTracker;
+use crate::settings;
+use crate::AppState;
+use axum::extract::{Path as UrlPath, State};
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::Router;
+use chrono::{DateTime, Utc};
+use rusqlite::{Connection, OptionalExtension};
+use std::path::{Path, PathBuf};
+
+pub const SEGMENT_SECS_RANGE: (i32, i32) = (1, 10);
+// Up to an hour of 2 second segments for pause/rewind
+pub const LIST_SIZE_RANGE: (i32, i32) = (3, 1800);
+pub const DELETE_THRESHOLD_RANGE: (i32, i32) = (1, 100);
+pub const TIMESHIFT_MINUTES_RANGE: (i32, i32) = (0, 60);
+/// How a live stream is packaged: HLS, MPEG-DASH, or both from one encode (FFmpeg tee muxer).
+/// The app's player, motion and object detection read the HLS playlist, so "dash" alone is
+/// for external players only.
+pub const PACKAGINGS: [&str; 3] = ["hls", "dash", "both"];
+pub const PLAYLIST_NAME: &str = "index.m3u8";
+pub const MANIFEST_NAME: &str = "manifest.mpd";
+/// Variant playlist of an HLS stream with extra renditions; the source rendition stays in
+/// index.m3u8 and each lower one gets a "<height>p" subdirectory
+pub const MASTER_PLAYLIST_NAME: &str = "master.m3u8";
+
+/// HLS output parameters of one camera: the global settings with the camera's overrides applied
+pub struct HlsParams {
+    pub segment_secs: i32,
+    // Grown to cover the timeshift window when there is one
+    pub list_size: i32,
+    pub delete_threshold: i32,
+    pub segment_dir: Option<PathBuf>,
+    pub timeshift_minutes: i32,
+    // Heights of the lower renditions (hls.renditionHeights), largest first
+    pub renditions: Vec<i32>,
+}
+
+pub fn load_settings(conn: &Connection) -> Result<HlsSettings, String> {
+    Ok(HlsSettings {
+        id: 1,
+        segmentSecs: settings::get_i32(conn, settings::HLS_SEGMENT_SECS)?,
+        listSize: settings::get_i32(conn, settings::HLS_LIST_SIZE)?,
+        deleteThreshold: settings::get_i32(conn, settings::HLS_DELETE_THRESHOLD)?,
+        segmentDir: settings::get_opt_string(conn, settings::HLS_SEGMENT_DIR)?,
+        timeshiftMinutes: settings::get_i32(conn, settings::HLS_TIMESHIFT_MINUTES)?,
+        stallSegments: settings::get_i32(conn, settings::HLS_STALL_SEGMENTS)?,
+    })
+}
+
+pub fn load_camera_settings(conn: &Connection, camera_id: i32) -> Result<Option<CameraHlsSettings>, String> {
+    conn.query_row(
+        "SELECT camera_id, segment_secs, list_size, delete_threshold, segment_dir, timeshift_minutes, packaging
+         FROM camera_hls_settings WHERE camera_id = ?1",
+        [camera_id],
+        |row| {
+            Ok(CameraHlsSettings {
+                camera_id: row.get(0)?,
+                segment_secs: row.get(1)?,
+                list_size: row.get(2)?,
+                delete_threshold: row.get(3)?,
+                segment_dir: row.get(4)?,
+                timeshift_minutes: row.get(5)?,
+                packaging: row.get(6)?,
+            })
+        },
+    ).optional().map_err(|e| e.to_string())
+}
+
+pub fn params_for(conn: &Connection, camera_id: i32) -> Result<HlsParams, String> {
+    let settings = load_settings(conn)?;
+    let camera = load_camera_settings(conn, camera_id)?.unwrap_or_default();
+
+    // A quality preset's segment length stands in for the global one, but not a camera's own
+    let preset = crate::encoder::quality_preset_for(conn, camera_id)?;
+    let segment_secs = camera.segment_secs
+        .or(preset.map(|preset| preset.segment_secs))
+        .unwrap_or(settings.segmentSecs)
+        .max(1);
+    let timeshift_minutes = camera.timeshift_minutes.unwrap_or(settings.timeshiftMinutes);
+    // Segments are only deleted once they drop out of the playlist, so a playlist spanning the
+    // window keeps it on disk
+    let window_segments = (timeshift_minutes * 60 + segment_secs - 1) / segment_secs;
+
+    Ok(HlsParams {
+        segment_secs,
+        list_size: camera.list_size.unwrap_or(settings.listSize).max(window_segments),
+        delete_threshold: camera.delete_threshold.unwrap_or(settings.deleteThreshold),
+        segment_dir: camera.segment_dir.or(settings.segmentDir).map(PathBuf::from),
+        timeshift_minutes,
+        renditions: settings::get_i32_list(conn, settings::HLS_RENDITION_HEIGHTS)?,
+    })
+}
+
+/// Packaging of the camera's live stream, "hls" unless overridden
+pub fn packaging_for(conn: &Connection, camera_id: i32) -> Result<String, String> {
+    let camera = load_camera_settings(conn, camera_id)?.unwrap_or_default();
+    Ok(camera.packaging.unwrap_or_else(|| "hls".to_string()))
+}
+
+/// File players open in a live stream directory: the HLS playlist when there is one,
+/// its master playlist when the stream has extra renditions
+pub fn stream_file(packaging: &str, adaptive: bool) -> &'static str {
+    match packaging {
+        "dash" => MANIFEST_NAME,
+        _ if adaptive => MASTER_PLAYLIST_NAME,
+        _ => PLAYLIST_NAME,
+    }
+}
+
+/// Subdirectory of the stream directory holding a lower rendition
+pub fn rendition_dir_name(height: i32) -> String {
+    format!("{}p", height)
+}
+
+/// Master playlist listing the source rendition and the lower ones, given as (height, kbps).
+/// Players pick a variant by BANDWIDTH; the source's resolution isn't known up front.
+pub fn master_playlist(source_kbps: u32, renditions: &[(i32, u32)]) -> String {
+    let mut playlist = String::from("#EXTM3U\n#EXT-X-VERSION:3\n");
+    playlist.push_str(&format!("#EXT-X-STREAM-INF:BANDWIDTH={}\n{}\n", source_kbps * 1000, PLAYLIST_NAME));
+    for (height, kbps) in renditions {
+        playlist.push_str(&format!(
+            "#EXT-X-STREAM-INF:BANDWIDTH={}\n{}/{}\n",
+            kbps * 1000,
+            rendition_dir_name(*height),
+            PLAYLIST_NAME
+        ));
+    }
+    playlist
+}
+
+pub fn check_range(name: &str, value: i32, (min, max): (i32, i32)) -> Result<(), String> {
+    if !(min..=max).contains(&value) {
+        return Err(format!("{} must be between {} and {}", name, min, max));
+    }
+    Ok(())
+}
+
+/// Check a segment location and create it if needed; it has to be an absolute path the app
+/// can write to
+pub fn check_segment_dir(dir: &str) -> Result<(), String> {
+    let path = Path::new(dir);
+    if !path.is_absolute() {
+        return Err("Segment location must be an absolute path".to_string());
+    }
+    std::fs::create_dir_all(path).map_err(|e| format!("Can't use {} for segments: {}", dir, e))?;
+
+    let probe = path.join(".write-test");
+    std::fs::write(&probe, b"").map_err(|e| format!("Can't write segments to {}: {}", dir, e))?;
+    let _ = std::fs::remove_file(probe);
+    Ok(())
+}
+
+/// FFmpeg HLS muxer arguments writing `playlist` and segments named like `segment_pattern`
+pub fn output_args(params: &HlsParams, segment_pattern: &Path, playlist: &Path) -> Vec<String> {
+    vec![
+        "-f".to_string(), "hls".to_string(),
+        "-hls_time".to_string(), params.segment_secs.to_string(),
+        "-hls_list_size".to_string(), params.list_size.to_string(),
+        "-hls_delete_threshold".to_string(), params.delete_threshold.to_string(),
+        "-hls_flags".to_string(), "delete_segments+omit_endlist+program_date_time".to_string(),
+        "-hls_segment_type".to_string(), "mpegts".to_string(),
+        "-hls_segment_filename".to_string(), segment_pattern.to_string_lossy().to_string(),
+        playlist.to_string_lossy().to_string(),
+    ]
+}
+
+/// FFmpeg DASH muxer arguments writing `manifest` with its fMP4 segments next to it, keeping
+/// the same window as the HLS playlist
+pub fn dash_output_args(params: &HlsParams, manifest: &Path) -> Vec<String> {
+    vec![
+        "-f".to_string(), "dash".to_string(),
+        "-seg_duration".to_string(), params.segment_secs.to_string(),
+        "-window_size".to_string(), params.list_size.to_string(),
+        "-extra_window_size".to_string(), params.delete_threshold.to_string(),
+        "-use_template".to_string(), "1".to_string(),
+        "-use_timeline".to_string(), "1".to_string(),
+        "-init_seg_name".to_string(), "init-$RepresentationID$.m4s".to_string(),
+        "-media_seg_name".to_string(), "chunk-$RepresentationID$-$Number%05d$.m4s".to_string(),
+        manifest.to_string_lossy().to_string(),
+    ]
+}
+
+/// Output arguments for a packaging; "both" runs the HLS and DASH muxers off one encode
+pub fn packaged_output_args(packaging: &str, params: &HlsParams, segment_pattern: &Path, playlist: &Path, manifest: &Path) -> Vec<String> {
+    match packaging {
+        "dash" => dash_output_args(params, manifest),
+        "both" => vec![
+            // The tee muxer can't ask the encoder for global headers on behalf of DASH
+            "-flags".to_string(), "+global_header".to_string(),
+            "-map".to_string(), "0:v".to_string(),
+            "-f".to_string(), "tee".to_string(),
+            format!(
+                "{}|{}",
+                tee_output(&output_args(params, segment_pattern, playlist)),
+                tee_output(&dash_output_args(params, manifest)),
+            ),
+        ],
+        _ => output_args(params, segment_pattern, playlist),
+    }
+}
+
+// One tee slave, "[f=hls:hls_time=2:...]path", from muxer arguments ending in the output path.
+// Option values are unescaped twice (slave list, then option list), the path once.
+fn tee_output(args: &[String]) -> String {
+    let Some((path, options)) = args.split_last() else { return String::new() };
+    let options: Vec<String> = options.chunks(2)
+        .map(|pair| format!("{}={}", pair[0].trim_start_matches('-'), tee_escape(&tee_escape(&pair[1]))))
+        .collect();
+    format!("[{}]{}", options.join(":"), tee_escape(path))
+}
+
+fn tee_escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '\\' | '\'' | ':' | '|' | '[' | ']' | '=') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// How far back the camera's live stream can be scrubbed, read from its current playlist
+pub fn timeshift_info(state: &AppState, conn: &Connection, camera_id: i32) -> Result<TimeshiftInfo, String> {
+    let params = params_for(conn, camera_id)?;
+    let window_secs = if params.timeshift_minutes > 0 {
+        params.timeshift_minutes as i64 * 60
+    } else {
+        params.list_size as i64 * params.segment_secs as i64
+    };
+
+    let live = state.processes.lock().map_err(|e| e.to_string())?.contains_key(&camera_id);
+    let playlist_path = state.stream_dir.join(camera_id.to_string()).join("index.m3u8");
+    let playlist = if live { std::fs::read_to_string(playlist_path).ok() } else { None };
+    let segments = playlist.as_deref().map(parse_playlist).unwrap_or_default();
+
+    let available_secs: f64 = segments.iter().map(|(_, duration)| duration).sum();
+    let oldest_time = segments.first().and_then(|(time, _)| *time);
+    let newest_time = segments.last().and_then(|(time, duration)| {
+        time.map(|time| time + chrono::Duration::milliseconds((duration * 1000.0) as i64))
+    });
+
+    Ok(TimeshiftInfo {
+        cameraId: camera_id,
+        live,
+        windowSecs: window_secs,
+        availableSecs: available_secs,
+        segmentCount: segments.len(),
+        oldestTime: oldest_time,
+        newestTime: newest_time,
+        streamUrl: live.then(|| {
+            let session = state.stream_tokens.status_session(camera_id);
+            state.stream_tokens.session_url(state.server_port, &session, PLAYLIST_NAME)
+        }),
+    })
+}
+
+// (EXT-X-PROGRAM-DATE-TIME, EXTINF duration) of every segment in a media playlist
+fn parse_playlist(text: &str) -> Vec<(Option<DateTime<Utc>>, f64)> {
+    let mut segments = Vec::new();
+    let mut time = None;
+
+    for line in text.lines() {
+        if let Some(value) = line.strip_prefix("#EXT-X-PROGRAM-DATE-TIME:") {
+            // FFmpeg writes "2024-05-01T12:00:00.000+0900"
+            time = DateTime::parse_from_str(value.trim(), "%Y-%m-%dT%H:%M:%S%.f%z")
+                .or_else(|_| DateTime::parse_from_rfc3339(value.trim()))
+                .ok()
+                .map(|time| time.with_timezone(&Utc));
+        } else if let Some(value) = line.strip_prefix("#EXTINF:") {
+            let duration = value.split(',').next().and_then(|d| d.trim().parse::<f64>().ok()).unwrap_or(0.0);
+            segments.push((time.take(), duration));
+        }
+    }
+
+    segments
+}
+
+/// Fallback of the /streams file service: segments of cameras whose segments live outside the
+/// stream directory. The playlist stays in the stream directory and lists segments by file
+/// name, so players ask for them next to it.
+pub fn segment_router(pipelines: PipelineTracker) -> Router {
+    Router::new()
+        .route("/:camera_id/:file", get(serve_segment))
+        .with_state(pipelines)
+}
+
+async fn serve_segment(
+    State(pipelines): State<PipelineTracker>,
+    UrlPath((camera_id, file)): UrlPath<(i32, String)>,
+) -> Response {
+    let is_segment = file.starts_with("segment_") && file.ends_with(".ts") && !file.contains(['/', '\\']) && !file.contains("..");
+    let Some(dir) = pipelines.segment_dir(camera_id).filter(|_| is_segment) else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+
+    match tokio::fs::read(dir.join(&file)).await {
+        Ok(bytes) => ([(header::CONTENT_TYPE, "video/mp2t")], bytes).into_response(),
+        Err(_) => StatusCode::NOT_FOUND.into_response(),
+    }
+}
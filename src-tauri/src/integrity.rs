@@ -0,0 +1,319 @@
+use crate::models::{RecordingIssue, VerifyReport};
+use crate::playback::probe_media;
+use crate::events::AppEvent;
+use crate::AppState;
+use chrono::Utc;
+use std::path::Path;
+use std::process::Command;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+// Windows-specific imports for hiding console window
+#[cfg(target_os = "windows")]
+use std::os::windows::process::CommandExt;
+
+// Values of recordings.integrity (NULL means never checked)
+const STATUS_OK: &str = "ok";
+const STATUS_MISSING: &str = "missing";
+const STATUS_CORRUPT: &str = "corrupt";
+
+// How often the library is compared with the files on disk
+const LIBRARY_WATCH_INTERVAL_SECS: u64 = 60;
+
+struct CheckResult {
+    status: &'static str,
+    detail: Option<String>,
+    size: Option<u64>,
+}
+
+// Check one file. The quick check only looks at existence and size; `deep` also
+// asks ffprobe to read the container.
+fn check_file(path: &Path, stored_size: Option<u64>, deep: bool) -> CheckResult {
+    let size = match std::fs::metadata(path) {
+        Ok(metadata) => metadata.len(),
+        Err(_) => {
+            return CheckResult { status: STATUS_MISSING, detail: Some("File not found".to_string()), size: None };
+        }
+    };
+
+    if let Some(stored) = stored_size.filter(|stored| *stored != size) {
+        return CheckResult {
+            status: STATUS_CORRUPT,
+            detail: Some(format!("File size changed from {} to {} bytes", stored, size)),
+            size: Some(size),
+        };
+    }
+
+    if deep {
+        match probe_media(path) {
+            Ok(info) if info.video_codec.is_none() => {
+                return CheckResult { status: STATUS_CORRUPT, detail: Some("No video stream".to_string()), size: Some(size) };
+            }
+            Ok(info) if info.duration.is_none_or(|d| d <= 0.0) => {
+                return CheckResult { status: STATUS_CORRUPT, detail: Some("Unknown or zero duration".to_string()), size: Some(size) };
+            }
+            Ok(_) => {}
+            Err(e) => {
+                return CheckResult { status: STATUS_CORRUPT, detail: Some(e.trim().to_string()), size: Some(size) };
+            }
+        }
+    }
+
+    CheckResult { status: STATUS_OK, detail: None, size: Some(size) }
+}
+
+/// Check every finished recording and store the result in recordings.integrity.
+/// The quick mode (used at startup) never clears a corrupt flag set by a deep check,
+/// since it can't see container damage.
+pub fn verify_recordings(db_path: &str, recording_dir: &Path, deep: bool) -> Result<VerifyReport, String> {
+    verify_recordings_with_progress(db_path, recording_dir, deep, |_, _| Ok(()))
+}
+
+/// `verify_recordings` calling `on_progress(checked, total)` after each recording; an error
+/// from it (e.g. a cancelled job) stops the check
+pub fn verify_recordings_with_progress<F>(db_path: &str, recording_dir: &Path, deep: bool, mut on_progress: F) -> Result<VerifyReport, String>
+where
+    F: FnMut(usize, usize) -> Result<(), String>,
+{
+    let conn = crate::db::open(db_path).map_err(|e| e.to_string())?;
+
+    let rows = {
+        let mut stmt = conn.prepare(
+            "SELECT id, filename, file_size, integrity, location_id FROM recordings WHERE is_finished = 1 AND failure IS NULL ORDER BY id"
+        ).map_err(|e| e.to_string())?;
+        let rows = stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, i32>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, Option<i64>>(2)?,
+                row.get::<_, Option<String>>(3)?,
+                row.get::<_, Option<i64>>(4)?,
+            ))
+        }).map_err(|e| e.to_string())?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())?;
+        rows
+    };
+
+    let mut report = VerifyReport { checked: 0, ok: 0, missing: 0, corrupt: 0, issues: Vec::new() };
+    let now = Utc::now().to_rfc3339();
+    let total = rows.len();
+
+    for (id, filename, stored_size, previous, location_id) in rows {
+        let path = crate::storage::resolve(&conn, recording_dir, location_id, &filename)?;
+        let result = check_file(&path, stored_size.map(|s| s as u64), deep);
+        report.checked += 1;
+
+        let keep_previous = !deep
+            && result.status == STATUS_OK
+            && previous.as_deref().is_some_and(|p| p != STATUS_MISSING);
+
+        let (status, detail) = if keep_previous {
+            let detail: Option<String> = conn.query_row(
+                "SELECT integrity_detail FROM recordings WHERE id = ?1", [id], |row| row.get(0),
+            ).map_err(|e| e.to_string())?;
+            (previous.clone().unwrap_or_default(), detail)
+        } else {
+            conn.execute(
+                "UPDATE recordings SET integrity = ?1, integrity_detail = ?2, verified_at = ?3,
+                        file_size = COALESCE(file_size, ?4)
+                 WHERE id = ?5",
+                rusqlite::params![result.status, result.detail, now, result.size.map(|s| s as i64), id],
+            ).map_err(|e| e.to_string())?;
+            (result.status.to_string(), result.detail)
+        };
+
+        match status.as_str() {
+            STATUS_OK => report.ok += 1,
+            STATUS_MISSING => report.missing += 1,
+            _ => report.corrupt += 1,
+        }
+        if status != STATUS_OK {
+            report.issues.push(RecordingIssue { id, filename, status, detail });
+        }
+        on_progress(report.checked, total)?;
+    }
+
+    println!(
+        "[Integrity] Checked {} recordings ({}): {} ok, {} missing, {} corrupt",
+        report.checked, if deep { "deep" } else { "quick" }, report.ok, report.missing, report.corrupt
+    );
+
+    Ok(report)
+}
+
+/// Recordings whose files changed since the last pass of `reconcile_files`
+#[derive(Debug, Default)]
+pub struct LibraryChanges {
+    pub missing: Vec<i32>,
+    pub restored: Vec<i32>,
+    pub purged: Vec<i32>,
+}
+
+impl LibraryChanges {
+    fn is_empty(&self) -> bool {
+        self.missing.is_empty() && self.restored.is_empty() && self.purged.is_empty()
+    }
+}
+
+/// Compare finished recordings with their files: flag rows whose file was deleted outside
+/// the app as missing, and clear the flag when the file comes back. With the
+/// recordings.purgeMissing setting, unprotected missing rows are removed instead. Rows on
+/// a storage location whose directory is gone (an unmounted drive) are left alone.
+pub fn reconcile_files(db_path: &str, recording_dir: &Path) -> Result<LibraryChanges, String> {
+    let conn = crate::db::open(db_path).map_err(|e| e.to_string())?;
+    let purge = crate::settings::get(&conn, crate::settings::RECORDINGS_PURGE_MISSING)?.as_bool().unwrap_or(false);
+
+    let rows = {
+        let mut stmt = conn.prepare(
+            "SELECT id, camera_id, filename, file_size, integrity, location_id, is_protected, thumbnail
+             FROM recordings WHERE is_finished = 1 AND failure IS NULL ORDER BY id"
+        ).map_err(|e| e.to_string())?;
+        let rows = stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, i32>(0)?,
+                row.get::<_, i32>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, Option<i64>>(3)?,
+                row.get::<_, Option<String>>(4)?,
+                row.get::<_, Option<i64>>(5)?,
+                row.get::<_, bool>(6)?,
+                row.get::<_, Option<String>>(7)?,
+            ))
+        }).map_err(|e| e.to_string())?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())?;
+        rows
+    };
+
+    let mut changes = LibraryChanges::default();
+    let now = Utc::now().to_rfc3339();
+
+    for (id, camera_id, filename, stored_size, previous, location_id, is_protected, thumbnail) in rows {
+        let root = crate::storage::location_root(&conn, recording_dir, location_id)?;
+        if !root.is_dir() {
+            continue;
+        }
+        let path = root.join(&filename);
+        let was_missing = previous.as_deref() == Some(STATUS_MISSING);
+
+        if path.exists() {
+            if was_missing {
+                let result = check_file(&path, stored_size.map(|s| s as u64), false);
+                conn.execute(
+                    "UPDATE recordings SET integrity = ?1, integrity_detail = ?2, verified_at = ?3 WHERE id = ?4",
+                    rusqlite::params![result.status, result.detail, now, id],
+                ).map_err(|e| e.to_string())?;
+                changes.restored.push(id);
+            }
+            continue;
+        }
+
+        if purge && !is_protected {
+            if let Some(thumbnail) = thumbnail {
+                let _ = std::fs::remove_file(recording_dir.join("thumbnails").join(thumbnail));
+            }
+            conn.execute("DELETE FROM recordings WHERE id = ?1", [id]).map_err(|e| e.to_string())?;
+            crate::stream::prune_recording_sessions(&conn, camera_id)?;
+            println!("[Library] Removed recording {} ({}): file was deleted", id, filename);
+            changes.purged.push(id);
+        } else if !was_missing {
+            conn.execute(
+                "UPDATE recordings SET integrity = ?1, integrity_detail = 'File not found', verified_at = ?2 WHERE id = ?3",
+                rusqlite::params![STATUS_MISSING, now, id],
+            ).map_err(|e| e.to_string())?;
+            println!("[Library] Recording {} ({}) is missing its file", id, filename);
+            changes.missing.push(id);
+        }
+    }
+
+    Ok(changes)
+}
+
+/// Periodically reconcile the library with the files on disk and send LibraryChanged when
+/// recordings went missing, came back or were purged
+pub fn spawn_library_watch(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(Duration::from_secs(LIBRARY_WATCH_INTERVAL_SECS)).await;
+
+            let app_handle = app_handle.clone();
+            let result = tauri::async_runtime::spawn_blocking(move || {
+                let state = app_handle.state::<AppState>();
+                let changes = reconcile_files(&state.db_path, &state.recording_dir)?;
+                if !changes.is_empty() {
+                    state.events.emit(AppEvent::LibraryChanged {
+                        missing: changes.missing,
+                        restored: changes.restored,
+                        purged: changes.purged,
+                    });
+                }
+                Ok::<_, String>(())
+            }).await;
+            match result {
+                Ok(Ok(())) => {}
+                Ok(Err(e)) => eprintln!("[Library] Reconciliation failed: {}", e),
+                Err(e) => eprintln!("[Library] Reconciliation task failed: {}", e),
+            }
+        }
+    });
+}
+
+/// Remux a damaged recording, skipping unreadable packets, and replace the original
+/// once the result passes a deep check
+pub fn repair_recording(db_path: &str, recording_dir: &Path, id: i32) -> Result<(), String> {
+    let conn = crate::db::open(db_path).map_err(|e| e.to_string())?;
+    let (filename, is_finished): (String, bool) = conn.query_row(
+        "SELECT filename, is_finished FROM recordings WHERE id = ?1",
+        [id],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    ).map_err(|e| e.to_string())?;
+
+    if !is_finished {
+        return Err("Recording is still in progress".to_string());
+    }
+
+    let source = crate::storage::recording_path(&conn, recording_dir, id)?;
+    if !source.exists() {
+        return Err(format!("Recording file {} is missing and can't be repaired", filename));
+    }
+
+    // Same container as the original, since the result replaces it under the same name
+    let extension = Path::new(&filename).extension().and_then(|e| e.to_str()).unwrap_or("mp4");
+    let repaired = source.with_file_name(format!("{}.repair.{}", filename.trim_end_matches(&format!(".{}", extension)), extension));
+    println!("[Integrity] Repairing recording {} ({})", id, filename);
+
+    let mut cmd = Command::new("ffmpeg");
+    cmd.args(["-y", "-err_detect", "ignore_err", "-fflags", "+genpts+discardcorrupt", "-i"])
+        .arg(&source)
+        .args(["-map", "0", "-c", "copy"])
+        .args(if extension == "mp4" { &["-movflags", "+faststart"][..] } else { &[] })
+        .arg(&repaired);
+
+    #[cfg(target_os = "windows")]
+    {
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        cmd.creation_flags(CREATE_NO_WINDOW);
+    }
+
+    let output = cmd.output().map_err(|e| format!("Failed to run ffmpeg: {}", e))?;
+    if !output.status.success() {
+        let _ = std::fs::remove_file(&repaired);
+        return Err(format!("Repair remux failed: {}", String::from_utf8_lossy(&output.stderr)));
+    }
+
+    let result = check_file(&repaired, None, true);
+    if result.status != STATUS_OK {
+        let _ = std::fs::remove_file(&repaired);
+        return Err(format!("Repaired file is still unreadable: {}", result.detail.unwrap_or_default()));
+    }
+
+    std::fs::rename(&repaired, &source).map_err(|e| e.to_string())?;
+    conn.execute(
+        "UPDATE recordings SET integrity = ?1, integrity_detail = NULL, verified_at = ?2, file_size = ?3 WHERE id = ?4",
+        rusqlite::params![STATUS_OK, Utc::now().to_rfc3339(), result.size.map(|s| s as i64), id],
+    ).map_err(|e| e.to_string())?;
+
+    println!("[Integrity] Recording {} repaired", id);
+    Ok(())
+}
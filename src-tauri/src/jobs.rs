@@ -0,0 +1,349 @@
+use crate::events::{AppEvent, EventBus};
+use crate::models::BackgroundJob;
+use chrono::{DateTime, Utc};
+use rusqlite::{Connection, OptionalExtension};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::Notify;
+
+/// Kinds of job the queue can run
+pub const JOB_KINDS: [&str; 3] = ["verify_recordings", "regenerate_thumbnails", "create_preview"];
+
+// Jobs that run at the same time
+const WORKERS: usize = 2;
+// Finished jobs kept in the jobs table
+const MAX_FINISHED_JOBS: i64 = 200;
+
+/// Persistent queue for long-running work (stored in AppState). Jobs are rows in the jobs
+/// table, so queued work survives a restart; a job that was running when the app quit is
+/// queued again. Every status or progress change is sent as a `job-progress` event.
+#[derive(Clone)]
+pub struct JobQueue {
+    inner: Arc<QueueInner>,
+}
+
+struct QueueInner {
+    db_path: String,
+    recording_dir: PathBuf,
+    events: EventBus,
+    wake: Notify,
+    // Held while a worker picks its next job so two workers can't take the same one
+    claim: Mutex<()>,
+    // Cancellation flags of running jobs
+    running: Mutex<HashMap<i64, Arc<AtomicBool>>>,
+}
+
+/// Handed to a running job: its parameters, progress reporting and cancellation
+pub struct JobContext {
+    pub id: i64,
+    pub params: Value,
+    cancelled: Arc<AtomicBool>,
+    last_percent: AtomicI64,
+    inner: Arc<QueueInner>,
+}
+
+impl JobContext {
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// Err once the job has been cancelled, for use with `?` between steps
+    pub fn check_cancelled(&self) -> Result<(), String> {
+        if self.is_cancelled() {
+            return Err("Cancelled".to_string());
+        }
+        Ok(())
+    }
+
+    /// Report `done` of `total` steps. Only whole-percent changes are stored and sent.
+    pub fn progress(&self, done: usize, total: usize) {
+        let fraction = if total == 0 { 1.0 } else { done as f64 / total as f64 };
+        let percent = (fraction * 100.0) as i64;
+        if self.last_percent.swap(percent, Ordering::Relaxed) == percent {
+            return;
+        }
+        self.inner.update(self.id, "UPDATE jobs SET progress = ?1 WHERE id = ?2", rusqlite::params![fraction, self.id]);
+    }
+}
+
+impl JobQueue {
+    /// Create the queue, re-queue interrupted jobs and start the workers
+    pub fn start(db_path: String, recording_dir: PathBuf, events: EventBus) -> Result<Self, String> {
+        {
+            let conn = crate::db::open(&db_path).map_err(|e| e.to_string())?;
+            let interrupted = conn.execute(
+                "UPDATE jobs SET status = 'queued', progress = 0, started_at = NULL WHERE status = 'running'",
+                [],
+            ).map_err(|e| e.to_string())?;
+            if interrupted > 0 {
+                println!("[Jobs] Re-queued {} interrupted job(s)", interrupted);
+            }
+            conn.execute(
+                "DELETE FROM jobs WHERE status IN ('done', 'failed', 'cancelled')
+                 AND id NOT IN (SELECT id FROM jobs ORDER BY id DESC LIMIT ?1)",
+                [MAX_FINISHED_JOBS],
+            ).map_err(|e| e.to_string())?;
+        }
+
+        let inner = Arc::new(QueueInner {
+            db_path,
+            recording_dir,
+            events,
+            wake: Notify::new(),
+            claim: Mutex::new(()),
+            running: Mutex::new(HashMap::new()),
+        });
+
+        for _ in 0..WORKERS {
+            let inner = inner.clone();
+            tauri::async_runtime::spawn(async move {
+                loop {
+                    match inner.claim_next() {
+                        Ok(Some(context)) => inner.run(context).await,
+                        Ok(None) => inner.wake.notified().await,
+                        Err(e) => {
+                            eprintln!("[Jobs] Failed to pick the next job: {}", e);
+                            tokio::time::sleep(Duration::from_secs(5)).await;
+                        }
+                    }
+                }
+            });
+        }
+
+        Ok(Self { inner })
+    }
+
+    /// Queue a job; `params` are passed to it unchanged
+    pub fn submit(&self, kind: &str, params: Value) -> Result<BackgroundJob, String> {
+        if !JOB_KINDS.contains(&kind) {
+            return Err(format!("Unknown job kind '{}' (expected one of {})", kind, JOB_KINDS.join(", ")));
+        }
+
+        let conn = crate::db::open(&self.inner.db_path).map_err(|e| e.to_string())?;
+        conn.execute(
+            "INSERT INTO jobs (kind, params, status, progress, created_at) VALUES (?1, ?2, 'queued', 0, ?3)",
+            rusqlite::params![kind, params.to_string(), Utc::now().to_rfc3339()],
+        ).map_err(|e| e.to_string())?;
+        let job = load_job(&conn, conn.last_insert_rowid())?.ok_or("Job not found")?;
+
+        println!("[Jobs] Queued job {} ({})", job.id, kind);
+        self.inner.events.emit(AppEvent::JobProgress(job.clone()));
+        self.inner.wake.notify_one();
+        Ok(job)
+    }
+
+    /// Cancel a queued job, or ask a running one to stop at its next step
+    pub fn cancel(&self, id: i64) -> Result<(), String> {
+        if let Some(cancelled) = self.inner.running.lock().map_err(|e| e.to_string())?.get(&id) {
+            cancelled.store(true, Ordering::Relaxed);
+            println!("[Jobs] Cancelling running job {}", id);
+            return Ok(());
+        }
+
+        let conn = crate::db::open(&self.inner.db_path).map_err(|e| e.to_string())?;
+        let cancelled = conn.execute(
+            "UPDATE jobs SET status = 'cancelled', finished_at = ?1 WHERE id = ?2 AND status = 'queued'",
+            rusqlite::params![Utc::now().to_rfc3339(), id],
+        ).map_err(|e| e.to_string())?;
+        if cancelled == 0 {
+            return Err("Job is not queued or running".to_string());
+        }
+
+        println!("[Jobs] Cancelled queued job {}", id);
+        if let Some(job) = load_job(&conn, id)? {
+            self.inner.events.emit(AppEvent::JobProgress(job));
+        }
+        Ok(())
+    }
+}
+
+impl QueueInner {
+    // Mark the oldest queued job as running
+    fn claim_next(self: &Arc<Self>) -> Result<Option<JobContext>, String> {
+        let _claim = self.claim.lock().map_err(|e| e.to_string())?;
+        let conn = crate::db::open(&self.db_path).map_err(|e| e.to_string())?;
+
+        let next: Option<(i64, String)> = conn.query_row(
+            "SELECT id, params FROM jobs WHERE status = 'queued' ORDER BY id LIMIT 1",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        ).optional().map_err(|e| e.to_string())?;
+        let Some((id, params)) = next else { return Ok(None) };
+
+        conn.execute(
+            "UPDATE jobs SET status = 'running', started_at = ?1 WHERE id = ?2",
+            rusqlite::params![Utc::now().to_rfc3339(), id],
+        ).map_err(|e| e.to_string())?;
+
+        let cancelled = Arc::new(AtomicBool::new(false));
+        self.running.lock().map_err(|e| e.to_string())?.insert(id, cancelled.clone());
+        if let Some(job) = load_job(&conn, id)? {
+            self.events.emit(AppEvent::JobProgress(job));
+        }
+
+        Ok(Some(JobContext {
+            id,
+            params: serde_json::from_str(&params).unwrap_or(Value::Null),
+            cancelled,
+            last_percent: AtomicI64::new(0),
+            inner: self.clone(),
+        }))
+    }
+
+    async fn run(self: &Arc<Self>, context: JobContext) {
+        let id = context.id;
+        let (db_path, recording_dir) = (self.db_path.clone(), self.recording_dir.clone());
+        let (result, cancelled) = tauri::async_runtime::spawn_blocking(move || {
+            let result = run_job(&context, &db_path, &recording_dir);
+            (result, context.is_cancelled())
+        }).await.unwrap_or_else(|e| (Err(e.to_string()), false));
+
+        if let Ok(mut running) = self.running.lock() {
+            running.remove(&id);
+        }
+
+        let now = Utc::now().to_rfc3339();
+        match result {
+            _ if cancelled => {
+                println!("[Jobs] Job {} cancelled", id);
+                self.update(id, "UPDATE jobs SET status = 'cancelled', finished_at = ?1 WHERE id = ?2", rusqlite::params![now, id]);
+            }
+            Ok(message) => {
+                println!("[Jobs] Job {} done", id);
+                self.update(
+                    id,
+                    "UPDATE jobs SET status = 'done', progress = 1, message = ?1, finished_at = ?2 WHERE id = ?3",
+                    rusqlite::params![message, now, id],
+                );
+            }
+            Err(e) => {
+                eprintln!("[Jobs] Job {} failed: {}", id, e);
+                self.update(
+                    id,
+                    "UPDATE jobs SET status = 'failed', error = ?1, finished_at = ?2 WHERE id = ?3",
+                    rusqlite::params![e, now, id],
+                );
+            }
+        }
+    }
+
+    // Apply a change to a job row and send the new state
+    fn update(&self, id: i64, sql: &str, params: impl rusqlite::Params) {
+        let result = crate::db::open(&self.db_path)
+            .map_err(|e| e.to_string())
+            .and_then(|conn| {
+                conn.execute(sql, params).map_err(|e| e.to_string())?;
+                load_job(&conn, id)
+            });
+        match result {
+            Ok(Some(job)) => self.events.emit(AppEvent::JobProgress(job)),
+            Ok(None) => {}
+            Err(e) => eprintln!("[Jobs] Failed to update job {}: {}", id, e),
+        }
+    }
+}
+
+// Run a job to completion on a blocking thread; returns a summary for the UI
+fn run_job(context: &JobContext, db_path: &str, recording_dir: &std::path::Path) -> Result<Option<String>, String> {
+    let kind: String = {
+        let conn = crate::db::open(db_path).map_err(|e| e.to_string())?;
+        conn.query_row("SELECT kind FROM jobs WHERE id = ?1", [context.id], |row| row.get(0))
+            .map_err(|e| e.to_string())?
+    };
+
+    match kind.as_str() {
+        "verify_recordings" => {
+            let deep = context.params["deep"].as_bool().unwrap_or(true);
+            let report = crate::integrity::verify_recordings_with_progress(db_path, recording_dir, deep, |done, total| {
+                context.progress(done, total);
+                context.check_cancelled()
+            })?;
+            Ok(Some(format!("{} ok, {} missing, {} corrupt", report.ok, report.missing, report.corrupt)))
+        }
+        "regenerate_thumbnails" => {
+            // Given recordings, or every finished one without a thumbnail
+            let ids: Vec<i32> = match context.params["ids"].as_array() {
+                Some(ids) => ids.iter().filter_map(|id| id.as_i64()).map(|id| id as i32).collect(),
+                None => {
+                    let conn = crate::db::open(db_path).map_err(|e| e.to_string())?;
+                    let mut stmt = conn.prepare("SELECT id FROM recordings WHERE is_finished = 1 AND thumbnail IS NULL ORDER BY id")
+                        .map_err(|e| e.to_string())?;
+                    let ids = stmt.query_map([], |row| row.get(0))
+                        .map_err(|e| e.to_string())?
+                        .collect::<Result<Vec<_>, _>>()
+                        .map_err(|e| e.to_string())?;
+                    ids
+                }
+            };
+
+            let mut failed = 0;
+            for (index, id) in ids.iter().enumerate() {
+                context.check_cancelled()?;
+                if let Err(e) = crate::thumbnails::create_thumbnail(db_path, recording_dir, *id) {
+                    eprintln!("[Jobs] Thumbnail of recording {} failed: {}", id, e);
+                    failed += 1;
+                }
+                context.progress(index + 1, ids.len());
+            }
+            Ok(Some(format!("{} thumbnail(s) created, {} failed", ids.len() - failed, failed)))
+        }
+        "create_preview" => {
+            let recording_id = context.params["recording_id"].as_i64().ok_or("create_preview needs a recording_id")? as i32;
+            let offset = context.params["offset"].as_f64().unwrap_or(0.0);
+            let format = context.params["format"].as_str().unwrap_or(crate::previews::PREVIEW_FORMATS[0]);
+            let preview = crate::previews::create_preview(db_path, recording_dir, recording_id, offset, format)?;
+
+            let conn = crate::db::open(db_path).map_err(|e| e.to_string())?;
+            let camera_id: i32 = conn.query_row("SELECT camera_id FROM recordings WHERE id = ?1", [recording_id], |row| row.get(0))
+                .map_err(|e| e.to_string())?;
+            context.inner.events.emit(AppEvent::RecordingUpdated { camera_id, recording_id });
+            Ok(Some(format!("Preview {}", preview)))
+        }
+        other => Err(format!("Unknown job kind: {}", other)),
+    }
+}
+
+fn load_job(conn: &Connection, id: i64) -> Result<Option<BackgroundJob>, String> {
+    conn.query_row(
+        &format!("SELECT {} FROM jobs WHERE id = ?1", JOB_COLUMNS),
+        [id],
+        job_from_row,
+    ).optional().map_err(|e| e.to_string())
+}
+
+/// Recent jobs, newest first
+pub fn list_jobs(conn: &Connection, limit: u32) -> Result<Vec<BackgroundJob>, String> {
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM jobs ORDER BY id DESC LIMIT ?1", JOB_COLUMNS))
+        .map_err(|e| e.to_string())?;
+    let jobs = stmt.query_map([limit], job_from_row)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    Ok(jobs)
+}
+
+const JOB_COLUMNS: &str = "id, kind, params, status, progress, message, error, created_at, started_at, finished_at";
+
+fn job_from_row(row: &rusqlite::Row) -> rusqlite::Result<BackgroundJob> {
+    let parse_time = |t: Option<String>| {
+        t.and_then(|t| DateTime::parse_from_rfc3339(&t).ok()).map(|t| t.with_timezone(&Utc))
+    };
+    let params: String = row.get(2)?;
+
+    Ok(BackgroundJob {
+        id: row.get(0)?,
+        kind: row.get(1)?,
+        params: serde_json::from_str(&params).unwrap_or(Value::Null),
+        status: row.get(3)?,
+        progress: row.get(4)?,
+        message: row.get(5)?,
+        error: row.get(6)?,
+        created_at: parse_time(row.get(7)?).unwrap_or_else(Utc::now),
+        started_at: parse_time(row.get(8)?),
+        finished_at: parse_time(row.get(9)?),
+    })
+}
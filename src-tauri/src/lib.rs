@@ -3,37 +3,314 @@ pub mod models;
 pub mod commands;
 pub mod stream;
 pub mod onvif;
+pub mod onvif_trace;
+pub mod onvif_diagnostics;
+#[cfg(feature = "mock-onvif")]
+pub mod mock_onvif;
 pub mod gpu_detector;
 pub mod encoder;
+pub mod ffmpeg;
 pub mod scheduler;
+pub mod settings;
 pub mod camera_plugin;
 pub mod plugins;
+pub mod playback;
+pub mod timeline;
+pub mod weekly_schedule;
+pub mod archive;
+pub mod auth;
+pub mod s3;
+pub mod cloud_upload;
+pub mod integrity;
+pub mod download;
+pub mod snapshots;
+pub mod events;
+pub mod pipeline;
+pub mod prebuffer;
+pub mod camera_tasks;
+pub mod audio_monitor;
+pub mod motion;
+pub mod object_detector;
+pub mod detectors;
+pub mod analytics;
+pub mod alarms;
+pub mod plugin_config;
+pub mod triggers;
+pub mod mqtt;
+pub mod nvr_config;
+pub mod hls;
+pub mod jobs;
+pub mod messages;
+pub mod process_jobs;
+pub mod process_registry;
+pub mod redact;
+pub mod report;
+pub mod resource_monitor;
+pub mod repository;
+pub mod storage;
+pub mod stream_cleanup;
+pub mod stream_watchdog;
+pub mod budget;
+pub mod stream_tokens;
+pub mod stream_url;
+pub mod thumbnails;
+pub mod watermark;
+pub mod posix_tz;
+pub mod heartbeat;
+pub mod diagnostics;
+pub mod previews;
 
 use tauri::Manager;
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::process::Child;
+use std::time::Duration;
 use crate::camera_plugin::PluginManager;
 
-pub struct AppState {
+// Timeout for a single ONVIF SOAP request
+const ONVIF_REQUEST_TIMEOUT_SECS: u64 = 5;
+
+/// Backend state managed by Tauri. Cloning it only clones the inner Arc, so commands, the
+/// scheduler and background tasks all share one instance; fields are reached through Deref.
+#[derive(Clone)]
+pub struct AppState(Arc<AppStateInner>);
+
+impl AppState {
+    pub fn new(inner: AppStateInner) -> Self {
+        AppState(Arc::new(inner))
+    }
+}
+
+impl std::ops::Deref for AppState {
+    type Target = AppStateInner;
+
+    fn deref(&self) -> &AppStateInner {
+        &self.0
+    }
+}
+
+pub struct AppStateInner {
     pub db_path: String,
     pub server_port: u16,
+    // Random per-launch token required by authenticated HTTP endpoints (downloads)
+    pub server_token: String,
+    // Signs the /streams URLs handed out while LAN mode is on
+    pub stream_tokens: stream_tokens::StreamTokens,
     pub stream_dir: PathBuf,
     pub recording_dir: PathBuf,
     // Map<camera_id, ChildProcess>
     // using std::process::Child allows us to kill it later
     pub processes: Arc<Mutex<HashMap<i32, Child>>>,
     pub recording_processes: Arc<Mutex<HashMap<i32, Child>>>,
+    // Map<playback session id, transcoding ChildProcess>
+    pub playback_processes: Arc<Mutex<HashMap<String, Child>>>,
     pub scheduler: Arc<tokio::sync::Mutex<scheduler::SchedulerManager>>,
-    // Map<schedule_id, camera_id> for active scheduled recordings
-    pub active_scheduled_recordings: Arc<tokio::sync::Mutex<HashMap<i32, i32>>>,
+    // (schedule_id, camera_id) of active scheduled recordings; a group schedule has one per camera
+    pub active_scheduled_recordings: Arc<tokio::sync::Mutex<HashSet<(i32, i32)>>>,
     pub app_handle: tauri::AppHandle,
+    // Typed backend events, sent to the window and to /events WebSocket clients
+    pub events: events::EventBus,
+    // Stream start times, encoders and last errors per camera (see get_pipeline_status)
+    pub pipelines: pipeline::PipelineTracker,
+    // Audio level analysis tasks of cameras with audio alerts enabled
+    pub audio_monitors: camera_tasks::CameraTasks,
+    // FFmpeg motion detection tasks of cameras with motion detection enabled
+    pub motion_detectors: camera_tasks::CameraTasks,
+    // Pre-recording ring buffers of cameras with one configured
+    pub prebuffers: camera_tasks::CameraTasks,
+    // Frame sampling tasks of cameras with object detection enabled
+    pub object_detectors: camera_tasks::CameraTasks,
+    /// Camera-side event streams of vendor plugins (Hikvision alertStream, ...)
+    pub alarm_streams: camera_tasks::CameraTasks,
+    // Home Assistant MQTT connection, when enabled in app_settings
+    pub mqtt: mqtt::MqttBridge,
     pub plugin_manager: Arc<PluginManager>,
+    // Pooled HTTP clients shared by all ONVIF requests
+    pub onvif_http: onvif::OnvifHttpClient,
+    // Map<scan_id, DiscoveryScan> for discovery scans still running
+    pub discovery_scans: Arc<Mutex<HashMap<String, camera_plugin::DiscoveryScan>>>,
+    // Background transfer queue for archiving recordings
+    pub archive_queue: archive::ArchiveQueue,
+    // Background queue uploading finished recordings to S3-compatible storage
+    pub upload_queue: cloud_upload::UploadQueue,
+    pub thumbnail_queue: thumbnails::ThumbnailQueue,
+    pub job_queue: jobs::JobQueue,
+    // Login of the app window, checked before every command when auth.required is set
+    pub auth: auth::UiSession,
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    let handler = tauri::generate_handler![
+        commands::get_cameras,
+        commands::add_camera,
+        commands::add_camera_channels,
+        commands::add_camera_from_url,
+        commands::import_cameras,
+        commands::export_cameras,
+        commands::delete_camera,
+        commands::get_archived_cameras,
+        commands::restore_camera,
+        commands::reorder_cameras,
+        commands::set_favorite,
+        commands::get_camera_groups,
+        commands::add_camera_group,
+        commands::update_camera_group,
+        commands::delete_camera_group,
+        commands::discover_cameras,
+        commands::start_discovery,
+        commands::cancel_discovery,
+        commands::probe_onvif_device,
+        commands::probe_hikvision_device,
+        commands::probe_dahua_device,
+        commands::probe_axis_device,
+        commands::get_axis_parameters,
+        commands::list_rpi_cameras,
+        commands::set_camera_video_mode,
+        commands::set_camera_rtsp_transport,
+        commands::set_camera_stream_timeout,
+        commands::set_camera_priority,
+        commands::get_load_budget,
+        commands::set_camera_input_tuning,
+        commands::set_camera_ffmpeg_args,
+        commands::set_camera_video_filters,
+        commands::get_quality_presets,
+        commands::set_camera_quality_preset,
+        commands::set_camera_time_sync_strategy,
+        commands::get_camera_certificate,
+        commands::set_camera_tls,
+        commands::set_onvif_trace,
+        commands::get_onvif_trace,
+        commands::run_camera_diagnostics,
+        commands::get_plugin_config,
+        commands::set_plugin_config,
+        commands::start_stream,
+        commands::stop_stream,
+        commands::revoke_stream_session,
+        commands::force_cleanup,
+        commands::start_recording,
+        commands::stop_recording,
+        commands::get_audio_monitor,
+        commands::set_audio_monitor,
+        commands::get_motion_detector,
+        commands::set_motion_detector,
+        commands::get_analytics_settings,
+        commands::update_analytics_settings,
+        commands::get_object_detector,
+        commands::set_object_detector,
+        commands::pause_recording,
+        commands::resume_recording,
+        commands::get_recording_gaps,
+        commands::get_recordings,
+        commands::get_recording_sessions,
+        commands::update_recording_meta,
+        commands::delete_recording,
+        commands::set_recording_protected,
+        commands::delete_recordings,
+        commands::export_recordings,
+        commands::delete_recording_session,
+        commands::export_recording_session,
+        commands::export_comparison,
+        commands::start_playback_session,
+        commands::start_recording_session_playback,
+        commands::stop_playback_session,
+        commands::get_recording_timeline,
+        commands::generate_report,
+        commands::get_events,
+        commands::trigger_camera,
+        commands::get_trigger_settings,
+        commands::update_trigger_settings,
+        commands::get_mqtt_settings,
+        commands::update_mqtt_settings,
+        commands::verify_recordings,
+        commands::get_server_token,
+        commands::get_server_port,
+        commands::get_server_settings,
+        commands::update_server_settings,
+        commands::get_recording_settings,
+        commands::update_recording_settings,
+        commands::get_app_settings,
+        commands::update_app_settings,
+        commands::get_hls_settings,
+        commands::update_hls_settings,
+        commands::get_camera_hls_settings,
+        commands::set_camera_hls_settings,
+        commands::get_timeshift_info,
+        commands::repair_recording,
+        commands::archive_recording,
+        commands::get_archive_queue,
+        commands::get_archive_settings,
+        commands::update_archive_settings,
+        commands::upload_recording,
+        commands::get_upload_queue,
+        commands::submit_job,
+        commands::create_preview,
+        commands::get_jobs,
+        commands::cancel_job,
+        commands::get_cloud_upload_settings,
+        commands::update_cloud_upload_settings,
+        commands::get_camera_upload_policies,
+        commands::set_camera_upload_policy,
+        commands::get_camera_recording_formats,
+        commands::set_camera_recording_format,
+        commands::get_camera_prebuffers,
+        commands::set_camera_prebuffer,
+        commands::get_storage_locations,
+        commands::add_storage_location,
+        commands::update_storage_location,
+        commands::delete_storage_location,
+        commands::get_camera_storage,
+        commands::set_camera_storage,
+        commands::get_camera_time,
+        commands::get_all_camera_times,
+        commands::sync_camera_time,
+        commands::check_ptz_capabilities,
+        commands::move_ptz,
+        commands::stop_ptz,
+        commands::get_camera_network,
+        commands::set_camera_network,
+        commands::get_camera_users,
+        commands::create_camera_user,
+        commands::update_camera_user,
+        commands::delete_camera_user,
+        commands::get_camera_capabilities,
+        commands::detect_gpu,
+        commands::get_encoder_settings,
+        commands::update_encoder_settings,
+        commands::get_recording_schedules,
+        commands::get_recording_cameras,
+        commands::get_pipeline_status,
+        commands::get_resource_usage,
+        commands::ping,
+        commands::export_diagnostics,
+        commands::add_weekly_schedule,
+        commands::update_weekly_schedule,
+        commands::add_snapshot_schedule,
+        commands::update_snapshot_schedule,
+        commands::capture_snapshot,
+        commands::capture_frame,
+        commands::get_snapshots,
+        commands::delete_snapshot,
+        commands::get_scheduler_settings,
+        commands::update_scheduler_settings,
+        commands::pause_schedules,
+        commands::resume_schedules,
+        commands::get_schedule_history,
+        commands::add_recording_schedule,
+        commands::update_recording_schedule,
+        commands::delete_recording_schedule,
+        commands::toggle_schedule,
+        commands::login,
+        commands::logout,
+        commands::get_auth_status,
+        commands::change_password,
+        commands::get_app_users,
+        commands::add_app_user,
+        commands::update_app_user,
+        commands::delete_app_user
+    ];
+
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
         .setup(|app| {
@@ -43,6 +320,29 @@ pub fn run() {
 
             let db_path = app_dir.join("cameras.db");
             db::init_db(&db_path).expect("failed to init db");
+            let server_port = db::open(&db_path).map_err(|e| e.to_string())
+                .and_then(|conn| {
+                    settings::init(&conn)?;
+                    settings::get_i32(&conn, settings::SERVER_PORT)
+                })
+                .unwrap_or_else(|e| {
+                    eprintln!("[Init] Failed to load app settings: {}", e);
+                    3333
+                }) as u16;
+            let lan_mode = db::open(&db_path).map_err(|e| e.to_string())
+                .and_then(|conn| settings::get(&conn, settings::SERVER_LAN_MODE))
+                .map(|value| value.as_bool().unwrap_or(false))
+                .unwrap_or(false);
+            if let Err(e) = db::open(&db_path).map_err(|e| e.to_string())
+                .and_then(|conn| onvif_trace::load(&conn, &db_path.to_string_lossy()))
+            {
+                eprintln!("[Init] Failed to load ONVIF trace settings: {}", e);
+            }
+            match db::check_integrity(&db_path) {
+                Ok(0) => {}
+                Ok(count) => eprintln!("[Init] Database has {} orphaned row(s); they are ignored until removed", count),
+                Err(e) => eprintln!("[Init] Database integrity check failed: {}", e),
+            }
 
             // Initialize GPU encoder settings after DB is created
             let db_path_clone = db_path.clone();
@@ -59,36 +359,106 @@ pub fn run() {
             }
             std::fs::create_dir_all(&stream_dir).expect("failed to create streams dir");
 
+            // FFmpeg processes a crashed previous session left running still hold camera sessions
+            let process_state_file = app_dir.join("ffmpeg_processes.json");
+            process_registry::terminate_orphans(&process_state_file);
+            let process_registry = process_registry::ProcessRegistry::start(process_state_file);
+            let pipelines = pipeline::PipelineTracker::new(process_registry);
+
             let recording_dir = app_dir.join("recordings");
             std::fs::create_dir_all(&recording_dir).expect("failed to create recordings dir");
 
             let thumbnails_dir = recording_dir.join("thumbnails");
             std::fs::create_dir_all(&thumbnails_dir).expect("failed to create thumbnails dir");
 
+            let snapshots_dir = recording_dir.join("snapshots");
+            std::fs::create_dir_all(&snapshots_dir).expect("failed to create snapshots dir");
+
             // Initialize scheduler
             let scheduler = tauri::async_runtime::block_on(async {
                 scheduler::SchedulerManager::new().await
                     .expect("Failed to create scheduler")
             });
 
+            // Shared ONVIF HTTP client (connection pooling across requests)
+            let onvif_http = onvif::OnvifHttpClient::new(Duration::from_secs(ONVIF_REQUEST_TIMEOUT_SECS))
+                .expect("failed to create ONVIF HTTP client");
+
             // Initialize plugin manager and register plugins
             let mut plugin_manager = PluginManager::new();
-            plugin_manager.register_plugin(Box::new(plugins::OnvifPlugin::new()));
+            plugin_manager.register_plugin(Box::new(plugins::OnvifPlugin::new(onvif_http.clone())));
             plugin_manager.register_plugin(Box::new(plugins::UvcPlugin::new()));
+            plugin_manager.register_plugin(Box::new(plugins::RpiCameraPlugin::new()));
+            plugin_manager.register_plugin(Box::new(plugins::HikvisionPlugin::new(onvif_http.clone())));
+            plugin_manager.register_plugin(Box::new(plugins::DahuaPlugin::new(onvif_http.clone())));
+            plugin_manager.register_plugin(Box::new(plugins::AxisPlugin::new(onvif_http.clone())));
             println!("[Init] Registered camera plugins: {:?}", plugin_manager.get_plugin_types());
+            match crate::db::open(&db_path) {
+                Ok(conn) => plugin_config::configure_all(&plugin_manager, &conn),
+                Err(e) => eprintln!("[Init] Failed to load plugin settings: {}", e),
+            }
 
-            let state = AppState {
+            let events = events::EventBus::new(app_handle.clone());
+            events::spawn_disk_monitor(events.clone(), recording_dir.clone());
+
+            let archive_queue = archive::ArchiveQueue::start(
+                db_path.to_string_lossy().to_string(),
+                recording_dir.clone(),
+                events.clone(),
+            ).expect("failed to start archive queue");
+
+            let upload_queue = cloud_upload::UploadQueue::start(
+                db_path.to_string_lossy().to_string(),
+                recording_dir.clone(),
+                events.clone(),
+            ).expect("failed to start upload queue");
+
+            let thumbnail_queue = thumbnails::ThumbnailQueue::start(
+                db_path.to_string_lossy().to_string(),
+                recording_dir.clone(),
+                events.clone(),
+                upload_queue.clone(),
+            );
+
+            let job_queue = jobs::JobQueue::start(
+                db_path.to_string_lossy().to_string(),
+                recording_dir.clone(),
+                events.clone(),
+            ).expect("failed to start job queue");
+
+            let server_token = uuid::Uuid::new_v4().simple().to_string();
+            let stream_tokens = stream_tokens::StreamTokens::new(lan_mode);
+
+            let state = AppState::new(AppStateInner {
                 db_path: db_path.to_string_lossy().to_string(),
-                server_port: 3333,
+                server_port,
+                server_token: server_token.clone(),
+                stream_tokens: stream_tokens.clone(),
                 stream_dir: stream_dir.clone(),
                 recording_dir: recording_dir.clone(),
                 processes: Arc::new(Mutex::new(HashMap::new())),
                 recording_processes: Arc::new(Mutex::new(HashMap::new())),
+                playback_processes: Arc::new(Mutex::new(HashMap::new())),
                 scheduler: Arc::new(tokio::sync::Mutex::new(scheduler)),
-                active_scheduled_recordings: Arc::new(tokio::sync::Mutex::new(HashMap::new())),
+                active_scheduled_recordings: Arc::new(tokio::sync::Mutex::new(HashSet::new())),
                 app_handle: app_handle.clone(),
+                events: events.clone(),
+                pipelines: pipelines.clone(),
+                audio_monitors: camera_tasks::CameraTasks::default(),
+                motion_detectors: camera_tasks::CameraTasks::default(),
+                prebuffers: camera_tasks::CameraTasks::default(),
+                object_detectors: camera_tasks::CameraTasks::default(),
+                alarm_streams: camera_tasks::CameraTasks::default(),
+                mqtt: mqtt::MqttBridge::default(),
                 plugin_manager: Arc::new(plugin_manager),
-            };
+                onvif_http,
+                discovery_scans: Arc::new(Mutex::new(HashMap::new())),
+                archive_queue,
+                upload_queue,
+                thumbnail_queue,
+                job_queue,
+                auth: auth::UiSession::default(),
+            });
 
             // Manage state first
             app.manage(state);
@@ -101,19 +471,76 @@ pub fn run() {
                 }
             });
 
+            if let Err(e) = audio_monitor::start_enabled(app.handle()) {
+                eprintln!("[Init] Failed to start audio monitors: {}", e);
+            }
+            if let Err(e) = motion::start_enabled(app.handle()) {
+                eprintln!("[Init] Failed to start motion detection: {}", e);
+            }
+            if let Err(e) = prebuffer::start_enabled(app.handle()) {
+                eprintln!("[Init] Failed to start pre-recording buffers: {}", e);
+            }
+            if let Err(e) = analytics::start_enabled(app.handle()) {
+                eprintln!("[Init] Failed to start object detection: {}", e);
+            }
+            if let Err(e) = alarms::start_all(app.handle()) {
+                eprintln!("[Init] Failed to start camera event streams: {}", e);
+            }
+            triggers::spawn_remote_listener(app.handle().clone());
+            stream_cleanup::spawn_stream_gc(app.handle().clone());
+            integrity::spawn_library_watch(app.handle().clone());
+            stream_watchdog::spawn_stream_watchdog(app.handle().clone());
+            budget::spawn_load_governor(app.handle().clone());
+            pipeline::spawn_recording_progress(app.handle().clone());
+            report::spawn_incident_log(app.handle().clone());
+            report::spawn_digest(app.handle().clone());
+            resource_monitor::spawn_resource_monitor(app.handle().clone());
+            heartbeat::spawn_heartbeat(app.handle().clone());
+            if let Err(e) = mqtt::start(app.handle()) {
+                eprintln!("[Init] Failed to start MQTT: {}", e);
+            }
+
+            // Quick integrity check (existence and size only) so deleted files get flagged
+            let integrity_db_path = db_path.to_string_lossy().to_string();
+            let integrity_recording_dir = recording_dir.clone();
+            tauri::async_runtime::spawn_blocking(move || {
+                if let Err(e) = integrity::verify_recordings(&integrity_db_path, &integrity_recording_dir, false) {
+                    eprintln!("[Init] Failed to verify recordings: {}", e);
+                }
+            });
+
             // Start Axum server
+            let trigger_handle = app.handle().clone();
             tauri::async_runtime::spawn(async move {
                 use axum::Router;
                 use tower_http::services::ServeDir;
                 use tower_http::cors::CorsLayer;
                 use std::net::SocketAddr;
 
+                let recordings = download::router(download::DownloadState {
+                    db_path: db_path.to_string_lossy().to_string(),
+                    recording_dir,
+                    token: server_token.clone(),
+                });
+
+                // Segments stored outside stream_dir are found through the camera's pipeline
+                let streams = Router::new()
+                    .nest_service("/streams", ServeDir::new(stream_dir).fallback(hls::segment_router(pipelines)))
+                    .layer(axum::middleware::from_fn_with_state(stream_tokens, stream_tokens::guard));
+
                 let app = Router::new()
-                    .nest_service("/streams", ServeDir::new(stream_dir))
-                    .nest_service("/recordings", ServeDir::new(recording_dir))
+                    .merge(streams)
+                    .merge(recordings)
+                    .merge(events::router(events, server_token, db_path.to_string_lossy().to_string()))
+                    .merge(triggers::router(trigger_handle))
+                    .merge(auth::router(db_path.to_string_lossy().to_string()))
                     .layer(CorsLayer::permissive()); // Allow all CORS
                 
-                let addr = SocketAddr::from(([127, 0, 0, 1], 3333));
+                let addr = if lan_mode {
+                    SocketAddr::from(([0, 0, 0, 0], server_port))
+                } else {
+                    SocketAddr::from(([127, 0, 0, 1], server_port))
+                };
                 let listener = tokio::net::TcpListener::bind(&addr).await.unwrap();
                 axum::serve(listener, app).await.unwrap();
             });
@@ -130,7 +557,7 @@ pub fn run() {
                     if let Ok(mut processes) = state.processes.lock() {
                         for (camera_id, mut child) in processes.drain() {
                             println!("[Cleanup] Stopping stream for camera {}", camera_id);
-                            let _ = child.kill();
+                            let _ = state.pipelines.kill_process(&mut child);
                             let _ = child.wait();
                         }
                     }
@@ -139,7 +566,16 @@ pub fn run() {
                     if let Ok(mut recording_processes) = state.recording_processes.lock() {
                         for (camera_id, mut child) in recording_processes.drain() {
                             println!("[Cleanup] Stopping recording for camera {}", camera_id);
-                            let _ = child.kill();
+                            let _ = state.pipelines.kill_process(&mut child);
+                            let _ = child.wait();
+                        }
+                    }
+
+                    // Stop all playback transcodes
+                    if let Ok(mut playback_processes) = state.playback_processes.lock() {
+                        for (session_id, mut child) in playback_processes.drain() {
+                            println!("[Cleanup] Stopping playback session {}", session_id);
+                            let _ = state.pipelines.kill_process(&mut child);
                             let _ = child.wait();
                         }
                     }
@@ -148,108 +584,47 @@ pub fn run() {
                 }
             }
         })
-        .invoke_handler(tauri::generate_handler![
-            commands::get_cameras,
-            commands::add_camera,
-            commands::delete_camera,
-            commands::discover_cameras,
-            commands::start_stream,
-            commands::stop_stream,
-            commands::start_recording,
-            commands::stop_recording,
-            commands::get_recordings,
-            commands::delete_recording,
-            commands::get_camera_time,
-            commands::sync_camera_time,
-            commands::check_ptz_capabilities,
-            commands::move_ptz,
-            commands::stop_ptz,
-            commands::get_camera_capabilities,
-            commands::detect_gpu,
-            commands::get_encoder_settings,
-            commands::update_encoder_settings,
-            commands::get_recording_schedules,
-            commands::get_recording_cameras,
-            commands::add_recording_schedule,
-            commands::update_recording_schedule,
-            commands::delete_recording_schedule,
-            commands::toggle_schedule
-        ])
+        .invoke_handler(move |invoke| {
+            // Role check of every command while login is required (see auth::required_role)
+            let state = invoke.message.webview_ref().state::<AppState>();
+            if let Err(e) = auth::authorize_command(&state.db_path, &state.auth, invoke.message.command()) {
+                invoke.resolver.reject(e);
+                return true;
+            }
+            handler(invoke)
+        })
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
 
 // Helper function to load enabled schedules on startup
 async fn load_enabled_schedules_from_app(app_handle: tauri::AppHandle) -> Result<(), String> {
-    use rusqlite::Connection;
-    use chrono::DateTime;
-
     println!("[Init] Loading enabled schedules from database...");
 
     // Get managed state
     let state = app_handle.state::<AppState>();
 
-    let conn = Connection::open(&state.db_path).map_err(|e| e.to_string())?;
-
-    let schedules = {
-        let mut stmt = conn.prepare(
-            "SELECT s.id, s.camera_id, s.name, s.cron_expression, s.duration_minutes, s.fps, s.is_enabled,
-                    s.created_at, s.updated_at, c.name as camera_name
-             FROM recording_schedules s
-             LEFT JOIN cameras c ON s.camera_id = c.id
-             WHERE s.is_enabled = 1"
-        ).map_err(|e| e.to_string())?;
-
-        let schedules_iter = stmt.query_map([], |row| {
-            Ok(models::RecordingSchedule {
-                id: row.get(0)?,
-                camera_id: row.get(1)?,
-                name: row.get(2)?,
-                cron_expression: row.get(3)?,
-                duration_minutes: row.get(4)?,
-                fps: row.get(5)?,
-                is_enabled: row.get(6)?,
-                created_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(7)?).unwrap_or(chrono::Utc::now().into()).with_timezone(&chrono::Utc),
-                updated_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(8)?).unwrap_or(chrono::Utc::now().into()).with_timezone(&chrono::Utc),
-                camera_name: row.get(9)?,
-                next_run: None, // Not needed for scheduler initialization
-            })
-        }).map_err(|e| e.to_string())?;
-
-        let mut schedules = Vec::new();
-        for schedule in schedules_iter {
-            schedules.push(schedule.map_err(|e| e.to_string())?);
-        }
-        schedules
-    };
+    let conn = crate::db::open(&state.db_path).map_err(|e| e.to_string())?;
 
-    // Drop connection before async operations (stmt is already dropped by this point)
-    drop(conn);
+    let schedules = repository::ScheduleRepo::list_enabled(&conn)?;
 
-    // Create Arc<AppState> for scheduler since it expects Arc
-    let state_arc = Arc::new(AppState {
-        db_path: state.db_path.clone(),
-        server_port: state.server_port,
-        stream_dir: state.stream_dir.clone(),
-        recording_dir: state.recording_dir.clone(),
-        processes: state.processes.clone(),
-        recording_processes: state.recording_processes.clone(),
-        scheduler: state.scheduler.clone(),
-        active_scheduled_recordings: state.active_scheduled_recordings.clone(),
-        app_handle: state.app_handle.clone(),
-        plugin_manager: state.plugin_manager.clone(),
-    });
+    // Drop connection before async operations
+    drop(conn);
 
     let scheduler = state.scheduler.lock().await;
 
     for schedule in schedules {
         println!("[Init] Adding schedule '{}' (ID: {})", schedule.name, schedule.id);
-        if let Err(e) = scheduler.add_schedule(schedule.clone(), state_arc.clone()).await {
+        if let Err(e) = scheduler.add_schedule(schedule.clone(), state.inner().clone()).await {
             eprintln!("[Init] Failed to add schedule '{}': {}", schedule.name, e);
         }
     }
 
     println!("[Init] Finished loading schedules");
 
+    if let Err(e) = scheduler.resume_interrupted_runs(state.inner().clone()).await {
+        eprintln!("[Init] Failed to resume interrupted schedules: {}", e);
+    }
+
     Ok(())
 }
\ No newline at end of file
@@ -0,0 +1,69 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+/// Result or error text the frontend can localize: a stable `code` with its `params`, plus
+/// `message`, the English rendering for clients without a translation of the code.
+/// Plain string errors convert to the code "error" with the text as its only parameter.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppMessage {
+    pub code: String,
+    pub params: Value,
+    pub message: String,
+}
+
+impl AppMessage {
+    pub fn new(code: &str, params: Value) -> Self {
+        AppMessage { code: code.to_string(), message: english(code, &params), params }
+    }
+}
+
+impl From<String> for AppMessage {
+    fn from(message: String) -> Self {
+        AppMessage::new("error", json!({ "message": message }))
+    }
+}
+
+impl From<&str> for AppMessage {
+    fn from(message: &str) -> Self {
+        AppMessage::from(message.to_string())
+    }
+}
+
+impl std::fmt::Display for AppMessage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+// English text of a message; keep in sync with the translations in src/services/messages.ts
+fn english(code: &str, params: &Value) -> String {
+    let param = |name: &str| match &params[name] {
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    };
+
+    match code {
+        "camera.not_found" => "Camera not found".to_string(),
+        "camera.not_onvif" => "Not an ONVIF camera".to_string(),
+        "time_sync.unsupported" => format!("Time synchronization is not supported for {} cameras", param("cameraType")),
+        "time_sync.invalid_camera_time" => "Invalid camera time format".to_string(),
+        "time_sync.verified" => format!("Camera time synchronized successfully (adjusted by {}s, verified)", param("adjustedSecs")),
+        "time_sync.not_applied" => format!(
+            "Camera time may not have been set correctly (before diff: {}s, after diff: {}s)",
+            param("beforeDiffSecs"), param("afterDiffSecs")
+        ),
+        "time_sync.already_synced" => format!("Camera time is already synchronized (difference: {}s)", param("diffSecs")),
+        "time_sync.unverified" => format!("Camera time command sent (adjusted by {}s, verification unavailable)", param("adjustedSecs")),
+        "time_sync.stream_restarted" => "The live stream stalled after the clock change and was restarted".to_string(),
+        "ptz.moving" => "Moving".to_string(),
+        "ptz.stopped" => "Stopped".to_string(),
+        "network.reachable" => format!("Camera is reachable at {}", param("host")),
+        "network.dhcp_moved" => "Camera no longer answers at its old address; its DHCP lease may have changed it. Find it with discovery and update the host".to_string(),
+        "network.unreachable" => format!(
+            "Camera did not answer at {} within {} seconds; check the address, prefix and gateway",
+            param("host"), param("timeoutSecs")
+        ),
+        "error" => param("message"),
+        other => other.to_string(),
+    }
+}
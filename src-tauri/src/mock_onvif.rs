@@ -0,0 +1,492 @@
+//! In-process ONVIF camera for development and the integration tests (feature "mock-onvif").
+//! It answers the SOAP operations the app uses, checks WS-Security digests against its own
+//! (optionally skewed) clock and records what it was asked to do. `RtspTestSource` provides a
+//! matching video stream.
+
+use crate::models::Camera;
+use axum::extract::{Path as UrlPath, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::post;
+use axum::Router;
+use base64::prelude::*;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use roxmltree::{Document, Node};
+use sha1::{Digest, Sha1};
+use std::net::SocketAddr;
+use std::process::{Child, Command, Stdio};
+use std::sync::{Arc, Mutex};
+use tokio::net::{TcpListener, UdpSocket};
+use tokio::task::JoinHandle;
+
+const DISCOVERY_PORT: u16 = 3702;
+// Accepted difference between a UsernameToken's Created and the camera clock
+const AUTH_WINDOW_SECS: i64 = 300;
+
+/// What the mock camera looks like
+#[derive(Debug, Clone)]
+pub struct MockCameraConfig {
+    pub name: String,
+    pub user: String,
+    pub pass: String,
+    /// Returned by GetStreamUri
+    pub stream_uri: String,
+    pub has_ptz: bool,
+    /// Camera clock minus real time
+    pub clock_offset_secs: i64,
+}
+
+impl Default for MockCameraConfig {
+    fn default() -> Self {
+        Self {
+            name: "Mock Camera".to_string(),
+            user: "admin".to_string(),
+            pass: "password".to_string(),
+            stream_uri: "rtsp://127.0.0.1:8554/mock".to_string(),
+            has_ptz: true,
+            clock_offset_secs: 0,
+        }
+    }
+}
+
+/// A ContinuousMove the camera received
+#[derive(Debug, Clone, PartialEq)]
+pub struct PtzMove {
+    pub x: f32,
+    pub y: f32,
+    pub zoom: f32,
+}
+
+#[derive(Default)]
+struct MockState {
+    // Operation names in the order they arrived, rejected ones included
+    requests: Vec<String>,
+    ptz_moves: Vec<PtzMove>,
+    ptz_stops: usize,
+}
+
+#[derive(Clone)]
+struct Shared {
+    config: Arc<Mutex<MockCameraConfig>>,
+    state: Arc<Mutex<MockState>>,
+    base_url: String,
+}
+
+/// A running mock camera; its servers stop when it is dropped
+pub struct MockOnvifServer {
+    shared: Shared,
+    addr: SocketAddr,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl MockOnvifServer {
+    /// Serve the ONVIF services on a free port of 127.0.0.1
+    pub async fn start(config: MockCameraConfig) -> Result<Self, String> {
+        let listener = TcpListener::bind("127.0.0.1:0").await.map_err(|e| e.to_string())?;
+        let addr = listener.local_addr().map_err(|e| e.to_string())?;
+        let shared = Shared {
+            config: Arc::new(Mutex::new(config)),
+            state: Arc::new(Mutex::new(MockState::default())),
+            base_url: format!("http://{}/onvif", addr),
+        };
+
+        let app = Router::new()
+            .route("/onvif/:service", post(handle_soap))
+            .with_state(shared.clone());
+        let server = tokio::spawn(async move {
+            if let Err(e) = axum::serve(listener, app).await {
+                eprintln!("[MockONVIF] Server stopped: {}", e);
+            }
+        });
+
+        println!("[MockONVIF] Serving at {}", addr);
+        Ok(Self { shared, addr, tasks: vec![server] })
+    }
+
+    /// Also answer WS-Discovery probes sent to 127.0.0.1:3702 (one mock at a time)
+    pub async fn start_discovery_responder(&mut self) -> Result<(), String> {
+        let socket = UdpSocket::bind(("127.0.0.1", DISCOVERY_PORT)).await
+            .map_err(|e| format!("Can't bind WS-Discovery port {}: {}", DISCOVERY_PORT, e))?;
+        let shared = self.shared.clone();
+
+        self.tasks.push(tokio::spawn(async move {
+            let mut buf = [0u8; 4096];
+            while let Ok((len, from)) = socket.recv_from(&mut buf).await {
+                let probe = String::from_utf8_lossy(&buf[..len]);
+                if !probe.contains("Probe") {
+                    continue;
+                }
+                let reply = probe_match(&shared);
+                let _ = socket.send_to(reply.as_bytes(), from).await;
+            }
+        }));
+        Ok(())
+    }
+
+    /// Device service URL, as discovery reports it
+    pub fn xaddr(&self) -> String {
+        format!("{}/device_service", self.shared.base_url)
+    }
+
+    pub fn port(&self) -> u16 {
+        self.addr.port()
+    }
+
+    /// A registered ONVIF camera pointing at this mock
+    pub fn camera(&self, id: i32) -> Camera {
+        let config = self.config();
+        let now = Utc::now();
+        Camera {
+            id,
+            name: config.name,
+            camera_type: "onvif".to_string(),
+            host: self.addr.ip().to_string(),
+            port: self.addr.port() as i32,
+            user: Some(config.user),
+            pass: Some(config.pass),
+            xaddr: Some(self.xaddr()),
+            stream_path: None,
+            device_path: None,
+            device_id: None,
+            device_index: None,
+            video_format: None,
+            video_width: None,
+            video_height: None,
+            video_fps: None,
+            tls_verify: false,
+            tls_cert_pem: None,
+            rtsp_transport: "tcp".to_string(),
+            stream_timeout_secs: None,
+            input_tuning: Default::default(),
+            extra_input_args: Vec::new(),
+            extra_output_args: Vec::new(),
+            quality_preset: None,
+            video_filters: Default::default(),
+            time_sync_strategy: None,
+            profile_token: None,
+            sort_order: 0,
+            is_favorite: false,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    pub fn config(&self) -> MockCameraConfig {
+        self.shared.config.lock().unwrap().clone()
+    }
+
+    pub fn set_stream_uri(&self, uri: &str) {
+        self.shared.config.lock().unwrap().stream_uri = uri.to_string();
+    }
+
+    /// The camera's current clock
+    pub fn camera_time(&self) -> DateTime<Utc> {
+        camera_now(&self.shared)
+    }
+
+    pub fn requests(&self) -> Vec<String> {
+        self.shared.state.lock().unwrap().requests.clone()
+    }
+
+    pub fn ptz_moves(&self) -> Vec<PtzMove> {
+        self.shared.state.lock().unwrap().ptz_moves.clone()
+    }
+
+    pub fn ptz_stops(&self) -> usize {
+        self.shared.state.lock().unwrap().ptz_stops
+    }
+}
+
+impl Drop for MockOnvifServer {
+    fn drop(&mut self) {
+        for task in &self.tasks {
+            task.abort();
+        }
+    }
+}
+
+fn camera_now(shared: &Shared) -> DateTime<Utc> {
+    Utc::now() + Duration::seconds(shared.config.lock().unwrap().clock_offset_secs)
+}
+
+async fn handle_soap(State(shared): State<Shared>, UrlPath(service): UrlPath<String>, body: String) -> Response {
+    let Ok(doc) = Document::parse(&body) else {
+        return fault(StatusCode::BAD_REQUEST, "Sender", "WellFormed", "Malformed SOAP envelope");
+    };
+    let Some(operation) = doc.descendants()
+        .find(|n| n.tag_name().name() == "Body")
+        .and_then(|body| body.children().find(|n| n.is_element()))
+    else {
+        return fault(StatusCode::BAD_REQUEST, "Sender", "InvalidArgs", "Empty SOAP body");
+    };
+    let name = operation.tag_name().name().to_string();
+    shared.state.lock().unwrap().requests.push(name.clone());
+
+    // GetSystemDateAndTime is public so clients can measure the clock skew
+    if name != "GetSystemDateAndTime" {
+        if let Err(reason) = check_security(&shared, &doc) {
+            return fault(StatusCode::BAD_REQUEST, "Sender", "NotAuthorized", &reason);
+        }
+    }
+
+    let response = match (service.as_str(), name.as_str()) {
+        ("device_service", "GetSystemDateAndTime") => system_date_time(camera_now(&shared)),
+        ("device_service", "SetSystemDateAndTime") => match set_system_date_time(&shared, operation) {
+            Ok(()) => "<tds:SetSystemDateAndTimeResponse/>".to_string(),
+            Err(reason) => return fault(StatusCode::BAD_REQUEST, "Sender", "InvalidArgVal", &reason),
+        },
+        ("device_service", "GetDeviceInformation") => format!(
+            "<tds:GetDeviceInformationResponse><tds:Manufacturer>Mock</tds:Manufacturer><tds:Model>{}</tds:Model>\
+             <tds:FirmwareVersion>1.0.0</tds:FirmwareVersion><tds:SerialNumber>MOCK0001</tds:SerialNumber>\
+             <tds:HardwareId>mock</tds:HardwareId></tds:GetDeviceInformationResponse>",
+            shared.config.lock().unwrap().name,
+        ),
+        ("device_service", "GetServices") => services(&shared),
+        ("device_service", "GetCapabilities") => capabilities(&shared),
+        ("media", "GetProfiles") => "<trt:GetProfilesResponse><trt:Profiles token=\"profile_1\" fixed=\"true\">\
+             <tt:Name>MainStream</tt:Name></trt:Profiles></trt:GetProfilesResponse>".to_string(),
+        ("media", "GetStreamUri") => format!(
+            "<trt:GetStreamUriResponse><trt:MediaUri><tt:Uri>{}</tt:Uri><tt:InvalidAfterConnect>false</tt:InvalidAfterConnect>\
+             <tt:InvalidAfterReboot>false</tt:InvalidAfterReboot><tt:Timeout>PT0S</tt:Timeout></trt:MediaUri></trt:GetStreamUriResponse>",
+            shared.config.lock().unwrap().stream_uri,
+        ),
+        ("ptz", "ContinuousMove") if shared.config.lock().unwrap().has_ptz => {
+            let velocity = |name: &str| {
+                operation.descendants()
+                    .find(|n| n.tag_name().name() == name)
+                    .and_then(|n| n.attribute("x").map(|x| (x, n.attribute("y"))))
+            };
+            let (x, y) = velocity("PanTilt").unwrap_or(("0", Some("0")));
+            let zoom = velocity("Zoom").map(|(x, _)| x).unwrap_or("0");
+            shared.state.lock().unwrap().ptz_moves.push(PtzMove {
+                x: x.parse().unwrap_or(0.0),
+                y: y.and_then(|y| y.parse().ok()).unwrap_or(0.0),
+                zoom: zoom.parse().unwrap_or(0.0),
+            });
+            "<tptz:ContinuousMoveResponse/>".to_string()
+        }
+        ("ptz", "Stop") if shared.config.lock().unwrap().has_ptz => {
+            shared.state.lock().unwrap().ptz_stops += 1;
+            "<tptz:StopResponse/>".to_string()
+        }
+        _ => return fault(StatusCode::BAD_REQUEST, "Receiver", "ActionNotSupported", &format!("{} is not supported", name)),
+    };
+
+    envelope(StatusCode::OK, &response)
+}
+
+// WS-Security UsernameToken with a PasswordDigest made from our password and a Created time
+// within the window of the camera clock
+fn check_security(shared: &Shared, doc: &Document) -> Result<(), String> {
+    let config = shared.config.lock().unwrap().clone();
+    let text = |name: &str| {
+        doc.descendants()
+            .find(|n| n.tag_name().name() == name)
+            .and_then(|n| n.text())
+            .map(|t| t.trim().to_string())
+    };
+
+    let (Some(user), Some(digest), Some(nonce), Some(created)) = (text("Username"), text("Password"), text("Nonce"), text("Created")) else {
+        return Err("Missing UsernameToken".to_string());
+    };
+    if user != config.user {
+        return Err("Unknown user".to_string());
+    }
+
+    let nonce = BASE64_STANDARD.decode(nonce).map_err(|_| "Invalid nonce".to_string())?;
+    let mut hasher = Sha1::new();
+    hasher.update(&nonce);
+    hasher.update(created.as_bytes());
+    hasher.update(config.pass.as_bytes());
+    if BASE64_STANDARD.encode(hasher.finalize()) != digest {
+        return Err("Wrong password".to_string());
+    }
+
+    let created: DateTime<Utc> = created.parse().map_err(|_| "Invalid Created time".to_string())?;
+    if (created - camera_now(shared)).num_seconds().abs() > AUTH_WINDOW_SECS {
+        return Err("The security token is not in the correct time range".to_string());
+    }
+    Ok(())
+}
+
+fn set_system_date_time(shared: &Shared, operation: Node) -> Result<(), String> {
+    let utc = operation.descendants()
+        .find(|n| n.tag_name().name() == "UTCDateTime")
+        .ok_or("UTCDateTime missing")?;
+    let field = |name: &str| {
+        utc.descendants()
+            .find(|n| n.tag_name().name() == name)
+            .and_then(|n| n.text())
+            .and_then(|t| t.trim().parse::<u32>().ok())
+            .ok_or(format!("{} missing", name))
+    };
+
+    let (hour, minute, second) = (field("Hour")?, field("Minute")?, field("Second")?);
+    let time = NaiveDate::from_ymd_opt(field("Year")? as i32, field("Month")?, field("Day")?)
+        .and_then(|date| date.and_hms_opt(hour, minute, second))
+        .ok_or("Invalid date")?
+        .and_utc();
+    shared.config.lock().unwrap().clock_offset_secs = (time - Utc::now()).num_seconds();
+    Ok(())
+}
+
+fn system_date_time(now: DateTime<Utc>) -> String {
+    format!(
+        "<tds:GetSystemDateAndTimeResponse><tds:SystemDateAndTime><tt:DateTimeType>Manual</tt:DateTimeType>\
+         <tt:DaylightSavings>false</tt:DaylightSavings><tt:TimeZone><tt:TZ>UTC</tt:TZ></tt:TimeZone><tt:UTCDateTime>\
+         <tt:Date><tt:Year>{}</tt:Year><tt:Month>{}</tt:Month><tt:Day>{}</tt:Day></tt:Date>\
+         <tt:Time><tt:Hour>{}</tt:Hour><tt:Minute>{}</tt:Minute><tt:Second>{}</tt:Second></tt:Time>\
+         </tt:UTCDateTime></tds:SystemDateAndTime></tds:GetSystemDateAndTimeResponse>",
+        now.format("%Y"), now.format("%-m"), now.format("%-d"), now.format("%-H"), now.format("%-M"), now.format("%-S"),
+    )
+}
+
+fn services(shared: &Shared) -> String {
+    let mut services = vec![
+        ("http://www.onvif.org/ver10/device/wsdl", "device_service"),
+        ("http://www.onvif.org/ver10/media/wsdl", "media"),
+    ];
+    if shared.config.lock().unwrap().has_ptz {
+        services.push(("http://www.onvif.org/ver20/ptz/wsdl", "ptz"));
+    }
+
+    let services: String = services.iter()
+        .map(|(namespace, path)| format!(
+            "<tds:Service><tds:Namespace>{}</tds:Namespace><tds:XAddr>{}/{}</tds:XAddr>\
+             <tds:Version><tt:Major>2</tt:Major><tt:Minor>0</tt:Minor></tds:Version></tds:Service>",
+            namespace, shared.base_url, path,
+        ))
+        .collect();
+    format!("<tds:GetServicesResponse>{}</tds:GetServicesResponse>", services)
+}
+
+fn capabilities(shared: &Shared) -> String {
+    let ptz = if shared.config.lock().unwrap().has_ptz {
+        format!("<tt:PTZ><tt:XAddr>{}/ptz</tt:XAddr></tt:PTZ>", shared.base_url)
+    } else {
+        String::new()
+    };
+    format!(
+        "<tds:GetCapabilitiesResponse><tds:Capabilities><tt:Device><tt:XAddr>{0}/device_service</tt:XAddr></tt:Device>\
+         <tt:Media><tt:XAddr>{0}/media</tt:XAddr></tt:Media>{1}</tds:Capabilities></tds:GetCapabilitiesResponse>",
+        shared.base_url, ptz,
+    )
+}
+
+fn probe_match(shared: &Shared) -> String {
+    let name = urlencoding::encode(&shared.config.lock().unwrap().name).into_owned();
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<env:Envelope xmlns:env="http://www.w3.org/2003/05/soap-envelope" xmlns:d="http://schemas.xmlsoap.org/ws/2005/04/discovery">
+  <env:Body>
+    <d:ProbeMatches>
+      <d:ProbeMatch>
+        <d:Types>dn:NetworkVideoTransmitter</d:Types>
+        <d:Scopes>onvif://www.onvif.org/name/{} onvif://www.onvif.org/hardware/MockModel onvif://www.onvif.org/MAC/02:00:00:00:00:01</d:Scopes>
+        <d:XAddrs>{}/device_service</d:XAddrs>
+      </d:ProbeMatch>
+    </d:ProbeMatches>
+  </env:Body>
+</env:Envelope>"#,
+        name, shared.base_url,
+    )
+}
+
+fn envelope(status: StatusCode, body: &str) -> Response {
+    let xml = format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<env:Envelope xmlns:env="http://www.w3.org/2003/05/soap-envelope" xmlns:ter="http://www.onvif.org/ver10/error"
+  xmlns:tds="http://www.onvif.org/ver10/device/wsdl" xmlns:trt="http://www.onvif.org/ver10/media/wsdl"
+  xmlns:tptz="http://www.onvif.org/ver20/ptz/wsdl" xmlns:tt="http://www.onvif.org/ver10/schema">
+  <env:Body>{}</env:Body>
+</env:Envelope>"#,
+        body,
+    );
+    (status, [("Content-Type", "application/soap+xml; charset=utf-8")], xml).into_response()
+}
+
+fn fault(status: StatusCode, code: &str, subcode: &str, reason: &str) -> Response {
+    envelope(status, &format!(
+        "<env:Fault><env:Code><env:Value>env:{}</env:Value><env:Subcode><env:Value>ter:{}</env:Value></env:Subcode></env:Code>\
+         <env:Reason><env:Text xml:lang=\"en\">{}</env:Text></env:Reason></env:Fault>",
+        code, subcode, reason,
+    ))
+}
+
+/// An RTSP stream of FFmpeg's test pattern. FFmpeg can publish but not serve RTSP, so the
+/// stream goes through a local mediamtx server; both binaries have to be on the PATH.
+pub struct RtspTestSource {
+    server: Child,
+    publisher: Child,
+    url: String,
+}
+
+impl RtspTestSource {
+    /// Whether ffmpeg, ffprobe and mediamtx are installed
+    pub fn available() -> bool {
+        [("ffmpeg", "-version"), ("ffprobe", "-version"), ("mediamtx", "--version")].iter().all(|(program, arg)| {
+            Command::new(program).arg(arg).stdout(Stdio::null()).stderr(Stdio::null()).status().is_ok()
+        })
+    }
+
+    /// Start publishing at rtsp://127.0.0.1:<port>/test and wait until it can be read
+    pub fn start(port: u16) -> Result<Self, String> {
+        let server = Command::new("mediamtx")
+            .env("MTX_RTSPADDRESS", format!("127.0.0.1:{}", port))
+            .env("MTX_RTMP", "no")
+            .env("MTX_HLS", "no")
+            .env("MTX_WEBRTC", "no")
+            .env("MTX_SRT", "no")
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| format!("Failed to start mediamtx: {}", e))?;
+        std::thread::sleep(std::time::Duration::from_millis(500));
+
+        let url = format!("rtsp://127.0.0.1:{}/test", port);
+        let publisher = Command::new("ffmpeg")
+            .args(["-hide_banner", "-loglevel", "error", "-re"])
+            .args(["-f", "lavfi", "-i", "testsrc=size=640x360:rate=15"])
+            .args(["-c:v", "libx264", "-preset", "ultrafast", "-tune", "zerolatency", "-g", "15"])
+            .args(["-f", "rtsp", "-rtsp_transport", "tcp", &url])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn();
+        let mut source = Self {
+            server,
+            publisher: publisher.map_err(|e| format!("Failed to start ffmpeg: {}", e))?,
+            url,
+        };
+
+        for _ in 0..20 {
+            let probe = Command::new("ffprobe")
+                .args(["-v", "error", "-rtsp_transport", "tcp", "-show_streams", &source.url])
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .status();
+            if probe.is_ok_and(|status| status.success()) {
+                return Ok(source);
+            }
+            std::thread::sleep(std::time::Duration::from_millis(500));
+        }
+        source.stop();
+        Err("RTSP test source did not come up".to_string())
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    fn stop(&mut self) {
+        let _ = self.publisher.kill();
+        let _ = self.publisher.wait();
+        let _ = self.server.kill();
+        let _ = self.server.wait();
+    }
+}
+
+impl Drop for RtspTestSource {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
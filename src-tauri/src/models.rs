@@ -7,7 +7,7 @@ pub struct Camera {
     pub id: i32,
     pub name: String,
     #[serde(rename = "type")]
-    pub camera_type: String, // "onvif", "rtsp", or "uvc"
+    pub camera_type: String, // "onvif", "rtsp", "uvc" "rpicam" or a vendor plugin ("hikvision", "dahua", "axis")
     pub host: String,
     pub port: i32,
     pub user: Option<String>,
@@ -23,11 +23,87 @@ pub struct Camera {
     pub video_width: Option<i32>,      // e.g., 1280
     pub video_height: Option<i32>,     // e.g., 720
     pub video_fps: Option<i32>,        // e.g., 30
+    // Verify the camera's TLS certificate for HTTPS ONVIF endpoints
+    pub tls_verify: bool,
+    // PEM certificate the camera must present; when set, only it is trusted (see OnvifHttpClient)
+    pub tls_cert_pem: Option<String>,
+    // RTSP transport FFmpeg uses: "tcp", "udp", "multicast" or "http" (tunnelled)
+    pub rtsp_transport: String,
+    // Seconds FFmpeg gets to produce the first HLS segment; None uses the app default
+    pub stream_timeout_secs: Option<i32>,
+    // FFmpeg input options for the network stream (set_camera_input_tuning)
+    #[serde(default)]
+    pub input_tuning: InputTuning,
+    // Advanced: arguments added before the camera input and before the stream/recording output
+    // (set_camera_ffmpeg_args)
+    #[serde(default)]
+    pub extra_input_args: Vec<String>,
+    #[serde(default)]
+    pub extra_output_args: Vec<String>,
+    // Named quality preset (encoder::QUALITY_PRESET_NAMES), None to follow encoder.qualityPreset
+    #[serde(default)]
+    pub quality_preset: Option<String>,
+    // Deinterlacing, rotation and flips (set_camera_video_filters)
+    #[serde(default)]
+    pub video_filters: VideoFilters,
+    // Time zone handling of time sync (onvif::TIME_SYNC_STRATEGIES), None for "utc"
+    #[serde(default)]
+    pub time_sync_strategy: Option<String>,
+    // ONVIF media profile streamed, which picks the channel of a multichannel device;
+    // None uses the device's first profile
+    #[serde(default)]
+    pub profile_token: Option<String>,
+    // Position in the camera list and grid (reorder_cameras)
+    pub sort_order: i32,
+    pub is_favorite: bool,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+// Per-camera FFmpeg input options for cameras whose firmware drops or stalls streams;
+// None leaves FFmpeg's default
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InputTuning {
+    pub socket_timeout_ms: Option<i32>, // RTSP socket I/O timeout (-timeout, -stimeout before FFmpeg 5)
+    pub rw_timeout_ms: Option<i32>,     // any single read or write (-rw_timeout)
+    pub reconnect: bool,                // reconnect HTTP(S) sources that drop (-reconnect*)
+    pub reconnect_delay_max_secs: Option<i32>,
+    pub probesize: Option<i64>,         // bytes read to detect the streams
+    pub analyzeduration_ms: Option<i64>,
+    pub user_agent: Option<String>,
+}
+
+// Per-camera corrections for analog encoders that deliver interlaced or rotated video, applied
+// to the live stream, recordings and snapshots
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VideoFilters {
+    pub deinterlace: bool, // yadif
+    pub rotation: i32,     // clockwise degrees: 0, 90, 180 or 270
+    pub hflip: bool,       // mirror horizontally, after rotating
+    pub vflip: bool,
+}
+
+// SOAP trace of a camera (get_onvif_trace)
+#[derive(Debug, Serialize)]
+pub struct OnvifTrace {
+    pub enabled: bool,
+    pub trace: String,
+}
+
+// How delete_camera treats the camera's data
+#[allow(non_snake_case)]
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DeleteCameraOptions {
+    // Keep the camera hidden instead of removing it, so its recordings stay browsable
+    pub archive: bool,
+    // Remove the camera's recordings and snapshots (files included)
+    pub deleteRecordings: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NewCamera {
     pub name: String,
     #[serde(rename = "type")]
@@ -47,6 +123,37 @@ pub struct NewCamera {
     pub video_width: Option<i32>,
     pub video_height: Option<i32>,
     pub video_fps: Option<i32>,
+    #[serde(default)]
+    pub tls_verify: bool,
+    pub tls_cert_pem: Option<String>,
+    // Defaults to "tcp"
+    pub rtsp_transport: Option<String>,
+    pub stream_timeout_secs: Option<i32>,
+    // ONVIF: channel of a multichannel device (OnvifChannel::profile_token)
+    #[serde(default)]
+    pub profile_token: Option<String>,
+}
+
+// Result of import_cameras: the cameras read from another NVR's configuration
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CameraImport {
+    pub source: String,          // "frigate", "shinobi" or "generic" (an exported camera list)
+    pub cameras: Vec<NewCamera>, // created, or on a dry run to be created
+    pub skipped: Vec<SkippedCamera>,
+    pub created: Vec<i32>,       // ids of the new cameras, empty on a dry run
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SkippedCamera {
+    pub name: String,
+    pub reason: String,
+}
+
+// Certificate a camera presents on its HTTPS endpoint (get_camera_certificate)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TlsCertificate {
+    pub pem: String,
+    pub sha256: String, // colon-separated hex fingerprint
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -58,11 +165,301 @@ pub struct Recording {
     pub start_time: DateTime<Utc>,
     pub end_time: Option<DateTime<Utc>>,
     pub is_finished: bool,
+    // What started the recording: "manual", "scheduled:<id>", "motion", "audio" or "continuous"
+    pub trigger: String,
+    pub tags: Vec<String>,
+    pub notes: Option<String>,
+    // Protected recordings are skipped by automatic cleanup and need a forced delete
+    pub is_protected: bool,
+    // Where the archived copy lives (path or URL), once archived
+    pub archive_location: Option<String>,
+    pub archived_at: Option<DateTime<Utc>>,
+    // Result of the last integrity check: "ok", "missing", "corrupt" (None if never checked)
+    pub integrity: Option<String>,
+    pub integrity_detail: Option<String>,
+    pub file_size: Option<i64>,
+    // Recording session the file is a segment of (see RecordingSession)
+    pub session_id: Option<i64>,
+    // Set when FFmpeg exited right after starting: exit status and its last stderr lines.
+    // Failed recordings are finished but have no file.
+    pub failure: Option<String>,
+    // Animated preview made on demand (create_preview), in the thumbnails directory
+    pub preview: Option<String>,
     // Joined fields
     pub camera_name: Option<String>,
 }
 
+/// A recording as the user started it, with its files: one per segment when it was paused
+/// and resumed. Totals cover the finished segments.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordingSession {
+    pub id: i64,
+    pub camera_id: i32,
+    pub camera_name: Option<String>,
+    pub trigger: String,
+    pub started_at: DateTime<Utc>,
+    pub ended_at: Option<DateTime<Utc>>, // None while recording or paused
+    pub segment_count: usize,
+    pub total_duration_secs: f64,
+    pub total_size: i64,
+    // Set when any segment is protected
+    pub is_protected: bool,
+    // Oldest first
+    pub segments: Vec<Recording>,
+}
+
+/// Interval left out of a recording session by pause/resume
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordingGap {
+    pub id: i64,
+    pub session_id: i64,
+    pub camera_id: i32,
+    pub paused_at: DateTime<Utc>,
+    pub resumed_at: Option<DateTime<Utc>>, // None while still paused
+}
+
+/// What started a recording (stored as text in recordings.trigger)
+#[derive(Debug, Clone, PartialEq)]
+pub enum RecordingTrigger {
+    Manual,
+    Scheduled(i32), // schedule id
+    Motion,
+    Audio,
+    Continuous,
+    External, // /api/triggers, trigger_camera or Home Assistant (MQTT)
+}
+
+impl std::fmt::Display for RecordingTrigger {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RecordingTrigger::Manual => write!(f, "manual"),
+            RecordingTrigger::Scheduled(id) => write!(f, "scheduled:{}", id),
+            RecordingTrigger::Motion => write!(f, "motion"),
+            RecordingTrigger::Audio => write!(f, "audio"),
+            RecordingTrigger::Continuous => write!(f, "continuous"),
+            RecordingTrigger::External => write!(f, "external"),
+        }
+    }
+}
+
+impl std::str::FromStr for RecordingTrigger {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "manual" => Ok(RecordingTrigger::Manual),
+            "motion" => Ok(RecordingTrigger::Motion),
+            "audio" => Ok(RecordingTrigger::Audio),
+            "continuous" => Ok(RecordingTrigger::Continuous),
+            "external" => Ok(RecordingTrigger::External),
+            _ => value.strip_prefix("scheduled:")
+                .and_then(|id| id.parse().ok())
+                .map(RecordingTrigger::Scheduled)
+                .ok_or_else(|| format!("Unknown recording trigger '{}'", value)),
+        }
+    }
+}
+
+/// Context stored with a recording when it starts
+#[derive(Debug, Clone)]
+pub struct RecordingMeta {
+    pub trigger: RecordingTrigger,
+    pub tags: Vec<String>,
+    pub notes: Option<String>,
+    // Set when the recording continues a paused session
+    pub session_id: Option<i64>,
+}
+
+impl RecordingMeta {
+    pub fn new(trigger: RecordingTrigger) -> Self {
+        RecordingMeta { trigger, tags: Vec::new(), notes: None, session_id: None }
+    }
+}
+
+// Filters for get_recordings (all optional, combined with AND)
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct RecordingFilter {
+    pub camera_id: Option<i32>,
+    // Exact trigger, or "scheduled" to match recordings from any schedule
+    pub trigger: Option<String>,
+    pub tag: Option<String>,
+    // Case-insensitive substring match on notes
+    pub notes: Option<String>,
+    pub is_protected: Option<bool>,
+    pub integrity: Option<String>,
+    pub session_id: Option<i64>,
+    // true for failed recordings only, false to leave them out
+    pub failed: Option<bool>,
+}
+
+// How the webview should play a recording
+#[allow(non_snake_case)]
 #[derive(Debug, Serialize, Deserialize)]
+pub struct PlaybackSession {
+    pub sessionId: Option<String>, // None when the file is served directly
+    pub url: String,
+    pub transcoded: bool,
+    pub startOffset: f64,          // seconds into the recording where the stream starts
+    pub duration: Option<f64>,     // seconds
+}
+
+// Live state of a camera's streaming and recording FFmpeg processes
+#[allow(non_snake_case)]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PipelineStatus {
+    pub cameraId: i32,
+    pub streaming: bool,
+    pub streamUrl: Option<String>,
+    pub streamStartedAt: Option<DateTime<Utc>>,
+    pub streamUptimeSecs: Option<i64>,
+    pub streamEncoder: Option<String>,
+    pub streamGpu: Option<bool>,
+    pub streamCpuFallback: Option<String>, // why the GPU encoder was given up ("degraded to CPU")
+    pub streamDowngrade: u8,         // load budget downgrade (budget::MAX_DOWNGRADE), 0 for full quality
+    pub streamRestartCount: u32,     // starts after the first one in this session
+    pub recording: bool,
+    pub recordingId: Option<i32>,
+    pub recordingStartedAt: Option<DateTime<Utc>>,
+    pub recordingElapsedSecs: Option<i64>,
+    pub recordingFileSize: Option<u64>, // bytes written so far
+    pub recordingPaused: bool,
+    pub recordingStopsAt: Option<DateTime<Utc>>, // automatic stop of a manual recording with a max duration
+    pub lastError: Option<String>,
+    pub lastErrorAt: Option<DateTime<Utc>>,
+}
+
+// Encoding load against encoder.loadBudget (get_load_budget), in megapixels per second
+#[allow(non_snake_case)]
+#[derive(Debug, Clone, Serialize)]
+pub struct LoadBudgetStatus {
+    pub budget: i32, // 0 for no budget
+    pub load: f64,
+    pub cameras: Vec<CameraLoad>, // cameras streaming or recording
+}
+
+#[allow(non_snake_case)]
+#[derive(Debug, Clone, Serialize)]
+pub struct CameraLoad {
+    pub cameraId: i32,
+    pub priority: String, // budget::PRIORITY_LEVELS
+    pub streamLoad: Option<f64>,    // None when not streaming
+    pub recordingLoad: Option<f64>, // None when not recording
+    pub streamDowngrade: u8,
+}
+
+// Archive written by export_diagnostics
+#[derive(Debug, Clone, Serialize)]
+pub struct DiagnosticsBundle {
+    pub path: String,
+    pub files: Vec<String>, // names inside the zip
+    pub bytes: u64,
+}
+
+// Payload of ping and the backend-heartbeat event
+#[allow(non_snake_case)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackendHeartbeat {
+    pub sentAt: DateTime<Utc>,
+    pub intervalSecs: u64, // until the next heartbeat event
+    pub uptimeSecs: u64,
+    pub activeStreams: usize,
+    pub activeRecordings: usize,
+    pub activePlaybacks: usize,
+    pub dbOk: bool,
+    pub dbError: Option<String>,
+    pub ffmpegAvailable: bool,
+    pub problems: Vec<String>, // empty while the backend is healthy
+}
+
+// What force_cleanup tore down for one camera
+#[allow(non_snake_case)]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PipelineCleanup {
+    pub cameraId: i32,
+    pub streamKilled: bool,
+    pub recordingKilled: bool,
+    pub streamDirRemoved: bool,
+    pub recordingsRemoved: Vec<i32>, // ids of unfinished recording rows; their files are kept
+    pub errors: Vec<String>,         // parts that could not be cleaned up
+}
+
+// Recording coverage for one camera over a time window (NVR-style timeline bar)
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RecordingTimeline {
+    pub camera_id: i32,
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    // Consecutive, non-overlapping ranges spanning the whole window
+    pub ranges: Vec<TimelineRange>,
+    pub markers: Vec<TimelineMarker>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TimelineRange {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub covered: bool,
+    pub recording_ids: Vec<i32>,
+}
+
+// Camera event (e.g. motion) shown on the timeline
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TimelineMarker {
+    pub id: i64,
+    pub event_type: String,
+    pub time: DateTime<Utc>,
+    pub end_time: Option<DateTime<Utc>>,
+}
+
+// Camera event with the footage covering it, for the events browser
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CameraEvent {
+    pub id: i64,
+    pub camera_id: i32,
+    pub camera_name: Option<String>,
+    pub event_type: String,
+    pub start_time: DateTime<Utc>,
+    pub end_time: Option<DateTime<Utc>>,
+    pub details: Option<serde_json::Value>,
+    // Recording (segment) covering the event, None if nothing was recorded
+    pub recording_id: Option<i32>,
+    pub recording_session_id: Option<i64>,
+    // Seconds into the recording where the event starts (start_secs for playback)
+    pub recording_offset_secs: Option<f64>,
+    // Animated preview of a motion event, made once its recording is finished
+    pub preview: Option<String>,
+}
+
+// Outcome of a batch operation on recordings (failures don't abort the batch)
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BatchResult {
+    pub succeeded: Vec<i32>,
+    pub failed: Vec<BatchFailure>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BatchFailure {
+    pub id: i32,
+    pub error: String,
+}
+
+// Text or PNG logo burned into exported clips (the recordings themselves are left alone)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Watermark {
+    pub text: Option<String>,
+    pub image_path: Option<String>, // PNG, drawn at its own size
+    pub position: String,           // top_left, top_right, bottom_left, bottom_right or center
+    pub opacity: f32,               // 0.0-1.0
+    pub font_size: Option<u32>,     // text only; default scales with the video height
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateRecordingMeta {
+    pub tags: Option<Vec<String>>,
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DiscoveredDevice {
     pub address: String,
     pub port: i32,
@@ -70,6 +467,76 @@ pub struct DiscoveredDevice {
     pub name: String,
     pub manufacturer: String,
     pub xaddr: Option<String>,
+    // From an "onvif://www.onvif.org/MAC/..." scope, which some devices publish
+    #[serde(default)]
+    pub mac: Option<String>,
+}
+
+// ONVIF device found by probing an address directly (same shape as a discovered camera,
+// plus the device information reported by the camera)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProbedDevice {
+    pub name: String,
+    pub host: String,
+    pub port: u16,
+    pub camera_type: String,
+    pub user: Option<String>,
+    pub pass: Option<String>,
+    pub xaddr: String,
+    pub manufacturer: String,
+    pub model: String,
+    pub firmware_version: String,
+    pub serial_number: String,
+    pub hardware_id: String,
+    pub has_ptz: bool,
+    // Video inputs; more than one for multichannel encoders and NVRs. Empty when the
+    // profiles couldn't be read.
+    #[serde(default)]
+    pub channels: Vec<OnvifChannel>,
+}
+
+// Video source of an ONVIF device with the media profile used to stream it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OnvifChannel {
+    pub profile_token: String,
+    pub profile_name: String,
+    pub source_token: Option<String>, // VideoSourceConfiguration SourceToken
+    pub width: Option<i32>,           // encoder resolution of the profile
+    pub height: Option<i32>,
+}
+
+// What ffprobe reports for a network stream
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StreamProbe {
+    pub video_codec: Option<String>,
+    pub audio_codec: Option<String>,
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+    pub fps: Option<i32>, // average frame rate, rounded
+}
+
+// Camera created by add_camera_from_url with the probe that filled in its metadata
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuickAddedCamera {
+    pub camera: Camera,
+    pub probe: StreamProbe,
+}
+
+// Raspberry Pi CSI camera as listed by libcamera, with the sensor modes to pick a
+// resolution and frame rate from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpiCameraInfo {
+    pub index: u32,
+    pub sensor: String, // e.g. "imx708"
+    pub path: String,
+    pub modes: Vec<RpiCameraMode>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpiCameraMode {
+    pub width: i32,
+    pub height: i32,
+    pub max_fps: f64,
 }
 
 #[allow(non_snake_case)]
@@ -77,6 +544,30 @@ pub struct DiscoveredDevice {
 pub struct CameraTimeInfo {
     pub cameraTime: serde_json::Value, // Using Value for flexibility
     pub serverTime: String,
+    pub timeZone: Option<String>, // POSIX TZ configured on the camera, when it reports one
+    pub daylightSavings: bool,
+}
+
+#[allow(non_snake_case)]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CameraClockStatus {
+    pub cameraId: i32,
+    pub cameraName: String,
+    pub reachable: bool,
+    pub cameraTime: Option<String>, // RFC 3339, UTC
+    pub driftSecs: Option<i64>,     // camera minus server; positive when the camera is ahead
+    pub error: Option<String>,
+}
+
+#[allow(non_snake_case)]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CameraClockReport {
+    pub serverTime: String,
+    pub cameras: Vec<CameraClockStatus>,
+    pub reachableCount: usize,
+    pub unreachableCount: usize,
+    pub driftedCount: usize, // reachable cameras off by CLOCK_DRIFT_TOLERANCE_SECS or more
+    pub maxDriftSecs: Option<i64>, // largest drift by magnitude
 }
 
 #[allow(non_snake_case)]
@@ -85,7 +576,10 @@ pub struct TimeSyncResult {
     pub success: bool,
     pub beforeTime: serde_json::Value,
     pub serverTime: String,
-    pub message: String,
+    pub timeZone: Option<String>, // POSIX TZ the camera was given; None for UTC or plugin cameras
+    pub streamRestarted: bool, // the live stream stalled after the clock change and was restarted
+    #[serde(flatten)]
+    pub message: crate::messages::AppMessage, // code, params and English message
     pub error: Option<String>,
 }
 
@@ -116,7 +610,108 @@ pub struct PTZMovement {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct PTZResult {
     pub success: bool,
-    pub message: String,
+    #[serde(flatten)]
+    pub message: crate::messages::AppMessage,
+}
+
+// App login account (see auth)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppUser {
+    pub id: i32,
+    pub username: String,
+    pub role: crate::auth::Role,
+    pub created_at: DateTime<Utc>,
+    pub last_login_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NewAppUser {
+    pub username: String,
+    pub password: String,
+    pub role: crate::auth::Role,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateAppUser {
+    pub role: Option<crate::auth::Role>,
+    pub password: Option<String>,
+}
+
+// Result of a login; the token also authorizes HTTP API requests
+#[derive(Debug, Clone, Serialize)]
+pub struct AuthSession {
+    pub token: String,
+    pub user: AppUser,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AuthStatus {
+    // auth.required setting
+    pub required: bool,
+    pub has_admin: bool,
+    // Logged-in user of the app window
+    pub user: Option<AppUser>,
+}
+
+// ONVIF camera-side user accounts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OnvifUser {
+    pub username: String,
+    pub user_level: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OnvifUserChange {
+    pub username: String,
+    pub password: Option<String>,
+    pub user_level: String,
+    // Switch the app's stored credentials for this camera to this account
+    #[serde(default)]
+    pub use_for_app: bool,
+}
+
+// ONVIF network settings (GetNetworkInterfaces, GetDNS, GetNetworkDefaultGateway)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NetworkInterface {
+    pub token: String,
+    pub name: Option<String>,
+    pub hw_address: Option<String>,
+    pub enabled: bool,
+    pub dhcp: bool,
+    // Address in use: the DHCP lease when dhcp is set, else the first manual address
+    pub ipv4_address: Option<String>,
+    pub prefix_length: Option<u8>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NetworkConfig {
+    pub interfaces: Vec<NetworkInterface>,
+    pub gateways: Vec<String>,
+    pub dns_from_dhcp: bool,
+    pub dns_servers: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NetworkChange {
+    pub interface_token: String,
+    pub dhcp: bool,
+    // Required for a static configuration
+    pub ipv4_address: Option<String>,
+    pub prefix_length: Option<u8>,
+    pub gateway: Option<String>,
+    // DNS is left unchanged when both are omitted
+    pub dns_from_dhcp: Option<bool>,
+    pub dns_servers: Option<Vec<String>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NetworkChangeResult {
+    pub camera: Camera,       // with host and xaddr moved to the new address
+    pub rebooted: bool,       // the camera needed a reboot to apply the change
+    pub reachable: bool,      // answered at its new address within the re-check window
+    #[serde(flatten)]
+    pub message: crate::messages::AppMessage,
 }
 
 #[allow(non_snake_case)]
@@ -166,12 +761,452 @@ pub struct UpdateEncoderSettings {
     pub quality: Option<i32>,
 }
 
+// Archive Settings
+#[allow(non_snake_case)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchiveSettings {
+    pub id: i32,
+    pub targetType: String,           // "local" (also mounted SMB/NFS shares) or "webdav"
+    pub target: Option<String>,       // directory path or WebDAV collection URL
+    pub webdavUser: Option<String>,
+    pub webdavPass: Option<String>,
+    pub autoArchive: bool,            // archive recordings before automatic cleanup deletes them
+}
+
+#[allow(non_snake_case)]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateArchiveSettings {
+    pub targetType: Option<String>,
+    pub target: Option<String>,
+    pub webdavUser: Option<String>,
+    pub webdavPass: Option<String>,
+    pub autoArchive: Option<bool>,
+}
+
+// Local HTTP server settings
+#[allow(non_snake_case)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerSettings {
+    pub id: i32,
+    pub downloadLimitKbps: Option<i32>, // per-download bandwidth cap, None for unlimited
+}
+
+#[allow(non_snake_case)]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateServerSettings {
+    pub downloadLimitKbps: Option<i32>, // 0 removes the limit
+}
+
+// Live HLS output of the camera streams
+#[allow(non_snake_case)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HlsSettings {
+    pub id: i32,
+    pub segmentSecs: i32,           // target segment duration
+    pub listSize: i32,              // segments in the playlist (the rewind window)
+    pub deleteThreshold: i32,       // unlisted segments kept before deletion
+    pub segmentDir: Option<String>, // e.g. a tmpfs mount for the segments, None for the app data dir
+    pub timeshiftMinutes: i32,      // live rewind window kept in the playlist, 0 for off
+    pub stallSegments: i32,         // segment durations without output before a restart, 0 for off
+}
+
+#[allow(non_snake_case)]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateHlsSettings {
+    pub segmentSecs: Option<i32>,
+    pub listSize: Option<i32>,
+    pub deleteThreshold: Option<i32>,
+    pub segmentDir: Option<String>, // "" goes back to the app data dir
+    pub timeshiftMinutes: Option<i32>,
+    pub stallSegments: Option<i32>,
+}
+
+// Per-camera HLS overrides; None fields follow hls_settings
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CameraHlsSettings {
+    pub camera_id: i32,
+    pub segment_secs: Option<i32>,
+    pub list_size: Option<i32>,
+    pub delete_threshold: Option<i32>,
+    pub segment_dir: Option<String>,
+    pub timeshift_minutes: Option<i32>,
+    pub packaging: Option<String>, // one of hls::PACKAGINGS
+}
+
+// Live rewind window of a running stream
+#[allow(non_snake_case)]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TimeshiftInfo {
+    pub cameraId: i32,
+    pub live: bool,
+    pub windowSecs: i64,                      // configured window (timeshift minutes, or the playlist length)
+    pub availableSecs: f64,                   // video currently in the playlist
+    pub segmentCount: usize,
+    pub oldestTime: Option<DateTime<Utc>>,    // wall-clock time of the first frame that can be replayed
+    pub newestTime: Option<DateTime<Utc>>,    // end of the latest segment
+    pub streamUrl: Option<String>,
+}
+
+// Manual recording settings
+#[allow(non_snake_case)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordingSettings {
+    pub id: i32,
+    pub defaultMaxDurationMinutes: Option<i32>, // manual recordings stop after this long, None for no limit
+}
+
+#[allow(non_snake_case)]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateRecordingSettings {
+    pub defaultMaxDurationMinutes: Option<i32>, // 0 removes the limit
+}
+
+// External triggers (/api/triggers/{camera_id})
+#[allow(non_snake_case)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TriggerSettings {
+    pub id: i32,
+    pub token: String,              // accepted by /api/triggers across launches
+    pub allowRemote: bool,          // also listen on all interfaces (takes effect on restart)
+    pub remotePort: u16,
+    pub defaultRecordMinutes: i32,  // when a trigger doesn't give a duration
+}
+
+#[allow(non_snake_case)]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateTriggerSettings {
+    pub allowRemote: Option<bool>,
+    pub remotePort: Option<u16>,
+    pub defaultRecordMinutes: Option<i32>,
+    pub regenerateToken: Option<bool>,
+}
+
+// Settings of a camera plugin with the schema they follow (None if it has no settings)
+#[allow(non_snake_case)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginConfig {
+    pub pluginType: String,
+    pub schema: Option<serde_json::Value>,
+    pub config: serde_json::Value, // stored values with schema defaults filled in
+}
+
+// Home Assistant MQTT discovery
+#[allow(non_snake_case)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MqttSettings {
+    pub id: i32,
+    pub enabled: bool,
+    pub host: Option<String>,
+    pub port: u16,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub clientId: String,
+    pub discoveryPrefix: String, // Home Assistant's discovery prefix, "homeassistant" by default
+    pub baseTopic: String,       // state and command topics live under <baseTopic>/<camera id>/
+    pub connected: bool,         // runtime state, not stored
+}
+
+#[allow(non_snake_case)]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateMqttSettings {
+    pub enabled: Option<bool>,
+    pub host: Option<String>,
+    pub port: Option<u16>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub clientId: Option<String>,
+    pub discoveryPrefix: Option<String>,
+    pub baseTopic: Option<String>,
+}
+
+#[allow(non_snake_case)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TriggerRequest {
+    pub action: Option<String>,       // "record" (default) or "snapshot"
+    pub reason: Option<String>,       // e.g. "doorbell", stored with the event
+    pub durationMinutes: Option<i32>, // recording length, defaults to defaultRecordMinutes
+}
+
+#[allow(non_snake_case)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TriggerResult {
+    pub eventId: i64,
+    pub action: String,
+    pub recordingId: Option<i64>,
+    pub alreadyRecording: bool, // a recording was running, so none was started
+    pub snapshot: Option<Snapshot>,
+}
+
+// Result of verify_recordings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VerifyReport {
+    pub checked: usize,
+    pub ok: usize,
+    pub missing: usize,
+    pub corrupt: usize,
+    pub issues: Vec<RecordingIssue>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordingIssue {
+    pub id: i32,
+    pub filename: String,
+    pub status: String, // "missing" or "corrupt"
+    pub detail: Option<String>,
+}
+
+// Entry in the archive transfer queue
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchiveJob {
+    pub recording_id: i32,
+    pub filename: String,
+    pub status: String, // "queued", "transferring", "verifying", "done", "failed"
+    pub bytes_done: u64,
+    pub bytes_total: u64,
+    pub location: Option<String>,
+    pub error: Option<String>,
+}
+
+// Cloud (S3-compatible) Upload Settings
+#[allow(non_snake_case)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CloudUploadSettings {
+    pub id: i32,
+    pub enabled: bool,                     // default policy for cameras without their own
+    pub endpoint: Option<String>,          // e.g. https://s3.amazonaws.com or http://minio:9000
+    pub region: String,
+    pub bucket: Option<String>,
+    pub accessKey: Option<String>,
+    pub secretKey: Option<String>,
+    pub prefix: String,                    // key prefix, objects go to <prefix><camera_id>/<filename>
+    pub pathStyle: bool,                   // bucket in the path instead of the host name (MinIO)
+    pub includeThumbnails: bool,
+    pub retentionDays: Option<i32>,        // delete uploaded objects after this many days
+    pub bandwidthLimitKbps: Option<i32>,
+    pub maxRetries: i32,
+}
+
+#[allow(non_snake_case)]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateCloudUploadSettings {
+    pub enabled: Option<bool>,
+    pub endpoint: Option<String>,
+    pub region: Option<String>,
+    pub bucket: Option<String>,
+    pub accessKey: Option<String>,
+    pub secretKey: Option<String>,
+    pub prefix: Option<String>,
+    pub pathStyle: Option<bool>,
+    pub includeThumbnails: Option<bool>,
+    pub retentionDays: Option<i32>,        // 0 keeps uploads forever
+    pub bandwidthLimitKbps: Option<i32>,   // 0 removes the limit
+    pub maxRetries: Option<i32>,
+}
+
+// Per-camera audio level alerts
+#[allow(non_snake_case)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AudioMonitorSettings {
+    pub cameraId: i32,
+    pub enabled: bool,
+    pub thresholdDb: f64,        // alert when the level rises above this (dBFS, e.g. -20)
+    pub cooldownSecs: i32,       // minimum time between alerts
+    pub recordOnAlert: bool,
+    pub recordMinutes: i32,      // length of recordings started by an alert
+    pub running: bool,           // monitor task is active
+}
+
+#[allow(non_snake_case)]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateAudioMonitorSettings {
+    pub enabled: Option<bool>,
+    pub thresholdDb: Option<f64>,
+    pub cooldownSecs: Option<i32>,
+    pub recordOnAlert: Option<bool>,
+    pub recordMinutes: Option<i32>,
+}
+
+// Region of the frame analysed for motion, as fractions of width and height
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MotionRoi {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+// Per-camera FFmpeg motion detection (for cameras without ONVIF analytics)
+#[allow(non_snake_case)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MotionDetectorSettings {
+    pub cameraId: i32,
+    pub enabled: bool,
+    pub sensitivity: i32,        // 1..100, higher reacts to smaller changes
+    pub roi: Option<MotionRoi>,  // None for the whole frame
+    pub cooldownSecs: i32,       // minimum time between motion events
+    pub recordOnMotion: bool,
+    pub recordMinutes: i32,      // length of recordings started by motion
+    pub running: bool,           // detection task is active
+}
+
+#[allow(non_snake_case)]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateMotionDetectorSettings {
+    pub enabled: Option<bool>,
+    pub sensitivity: Option<i32>,
+    pub roi: Option<MotionRoi>,  // the whole frame (0, 0, 1, 1) removes the region
+    pub cooldownSecs: Option<i32>,
+    pub recordOnMotion: Option<bool>,
+    pub recordMinutes: Option<i32>,
+}
+
+// Object detection backend (single row)
+#[allow(non_snake_case)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnalyticsSettings {
+    pub id: i32,
+    pub backend: String,                 // "http" or "onnx"
+    pub modelPath: Option<String>,       // ONNX model file (onnx backend)
+    pub endpointUrl: Option<String>,     // detection server URL (http backend)
+    pub sampleFps: f64,                  // frames analysed per second of a live stream
+    pub availableBackends: Vec<String>,  // backends compiled into this build
+}
+
+#[allow(non_snake_case)]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateAnalyticsSettings {
+    pub backend: Option<String>,
+    pub modelPath: Option<String>,
+    pub endpointUrl: Option<String>,
+    pub sampleFps: Option<f64>,
+}
+
+// Per-camera object detection
+#[allow(non_snake_case)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ObjectDetectorSettings {
+    pub cameraId: i32,
+    pub enabled: bool,
+    pub minConfidence: f64,      // 0..1
+    pub classes: Vec<String>,    // categories that create events: "person", "vehicle", "animal"
+    pub cooldownSecs: i32,       // minimum time between object events
+    pub running: bool,           // detection task is active
+}
+
+#[allow(non_snake_case)]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateObjectDetectorSettings {
+    pub enabled: Option<bool>,
+    pub minConfidence: Option<f64>,
+    pub classes: Option<Vec<String>>,
+    pub cooldownSecs: Option<i32>,
+}
+
+// Per-camera override of the global upload policy
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CameraUploadPolicy {
+    pub camera_id: i32,
+    pub enabled: bool,
+}
+
+// Container a camera records to, one of stream::RECORDING_CONTAINERS
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CameraRecordingFormat {
+    pub camera_id: i32,
+    pub container: String,
+}
+
+// Recording directory besides the built-in one (see storage)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StorageLocation {
+    pub id: i64,
+    pub name: String,
+    pub path: String,
+    // Lower is tried first when a camera's own location is full or unavailable
+    pub priority: i32,
+    // Skipped for new recordings below this much free space
+    pub min_free_mb: i64,
+    pub enabled: bool,
+    // Status when listed: whether it takes recordings now, and its disk space
+    pub available: bool,
+    pub free_bytes: Option<u64>,
+    pub total_bytes: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewStorageLocation {
+    pub name: String,
+    pub path: String,
+    pub priority: i32,
+    pub min_free_mb: i64,
+    pub enabled: bool,
+}
+
+// Storage location a camera records to first
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CameraStorage {
+    pub camera_id: i32,
+    pub location_id: i64,
+}
+
+// Named set of cameras, e.g. "Outdoor", that a schedule can target
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CameraGroup {
+    pub id: i64,
+    pub name: String,
+    pub camera_ids: Vec<i32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NewCameraGroup {
+    pub name: String,
+    pub camera_ids: Vec<i32>,
+}
+
+// Seconds a camera's pre-recording buffer keeps, see prebuffer::PREBUFFER_SECS_RANGE
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CameraPrebuffer {
+    pub camera_id: i32,
+    pub seconds: i32,
+}
+
+// Row of the background job queue
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackgroundJob {
+    pub id: i64,
+    pub kind: String,             // one of jobs::JOB_KINDS
+    pub params: serde_json::Value,
+    pub status: String,           // "queued", "running", "done", "failed", "cancelled"
+    pub progress: f64,            // 0.0-1.0
+    pub message: Option<String>,  // summary once done
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+// Entry in the cloud upload queue
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UploadJob {
+    pub recording_id: i32,
+    pub filename: String,
+    pub status: String, // "queued", "uploading", "retrying", "done", "failed"
+    pub attempts: u32,
+    pub bytes_done: u64,
+    pub bytes_total: u64,
+    pub key: Option<String>,
+    pub next_retry_at: Option<DateTime<Utc>>,
+    pub error: Option<String>,
+}
+
 // Recording Schedule
 #[allow(non_snake_case)]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RecordingSchedule {
     pub id: i32,
-    pub camera_id: i32,
+    // Either a single camera or a camera group
+    pub camera_id: Option<i32>,
+    pub group_id: Option<i64>,
     pub name: String,
     pub cron_expression: String,
     pub duration_minutes: i32,
@@ -179,16 +1214,129 @@ pub struct RecordingSchedule {
     pub is_enabled: bool,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    // "cron", or "weekly" when defined by weekly_blocks (cron_expression and
+    // duration_minutes then hold the first compiled block)
+    pub schedule_type: String,
+    pub weekly_blocks: Option<Vec<WeeklyBlock>>,
+    // "record", or "snapshot" to capture a still image on each trigger
+    // (duration_minutes and fps are then unused)
+    pub action: String,
+    pub retention_days: Option<i32>, // snapshot schedules: delete captures older than this
     // Joined fields
     pub camera_name: Option<String>,
+    pub group_name: Option<String>,
+    pub is_paused: bool, // all schedules are paused (scheduler_settings.schedules_enabled)
     // Computed field (not stored in DB)
-    pub next_run: Option<String>, // ISO 8601 format (JST)
+    pub next_run: Option<String>, // ISO 8601 in the configured time zone
+}
+
+// Time range recorded on each of the given days, e.g. Mon-Fri 09:00-18:00
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WeeklyBlock {
+    pub days: Vec<u8>,  // ISO weekdays: 1 = Monday .. 7 = Sunday
+    pub start: String,  // "HH:MM" in the configured time zone
+    pub end: String,    // "HH:MM"; at or before start means the block ends the next day
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NewWeeklySchedule {
+    // Set one of camera_id and group_id
+    pub camera_id: Option<i32>,
+    pub group_id: Option<i64>,
+    pub name: String,
+    pub blocks: Vec<WeeklyBlock>,
+    pub fps: Option<i32>,
+    pub is_enabled: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateWeeklySchedule {
+    pub name: Option<String>,
+    pub blocks: Option<Vec<WeeklyBlock>>,
+    pub fps: Option<i32>,
+    pub is_enabled: Option<bool>,
+}
+
+// Snapshot schedule: either a cron expression or a fixed interval
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NewSnapshotSchedule {
+    pub camera_id: i32,
+    pub name: String,
+    pub cron_expression: Option<String>,
+    pub interval_minutes: Option<i32>,
+    pub retention_days: Option<i32>,
+    pub is_enabled: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateSnapshotSchedule {
+    pub name: Option<String>,
+    pub cron_expression: Option<String>,
+    pub interval_minutes: Option<i32>,
+    pub retention_days: Option<i32>, // 0 keeps snapshots forever
+    pub is_enabled: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub id: i64,
+    pub camera_id: i32,
+    pub schedule_id: Option<i32>,
+    pub filename: String, // relative to the recordings dir, e.g. "snapshots/3/20240501_134500.jpg"
+    pub captured_at: DateTime<Utc>,
+    pub file_size: Option<i64>,
+    // Joined fields
+    pub camera_name: Option<String>,
+}
+
+// Result of capture_frame
+#[derive(Debug, Clone, Serialize)]
+pub struct CapturedFrame {
+    pub path: String,               // where the image was written
+    pub from_live_stream: bool,     // taken from the running live stream rather than the camera
+    pub snapshot: Option<Snapshot>, // library entry, when no destination was given
+}
+
+// Scheduler Settings
+#[allow(non_snake_case)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchedulerSettings {
+    pub id: i32,
+    pub maxConcurrentRecordings: Option<i32>, // None for unlimited
+    pub maxDeferMinutes: i32,                 // how long a schedule may wait for a free slot
+    pub staggerSeconds: i32,                  // minimum gap between scheduled starts
+    pub schedulesEnabled: bool,               // false while all schedules are paused
 }
 
 #[allow(non_snake_case)]
 #[derive(Debug, Serialize, Deserialize)]
-pub struct NewRecordingSchedule {
+pub struct UpdateSchedulerSettings {
+    pub maxConcurrentRecordings: Option<i32>, // 0 removes the limit
+    pub maxDeferMinutes: Option<i32>,
+    pub staggerSeconds: Option<i32>,
+    pub schedulesEnabled: Option<bool>,
+}
+
+// One execution of a schedule
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduleRun {
+    pub id: i64,
+    pub schedule_id: i32,
     pub camera_id: i32,
+    pub scheduled_at: DateTime<Utc>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub planned_stop_at: Option<DateTime<Utc>>,
+    pub status: String, // "pending", "queued", "recording", "completed", "deferred", "failed", "interrupted"
+    pub detail: Option<String>,
+}
+
+#[allow(non_snake_case)]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NewRecordingSchedule {
+    // Set one of camera_id and group_id
+    pub camera_id: Option<i32>,
+    pub group_id: Option<i64>,
     pub name: String,
     pub cron_expression: String,
     pub duration_minutes: i32,
@@ -205,3 +1353,140 @@ pub struct UpdateRecordingSchedule {
     pub fps: Option<i32>,
     pub is_enabled: Option<bool>,
 }
+
+// Activity report (report::generate_report): what was recorded and what went wrong in a time range
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReportRange {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActivityReport {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub generated_at: DateTime<Utc>,
+    pub recordings: i64,
+    pub recorded_bytes: i64,
+    pub recorded_secs: i64,
+    pub cameras: Vec<CameraActivity>,
+    pub failure_count: i64,
+    pub failures: Vec<ReportFailure>, // most recent first, capped
+    pub offline_cameras: Vec<OfflineCamera>,
+    pub daily_usage: Vec<DailyUsage>, // bytes recorded per local day
+    pub storage: Vec<StorageUsage>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CameraActivity {
+    pub camera_id: i32,
+    pub camera_name: String,
+    pub recordings: i64,
+    pub recorded_bytes: i64,
+    pub recorded_secs: i64,
+    pub failures: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReportFailure {
+    pub camera_id: i32,
+    pub camera_name: Option<String>,
+    pub kind: String, // "recording" or "schedule"
+    pub detail: Option<String>,
+    pub occurred_at: DateTime<Utc>,
+}
+
+// Camera whose live stream died during the range
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OfflineCamera {
+    pub camera_id: i32,
+    pub camera_name: Option<String>,
+    pub incidents: i64,
+    pub last_incident_at: DateTime<Utc>,
+    pub last_reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DailyUsage {
+    pub date: String, // YYYY-MM-DD in the configured time zone
+    pub bytes: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StorageUsage {
+    pub name: String,
+    pub path: String,
+    pub free_bytes: Option<u64>,
+    pub total_bytes: Option<u64>,
+    pub days_left: Option<i64>, // at the range's average daily recording volume
+}
+
+// Result of run_camera_diagnostics: which ONVIF operations the camera handles
+#[derive(Debug, Clone, Serialize)]
+pub struct CameraDiagnostics {
+    pub camera_id: i32,
+    pub camera_name: String,
+    pub ran_at: DateTime<Utc>,
+    pub checks: Vec<DiagnosticCheck>, // in the order they ran
+    pub passed: usize,
+    pub failed: usize,
+    pub skipped: usize,
+    pub conclusion: String, // whether the failures point at the camera, its setup or the app
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DiagnosticCheck {
+    pub operation: String, // e.g. "GetProfiles", or "Snapshot" for the image download
+    pub status: String,    // "pass", "fail" or "skipped"
+    pub detail: Option<String>, // what the camera answered, or why the check failed or was skipped
+    pub http_status: Option<u16>,
+    pub fault: Option<DiagnosticFault>,
+    pub duration_ms: Option<u64>, // None when skipped
+}
+
+// SOAP fault a camera returned, as sent
+#[derive(Debug, Clone, Serialize)]
+pub struct DiagnosticFault {
+    pub code: String,
+    pub subcodes: Vec<String>,
+    pub reason: String,
+    pub raw: String, // response body, credentials masked and truncated
+}
+
+// Sample of get_resource_usage / the resource-usage event
+#[derive(Debug, Clone, Serialize)]
+pub struct ResourceUsage {
+    pub sampled_at: DateTime<Utc>,
+    pub cpu_percent: f32, // all cores together, 0-100
+    pub cpu_count: usize,
+    pub memory_used_bytes: u64,
+    pub memory_total_bytes: u64,
+    pub gpus: Vec<GpuUsage>,
+    pub processes: Vec<ProcessUsage>, // pipeline FFmpeg processes
+    pub ffmpeg_cpu_percent: f32,      // sum over `processes`, 100 per fully used core
+    pub ffmpeg_memory_bytes: u64,
+    pub warnings: Vec<String>, // resources close to their limit, e.g. "GPU 0 encoder at 97%"
+}
+
+// Load of one GPU; None where the vendor tool doesn't report a value
+#[derive(Debug, Clone, Serialize)]
+pub struct GpuUsage {
+    pub index: u32,
+    pub vendor: String, // "NVIDIA", "Intel" or "AMD"
+    pub name: Option<String>,
+    pub utilization_percent: Option<f32>,
+    pub encoder_percent: Option<f32>, // Intel reports its video engine (encode and decode) here
+    pub decoder_percent: Option<f32>,
+    pub memory_used_bytes: Option<u64>,
+    pub memory_total_bytes: Option<u64>,
+    pub temperature_c: Option<f32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProcessUsage {
+    pub pid: u32,
+    pub kind: String, // "stream", "recording" or "playback"
+    pub camera_id: Option<i32>, // None for playback transcodes
+    pub cpu_percent: f32, // 100 per fully used core
+    pub memory_bytes: u64,
+}
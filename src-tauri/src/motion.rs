@@ -0,0 +1,269 @@
+use crate::events::AppEvent;
+use crate::models::{MotionDetectorSettings, MotionRoi, RecordingMeta, RecordingTrigger};
+use crate::AppState;
+use rusqlite::Connection;
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+use tokio::io::{AsyncBufReadExt, BufReader};
+
+// The secondary decode only needs a few small frames per second
+const ANALYSIS_FPS: u32 = 2;
+const ANALYSIS_WIDTH: u32 = 320;
+// A motion event ends after this long without a changed frame
+const MOTION_END_SECS: u64 = 5;
+// Wait before reconnecting after the analysing FFmpeg exits (camera offline, UVC stream stopped, ...)
+const RESTART_DELAY_SECS: u64 = 30;
+
+/// Start (or restart with fresh settings) motion detection for a camera
+pub fn start(app_handle: &AppHandle, camera_id: i32) {
+    let state = app_handle.state::<AppState>();
+    state.motion_detectors.start(camera_id, detect_camera(app_handle.clone(), camera_id));
+}
+
+pub fn stop(state: &AppState, camera_id: i32) {
+    if state.motion_detectors.stop(camera_id) {
+        println!("[Motion] Stopped motion detection for camera {}", camera_id);
+    }
+}
+
+/// Settings of one camera; cameras without a row get the defaults (disabled)
+pub fn load_settings(conn: &Connection, camera_id: i32) -> Result<MotionDetectorSettings, String> {
+    let settings = conn.query_row(
+        "SELECT enabled, sensitivity, roi_x, roi_y, roi_width, roi_height, cooldown_secs, record_on_motion, record_minutes
+         FROM motion_detectors WHERE camera_id = ?1",
+        [camera_id],
+        |row| {
+            let roi = match (row.get(2)?, row.get(3)?, row.get(4)?, row.get(5)?) {
+                (Some(x), Some(y), Some(width), Some(height)) => Some(MotionRoi { x, y, width, height }),
+                _ => None,
+            };
+            Ok(MotionDetectorSettings {
+                cameraId: camera_id,
+                enabled: row.get(0)?,
+                sensitivity: row.get(1)?,
+                roi,
+                cooldownSecs: row.get(6)?,
+                recordOnMotion: row.get(7)?,
+                recordMinutes: row.get(8)?,
+                running: false,
+            })
+        },
+    );
+
+    match settings {
+        Ok(settings) => Ok(settings),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(MotionDetectorSettings {
+            cameraId: camera_id,
+            enabled: false,
+            sensitivity: 50,
+            roi: None,
+            cooldownSecs: 30,
+            recordOnMotion: false,
+            recordMinutes: 5,
+            running: false,
+        }),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Check the region lies inside the frame (fractions of width and height)
+pub fn validate_roi(roi: &MotionRoi) -> Result<(), String> {
+    let in_frame = |v: f64| (0.0..=1.0).contains(&v);
+    if !(in_frame(roi.x) && in_frame(roi.y) && in_frame(roi.x + roi.width) && in_frame(roi.y + roi.height)) {
+        return Err("Motion region must lie inside the frame (fractions between 0 and 1)".to_string());
+    }
+    if roi.width < 0.05 || roi.height < 0.05 {
+        return Err("Motion region must cover at least 5% of the frame in each direction".to_string());
+    }
+    Ok(())
+}
+
+/// Scene change score (0..1) a frame must exceed; sensitivity 100 reacts to the smallest changes
+pub fn scene_threshold(sensitivity: i32) -> f64 {
+    0.01 + (100 - sensitivity.clamp(1, 100)) as f64 * 0.003
+}
+
+/// Start detection for every camera with motion detection enabled (app startup)
+pub fn start_enabled(app_handle: &AppHandle) -> Result<(), String> {
+    let state = app_handle.state::<AppState>();
+    let conn = crate::db::open(&state.db_path).map_err(|e| e.to_string())?;
+
+    let camera_ids = {
+        let mut stmt = conn.prepare("SELECT camera_id FROM motion_detectors WHERE enabled = 1 AND camera_id IN (SELECT id FROM cameras WHERE archived_at IS NULL)")
+            .map_err(|e| e.to_string())?;
+        let ids = stmt.query_map([], |row| row.get::<_, i32>(0))
+            .map_err(|e| e.to_string())?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())?;
+        ids
+    };
+
+    for camera_id in camera_ids {
+        start(app_handle, camera_id);
+    }
+    Ok(())
+}
+
+async fn detect_camera(app_handle: AppHandle, camera_id: i32) {
+    let state = app_handle.state::<AppState>();
+    println!("[Motion] Detecting motion on camera {}", camera_id);
+
+    loop {
+        let settings = match crate::db::open(&state.db_path).map_err(|e| e.to_string())
+            .and_then(|conn| load_settings(&conn, camera_id))
+        {
+            Ok(settings) => settings,
+            Err(e) => {
+                eprintln!("[Motion] Failed to load settings of camera {}: {}", camera_id, e);
+                break;
+            }
+        };
+        if !settings.enabled {
+            break;
+        }
+
+        if let Err(e) = run_detector(&state, &settings).await {
+            eprintln!("[Motion] Camera {}: {}", camera_id, e);
+            state.pipelines.error(camera_id, &format!("Motion detection: {}", e));
+        }
+
+        tokio::time::sleep(Duration::from_secs(RESTART_DELAY_SECS)).await;
+    }
+
+    println!("[Motion] Motion detection for camera {} finished", camera_id);
+}
+
+// Decode at low fps and resolution, keep only frames whose scene change score exceeds the
+// threshold and print their score; each printed score is a frame with motion
+fn filter_chain(settings: &MotionDetectorSettings) -> String {
+    let mut filters = vec![format!("fps={}", ANALYSIS_FPS)];
+    if let Some(roi) = &settings.roi {
+        filters.push(format!("crop=iw*{}:ih*{}:iw*{}:ih*{}", roi.width, roi.height, roi.x, roi.y));
+    }
+    filters.push(format!("scale={}:-2", ANALYSIS_WIDTH));
+    filters.push(format!("select='gt(scene,{:.3})'", scene_threshold(settings.sensitivity)));
+    filters.push("metadata=print:key=lavfi.scene_score".to_string());
+    filters.join(",")
+}
+
+// Network cameras are read directly. A local device (UVC, Pi CSI) can only be opened once, so
+// those cameras are analysed from their live HLS stream, which must be running.
+async fn input_args(state: &AppState, camera_id: i32) -> Result<Vec<String>, String> {
+    let camera = crate::stream::load_camera(&state.db_path, camera_id)?;
+
+    if crate::stream::is_local_device(&camera.camera_type) {
+        let playlist = state.stream_dir.join(camera_id.to_string()).join("index.m3u8");
+        if !playlist.exists() {
+            return Err("UVC and Pi cameras are analysed from the live stream; start the stream first".to_string());
+        }
+        return Ok(vec![
+            "-live_start_index".to_string(), "-1".to_string(),
+            "-i".to_string(), playlist.to_string_lossy().to_string(),
+        ]);
+    }
+
+    let source = crate::stream::get_rtsp_url(&state.onvif_http, &camera).await?;
+    let mut args = crate::stream::network_input_args(&camera, &source);
+    args.extend(["-i".to_string(), source]);
+    Ok(args)
+}
+
+async fn run_detector(state: &AppState, settings: &MotionDetectorSettings) -> Result<(), String> {
+    let camera_id = settings.cameraId;
+
+    let mut cmd = tokio::process::Command::new("ffmpeg");
+    cmd.args(["-hide_banner", "-nostats"])
+        .args(input_args(state, camera_id).await?)
+        .args(["-an", "-vf", &filter_chain(settings)])
+        .args(state.pipelines.run_marker_args())
+        .args(["-f", "null", "-"])
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+
+    #[cfg(target_os = "windows")]
+    {
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        cmd.creation_flags(CREATE_NO_WINDOW);
+    }
+
+    let mut child = cmd.spawn().map_err(|e| format!("Failed to start ffmpeg: {}", e))?;
+    let _process = state.pipelines.adopt_tokio_process(&child);
+    let stderr = child.stderr.take().ok_or("Failed to capture ffmpeg output")?;
+    let mut lines = BufReader::new(stderr).lines();
+
+    let idle = Duration::from_secs(MOTION_END_SECS);
+    let cooldown = Duration::from_secs(settings.cooldownSecs.max(0) as u64);
+    let mut last_motion: Option<Instant> = None;
+    let mut last_alert: Option<Instant> = None;
+    let mut open_event: Option<i64> = None;
+    let mut last_output = String::new();
+
+    loop {
+        match tokio::time::timeout(idle, lines.next_line()).await {
+            Ok(Ok(Some(line))) => {
+                if let Some((_, score)) = line.split_once("lavfi.scene_score=") {
+                    last_motion = Some(Instant::now());
+                    let cooling_down = last_alert.is_some_and(|at| at.elapsed() < cooldown);
+                    if open_event.is_none() && !cooling_down {
+                        last_alert = Some(Instant::now());
+                        let score = score.trim().parse().unwrap_or(0.0);
+                        open_event = Some(raise_alert(state, settings, score).await?);
+                    }
+                } else if !line.trim().is_empty() {
+                    last_output = line;
+                }
+            }
+            Ok(Ok(None)) => break,
+            Ok(Err(e)) => return Err(e.to_string()),
+            // No changed frame for a while
+            Err(_) => {}
+        }
+
+        if open_event.is_some() && last_motion.is_some_and(|at| at.elapsed() >= idle) {
+            if let Some(event_id) = open_event.take() {
+                end_event(&state.db_path, event_id)?;
+            }
+        }
+    }
+
+    if let Some(event_id) = open_event {
+        end_event(&state.db_path, event_id)?;
+    }
+
+    let status = child.wait().await.map_err(|e| e.to_string())?;
+    Err(format!("FFmpeg exited with {}: {}", status, crate::redact::redact(last_output.trim())))
+}
+
+// A "motion" timeline event, a UI event and optionally a recording with the motion trigger
+async fn raise_alert(state: &AppState, settings: &MotionDetectorSettings, score: f64) -> Result<i64, String> {
+    let camera_id = settings.cameraId;
+    let event_id = {
+        let conn = crate::db::open(&state.db_path).map_err(|e| e.to_string())?;
+        crate::timeline::insert_event(&conn, camera_id, "motion", serde_json::json!({
+            "source": "ffmpeg",
+            "score": score,
+            "sensitivity": settings.sensitivity,
+        }))?
+    };
+
+    println!("[Motion] Motion on camera {} (scene score {:.3})", camera_id, score);
+    state.events.emit(AppEvent::MotionDetected { camera_id, event_id, score });
+
+    let already_recording = state.recording_processes.lock().map_err(|e| e.to_string())?.contains_key(&camera_id);
+    if settings.recordOnMotion && !already_recording {
+        let meta = RecordingMeta::new(RecordingTrigger::Motion);
+        match crate::stream::start_recording_with_options_direct(state, camera_id, None, meta).await {
+            Ok(recording_id) => crate::stream::schedule_auto_stop(state, camera_id, recording_id, settings.recordMinutes),
+            Err(e) => eprintln!("[Motion] Failed to start recording for camera {}: {}", camera_id, e),
+        }
+    }
+
+    Ok(event_id)
+}
+
+fn end_event(db_path: &str, event_id: i64) -> Result<(), String> {
+    let conn = crate::db::open(db_path).map_err(|e| e.to_string())?;
+    crate::timeline::end_event(&conn, event_id)
+}
@@ -0,0 +1,453 @@
+use crate::events::AppEvent;
+use crate::models::{MqttSettings, RecordingMeta, RecordingTrigger};
+use crate::AppState;
+use rumqttc::{AsyncClient, Event, EventLoop, LastWill, MqttOptions, Packet, QoS};
+use rusqlite::Connection;
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::async_runtime::JoinHandle;
+use tauri::{AppHandle, Manager};
+
+// Wait before polling again after the connection to the broker fails
+const RECONNECT_DELAY_SECS: u64 = 5;
+// Home Assistant turns the motion sensor off this long after the last motion message
+const MOTION_OFF_DELAY_SECS: u64 = 30;
+// Outgoing messages buffered while the connection is busy
+const REQUEST_CAPACITY: usize = 64;
+
+pub fn load_settings(conn: &Connection) -> Result<MqttSettings, String> {
+    use crate::settings::{self, MQTT_BASE_TOPIC, MQTT_CLIENT_ID, MQTT_DISCOVERY_PREFIX, MQTT_ENABLED, MQTT_HOST, MQTT_PASSWORD, MQTT_PORT, MQTT_USERNAME};
+
+    Ok(MqttSettings {
+        id: 1,
+        enabled: settings::get_bool(conn, MQTT_ENABLED)?,
+        host: settings::get_opt_string(conn, MQTT_HOST)?,
+        port: settings::get_i32(conn, MQTT_PORT)? as u16,
+        username: settings::get_opt_string(conn, MQTT_USERNAME)?,
+        password: settings::get_opt_string(conn, MQTT_PASSWORD)?,
+        clientId: settings::get_opt_string(conn, MQTT_CLIENT_ID)?.unwrap_or_default(),
+        discoveryPrefix: settings::get_opt_string(conn, MQTT_DISCOVERY_PREFIX)?.unwrap_or_default(),
+        baseTopic: settings::get_opt_string(conn, MQTT_BASE_TOPIC)?.unwrap_or_default(),
+        connected: false,
+    })
+}
+
+// Topic layout: state and commands under `<base>/<camera id>/...`, discovery configs under
+// `<prefix>/<component>/<base>/camera_<id>_<object>/config`
+#[derive(Clone)]
+struct Topics {
+    discovery_prefix: String,
+    base: String,
+}
+
+impl Topics {
+    fn availability(&self) -> String {
+        format!("{}/status", self.base)
+    }
+
+    // Latest activity digest (retained JSON)
+    fn report(&self) -> String {
+        format!("{}/report", self.base)
+    }
+
+    fn camera(&self, camera_id: i32, leaf: &str) -> String {
+        format!("{}/{}/{}", self.base, camera_id, leaf)
+    }
+
+    fn config(&self, component: &str, camera_id: i32, object: &str) -> String {
+        format!("{}/{}/{}/camera_{}_{}/config", self.discovery_prefix, component, self.base, camera_id, object)
+    }
+
+    // (camera id, command) of a subscribed command topic
+    fn parse_command<'a>(&self, topic: &'a str) -> Option<(i32, &'a str)> {
+        let rest = topic.strip_prefix(&self.base)?.strip_prefix('/')?;
+        let (camera_id, command) = rest.split_once('/')?;
+        Some((camera_id.parse().ok()?, command))
+    }
+}
+
+struct Bridge {
+    client: AsyncClient,
+    topics: Topics,
+    connected: Arc<AtomicBool>,
+    task: JoinHandle<()>,
+}
+
+/// Connection to the MQTT broker publishing cameras to Home Assistant, if enabled
+#[derive(Clone, Default)]
+pub struct MqttBridge {
+    bridge: Arc<Mutex<Option<Bridge>>>,
+}
+
+impl MqttBridge {
+    pub fn is_connected(&self) -> bool {
+        self.bridge.lock().is_ok_and(|bridge| {
+            bridge.as_ref().is_some_and(|b| b.connected.load(Ordering::Relaxed))
+        })
+    }
+
+    /// Announce a new camera to Home Assistant
+    pub fn publish_camera(&self, camera_id: i32, name: &str, camera_type: &str) {
+        if let Ok(bridge) = self.bridge.lock() {
+            if let Some(bridge) = bridge.as_ref() {
+                for (topic, config) in discovery_configs(&bridge.topics, camera_id, name, camera_type) {
+                    let _ = bridge.client.try_publish(topic, QoS::AtLeastOnce, true, config);
+                }
+            }
+        }
+    }
+
+    /// Remove a deleted camera's entities (an empty retained config deletes them)
+    pub fn remove_camera(&self, camera_id: i32) {
+        if let Ok(bridge) = self.bridge.lock() {
+            if let Some(bridge) = bridge.as_ref() {
+                for (component, object) in ENTITIES {
+                    let _ = bridge.client.try_publish(
+                        bridge.topics.config(component, camera_id, object), QoS::AtLeastOnce, true, Vec::new()
+                    );
+                }
+            }
+        }
+    }
+
+    fn stop(&self) -> bool {
+        let bridge = self.bridge.lock().ok().and_then(|mut bridge| bridge.take());
+        bridge.map(|bridge| {
+            let _ = bridge.client.try_publish(bridge.topics.availability(), QoS::AtLeastOnce, true, "offline");
+            let _ = bridge.client.try_disconnect();
+            bridge.task.abort();
+        }).is_some()
+    }
+}
+
+// (component, object id) of the entities published per camera
+const ENTITIES: [(&str, &str); 4] = [
+    ("camera", "snapshot"),
+    ("binary_sensor", "motion"),
+    ("switch", "recording"),
+    ("button", "capture"),
+];
+
+/// Connect to the broker with the stored settings, replacing any existing connection
+pub fn start(app_handle: &AppHandle) -> Result<(), String> {
+    let state = app_handle.state::<AppState>();
+    stop(&state);
+
+    let settings = {
+        let conn = crate::db::open(&state.db_path).map_err(|e| e.to_string())?;
+        load_settings(&conn)?
+    };
+    let host = match settings.host.as_deref().map(str::trim) {
+        Some(host) if settings.enabled && !host.is_empty() => host.to_string(),
+        _ => return Ok(()),
+    };
+
+    let topics = Topics {
+        discovery_prefix: settings.discoveryPrefix.trim_end_matches('/').to_string(),
+        base: settings.baseTopic.trim_end_matches('/').to_string(),
+    };
+
+    let mut options = MqttOptions::new(settings.clientId.clone(), host.clone(), settings.port);
+    options.set_keep_alive(Duration::from_secs(30));
+    options.set_max_packet_size(10 * 1024 * 1024, 10 * 1024 * 1024); // snapshots are sent as images
+    options.set_last_will(LastWill::new(topics.availability(), "offline", QoS::AtLeastOnce, true));
+    if let Some(username) = settings.username.as_deref().filter(|u| !u.is_empty()) {
+        options.set_credentials(username, settings.password.clone().unwrap_or_default());
+    }
+
+    let (client, eventloop) = AsyncClient::new(options, REQUEST_CAPACITY);
+    let connected = Arc::new(AtomicBool::new(false));
+    let task = tauri::async_runtime::spawn(run(
+        app_handle.clone(),
+        client.clone(),
+        eventloop,
+        topics.clone(),
+        connected.clone(),
+    ));
+
+    println!("[MQTT] Connecting to {}:{} as {}", host, settings.port, settings.clientId);
+    if let Ok(mut bridge) = state.mqtt.bridge.lock() {
+        *bridge = Some(Bridge { client, topics, connected, task });
+    }
+    Ok(())
+}
+
+pub fn stop(state: &AppState) {
+    if state.mqtt.stop() {
+        println!("[MQTT] Disconnected from broker");
+    }
+}
+
+// Drive the connection: (re)announce everything on connect, forward backend events as
+// state updates and run commands from Home Assistant
+async fn run(
+    app_handle: AppHandle,
+    client: AsyncClient,
+    mut eventloop: EventLoop,
+    topics: Topics,
+    connected: Arc<AtomicBool>,
+) {
+    let state = app_handle.state::<AppState>();
+    let mut events = state.events.subscribe();
+
+    loop {
+        tokio::select! {
+            notification = eventloop.poll() => match notification {
+                Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                    println!("[MQTT] Connected to broker");
+                    connected.store(true, Ordering::Relaxed);
+                    match announcements(&state, &topics) {
+                        Ok(messages) => {
+                            let (client, topics) = (client.clone(), topics.clone());
+                            tauri::async_runtime::spawn(async move {
+                                if let Err(e) = announce(client, topics, messages).await {
+                                    eprintln!("[MQTT] Failed to publish cameras: {}", e);
+                                }
+                            });
+                        }
+                        Err(e) => eprintln!("[MQTT] Failed to load cameras: {}", e),
+                    }
+                }
+                Ok(Event::Incoming(Packet::Publish(message))) => {
+                    if let Some((camera_id, command)) = topics.parse_command(&message.topic) {
+                        let payload = String::from_utf8_lossy(&message.payload).to_string();
+                        tauri::async_runtime::spawn(run_command(app_handle.clone(), camera_id, command.to_string(), payload));
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    if connected.swap(false, Ordering::Relaxed) {
+                        eprintln!("[MQTT] Connection lost: {}", e);
+                    } else {
+                        eprintln!("[MQTT] Failed to connect: {}", e);
+                    }
+                    tokio::time::sleep(Duration::from_secs(RECONNECT_DELAY_SECS)).await;
+                }
+            },
+            event = events.recv() => match event {
+                Ok(text) => {
+                    if connected.load(Ordering::Relaxed) {
+                        forward_event(&state, &client, &topics, &text);
+                    }
+                }
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                    eprintln!("[MQTT] Fell behind, skipped {} events", skipped);
+                }
+                Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+            },
+        }
+    }
+}
+
+fn device(topics: &Topics, camera_id: i32, name: &str, camera_type: &str) -> Value {
+    json!({
+        "identifiers": [format!("{}_camera_{}", topics.base, camera_id)],
+        "name": name,
+        "model": match camera_type {
+            "uvc" => "UVC camera",
+            "rpicam" => "Raspberry Pi camera",
+            _ => "ONVIF camera",
+        },
+    })
+}
+
+// Retained discovery configs of a camera's entities as (topic, JSON)
+fn discovery_configs(topics: &Topics, camera_id: i32, name: &str, camera_type: &str) -> Vec<(String, String)> {
+    let device = device(topics, camera_id, name, camera_type);
+    let unique_id = |object: &str| format!("{}_camera_{}_{}", topics.base, camera_id, object);
+    let availability = topics.availability();
+
+    let configs = [
+        ("camera", "snapshot", json!({
+            "name": null,
+            "unique_id": unique_id("snapshot"),
+            "topic": topics.camera(camera_id, "snapshot"),
+            "availability_topic": availability,
+            "device": device,
+        })),
+        ("binary_sensor", "motion", json!({
+            "name": "Motion",
+            "unique_id": unique_id("motion"),
+            "device_class": "motion",
+            "state_topic": topics.camera(camera_id, "motion"),
+            "off_delay": MOTION_OFF_DELAY_SECS,
+            "availability_topic": availability,
+            "device": device,
+        })),
+        ("switch", "recording", json!({
+            "name": "Recording",
+            "unique_id": unique_id("recording"),
+            "icon": "mdi:record-rec",
+            "state_topic": topics.camera(camera_id, "recording"),
+            "command_topic": topics.camera(camera_id, "recording/set"),
+            "availability_topic": availability,
+            "device": device,
+        })),
+        ("button", "capture", json!({
+            "name": "Capture snapshot",
+            "unique_id": unique_id("capture"),
+            "icon": "mdi:camera",
+            "command_topic": topics.camera(camera_id, "snapshot/capture"),
+            "availability_topic": availability,
+            "device": device,
+        })),
+    ];
+
+    configs.into_iter()
+        .map(|(component, object, config)| (topics.config(component, camera_id, object), config.to_string()))
+        .collect()
+}
+
+// Retained messages describing every camera: discovery configs, recording state and the
+// latest snapshot, as (topic, payload)
+fn announcements(state: &AppState, topics: &Topics) -> Result<Vec<(String, Vec<u8>)>, String> {
+    let conn = crate::db::open(&state.db_path).map_err(|e| e.to_string())?;
+    let cameras = {
+        let mut stmt = conn.prepare("SELECT id, name, type FROM cameras WHERE archived_at IS NULL ORDER BY id").map_err(|e| e.to_string())?;
+        let rows = stmt.query_map([], |row| Ok((row.get::<_, i32>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?)))
+            .map_err(|e| e.to_string())?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())?;
+        rows
+    };
+
+    let recording: Vec<i32> = state.recording_processes.lock()
+        .map_err(|e| e.to_string())?
+        .keys()
+        .copied()
+        .collect();
+
+    let mut messages = Vec::new();
+    for (camera_id, name, camera_type) in cameras {
+        for (topic, config) in discovery_configs(topics, camera_id, &name, &camera_type) {
+            messages.push((topic, config.into_bytes()));
+        }
+        messages.push((topics.camera(camera_id, "recording"), on_off(recording.contains(&camera_id)).into()));
+
+        let latest: Option<String> = conn.query_row(
+            "SELECT filename FROM snapshots WHERE camera_id = ?1 ORDER BY captured_at DESC LIMIT 1",
+            [camera_id],
+            |row| row.get(0),
+        ).ok();
+        if let Some(image) = latest.and_then(|filename| std::fs::read(state.recording_dir.join(filename)).ok()) {
+            messages.push((topics.camera(camera_id, "snapshot"), image));
+        }
+    }
+
+    Ok(messages)
+}
+
+// Subscribe to command topics and publish availability and the announcements. Runs in its
+// own task so the event loop keeps sending while the request queue is full.
+async fn announce(client: AsyncClient, topics: Topics, messages: Vec<(String, Vec<u8>)>) -> Result<(), String> {
+    for command in ["recording/set", "snapshot/capture"] {
+        client.subscribe(format!("{}/+/{}", topics.base, command), QoS::AtLeastOnce)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    client.publish(topics.availability(), QoS::AtLeastOnce, true, "online").await.map_err(|e| e.to_string())?;
+
+    for (topic, payload) in messages {
+        client.publish(topic, QoS::AtLeastOnce, true, payload).await.map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+fn on_off(value: bool) -> &'static str {
+    if value { "ON" } else { "OFF" }
+}
+
+fn publish_recording(client: &AsyncClient, topics: &Topics, camera_id: i32, recording: bool) {
+    let _ = client.try_publish(topics.camera(camera_id, "recording"), QoS::AtLeastOnce, true, on_off(recording));
+}
+
+fn publish_snapshot(state: &AppState, client: &AsyncClient, topics: &Topics, camera_id: i32, filename: &str) {
+    match std::fs::read(state.recording_dir.join(filename)) {
+        Ok(image) => {
+            let _ = client.try_publish(topics.camera(camera_id, "snapshot"), QoS::AtMostOnce, true, image);
+        }
+        Err(e) => eprintln!("[MQTT] Failed to read snapshot {}: {}", filename, e),
+    }
+}
+
+// Map a backend event (as sent to WebSocket clients) to entity state updates
+fn forward_event(state: &AppState, client: &AsyncClient, topics: &Topics, text: &str) {
+    let Ok(message) = serde_json::from_str::<Value>(text) else { return };
+    let payload = &message["payload"];
+    if message["event"] == "report-ready" {
+        let _ = client.try_publish(topics.report(), QoS::AtLeastOnce, true, payload.to_string());
+        return;
+    }
+    let camera_id = payload["cameraId"].as_i64().or_else(|| payload["camera_id"].as_i64());
+    let Some(camera_id) = camera_id.map(|id| id as i32) else { return };
+
+    match message["event"].as_str().unwrap_or("") {
+        "motion-detected" => {
+            let _ = client.try_publish(topics.camera(camera_id, "motion"), QoS::AtLeastOnce, false, "ON");
+        }
+        "recording-started" | "recording-resumed" => publish_recording(client, topics, camera_id, true),
+        "recording-completed" | "recording-failed" | "recording-paused" => publish_recording(client, topics, camera_id, false),
+        "snapshot-captured" => {
+            if let Some(filename) = payload["filename"].as_str() {
+                publish_snapshot(state, client, topics, camera_id, filename);
+            }
+        }
+        _ => {}
+    }
+}
+
+async fn run_command(app_handle: AppHandle, camera_id: i32, command: String, payload: String) {
+    let state = app_handle.state::<AppState>();
+    println!("[MQTT] Command {} ({}) for camera {}", command, payload, camera_id);
+
+    let result = match command.as_str() {
+        "recording/set" if payload.eq_ignore_ascii_case("ON") => start_recording(&state, camera_id).await,
+        "recording/set" => crate::stream::stop_recording_direct(&state, camera_id).await,
+        "snapshot/capture" => {
+            crate::snapshots::capture_snapshot(&state.db_path, &state.onvif_http, &state.recording_dir, camera_id, None)
+                .await
+                .map(|snapshot| state.events.emit(AppEvent::SnapshotCaptured(snapshot)))
+        }
+        _ => Ok(()),
+    };
+
+    if let Err(e) = result {
+        eprintln!("[MQTT] Command {} for camera {} failed: {}", command, camera_id, e);
+        // Put the switch back to the actual state
+        if command == "recording/set" {
+            let recording = state.recording_processes.lock().is_ok_and(|p| p.contains_key(&camera_id));
+            if let Ok(bridge) = state.mqtt.bridge.lock() {
+                if let Some(bridge) = bridge.as_ref() {
+                    publish_recording(&bridge.client, &bridge.topics, camera_id, recording);
+                }
+            }
+        }
+    }
+}
+
+// Same as a manual recording from the app, including its default max duration
+async fn start_recording(state: &AppState, camera_id: i32) -> Result<(), String> {
+    if state.recording_processes.lock().map_err(|e| e.to_string())?.contains_key(&camera_id) {
+        return Ok(());
+    }
+
+    let camera = crate::stream::load_camera(&state.db_path, camera_id)?;
+    if crate::stream::is_local_device(&camera.camera_type) && state.processes.lock().map_err(|e| e.to_string())?.contains_key(&camera_id) {
+        return Err("Local camera is streaming; stop the stream before recording".to_string());
+    }
+
+    let max_duration = {
+        let conn = crate::db::open(&state.db_path).map_err(|e| e.to_string())?;
+        crate::stream::load_default_max_duration(&conn)?
+    };
+
+    let mut meta = RecordingMeta::new(RecordingTrigger::External);
+    meta.notes = Some("Home Assistant".to_string());
+    let recording_id = crate::stream::start_recording_with_options_direct(state, camera_id, None, meta).await?;
+    if let Some(minutes) = max_duration {
+        crate::stream::schedule_auto_stop(state, camera_id, recording_id, minutes);
+    }
+    Ok(())
+}
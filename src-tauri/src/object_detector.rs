@@ -0,0 +1,61 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+/// Object box as fractions of the frame width and height (top-left origin)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BoundingBox {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+/// One object found in a frame
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Detection {
+    pub label: String,      // model class, e.g. "person", "car", "dog"
+    pub confidence: f32,    // 0..1
+    #[serde(rename = "box")]
+    pub bbox: BoundingBox,
+}
+
+/// Backend running object detection on a single JPEG frame.
+/// Implementations live in `detectors`; the analytics subsystem only talks to this trait.
+#[async_trait]
+pub trait ObjectDetector: Send + Sync {
+    /// Backend identifier stored in analytics_settings.backend
+    fn name(&self) -> &'static str;
+
+    /// Detect objects in a JPEG frame; labels use COCO class names
+    async fn detect(&self, jpeg: &[u8]) -> Result<Vec<Detection>, String>;
+}
+
+/// Category used for per-camera filtering and event labels, or None for classes we ignore
+pub fn category(label: &str) -> Option<&'static str> {
+    match label {
+        "person" => Some("person"),
+        "bicycle" | "car" | "motorcycle" | "bus" | "truck" | "train" | "boat" => Some("vehicle"),
+        "bird" | "cat" | "dog" | "horse" | "sheep" | "cow" | "bear" => Some("animal"),
+        _ => None,
+    }
+}
+
+/// Width and height from a JPEG's start-of-frame marker
+pub fn jpeg_dimensions(jpeg: &[u8]) -> Option<(u32, u32)> {
+    let mut pos = 2;
+    while pos + 9 < jpeg.len() {
+        if jpeg[pos] != 0xFF {
+            return None;
+        }
+        let marker = jpeg[pos + 1];
+        let length = u16::from_be_bytes([jpeg[pos + 2], jpeg[pos + 3]]) as usize;
+        // SOF0..SOF15, except DHT (C4), JPG (C8) and DAC (CC)
+        if (0xC0..=0xCF).contains(&marker) && ![0xC4, 0xC8, 0xCC].contains(&marker) {
+            let height = u16::from_be_bytes([jpeg[pos + 5], jpeg[pos + 6]]) as u32;
+            let width = u16::from_be_bytes([jpeg[pos + 7], jpeg[pos + 8]]) as u32;
+            return Some((width, height));
+        }
+        pos += 2 + length;
+    }
+    None
+}
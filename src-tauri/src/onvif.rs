@@ -1,4 +1,4 @@
-use crate::models::{DiscoveredDevice, Camera};
+use crate::models::{DiscoveredDevice, Camera, NetworkConfig, NetworkInterface, OnvifChannel, OnvifUser, ProbedDevice, TlsCertificate};
 use std::net::{IpAddr, SocketAddr};
 use std::time::Duration;
 use tokio::net::UdpSocket;
@@ -10,57 +10,265 @@ use reqwest::Client;
 use sha1::{Sha1, Digest};
 use base64::prelude::*;
 use chrono::{Utc, Datelike, Timelike};
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// WS-Discovery port; other scan ports are probed with an ONVIF HTTP request
+pub const WS_DISCOVERY_PORT: u16 = 3702;
+/// Ports ONVIF device services commonly listen on
+pub const COMMON_HTTP_PORTS: [u16; 5] = [80, 8080, 8000, 8899, 2020];
+
+/// How a discovery scan probes hosts (ONVIF plugin config)
+#[derive(Debug, Clone)]
+pub struct DiscoveryOptions {
+    /// Probes in flight at once
+    pub concurrency: usize,
+    /// Wait for each probe's answer
+    pub probe_timeout: Duration,
+    /// Extra attempts for a probe that got no answer
+    pub retries: u32,
+    /// WS_DISCOVERY_PORT and/or HTTP ports of the device service
+    pub ports: Vec<u16>,
+}
+
+impl Default for DiscoveryOptions {
+    fn default() -> Self {
+        Self {
+            concurrency: 50,
+            probe_timeout: Duration::from_millis(2000),
+            retries: 0,
+            ports: vec![WS_DISCOVERY_PORT],
+        }
+    }
+}
+
+impl DiscoveryOptions {
+    /// Options from the ONVIF plugin config, defaults for what it doesn't set
+    pub fn from_config(config: &serde_json::Value) -> Self {
+        let defaults = Self::default();
+        let ports: Vec<u16> = serde_json::from_value(config["ports"].clone()).unwrap_or_default();
+        Self {
+            concurrency: config["concurrency"].as_u64().map(|n| n.max(1) as usize).unwrap_or(defaults.concurrency),
+            probe_timeout: config["probeTimeoutMs"].as_u64().map(Duration::from_millis).unwrap_or(defaults.probe_timeout),
+            retries: config["retries"].as_u64().map(|n| n as u32).unwrap_or(defaults.retries),
+            ports: if ports.is_empty() { defaults.ports } else { ports },
+        }
+    }
+}
 
-const ONVIF_PORT: u16 = 3702;
-const PROBE_TIMEOUT_MS: u64 = 2000;
-const CONCURRENCY_LIMIT: usize = 50;
+/// Pooled HTTP clients shared by all ONVIF requests (stored in AppState).
+/// Certificate verification is a per-camera choice, so one client is kept for each policy,
+/// plus one per pinned certificate.
+#[derive(Clone)]
+pub struct OnvifHttpClient {
+    verified: Client,
+    insecure: Client,
+    timeout: Duration,
+    // Map<pinned certificate PEM, client trusting only it>
+    pinned: Arc<Mutex<HashMap<String, Client>>>,
+}
+
+impl OnvifHttpClient {
+    pub fn new(timeout: Duration) -> Result<Self, String> {
+        let verified = Client::builder()
+            .timeout(timeout)
+            .build()
+            .map_err(|e| e.to_string())?;
+
+        // Most cameras ship self-signed certificates
+        let insecure = Client::builder()
+            .timeout(timeout)
+            .danger_accept_invalid_certs(true)
+            .build()
+            .map_err(|e| e.to_string())?;
+
+        Ok(Self { verified, insecure, timeout, pinned: Arc::new(Mutex::new(HashMap::new())) })
+    }
+
+    /// Client honoring the camera's TLS settings: its pinned certificate if it has one,
+    /// otherwise its verification toggle
+    pub fn for_camera(&self, camera: &Camera) -> Client {
+        let Some(pem) = camera.tls_cert_pem.as_deref() else {
+            return self.for_tls(camera.tls_verify).clone();
+        };
+        match self.pinned_client(pem) {
+            Ok(client) => client,
+            Err(e) => {
+                // Never fall back to accepting any certificate
+                eprintln!("[ONVIF] Pinned certificate of camera {} is unusable: {}", camera.id, e);
+                self.verified.clone()
+            }
+        }
+    }
+
+    fn pinned_client(&self, pem: &str) -> Result<Client, String> {
+        let mut pinned = self.pinned.lock().map_err(|e| e.to_string())?;
+        if let Some(client) = pinned.get(pem) {
+            return Ok(client.clone());
+        }
+        let client = pin_certificate(Client::builder().timeout(self.timeout), pem)?
+            .build()
+            .map_err(|e| e.to_string())?;
+        pinned.insert(pem.to_string(), client.clone());
+        Ok(client)
+    }
+
+    /// Client for a device that isn't registered yet
+    pub fn for_tls(&self, verify: bool) -> &Client {
+        if verify {
+            &self.verified
+        } else {
+            &self.insecure
+        }
+    }
+}
+
+/// Make a client trust only `pem`. Cameras are mostly reached by IP, which their certificates
+/// rarely name, so the hostname isn't checked: the pinned certificate is the identity.
+pub fn pin_certificate(builder: reqwest::ClientBuilder, pem: &str) -> Result<reqwest::ClientBuilder, String> {
+    let certificate = reqwest::Certificate::from_pem(pem.as_bytes())
+        .map_err(|e| format!("Invalid certificate: {}", e))?;
+    Ok(builder
+        .tls_built_in_root_certs(false)
+        .add_root_certificate(certificate)
+        .danger_accept_invalid_hostnames(true))
+}
+
+/// Apply a camera's TLS settings to a client built for it alone (e.g. long-lived event streams)
+pub fn camera_tls(builder: reqwest::ClientBuilder, camera: &Camera) -> Result<reqwest::ClientBuilder, String> {
+    match camera.tls_cert_pem.as_deref() {
+        Some(pem) => pin_certificate(builder, pem),
+        None => Ok(builder.danger_accept_invalid_certs(!camera.tls_verify)),
+    }
+}
+
+/// Check that `pem` holds a certificate a client can pin
+pub fn check_certificate(pem: &str) -> Result<(), String> {
+    pin_certificate(Client::builder(), pem)?.build().map(|_| ()).map_err(|e| e.to_string())
+}
+
+/// Certificate the HTTPS endpoint at `url` presents, for the user to pin (trust on first use)
+pub async fn fetch_certificate(url: &str) -> Result<TlsCertificate, String> {
+    if !url.starts_with("https://") {
+        return Err("Certificates can only be fetched from https endpoints".to_string());
+    }
+    let client = Client::builder()
+        .timeout(Duration::from_secs(10))
+        .danger_accept_invalid_certs(true)
+        .tls_info(true)
+        .build()
+        .map_err(|e| e.to_string())?;
+    // Any answer will do, the handshake already happened
+    let response = client.get(url).send().await.map_err(|e| e.to_string())?;
+    let der = response.extensions()
+        .get::<reqwest::tls::TlsInfo>()
+        .and_then(|info| info.peer_certificate())
+        .ok_or("The endpoint presented no certificate")?;
+
+    let encoded = BASE64_STANDARD.encode(der);
+    let lines: Vec<&str> = encoded.as_bytes().chunks(64)
+        .map(|chunk| std::str::from_utf8(chunk).unwrap_or_default())
+        .collect();
+    let pem = format!("-----BEGIN CERTIFICATE-----\n{}\n-----END CERTIFICATE-----\n", lines.join("\n"));
+    let sha256 = sha2::Sha256::digest(der).iter()
+        .map(|byte| format!("{:02X}", byte))
+        .collect::<Vec<_>>()
+        .join(":");
+    Ok(TlsCertificate { pem, sha256 })
+}
 
 // --- Discovery (Existing) ---
 
-pub async fn discover_devices() -> Result<Vec<DiscoveredDevice>, String> {
-    let local_ip = local_ip().map_err(|e| format!("Failed to get local IP: {}", e))?;
-    let ipv4 = match local_ip {
-        IpAddr::V4(ip) => ip,
-        _ => return Err("IPv6 not supported for simple subnet scan yet".to_string()),
-    };
+pub async fn discover_devices(http: &OnvifHttpClient, subnets: &[String], options: &DiscoveryOptions) -> Result<Vec<DiscoveredDevice>, String> {
+    discover_devices_incremental(http, &AtomicBool::new(false), subnets, options, |_| {}).await
+}
 
-    let octets = ipv4.octets();
-    let subnet_base = format!("{}.{}.{}", octets[0], octets[1], octets[2]);
-    
-    println!("[Discovery] Scanning subnet: {}.1-254", subnet_base);
+/// Scan the given /24 subnets ("192.168.2" or "192.168.2.0/24"), or the local subnet if none
+/// are given, reporting each device through `on_found` as soon as it answers.
+/// Every host is probed on each of the option's ports.
+/// The scan stops early once `cancel` is set; devices found so far are still returned.
+pub async fn discover_devices_incremental<F>(
+    http: &OnvifHttpClient,
+    cancel: &AtomicBool,
+    subnets: &[String],
+    options: &DiscoveryOptions,
+    on_found: F,
+) -> Result<Vec<DiscoveredDevice>, String>
+where
+    F: Fn(&DiscoveredDevice),
+{
+    let subnet_bases: Vec<String> = if subnets.is_empty() {
+        let local_ip = local_ip().map_err(|e| format!("Failed to get local IP: {}", e))?;
+        let ipv4 = match local_ip {
+            IpAddr::V4(ip) => ip,
+            _ => return Err("IPv6 not supported for simple subnet scan yet".to_string()),
+        };
+
+        let octets = ipv4.octets();
+        vec![format!("{}.{}.{}", octets[0], octets[1], octets[2])]
+    } else {
+        subnets.iter().map(|subnet| subnet.strip_suffix(".0/24").unwrap_or(subnet).to_string()).collect()
+    };
 
     let mut target_ips = Vec::new();
-    for i in 1..=254 {
-        target_ips.push(format!("{}.{}", subnet_base, i));
+    for subnet_base in &subnet_bases {
+        println!("[Discovery] Scanning subnet: {}.1-254", subnet_base);
+        for i in 1..=254 {
+            target_ips.push(format!("{}.{}", subnet_base, i));
+        }
     }
 
-    let tasks = target_ips.into_iter().map(|ip| {
-        let ip_addr = ip.clone();
+    println!(
+        "[Discovery] Probing ports {:?} ({} at a time, {}ms timeout, {} retries)",
+        options.ports, options.concurrency, options.probe_timeout.as_millis(), options.retries
+    );
+    let mut targets = Vec::new();
+    for ip in &target_ips {
+        targets.extend(options.ports.iter().map(|&port| (ip.clone(), port)));
+    }
+
+    let (timeout, retries) = (options.probe_timeout, options.retries);
+    let tasks = targets.into_iter().map(|(ip, port)| {
+        let http = http.clone();
         async move {
-            probe_ip(&ip_addr).await
+            for _ in 0..=retries {
+                let device = if port == WS_DISCOVERY_PORT {
+                    probe_ip(&ip, timeout).await
+                } else {
+                    probe_http(&http, &ip, port, timeout).await
+                };
+                if device.is_some() {
+                    return device;
+                }
+            }
+            None
         }
     });
 
-    let results = stream::iter(tasks)
-        .buffer_unordered(CONCURRENCY_LIMIT)
-        .collect::<Vec<_>>()
-        .await;
+    let mut results = stream::iter(tasks).buffer_unordered(options.concurrency.max(1));
 
     let mut devices = Vec::new();
-    for res in results {
+    while let Some(res) = results.next().await {
+        if cancel.load(Ordering::Relaxed) {
+            // Dropping the stream aborts the probes still in flight
+            println!("[Discovery] Scan cancelled");
+            break;
+        }
         if let Some(device) = res {
             if !devices.iter().any(|d: &DiscoveredDevice| d.address == device.address) {
+                on_found(&device);
                 devices.push(device);
             }
         }
     }
-    
+
     println!("[Discovery] Found {} devices", devices.len());
     Ok(devices)
 }
 
-async fn probe_ip(ip: &str) -> Option<DiscoveredDevice> {
-    let target: SocketAddr = format!("{}:{}", ip, ONVIF_PORT).parse().ok()?;
+async fn probe_ip(ip: &str, timeout: Duration) -> Option<DiscoveredDevice> {
+    let target: SocketAddr = format!("{}:{}", ip, WS_DISCOVERY_PORT).parse().ok()?;
     let socket = UdpSocket::bind("0.0.0.0:0").await.ok()?;
     
     let uuid = Uuid::new_v4();
@@ -87,7 +295,7 @@ async fn probe_ip(ip: &str) -> Option<DiscoveredDevice> {
     }
 
     let mut buf = [0u8; 4096];
-    let res = tokio::time::timeout(Duration::from_millis(PROBE_TIMEOUT_MS), socket.recv_from(&mut buf)).await;
+    let res = tokio::time::timeout(timeout, socket.recv_from(&mut buf)).await;
 
     match res {
         Ok(Ok((len, _src))) => {
@@ -102,6 +310,38 @@ async fn probe_ip(ip: &str) -> Option<DiscoveredDevice> {
     None
 }
 
+// A device service on an HTTP port, for devices that don't answer WS-Discovery. Only the
+// public GetSystemDateAndTime is asked, so nothing is known about the device beyond its address.
+async fn probe_http(http: &OnvifHttpClient, ip: &str, port: u16, timeout: Duration) -> Option<DiscoveredDevice> {
+    let xaddr = device_service_url(ip, port);
+    tokio::time::timeout(timeout, fetch_system_date_time(http.for_tls(false), &xaddr)).await.ok()?.ok()?;
+
+    Some(DiscoveredDevice {
+        address: ip.to_string(),
+        port: port as i32,
+        hostname: "".to_string(),
+        name: "ONVIF Camera".to_string(),
+        manufacturer: "Unknown".to_string(),
+        xaddr: Some(xaddr),
+        mac: None,
+    })
+}
+
+/// First of `ports` (COMMON_HTTP_PORTS when empty) where the host answers as an ONVIF device
+pub async fn find_device_port(http: &OnvifHttpClient, host: &str, ports: &[u16], options: &DiscoveryOptions) -> Result<u16, String> {
+    let ports = if ports.is_empty() { &COMMON_HTTP_PORTS[..] } else { ports };
+    for &port in ports {
+        for _ in 0..=options.retries {
+            let xaddr = device_service_url(host, port);
+            let answered = tokio::time::timeout(options.probe_timeout, fetch_system_date_time(http.for_tls(false), &xaddr)).await;
+            if matches!(answered, Ok(Ok(_))) {
+                return Ok(port);
+            }
+        }
+    }
+    Err(format!("No ONVIF device service found on {} (tried ports {:?})", host, ports))
+}
+
 fn parse_probe_match(xml: &str, ip_addr: String) -> Option<DiscoveredDevice> {
     let doc = Document::parse(xml).ok()?;
     
@@ -111,7 +351,12 @@ fn parse_probe_match(xml: &str, ip_addr: String) -> Option<DiscoveredDevice> {
     
     let xaddrs_node = probe_match.descendants().find(|n| n.tag_name().name().ends_with("XAddrs"))?;
     let xaddrs_text = xaddrs_node.text().unwrap_or("");
-    let xaddr = xaddrs_text.split_whitespace().next().map(|s| s.to_string());
+    // Devices may advertise an https endpoint next to the plain one; prefer it
+    let xaddrs: Vec<&str> = xaddrs_text.split_whitespace().collect();
+    let xaddr = xaddrs.iter()
+        .find(|addr| addr.starts_with("https://"))
+        .or(xaddrs.first())
+        .map(|s| s.to_string());
 
     let scopes_node = probe_match.descendants().find(|n| n.tag_name().name().ends_with("Scopes"))?;
     let scopes_text = scopes_node.text().unwrap_or("");
@@ -119,6 +364,7 @@ fn parse_probe_match(xml: &str, ip_addr: String) -> Option<DiscoveredDevice> {
     let mut name = "Unknown Camera".to_string();
     let mut manufacturer = "Unknown".to_string();
     let mut hardware = "".to_string();
+    let mut mac = None;
 
     for scope in scopes_text.split_whitespace() {
         let decoded_scope = urlencoding::decode(scope).unwrap_or(std::borrow::Cow::Borrowed(scope));
@@ -128,6 +374,8 @@ fn parse_probe_match(xml: &str, ip_addr: String) -> Option<DiscoveredDevice> {
             name = scope_str.split("/name/").last().unwrap_or("").to_string();
         } else if scope_str.contains("/hardware/") {
             hardware = scope_str.split("/hardware/").last().unwrap_or("").to_string();
+        } else if let Some((_, value)) = scope_str.split_once("/MAC/") {
+            mac = crate::camera_plugin::normalize_mac(value);
         }
     }
     
@@ -138,7 +386,7 @@ fn parse_probe_match(xml: &str, ip_addr: String) -> Option<DiscoveredDevice> {
     let mut port = 80;
     if let Some(ref addr) = xaddr {
         if let Ok(url) = url::Url::parse(addr) {
-            if let Some(p) = url.port() {
+            if let Some(p) = url.port_or_known_default() {
                 port = p as i32;
             }
         }
@@ -151,15 +399,107 @@ fn parse_probe_match(xml: &str, ip_addr: String) -> Option<DiscoveredDevice> {
         name,
         manufacturer,
         xaddr,
+        mac,
     })
 }
 
+// --- Manual Device Probe ---
+
+// Device service URL for a host entered by the user (a full URL is used as-is)
+fn device_service_url(host: &str, port: u16) -> String {
+    let host = host.trim();
+    if host.starts_with("http://") || host.starts_with("https://") {
+        return host.to_string();
+    }
+
+    let scheme = if port == 443 { "https" } else { "http" };
+    if host.contains(':') && !host.starts_with('[') {
+        // Bare IPv6 address
+        format!("{}://[{}]:{}/onvif/device_service", scheme, host, port)
+    } else {
+        format!("{}://{}:{}/onvif/device_service", scheme, host, port)
+    }
+}
+
+/// Query a device directly by address, for cameras that WS-Discovery can't reach
+/// (other VLANs, routed networks). Credentials are checked by GetDeviceInformation.
+pub async fn probe_device(http: &OnvifHttpClient, host: &str, port: u16, user: &str, pass: &str) -> Result<ProbedDevice, String> {
+    let xaddr = device_service_url(host, port);
+    let client = http.for_tls(false);
+
+    println!("[Probe] Probing ONVIF device at {}", xaddr);
+
+    let body = r###"<GetDeviceInformation xmlns="http://www.onvif.org/ver10/device/wsdl"/>"###;
+    let xml = soap_request(
+        client, &xaddr, &xaddr,
+        "http://www.onvif.org/ver10/device/wsdl/GetDeviceInformation",
+        user, pass, body,
+    ).await?;
+
+    let doc = Document::parse(&xml).map_err(|e| format!("Invalid GetDeviceInformation response: {}", e))?;
+    let info = doc.descendants()
+        .find(|n| n.tag_name().name() == "GetDeviceInformationResponse")
+        .ok_or("No GetDeviceInformationResponse in response")?;
+    let field = |name: &str| {
+        child(info, name)
+            .and_then(|n| n.text())
+            .map(|t| t.trim().to_string())
+            .unwrap_or_default()
+    };
+
+    let manufacturer = field("Manufacturer");
+    let model = field("Model");
+
+    let services = match fetch_services(client, &xaddr, user, pass).await {
+        Ok(services) if services.is_resolved() => services,
+        _ => fetch_capabilities(client, &xaddr, user, pass).await.unwrap_or_default(),
+    };
+
+    // Best effort: the device is usable without knowing its channels up front
+    let media_xaddr = Some(services.media_url()).filter(|url| !url.is_empty()).unwrap_or(&xaddr);
+    let channels = match fetch_profiles(client, &xaddr, media_xaddr, user, pass).await {
+        Ok(xml) => parse_channels(&xml),
+        Err(e) => {
+            println!("[Probe] Could not list the channels of {}: {}", xaddr, e);
+            Vec::new()
+        }
+    };
+
+    let name = format!("{} {}", manufacturer, model).trim().to_string();
+    let url = url::Url::parse(&xaddr).map_err(|e| e.to_string())?;
+
+    let device = ProbedDevice {
+        name: if name.is_empty() { "ONVIF Camera".to_string() } else { name },
+        host: url.host_str().unwrap_or(host).trim_matches(|c| c == '[' || c == ']').to_string(),
+        port: url.port_or_known_default().unwrap_or(port),
+        camera_type: "onvif".to_string(),
+        user: (!user.is_empty()).then(|| user.to_string()),
+        pass: (!pass.is_empty()).then(|| pass.to_string()),
+        xaddr,
+        manufacturer,
+        model,
+        firmware_version: field("FirmwareVersion"),
+        serial_number: field("SerialNumber"),
+        hardware_id: field("HardwareId"),
+        has_ptz: services.ptz.is_some(),
+        channels,
+    };
+
+    println!(
+        "[Probe] Found {} (firmware {}, {} channel(s)) at {}",
+        device.name, device.firmware_version, device.channels.len(), device.xaddr
+    );
+    Ok(device)
+}
+
 // --- ONVIF Stream URI Retrieval ---
 
-fn generate_security_header(user: &str, pass: &str) -> String {
+fn generate_security_header(user: &str, pass: &str, skew_secs: i64) -> String {
     let nonce_raw: [u8; 16] = rand::random();
     let nonce = BASE64_STANDARD.encode(nonce_raw);
-    let created = Utc::now().format("%Y-%m-%dT%H:%M:%S.000Z").to_string();
+    // Created must be within the camera's accepted window, so express it in the camera's clock
+    let created = (Utc::now() + chrono::Duration::seconds(skew_secs))
+        .format("%Y-%m-%dT%H:%M:%S.000Z").to_string();
 
     let mut hasher = Sha1::new();
     hasher.update(&nonce_raw);
@@ -180,30 +520,430 @@ fn generate_security_header(user: &str, pass: &str) -> String {
     )
 }
 
-pub async fn get_onvif_stream_url(camera: &Camera) -> Result<String, String> {
+// --- Clock Skew Handling ---
+
+// Map<device xaddr, camera clock minus local clock in seconds>
+static CLOCK_SKEW: LazyLock<Mutex<HashMap<String, i64>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+// Re-measured skew must differ by more than this before a retry is worthwhile
+const SKEW_TOLERANCE_SECS: i64 = 5;
+
+fn clock_skew(xaddr: &str) -> i64 {
+    CLOCK_SKEW.lock().ok()
+        .and_then(|skews| skews.get(xaddr).copied())
+        .unwrap_or(0)
+}
+
+/// Forget the measured clock skew for a device (e.g. after its clock was synchronized)
+pub fn reset_clock_skew(xaddr: &str) {
+    if let Ok(mut skews) = CLOCK_SKEW.lock() {
+        skews.remove(xaddr);
+    }
+}
+
+// Faults cameras return when the UsernameToken is rejected. Many cameras report an
+// out-of-window Created timestamp as a plain NotAuthorized, so all of these are retried.
+fn is_auth_time_fault(fault: &SoapFault) -> bool {
+    let reason = fault.reason.to_lowercase();
+    fault.has_code("NotAuthorized")
+        || fault.has_code("FailedAuthentication")
+        || fault.has_code("InvalidSecurity")
+        || fault.has_code("MessageExpired")
+        || reason.contains("not in the correct time range")
+}
+
+/// POST an authenticated SOAP request and return the response body.
+/// If the camera rejects the security header, the clock skew is measured via
+/// GetSystemDateAndTime and the request is retried once with the camera's notion of time.
+pub async fn soap_request(
+    client: &Client,
+    device_xaddr: &str,
+    url: &str,
+    action: &str,
+    user: &str,
+    pass: &str,
+    body: &str,
+) -> Result<String, String> {
+    let operation = action.rsplit('/').next().unwrap_or(action);
+    let skew = clock_skew(device_xaddr);
+
+    let envelope = build_soap_envelope_with_skew(user, pass, body, skew);
+    let (status, text) = post_soap(client, device_xaddr, url, action, envelope).await
+        .map_err(|e| format!("Failed to {}: {}", operation, e))?;
+
+    let auth_fault = parse_soap_fault(&text).is_some_and(|fault| is_auth_time_fault(&fault));
+
+    if auth_fault && !user.is_empty() {
+        let camera_time = fetch_system_date_time(client, device_xaddr).await.ok()
+            .and_then(|dt| dt.to_chrono());
+
+        if let Some(camera_time) = camera_time {
+            let measured = camera_time.signed_duration_since(Utc::now()).num_seconds();
+
+            if (measured - skew).abs() > SKEW_TOLERANCE_SECS {
+                println!("[ONVIF] {} rejected credentials, retrying with camera clock skew of {}s", operation, measured);
+                if let Ok(mut skews) = CLOCK_SKEW.lock() {
+                    skews.insert(device_xaddr.to_string(), measured);
+                }
+
+                let envelope = build_soap_envelope_with_skew(user, pass, body, measured);
+                let (status, text) = post_soap(client, device_xaddr, url, action, envelope).await
+                    .map_err(|e| format!("Failed to {}: {}", operation, e))?;
+                return check_soap_response(action, status, text);
+            }
+        }
+    }
+
+    check_soap_response(action, status, text)
+}
+
+/// POST one SOAP request signed with the given clock skew and return the HTTP status and body
+/// as the camera sent them: no skew retry and no fault mapping (camera diagnostics)
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn soap_exchange(
+    client: &Client,
+    device_xaddr: &str,
+    url: &str,
+    action: &str,
+    user: &str,
+    pass: &str,
+    body: &str,
+    skew_secs: i64,
+) -> Result<(reqwest::StatusCode, String), String> {
+    let envelope = build_soap_envelope_with_skew(user, pass, body, skew_secs);
+    post_soap(client, device_xaddr, url, action, envelope).await.map_err(|e| e.to_string())
+}
+
+// Traced (see onvif_trace) when the device has tracing enabled
+async fn post_soap(
+    client: &Client,
+    device_xaddr: &str,
+    url: &str,
+    action: &str,
+    envelope: String,
+) -> Result<(reqwest::StatusCode, String), reqwest::Error> {
+    let result = send_soap(client, url, action, envelope.clone()).await;
+    match &result {
+        Ok((status, text)) => crate::onvif_trace::record(device_xaddr, url, action, &envelope, Ok((*status, text))),
+        Err(e) => crate::onvif_trace::record(device_xaddr, url, action, &envelope, Err(e)),
+    }
+    result
+}
+
+async fn send_soap(
+    client: &Client,
+    url: &str,
+    action: &str,
+    envelope: String,
+) -> Result<(reqwest::StatusCode, String), reqwest::Error> {
+    let res = client.post(url)
+        .header("Content-Type", format!("application/soap+xml; charset=utf-8; action=\"{}\"", action))
+        .body(envelope)
+        .send()
+        .await?;
+
+    let status = res.status();
+    let text = res.text().await?;
+    Ok((status, text))
+}
+
+// Turn SOAP faults (which some cameras send with HTTP 200) and HTTP errors into readable errors
+fn check_soap_response(action: &str, status: reqwest::StatusCode, text: String) -> Result<String, String> {
+    let operation = action.rsplit('/').next().unwrap_or(action);
+
+    if let Some(fault) = parse_soap_fault(&text) {
+        let error = fault.to_error(action);
+        eprintln!("[ONVIF] {} returned fault {:?}: {}", operation, fault.subcodes, fault.reason);
+        return Err(error.to_string());
+    }
+
+    if !status.is_success() {
+        return Err(format!("{} failed with HTTP status {}", operation, status));
+    }
+
+    Ok(text)
+}
+
+// --- SOAP Fault Handling ---
+
+/// ONVIF errors with user-meaningful messages
+#[derive(Debug, thiserror::Error)]
+pub enum OnvifError {
+    #[error("Wrong ONVIF credentials: the camera rejected the username or password")]
+    NotAuthorized,
+    #[error("The camera does not support PTZ")]
+    PtzNotSupported,
+    #[error("The camera does not support {0}")]
+    ActionNotSupported(String),
+    #[error("The camera has no matching media profile")]
+    NoProfile,
+    #[error("The camera rejected the request parameters for {operation}: {reason}")]
+    InvalidArgs { operation: String, reason: String },
+    #[error("The camera is busy or refused {0}, try again later")]
+    OperationProhibited(String),
+    #[error("{operation} failed: {reason}")]
+    Fault { operation: String, reason: String },
+}
+
+/// A parsed SOAP 1.1/1.2 fault
+#[derive(Debug, Clone)]
+pub struct SoapFault {
+    /// Top-level code without namespace prefix, e.g. "Sender"
+    pub code: String,
+    /// Subcodes without namespace prefix, outermost first, e.g. ["NotAuthorized"]
+    pub subcodes: Vec<String>,
+    pub reason: String,
+}
+
+impl SoapFault {
+    pub fn has_code(&self, name: &str) -> bool {
+        self.code.eq_ignore_ascii_case(name)
+            || self.subcodes.iter().any(|c| c.eq_ignore_ascii_case(name))
+    }
+
+    /// Map the fault to a user-meaningful error for the given SOAP action
+    pub fn to_error(&self, action: &str) -> OnvifError {
+        let operation = action.rsplit('/').next().unwrap_or(action).to_string();
+
+        if self.has_code("NotAuthorized") || self.has_code("FailedAuthentication")
+            || self.has_code("InvalidSecurity") || self.has_code("MessageExpired") {
+            OnvifError::NotAuthorized
+        } else if self.has_code("ActionNotSupported") || self.has_code("NoSuchService")
+            || self.has_code("NoPTZProfile") || self.has_code("NotSupported") {
+            if action.contains("/ptz/") || self.has_code("NoPTZProfile") {
+                OnvifError::PtzNotSupported
+            } else {
+                OnvifError::ActionNotSupported(operation)
+            }
+        } else if self.has_code("NoProfile") {
+            OnvifError::NoProfile
+        } else if self.has_code("InvalidArgVal") || self.has_code("InvalidArgs")
+            || self.has_code("InvalidArgValue") {
+            OnvifError::InvalidArgs { operation, reason: self.reason.clone() }
+        } else if self.has_code("OperationProhibited") {
+            OnvifError::OperationProhibited(operation)
+        } else {
+            let reason = if self.reason.is_empty() { self.code.clone() } else { self.reason.clone() };
+            OnvifError::Fault { operation, reason }
+        }
+    }
+}
+
+fn child<'a, 'input>(node: roxmltree::Node<'a, 'input>, name: &str) -> Option<roxmltree::Node<'a, 'input>> {
+    node.children().find(|n| n.tag_name().name() == name)
+}
+
+fn local_name(qname: &str) -> String {
+    qname.trim().rsplit(':').next().unwrap_or("").to_string()
+}
+
+/// Parse a SOAP fault from a response body, if it contains one
+pub fn parse_soap_fault(xml: &str) -> Option<SoapFault> {
+    let doc = Document::parse(xml).ok()?;
+    let fault = doc.descendants().find(|n| n.tag_name().name() == "Fault")?;
+
+    // SOAP 1.1: <faultcode>/<faultstring>
+    if let Some(faultcode) = child(fault, "faultcode") {
+        return Some(SoapFault {
+            code: local_name(faultcode.text().unwrap_or("")),
+            subcodes: Vec::new(),
+            reason: child(fault, "faultstring")
+                .and_then(|n| n.text())
+                .unwrap_or("")
+                .trim()
+                .to_string(),
+        });
+    }
+
+    // SOAP 1.2: <Code><Value/><Subcode><Value/><Subcode>...</Subcode></Subcode></Code>
+    let code_node = child(fault, "Code");
+    let code = code_node
+        .and_then(|n| child(n, "Value"))
+        .and_then(|n| n.text())
+        .map(local_name)
+        .unwrap_or_default();
+
+    let mut subcodes = Vec::new();
+    let mut current = code_node.and_then(|n| child(n, "Subcode"));
+    while let Some(subcode) = current {
+        if let Some(value) = child(subcode, "Value").and_then(|n| n.text()) {
+            subcodes.push(local_name(value));
+        }
+        current = child(subcode, "Subcode");
+    }
+
+    let reason = child(fault, "Reason")
+        .and_then(|n| child(n, "Text"))
+        .and_then(|n| n.text())
+        .unwrap_or("")
+        .trim()
+        .to_string();
+
+    Some(SoapFault { code, subcodes, reason })
+}
+
+// --- Service Endpoint Resolution ---
+
+/// Service endpoints advertised by an ONVIF device.
+/// Media/PTZ/etc. may live on a different path or port than the device service.
+#[derive(Debug, Clone, Default)]
+pub struct OnvifServices {
+    pub device: String,
+    pub media: Option<String>,
+    pub media2: Option<String>,
+    pub ptz: Option<String>,
+    pub imaging: Option<String>,
+    pub events: Option<String>,
+}
+
+impl OnvifServices {
+    /// Media (ver10) service URL, falling back to the device service
+    pub fn media_url(&self) -> &str {
+        self.media.as_deref().unwrap_or(&self.device)
+    }
+
+    pub(crate) fn is_resolved(&self) -> bool {
+        self.media.is_some() || self.media2.is_some() || self.ptz.is_some()
+            || self.imaging.is_some() || self.events.is_some()
+    }
+}
+
+// Map<device xaddr, resolved services>
+static SERVICE_CACHE: LazyLock<Mutex<HashMap<String, OnvifServices>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Resolve service endpoints for a camera (cached per device xaddr).
+/// Tries GetServices first and falls back to GetCapabilities for older devices.
+pub async fn get_services(http: &OnvifHttpClient, camera: &Camera) -> Result<OnvifServices, String> {
     let xaddr = camera.xaddr.clone().ok_or("No xAddr available for ONVIF camera")?;
+
+    if let Some(cached) = SERVICE_CACHE.lock().map_err(|e| e.to_string())?.get(&xaddr) {
+        return Ok(cached.clone());
+    }
+
     let user = camera.user.clone().unwrap_or_default();
     let pass = camera.pass.clone().unwrap_or_default();
-    
-    let client = Client::builder()
-        .timeout(Duration::from_secs(5))
-        .danger_accept_invalid_certs(true)
-        .build()
-        .map_err(|e| e.to_string())?;
 
-    // 1. GetProfiles to get a ProfileToken
-    let profiles_body = r###"<GetProfiles xmlns="http://www.onvif.org/ver10/media/wsdl"/>"###;
-    let profiles_envelope = build_soap_envelope(&user, &pass, profiles_body);
+    let client = &http.for_camera(camera);
 
-    let profiles_res = client.post(&xaddr)
-        .header("Content-Type", "application/soap+xml; charset=utf-8; action=\"http://www.onvif.org/ver10/media/wsdl/GetProfiles\"")
-        .body(profiles_envelope)
-        .send()
-        .await
-        .map_err(|e| format!("Failed to GetProfiles: {}", e))?;
-    
-    let profiles_xml = profiles_res.text().await.map_err(|e| e.to_string())?;
-    let profile_token = parse_first_profile_token(&profiles_xml).ok_or("Failed to parse ProfileToken")?;
+    let mut services = match fetch_services(client, &xaddr, &user, &pass).await {
+        Ok(services) if services.is_resolved() => services,
+        Ok(_) | Err(_) => {
+            println!("[ONVIF] GetServices unavailable for {}, falling back to GetCapabilities", xaddr);
+            fetch_capabilities(client, &xaddr, &user, &pass).await
+                .unwrap_or_default()
+        }
+    };
+
+    if services.device.is_empty() {
+        services.device = xaddr.clone();
+    }
+
+    // Only cache successful resolutions so a camera that was offline gets retried
+    if services.is_resolved() {
+        println!("[ONVIF] Resolved services for {}: {:?}", xaddr, services);
+        SERVICE_CACHE.lock().map_err(|e| e.to_string())?.insert(xaddr, services.clone());
+    }
+
+    Ok(services)
+}
+
+/// Drop cached service endpoints for a device (e.g. after the camera is edited or removed)
+pub fn invalidate_services(xaddr: &str) {
+    if let Ok(mut cache) = SERVICE_CACHE.lock() {
+        cache.remove(xaddr);
+    }
+    // PTZ contexts of every channel of the device
+    if let Ok(mut cache) = PTZ_CACHE.lock() {
+        let prefix = format!("{}#", xaddr);
+        cache.retain(|key, _| !key.starts_with(&prefix));
+    }
+}
+
+async fn fetch_services(client: &Client, xaddr: &str, user: &str, pass: &str) -> Result<OnvifServices, String> {
+    let body = r###"<GetServices xmlns="http://www.onvif.org/ver10/device/wsdl">
+        <IncludeCapability>false</IncludeCapability>
+    </GetServices>"###;
+
+    let xml = soap_request(client, xaddr, xaddr, "http://www.onvif.org/ver10/device/wsdl/GetServices", user, pass, body).await?;
+    parse_services(&xml)
+}
+
+async fn fetch_capabilities(client: &Client, xaddr: &str, user: &str, pass: &str) -> Result<OnvifServices, String> {
+    let body = r###"<GetCapabilities xmlns="http://www.onvif.org/ver10/device/wsdl">
+        <Category>All</Category>
+    </GetCapabilities>"###;
+
+    let xml = soap_request(client, xaddr, xaddr, "http://www.onvif.org/ver10/device/wsdl/GetCapabilities", user, pass, body).await?;
+    parse_capabilities(&xml)
+}
+
+pub(crate) fn parse_services(xml: &str) -> Result<OnvifServices, String> {
+    let doc = Document::parse(xml).map_err(|e| format!("Invalid GetServices response: {}", e))?;
+    let mut services = OnvifServices::default();
+
+    for service in doc.descendants().filter(|n| n.tag_name().name() == "Service") {
+        let child_text = |name: &str| {
+            service.children()
+                .find(|n| n.tag_name().name() == name)
+                .and_then(|n| n.text())
+                .map(|t| t.trim().to_string())
+        };
+
+        let (Some(namespace), Some(url)) = (child_text("Namespace"), child_text("XAddr")) else {
+            continue;
+        };
+
+        match namespace.as_str() {
+            "http://www.onvif.org/ver10/device/wsdl" => services.device = url,
+            "http://www.onvif.org/ver10/media/wsdl" => services.media = Some(url),
+            "http://www.onvif.org/ver20/media/wsdl" => services.media2 = Some(url),
+            "http://www.onvif.org/ver20/ptz/wsdl" => services.ptz = Some(url),
+            "http://www.onvif.org/ver20/imaging/wsdl" => services.imaging = Some(url),
+            "http://www.onvif.org/ver10/events/wsdl" => services.events = Some(url),
+            _ => {}
+        }
+    }
+
+    Ok(services)
+}
+
+pub(crate) fn parse_capabilities(xml: &str) -> Result<OnvifServices, String> {
+    let doc = Document::parse(xml).map_err(|e| format!("Invalid GetCapabilities response: {}", e))?;
+    let capabilities = doc.descendants()
+        .find(|n| n.tag_name().name() == "Capabilities")
+        .ok_or("No Capabilities in response")?;
+
+    // XAddr of a capability category, e.g. <tt:Media><tt:XAddr>...</tt:XAddr></tt:Media>
+    let xaddr_of = |category: &str| {
+        capabilities.descendants()
+            .find(|n| n.tag_name().name() == category)
+            .and_then(|n| n.children().find(|c| c.tag_name().name() == "XAddr"))
+            .and_then(|n| n.text())
+            .map(|t| t.trim().to_string())
+    };
+
+    Ok(OnvifServices {
+        device: xaddr_of("Device").unwrap_or_default(),
+        media: xaddr_of("Media"),
+        media2: None,
+        ptz: xaddr_of("PTZ"),
+        imaging: xaddr_of("Imaging"),
+        events: xaddr_of("Events"),
+    })
+}
+
+pub async fn get_onvif_stream_url(http: &OnvifHttpClient, camera: &Camera) -> Result<String, String> {
+    let services = get_services(http, camera).await?;
+    let media_xaddr = services.media_url().to_string();
+    let user = camera.user.clone().unwrap_or_default();
+    let pass = camera.pass.clone().unwrap_or_default();
+
+    let client = &http.for_camera(camera);
+
+    // 1. The camera's channel, or GetProfiles for the first ProfileToken
+    let profile_token = camera_profile_token(client, camera, &services, &user, &pass).await?;
     
     // 2. GetStreamUri with the token
     let stream_body = format!(
@@ -218,163 +958,254 @@ pub async fn get_onvif_stream_url(camera: &Camera) -> Result<String, String> {
     </GetStreamUri>"###,
         profile_token
     );
-    let stream_envelope = build_soap_envelope(&user, &pass, &stream_body);
 
-    let stream_res = client.post(&xaddr)
-        .header("Content-Type", "application/soap+xml; charset=utf-8; action=\"http://www.onvif.org/ver10/media/wsdl/GetStreamUri\"")
-        .body(stream_envelope)
-        .send()
-        .await
-        .map_err(|e| format!("Failed to GetStreamUri: {}", e))?;
-
-    let stream_xml = stream_res.text().await.map_err(|e| e.to_string())?;
+    let stream_xml = soap_request(
+        client, &services.device, &media_xaddr,
+        "http://www.onvif.org/ver10/media/wsdl/GetStreamUri",
+        &user, &pass, &stream_body
+    ).await?;
     let rtsp_uri = parse_stream_uri(&stream_xml).ok_or("Failed to parse Stream URI")?;
 
-    // Inject credentials into RTSP URL
-    let final_url = if !user.is_empty() {
-        // Check if URL already has auth? Usually ONVIF returns raw URL.
-        // We assume standard rtsp://host...
-        if let Some(idx) = rtsp_uri.find("://") {
-            let (scheme, rest) = rtsp_uri.split_at(idx + 3);
-             // encode password
-             let encoded_pass = urlencoding::encode(&pass);
-             format!("{}{}:{}@{}", scheme, user, encoded_pass, rest)
-        } else {
-            rtsp_uri
-        }
-    } else {
-        rtsp_uri
+    println!("[ONVIF] Resolved Stream URL: {}", crate::redact::redact(&rtsp_uri));
+    // Some cameras already put (other) credentials in the URI; ours replace them
+    crate::stream_url::with_credentials(&rtsp_uri, &user, &pass)
+}
+
+/// Snapshot of a discovered device that answers without credentials (discovery thumbnails).
+/// Fails as soon as any step asks for authentication.
+pub async fn anonymous_snapshot(http: &OnvifHttpClient, xaddr: &str) -> Result<Vec<u8>, String> {
+    let client = http.for_tls(false);
+
+    let services = match fetch_services(client, xaddr, "", "").await {
+        Ok(services) if services.is_resolved() => services,
+        _ => fetch_capabilities(client, xaddr, "", "").await?,
     };
+    let media_xaddr = services.media_url().to_string();
+    let media_xaddr = if media_xaddr.is_empty() { xaddr.to_string() } else { media_xaddr };
 
-    println!("[ONVIF] Resolved Stream URL: {}", final_url);
-    Ok(final_url)
+    let profile_token = get_profile_token(client, xaddr, &media_xaddr, "", "").await?;
+    let snapshot_body = format!(
+        r###"<GetSnapshotUri xmlns="http://www.onvif.org/ver10/media/wsdl">
+      <ProfileToken>{}</ProfileToken>
+    </GetSnapshotUri>"###,
+        profile_token
+    );
+    let snapshot_xml = soap_request(
+        client, xaddr, &media_xaddr,
+        "http://www.onvif.org/ver10/media/wsdl/GetSnapshotUri",
+        "", "", &snapshot_body
+    ).await?;
+    let snapshot_uri = parse_stream_uri(&snapshot_xml).ok_or("Failed to parse Snapshot URI")?;
+
+    let response = client.get(&snapshot_uri).send().await.map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        return Err(format!("Snapshot request failed with HTTP {}", response.status()));
+    }
+    Ok(response.bytes().await.map_err(|e| e.to_string())?.to_vec())
 }
 
 // --- PTZ Functions ---
 
-pub async fn get_ptz_service_url(camera: &Camera) -> Result<String, String> {
-    let xaddr = camera.xaddr.clone().ok_or("No xAddr available")?;
+/// PTZ service URL and profile token, cached so a PTZ command is a single round trip
+#[derive(Debug, Clone)]
+struct PtzContext {
+    device_xaddr: String,
+    ptz_url: String,
+    profile_token: String,
+}
+
+// Map<device xaddr and profile token, PTZ context>; channels of one device move separately
+static PTZ_CACHE: LazyLock<Mutex<HashMap<String, PtzContext>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Resolve the PTZ service URL. This also warms the PTZ context cache,
+/// so the first movement after a capability check is a single request.
+pub async fn get_ptz_service_url(http: &OnvifHttpClient, camera: &Camera) -> Result<String, String> {
+    get_ptz_context(http, camera).await.map(|(context, _)| context.ptz_url)
+}
+
+async fn get_profile_token(client: &Client, device_xaddr: &str, media_xaddr: &str, user: &str, pass: &str) -> Result<String, String> {
+    let profiles_xml = fetch_profiles(client, device_xaddr, media_xaddr, user, pass).await?;
+    parse_first_profile_token(&profiles_xml).ok_or("Failed to parse ProfileToken".to_string())
+}
+
+// Profile of the camera's channel; cameras added before channels existed use the first one
+async fn camera_profile_token(client: &Client, camera: &Camera, services: &OnvifServices, user: &str, pass: &str) -> Result<String, String> {
+    match camera.profile_token.as_deref().filter(|token| !token.is_empty()) {
+        Some(token) => Ok(token.to_string()),
+        None => get_profile_token(client, &services.device, services.media_url(), user, pass).await,
+    }
+}
+
+async fn fetch_profiles(client: &Client, device_xaddr: &str, media_xaddr: &str, user: &str, pass: &str) -> Result<String, String> {
+    let profiles_body = r###"<GetProfiles xmlns="http://www.onvif.org/ver10/media/wsdl"/>"###;
+
+    soap_request(
+        client, device_xaddr, media_xaddr,
+        "http://www.onvif.org/ver10/media/wsdl/GetProfiles",
+        user, pass, profiles_body
+    ).await
+}
+
+/// Video channels of a device that isn't added yet (see `parse_channels`)
+pub async fn fetch_channels(client: &Client, xaddr: &str, user: &str, pass: &str) -> Result<Vec<OnvifChannel>, String> {
+    let services = match fetch_services(client, xaddr, user, pass).await {
+        Ok(services) if services.is_resolved() => services,
+        _ => fetch_capabilities(client, xaddr, user, pass).await?,
+    };
+    let media_xaddr = Some(services.media_url()).filter(|url| !url.is_empty()).unwrap_or(xaddr);
+    Ok(parse_channels(&fetch_profiles(client, xaddr, media_xaddr, user, pass).await?))
+}
+
+/// Video channels of a camera's device (see `parse_channels`)
+pub async fn get_channels(http: &OnvifHttpClient, camera: &Camera) -> Result<Vec<OnvifChannel>, String> {
+    let services = get_services(http, camera).await?;
     let user = camera.user.clone().unwrap_or_default();
     let pass = camera.pass.clone().unwrap_or_default();
 
-    let client = Client::builder()
-        .timeout(Duration::from_secs(5))
-        .danger_accept_invalid_certs(true)
-        .build()
-        .map_err(|e| e.to_string())?;
-
-    // GetCapabilities
-    let body = r###"<GetCapabilities xmlns="http://www.onvif.org/ver10/device/wsdl">
-        <Category>PTZ</Category>
-    </GetCapabilities>"###;
-    let envelope = build_soap_envelope(&user, &pass, body);
+    let xml = fetch_profiles(&http.for_camera(camera), &services.device, services.media_url(), &user, &pass).await?;
+    Ok(parse_channels(&xml))
+}
 
-    let res = client.post(&xaddr)
-        .header("Content-Type", "application/soap+xml; charset=utf-8; action=\"http://www.onvif.org/ver10/device/wsdl/GetCapabilities\"")
-        .body(envelope)
-        .send()
-        .await
-        .map_err(|e| format!("Failed to GetCapabilities: {}", e))?;
+/// One channel per video source of a GetProfiles response, streamed with the first profile
+/// using it (usually the main stream; later ones are substreams). Profiles without a video
+/// source only count when no profile has one.
+pub(crate) fn parse_channels(xml: &str) -> Vec<OnvifChannel> {
+    let Ok(doc) = Document::parse(xml) else { return Vec::new() };
+
+    let profiles: Vec<OnvifChannel> = doc.descendants()
+        .filter(|n| n.tag_name().name() == "Profiles" && n.attribute("token").is_some())
+        .map(|profile| {
+            let text = |node: Option<roxmltree::Node>| node.and_then(|n| n.text()).map(|t| t.trim().to_string());
+            let resolution = profile.children()
+                .find(|n| n.tag_name().name() == "VideoEncoderConfiguration")
+                .and_then(|encoder| child(encoder, "Resolution"));
+            let dimension = |name: &str| text(resolution.and_then(|r| child(r, name))).and_then(|t| t.parse().ok());
+            OnvifChannel {
+                profile_token: profile.attribute("token").unwrap_or_default().to_string(),
+                profile_name: text(child(profile, "Name")).unwrap_or_default(),
+                source_token: text(
+                    child(profile, "VideoSourceConfiguration").and_then(|config| child(config, "SourceToken")),
+                ),
+                width: dimension("Width"),
+                height: dimension("Height"),
+            }
+        })
+        .collect();
 
-    let xml = res.text().await.map_err(|e| e.to_string())?;
-    
-    // Parse PTZ XAddr
-    let re = Regex::new(r"(?s)<[^:]*:PTZ>.*?<[^:]*:XAddr>(.*?)</[^:]*:XAddr>").map_err(|e| e.to_string())?;
-    if let Some(caps) = re.captures(&xml) {
-        return Ok(caps[1].trim().to_string());
+    if profiles.iter().all(|profile| profile.source_token.is_none()) {
+        return profiles.into_iter().take(1).collect();
     }
+    let mut sources = std::collections::HashSet::new();
+    profiles.into_iter()
+        .filter(|profile| profile.source_token.as_ref().is_some_and(|source| sources.insert(source.clone())))
+        .collect()
+}
 
-    Err("PTZ Service not found in capabilities".to_string())
+// Returns the PTZ context and whether it came from the cache
+fn ptz_cache_key(camera: &Camera) -> Result<String, String> {
+    let xaddr = camera.xaddr.as_deref().ok_or("No xAddr available")?;
+    Ok(format!("{}#{}", xaddr, camera.profile_token.as_deref().unwrap_or_default()))
 }
 
-async fn get_profile_token(client: &Client, xaddr: &str, user: &str, pass: &str) -> Result<String, String> {
-     let profiles_body = r###"<GetProfiles xmlns="http://www.onvif.org/ver10/media/wsdl"/>"###;
-    let profiles_envelope = build_soap_envelope(user, pass, profiles_body);
+async fn get_ptz_context(http: &OnvifHttpClient, camera: &Camera) -> Result<(PtzContext, bool), String> {
+    let key = ptz_cache_key(camera)?;
 
-    let profiles_res = client.post(xaddr)
-        .header("Content-Type", "application/soap+xml; charset=utf-8; action=\"http://www.onvif.org/ver10/media/wsdl/GetProfiles\"")
-        .body(profiles_envelope)
-        .send()
-        .await
-        .map_err(|e| format!("Failed to GetProfiles: {}", e))?;
-    
-    let profiles_xml = profiles_res.text().await.map_err(|e| e.to_string())?;
-    parse_first_profile_token(&profiles_xml).ok_or("Failed to parse ProfileToken".to_string())
+    if let Some(cached) = PTZ_CACHE.lock().map_err(|e| e.to_string())?.get(&key) {
+        return Ok((cached.clone(), true));
+    }
+
+    let user = camera.user.clone().unwrap_or_default();
+    let pass = camera.pass.clone().unwrap_or_default();
+
+    let services = get_services(http, camera).await?;
+    let ptz_url = services.ptz.clone().ok_or("PTZ Service not found in capabilities")?;
+    let profile_token = camera_profile_token(&http.for_camera(camera), camera, &services, &user, &pass).await?;
+
+    let context = PtzContext {
+        device_xaddr: services.device.clone(),
+        ptz_url,
+        profile_token,
+    };
+    PTZ_CACHE.lock().map_err(|e| e.to_string())?.insert(key, context.clone());
+
+    Ok((context, false))
 }
 
-pub async fn continuous_move(camera: &Camera, x: f32, y: f32, zoom: f32) -> Result<(), String> {
-    let ptz_url = get_ptz_service_url(camera).await?;
-    let media_xaddr = camera.xaddr.clone().ok_or("No XAddr")?; // Assume Media Service is at Device XAddr for simplicity (often true or routed)
+fn invalidate_ptz_context(key: &str) {
+    if let Ok(mut cache) = PTZ_CACHE.lock() {
+        cache.remove(key);
+    }
+}
+
+// Send a PTZ request using the cached context. A failure with a cached context
+// invalidates it and retries once with freshly resolved values.
+async fn send_ptz_request<F>(http: &OnvifHttpClient, camera: &Camera, action: &str, build_body: F) -> Result<(), String>
+where
+    F: Fn(&str) -> String,
+{
+    let key = ptz_cache_key(camera)?;
     let user = camera.user.clone().unwrap_or_default();
     let pass = camera.pass.clone().unwrap_or_default();
 
-    let client = Client::builder()
-        .timeout(Duration::from_secs(5))
-        .danger_accept_invalid_certs(true)
-        .build()
-        .map_err(|e| e.to_string())?;
+    let (context, cached) = get_ptz_context(http, camera).await?;
+    let body = build_body(&context.profile_token);
+    let result = soap_request(&http.for_camera(camera), &context.device_xaddr, &context.ptz_url, action, &user, &pass, &body).await;
 
-    let token = get_profile_token(&client, &media_xaddr, &user, &pass).await?;
+    let err = match result {
+        Ok(_) => return Ok(()),
+        Err(e) => e,
+    };
 
-    let body = format!(
-        r###"<ContinuousMove xmlns="http://www.onvif.org/ver20/ptz/wsdl">
+    invalidate_ptz_context(&key);
+    if !cached {
+        return Err(err);
+    }
+
+    println!("[ONVIF] PTZ request failed with cached context, re-resolving: {}", err);
+    let (context, _) = get_ptz_context(http, camera).await?;
+    let body = build_body(&context.profile_token);
+    soap_request(&http.for_camera(camera), &context.device_xaddr, &context.ptz_url, action, &user, &pass, &body).await
+        .map(|_| ())
+        .inspect_err(|_| invalidate_ptz_context(&key))
+}
+
+pub async fn continuous_move(http: &OnvifHttpClient, camera: &Camera, x: f32, y: f32, zoom: f32) -> Result<(), String> {
+    send_ptz_request(http, camera, "http://www.onvif.org/ver20/ptz/wsdl/ContinuousMove", |token| {
+        format!(
+            r###"<ContinuousMove xmlns="http://www.onvif.org/ver20/ptz/wsdl">
       <ProfileToken>{}</ProfileToken>
       <Velocity>
         <PanTilt x="{}" y="{}" space="http://www.onvif.org/ver10/tptz/PanTiltSpaces/VelocityGenericSpace" xmlns="http://www.onvif.org/ver10/schema"/>
         <Zoom x="{}" space="http://www.onvif.org/ver10/tptz/ZoomSpaces/VelocityGenericSpace" xmlns="http://www.onvif.org/ver10/schema"/>
       </Velocity>
     </ContinuousMove>"###,
-        token, x, y, zoom
-    );
-    let envelope = build_soap_envelope(&user, &pass, &body);
-
-    client.post(&ptz_url)
-        .header("Content-Type", "application/soap+xml; charset=utf-8; action=\"http://www.onvif.org/ver20/ptz/wsdl/ContinuousMove\"")
-        .body(envelope)
-        .send()
-        .await
-        .map_err(|e| format!("Failed to ContinuousMove: {}", e))?;
-
-    Ok(())
+            token, x, y, zoom
+        )
+    }).await
 }
 
-pub async fn stop_move(camera: &Camera) -> Result<(), String> {
-    let ptz_url = get_ptz_service_url(camera).await?;
-    let media_xaddr = camera.xaddr.clone().ok_or("No XAddr")?;
-    let user = camera.user.clone().unwrap_or_default();
-    let pass = camera.pass.clone().unwrap_or_default();
-
-    let client = Client::builder()
-        .timeout(Duration::from_secs(5))
-        .danger_accept_invalid_certs(true)
-        .build()
-        .map_err(|e| e.to_string())?;
-
-    let token = get_profile_token(&client, &media_xaddr, &user, &pass).await?;
-
-    let body = format!(
-        r###"<Stop xmlns="http://www.onvif.org/ver20/ptz/wsdl">
+pub async fn stop_move(http: &OnvifHttpClient, camera: &Camera) -> Result<(), String> {
+    send_ptz_request(http, camera, "http://www.onvif.org/ver20/ptz/wsdl/Stop", |token| {
+        format!(
+            r###"<Stop xmlns="http://www.onvif.org/ver20/ptz/wsdl">
       <ProfileToken>{}</ProfileToken>
       <PanTilt>true</PanTilt>
       <Zoom>true</Zoom>
     </Stop>"###,
-        token
-    );
-    let envelope = build_soap_envelope(&user, &pass, &body);
-
-    client.post(&ptz_url)
-        .header("Content-Type", "application/soap+xml; charset=utf-8; action=\"http://www.onvif.org/ver20/ptz/wsdl/Stop\"")
-        .body(envelope)
-        .send()
-        .await
-        .map_err(|e| format!("Failed to Stop move: {}", e))?;
-
-    Ok(())
+            token
+        )
+    }).await
 }
 
 pub fn build_soap_envelope(user: &str, pass: &str, body_content: &str) -> String {
+    build_soap_envelope_with_skew(user, pass, body_content, 0)
+}
+
+/// Build a SOAP envelope whose UsernameToken timestamp is shifted by `skew_secs`
+pub fn build_soap_envelope_with_skew(user: &str, pass: &str, body_content: &str, skew_secs: i64) -> String {
     let security_header = if !user.is_empty() {
-        generate_security_header(user, pass)
+        generate_security_header(user, pass, skew_secs)
     } else {
         "".to_string()
     };
@@ -413,7 +1244,7 @@ use regex::Regex;
 
 
 
-fn parse_first_profile_token(xml: &str) -> Option<String> {
+pub(crate) fn parse_first_profile_token(xml: &str) -> Option<String> {
 
     // Regex to find token="VALUE" inside a tag ending with Profiles
 
@@ -441,7 +1272,7 @@ fn parse_first_profile_token(xml: &str) -> Option<String> {
 
 
 
-fn parse_stream_uri(xml: &str) -> Option<String> {
+pub(crate) fn parse_stream_uri(xml: &str) -> Option<String> {
 
     // Regex to find <*:Uri>VALUE</*:Uri>
 
@@ -461,6 +1292,31 @@ fn parse_stream_uri(xml: &str) -> Option<String> {
 
 // --- Time Synchronization Functions ---
 
+/// How time sync treats the camera's time zone (Camera::time_sync_strategy): "utc" sets the
+/// camera to UTC, "camera" keeps the zone configured on the camera, "host" gives it this app's
+/// time zone (general.timezone). The time sent is UTC in every case; the camera shows it in its zone.
+pub const TIME_SYNC_STRATEGIES: [&str; 3] = ["utc", "camera", "host"];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CameraTimeZone {
+    pub tz: String, // POSIX TZ string, e.g. "CET-1CEST,M3.5.0,M10.5.0/3"
+    pub daylight_savings: bool,
+}
+
+/// Time zone to set along with the time under a time sync strategy (see TIME_SYNC_STRATEGIES);
+/// `current` is what the camera reported, whose zone "camera" keeps. None sets UTC.
+pub fn sync_time_zone(strategy: Option<&str>, current: &ONVIFDateTime, now: chrono::DateTime<Utc>) -> Option<CameraTimeZone> {
+    match strategy.unwrap_or("utc") {
+        "camera" => current.time_zone.clone(),
+        "host" => {
+            let tz = crate::posix_tz::for_zone(crate::settings::timezone(), now);
+            let daylight_savings = crate::posix_tz::has_dst(&tz);
+            Some(CameraTimeZone { tz, daylight_savings })
+        }
+        _ => None,
+    }
+}
+
 #[derive(Debug)]
 pub struct ONVIFDateTime {
     pub year: i32,
@@ -469,6 +1325,8 @@ pub struct ONVIFDateTime {
     pub hour: i32,
     pub minute: i32,
     pub second: i32,
+    // Read from the camera, or set along with the time; None is UTC
+    pub time_zone: Option<CameraTimeZone>,
 }
 
 impl ONVIFDateTime {
@@ -480,6 +1338,7 @@ impl ONVIFDateTime {
             hour: dt.hour() as i32,
             minute: dt.minute() as i32,
             second: dt.second() as i32,
+            time_zone: None,
         }
     }
 
@@ -496,34 +1355,28 @@ impl ONVIFDateTime {
     }
 }
 
-pub async fn get_system_date_time(camera: &Camera) -> Result<ONVIFDateTime, String> {
+pub async fn get_system_date_time(http: &OnvifHttpClient, camera: &Camera) -> Result<ONVIFDateTime, String> {
     let xaddr = camera.xaddr.clone().ok_or("No xAddr available for ONVIF camera")?;
 
-    let client = Client::builder()
-        .timeout(Duration::from_secs(5))
-        .danger_accept_invalid_certs(true)
-        .build()
-        .map_err(|e| e.to_string())?;
+    let client = &http.for_camera(camera);
+
+    fetch_system_date_time(client, &xaddr).await
+}
 
+async fn fetch_system_date_time(client: &Client, xaddr: &str) -> Result<ONVIFDateTime, String> {
     // GetSystemDateAndTime does not require authentication in ONVIF spec
     let body = r###"<GetSystemDateAndTime xmlns="http://www.onvif.org/ver10/device/wsdl"/>"###;
 
     // Use empty credentials for GetSystemDateAndTime (public endpoint)
     let envelope = build_soap_envelope("", "", body);
 
-    let res = client.post(&xaddr)
-        .header("Content-Type", "application/soap+xml; charset=utf-8; action=\"http://www.onvif.org/ver10/device/wsdl/GetSystemDateAndTime\"")
-        .body(envelope)
-        .send()
-        .await
+    let (_, xml) = post_soap(client, xaddr, xaddr, "http://www.onvif.org/ver10/device/wsdl/GetSystemDateAndTime", envelope).await
         .map_err(|e| format!("Failed to GetSystemDateAndTime: {}", e))?;
 
-    let xml = res.text().await.map_err(|e| e.to_string())?;
-
     parse_system_date_time(&xml)
 }
 
-fn parse_system_date_time(xml: &str) -> Result<ONVIFDateTime, String> {
+pub(crate) fn parse_system_date_time(xml: &str) -> Result<ONVIFDateTime, String> {
     // Parse UTC DateTime from response
     // Expected structure: <UTCDateTime><Date><Year>...</Year><Month>...</Month><Day>...</Day></Date><Time>...</Time></UTCDateTime>
 
@@ -571,26 +1424,60 @@ fn parse_system_date_time(xml: &str) -> Result<ONVIFDateTime, String> {
         hour,
         minute,
         second,
+        time_zone: parse_time_zone(xml),
     })
 }
 
-pub async fn set_system_date_time(camera: &Camera, datetime: &ONVIFDateTime) -> Result<(), String> {
+// TimeZone and DaylightSavings of a GetSystemDateAndTime response; None when the camera leaves them out
+fn parse_time_zone(xml: &str) -> Option<CameraTimeZone> {
+    let tz_re = Regex::new(r"<[^:>]*:?TZ(?:\s[^>]*)?>([^<]*)</[^:>]*:?TZ>").ok()?;
+    let dst_re = Regex::new(r"<[^:>]*:?DaylightSavings>\s*(\w+)\s*</").ok()?;
+    let tz = tz_re.captures(xml)?.get(1)?.as_str().trim().to_string();
+    if tz.is_empty() {
+        return None;
+    }
+    let daylight_savings = dst_re.captures(xml)
+        .and_then(|c| c.get(1))
+        .is_some_and(|m| m.as_str() == "true");
+    Some(CameraTimeZone { tz, daylight_savings })
+}
+
+pub async fn set_system_date_time(http: &OnvifHttpClient, camera: &Camera, datetime: &ONVIFDateTime) -> Result<(), String> {
     let xaddr = camera.xaddr.clone().ok_or("No xAddr available for ONVIF camera")?;
     let user = camera.user.clone().unwrap_or_default();
     let pass = camera.pass.clone().unwrap_or_default();
 
-    let client = Client::builder()
-        .timeout(Duration::from_secs(5))
-        .danger_accept_invalid_certs(true)
-        .build()
-        .map_err(|e| e.to_string())?;
+    let client = &http.for_camera(camera);
+    let body = set_system_date_time_body(datetime);
 
-    let body = format!(
+    // Signed with the camera's (possibly skewed) clock, since that is what it validates against
+    let response_text = soap_request(
+        client, &xaddr, &xaddr,
+        "http://www.onvif.org/ver10/device/wsdl/SetSystemDateAndTime",
+        &user, &pass, &body
+    ).await?;
+
+    println!("[ONVIF] SetSystemDateAndTime response body: {}", response_text);
+
+    // The camera clock now matches ours, so the measured skew no longer applies
+    reset_clock_skew(&xaddr);
+
+    println!("[ONVIF] SetSystemDateAndTime succeeded");
+    Ok(())
+}
+
+// Switches the camera to manual time, in the datetime's time zone or UTC
+pub(crate) fn set_system_date_time_body(datetime: &ONVIFDateTime) -> String {
+    let (tz, daylight_savings) = match &datetime.time_zone {
+        Some(zone) => (xml_escape(&zone.tz), zone.daylight_savings),
+        None => ("UTC".to_string(), false),
+    };
+    format!(
         r###"<SetSystemDateAndTime xmlns="http://www.onvif.org/ver10/device/wsdl">
       <DateTimeType>Manual</DateTimeType>
-      <DaylightSavings>false</DaylightSavings>
+      <DaylightSavings>{}</DaylightSavings>
       <TimeZone>
-        <TZ xmlns="http://www.onvif.org/ver10/schema">UTC</TZ>
+        <TZ xmlns="http://www.onvif.org/ver10/schema">{}</TZ>
       </TimeZone>
       <UTCDateTime>
         <Date xmlns="http://www.onvif.org/ver10/schema">
@@ -605,34 +1492,321 @@ pub async fn set_system_date_time(camera: &Camera, datetime: &ONVIFDateTime) ->
         </Time>
       </UTCDateTime>
     </SetSystemDateAndTime>"###,
+        daylight_savings, tz,
         datetime.year, datetime.month, datetime.day,
         datetime.hour, datetime.minute, datetime.second
+    )
+}
+
+// --- User Management Functions ---
+
+const DEVICE_WSDL: &str = "http://www.onvif.org/ver10/device/wsdl";
+const USER_LEVELS: [&str; 3] = ["Administrator", "Operator", "User"];
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+fn validate_user_level(level: &str) -> Result<(), String> {
+    if USER_LEVELS.contains(&level) {
+        Ok(())
+    } else {
+        Err(format!("Invalid user level '{}' (expected one of: {})", level, USER_LEVELS.join(", ")))
+    }
+}
+
+fn build_user_xml(username: &str, password: Option<&str>, user_level: &str) -> String {
+    let password = password
+        .map(|p| format!("<tt:Password>{}</tt:Password>", xml_escape(p)))
+        .unwrap_or_default();
+
+    format!(
+        r###"<User xmlns:tt="http://www.onvif.org/ver10/schema"><tt:Username>{}</tt:Username>{}<tt:UserLevel>{}</tt:UserLevel></User>"###,
+        xml_escape(username),
+        password,
+        user_level
+    )
+}
+
+async fn device_request(http: &OnvifHttpClient, camera: &Camera, operation: &str, body: &str) -> Result<String, String> {
+    let xaddr = camera.xaddr.clone().ok_or("No xAddr available for ONVIF camera")?;
+    let user = camera.user.clone().unwrap_or_default();
+    let pass = camera.pass.clone().unwrap_or_default();
+
+    let services = get_services(http, camera).await?;
+    let action = format!("{}/{}", DEVICE_WSDL, operation);
+
+    soap_request(&http.for_camera(camera), &xaddr, &services.device, &action, &user, &pass, body).await
+}
+
+pub async fn get_users(http: &OnvifHttpClient, camera: &Camera) -> Result<Vec<OnvifUser>, String> {
+    let body = format!(r###"<GetUsers xmlns="{}"/>"###, DEVICE_WSDL);
+    let xml = device_request(http, camera, "GetUsers", &body).await?;
+
+    parse_users(&xml)
+}
+
+fn parse_users(xml: &str) -> Result<Vec<OnvifUser>, String> {
+    let doc = Document::parse(xml).map_err(|e| format!("Invalid GetUsers response: {}", e))?;
+
+    let users = doc
+        .descendants()
+        .filter(|n| n.is_element() && n.tag_name().name() == "User")
+        .filter_map(|n| {
+            let username = child(n, "Username")?.text()?.trim().to_string();
+            let user_level = child(n, "UserLevel")
+                .and_then(|l| l.text())
+                .map(|t| t.trim().to_string())
+                .unwrap_or_default();
+            Some(OnvifUser { username, user_level })
+        })
+        .collect();
+
+    Ok(users)
+}
+
+pub async fn create_user(http: &OnvifHttpClient, camera: &Camera, username: &str, password: &str, user_level: &str) -> Result<(), String> {
+    validate_user_level(user_level)?;
+
+    let body = format!(
+        r###"<CreateUsers xmlns="{}">{}</CreateUsers>"###,
+        DEVICE_WSDL,
+        build_user_xml(username, Some(password), user_level)
     );
+    device_request(http, camera, "CreateUsers", &body).await?;
 
-    let envelope = build_soap_envelope(&user, &pass, &body);
+    println!("[ONVIF] Created user '{}' ({})", username, user_level);
+    Ok(())
+}
 
-    let res = client.post(&xaddr)
-        .header("Content-Type", "application/soap+xml; charset=utf-8; action=\"http://www.onvif.org/ver10/device/wsdl/SetSystemDateAndTime\"")
-        .body(envelope)
-        .send()
-        .await
-        .map_err(|e| format!("Failed to SetSystemDateAndTime: {}", e))?;
+pub async fn set_user(http: &OnvifHttpClient, camera: &Camera, username: &str, password: Option<&str>, user_level: &str) -> Result<(), String> {
+    validate_user_level(user_level)?;
 
-    let status = res.status();
-    let response_text = res.text().await.map_err(|e| e.to_string())?;
+    let body = format!(
+        r###"<SetUser xmlns="{}">{}</SetUser>"###,
+        DEVICE_WSDL,
+        build_user_xml(username, password, user_level)
+    );
+    device_request(http, camera, "SetUser", &body).await?;
 
-    println!("[ONVIF] SetSystemDateAndTime response status: {}", status);
-    println!("[ONVIF] SetSystemDateAndTime response body: {}", response_text);
+    println!("[ONVIF] Updated user '{}' ({})", username, user_level);
+    Ok(())
+}
 
-    if !status.is_success() {
-        return Err(format!("SetSystemDateAndTime failed with status {}: {}", status, response_text));
+pub async fn delete_user(http: &OnvifHttpClient, camera: &Camera, username: &str) -> Result<(), String> {
+    let body = format!(
+        r###"<DeleteUsers xmlns="{}"><Username>{}</Username></DeleteUsers>"###,
+        DEVICE_WSDL,
+        xml_escape(username)
+    );
+    device_request(http, camera, "DeleteUsers", &body).await?;
+
+    println!("[ONVIF] Deleted user '{}'", username);
+    Ok(())
+}
+
+// --- Network Configuration ---
+
+const SCHEMA_NS: &str = "http://www.onvif.org/ver10/schema";
+
+fn child_text(node: roxmltree::Node, name: &str) -> Option<String> {
+    child(node, name).and_then(|n| n.text()).map(|t| t.trim().to_string()).filter(|t| !t.is_empty())
+}
+
+pub async fn get_network_config(http: &OnvifHttpClient, camera: &Camera) -> Result<NetworkConfig, String> {
+    let body = format!(r###"<GetNetworkInterfaces xmlns="{}"/>"###, DEVICE_WSDL);
+    let interfaces = parse_network_interfaces(&device_request(http, camera, "GetNetworkInterfaces", &body).await?)?;
+
+    let body = format!(r###"<GetNetworkDefaultGateway xmlns="{}"/>"###, DEVICE_WSDL);
+    let gateways = parse_gateways(&device_request(http, camera, "GetNetworkDefaultGateway", &body).await?)?;
+
+    let body = format!(r###"<GetDNS xmlns="{}"/>"###, DEVICE_WSDL);
+    let (dns_from_dhcp, dns_servers) = parse_dns(&device_request(http, camera, "GetDNS", &body).await?)?;
+
+    Ok(NetworkConfig { interfaces, gateways, dns_from_dhcp, dns_servers })
+}
+
+fn parse_network_interfaces(xml: &str) -> Result<Vec<NetworkInterface>, String> {
+    let doc = Document::parse(xml).map_err(|e| format!("Invalid GetNetworkInterfaces response: {}", e))?;
+
+    let interfaces = doc
+        .descendants()
+        .filter(|n| n.is_element() && n.tag_name().name() == "NetworkInterfaces")
+        .filter_map(|n| {
+            let token = n.attribute("token")?.to_string();
+            let info = child(n, "Info");
+            let config = child(n, "IPv4").and_then(|ipv4| child(ipv4, "Config"));
+            let dhcp = config.and_then(|c| child_text(c, "DHCP")).is_some_and(|v| v == "true");
+            let address = config.and_then(|c| child(c, if dhcp { "FromDHCP" } else { "Manual" }));
+
+            Some(NetworkInterface {
+                token,
+                name: info.and_then(|i| child_text(i, "Name")),
+                hw_address: info.and_then(|i| child_text(i, "HwAddress")),
+                enabled: child_text(n, "Enabled").is_none_or(|v| v == "true"),
+                dhcp,
+                ipv4_address: address.and_then(|a| child_text(a, "Address")),
+                prefix_length: address.and_then(|a| child_text(a, "PrefixLength")).and_then(|p| p.parse().ok()),
+            })
+        })
+        .collect();
+
+    Ok(interfaces)
+}
+
+fn parse_gateways(xml: &str) -> Result<Vec<String>, String> {
+    let doc = Document::parse(xml).map_err(|e| format!("Invalid GetNetworkDefaultGateway response: {}", e))?;
+
+    Ok(doc
+        .descendants()
+        .filter(|n| n.is_element() && n.tag_name().name() == "IPv4Address")
+        .filter_map(|n| n.text().map(|t| t.trim().to_string()))
+        .filter(|t| !t.is_empty())
+        .collect())
+}
+
+// (FromDHCP, servers in use): DNSFromDHCP entries when FromDHCP is set, else DNSManual
+fn parse_dns(xml: &str) -> Result<(bool, Vec<String>), String> {
+    let doc = Document::parse(xml).map_err(|e| format!("Invalid GetDNS response: {}", e))?;
+    let info = doc.descendants()
+        .find(|n| n.is_element() && n.tag_name().name() == "DNSInformation")
+        .ok_or("No DNSInformation in response")?;
+
+    let from_dhcp = child_text(info, "FromDHCP").is_some_and(|v| v == "true");
+    let source = if from_dhcp { "DNSFromDHCP" } else { "DNSManual" };
+    let servers = info
+        .children()
+        .filter(|n| n.is_element() && n.tag_name().name() == source)
+        .filter_map(|n| child_text(n, "IPv4Address").or_else(|| child_text(n, "IPv6Address")))
+        .collect();
+
+    Ok((from_dhcp, servers))
+}
+
+/// Switch an interface between DHCP and a static IPv4 address.
+/// Returns whether the camera must reboot for the change to take effect.
+pub async fn set_network_interface(
+    http: &OnvifHttpClient,
+    camera: &Camera,
+    token: &str,
+    dhcp: bool,
+    address: Option<(&str, u8)>,
+) -> Result<bool, String> {
+    let manual = address
+        .map(|(address, prefix_length)| format!(
+            "<Manual><Address>{}</Address><PrefixLength>{}</PrefixLength></Manual>",
+            xml_escape(address), prefix_length
+        ))
+        .unwrap_or_default();
+
+    let body = format!(
+        r###"<SetNetworkInterfaces xmlns="{}"><InterfaceToken>{}</InterfaceToken><NetworkInterface><Enabled xmlns="{}">true</Enabled><IPv4 xmlns="{}"><Enabled>true</Enabled>{}<DHCP>{}</DHCP></IPv4></NetworkInterface></SetNetworkInterfaces>"###,
+        DEVICE_WSDL, xml_escape(token), SCHEMA_NS, SCHEMA_NS, manual, dhcp
+    );
+    let xml = device_request(http, camera, "SetNetworkInterfaces", &body).await?;
+
+    let doc = Document::parse(&xml).map_err(|e| format!("Invalid SetNetworkInterfaces response: {}", e))?;
+    let reboot_needed = doc.descendants()
+        .find(|n| n.is_element() && n.tag_name().name() == "RebootNeeded")
+        .and_then(|n| n.text())
+        .is_some_and(|t| t.trim() == "true");
+
+    println!("[ONVIF] Set interface {} to {} (reboot needed: {})",
+             token, address.map(|(a, p)| format!("{}/{}", a, p)).unwrap_or("DHCP".to_string()), reboot_needed);
+    Ok(reboot_needed)
+}
+
+pub async fn set_default_gateway(http: &OnvifHttpClient, camera: &Camera, gateway: &str) -> Result<(), String> {
+    let body = format!(
+        r###"<SetNetworkDefaultGateway xmlns="{}"><IPv4Address>{}</IPv4Address></SetNetworkDefaultGateway>"###,
+        DEVICE_WSDL, xml_escape(gateway)
+    );
+    device_request(http, camera, "SetNetworkDefaultGateway", &body).await?;
+
+    println!("[ONVIF] Set default gateway to {}", gateway);
+    Ok(())
+}
+
+pub async fn set_dns(http: &OnvifHttpClient, camera: &Camera, from_dhcp: bool, servers: &[String]) -> Result<(), String> {
+    let manual: String = servers.iter()
+        .map(|server| format!(
+            r###"<DNSManual><Type xmlns="{0}">IPv4</Type><IPv4Address xmlns="{0}">{1}</IPv4Address></DNSManual>"###,
+            SCHEMA_NS, xml_escape(server)
+        ))
+        .collect();
+
+    let body = format!(r###"<SetDNS xmlns="{}"><FromDHCP>{}</FromDHCP>{}</SetDNS>"###, DEVICE_WSDL, from_dhcp, manual);
+    device_request(http, camera, "SetDNS", &body).await?;
+
+    println!("[ONVIF] Set DNS to {}", if from_dhcp { "DHCP".to_string() } else { servers.join(", ") });
+    Ok(())
+}
+
+pub async fn system_reboot(http: &OnvifHttpClient, camera: &Camera) -> Result<(), String> {
+    let body = format!(r###"<SystemReboot xmlns="{}"/>"###, DEVICE_WSDL);
+    device_request(http, camera, "SystemReboot", &body).await?;
+
+    println!("[ONVIF] Reboot requested");
+    Ok(())
+}
+
+/// Device service URL with its host replaced, for a camera that moved to a new address
+pub fn move_xaddr(xaddr: &str, host: &str) -> Result<String, String> {
+    let mut url = url::Url::parse(xaddr).map_err(|e| format!("Invalid xAddr '{}': {}", xaddr, e))?;
+    url.set_host(Some(host)).map_err(|e| format!("Invalid host '{}': {}", host, e))?;
+    Ok(url.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(token: &str, name: &str, source: Option<&str>, width: i32) -> String {
+        let source = source
+            .map(|source| format!("<tt:VideoSourceConfiguration token=\"vsc\"><tt:SourceToken>{}</tt:SourceToken></tt:VideoSourceConfiguration>", source))
+            .unwrap_or_default();
+        format!(
+            "<trt:Profiles token=\"{}\" fixed=\"true\"><tt:Name>{}</tt:Name>{}\
+             <tt:VideoEncoderConfiguration token=\"vec\"><tt:Resolution><tt:Width>{}</tt:Width><tt:Height>{}</tt:Height></tt:Resolution></tt:VideoEncoderConfiguration>\
+             </trt:Profiles>",
+            token, name, source, width, width * 9 / 16,
+        )
     }
 
-    // Check for SOAP fault
-    if response_text.contains("Fault") || response_text.contains("fault") {
-        return Err(format!("SOAP Fault returned: {}", response_text));
+    fn response(profiles: &[String]) -> String {
+        format!(
+            "<s:Envelope xmlns:s=\"http://www.w3.org/2003/05/soap-envelope\" xmlns:trt=\"http://www.onvif.org/ver10/media/wsdl\" \
+             xmlns:tt=\"http://www.onvif.org/ver10/schema\"><s:Body><trt:GetProfilesResponse>{}</trt:GetProfilesResponse></s:Body></s:Envelope>",
+            profiles.concat(),
+        )
     }
 
-    println!("[ONVIF] SetSystemDateAndTime succeeded");
-    Ok(())
+    #[test]
+    fn one_channel_per_video_source() {
+        let xml = response(&[
+            profile("ch1_main", "Channel1 Main", Some("VideoSource_1"), 1920),
+            profile("ch1_sub", "Channel1 Sub", Some("VideoSource_1"), 640),
+            profile("ch2_main", "Channel2 Main", Some("VideoSource_2"), 1280),
+        ]);
+        let channels = parse_channels(&xml);
+        let tokens: Vec<&str> = channels.iter().map(|c| c.profile_token.as_str()).collect();
+        assert_eq!(tokens, ["ch1_main", "ch2_main"]);
+        assert_eq!(channels[1].profile_name, "Channel2 Main");
+        assert_eq!(channels[1].source_token.as_deref(), Some("VideoSource_2"));
+        assert_eq!((channels[1].width, channels[1].height), (Some(1280), Some(720)));
+    }
+
+    #[test]
+    fn profiles_without_video_sources_are_one_channel() {
+        let xml = response(&[profile("main", "MainStream", None, 1920), profile("sub", "SubStream", None, 640)]);
+        let channels = parse_channels(&xml);
+        assert_eq!(channels.len(), 1);
+        assert_eq!(channels[0].profile_token, "main");
+        assert!(parse_channels("not xml").is_empty());
+    }
 }
@@ -0,0 +1,119 @@
+use crate::redact::redact_soap;
+use chrono::Utc;
+use rusqlite::{Connection, OptionalExtension};
+use std::collections::HashMap;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex};
+
+// Once a trace file grows past this it is moved to "<name>.1", replacing the previous one
+const MAX_TRACE_BYTES: u64 = 5 * 1024 * 1024;
+
+// Map<device xaddr, trace file> of the cameras being traced
+static TRACED: LazyLock<Mutex<HashMap<String, PathBuf>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+fn trace_path(db_path: &str, camera_id: i32) -> PathBuf {
+    Path::new(db_path)
+        .parent()
+        .unwrap_or(Path::new("."))
+        .join("onvif-traces")
+        .join(format!("camera_{}.log", camera_id))
+}
+
+fn rotated_path(path: &Path) -> PathBuf {
+    path.with_extension("log.1")
+}
+
+/// Resume tracing the cameras it was enabled for; runs once at startup
+pub fn load(conn: &Connection, db_path: &str) -> Result<(), String> {
+    let mut stmt = conn.prepare(
+        "SELECT c.id, c.xaddr FROM onvif_trace_cameras t JOIN cameras c ON c.id = t.camera_id
+         WHERE c.xaddr IS NOT NULL",
+    ).map_err(|e| e.to_string())?;
+    let rows = stmt.query_map([], |row| Ok((row.get::<_, i32>(0)?, row.get::<_, String>(1)?)))
+        .map_err(|e| e.to_string())?;
+
+    let mut traced = TRACED.lock().map_err(|e| e.to_string())?;
+    for row in rows {
+        let (camera_id, xaddr) = row.map_err(|e| e.to_string())?;
+        println!("[ONVIF] Tracing SOAP requests of camera {}", camera_id);
+        traced.insert(xaddr, trace_path(db_path, camera_id));
+    }
+    Ok(())
+}
+
+/// Turn tracing of a camera's SOAP requests on or off
+pub fn set_enabled(conn: &Connection, db_path: &str, camera_id: i32, enabled: bool) -> Result<(), String> {
+    let xaddr: Option<String> = conn.query_row("SELECT xaddr FROM cameras WHERE id = ?1", [camera_id], |row| row.get(0))
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or("Camera not found")?;
+    let xaddr = xaddr.ok_or("The camera has no ONVIF device address")?;
+
+    let mut traced = TRACED.lock().map_err(|e| e.to_string())?;
+    if enabled {
+        conn.execute(
+            "INSERT OR REPLACE INTO onvif_trace_cameras (camera_id, enabled_at) VALUES (?1, ?2)",
+            rusqlite::params![camera_id, Utc::now().to_rfc3339()],
+        ).map_err(|e| e.to_string())?;
+        traced.insert(xaddr, trace_path(db_path, camera_id));
+    } else {
+        conn.execute("DELETE FROM onvif_trace_cameras WHERE camera_id = ?1", [camera_id])
+            .map_err(|e| e.to_string())?;
+        traced.remove(&xaddr);
+    }
+
+    println!("[ONVIF] SOAP tracing of camera {} {}", camera_id, if enabled { "enabled" } else { "disabled" });
+    Ok(())
+}
+
+pub fn is_enabled(conn: &Connection, camera_id: i32) -> Result<bool, String> {
+    conn.query_row("SELECT 1 FROM onvif_trace_cameras WHERE camera_id = ?1", [camera_id], |_| Ok(()))
+        .optional()
+        .map(|row| row.is_some())
+        .map_err(|e| e.to_string())
+}
+
+/// The camera's trace, the rotated part first; empty when nothing was traced
+pub fn read(db_path: &str, camera_id: i32) -> Result<String, String> {
+    let path = trace_path(db_path, camera_id);
+    let mut trace = std::fs::read_to_string(rotated_path(&path)).unwrap_or_default();
+    match std::fs::read_to_string(&path) {
+        Ok(text) => trace.push_str(&text),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => return Err(format!("Failed to read ONVIF trace: {}", e)),
+    }
+    Ok(trace)
+}
+
+/// Append one SOAP exchange to the device's trace if it is traced. `response` is the HTTP
+/// status and body, or the error when no response arrived. Credentials are masked.
+pub fn record(device_xaddr: &str, url: &str, action: &str, request: &str, response: Result<(reqwest::StatusCode, &str), &reqwest::Error>) {
+    let Some(path) = TRACED.lock().ok().and_then(|traced| traced.get(device_xaddr).cloned()) else { return };
+
+    let operation = action.rsplit('/').next().unwrap_or(action);
+    let response = match response {
+        Ok((status, body)) => format!("--- response (HTTP {})\n{}", status, redact_soap(body)),
+        Err(e) => format!("--- no response: {}", redact_soap(&e.to_string())),
+    };
+    let entry = format!(
+        "==== {} {} -> {}\n--- request\n{}\n{}\n\n",
+        Utc::now().to_rfc3339(), operation, redact_soap(url), redact_soap(request), response,
+    );
+
+    if let Err(e) = append(&path, &entry) {
+        eprintln!("[ONVIF] Failed to write trace {}: {}", path.display(), e);
+    }
+}
+
+fn append(path: &Path, entry: &str) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    if std::fs::metadata(path).is_ok_and(|metadata| metadata.len() > MAX_TRACE_BYTES) {
+        std::fs::rename(path, rotated_path(path))?;
+    }
+    OpenOptions::new().create(true).append(true).open(path)?.write_all(entry.as_bytes())
+}
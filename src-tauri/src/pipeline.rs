@@ -0,0 +1,548 @@
+use crate::events::{AppEvent, EventBus};
+use crate::models::PipelineStatus;
+use crate::process_jobs::ProcessJobs;
+use crate::process_registry::ProcessRegistry;
+use crate::redact::StderrTail;
+use crate::AppState;
+use chrono::{DateTime, Utc};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::process::Child;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+
+const RECORDING_PROGRESS_INTERVAL_SECS: u64 = 3;
+// A recording FFmpeg exiting this soon after its start captured nothing worth keeping (wrong
+// credentials or path, unsupported stream) and the recording is marked failed
+const RECORDING_STARTUP_SECS: u64 = 15;
+// Lines of the recording FFmpeg's stderr kept with a failure
+const FAILURE_STDERR_LINES: usize = 5;
+
+struct StreamInfo {
+    started_at: DateTime<Utc>,
+    file: &'static str, // in the stream directory, e.g. "index.m3u8"
+    encoder: String,
+    is_gpu: bool,
+}
+
+#[derive(Default)]
+struct CameraPipeline {
+    stream: Option<StreamInfo>,
+    stream_starts: u32,
+    // (recording id, time) a max-duration timer will stop the recording
+    recording_stop: Option<(i32, DateTime<Utc>)>,
+    last_error: Option<(String, DateTime<Utc>)>,
+    // Where the stream's segments are written when not next to the playlist (e.g. tmpfs)
+    segment_dir: Option<PathBuf>,
+    // Encoder of the running stream FFmpeg, known from spawn on (unlike `stream`)
+    live_encoder: Option<LiveEncoder>,
+    // Why the GPU encoder was given up for this camera; streams use the CPU encoder until the
+    // encoder settings change
+    cpu_fallback: Option<String>,
+    // The recording FFmpeg encodes on the GPU
+    recording_gpu: bool,
+    // When the running recording FFmpeg was spawned, and its stderr
+    recording_process: Option<(Instant, StderrTail)>,
+    // Load budget downgrade of the live stream, applied when it (re)starts
+    stream_downgrade: u8,
+    // Whether the camera was last seen delivering video; None until its first stream or
+    // recording started or failed
+    reachable: Option<bool>,
+}
+
+/// Encoder of a live stream FFmpeg and its stderr, for telling a broken GPU encoder from other
+/// exits (see stream_watchdog)
+#[derive(Clone)]
+pub struct LiveEncoder {
+    pub codec: String,
+    pub is_gpu: bool,
+    // false in GpuOnly mode, which never falls back
+    pub can_fall_back: bool,
+    pub stderr: StderrTail,
+    // GPU encoder sessions the FFmpeg holds: the source rendition and any lower ones on the GPU
+    pub gpu_sessions: usize,
+}
+
+/// A process taken over with `PipelineTracker::adopt_tokio_process`; dropping it unregisters
+/// the process and closes its job
+pub struct TrackedProcess {
+    pid: Option<u32>,
+    jobs: ProcessJobs,
+    registry: ProcessRegistry,
+}
+
+impl Drop for TrackedProcess {
+    fn drop(&mut self) {
+        if let Some(pid) = self.pid {
+            self.registry.remove(pid);
+            self.jobs.release(pid);
+        }
+    }
+}
+
+/// Per-camera details the process maps don't hold: start times, encoder and the last error.
+/// Also owns the job objects that tie each pipeline's FFmpeg process tree together on Windows
+/// and the registry of spawned PIDs used to clean up after a crash.
+#[derive(Clone, Default)]
+pub struct PipelineTracker {
+    cameras: Arc<Mutex<HashMap<i32, CameraPipeline>>>,
+    jobs: ProcessJobs,
+    registry: ProcessRegistry,
+}
+
+impl PipelineTracker {
+    pub fn new(registry: ProcessRegistry) -> Self {
+        PipelineTracker { registry, ..Default::default() }
+    }
+
+    /// FFmpeg output options marking a pipeline process as spawned by this run
+    pub fn run_marker_args(&self) -> Vec<String> {
+        self.registry.marker_args()
+    }
+
+    fn update<F: FnOnce(&mut CameraPipeline)>(&self, camera_id: i32, f: F) {
+        if let Ok(mut cameras) = self.cameras.lock() {
+            f(cameras.entry(camera_id).or_default());
+        }
+    }
+
+    /// Take charge of a spawned stream, recording or playback FFmpeg
+    pub fn adopt_process(&self, child: &Child) {
+        self.jobs.adopt(child);
+        self.registry.add(child.id());
+    }
+
+    /// Kill a pipeline process along with any helpers it started
+    pub fn kill_process(&self, child: &mut Child) -> std::io::Result<()> {
+        self.registry.remove(child.id());
+        self.jobs.kill(child)
+    }
+
+    /// Take charge of an FFmpeg spawned through tokio with `kill_on_drop` (motion, audio monitor,
+    /// object detection, pre-recording buffer). Keep the returned guard next to the child: the
+    /// process stays registered until the guard is dropped.
+    pub fn adopt_tokio_process(&self, child: &tokio::process::Child) -> TrackedProcess {
+        self.jobs.adopt_tokio(child);
+        let pid = child.id();
+        if let Some(pid) = pid {
+            self.registry.add(pid);
+        }
+        TrackedProcess { pid, jobs: self.jobs.clone(), registry: self.registry.clone() }
+    }
+
+    pub fn stream_started(&self, camera_id: i32, file: &'static str, encoder: &str, is_gpu: bool) {
+        self.update(camera_id, |pipeline| {
+            pipeline.stream = Some(StreamInfo {
+                started_at: Utc::now(),
+                file,
+                encoder: encoder.to_string(),
+                is_gpu,
+            });
+            pipeline.stream_starts += 1;
+        });
+    }
+
+    pub fn set_segment_dir(&self, camera_id: i32, dir: Option<PathBuf>) {
+        self.update(camera_id, |pipeline| pipeline.segment_dir = dir);
+    }
+
+    /// Segment directory of the camera's last stream if it is outside the stream directory
+    pub fn segment_dir(&self, camera_id: i32) -> Option<PathBuf> {
+        let cameras = self.cameras.lock().ok()?;
+        cameras.get(&camera_id)?.segment_dir.clone()
+    }
+
+    pub fn stream_stopped(&self, camera_id: i32) {
+        self.update(camera_id, |pipeline| pipeline.stream = None);
+    }
+
+    pub fn set_live_encoder(&self, camera_id: i32, encoder: LiveEncoder) {
+        self.update(camera_id, |pipeline| pipeline.live_encoder = Some(encoder));
+    }
+
+    pub fn live_encoder(&self, camera_id: i32) -> Option<LiveEncoder> {
+        let cameras = self.cameras.lock().ok()?;
+        cameras.get(&camera_id)?.live_encoder.clone()
+    }
+
+    pub fn recording_spawned(&self, camera_id: i32, stderr: StderrTail) {
+        self.update(camera_id, |pipeline| pipeline.recording_process = Some((Instant::now(), stderr)));
+    }
+
+    pub fn set_recording_gpu(&self, camera_id: i32, is_gpu: bool) {
+        self.update(camera_id, |pipeline| pipeline.recording_gpu = is_gpu);
+    }
+
+    /// GPU encoder sessions held by the given running streams and recordings
+    pub fn gpu_sessions(&self, streaming: &[i32], recording: &[i32]) -> usize {
+        let Ok(cameras) = self.cameras.lock() else { return 0 };
+        let live: usize = streaming.iter()
+            .filter_map(|id| cameras.get(id)?.live_encoder.as_ref())
+            .map(|encoder| encoder.gpu_sessions)
+            .sum();
+        let recordings = recording.iter()
+            .filter(|id| cameras.get(id).is_some_and(|pipeline| pipeline.recording_gpu))
+            .count();
+        live + recordings
+    }
+
+    /// Stream the camera with the CPU encoder from now on
+    pub fn fall_back_to_cpu(&self, camera_id: i32, reason: &str) {
+        self.update(camera_id, |pipeline| pipeline.cpu_fallback = Some(crate::redact::redact(reason)));
+    }
+
+    /// Why the camera's stream was degraded to the CPU encoder, if it was
+    pub fn cpu_fallback(&self, camera_id: i32) -> Option<String> {
+        let cameras = self.cameras.lock().ok()?;
+        cameras.get(&camera_id)?.cpu_fallback.clone()
+    }
+
+    /// Give the GPU encoder another chance on every camera (after the encoder settings changed)
+    pub fn clear_cpu_fallbacks(&self) {
+        if let Ok(mut cameras) = self.cameras.lock() {
+            for pipeline in cameras.values_mut() {
+                pipeline.cpu_fallback = None;
+            }
+        }
+    }
+
+    /// Downgrade level the camera's stream starts with (see budget)
+    pub fn stream_downgrade(&self, camera_id: i32) -> u8 {
+        let Ok(cameras) = self.cameras.lock() else { return 0 };
+        cameras.get(&camera_id).map(|pipeline| pipeline.stream_downgrade).unwrap_or(0)
+    }
+
+    pub fn set_stream_downgrade(&self, camera_id: i32, level: u8) {
+        self.update(camera_id, |pipeline| pipeline.stream_downgrade = level);
+    }
+
+    /// Forget the downgrades of cameras that are no longer streaming
+    pub fn reset_stream_downgrades(&self, streaming: &HashSet<i32>) {
+        if let Ok(mut cameras) = self.cameras.lock() {
+            for (camera_id, pipeline) in cameras.iter_mut() {
+                if !streaming.contains(camera_id) {
+                    pipeline.stream_downgrade = 0;
+                }
+            }
+        }
+    }
+
+    pub fn recording_stop_scheduled(&self, camera_id: i32, recording_id: i32, at: DateTime<Utc>) {
+        self.update(camera_id, |pipeline| pipeline.recording_stop = Some((recording_id, at)));
+    }
+
+    /// When the camera's recording `recording_id` is due to stop, None without a max duration
+    pub fn recording_stops_at(&self, camera_id: i32, recording_id: i32) -> Option<DateTime<Utc>> {
+        let cameras = self.cameras.lock().ok()?;
+        cameras.get(&camera_id)?.recording_stop
+            .filter(|(id, _)| *id == recording_id)
+            .map(|(_, at)| at)
+    }
+
+    /// The camera delivered a stream or recording: emits camera-online unless it already was
+    pub fn camera_online(&self, events: &EventBus, camera_id: i32) {
+        if self.set_reachable(camera_id, true) {
+            println!("[Pipeline] Camera {} is online", camera_id);
+            events.emit(AppEvent::CameraOnline { camera_id });
+        }
+    }
+
+    /// The camera's stream died: emits camera-offline unless it already was
+    pub fn camera_offline(&self, events: &EventBus, camera_id: i32, reason: &str) {
+        if self.set_reachable(camera_id, false) {
+            println!("[Pipeline] Camera {} is offline: {}", camera_id, reason);
+            events.emit(AppEvent::CameraOffline { camera_id, reason: crate::redact::redact(reason) });
+        }
+    }
+
+    // Returns whether the camera's reachability changed
+    fn set_reachable(&self, camera_id: i32, reachable: bool) -> bool {
+        let Ok(mut cameras) = self.cameras.lock() else { return false };
+        let pipeline = cameras.entry(camera_id).or_default();
+        let changed = pipeline.reachable != Some(reachable);
+        pipeline.reachable = Some(reachable);
+        changed
+    }
+
+    pub fn error(&self, camera_id: i32, message: &str) {
+        self.update(camera_id, |pipeline| {
+            pipeline.last_error = Some((crate::redact::redact(message), Utc::now()));
+        });
+    }
+
+    // Record a streaming FFmpeg that exited by itself (only once per start)
+    fn stream_exited(&self, camera_id: i32, status: &str) {
+        self.update(camera_id, |pipeline| {
+            if pipeline.stream.take().is_some() {
+                pipeline.last_error = Some((format!("Stream FFmpeg exited with {}", status), Utc::now()));
+            }
+        });
+    }
+}
+
+// camera_id -> (recording id, output filename, start time) of unfinished recordings
+fn unfinished_recordings(conn: &rusqlite::Connection, recording_dir: &std::path::Path) -> Result<HashMap<i32, (i32, PathBuf, String)>, String> {
+    let mut stmt = conn.prepare(
+        "SELECT camera_id, id, filename, start_time, location_id FROM recordings WHERE is_finished = 0 ORDER BY start_time"
+    ).map_err(|e| e.to_string())?;
+    let rows = stmt.query_map([], |row| {
+        Ok((row.get::<_, i32>(0)?, row.get::<_, i32>(1)?, row.get::<_, String>(2)?, row.get::<_, String>(3)?, row.get::<_, Option<i64>>(4)?))
+    }).map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    // Files of running recordings, wherever they are being written
+    rows.into_iter()
+        .map(|(camera_id, id, filename, start_time, location_id)| {
+            let path = crate::storage::resolve(conn, recording_dir, location_id, &filename)?;
+            Ok((camera_id, (id, path, start_time)))
+        })
+        .collect()
+}
+
+/// Every few seconds, mark recordings whose FFmpeg died right after starting as failed and send
+/// a `recording-progress` event with the elapsed time and bytes written for each running recording
+pub fn spawn_recording_progress(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(Duration::from_secs(RECORDING_PROGRESS_INTERVAL_SECS)).await;
+
+            let state = app_handle.state::<AppState>();
+            if let Err(e) = detect_failed_recordings(&state) {
+                eprintln!("[Pipeline] Recording failure check failed: {}", e);
+            }
+            if let Err(e) = emit_recording_progress(&state) {
+                eprintln!("[Pipeline] Recording progress failed: {}", e);
+            }
+        }
+    });
+}
+
+// Recording FFmpegs that exited by themselves within RECORDING_STARTUP_SECS: the recording is
+// marked failed with FFmpeg's last words, its output removed and `recording-failed` sent. Later
+// exits keep what was captured and are left for stop_recording.
+fn detect_failed_recordings(state: &AppState) -> Result<(), String> {
+    let mut exited = Vec::new();
+    {
+        let mut processes = state.recording_processes.lock().map_err(|e| e.to_string())?;
+        let cameras = state.pipelines.cameras.lock().map_err(|e| e.to_string())?;
+        processes.retain(|camera_id, child| {
+            let Some((spawned_at, stderr)) = cameras.get(camera_id).and_then(|p| p.recording_process.as_ref()) else { return true };
+            if spawned_at.elapsed() >= Duration::from_secs(RECORDING_STARTUP_SECS) {
+                return true;
+            }
+            match child.try_wait() {
+                Ok(Some(status)) => {
+                    state.pipelines.registry.remove(child.id());
+                    exited.push((*camera_id, status.to_string(), spawned_at.elapsed(), stderr.clone()));
+                    false
+                }
+                _ => true,
+            }
+        });
+    }
+    if exited.is_empty() {
+        return Ok(());
+    }
+
+    let conn = crate::db::open(&state.db_path).map_err(|e| e.to_string())?;
+    let mut unfinished = unfinished_recordings(&conn, &state.recording_dir)?;
+    for (camera_id, status, after, stderr) in exited {
+        let lines = crate::redact::tail_lines(&stderr);
+        let last_lines = &lines[lines.len().saturating_sub(FAILURE_STDERR_LINES)..];
+        let mut failure = format!("FFmpeg exited with {} after {}s", status, after.as_secs());
+        if !last_lines.is_empty() {
+            failure = format!("{}: {}", failure, last_lines.join("\n"));
+        }
+        println!("[Recording] Recording of camera {} failed: {}", camera_id, failure);
+
+        if let Some((recording_id, path, _)) = unfinished.remove(&camera_id) {
+            let _ = std::fs::remove_file(&path);
+            let now = Utc::now().to_rfc3339();
+            conn.execute(
+                "UPDATE recordings SET is_finished = 1, end_time = ?1, file_size = NULL, failure = ?2 WHERE id = ?3",
+                rusqlite::params![now, failure, recording_id],
+            ).map_err(|e| e.to_string())?;
+            conn.execute(
+                "UPDATE recording_sessions SET ended_at = ?1
+                 WHERE id = (SELECT session_id FROM recordings WHERE id = ?2)",
+                rusqlite::params![now, recording_id],
+            ).map_err(|e| e.to_string())?;
+        }
+        state.pipelines.error(camera_id, &failure);
+        state.events.emit(AppEvent::RecordingFailed { camera_id, error: failure });
+    }
+    Ok(())
+}
+
+fn emit_recording_progress(state: &AppState) -> Result<(), String> {
+    let recording: HashSet<i32> = state.recording_processes.lock()
+        .map_err(|e| e.to_string())?
+        .keys()
+        .copied()
+        .collect();
+    if recording.is_empty() {
+        return Ok(());
+    }
+
+    let conn = crate::db::open(&state.db_path).map_err(|e| e.to_string())?;
+    let now = Utc::now();
+    for (camera_id, (recording_id, path, start_time)) in unfinished_recordings(&conn, &state.recording_dir)? {
+        if !recording.contains(&camera_id) {
+            continue;
+        }
+        let Ok(start_time) = DateTime::parse_from_rfc3339(&start_time) else { continue };
+        let bytes = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+
+        state.events.emit(AppEvent::RecordingProgress {
+            camera_id,
+            recording_id,
+            elapsed_secs: (now - start_time.with_timezone(&Utc)).num_seconds(),
+            bytes,
+        });
+    }
+    Ok(())
+}
+
+/// Streaming and recording state of every camera
+pub fn pipeline_status(state: &AppState) -> Result<Vec<PipelineStatus>, String> {
+    let conn = crate::db::open(&state.db_path).map_err(|e| e.to_string())?;
+
+    let camera_ids = {
+        let mut stmt = conn.prepare("SELECT id FROM cameras WHERE archived_at IS NULL ORDER BY id").map_err(|e| e.to_string())?;
+        let ids = stmt.query_map([], |row| row.get::<_, i32>(0))
+            .map_err(|e| e.to_string())?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())?;
+        ids
+    };
+
+    let unfinished = unfinished_recordings(&conn, &state.recording_dir)?;
+
+    // A stream counts as active only while its FFmpeg is still running
+    let streaming: HashSet<i32> = {
+        let mut processes = state.processes.lock().map_err(|e| e.to_string())?;
+        let mut running = HashSet::new();
+        for (camera_id, child) in processes.iter_mut() {
+            match child.try_wait() {
+                Ok(None) => {
+                    running.insert(*camera_id);
+                }
+                Ok(Some(status)) => state.pipelines.stream_exited(*camera_id, &status.to_string()),
+                Err(e) => state.pipelines.stream_exited(*camera_id, &e.to_string()),
+            }
+        }
+        running
+    };
+
+    let paused: HashSet<i32> = {
+        let mut stmt = conn.prepare("SELECT camera_id FROM recording_gaps WHERE resumed_at IS NULL")
+            .map_err(|e| e.to_string())?;
+        let ids = stmt.query_map([], |row| row.get::<_, i32>(0))
+            .map_err(|e| e.to_string())?
+            .collect::<Result<HashSet<_>, _>>()
+            .map_err(|e| e.to_string())?;
+        ids
+    };
+
+    let recording: HashSet<i32> = state.recording_processes.lock()
+        .map_err(|e| e.to_string())?
+        .keys()
+        .copied()
+        .collect();
+
+    let tracked = state.pipelines.cameras.lock().map_err(|e| e.to_string())?;
+    let now = Utc::now();
+
+    let statuses = camera_ids.into_iter().map(|camera_id| {
+        let pipeline = tracked.get(&camera_id);
+        let stream = pipeline
+            .and_then(|p| p.stream.as_ref())
+            .filter(|_| streaming.contains(&camera_id));
+        let active_recording = unfinished.get(&camera_id).filter(|_| recording.contains(&camera_id));
+        let recording_started_at = active_recording.and_then(|(_, _, start)| {
+            DateTime::parse_from_rfc3339(start).ok().map(|t| t.with_timezone(&Utc))
+        });
+
+        PipelineStatus {
+            cameraId: camera_id,
+            streaming: streaming.contains(&camera_id),
+            streamUrl: stream.map(|s| {
+                let session = state.stream_tokens.status_session(camera_id);
+                state.stream_tokens.session_url(state.server_port, &session, s.file)
+            }),
+            streamStartedAt: stream.map(|s| s.started_at),
+            streamUptimeSecs: stream.map(|s| (now - s.started_at).num_seconds()),
+            streamEncoder: stream.map(|s| s.encoder.clone()),
+            streamGpu: stream.map(|s| s.is_gpu),
+            streamCpuFallback: pipeline.and_then(|p| p.cpu_fallback.clone()),
+            streamDowngrade: pipeline.map(|p| p.stream_downgrade).unwrap_or(0),
+            streamRestartCount: pipeline.map(|p| p.stream_starts.saturating_sub(1)).unwrap_or(0),
+            recording: recording.contains(&camera_id),
+            recordingId: active_recording.map(|(id, _, _)| *id),
+            recordingStartedAt: recording_started_at,
+            recordingElapsedSecs: recording_started_at.map(|start| (now - start).num_seconds()),
+            recordingFileSize: active_recording.and_then(|(_, path, _)| {
+                std::fs::metadata(path).ok().map(|m| m.len())
+            }),
+            recordingPaused: paused.contains(&camera_id),
+            // Only meaningful while the recording it was scheduled for is still running
+            recordingStopsAt: pipeline.and_then(|p| p.recording_stop)
+                .filter(|(id, _)| active_recording.is_some_and(|(active_id, _, _)| active_id == id))
+                .map(|(_, at)| at),
+            lastError: pipeline.and_then(|p| p.last_error.as_ref().map(|(message, _)| message.clone())),
+            lastErrorAt: pipeline.and_then(|p| p.last_error.as_ref().map(|(_, at)| *at)),
+        }
+    }).collect();
+
+    Ok(statuses)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::test_db::TestDb;
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn tokio_processes_are_registered_until_dropped() {
+        let db = TestDb::new("tracked-process");
+        let state_file = db.dir().join("processes.json");
+        let pipelines = PipelineTracker::new(ProcessRegistry::start(state_file.clone()));
+        let registered = || {
+            let state: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&state_file).unwrap()).unwrap();
+            state["pids"].as_array().unwrap().iter().map(|pid| pid.as_u64().unwrap() as u32).collect::<Vec<_>>()
+        };
+
+        let child = tokio::process::Command::new("sleep").arg("30").kill_on_drop(true).spawn().unwrap();
+        let pid = child.id().unwrap();
+        let process = pipelines.adopt_tokio_process(&child);
+        assert_eq!(registered(), [pid]);
+
+        drop(process);
+        assert!(registered().is_empty());
+    }
+
+    #[test]
+    fn reachability_changes_are_emitted_once() {
+        let pipelines = PipelineTracker::default();
+        let events = EventBus::detached();
+        let mut received = events.subscribe();
+        let mut emitted = || std::iter::from_fn(|| received.try_recv().ok())
+            .map(|message| serde_json::from_str::<serde_json::Value>(&message).unwrap()["event"].as_str().unwrap().to_string())
+            .collect::<Vec<_>>();
+
+        pipelines.camera_online(&events, 1);
+        pipelines.camera_online(&events, 1);
+        assert_eq!(emitted(), ["camera-online"]);
+
+        pipelines.camera_offline(&events, 1, "FFmpeg exited with exit status: 1");
+        pipelines.camera_offline(&events, 1, "FFmpeg exited with exit status: 1");
+        pipelines.camera_online(&events, 2);
+        assert_eq!(emitted(), ["camera-offline", "camera-online"]);
+
+        pipelines.camera_online(&events, 1);
+        assert_eq!(emitted(), ["camera-online"]);
+    }
+}
@@ -0,0 +1,288 @@
+use crate::models::PlaybackSession;
+use crate::AppState;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::Duration;
+use tauri::State;
+
+// Windows-specific imports for hiding console window
+#[cfg(target_os = "windows")]
+use std::os::windows::process::CommandExt;
+
+// How long to wait for the first transcoded playlist before giving up
+const PLAYLIST_READY_TIMEOUT_SECS: u64 = 15;
+const PLAYLIST_POLL_INTERVAL_MS: u64 = 200;
+
+// Codecs the webview's <video> element plays natively from an MP4 container
+const COMPATIBLE_VIDEO_CODECS: [&str; 1] = ["h264"];
+const COMPATIBLE_AUDIO_CODECS: [&str; 2] = ["aac", "mp3"];
+
+pub(crate) struct MediaInfo {
+    pub(crate) video_codec: Option<String>,
+    pub(crate) audio_codec: Option<String>,
+    pub(crate) duration: Option<f64>,
+}
+
+// Read codecs and duration with ffprobe
+pub(crate) fn probe_media(path: &Path) -> Result<MediaInfo, String> {
+    let mut cmd = Command::new("ffprobe");
+    cmd.args([
+        "-v", "error",
+        "-show_entries", "stream=codec_type,codec_name:format=duration",
+        "-of", "json",
+    ])
+    .arg(path);
+
+    #[cfg(target_os = "windows")]
+    {
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        cmd.creation_flags(CREATE_NO_WINDOW);
+    }
+
+    let output = cmd.output().map_err(|e| format!("Failed to run ffprobe: {}", e))?;
+    if !output.status.success() {
+        return Err(format!("ffprobe failed: {}", String::from_utf8_lossy(&output.stderr)));
+    }
+
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).map_err(|e| e.to_string())?;
+    let codec_of = |kind: &str| {
+        json["streams"].as_array().and_then(|streams| {
+            streams.iter()
+                .find(|s| s["codec_type"] == kind)
+                .and_then(|s| s["codec_name"].as_str())
+                .map(|c| c.to_string())
+        })
+    };
+
+    Ok(MediaInfo {
+        video_codec: codec_of("video"),
+        audio_codec: codec_of("audio"),
+        duration: json["format"]["duration"].as_str().and_then(|d| d.parse().ok()),
+    })
+}
+
+fn is_webview_compatible(path: &Path, info: &MediaInfo) -> bool {
+    let is_mp4 = path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("mp4") || e.eq_ignore_ascii_case("m4v"));
+
+    let video_ok = info.video_codec.as_deref().is_some_and(|c| COMPATIBLE_VIDEO_CODECS.contains(&c));
+    let audio_ok = info.audio_codec.as_deref().is_none_or(|c| COMPATIBLE_AUDIO_CODECS.contains(&c));
+
+    is_mp4 && video_ok && audio_ok
+}
+
+/// Prepare a recording for playback in the webview.
+/// Compatible files are served as-is; anything else gets a transient HLS (fMP4) transcode
+/// starting at `start_secs`, so the player can seek past what has been transcoded by
+/// starting a new session at the target position.
+pub async fn start_playback_session(
+    state: State<'_, AppState>,
+    recording_id: i32,
+    start_secs: Option<f64>,
+) -> Result<PlaybackSession, String> {
+    let (filename, location_id, source) = {
+        let conn = crate::db::open(&state.db_path).map_err(|e| e.to_string())?;
+        let (filename, location_id): (String, Option<i64>) = conn.query_row(
+            "SELECT filename, location_id FROM recordings WHERE id = ?1",
+            [recording_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        ).map_err(|e| e.to_string())?;
+        let source = crate::storage::resolve(&conn, &state.recording_dir, location_id, &filename)?;
+        (filename, location_id, source)
+    };
+
+    if !source.exists() {
+        return Err(format!("Recording file {} is missing", filename));
+    }
+
+    let probe_path = source.clone();
+    let info = tauri::async_runtime::spawn_blocking(move || probe_media(&probe_path))
+        .await
+        .map_err(|e| e.to_string())??;
+
+    let port = state.server_port;
+
+    if is_webview_compatible(&source, &info) {
+        println!("[Playback] Recording {} is webview compatible, serving directly", recording_id);
+        return Ok(PlaybackSession {
+            sessionId: None,
+            url: crate::storage::recording_url(port, &state.server_token, location_id, &filename),
+            transcoded: false,
+            startOffset: 0.0,
+            duration: info.duration,
+        });
+    }
+
+    println!(
+        "[Playback] Transcoding recording {} (video: {:?}, audio: {:?})",
+        recording_id, info.video_codec, info.audio_codec
+    );
+    transcode(&state, &[source], start_secs, info.duration).await
+}
+
+/// Prepare a whole recording session for playback: its segments are transcoded as one
+/// stream, pauses left out. A session of one segment plays like that recording.
+pub async fn start_recording_session_playback(
+    state: State<'_, AppState>,
+    session_id: i64,
+    start_secs: Option<f64>,
+) -> Result<PlaybackSession, String> {
+    let segments: Vec<(i32, PathBuf)> = {
+        let conn = crate::db::open(&state.db_path).map_err(|e| e.to_string())?;
+        let mut stmt = conn.prepare(
+            "SELECT id FROM recordings WHERE session_id = ?1 AND is_finished = 1 ORDER BY start_time"
+        ).map_err(|e| e.to_string())?;
+        let ids = stmt.query_map([session_id], |row| row.get::<_, i32>(0))
+            .map_err(|e| e.to_string())?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())?;
+        ids.into_iter()
+            .map(|id| Ok((id, crate::storage::recording_path(&conn, &state.recording_dir, id)?)))
+            .collect::<Result<Vec<_>, String>>()?
+    };
+
+    match segments.as_slice() {
+        [] => Err(format!("Recording session {} has no finished segments", session_id)),
+        [(recording_id, _)] => start_playback_session(state, *recording_id, start_secs).await,
+        _ => {
+            let sources: Vec<_> = segments.into_iter().map(|(_, source)| source).collect();
+            if let Some(missing) = sources.iter().find(|source| !source.exists()) {
+                return Err(format!("Recording file {} is missing", missing.to_string_lossy()));
+            }
+
+            let probe_sources = sources.clone();
+            let duration = tauri::async_runtime::spawn_blocking(move || {
+                probe_sources.iter()
+                    .map(|source| probe_media(source).map(|info| info.duration))
+                    .sum::<Result<Option<f64>, String>>()
+            }).await.map_err(|e| e.to_string())??;
+
+            println!("[Playback] Transcoding recording session {} ({} segments)", session_id, sources.len());
+            transcode(&state, &sources, start_secs, duration).await
+        }
+    }
+}
+
+// Transient HLS (fMP4) transcode of `sources` played back to back, starting at `start_secs`
+async fn transcode(
+    state: &State<'_, AppState>,
+    sources: &[PathBuf],
+    start_secs: Option<f64>,
+    duration: Option<f64>,
+) -> Result<PlaybackSession, String> {
+    let port = state.server_port;
+    let start = start_secs.unwrap_or(0.0).max(0.0);
+    let session_id = uuid::Uuid::new_v4().to_string();
+    let session_dir = state.stream_dir.join("playback").join(&session_id);
+    std::fs::create_dir_all(&session_dir).map_err(|e| e.to_string())?;
+
+    println!("[Playback] Session {} starts at {}s", session_id, start);
+
+    let encoder_selector = crate::stream::build_encoder_selector_from_path(&state.db_path).await?;
+    let encoder_config = encoder_selector.select_encoder_for_streaming(None).await;
+
+    let mut args = vec![
+        "-y".to_string(),
+        "-ss".to_string(), format!("{:.3}", start),
+    ];
+    match sources {
+        [source] => args.extend(["-i".to_string(), source.to_string_lossy().to_string()]),
+        _ => {
+            // Segments of a session, through the concat demuxer
+            let list_path = session_dir.join("segments.txt");
+            let list: String = sources.iter()
+                .map(|source| format!("file '{}'\n", source.to_string_lossy().replace('\'', "'\\''")))
+                .collect();
+            std::fs::write(&list_path, list).map_err(|e| e.to_string())?;
+            args.extend(["-f", "concat", "-safe", "0", "-i"].map(String::from));
+            args.push(list_path.to_string_lossy().to_string());
+        }
+    }
+    args.extend(encoder_config.args);
+    args.extend(state.pipelines.run_marker_args());
+    args.extend_from_slice(&[
+        "-c:a".to_string(), "aac".to_string(),
+        "-f".to_string(), "hls".to_string(),
+        "-hls_time".to_string(), "4".to_string(),
+        "-hls_list_size".to_string(), "0".to_string(),
+        "-hls_playlist_type".to_string(), "event".to_string(),
+        "-hls_segment_type".to_string(), "fmp4".to_string(),
+        "-hls_fmp4_init_filename".to_string(), "init.mp4".to_string(),
+        "-hls_segment_filename".to_string(), session_dir.join("segment_%05d.m4s").to_string_lossy().to_string(),
+        session_dir.join("index.m3u8").to_string_lossy().to_string(),
+    ]);
+
+    let mut cmd = Command::new("ffmpeg");
+    cmd.args(&args)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+
+    #[cfg(target_os = "windows")]
+    {
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        cmd.creation_flags(CREATE_NO_WINDOW);
+    }
+
+    let child = cmd.spawn().map_err(|e| format!("Failed to start playback ffmpeg: {}", e))?;
+    state.pipelines.adopt_process(&child);
+
+    {
+        let mut sessions = state.playback_processes.lock().map_err(|e| e.to_string())?;
+        sessions.insert(session_id.clone(), child);
+    }
+
+    // Don't hand the player a URL until the first playlist exists
+    let playlist = session_dir.join("index.m3u8");
+    let mut waited = Duration::ZERO;
+    while !playlist.exists() {
+        if waited >= Duration::from_secs(PLAYLIST_READY_TIMEOUT_SECS) {
+            stop_playback_session(state, &session_id)?;
+            return Err("Timed out waiting for the transcoded stream".to_string());
+        }
+
+        let exited = {
+            let mut sessions = state.playback_processes.lock().map_err(|e| e.to_string())?;
+            match sessions.get_mut(&session_id) {
+                Some(child) => child.try_wait().map_err(|e| e.to_string())?.is_some(),
+                None => true,
+            }
+        };
+        if exited {
+            stop_playback_session(state, &session_id)?;
+            return Err("FFmpeg exited before producing a playable stream".to_string());
+        }
+
+        tokio::time::sleep(Duration::from_millis(PLAYLIST_POLL_INTERVAL_MS)).await;
+        waited += Duration::from_millis(PLAYLIST_POLL_INTERVAL_MS);
+    }
+
+    Ok(PlaybackSession {
+        sessionId: Some(session_id.clone()),
+        url: state.stream_tokens.url(port, &format!("streams/playback/{}/index.m3u8", session_id)),
+        transcoded: true,
+        startOffset: start,
+        duration,
+    })
+}
+
+/// Stop a transcode session and remove its files
+pub fn stop_playback_session(state: &AppState, session_id: &str) -> Result<(), String> {
+    let child = {
+        let mut sessions = state.playback_processes.lock().map_err(|e| e.to_string())?;
+        sessions.remove(session_id)
+    };
+
+    if let Some(mut child) = child {
+        println!("[Playback] Stopping session {}", session_id);
+        let _ = state.pipelines.kill_process(&mut child);
+        let _ = child.wait();
+    }
+
+    let session_dir = state.stream_dir.join("playback").join(session_id);
+    if session_dir.exists() {
+        std::fs::remove_dir_all(&session_dir).map_err(|e| e.to_string())?;
+    }
+
+    Ok(())
+}
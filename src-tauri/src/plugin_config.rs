@@ -0,0 +1,156 @@
+use crate::camera_plugin::{CameraPlugin, PluginManager};
+use rusqlite::{Connection, OptionalExtension};
+use serde_json::{json, Map, Value};
+
+/// Stored configuration of a plugin; plugins without a row get an empty object
+pub fn load(conn: &Connection, plugin_type: &str) -> Result<Value, String> {
+    let config: Option<String> = conn.query_row(
+        "SELECT config FROM plugin_settings WHERE plugin_type = ?1",
+        [plugin_type],
+        |row| row.get(0),
+    ).optional().map_err(|e| e.to_string())?;
+
+    match config {
+        Some(config) => serde_json::from_str(&config).map_err(|e| e.to_string()),
+        None => Ok(json!({})),
+    }
+}
+
+pub fn save(conn: &Connection, plugin_type: &str, config: &Value) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO plugin_settings (plugin_type, config, updated_at) VALUES (?1, ?2, CURRENT_TIMESTAMP)
+         ON CONFLICT(plugin_type) DO UPDATE SET config = excluded.config, updated_at = excluded.updated_at",
+        rusqlite::params![plugin_type, config.to_string()],
+    ).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Fill in the schema's defaults for properties the config doesn't set
+pub fn with_defaults(schema: &Value, config: &Value) -> Value {
+    let mut config = config.clone();
+    if let (Some(properties), Some(values)) = (schema["properties"].as_object(), config.as_object_mut()) {
+        for (name, property) in properties {
+            if !values.contains_key(name) {
+                if let Some(default) = property.get("default") {
+                    values.insert(name.clone(), default.clone());
+                }
+            }
+        }
+    }
+    config
+}
+
+/// Check a config against a plugin's schema. The schema is a JSON Schema subset: "type"
+/// (object, array, string, integer, number, boolean), "properties", "required",
+/// "additionalProperties": false, "items", "enum", "minimum"/"maximum", "minLength"/"maxLength"
+/// and "pattern".
+pub fn validate(schema: &Value, config: &Value) -> Result<(), String> {
+    validate_value(schema, config, "config")
+}
+
+fn validate_value(schema: &Value, value: &Value, path: &str) -> Result<(), String> {
+    if let Some(expected) = schema["type"].as_str() {
+        let matches = match expected {
+            "object" => value.is_object(),
+            "array" => value.is_array(),
+            "string" => value.is_string(),
+            "integer" => value.is_i64() || value.is_u64(),
+            "number" => value.is_number(),
+            "boolean" => value.is_boolean(),
+            "null" => value.is_null(),
+            other => return Err(format!("Unsupported schema type '{}' at {}", other, path)),
+        };
+        if !matches {
+            return Err(format!("{} must be of type {}", path, expected));
+        }
+    }
+
+    if let Some(options) = schema["enum"].as_array() {
+        if !options.contains(value) {
+            let options: Vec<String> = options.iter().map(|o| o.to_string()).collect();
+            return Err(format!("{} must be one of {}", path, options.join(", ")));
+        }
+    }
+
+    if let Some(number) = value.as_f64() {
+        if let Some(minimum) = schema["minimum"].as_f64() {
+            if number < minimum {
+                return Err(format!("{} must be at least {}", path, minimum));
+            }
+        }
+        if let Some(maximum) = schema["maximum"].as_f64() {
+            if number > maximum {
+                return Err(format!("{} must be at most {}", path, maximum));
+            }
+        }
+    }
+
+    if let Some(text) = value.as_str() {
+        let length = text.chars().count() as u64;
+        if schema["minLength"].as_u64().is_some_and(|min| length < min) {
+            return Err(format!("{} must be at least {} characters", path, schema["minLength"]));
+        }
+        if schema["maxLength"].as_u64().is_some_and(|max| length > max) {
+            return Err(format!("{} must be at most {} characters", path, schema["maxLength"]));
+        }
+        if let Some(pattern) = schema["pattern"].as_str() {
+            let re = regex::Regex::new(pattern).map_err(|e| format!("Invalid pattern at {}: {}", path, e))?;
+            if !re.is_match(text) {
+                return Err(format!("{} doesn't match the expected format", path));
+            }
+        }
+    }
+
+    if let (Some(items), Some(values)) = (schema.get("items"), value.as_array()) {
+        for (i, item) in values.iter().enumerate() {
+            validate_value(items, item, &format!("{}[{}]", path, i))?;
+        }
+    }
+
+    if let Some(values) = value.as_object() {
+        validate_object(schema, values, path)?;
+    }
+
+    Ok(())
+}
+
+fn validate_object(schema: &Value, values: &Map<String, Value>, path: &str) -> Result<(), String> {
+    let empty = Map::new();
+    let properties = schema["properties"].as_object().unwrap_or(&empty);
+
+    if let Some(required) = schema["required"].as_array() {
+        for name in required.iter().filter_map(|n| n.as_str()) {
+            if !values.contains_key(name) {
+                return Err(format!("{}.{} is required", path, name));
+            }
+        }
+    }
+
+    for (name, value) in values {
+        match properties.get(name) {
+            Some(property) => validate_value(property, value, &format!("{}.{}", path, name))?,
+            None if schema["additionalProperties"] == json!(false) => {
+                return Err(format!("Unknown setting {}.{}", path, name));
+            }
+            None => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// Hand every configurable plugin its stored config (app startup)
+pub fn configure_all(plugin_manager: &PluginManager, conn: &Connection) {
+    for plugin_type in plugin_manager.get_plugin_types() {
+        let Some(plugin) = plugin_manager.get_plugin(&plugin_type) else { continue };
+        if let Err(e) = configure(plugin.as_ref(), conn) {
+            eprintln!("[Plugins] Failed to configure plugin '{}': {}", plugin_type, e);
+        }
+    }
+}
+
+fn configure(plugin: &dyn CameraPlugin, conn: &Connection) -> Result<(), String> {
+    let Some(schema) = plugin.config_schema() else { return Ok(()) };
+    let config = with_defaults(&schema, &load(conn, plugin.plugin_type())?);
+    plugin.configure(&config)
+}
@@ -0,0 +1,505 @@
+use crate::camera_plugin::{CameraAlarm, CameraInfo, CameraPlugin, DiscoveryScan, PtzDirection};
+use crate::models::{Camera, ProbedDevice};
+use crate::plugins::http_auth::{base_url, key_values, send, send_text};
+use async_trait::async_trait;
+use futures::{SinkExt, StreamExt};
+use reqwest::{Client, Method};
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::time::{Duration, Instant};
+use tokio::net::UdpSocket;
+use tokio_tungstenite::tungstenite::Message;
+
+const DEFAULT_RTSP_PORT: u16 = 554;
+// PTZ speed (-100..100) used for direction moves
+const PTZ_SPEED: i32 = 50;
+// How long discovery listens for SSDP and mDNS answers
+const DISCOVERY_SECS: u64 = 3;
+const SSDP_ADDR: &str = "239.255.255.250:1900";
+const MDNS_ADDR: &str = "224.0.0.251:5353";
+const MDNS_SERVICE: &str = "_axis-video._tcp.local";
+// Event topics to subscribe to ("//." includes every child topic)
+const EVENT_TOPICS: [&str; 4] = [
+    "tns1:VideoSource//.",
+    "tns1:Device/tnsaxis:IO//.",
+    "tns1:RuleEngine//.",
+    "tnsaxis:CameraApplicationPlatform//.",
+];
+// The event stream has no heartbeat; ping it so a dead connection is noticed
+const EVENT_PING_SECS: u64 = 30;
+
+/// Axis camera plugin using VAPIX, for users whose ONVIF accounts are restricted or missing.
+/// `port` is the camera's HTTP port.
+pub struct AxisPlugin {
+    http: crate::onvif::OnvifHttpClient,
+}
+
+impl AxisPlugin {
+    pub fn new(http: crate::onvif::OnvifHttpClient) -> Self {
+        AxisPlugin { http }
+    }
+}
+
+fn credentials(camera: &Camera) -> (String, String) {
+    (camera.user.clone().unwrap_or_default(), camera.pass.clone().unwrap_or_default())
+}
+
+fn vapix_url(camera: &Camera, path: &str) -> String {
+    format!("{}{}", base_url(&camera.host, camera.port as u16), path)
+}
+
+// Video channel from a stream path like "/axis-media/media.amp?camera=2"
+fn channel(camera: &Camera) -> u32 {
+    camera.stream_path.as_deref()
+        .and_then(|path| path.split(['?', '&']).find_map(|param| param.strip_prefix("camera=")))
+        .and_then(|id| id.parse::<u32>().ok())
+        .map(|id| id.max(1))
+        .unwrap_or(1)
+}
+
+async fn get(client: &Client, camera: &Camera, path: &str) -> Result<String, String> {
+    let (user, pass) = credentials(camera);
+    send_text(client, Method::GET, &vapix_url(camera, path), &user, &pass, None).await
+}
+
+async fn list_parameters(client: &Client, base: &str, user: &str, pass: &str, group: &str) -> Result<HashMap<String, String>, String> {
+    let url = format!("{}/axis-cgi/param.cgi?action=list&group={}", base, urlencoding::encode(group));
+    let text = send_text(client, Method::GET, &url, user, pass, None).await?;
+    // VAPIX reports errors with HTTP 200 and a "# Error:" body
+    if let Some(error) = text.lines().find(|line| line.starts_with("# Error")) {
+        return Err(error.trim_start_matches('#').trim().to_string());
+    }
+    Ok(key_values(&text))
+}
+
+/// VAPIX parameters of a camera ("root.Brand", "root.Properties.Firmware", ...)
+pub async fn parameters(client: &Client, camera: &Camera, group: &str) -> Result<HashMap<String, String>, String> {
+    let (user, pass) = credentials(camera);
+    list_parameters(client, &base_url(&camera.host, camera.port as u16), &user, &pass, group).await
+}
+
+/// Query a camera by address and describe it for adding; `xaddr` is the VAPIX root
+pub async fn probe(client: &Client, host: &str, port: u16, user: &str, pass: &str) -> Result<ProbedDevice, String> {
+    let base = base_url(host, port);
+    println!("[AxisPlugin] Probing {}", base);
+
+    let params = list_parameters(client, &base, user, pass, "root.Brand,root.Properties").await?;
+    let field = |name: &str| params.get(name).cloned().unwrap_or_default();
+    let name = field("root.Brand.ProdFullName");
+
+    Ok(ProbedDevice {
+        name: if name.is_empty() { format!("AXIS {}", field("root.Brand.ProdNbr")) } else { name },
+        host: host.trim().to_string(),
+        port,
+        camera_type: "axis".to_string(),
+        user: (!user.is_empty()).then(|| user.to_string()),
+        pass: (!pass.is_empty()).then(|| pass.to_string()),
+        xaddr: format!("{}/axis-cgi", base),
+        manufacturer: "Axis".to_string(),
+        model: field("root.Brand.ProdNbr"),
+        firmware_version: field("root.Properties.Firmware.Version"),
+        serial_number: field("root.Properties.System.SerialNumber"),
+        hardware_id: field("root.Properties.System.HardwareID"),
+        has_ptz: field("root.Properties.PTZ.PTZ") == "yes",
+        channels: Vec::new(),
+    })
+}
+
+/// RTSP URL of the camera's stream (channel 1 unless stream_path says otherwise)
+pub async fn stream_url(client: &Client, camera: &Camera) -> Result<String, String> {
+    // The RTSP port is configurable; fall back to the default if the camera won't say
+    let rtsp_port = match parameters(client, camera, "root.Network.RTSP.Port").await {
+        Ok(params) => params.get("root.Network.RTSP.Port").and_then(|port| port.parse().ok()).unwrap_or(DEFAULT_RTSP_PORT),
+        Err(e) => {
+            println!("[AxisPlugin] Couldn't read the RTSP port of {} ({}), using {}", camera.name, e, DEFAULT_RTSP_PORT);
+            DEFAULT_RTSP_PORT
+        }
+    };
+
+    let path = camera.stream_path.clone()
+        .filter(|path| !path.is_empty())
+        .unwrap_or_else(|| format!("/axis-media/media.amp?camera={}", channel(camera)));
+    let (user, pass) = credentials(camera);
+    let url = crate::stream_url::rtsp_url(&camera.host, rtsp_port, &path)?;
+    crate::stream_url::with_credentials(&url, &user, &pass)
+}
+
+/// JPEG from the camera's snapshot URL
+pub async fn snapshot(client: &Client, camera: &Camera) -> Result<Vec<u8>, String> {
+    let (user, pass) = credentials(camera);
+    let url = vapix_url(camera, &format!("/axis-cgi/jpg/image.cgi?camera={}", channel(camera)));
+    let response = send(client, Method::GET, &url, &user, &pass, None).await?;
+    if !response.status().is_success() {
+        return Err(format!("Snapshot request failed with HTTP {}", response.status()));
+    }
+    Ok(response.bytes().await.map_err(|e| e.to_string())?.to_vec())
+}
+
+// --- Discovery (SSDP and Bonjour) ---
+
+fn ssdp_search() -> String {
+    format!(
+        "M-SEARCH * HTTP/1.1\r\nHOST: {}\r\nMAN: \"ssdp:discover\"\r\nMX: 2\r\nST: urn:axis-com:service:BasicService:1\r\n\r\n",
+        SSDP_ADDR
+    )
+}
+
+// Axis devices answer the BasicService search; check the headers in case others reply too
+fn is_axis_ssdp_reply(reply: &str) -> bool {
+    reply.to_ascii_lowercase().contains("axis")
+}
+
+// MAC address from the reply's USN ("uuid:Upnp-BasicDevice-1_0-ACCC8E123456::urn:...")
+fn ssdp_mac(reply: &str) -> Option<String> {
+    let usn = reply.lines().find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        name.trim().eq_ignore_ascii_case("USN").then(|| value.trim())
+    })?;
+    let uuid = usn.split("::").next()?;
+    crate::camera_plugin::normalize_mac(uuid.get(uuid.len().checked_sub(12)?..)?)
+}
+
+// DNS query for the PTR records of the Axis Bonjour service
+fn mdns_query() -> Vec<u8> {
+    let mut packet = vec![0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0];
+    for label in MDNS_SERVICE.split('.') {
+        packet.push(label.len() as u8);
+        packet.extend_from_slice(label.as_bytes());
+    }
+    // End of name, QTYPE PTR, QCLASS IN
+    packet.extend_from_slice(&[0, 0, 12, 0, 1]);
+    packet
+}
+
+// Answers are compressed DNS messages; a reply mentioning the service label is enough
+// to know the sender is an Axis device
+fn is_axis_mdns_reply(packet: &[u8]) -> bool {
+    packet.len() > 12
+        && packet[2] & 0x80 != 0
+        && packet.windows(11).any(|w| w == b"_axis-video")
+}
+
+fn discovered_camera(address: IpAddr, mac: Option<String>) -> CameraInfo {
+    CameraInfo {
+        name: format!("Axis camera ({})", address),
+        host: address.to_string(),
+        port: 80,
+        camera_type: "axis".to_string(),
+        user: None,
+        pass: None,
+        device_path: None,
+        device_id: None,
+        device_index: None,
+        video_format: None,
+        video_width: None,
+        video_height: None,
+        video_fps: None,
+        xaddr: None,
+        mac,
+        serial: None,
+        sources: vec!["axis".to_string()],
+        thumbnail: None,
+        existing_camera_id: None,
+    }
+}
+
+async fn discover_devices(scan: Option<&DiscoveryScan>) -> Result<Vec<CameraInfo>, String> {
+    let ssdp = UdpSocket::bind("0.0.0.0:0").await.map_err(|e| e.to_string())?;
+    let ssdp_target: SocketAddr = SSDP_ADDR.parse().map_err(|e: std::net::AddrParseError| e.to_string())?;
+    ssdp.send_to(ssdp_search().as_bytes(), ssdp_target).await.map_err(|e| e.to_string())?;
+
+    // Bonjour is optional; SSDP alone still finds most cameras
+    let mdns = match UdpSocket::bind("0.0.0.0:0").await {
+        Ok(socket) => {
+            let target: SocketAddr = MDNS_ADDR.parse().map_err(|e: std::net::AddrParseError| e.to_string())?;
+            match socket.send_to(&mdns_query(), target).await {
+                Ok(_) => Some(socket),
+                Err(e) => {
+                    println!("[AxisPlugin] mDNS query failed: {}", e);
+                    None
+                }
+            }
+        }
+        Err(e) => {
+            println!("[AxisPlugin] mDNS socket failed: {}", e);
+            None
+        }
+    };
+
+    let deadline = Instant::now() + Duration::from_secs(DISCOVERY_SECS);
+    let mut cameras: Vec<CameraInfo> = Vec::new();
+    let mut ssdp_buf = [0u8; 2048];
+    let mut mdns_buf = [0u8; 4096];
+
+    loop {
+        if scan.is_some_and(|scan| scan.is_cancelled()) {
+            break;
+        }
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            break;
+        }
+
+        let found = tokio::select! {
+            _ = tokio::time::sleep(remaining) => break,
+            result = ssdp.recv_from(&mut ssdp_buf) => match result {
+                Ok((len, from)) => {
+                    let reply = String::from_utf8_lossy(&ssdp_buf[..len]);
+                    is_axis_ssdp_reply(&reply).then(|| (from.ip(), ssdp_mac(&reply)))
+                }
+                Err(_) => None,
+            },
+            result = async { mdns.as_ref().unwrap().recv_from(&mut mdns_buf).await }, if mdns.is_some() => match result {
+                Ok((len, from)) => is_axis_mdns_reply(&mdns_buf[..len]).then_some((from.ip(), None)),
+                Err(_) => None,
+            },
+        };
+
+        if let Some((address, mac)) = found {
+            let camera = discovered_camera(address, mac);
+            if !cameras.iter().any(|c| c.host == camera.host) {
+                if let Some(scan) = scan {
+                    scan.report(camera.clone());
+                }
+                cameras.push(camera);
+            }
+        }
+    }
+
+    println!("[AxisPlugin] Found {} Axis camera(s)", cameras.len());
+    Ok(cameras)
+}
+
+// --- Events ---
+
+// Axis event topics mapped to the kinds shared by all plugins; housekeeping topics are ignored
+fn alarm_kind(topic: &str) -> Option<&'static str> {
+    if topic.contains("MotionDetection") || topic.contains("VMD") || topic.contains("MotionAlarm") {
+        Some("motion")
+    } else if topic.contains("CrossLineDetection") || topic.contains("LineCrossing") {
+        Some("line_crossing")
+    } else if topic.contains("FenceGuard") || topic.contains("ObjectAnalytics") || topic.contains("LoiteringGuard") {
+        Some("intrusion")
+    } else if topic.contains("Tampering") || topic.contains("GlobalSceneChange") {
+        Some("tampering")
+    } else if topic.contains("tnsaxis:IO/") {
+        Some("io")
+    } else {
+        None
+    }
+}
+
+// One "events:notify" message of the VAPIX event stream
+fn parse_notification(text: &str) -> Option<CameraAlarm> {
+    let message: serde_json::Value = serde_json::from_str(text).ok()?;
+    if message["method"] != "events:notify" {
+        return None;
+    }
+    let notification = &message["params"]["notification"];
+    let topic = notification["topic"].as_str()?;
+    let kind = alarm_kind(topic)?;
+
+    // Stateful topics carry their state under one of these keys; stateless ones are pulses
+    let data = &notification["message"]["data"];
+    let active = ["active", "motion", "tampering", "state", "triggered"]
+        .iter()
+        .find_map(|key| data[*key].as_str())
+        .is_none_or(|value| value == "1" || value.eq_ignore_ascii_case("true"));
+
+    let source = &notification["message"]["source"];
+    let channel = ["channel", "VideoSourceConfigurationToken", "port"]
+        .iter()
+        .find_map(|key| source[*key].as_str())
+        .and_then(|value| value.parse::<u32>().ok());
+
+    Some(CameraAlarm {
+        kind: kind.to_string(),
+        active,
+        channel,
+        details: serde_json::json!({
+            "source": "axis",
+            "topic": topic,
+        }),
+    })
+}
+
+#[async_trait]
+impl CameraPlugin for AxisPlugin {
+    fn plugin_type(&self) -> &str {
+        "axis"
+    }
+
+    async fn discover(&self) -> Result<Vec<CameraInfo>, String> {
+        println!("[AxisPlugin] Starting Axis camera discovery (SSDP, Bonjour)...");
+        discover_devices(None).await
+    }
+
+    async fn discover_incremental(&self, scan: &DiscoveryScan) -> Result<Vec<CameraInfo>, String> {
+        println!("[AxisPlugin] Starting incremental Axis camera discovery (SSDP, Bonjour)...");
+        discover_devices(Some(scan)).await
+    }
+
+    async fn discovery_thumbnail(&self, camera: &CameraInfo) -> Option<Vec<u8>> {
+        // Only cameras with anonymous viewing enabled answer without credentials
+        let url = format!("{}/axis-cgi/jpg/image.cgi", base_url(&camera.host, camera.port));
+        let response = self.http.for_tls(false).get(&url).send().await.ok()?;
+        if !response.status().is_success() {
+            return None;
+        }
+        Some(response.bytes().await.ok()?.to_vec())
+    }
+
+    async fn get_stream_url(&self, camera: &Camera) -> Result<String, String> {
+        stream_url(&self.http.for_camera(camera), camera).await
+    }
+
+    fn supports_ptz(&self) -> bool {
+        true
+    }
+
+    fn supports_alarms(&self) -> bool {
+        true
+    }
+
+    async fn ptz_move(&self, camera: &Camera, direction: PtzDirection, _duration_ms: u32) -> Result<(), String> {
+        let command = match direction {
+            PtzDirection::Up => format!("continuouspantiltmove=0,{}", PTZ_SPEED),
+            PtzDirection::Down => format!("continuouspantiltmove=0,{}", -PTZ_SPEED),
+            PtzDirection::Left => format!("continuouspantiltmove={},0", -PTZ_SPEED),
+            PtzDirection::Right => format!("continuouspantiltmove={},0", PTZ_SPEED),
+            PtzDirection::ZoomIn => format!("continuouszoommove={}", PTZ_SPEED),
+            PtzDirection::ZoomOut => format!("continuouszoommove={}", -PTZ_SPEED),
+        };
+        let path = format!("/axis-cgi/com/ptz.cgi?camera={}&{}", channel(camera), command);
+        get(&self.http.for_camera(camera), camera, &path).await?;
+        Ok(())
+    }
+
+    async fn ptz_stop(&self, camera: &Camera) -> Result<(), String> {
+        let path = format!("/axis-cgi/com/ptz.cgi?camera={}&continuouspantiltmove=0,0&continuouszoommove=0", channel(camera));
+        get(&self.http.for_camera(camera), camera, &path).await?;
+        Ok(())
+    }
+
+    async fn watch_alarms(&self, camera: &Camera, on_alarm: &(dyn Fn(CameraAlarm) + Send + Sync)) -> Result<(), String> {
+        // The WebSocket can't answer a Digest challenge itself; a session token authorizes it
+        let token = get(&self.http.for_camera(camera), camera, "/axis-cgi/wssession.cgi").await?;
+        let base = base_url(&camera.host, camera.port as u16);
+        let ws_base = base.replacen("http", "ws", 1);
+        let url = format!("{}/vapix/ws-data-stream?wssession={}&sources=events", ws_base, urlencoding::encode(token.trim()));
+
+        let mut tls = native_tls::TlsConnector::builder();
+        match camera.tls_cert_pem.as_deref() {
+            // Same trust as crate::onvif::pin_certificate
+            Some(pem) => {
+                let certificate = native_tls::Certificate::from_pem(pem.as_bytes()).map_err(|e| e.to_string())?;
+                tls.disable_built_in_roots(true)
+                    .add_root_certificate(certificate)
+                    .danger_accept_invalid_hostnames(true);
+            }
+            None => {
+                tls.danger_accept_invalid_certs(!camera.tls_verify);
+            }
+        }
+        let tls = tls.build().map_err(|e| e.to_string())?;
+        let (mut socket, _) = tokio_tungstenite::connect_async_tls_with_config(
+            url,
+            None,
+            false,
+            Some(tokio_tungstenite::Connector::NativeTls(tls)),
+        ).await.map_err(|e| format!("Event stream connection failed: {}", e))?;
+
+        let filters: Vec<serde_json::Value> = EVENT_TOPICS.iter().map(|topic| serde_json::json!({ "topicFilter": topic })).collect();
+        let configure = serde_json::json!({
+            "apiVersion": "1.0",
+            "method": "events:configure",
+            "params": { "eventFilterList": filters },
+        });
+        socket.send(Message::Text(configure.to_string())).await.map_err(|e| e.to_string())?;
+        println!("[AxisPlugin] Listening for events from {}", camera.name);
+
+        let mut ping = tokio::time::interval(Duration::from_secs(EVENT_PING_SECS));
+        ping.tick().await;
+        let mut awaiting_pong = false;
+        loop {
+            tokio::select! {
+                message = socket.next() => match message {
+                    Some(Ok(Message::Text(text))) => {
+                        if let Some(alarm) = parse_notification(&text) {
+                            on_alarm(alarm);
+                        } else if text.contains("\"error\"") {
+                            println!("[AxisPlugin] Event stream of {} reported: {}", camera.name, text);
+                        }
+                    }
+                    Some(Ok(Message::Pong(_))) => awaiting_pong = false,
+                    Some(Ok(Message::Close(_))) | None => return Err("Event stream closed by the camera".to_string()),
+                    Some(Ok(_)) => {}
+                    Some(Err(e)) => return Err(e.to_string()),
+                },
+                _ = ping.tick() => {
+                    if awaiting_pong {
+                        return Err(format!("No answer on the event stream for {} seconds", EVENT_PING_SECS));
+                    }
+                    socket.send(Message::Ping(Vec::new())).await.map_err(|e| e.to_string())?;
+                    awaiting_pong = true;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn notification(topic: &str, source: serde_json::Value, data: serde_json::Value) -> String {
+        serde_json::json!({
+            "apiVersion": "1.0",
+            "method": "events:notify",
+            "params": { "notification": {
+                "topic": topic,
+                "timestamp": 1714557600000u64,
+                "message": { "source": source, "key": {}, "data": data },
+            }},
+        }).to_string()
+    }
+
+    #[test]
+    fn event_notifications_are_parsed() {
+        let motion = notification("tns1:VideoSource/tnsaxis:MotionAlarm", serde_json::json!({ "channel": "1" }), serde_json::json!({ "state": "1" }));
+        let alarm = parse_notification(&motion).unwrap();
+        assert_eq!(alarm.kind, "motion");
+        assert!(alarm.active);
+        assert_eq!(alarm.channel, Some(1));
+
+        let input = notification("tns1:Device/tnsaxis:IO/Port", serde_json::json!({ "port": "2" }), serde_json::json!({ "state": "0" }));
+        let alarm = parse_notification(&input).unwrap();
+        assert_eq!(alarm.kind, "io");
+        assert!(!alarm.active);
+        assert_eq!(alarm.channel, Some(2));
+
+        // Stateless topics are pulses
+        let crossing = notification("tnsaxis:CameraApplicationPlatform/CrossLineDetection/xinternal_data", serde_json::json!({}), serde_json::json!({}));
+        assert!(parse_notification(&crossing).unwrap().active);
+
+        let housekeeping = notification("tns1:VideoSource/tnsaxis:LiveStreamAccessed", serde_json::json!({}), serde_json::json!({ "accessed": "1" }));
+        assert!(parse_notification(&housekeeping).is_none());
+        assert!(parse_notification(r#"{"apiVersion":"1.0","method":"events:configure","data":{}}"#).is_none());
+        assert!(parse_notification("not json").is_none());
+    }
+
+    #[test]
+    fn discovery_replies_are_recognised() {
+        let reply = "HTTP/1.1 200 OK\r\nST: urn:axis-com:service:BasicService:1\r\n\
+                     USN: uuid:Upnp-BasicDevice-1_0-ACCC8E123456::urn:axis-com:service:BasicService:1\r\n\r\n";
+        assert!(is_axis_ssdp_reply(reply));
+        assert_eq!(ssdp_mac(reply).as_deref(), Some("ac:cc:8e:12:34:56"));
+        assert_eq!(ssdp_mac("HTTP/1.1 200 OK\r\n\r\n"), None);
+
+        let mut answer = mdns_query();
+        answer[2] |= 0x80;
+        assert!(is_axis_mdns_reply(&answer));
+        // The query itself isn't a reply
+        assert!(!is_axis_mdns_reply(&mdns_query()));
+    }
+}
@@ -0,0 +1,305 @@
+use crate::camera_plugin::{CameraAlarm, CameraInfo, CameraPlugin, PtzDirection};
+use crate::models::{Camera, ProbedDevice};
+use crate::plugins::http_auth::{base_url, key_values, send, send_text};
+use async_trait::async_trait;
+use futures::StreamExt;
+use reqwest::{Client, Method};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+const DEFAULT_RTSP_PORT: u16 = 554;
+// PTZ speed (1..8) used for direction moves
+const PTZ_SPEED: u32 = 5;
+// Heartbeat interval requested from eventManager; a connection silent for much longer is dropped
+const EVENT_HEARTBEAT_SECS: u64 = 5;
+const EVENT_STREAM_IDLE_SECS: u64 = 30;
+
+/// Dahua/Amcrest camera plugin using the CGI API (configManager, eventManager, ptz.cgi).
+/// Cameras are added by address (see `probe`); `port` is the camera's HTTP port.
+pub struct DahuaPlugin {
+    http: crate::onvif::OnvifHttpClient,
+    // ptz.cgi stops a move by its code, so remember the last one per camera
+    ptz_codes: Mutex<HashMap<i32, &'static str>>,
+}
+
+impl DahuaPlugin {
+    pub fn new(http: crate::onvif::OnvifHttpClient) -> Self {
+        DahuaPlugin { http, ptz_codes: Mutex::new(HashMap::new()) }
+    }
+}
+
+fn credentials(camera: &Camera) -> (String, String) {
+    (camera.user.clone().unwrap_or_default(), camera.pass.clone().unwrap_or_default())
+}
+
+fn cgi_url(camera: &Camera, path: &str) -> String {
+    format!("{}{}", base_url(&camera.host, camera.port as u16), path)
+}
+
+// Channel number from a stream path like "/cam/realmonitor?channel=2&subtype=0"
+fn channel(camera: &Camera) -> u32 {
+    camera.stream_path.as_deref()
+        .and_then(|path| path.split(['?', '&']).find_map(|param| param.strip_prefix("channel=")))
+        .and_then(|id| id.parse::<u32>().ok())
+        .map(|id| id.max(1))
+        .unwrap_or(1)
+}
+
+async fn get(client: &Client, camera: &Camera, path: &str) -> Result<String, String> {
+    let (user, pass) = credentials(camera);
+    send_text(client, Method::GET, &cgi_url(camera, path), &user, &pass, None).await
+}
+
+/// Query a camera by address (Dahua's DHDiscover isn't supported) and describe it for adding;
+/// `xaddr` is the CGI root
+pub async fn probe(client: &Client, host: &str, port: u16, user: &str, pass: &str) -> Result<ProbedDevice, String> {
+    let base = base_url(host, port);
+    println!("[DahuaPlugin] Probing {}", base);
+
+    let query = |action: &'static str| {
+        let url = format!("{}/cgi-bin/magicBox.cgi?action={}", base, action);
+        async move { send_text(client, Method::GET, &url, user, pass, None).await.map(|text| key_values(&text)) }
+    };
+
+    let info = query("getSystemInfo").await?;
+    let field = |values: &HashMap<String, String>, name: &str| values.get(name).cloned().unwrap_or_default();
+    // Optional details; older firmwares lack some of these actions
+    let name = query("getMachineName").await.map(|v| field(&v, "name")).unwrap_or_default();
+    let vendor = query("getVendor").await.map(|v| field(&v, "vendor")).unwrap_or_default();
+    let firmware = query("getSoftwareVersion").await.map(|v| field(&v, "version")).unwrap_or_default();
+
+    let has_ptz = send_text(client, Method::GET, &format!("{}/cgi-bin/ptz.cgi?action=getCurrentProtocolCaps&channel=1", base), user, pass, None)
+        .await
+        .is_ok();
+
+    let manufacturer = if vendor.is_empty() { "Dahua".to_string() } else { vendor };
+    let model = field(&info, "deviceType");
+    Ok(ProbedDevice {
+        name: if name.is_empty() { format!("{} {}", manufacturer, model) } else { name },
+        host: host.trim().to_string(),
+        port,
+        camera_type: "dahua".to_string(),
+        user: (!user.is_empty()).then(|| user.to_string()),
+        pass: (!pass.is_empty()).then(|| pass.to_string()),
+        xaddr: format!("{}/cgi-bin", base),
+        manufacturer,
+        model,
+        firmware_version: firmware,
+        serial_number: field(&info, "serialNumber"),
+        hardware_id: field(&info, "hardwareVersion"),
+        has_ptz,
+        channels: Vec::new(),
+    })
+}
+
+/// RTSP URL of the camera's stream (main stream of channel 1 unless stream_path says otherwise)
+pub async fn stream_url(client: &Client, camera: &Camera) -> Result<String, String> {
+    // The RTSP port is configurable; fall back to the default if the camera won't say
+    let rtsp_port = match get(client, camera, "/cgi-bin/configManager.cgi?action=getConfig&name=RTSP").await {
+        Ok(text) => key_values(&text).get("table.RTSP.Port").and_then(|port| port.parse().ok()).unwrap_or(DEFAULT_RTSP_PORT),
+        Err(e) => {
+            println!("[DahuaPlugin] Couldn't read the RTSP port of {} ({}), using {}", camera.name, e, DEFAULT_RTSP_PORT);
+            DEFAULT_RTSP_PORT
+        }
+    };
+
+    let path = camera.stream_path.clone()
+        .filter(|path| !path.is_empty())
+        .unwrap_or_else(|| format!("/cam/realmonitor?channel={}&subtype=0", channel(camera)));
+    let (user, pass) = credentials(camera);
+    let url = crate::stream_url::rtsp_url(&camera.host, rtsp_port, &path)?;
+    crate::stream_url::with_credentials(&url, &user, &pass)
+}
+
+/// JPEG from the camera's snapshot URL
+pub async fn snapshot(client: &Client, camera: &Camera) -> Result<Vec<u8>, String> {
+    let (user, pass) = credentials(camera);
+    let url = cgi_url(camera, &format!("/cgi-bin/snapshot.cgi?channel={}", channel(camera)));
+    let response = send(client, Method::GET, &url, &user, &pass, None).await?;
+    if !response.status().is_success() {
+        return Err(format!("Snapshot request failed with HTTP {}", response.status()));
+    }
+    Ok(response.bytes().await.map_err(|e| e.to_string())?.to_vec())
+}
+
+// Dahua event codes mapped to the kinds shared by all plugins
+fn alarm_kind(code: &str) -> String {
+    match code {
+        "VideoMotion" | "SmartMotionHuman" | "SmartMotionVehicle" => "motion".to_string(),
+        "CrossLineDetection" => "line_crossing".to_string(),
+        "CrossRegionDetection" | "LeftDetection" => "intrusion".to_string(),
+        "VideoBlind" | "VideoAbnormalDetection" => "tampering".to_string(),
+        "AlarmLocal" => "io".to_string(),
+        "VideoLoss" => "video_loss".to_string(),
+        other => other.to_string(),
+    }
+}
+
+// Complete lines received so far, decoded whole so that characters split between chunks
+// stay intact; the unfinished last line stays in `pending`
+fn take_lines(pending: &mut Vec<u8>) -> Vec<String> {
+    let Some(end) = pending.iter().rposition(|b| *b == b'\n') else { return Vec::new() };
+    let lines = String::from_utf8_lossy(&pending[..=end]).lines().map(str::to_string).collect();
+    pending.drain(..=end);
+    lines
+}
+
+// One event line: "Code=VideoMotion;action=Start;index=0[;data={...]"
+fn parse_event(line: &str) -> Option<CameraAlarm> {
+    let fields: HashMap<&str, &str> = line.trim()
+        .split(';')
+        .filter_map(|field| field.split_once('='))
+        .collect();
+
+    let code = *fields.get("Code")?;
+    let action = fields.get("action").copied().unwrap_or("");
+    // "Pulse" events have no end; they close after the usual quiet period
+    let active = match action {
+        "Start" | "Pulse" => true,
+        "Stop" => false,
+        _ => return None,
+    };
+
+    Some(CameraAlarm {
+        kind: alarm_kind(code),
+        active,
+        // index is zero-based
+        channel: fields.get("index").and_then(|i| i.parse::<u32>().ok()).map(|i| i + 1),
+        details: serde_json::json!({
+            "source": "dahua",
+            "code": code,
+        }),
+    })
+}
+
+#[async_trait]
+impl CameraPlugin for DahuaPlugin {
+    fn plugin_type(&self) -> &str {
+        "dahua"
+    }
+
+    async fn discover(&self) -> Result<Vec<CameraInfo>, String> {
+        // Discovery-less: Dahua/Amcrest cameras are added by address through probe_dahua_device
+        Ok(Vec::new())
+    }
+
+    async fn get_stream_url(&self, camera: &Camera) -> Result<String, String> {
+        stream_url(&self.http.for_camera(camera), camera).await
+    }
+
+    fn supports_ptz(&self) -> bool {
+        true
+    }
+
+    fn supports_alarms(&self) -> bool {
+        true
+    }
+
+    async fn ptz_move(&self, camera: &Camera, direction: PtzDirection, _duration_ms: u32) -> Result<(), String> {
+        let code = match direction {
+            PtzDirection::Up => "Up",
+            PtzDirection::Down => "Down",
+            PtzDirection::Left => "Left",
+            PtzDirection::Right => "Right",
+            PtzDirection::ZoomIn => "ZoomTele",
+            PtzDirection::ZoomOut => "ZoomWide",
+        };
+
+        // A new direction doesn't cancel the previous one on some firmwares
+        let previous = self.ptz_codes.lock().map_err(|e| e.to_string())?.insert(camera.id, code);
+        if let Some(previous) = previous.filter(|previous| *previous != code) {
+            let path = format!("/cgi-bin/ptz.cgi?action=stop&channel={}&code={}&arg1=0&arg2=0&arg3=0", channel(camera), previous);
+            get(&self.http.for_camera(camera), camera, &path).await?;
+        }
+
+        let path = format!("/cgi-bin/ptz.cgi?action=start&channel={}&code={}&arg1=0&arg2={}&arg3=0", channel(camera), code, PTZ_SPEED);
+        get(&self.http.for_camera(camera), camera, &path).await?;
+        Ok(())
+    }
+
+    async fn ptz_stop(&self, camera: &Camera) -> Result<(), String> {
+        let code = self.ptz_codes.lock().map_err(|e| e.to_string())?.remove(&camera.id);
+        let Some(code) = code else { return Ok(()) };
+        let path = format!("/cgi-bin/ptz.cgi?action=stop&channel={}&code={}&arg1=0&arg2=0&arg3=0", channel(camera), code);
+        get(&self.http.for_camera(camera), camera, &path).await?;
+        Ok(())
+    }
+
+    async fn watch_alarms(&self, camera: &Camera, on_alarm: &(dyn Fn(CameraAlarm) + Send + Sync)) -> Result<(), String> {
+        // The shared client's request timeout would cut the endless response off
+        let builder = Client::builder().connect_timeout(Duration::from_secs(10));
+        let client = crate::onvif::camera_tls(builder, camera)?
+            .build()
+            .map_err(|e| e.to_string())?;
+        let (user, pass) = credentials(camera);
+        let url = cgi_url(camera, &format!("/cgi-bin/eventManager.cgi?action=attach&codes=[All]&heartbeat={}", EVENT_HEARTBEAT_SECS));
+
+        let response = send(&client, Method::GET, &url, &user, &pass, None).await?;
+        if !response.status().is_success() {
+            return Err(format!("Event stream request failed with HTTP {}", response.status()));
+        }
+        println!("[DahuaPlugin] Listening for events from {}", camera.name);
+
+        // multipart/x-mixed-replace; each part carries "Code=...;action=...;index=..." lines
+        // (or "Heartbeat"), possibly followed by a multi-line JSON data block
+        let mut body = response.bytes_stream();
+        let mut pending = Vec::new();
+        loop {
+            let chunk = match tokio::time::timeout(Duration::from_secs(EVENT_STREAM_IDLE_SECS), body.next()).await {
+                Ok(Some(chunk)) => chunk.map_err(|e| e.to_string())?,
+                Ok(None) => return Err("Event stream closed by the camera".to_string()),
+                Err(_) => return Err(format!("No data on the event stream for {} seconds", EVENT_STREAM_IDLE_SECS)),
+            };
+            pending.extend_from_slice(&chunk);
+            for line in take_lines(&mut pending) {
+                if line.starts_with("Code=") {
+                    if let Some(alarm) = parse_event(&line) {
+                        on_alarm(alarm);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn event_lines_are_parsed() {
+        let alarm = parse_event("Code=VideoMotion;action=Start;index=0\r\n").unwrap();
+        assert_eq!(alarm.kind, "motion");
+        assert!(alarm.active);
+        assert_eq!(alarm.channel, Some(1));
+
+        let alarm = parse_event("Code=CrossLineDetection;action=Stop;index=2;data={").unwrap();
+        assert_eq!(alarm.kind, "line_crossing");
+        assert!(!alarm.active);
+        assert_eq!(alarm.channel, Some(3));
+
+        assert!(parse_event("Code=VideoMotion;action=Start").unwrap().channel.is_none());
+        assert!(parse_event("Code=VideoMotion;action=Unknown;index=0").is_none());
+        assert!(parse_event("Heartbeat").is_none());
+    }
+
+    #[test]
+    fn lines_split_across_chunks_stay_whole() {
+        let body = "--myboundary\r\nContent-Type: text/plain\r\nContent-Length: 62\r\n\r\n\
+                    Code=AlarmLocal;action=Pulse;index=1;data={\"Name\":\"Tür\"}\r\n\r\n--myboundary\r\n";
+        // A chunk size of 1 splits the "ü"
+        for size in [1, 3, 16, body.len()] {
+            let mut pending = Vec::new();
+            let lines: Vec<String> = body.as_bytes().chunks(size)
+                .flat_map(|chunk| {
+                    pending.extend_from_slice(chunk);
+                    take_lines(&mut pending)
+                })
+                .collect();
+            let events: Vec<&String> = lines.iter().filter(|line| line.starts_with("Code=")).collect();
+            assert_eq!(events, ["Code=AlarmLocal;action=Pulse;index=1;data={\"Name\":\"Tür\"}"], "chunk size {}", size);
+            assert_eq!(parse_event(events[0]).unwrap().kind, "io");
+        }
+    }
+}
@@ -0,0 +1,370 @@
+use crate::camera_plugin::{CameraAlarm, CameraInfo, CameraPlugin, PtzDirection};
+use crate::models::{Camera, ProbedDevice};
+use crate::plugins::http_auth::{base_url, send, send_text};
+use async_trait::async_trait;
+use chrono::{DateTime, FixedOffset, NaiveDateTime, Utc};
+use futures::StreamExt;
+use reqwest::{Client, Method};
+use roxmltree::Document;
+use std::time::Duration;
+
+const XML: &str = "application/xml";
+const DEFAULT_RTSP_PORT: u16 = 554;
+// PTZ speed (-100..100) used for direction moves
+const PTZ_SPEED: i32 = 50;
+// The alert stream sends a heartbeat every few seconds; treat a silent connection as dropped
+const ALERT_STREAM_IDLE_SECS: u64 = 60;
+
+/// Hikvision camera plugin using ISAPI, for firmwares whose ONVIF service is broken or disabled.
+/// Cameras are added by address (see `probe`); `port` is the camera's HTTP port.
+pub struct HikvisionPlugin {
+    http: crate::onvif::OnvifHttpClient,
+}
+
+impl HikvisionPlugin {
+    pub fn new(http: crate::onvif::OnvifHttpClient) -> Self {
+        HikvisionPlugin { http }
+    }
+}
+
+fn credentials(camera: &Camera) -> (String, String) {
+    (camera.user.clone().unwrap_or_default(), camera.pass.clone().unwrap_or_default())
+}
+
+fn isapi_url(camera: &Camera, path: &str) -> String {
+    format!("{}{}", base_url(&camera.host, camera.port as u16), path)
+}
+
+// Channel number from a stream path like "/Streaming/Channels/102" (channel 1, sub stream)
+fn channel(camera: &Camera) -> u32 {
+    camera.stream_path.as_deref()
+        .and_then(|path| path.rsplit('/').next())
+        .and_then(|id| id.parse::<u32>().ok())
+        .map(|id| (id / 100).max(1))
+        .unwrap_or(1)
+}
+
+async fn get(client: &Client, camera: &Camera, path: &str) -> Result<String, String> {
+    let (user, pass) = credentials(camera);
+    send_text(client, Method::GET, &isapi_url(camera, path), &user, &pass, None).await
+}
+
+async fn put(client: &Client, camera: &Camera, path: &str, body: String) -> Result<String, String> {
+    let (user, pass) = credentials(camera);
+    send_text(client, Method::PUT, &isapi_url(camera, path), &user, &pass, Some((XML, body))).await
+}
+
+// Text of the first element with this local name
+fn element_text(xml: &str, name: &str) -> Option<String> {
+    let doc = Document::parse(xml).ok()?;
+    doc.descendants()
+        .find(|n| n.is_element() && n.tag_name().name() == name)
+        .and_then(|n| n.text())
+        .map(|t| t.trim().to_string())
+}
+
+/// Query a camera by address (Hikvision's SADP discovery isn't supported) and describe it
+/// for adding; `xaddr` is the ISAPI root
+pub async fn probe(client: &Client, host: &str, port: u16, user: &str, pass: &str) -> Result<ProbedDevice, String> {
+    let base = base_url(host, port);
+    println!("[HikvisionPlugin] Probing {}", base);
+
+    let info = send_text(client, Method::GET, &format!("{}/ISAPI/System/deviceInfo", base), user, pass, None).await?;
+    let field = |name: &str| element_text(&info, name).unwrap_or_default();
+
+    let has_ptz = send_text(client, Method::GET, &format!("{}/ISAPI/PTZCtrl/channels/1/capabilities", base), user, pass, None)
+        .await
+        .is_ok();
+
+    let name = field("deviceName");
+    Ok(ProbedDevice {
+        name: if name.is_empty() { format!("Hikvision {}", field("model")) } else { name },
+        host: host.trim().to_string(),
+        port,
+        camera_type: "hikvision".to_string(),
+        user: (!user.is_empty()).then(|| user.to_string()),
+        pass: (!pass.is_empty()).then(|| pass.to_string()),
+        xaddr: format!("{}/ISAPI", base),
+        manufacturer: "Hikvision".to_string(),
+        model: field("model"),
+        firmware_version: field("firmwareVersion"),
+        serial_number: field("serialNumber"),
+        hardware_id: field("macAddress"),
+        has_ptz,
+        channels: Vec::new(),
+    })
+}
+
+/// RTSP URL of the camera's stream (main stream of channel 1 unless stream_path says otherwise)
+pub async fn stream_url(client: &Client, camera: &Camera) -> Result<String, String> {
+    // The RTSP port is configurable; fall back to the default if the camera won't say
+    let rtsp_port = match get(client, camera, "/ISAPI/Security/adminAccesses").await {
+        Ok(xml) => rtsp_port(&xml).unwrap_or(DEFAULT_RTSP_PORT),
+        Err(e) => {
+            println!("[HikvisionPlugin] Couldn't read the RTSP port of {} ({}), using {}", camera.name, e, DEFAULT_RTSP_PORT);
+            DEFAULT_RTSP_PORT
+        }
+    };
+
+    let path = camera.stream_path.clone()
+        .filter(|path| !path.is_empty())
+        .unwrap_or_else(|| format!("/Streaming/Channels/{}01", channel(camera)));
+    let (user, pass) = credentials(camera);
+    let url = crate::stream_url::rtsp_url(&camera.host, rtsp_port, &path)?;
+    crate::stream_url::with_credentials(&url, &user, &pass)
+}
+
+fn rtsp_port(xml: &str) -> Option<u16> {
+    let doc = Document::parse(xml).ok()?;
+    doc.descendants()
+        .filter(|n| n.is_element() && n.tag_name().name() == "AdminAccessProtocol")
+        .find(|n| n.children().any(|c| c.tag_name().name() == "protocol" && c.text().is_some_and(|t| t.trim().eq_ignore_ascii_case("rtsp"))))
+        .and_then(|n| n.children().find(|c| c.tag_name().name() == "portNo"))
+        .and_then(|n| n.text())
+        .and_then(|t| t.trim().parse().ok())
+}
+
+/// JPEG from the camera's snapshot URL
+pub async fn snapshot(client: &Client, camera: &Camera) -> Result<Vec<u8>, String> {
+    let (user, pass) = credentials(camera);
+    let url = isapi_url(camera, &format!("/ISAPI/Streaming/channels/{}01/picture", channel(camera)));
+    let response = send(client, Method::GET, &url, &user, &pass, None).await?;
+    if !response.status().is_success() {
+        return Err(format!("Snapshot request failed with HTTP {}", response.status()));
+    }
+    Ok(response.bytes().await.map_err(|e| e.to_string())?.to_vec())
+}
+
+// Hikvision event types mapped to the kinds shared by all plugins
+fn alarm_kind(event_type: &str) -> String {
+    match event_type.to_ascii_lowercase().as_str() {
+        "vmd" => "motion".to_string(),
+        "linedetection" => "line_crossing".to_string(),
+        "fielddetection" | "regionentrance" | "regionexiting" => "intrusion".to_string(),
+        "shelteralarm" | "tamperdetection" => "tampering".to_string(),
+        "io" => "io".to_string(),
+        "videoloss" => "video_loss".to_string(),
+        other => other.to_string(),
+    }
+}
+
+fn parse_alert(xml: &str) -> Option<CameraAlarm> {
+    let doc = Document::parse(xml).ok()?;
+    let alert = doc.root_element();
+    let field = |name: &str| {
+        alert.children()
+            .find(|n| n.is_element() && n.tag_name().name() == name)
+            .and_then(|n| n.text())
+            .map(|t| t.trim().to_string())
+    };
+
+    let event_type = field("eventType")?;
+    let active = field("eventState").is_some_and(|s| s.eq_ignore_ascii_case("active"));
+    // The stream's heartbeat is an inactive "videoloss" alert
+    if !active && event_type.eq_ignore_ascii_case("videoloss") {
+        return None;
+    }
+
+    Some(CameraAlarm {
+        kind: alarm_kind(&event_type),
+        active,
+        channel: field("channelID").or_else(|| field("dynChannelID")).and_then(|c| c.parse().ok()),
+        details: serde_json::json!({
+            "source": "hikvision",
+            "eventType": event_type,
+            "description": field("eventDescription"),
+        }),
+    })
+}
+
+// The alert stream is multipart/mixed; each part holds one <EventNotificationAlert> document.
+// Bytes are decoded a line at a time so that characters split between chunks stay whole.
+#[derive(Default)]
+struct AlertStream {
+    pending: Vec<u8>,
+    buffer: String,
+}
+
+impl AlertStream {
+    // Alert documents completed by `chunk`
+    fn push(&mut self, chunk: &[u8]) -> Vec<String> {
+        const START_TAG: &str = "<EventNotificationAlert";
+        const END_TAG: &str = "</EventNotificationAlert>";
+
+        self.pending.extend_from_slice(chunk);
+        let Some(line_end) = self.pending.iter().rposition(|b| *b == b'\n') else { return Vec::new() };
+        self.buffer.push_str(&String::from_utf8_lossy(&self.pending[..=line_end]));
+        self.pending.drain(..=line_end);
+
+        let mut alerts = Vec::new();
+        while let Some(start) = self.buffer.find(START_TAG) {
+            let Some(end) = self.buffer[start..].find(END_TAG).map(|i| start + i + END_TAG.len()) else { break };
+            alerts.push(self.buffer[start..end].to_string());
+            self.buffer.drain(..end);
+        }
+
+        // Drop boundaries and part headers, keeping a possibly incomplete start tag
+        if !self.buffer.contains(START_TAG) {
+            match self.buffer.rfind('<') {
+                Some(pos) => drop(self.buffer.drain(..pos)),
+                None => self.buffer.clear(),
+            }
+        }
+        alerts
+    }
+}
+
+#[async_trait]
+impl CameraPlugin for HikvisionPlugin {
+    fn plugin_type(&self) -> &str {
+        "hikvision"
+    }
+
+    async fn discover(&self) -> Result<Vec<CameraInfo>, String> {
+        // Discovery-less: Hikvision cameras are added by address through probe_hikvision_device
+        Ok(Vec::new())
+    }
+
+    async fn get_stream_url(&self, camera: &Camera) -> Result<String, String> {
+        stream_url(&self.http.for_camera(camera), camera).await
+    }
+
+    fn supports_ptz(&self) -> bool {
+        true
+    }
+
+    fn supports_time_sync(&self) -> bool {
+        true
+    }
+
+    fn supports_alarms(&self) -> bool {
+        true
+    }
+
+    async fn ptz_move(&self, camera: &Camera, direction: PtzDirection, _duration_ms: u32) -> Result<(), String> {
+        let (pan, tilt, zoom) = match direction {
+            PtzDirection::Up => (0, PTZ_SPEED, 0),
+            PtzDirection::Down => (0, -PTZ_SPEED, 0),
+            PtzDirection::Left => (-PTZ_SPEED, 0, 0),
+            PtzDirection::Right => (PTZ_SPEED, 0, 0),
+            PtzDirection::ZoomIn => (0, 0, PTZ_SPEED),
+            PtzDirection::ZoomOut => (0, 0, -PTZ_SPEED),
+        };
+        let body = format!("<PTZData><pan>{}</pan><tilt>{}</tilt><zoom>{}</zoom></PTZData>", pan, tilt, zoom);
+        put(&self.http.for_camera(camera), camera, &format!("/ISAPI/PTZCtrl/channels/{}/continuous", channel(camera)), body).await?;
+        Ok(())
+    }
+
+    async fn ptz_stop(&self, camera: &Camera) -> Result<(), String> {
+        let body = "<PTZData><pan>0</pan><tilt>0</tilt><zoom>0</zoom></PTZData>".to_string();
+        put(&self.http.for_camera(camera), camera, &format!("/ISAPI/PTZCtrl/channels/{}/continuous", channel(camera)), body).await?;
+        Ok(())
+    }
+
+    async fn get_camera_time(&self, camera: &Camera) -> Result<DateTime<Utc>, String> {
+        let xml = get(&self.http.for_camera(camera), camera, "/ISAPI/System/time").await?;
+        let local_time = element_text(&xml, "localTime").ok_or("No localTime in response")?;
+
+        // Most firmwares include the offset; older ones give local time plus a POSIX zone
+        if let Ok(time) = DateTime::parse_from_rfc3339(&local_time) {
+            return Ok(time.with_timezone(&Utc));
+        }
+        let offset = element_text(&xml, "timeZone").and_then(|tz| crate::posix_tz::utc_offset(&tz))
+            .unwrap_or_else(|| FixedOffset::east_opt(0).unwrap());
+        let naive = NaiveDateTime::parse_from_str(&local_time, "%Y-%m-%dT%H:%M:%S")
+            .map_err(|e| format!("Invalid camera time '{}': {}", local_time, e))?;
+        naive.and_local_timezone(offset)
+            .single()
+            .map(|time| time.with_timezone(&Utc))
+            .ok_or_else(|| format!("Invalid camera time '{}'", local_time))
+    }
+
+    async fn set_camera_time(&self, camera: &Camera, time: DateTime<Utc>) -> Result<(), String> {
+        let client = &self.http.for_camera(camera);
+
+        // Keep the camera's zone and write the time in it
+        let xml = get(client, camera, "/ISAPI/System/time").await?;
+        let time_zone = element_text(&xml, "timeZone").unwrap_or_else(|| "CST+0:00:00".to_string());
+        let offset = crate::posix_tz::utc_offset(&time_zone).unwrap_or_else(|| FixedOffset::east_opt(0).unwrap());
+
+        let body = format!(
+            "<Time><timeMode>manual</timeMode><localTime>{}</localTime><timeZone>{}</timeZone></Time>",
+            time.with_timezone(&offset).format("%Y-%m-%dT%H:%M:%S"),
+            time_zone
+        );
+        put(client, camera, "/ISAPI/System/time", body).await?;
+        Ok(())
+    }
+
+    async fn watch_alarms(&self, camera: &Camera, on_alarm: &(dyn Fn(CameraAlarm) + Send + Sync)) -> Result<(), String> {
+        // The shared client's request timeout would cut the endless response off
+        let builder = Client::builder().connect_timeout(Duration::from_secs(10));
+        let client = crate::onvif::camera_tls(builder, camera)?
+            .build()
+            .map_err(|e| e.to_string())?;
+        let (user, pass) = credentials(camera);
+        let url = isapi_url(camera, "/ISAPI/Event/notification/alertStream");
+
+        let response = send(&client, Method::GET, &url, &user, &pass, None).await?;
+        if !response.status().is_success() {
+            return Err(format!("Alert stream request failed with HTTP {}", response.status()));
+        }
+        println!("[HikvisionPlugin] Listening for events from {}", camera.name);
+
+        let mut body = response.bytes_stream();
+        let mut alerts = AlertStream::default();
+        loop {
+            let chunk = match tokio::time::timeout(Duration::from_secs(ALERT_STREAM_IDLE_SECS), body.next()).await {
+                Ok(Some(chunk)) => chunk.map_err(|e| e.to_string())?,
+                Ok(None) => return Err("Alert stream closed by the camera".to_string()),
+                Err(_) => return Err(format!("No data on the alert stream for {} seconds", ALERT_STREAM_IDLE_SECS)),
+            };
+            for alert in alerts.push(&chunk) {
+                if let Some(alarm) = parse_alert(&alert) {
+                    on_alarm(alarm);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn part(event_type: &str, state: &str, description: &str) -> String {
+        let xml = format!(
+            "<EventNotificationAlert version=\"2.0\" xmlns=\"http://www.hikvision.com/ver20/XMLSchema\">\r\n\
+             <channelID>1</channelID>\r\n<eventType>{}</eventType>\r\n<eventState>{}</eventState>\r\n\
+             <eventDescription>{}</eventDescription>\r\n</EventNotificationAlert>\r\n",
+            event_type, state, description
+        );
+        format!("--boundary\r\nContent-Type: application/xml; charset=\"UTF-8\"\r\nContent-Length: {}\r\n\r\n{}", xml.len(), xml)
+    }
+
+    #[test]
+    fn alerts_split_across_chunks_are_reassembled() {
+        let body = [
+            part("VMD", "active", "Bewegung – Einfahrt"),
+            part("videoloss", "inactive", "videoloss alarm"),
+            part("linedetection", "active", "ライン"),
+        ].concat();
+        let body = body.as_bytes();
+        // A chunk size of 1 splits every multibyte character
+        for size in [1, 2, 5, 64, body.len()] {
+            let mut stream = AlertStream::default();
+            let alarms: Vec<CameraAlarm> = body.chunks(size)
+                .flat_map(|chunk| stream.push(chunk))
+                .filter_map(|alert| parse_alert(&alert))
+                .collect();
+
+            // The inactive video loss alert is the heartbeat
+            let kinds: Vec<&str> = alarms.iter().map(|alarm| alarm.kind.as_str()).collect();
+            assert_eq!(kinds, ["motion", "line_crossing"], "chunk size {}", size);
+            assert_eq!(alarms[0].details["description"], "Bewegung – Einfahrt", "chunk size {}", size);
+            assert_eq!(alarms[1].details["description"], "ライン", "chunk size {}", size);
+            assert_eq!(alarms[0].channel, Some(1));
+            assert!(alarms[0].active);
+        }
+    }
+}
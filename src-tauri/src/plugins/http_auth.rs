@@ -0,0 +1,208 @@
+use base64::prelude::*;
+use reqwest::header::{AUTHORIZATION, CONTENT_TYPE, WWW_AUTHENTICATE};
+use reqwest::{Client, Method, Response, StatusCode};
+use sha2::Digest;
+use std::collections::HashMap;
+
+/// Base URL of a camera's HTTP API (https on port 443)
+pub fn base_url(host: &str, port: u16) -> String {
+    let host = host.trim();
+    let scheme = if port == 443 { "https" } else { "http" };
+    if host.contains(':') && !host.starts_with('[') {
+        // Bare IPv6 address
+        format!("{}://[{}]:{}", scheme, host, port)
+    } else {
+        format!("{}://{}:{}", scheme, host, port)
+    }
+}
+
+/// Send a request to a vendor HTTP API, answering a Digest (or Basic) challenge.
+/// The request is sent without credentials first; only a 401 triggers the authenticated retry.
+pub async fn send(
+    client: &Client,
+    method: Method,
+    url: &str,
+    user: &str,
+    pass: &str,
+    body: Option<(&str, String)>,
+) -> Result<Response, String> {
+    let build = |authorization: Option<String>| {
+        let mut request = client.request(method.clone(), url);
+        if let Some((content_type, body)) = &body {
+            request = request.header(CONTENT_TYPE, *content_type).body(body.clone());
+        }
+        if let Some(authorization) = authorization {
+            request = request.header(AUTHORIZATION, authorization);
+        }
+        request
+    };
+
+    let response = build(None).send().await.map_err(|e| e.to_string())?;
+    if response.status() != StatusCode::UNAUTHORIZED || user.is_empty() {
+        return Ok(response);
+    }
+
+    let challenges: Vec<String> = response.headers()
+        .get_all(WWW_AUTHENTICATE)
+        .iter()
+        .filter_map(|value| value.to_str().ok().map(|v| v.to_string()))
+        .collect();
+
+    let authorization = if let Some(challenge) = challenges.iter().find(|c| c.to_ascii_lowercase().starts_with("digest")) {
+        let uri = url::Url::parse(url).map_err(|e| e.to_string())?;
+        let uri = match uri.query() {
+            Some(query) => format!("{}?{}", uri.path(), query),
+            None => uri.path().to_string(),
+        };
+        digest_authorization(challenge, method.as_str(), &uri, user, pass)?
+    } else {
+        format!("Basic {}", BASE64_STANDARD.encode(format!("{}:{}", user, pass)))
+    };
+
+    let response = build(Some(authorization)).send().await.map_err(|e| e.to_string())?;
+    if response.status() == StatusCode::UNAUTHORIZED {
+        return Err("Authentication failed (check user name and password)".to_string());
+    }
+    Ok(response)
+}
+
+/// Send a request and return the body of a successful response as text
+pub async fn send_text(
+    client: &Client,
+    method: Method,
+    url: &str,
+    user: &str,
+    pass: &str,
+    body: Option<(&str, String)>,
+) -> Result<String, String> {
+    let response = send(client, method, url, user, pass, body).await?;
+    let status = response.status();
+    let text = response.text().await.map_err(|e| e.to_string())?;
+    if !status.is_success() {
+        return Err(format!("HTTP {}: {}", status, text.trim()));
+    }
+    Ok(text)
+}
+
+/// "key=value" lines of a CGI response ("table.RTSP.Port=554", "root.Brand.ProdNbr=P3245")
+pub fn key_values(text: &str) -> HashMap<String, String> {
+    text.lines()
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+        .collect()
+}
+
+// key="value" pairs of a WWW-Authenticate challenge
+fn parse_challenge(challenge: &str) -> HashMap<String, String> {
+    let params = challenge.split_once(' ').map(|(_, rest)| rest).unwrap_or("");
+    let mut values = HashMap::new();
+    let mut rest = params.trim();
+
+    while let Some((key, value)) = rest.split_once('=') {
+        let key = key.trim().trim_start_matches(',').trim().to_ascii_lowercase();
+        let value = value.trim_start();
+        let (value, remaining) = if let Some(quoted) = value.strip_prefix('"') {
+            match quoted.find('"') {
+                Some(end) => (&quoted[..end], &quoted[end + 1..]),
+                None => (quoted, ""),
+            }
+        } else {
+            match value.find(',') {
+                Some(end) => (&value[..end], &value[end..]),
+                None => (value, ""),
+            }
+        };
+        values.insert(key, value.trim().to_string());
+        rest = remaining.trim_start_matches(',').trim();
+    }
+
+    values
+}
+
+// RFC 7616 response for MD5 or SHA-256 with qop "auth" (or the legacy RFC 2069 form)
+fn digest_authorization(challenge: &str, method: &str, uri: &str, user: &str, pass: &str) -> Result<String, String> {
+    let cnonce = format!("{:016x}", rand::random::<u64>());
+    digest_response(&parse_challenge(challenge), method, uri, user, pass, &cnonce)
+}
+
+// `digest_authorization` with the client nonce given
+fn digest_response(params: &HashMap<String, String>, method: &str, uri: &str, user: &str, pass: &str, cnonce: &str) -> Result<String, String> {
+    let realm = params.get("realm").cloned().unwrap_or_default();
+    let nonce = params.get("nonce").cloned().ok_or("Digest challenge without a nonce")?;
+    let algorithm = params.get("algorithm").cloned().unwrap_or_else(|| "MD5".to_string());
+
+    let hash = |data: String| -> Result<String, String> {
+        match algorithm.to_ascii_uppercase().as_str() {
+            "MD5" => Ok(format!("{:x}", md5::Md5::digest(data.as_bytes()))),
+            "SHA-256" => Ok(format!("{:x}", sha2::Sha256::digest(data.as_bytes()))),
+            other => Err(format!("Unsupported digest algorithm '{}'", other)),
+        }
+    };
+
+    let ha1 = hash(format!("{}:{}:{}", user, realm, pass))?;
+    let ha2 = hash(format!("{}:{}", method, uri))?;
+    let qop_auth = params.get("qop").is_some_and(|qop| qop.split(',').any(|q| q.trim() == "auth"));
+
+    let mut header = format!(
+        r#"Digest username="{}", realm="{}", nonce="{}", uri="{}", algorithm={}"#,
+        user, realm, nonce, uri, algorithm
+    );
+
+    if qop_auth {
+        let nc = "00000001";
+        let response = hash(format!("{}:{}:{}:{}:auth:{}", ha1, nonce, nc, cnonce, ha2))?;
+        header.push_str(&format!(r#", response="{}", qop=auth, nc={}, cnonce="{}""#, response, nc, cnonce));
+    } else {
+        let response = hash(format!("{}:{}:{}", ha1, nonce, ha2))?;
+        header.push_str(&format!(r#", response="{}""#, response));
+    }
+
+    if let Some(opaque) = params.get("opaque") {
+        header.push_str(&format!(r#", opaque="{}""#, opaque));
+    }
+
+    Ok(header)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn challenge_params_may_be_quoted_or_not() {
+        let params = parse_challenge(r#"Digest realm="IP Camera(C1234)", qop="auth,auth-int", nonce="4e5a=", stale=FALSE, algorithm=MD5"#);
+        assert_eq!(params["realm"], "IP Camera(C1234)");
+        assert_eq!(params["qop"], "auth,auth-int");
+        assert_eq!(params["nonce"], "4e5a=");
+        assert_eq!(params["stale"], "FALSE");
+        assert_eq!(params["algorithm"], "MD5");
+
+        let params = parse_challenge("Digest Realm=cam ,NONCE=\"abc\"");
+        assert_eq!(params["realm"], "cam");
+        assert_eq!(params["nonce"], "abc");
+    }
+
+    // Examples of RFC 2069 and RFC 7616 section 3.9.1
+    #[test]
+    fn digest_responses_match_the_rfc_examples() {
+        let legacy = parse_challenge(r#"Digest realm="testrealm@host.com", nonce="dcd98b7102dd2f0e8b11d0f600bfb0c093", opaque="5ccc069c403ebaf9f0171e9517f40e41""#);
+        let header = digest_response(&legacy, "GET", "/dir/index.html", "Mufasa", "CircleOfLife", "unused").unwrap();
+        assert!(header.contains(r#"response="1949323746fe6a43ef61f9606e7febea""#), "{}", header);
+        assert!(!header.contains("qop="));
+        assert!(header.ends_with(r#"opaque="5ccc069c403ebaf9f0171e9517f40e41""#));
+
+        let challenge = |algorithm: &str| parse_challenge(&format!(
+            r#"Digest realm="http-auth@example.org", qop="auth, auth-int", algorithm={}, nonce="7ypf/xlj9XXwfDPEoM4URrv/xwf94BcCAzFZH4GiTo0v", opaque="FQhe/qaU925kfnzjCev0ciny7QMkPqMAFRtzCUYo5tdS""#,
+            algorithm
+        ));
+        let cnonce = "f2/wE4q74E6zIJEtWaHKaf5wv/H5QzzpXusqGemxURZJ";
+        let md5 = digest_response(&challenge("MD5"), "GET", "/dir/index.html", "Mufasa", "Circle of Life", cnonce).unwrap();
+        assert!(md5.contains(r#"response="8ca523f5e9506fed4657c9700eebdbec", qop=auth, nc=00000001"#), "{}", md5);
+        let sha256 = digest_response(&challenge("SHA-256"), "GET", "/dir/index.html", "Mufasa", "Circle of Life", cnonce).unwrap();
+        assert!(sha256.contains(r#"response="753927fa0e85d155564e2e272a28d1802ca10daf4496794697cf8db5856cb6c1""#), "{}", sha256);
+        assert!(sha256.contains("algorithm=SHA-256"));
+
+        assert!(digest_response(&challenge("SHA-512-256"), "GET", "/", "user", "pass", cnonce).is_err());
+        assert!(digest_authorization(r#"Digest realm="cam""#, "GET", "/", "user", "pass").is_err());
+    }
+}
@@ -1,5 +1,14 @@
 pub mod onvif_plugin;
 pub mod uvc_plugin;
+pub mod http_auth;
+pub mod hikvision_plugin;
+pub mod dahua_plugin;
+pub mod axis_plugin;
+pub mod rpi_camera_plugin;
 
 pub use onvif_plugin::OnvifPlugin;
 pub use uvc_plugin::UvcPlugin;
+pub use hikvision_plugin::HikvisionPlugin;
+pub use dahua_plugin::DahuaPlugin;
+pub use axis_plugin::AxisPlugin;
+pub use rpi_camera_plugin::RpiCameraPlugin;
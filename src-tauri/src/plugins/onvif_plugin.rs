@@ -1,19 +1,33 @@
-use crate::camera_plugin::{CameraInfo, CameraPlugin, PtzDirection};
+use crate::camera_plugin::{CameraInfo, CameraPlugin, DiscoveryScan, PtzDirection};
 use crate::models::Camera;
 use async_trait::async_trait;
 use chrono::Utc;
-use reqwest::Client;
-use std::time::Duration;
+use serde_json::{json, Value};
+use std::sync::RwLock;
 
 // Re-export ONVIF module functions for existing code compatibility
 pub use crate::onvif::*;
 
 /// ONVIF camera plugin implementation
-pub struct OnvifPlugin;
+pub struct OnvifPlugin {
+    http: crate::onvif::OnvifHttpClient,
+    // Subnets to scan instead of the local one (plugin config "subnets")
+    subnets: RwLock<Vec<String>>,
+    // Scan concurrency, timeout, retries and ports (the rest of the plugin config)
+    discovery: RwLock<DiscoveryOptions>,
+}
 
 impl OnvifPlugin {
-    pub fn new() -> Self {
-        OnvifPlugin
+    pub fn new(http: crate::onvif::OnvifHttpClient) -> Self {
+        OnvifPlugin { http, subnets: RwLock::new(Vec::new()), discovery: RwLock::new(DiscoveryOptions::default()) }
+    }
+
+    fn subnets(&self) -> Vec<String> {
+        self.subnets.read().map(|subnets| subnets.clone()).unwrap_or_default()
+    }
+
+    fn discovery_options(&self) -> DiscoveryOptions {
+        self.discovery.read().map(|options| options.clone()).unwrap_or_default()
     }
 }
 
@@ -27,37 +41,111 @@ impl CameraPlugin for OnvifPlugin {
         println!("[OnvifPlugin] Starting ONVIF camera discovery...");
 
         // Use existing ONVIF discovery function
-        let devices = crate::onvif::discover_devices().await?;
+        let devices = crate::onvif::discover_devices(&self.http, &self.subnets(), &self.discovery_options()).await?;
 
         // Convert DiscoveredDevice to CameraInfo
-        let cameras: Vec<CameraInfo> = devices
-            .into_iter()
-            .map(|device| CameraInfo {
-                name: device.name,
-                host: device.address,
-                port: device.port as u16,
-                camera_type: "onvif".to_string(),
-                user: None,
-                pass: None,
-                device_path: None,
-                device_id: None,
-                device_index: None,
-                video_format: None,
-                video_width: None,
-                video_height: None,
-                video_fps: None,
-            })
-            .collect();
+        let cameras: Vec<CameraInfo> = devices.into_iter().map(to_camera_info).collect();
+
+        println!("[OnvifPlugin] Found {} ONVIF camera(s)", cameras.len());
+        Ok(cameras)
+    }
+
+    async fn discover_incremental(&self, scan: &DiscoveryScan) -> Result<Vec<CameraInfo>, String> {
+        println!("[OnvifPlugin] Starting incremental ONVIF camera discovery...");
+
+        let options = self.discovery_options();
+        let devices = crate::onvif::discover_devices_incremental(&self.http, scan.cancel_flag(), &self.subnets(), &options, |device| {
+            scan.report(to_camera_info(device.clone()));
+        })
+        .await?;
+
+        let cameras: Vec<CameraInfo> = devices.into_iter().map(to_camera_info).collect();
 
         println!("[OnvifPlugin] Found {} ONVIF camera(s)", cameras.len());
         Ok(cameras)
     }
 
+    async fn discovery_thumbnail(&self, camera: &CameraInfo) -> Option<Vec<u8>> {
+        let xaddr = camera.xaddr.clone()
+            .unwrap_or_else(|| format!("http://{}:{}/onvif/device_service", camera.host, camera.port));
+        match crate::onvif::anonymous_snapshot(&self.http, &xaddr).await {
+            Ok(jpeg) => Some(jpeg),
+            Err(e) => {
+                println!("[OnvifPlugin] No thumbnail for {}: {}", camera.host, e);
+                None
+            }
+        }
+    }
+
+    fn config_schema(&self) -> Option<Value> {
+        Some(json!({
+            "type": "object",
+            "additionalProperties": false,
+            "properties": {
+                "subnets": {
+                    "type": "array",
+                    "title": "Discovery subnets",
+                    "description": "/24 subnets to scan, e.g. \"192.168.2\" or \"192.168.2.0/24\". Empty scans the local subnet.",
+                    "items": { "type": "string", "pattern": "^\\d{1,3}\\.\\d{1,3}\\.\\d{1,3}(\\.0/24)?$" },
+                    "default": []
+                },
+                "concurrency": {
+                    "type": "integer",
+                    "title": "Concurrent probes",
+                    "description": "Lower it on flaky Wi-Fi, raise it to scan large subnets faster.",
+                    "minimum": 1,
+                    "maximum": 256,
+                    "default": 50
+                },
+                "probeTimeoutMs": {
+                    "type": "integer",
+                    "title": "Probe timeout (ms)",
+                    "minimum": 200,
+                    "maximum": 10000,
+                    "default": 2000
+                },
+                "retries": {
+                    "type": "integer",
+                    "title": "Probe retries",
+                    "description": "Extra attempts for hosts that didn't answer.",
+                    "minimum": 0,
+                    "maximum": 5,
+                    "default": 0
+                },
+                "ports": {
+                    "type": "array",
+                    "title": "Probe ports",
+                    "description": "3702 is WS-Discovery. HTTP ports such as 80, 8080, 8000 or 8899 also find devices that don't answer WS-Discovery, at the cost of a slower scan.",
+                    "items": { "type": "integer", "minimum": 1, "maximum": 65535 },
+                    "default": [3702]
+                }
+            }
+        }))
+    }
+
+    fn validate_config(&self, config: &Value) -> Result<(), String> {
+        crate::plugin_config::validate(&self.config_schema().unwrap_or_default(), config)?;
+        for subnet in config["subnets"].as_array().into_iter().flatten().filter_map(|s| s.as_str()) {
+            let base = subnet.strip_suffix(".0/24").unwrap_or(subnet);
+            if base.split('.').any(|octet| octet.parse::<u8>().is_err()) {
+                return Err(format!("Invalid subnet '{}'", subnet));
+            }
+        }
+        Ok(())
+    }
+
+    fn configure(&self, config: &Value) -> Result<(), String> {
+        let subnets: Vec<String> = serde_json::from_value(config["subnets"].clone()).unwrap_or_default();
+        *self.subnets.write().map_err(|e| e.to_string())? = subnets;
+        *self.discovery.write().map_err(|e| e.to_string())? = DiscoveryOptions::from_config(config);
+        Ok(())
+    }
+
     async fn get_stream_url(&self, camera: &Camera) -> Result<String, String> {
         println!("[OnvifPlugin] Getting stream URL for camera: {}", camera.name);
 
         // Use existing ONVIF stream URL retrieval
-        crate::onvif::get_onvif_stream_url(camera).await
+        crate::onvif::get_onvif_stream_url(&self.http, camera).await
     }
 
     fn supports_ptz(&self) -> bool {
@@ -90,21 +178,21 @@ impl CameraPlugin for OnvifPlugin {
         };
 
         // Use existing ONVIF continuous move function
-        crate::onvif::continuous_move(camera, x, y, zoom).await
+        crate::onvif::continuous_move(&self.http, camera, x, y, zoom).await
     }
 
     async fn ptz_stop(&self, camera: &Camera) -> Result<(), String> {
         println!("[OnvifPlugin] Stopping PTZ movement for camera: {}", camera.name);
 
         // Use existing ONVIF stop function
-        crate::onvif::stop_move(camera).await
+        crate::onvif::stop_move(&self.http, camera).await
     }
 
     async fn get_camera_time(&self, camera: &Camera) -> Result<chrono::DateTime<Utc>, String> {
         println!("[OnvifPlugin] Getting camera time: {}", camera.name);
 
         // Use existing ONVIF get time function
-        let onvif_dt = crate::onvif::get_system_date_time(camera).await?;
+        let onvif_dt = crate::onvif::get_system_date_time(&self.http, camera).await?;
 
         // Convert ONVIFDateTime to chrono::DateTime
         onvif_dt
@@ -126,64 +214,39 @@ impl CameraPlugin for OnvifPlugin {
         let onvif_dt = crate::onvif::ONVIFDateTime::from_chrono(&time);
 
         // Use existing ONVIF set time function
-        crate::onvif::set_system_date_time(camera, &onvif_dt).await
+        crate::onvif::set_system_date_time(&self.http, camera, &onvif_dt).await
     }
 
     async fn get_profiles(&self, camera: &Camera) -> Result<Vec<(String, String)>, String> {
         println!("[OnvifPlugin] Getting profiles for camera: {}", camera.name);
 
-        let xaddr = camera
-            .xaddr
-            .clone()
-            .ok_or("No xAddr available for ONVIF camera")?;
-        let user = camera.user.clone().unwrap_or_default();
-        let pass = camera.pass.clone().unwrap_or_default();
-
-        let client = Client::builder()
-            .timeout(Duration::from_secs(5))
-            .danger_accept_invalid_certs(true)
-            .build()
-            .map_err(|e| e.to_string())?;
-
-        // GetProfiles
-        let profiles_body = r###"<GetProfiles xmlns="http://www.onvif.org/ver10/media/wsdl"/>"###;
-        let profiles_envelope = crate::onvif::build_soap_envelope(&user, &pass, profiles_body);
-
-        let profiles_res = client
-            .post(&xaddr)
-            .header(
-                "Content-Type",
-                "application/soap+xml; charset=utf-8; action=\"http://www.onvif.org/ver10/media/wsdl/GetProfiles\"",
-            )
-            .body(profiles_envelope)
-            .send()
-            .await
-            .map_err(|e| format!("Failed to GetProfiles: {}", e))?;
-
-        let profiles_xml = profiles_res.text().await.map_err(|e| e.to_string())?;
-
-        // Parse all profiles (simplified version - returns token only)
-        // In a full implementation, you would parse profile names and tokens
-        let mut profiles = Vec::new();
-
-        // For now, return a simple result indicating success
-        if let Some(token) = parse_first_profile_token(&profiles_xml) {
-            profiles.push(("MainProfile".to_string(), token));
-        }
-
-        Ok(profiles)
+        // One (name, token) per channel; multichannel devices list several
+        let channels = crate::onvif::get_channels(&self.http, camera).await?;
+        Ok(channels.into_iter().map(|channel| (channel.profile_name, channel.profile_token)).collect())
     }
 }
 
-// Helper function to parse profile token
-fn parse_first_profile_token(xml: &str) -> Option<String> {
-    use regex::Regex;
-
-    let re = Regex::new(r#"(?s)<[^>]*:Profiles[^>]*\stoken="([^"]+)""#).ok()?;
-
-    if let Some(caps) = re.captures(xml) {
-        return Some(caps[1].to_string());
+// Convert a discovered ONVIF device into plugin-neutral camera info
+fn to_camera_info(device: crate::models::DiscoveredDevice) -> CameraInfo {
+    CameraInfo {
+        name: device.name,
+        host: device.address,
+        port: device.port as u16,
+        camera_type: "onvif".to_string(),
+        user: None,
+        pass: None,
+        device_path: None,
+        device_id: None,
+        device_index: None,
+        video_format: None,
+        video_width: None,
+        video_height: None,
+        video_fps: None,
+        xaddr: device.xaddr,
+        mac: device.mac,
+        serial: None,
+        sources: vec!["onvif".to_string()],
+        thumbnail: None,
+        existing_camera_id: None,
     }
-
-    None
 }
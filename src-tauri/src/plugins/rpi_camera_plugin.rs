@@ -0,0 +1,203 @@
+use crate::camera_plugin::{CameraInfo, CameraPlugin};
+use crate::models::{Camera, RpiCameraInfo, RpiCameraMode};
+use async_trait::async_trait;
+use std::process::{Command, Stdio};
+use std::sync::OnceLock;
+
+// Used when the camera has no resolution/frame rate of its own
+const DEFAULT_WIDTH: i32 = 1920;
+const DEFAULT_HEIGHT: i32 = 1080;
+const DEFAULT_FPS: i32 = 30;
+
+/// Raspberry Pi CSI camera plugin. The cameras are driven through libcamera's command line
+/// tools: `rpicam-vid` (`libcamera-vid` before Bookworm) encodes H.264 to stdout, which is
+/// piped into the same FFmpeg pipelines as every other camera.
+pub struct RpiCameraPlugin;
+
+impl RpiCameraPlugin {
+    pub fn new() -> Self {
+        RpiCameraPlugin
+    }
+}
+
+impl Default for RpiCameraPlugin {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Tool name prefix: "rpicam" on current Raspberry Pi OS, "libcamera" on older releases
+fn tool(name: &str) -> String {
+    static PREFIX: OnceLock<&'static str> = OnceLock::new();
+    let prefix = PREFIX.get_or_init(|| {
+        let found = Command::new("rpicam-hello")
+            .arg("--version")
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .is_ok();
+        if found { "rpicam" } else { "libcamera" }
+    });
+    format!("{}-{}", prefix, name)
+}
+
+/// CSI cameras reported by `rpicam-hello --list-cameras`, with their sensor modes
+pub fn list_cameras() -> Result<Vec<RpiCameraInfo>, String> {
+    let output = Command::new(tool("hello"))
+        .arg("--list-cameras")
+        .output()
+        .map_err(|e| format!("libcamera tools not available: {}", e))?;
+
+    // The list goes to stdout on newer releases and stderr on older ones
+    let mut text = String::from_utf8_lossy(&output.stdout).to_string();
+    text.push_str(&String::from_utf8_lossy(&output.stderr));
+    Ok(parse_camera_list(&text))
+}
+
+// 0 : imx219 [3280x2464 10-bit RGGB] (/base/soc/i2c0mux/i2c@1/imx219@10)
+//     Modes: 'SRGGB10_CSI2P' : 640x480 [206.65 fps - (1000, 752)/1280x960 crop]
+//                              1640x1232 [41.85 fps - (0, 0)/3280x2464 crop]
+fn parse_camera_list(text: &str) -> Vec<RpiCameraInfo> {
+    let mut cameras: Vec<RpiCameraInfo> = Vec::new();
+
+    for line in text.lines() {
+        let trimmed = line.trim();
+
+        if let Some((index, rest)) = trimmed.split_once(" : ") {
+            if let Ok(index) = index.trim().parse::<u32>() {
+                let sensor = rest.split_whitespace().next().unwrap_or("").to_string();
+                let path = rest.rsplit_once('(')
+                    .map(|(_, path)| path.trim_end_matches(')').to_string())
+                    .unwrap_or_default();
+                cameras.push(RpiCameraInfo { index, sensor, path, modes: Vec::new() });
+                continue;
+            }
+        }
+
+        // Mode lines: "[Modes: 'FORMAT' : ]WxH [FPS fps - ...]"
+        let Some(camera) = cameras.last_mut() else { continue };
+        let Some((size, rest)) = trimmed.rsplit_once(" [") else { continue };
+        let size = size.rsplit(' ').next().unwrap_or(size);
+        let Some((width, height)) = size.split_once('x') else { continue };
+        let (Ok(width), Ok(height)) = (width.parse::<i32>(), height.parse::<i32>()) else { continue };
+        let max_fps = rest.split_whitespace().next().and_then(|fps| fps.parse::<f64>().ok()).unwrap_or(0.0);
+
+        if !camera.modes.iter().any(|m| m.width == width && m.height == height) {
+            camera.modes.push(RpiCameraMode { width, height, max_fps });
+        }
+    }
+
+    cameras
+}
+
+// Default capture settings: up to 1080p at up to 30 fps, within what the sensor offers
+fn default_mode(camera: &RpiCameraInfo) -> (i32, i32, i32) {
+    if camera.modes.iter().any(|m| m.width >= DEFAULT_WIDTH && m.height >= DEFAULT_HEIGHT) {
+        return (DEFAULT_WIDTH, DEFAULT_HEIGHT, DEFAULT_FPS);
+    }
+    camera.modes.iter()
+        .max_by_key(|m| m.width * m.height)
+        .map(|m| (m.width, m.height, (m.max_fps as i32).clamp(1, DEFAULT_FPS)))
+        .unwrap_or((DEFAULT_WIDTH, DEFAULT_HEIGHT, DEFAULT_FPS))
+}
+
+/// Start `rpicam-vid` for the camera and return its H.264 output for FFmpeg's stdin.
+/// The encoder exits by itself once FFmpeg closes the pipe.
+pub fn start_source(camera: &Camera) -> Result<Stdio, String> {
+    let mut child = Command::new(tool("vid"))
+        .args([
+            "--timeout", "0",
+            "--nopreview",
+            "--camera", &camera.device_index.unwrap_or(0).to_string(),
+            "--width", &camera.video_width.unwrap_or(DEFAULT_WIDTH).to_string(),
+            "--height", &camera.video_height.unwrap_or(DEFAULT_HEIGHT).to_string(),
+            "--framerate", &camera.video_fps.unwrap_or(DEFAULT_FPS).to_string(),
+            "--codec", "h264",
+            "--inline",
+            "--flush",
+            "--output", "-",
+        ])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("Failed to start {}: {}", tool("vid"), e))?;
+
+    let stdout = child.stdout.take().ok_or("No output from the camera encoder")?;
+    // Reap the encoder when it exits so it doesn't linger as a zombie
+    std::thread::spawn(move || {
+        let _ = child.wait();
+    });
+    Ok(Stdio::from(stdout))
+}
+
+/// FFmpeg input arguments reading the encoder output started by `start_source` from stdin
+pub fn input_args(camera: &Camera) -> Vec<String> {
+    vec![
+        // Raw H.264 has no timestamps
+        "-fflags".to_string(), "+genpts".to_string(),
+        "-f".to_string(), "h264".to_string(),
+        "-framerate".to_string(), camera.video_fps.unwrap_or(DEFAULT_FPS).to_string(),
+        "-i".to_string(), "pipe:0".to_string(),
+    ]
+}
+
+#[async_trait]
+impl CameraPlugin for RpiCameraPlugin {
+    fn plugin_type(&self) -> &str {
+        "rpicam"
+    }
+
+    async fn discover(&self) -> Result<Vec<CameraInfo>, String> {
+        #[cfg(target_os = "linux")]
+        {
+            println!("[RpiCameraPlugin] Listing libcamera (CSI) cameras...");
+            // Most machines aren't a Pi; missing libcamera tools just mean no cameras
+            let cameras = match tokio::task::spawn_blocking(list_cameras).await.map_err(|e| e.to_string())? {
+                Ok(cameras) => cameras,
+                Err(e) => {
+                    println!("[RpiCameraPlugin] {}", e);
+                    return Ok(Vec::new());
+                }
+            };
+
+            let found: Vec<CameraInfo> = cameras.iter().map(|camera| {
+                let (width, height, fps) = default_mode(camera);
+                CameraInfo {
+                    name: format!("Raspberry Pi Camera {} ({})", camera.index, camera.sensor),
+                    host: "localhost".to_string(),
+                    port: 0,
+                    camera_type: "rpicam".to_string(),
+                    user: None,
+                    pass: None,
+                    device_path: Some(camera.path.clone()).filter(|path| !path.is_empty()),
+                    device_id: None,
+                    device_index: Some(camera.index),
+                    video_format: Some("h264".to_string()),
+                    video_width: Some(width),
+                    video_height: Some(height),
+                    video_fps: Some(fps),
+                    xaddr: None,
+                    mac: None,
+                    serial: None,
+                    sources: vec!["rpicam".to_string()],
+                    thumbnail: None,
+                    existing_camera_id: None,
+                }
+            }).collect();
+
+            println!("[RpiCameraPlugin] Found {} CSI camera(s)", found.len());
+            Ok(found)
+        }
+
+        #[cfg(not(target_os = "linux"))]
+        {
+            Ok(Vec::new())
+        }
+    }
+
+    async fn get_stream_url(&self, camera: &Camera) -> Result<String, String> {
+        // Not a URL: FFmpeg reads the encoder output from stdin (see start_source)
+        Ok(format!("{}:{}", tool("vid"), camera.device_index.unwrap_or(0)))
+    }
+}
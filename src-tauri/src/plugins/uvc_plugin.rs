@@ -1,5 +1,5 @@
-use crate::camera_plugin::{CameraInfo, CameraPlugin};
-use crate::models::Camera;
+use crate::camera_plugin::{CameraInfo, CameraPlugin, PtzDirection};
+use crate::models::{Camera, PTZCapabilitiesDetails};
 use async_trait::async_trait;
 use std::process::Command;
 
@@ -75,7 +75,35 @@ impl CameraPlugin for UvcPlugin {
     }
 
     fn supports_ptz(&self) -> bool {
-        false // UVC cameras typically don't support PTZ
+        // Through UVC camera controls; whether a webcam has them is checked per camera
+        cfg!(any(target_os = "linux", target_os = "windows"))
+    }
+
+    async fn ptz_capabilities(&self, camera: &Camera) -> Option<PTZCapabilitiesDetails> {
+        let camera = camera.clone();
+        let capabilities = match tokio::task::spawn_blocking(move || uvc_ptz_capabilities(&camera)).await {
+            Ok(Ok(capabilities)) => capabilities,
+            Ok(Err(e)) => {
+                println!("[UvcPlugin] Couldn't read camera controls: {}", e);
+                return None;
+            }
+            Err(_) => return None,
+        };
+        (capabilities.hasPanTilt || capabilities.hasZoom).then_some(capabilities)
+    }
+
+    async fn ptz_move(&self, camera: &Camera, direction: PtzDirection, _duration_ms: u32) -> Result<(), String> {
+        let camera = camera.clone();
+        tokio::task::spawn_blocking(move || uvc_ptz_move(&camera, &direction))
+            .await
+            .map_err(|e| e.to_string())?
+    }
+
+    async fn ptz_stop(&self, camera: &Camera) -> Result<(), String> {
+        let camera = camera.clone();
+        tokio::task::spawn_blocking(move || uvc_ptz_stop(&camera))
+            .await
+            .map_err(|e| e.to_string())?
     }
 
     fn supports_time_sync(&self) -> bool {
@@ -151,6 +179,12 @@ async fn discover_v4l2_cameras() -> Result<Vec<CameraInfo>, String> {
                         video_width,
                         video_height,
                         video_fps,
+                        xaddr: None,
+                        mac: None,
+                        serial: None,
+                        sources: vec!["uvc".to_string()],
+                        thumbnail: None,
+                        existing_camera_id: None,
                     });
 
                     println!("[UvcPlugin] Found v4l2 device: {}", path_str);
@@ -320,6 +354,287 @@ fn get_best_video_format(device_path: &str) -> (Option<String>, Option<i32>, Opt
     (best_format, best_width, best_height, best_fps)
 }
 
+// ============================================================================
+// PTZ through UVC camera controls
+// ============================================================================
+
+// Portion of a control's range covered by one move when the camera only has absolute controls
+#[cfg(any(target_os = "linux", target_os = "windows"))]
+const PTZ_STEP_FRACTION: f64 = 0.1;
+
+// Range and current value of a camera control
+#[cfg(any(target_os = "linux", target_os = "windows"))]
+#[derive(Debug, Clone, Copy)]
+struct ControlRange {
+    min: i64,
+    max: i64,
+    step: i64,
+    value: i64,
+}
+
+#[cfg(any(target_os = "linux", target_os = "windows"))]
+impl ControlRange {
+    // Value one move away in the direction of `sign`, aligned to the control's step
+    fn stepped(&self, sign: i64) -> i64 {
+        let unit = self.step.max(1);
+        let step = ((((self.max - self.min) as f64 * PTZ_STEP_FRACTION) as i64) / unit).max(1) * unit;
+        (self.value + sign * step).clamp(self.min, self.max)
+    }
+}
+
+// Control axis and sign of a move. UVC: positive pan turns right, positive tilt up.
+#[cfg(any(target_os = "linux", target_os = "windows"))]
+fn ptz_axis(direction: &PtzDirection) -> (&'static str, i64) {
+    match direction {
+        PtzDirection::Up => ("tilt", 1),
+        PtzDirection::Down => ("tilt", -1),
+        PtzDirection::Left => ("pan", -1),
+        PtzDirection::Right => ("pan", 1),
+        PtzDirection::ZoomIn => ("zoom", 1),
+        PtzDirection::ZoomOut => ("zoom", -1),
+    }
+}
+
+// Linux: v4l2-ctl. Relative controls (pan_speed, tilt_speed, zoom_continuous) move until
+// stopped; cameras with only absolute controls move one step per command.
+
+#[cfg(target_os = "linux")]
+fn device_path(camera: &Camera) -> Result<&str, String> {
+    camera.device_path.as_deref().ok_or_else(|| "No device path for UVC camera".to_string())
+}
+
+#[cfg(target_os = "linux")]
+fn v4l2_controls(device_path: &str) -> Result<std::collections::HashMap<String, ControlRange>, String> {
+    let output = Command::new("v4l2-ctl")
+        .args(["-d", device_path, "--list-ctrls"])
+        .output()
+        .map_err(|e| format!("v4l2-ctl not available: {}", e))?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    Ok(parse_v4l2_controls(&String::from_utf8_lossy(&output.stdout)))
+}
+
+//                    pan_absolute 0x009a0908 (int)    : min=-36000 max=36000 step=3600 default=0 value=0
+#[cfg(target_os = "linux")]
+fn parse_v4l2_controls(text: &str) -> std::collections::HashMap<String, ControlRange> {
+    let mut controls = std::collections::HashMap::new();
+
+    for line in text.lines() {
+        let Some((head, values)) = line.split_once(':') else { continue };
+        let Some(name) = head.split_whitespace().next() else { continue };
+
+        let field = |key: &str| {
+            values.split_whitespace()
+                .find_map(|pair| pair.strip_prefix(key)?.strip_prefix('='))
+                .and_then(|value| value.parse::<i64>().ok())
+        };
+        let (Some(min), Some(max)) = (field("min"), field("max")) else { continue };
+
+        controls.insert(name.to_string(), ControlRange {
+            min,
+            max,
+            step: field("step").unwrap_or(1),
+            value: field("value").unwrap_or(0),
+        });
+    }
+
+    controls
+}
+
+#[cfg(target_os = "linux")]
+fn v4l2_set_control(device_path: &str, name: &str, value: i64) -> Result<(), String> {
+    let output = Command::new("v4l2-ctl")
+        .args(["-d", device_path, &format!("--set-ctrl={}={}", name, value)])
+        .output()
+        .map_err(|e| format!("v4l2-ctl not available: {}", e))?;
+    if !output.status.success() {
+        return Err(format!("Failed to set {}: {}", name, String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+fn speed_control(axis: &str) -> &'static str {
+    match axis {
+        "pan" => "pan_speed",
+        "tilt" => "tilt_speed",
+        _ => "zoom_continuous",
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn uvc_ptz_capabilities(camera: &Camera) -> Result<PTZCapabilitiesDetails, String> {
+    let controls = v4l2_controls(device_path(camera)?)?;
+    let has = |names: &[&str]| names.iter().any(|name| controls.contains_key(*name));
+    Ok(PTZCapabilitiesDetails {
+        hasPanTilt: has(&["pan_absolute", "tilt_absolute", "pan_speed", "tilt_speed"]),
+        hasZoom: has(&["zoom_absolute", "zoom_continuous"]),
+    })
+}
+
+#[cfg(target_os = "linux")]
+fn uvc_ptz_move(camera: &Camera, direction: &PtzDirection) -> Result<(), String> {
+    let device_path = device_path(camera)?;
+    let controls = v4l2_controls(device_path)?;
+    let (axis, sign) = ptz_axis(direction);
+
+    if let Some(range) = controls.get(speed_control(axis)) {
+        let speed = if sign > 0 { range.max } else { range.min };
+        return v4l2_set_control(device_path, speed_control(axis), speed);
+    }
+
+    let absolute = format!("{}_absolute", axis);
+    let range = controls.get(&absolute).ok_or_else(|| format!("The camera has no {} control", axis))?;
+    v4l2_set_control(device_path, &absolute, range.stepped(sign))
+}
+
+#[cfg(target_os = "linux")]
+fn uvc_ptz_stop(camera: &Camera) -> Result<(), String> {
+    let device_path = device_path(camera)?;
+    let controls = v4l2_controls(device_path)?;
+    for axis in ["pan", "tilt", "zoom"] {
+        if controls.contains_key(speed_control(axis)) {
+            v4l2_set_control(device_path, speed_control(axis), 0)?;
+        }
+    }
+    Ok(())
+}
+
+// Windows: IAMCameraControl of the DirectShow capture filter. It only has absolute
+// pan/tilt/zoom, so every command moves one step and stopping is a no-op.
+
+#[cfg(target_os = "windows")]
+mod dshow_controls {
+    use super::ControlRange;
+    use windows::core::{Interface, BSTR, GUID, VARIANT};
+    use windows::Win32::Media::DirectShow::{
+        CameraControlProperty, CameraControl_Flags_Manual, CameraControl_Pan, CameraControl_Tilt,
+        CameraControl_Zoom, IAMCameraControl, IBaseFilter, ICreateDevEnum,
+    };
+    use windows::Win32::System::Com::StructuredStorage::IPropertyBag;
+    use windows::Win32::System::Com::{
+        CoCreateInstance, CoInitializeEx, CoUninitialize, IEnumMoniker, IMoniker, CLSCTX_INPROC_SERVER,
+        COINIT_MULTITHREADED,
+    };
+
+    // Declared in the SDK's uuids.h; the windows crate only exports them with Media Foundation
+    const CLSID_SYSTEM_DEVICE_ENUM: GUID = GUID::from_u128(0x62be5d10_60eb_11d0_bd3b_00a0c911ce86);
+    const CLSID_VIDEO_INPUT_DEVICE_CATEGORY: GUID = GUID::from_u128(0x860bb310_5d01_11d0_bd3b_00a0c911ce86);
+
+    // Camera control interface of the video input device with the given DirectShow name
+    unsafe fn camera_control(name: &str) -> Result<IAMCameraControl, String> {
+        let dev_enum: ICreateDevEnum = CoCreateInstance(&CLSID_SYSTEM_DEVICE_ENUM, None, CLSCTX_INPROC_SERVER)
+            .map_err(|e| e.to_string())?;
+        let mut monikers: Option<IEnumMoniker> = None;
+        dev_enum.CreateClassEnumerator(&CLSID_VIDEO_INPUT_DEVICE_CATEGORY, &mut monikers, 0)
+            .map_err(|e| e.to_string())?;
+        let monikers = monikers.ok_or("No video input devices")?;
+
+        loop {
+            let mut moniker: [Option<IMoniker>; 1] = [None];
+            if monikers.Next(&mut moniker, None).is_err() {
+                break;
+            }
+            let Some(moniker) = moniker[0].take() else { break };
+
+            let Ok(bag) = moniker.BindToStorage::<_, _, IPropertyBag>(None, None) else { continue };
+            let mut friendly_name = VARIANT::default();
+            if bag.Read(windows::core::w!("FriendlyName"), &mut friendly_name, None).is_err() {
+                continue;
+            }
+            if BSTR::try_from(&friendly_name).map(|n| n.to_string()).unwrap_or_default() != name {
+                continue;
+            }
+
+            let filter: IBaseFilter = moniker.BindToObject(None, None).map_err(|e| e.to_string())?;
+            return filter.cast::<IAMCameraControl>().map_err(|_| "The camera has no camera controls".to_string());
+        }
+        Err(format!("Video device '{}' not found", name))
+    }
+
+    fn with_control<T>(name: &str, f: impl FnOnce(&IAMCameraControl) -> Result<T, String>) -> Result<T, String> {
+        unsafe {
+            CoInitializeEx(None, COINIT_MULTITHREADED).ok().map_err(|e| e.to_string())?;
+            let result = camera_control(name).and_then(|control| f(&control));
+            CoUninitialize();
+            result
+        }
+    }
+
+    fn property(axis: &str) -> CameraControlProperty {
+        match axis {
+            "pan" => CameraControl_Pan,
+            "tilt" => CameraControl_Tilt,
+            _ => CameraControl_Zoom,
+        }
+    }
+
+    /// Range and value of the pan, tilt and zoom controls (None for the ones the camera lacks)
+    pub fn ranges(name: &str) -> Result<[Option<ControlRange>; 3], String> {
+        with_control(name, |control| {
+            Ok(["pan", "tilt", "zoom"].map(|axis| unsafe {
+                let (mut min, mut max, mut step, mut default, mut flags, mut value) = (0, 0, 0, 0, 0, 0);
+                control.GetRange(property(axis).0, &mut min, &mut max, &mut step, &mut default, &mut flags).ok()?;
+                control.Get(property(axis).0, &mut value, &mut flags).ok()?;
+                Some(ControlRange { min: min.into(), max: max.into(), step: step.into(), value: value.into() })
+            }))
+        })
+    }
+
+    pub fn set(name: &str, axis: &str, value: i64) -> Result<(), String> {
+        let value = i32::try_from(value).map_err(|e| e.to_string())?;
+        with_control(name, |control| unsafe {
+            control.Set(property(axis).0, value, CameraControl_Flags_Manual.0).map_err(|e| e.to_string())
+        })
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn device_name(camera: &Camera) -> Result<&str, String> {
+    camera.device_id.as_deref().ok_or_else(|| "No device ID for UVC camera".to_string())
+}
+
+#[cfg(target_os = "windows")]
+fn uvc_ptz_capabilities(camera: &Camera) -> Result<PTZCapabilitiesDetails, String> {
+    let [pan, tilt, zoom] = dshow_controls::ranges(device_name(camera)?)?;
+    Ok(PTZCapabilitiesDetails { hasPanTilt: pan.is_some() || tilt.is_some(), hasZoom: zoom.is_some() })
+}
+
+#[cfg(target_os = "windows")]
+fn uvc_ptz_move(camera: &Camera, direction: &PtzDirection) -> Result<(), String> {
+    let name = device_name(camera)?;
+    let [pan, tilt, zoom] = dshow_controls::ranges(name)?;
+    let (axis, sign) = ptz_axis(direction);
+    let range = match axis {
+        "pan" => pan,
+        "tilt" => tilt,
+        _ => zoom,
+    };
+    let range = range.ok_or_else(|| format!("The camera has no {} control", axis))?;
+    dshow_controls::set(name, axis, range.stepped(sign))
+}
+
+#[cfg(target_os = "windows")]
+fn uvc_ptz_stop(_camera: &Camera) -> Result<(), String> {
+    Ok(())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "windows")))]
+fn uvc_ptz_capabilities(_camera: &Camera) -> Result<PTZCapabilitiesDetails, String> {
+    Err("UVC camera controls not supported on this platform".to_string())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "windows")))]
+fn uvc_ptz_move(_camera: &Camera, _direction: &PtzDirection) -> Result<(), String> {
+    Err("UVC camera controls not supported on this platform".to_string())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "windows")))]
+fn uvc_ptz_stop(_camera: &Camera) -> Result<(), String> {
+    Err("UVC camera controls not supported on this platform".to_string())
+}
+
 // ============================================================================
 // Windows DirectShow Discovery
 // ============================================================================
@@ -373,6 +688,12 @@ async fn discover_directshow_cameras() -> Result<Vec<CameraInfo>, String> {
                     video_width: None,
                     video_height: None,
                     video_fps: None,
+                    xaddr: None,
+                    mac: None,
+                    serial: None,
+                    sources: vec!["uvc".to_string()],
+                    thumbnail: None,
+                    existing_camera_id: None,
                 });
 
                 println!("[UvcPlugin] Found DirectShow device: {}", device_name);
@@ -451,6 +772,12 @@ async fn discover_avfoundation_cameras() -> Result<Vec<CameraInfo>, String> {
                     video_width: None,
                     video_height: None,
                     video_fps: None,
+                    xaddr: None,
+                    mac: None,
+                    serial: None,
+                    sources: vec!["uvc".to_string()],
+                    thumbnail: None,
+                    existing_camera_id: None,
                 });
 
                 println!("[UvcPlugin] Found AVFoundation device [{}]", device_index);
@@ -0,0 +1,172 @@
+use chrono::{DateTime, Datelike, Duration, FixedOffset, Offset, TimeZone, Timelike, Utc};
+use chrono_tz::{OffsetComponents, OffsetName, Tz, TzOffset};
+
+/// POSIX TZ string of an IANA time zone as of `now`'s year, e.g. "EST5EDT,M3.2.0,M11.1.0"
+/// for America/New_York, the form ONVIF cameras take in SetSystemDateAndTime. Zones
+/// without daylight saving time that year get just their offset, e.g. "JST-9".
+pub fn for_zone(tz: Tz, now: DateTime<Utc>) -> String {
+    let year_start = |year| Utc.with_ymd_and_hms(year, 1, 1, 0, 0, 0).unwrap();
+    let transitions = transitions(tz, year_start(now.year()), year_start(now.year() + 1));
+
+    let start = transitions.iter().find(|(_, after)| is_dst(after));
+    let end = transitions.iter().find(|(_, after)| !is_dst(after));
+    match (start, end) {
+        (Some((start_at, dst)), Some((end_at, standard))) => format!(
+            "{}{}{}{},{},{}",
+            name(standard),
+            offset(standard),
+            name(dst),
+            // DST one hour ahead of standard time is implied
+            if dst.fix().local_minus_utc() - standard.fix().local_minus_utc() == 3600 { String::new() } else { offset(dst) },
+            rule(tz, *start_at),
+            rule(tz, *end_at),
+        ),
+        _ => {
+            let current = tz.offset_from_utc_datetime(&now.naive_utc());
+            format!("{}{}", name(&current), offset(&current))
+        }
+    }
+}
+
+/// Whether a POSIX TZ string has daylight saving rules
+pub fn has_dst(posix: &str) -> bool {
+    posix.contains(',')
+}
+
+/// Standard time UTC offset of a POSIX TZ string, e.g. UTC+9 for "JST-9" or "CST-9:00:00"
+pub fn utc_offset(posix: &str) -> Option<FixedOffset> {
+    let spec = match posix.strip_prefix('<') {
+        Some(quoted) => &quoted[quoted.find('>')? + 1..],
+        None => posix.trim_start_matches(|c: char| c.is_ascii_alphabetic()),
+    };
+    // Counted west of Greenwich, so the sign is inverted
+    let (sign, spec) = match spec.strip_prefix('-') {
+        Some(rest) => (1, rest),
+        None => (-1, spec.strip_prefix('+').unwrap_or(spec)),
+    };
+    let clock: String = spec.chars().take_while(|c| c.is_ascii_digit() || *c == ':').collect();
+    let mut parts = clock.split(':');
+    let hours: i32 = parts.next()?.parse().ok()?;
+    let mut part = || parts.next().and_then(|p| p.parse::<i32>().ok()).unwrap_or(0);
+    let seconds = hours * 3600 + part() * 60 + part();
+    FixedOffset::east_opt(sign * seconds)
+}
+
+fn is_dst(offset: &TzOffset) -> bool {
+    offset.dst_offset() != Duration::zero()
+}
+
+// (instant, offset from then on) of every offset change in [from, to)
+fn transitions(tz: Tz, from: DateTime<Utc>, to: DateTime<Utc>) -> Vec<(DateTime<Utc>, TzOffset)> {
+    let offset_at = |time: DateTime<Utc>| tz.offset_from_utc_datetime(&time.naive_utc());
+    let mut found = Vec::new();
+    let mut time = from;
+    let mut current = offset_at(time);
+    while time < to {
+        let next_hour = time + Duration::hours(1);
+        let next = offset_at(next_hour);
+        if next.fix() != current.fix() || is_dst(&next) != is_dst(&current) {
+            // Changes happen on the minute, mostly on the hour
+            let mut at = time + Duration::minutes(1);
+            while offset_at(at).fix() == current.fix() && at < next_hour {
+                at += Duration::minutes(1);
+            }
+            found.push((at, next));
+        }
+        time = next_hour;
+        current = next;
+    }
+    found
+}
+
+// Abbreviation, quoted with <> unless it is plain letters; zones without one are named after
+// their offset, e.g. "<+03>" or "<+0530>"
+fn name(offset: &TzOffset) -> String {
+    match offset.abbreviation() {
+        Some(name) if name.len() >= 3 && name.chars().all(|c| c.is_ascii_alphabetic()) => name.to_string(),
+        Some(name) if !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '+' || c == '-') => {
+            format!("<{}>", name)
+        }
+        _ => {
+            let seconds = offset.fix().local_minus_utc();
+            let (hours, minutes) = (seconds.abs() / 3600, seconds.abs() / 60 % 60);
+            let sign = if seconds < 0 { '-' } else { '+' };
+            if minutes == 0 { format!("<{}{:02}>", sign, hours) } else { format!("<{}{:02}{:02}>", sign, hours, minutes) }
+        }
+    }
+}
+
+// POSIX offsets count west of Greenwich: UTC+9 is "-9"
+fn offset(offset: &TzOffset) -> String {
+    let seconds = offset.fix().local_minus_utc();
+    let sign = if seconds > 0 { "-" } else { "" };
+    format!("{}{}", sign, clock(seconds.abs()))
+}
+
+// "Mm.w.d[/time]" of a transition, in the local time it leaves; week 5 is the last one
+fn rule(tz: Tz, at: DateTime<Utc>) -> String {
+    let before = tz.offset_from_utc_datetime(&(at - Duration::seconds(1)).naive_utc());
+    let local = at.naive_utc() + Duration::seconds(before.fix().local_minus_utc() as i64);
+    let date = local.date();
+    let week = (date.day() - 1) / 7 + 1;
+    let last_week = date.checked_add_signed(Duration::days(7)).is_none_or(|next| next.month() != date.month());
+    let seconds = local.num_seconds_from_midnight() as i32;
+    format!(
+        "M{}.{}.{}{}",
+        date.month(),
+        if last_week { 5 } else { week },
+        date.weekday().num_days_from_sunday(),
+        // 02:00 is the default time
+        if seconds == 2 * 3600 { String::new() } else { format!("/{}", clock(seconds)) },
+    )
+}
+
+// "h", "h:mm" or "h:mm:ss"
+fn clock(seconds: i32) -> String {
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    match (minutes, seconds) {
+        (0, 0) => hours.to_string(),
+        (_, 0) => format!("{}:{:02}", hours, minutes),
+        _ => format!("{}:{:02}:{:02}", hours, minutes, seconds),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn posix(tz: Tz) -> String {
+        for_zone(tz, Utc.with_ymd_and_hms(2026, 6, 1, 0, 0, 0).unwrap())
+    }
+
+    #[test]
+    fn zones_with_daylight_saving_time() {
+        assert_eq!(posix(chrono_tz::America::New_York), "EST5EDT,M3.2.0,M11.1.0");
+        assert_eq!(posix(chrono_tz::Europe::Berlin), "CET-1CEST,M3.5.0,M10.5.0/3");
+        // Southern hemisphere: DST starts late in the year
+        assert_eq!(posix(chrono_tz::Australia::Sydney), "AEST-10AEDT,M10.1.0,M4.1.0/3");
+        assert!(has_dst(&posix(chrono_tz::Europe::London)));
+    }
+
+    #[test]
+    fn zones_without_daylight_saving_time() {
+        assert_eq!(posix(chrono_tz::Asia::Tokyo), "JST-9");
+        assert_eq!(posix(chrono_tz::Asia::Kolkata), "IST-5:30");
+        assert_eq!(posix(chrono_tz::UTC), "UTC0");
+        assert_eq!(posix(chrono_tz::Europe::Istanbul), "<+03>-3");
+        assert!(!has_dst(&posix(chrono_tz::Asia::Tokyo)));
+    }
+
+    #[test]
+    fn offsets_are_read_back() {
+        let east = |seconds| FixedOffset::east_opt(seconds);
+        assert_eq!(utc_offset("JST-9"), east(9 * 3600));
+        assert_eq!(utc_offset("IST-5:30"), east(5 * 3600 + 30 * 60));
+        assert_eq!(utc_offset("<+03>-3"), east(3 * 3600));
+        assert_eq!(utc_offset("EST5EDT,M3.2.0,M11.1.0"), east(-5 * 3600));
+        // Hikvision's form
+        assert_eq!(utc_offset("CST-9:00:00"), east(9 * 3600));
+        assert_eq!(utc_offset("CST+0:00:00"), east(0));
+        assert_eq!(utc_offset("garbage"), None);
+    }
+}
@@ -0,0 +1,322 @@
+use crate::models::CameraPrebuffer;
+use crate::AppState;
+use rusqlite::{Connection, OptionalExtension};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, SystemTime};
+use tauri::{AppHandle, Manager};
+use tokio::io::{AsyncBufReadExt, BufReader};
+
+// Windows-specific imports for hiding console window
+#[cfg(target_os = "windows")]
+use std::os::windows::process::CommandExt;
+
+pub const PREBUFFER_SECS_RANGE: (i32, i32) = (5, 120);
+// Ring segments are cut at the first keyframe after this, so cameras with long GOPs get longer ones
+const SEGMENT_SECS: i32 = 2;
+// Wait before reconnecting after the ring FFmpeg exits (camera offline, ...)
+const RESTART_DELAY_SECS: u64 = 10;
+
+// Pre-recording ring buffer: while enabled for a camera, an FFmpeg copies its stream (no
+// decoding) into a small ring of TS segments. A recording starting on the camera snapshots
+// the ring, and when the recording stops the snapshot is encoded like the recording and put
+// in front of it, so the seconds before a manual, scheduled or motion start are kept.
+
+/// Start (or restart with fresh settings) the camera's ring buffer
+pub fn start(app_handle: &AppHandle, camera_id: i32) {
+    let state = app_handle.state::<AppState>();
+    state.prebuffers.start(camera_id, run_ring(app_handle.clone(), camera_id));
+}
+
+pub fn stop(state: &AppState, camera_id: i32) {
+    if state.prebuffers.stop(camera_id) {
+        println!("[Prebuffer] Stopped the ring buffer of camera {}", camera_id);
+    }
+    let _ = fs::remove_dir_all(ring_dir(camera_id));
+}
+
+/// Start the ring buffers of all cameras that have one configured (app start)
+pub fn start_enabled(app_handle: &AppHandle) -> Result<(), String> {
+    let state = app_handle.state::<AppState>();
+    let conn = crate::db::open(&state.db_path).map_err(|e| e.to_string())?;
+
+    for prebuffer in load_all(&conn)? {
+        start(app_handle, prebuffer.camera_id);
+    }
+    Ok(())
+}
+
+/// Seconds of pre-roll the camera keeps, None when it has no ring buffer
+pub fn load_seconds(conn: &Connection, camera_id: i32) -> Result<Option<i32>, String> {
+    conn.query_row(
+        "SELECT seconds FROM camera_prebuffers WHERE camera_id = ?1",
+        [camera_id],
+        |row| row.get(0),
+    ).optional().map_err(|e| e.to_string())
+}
+
+/// Ring buffers of the cameras that aren't archived
+pub fn load_all(conn: &Connection) -> Result<Vec<CameraPrebuffer>, String> {
+    let mut stmt = conn.prepare(
+        "SELECT camera_id, seconds FROM camera_prebuffers
+         WHERE camera_id IN (SELECT id FROM cameras WHERE archived_at IS NULL)"
+    ).map_err(|e| e.to_string())?;
+
+    let prebuffers = stmt.query_map([], |row| {
+        Ok(CameraPrebuffer { camera_id: row.get(0)?, seconds: row.get(1)? })
+    }).map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    Ok(prebuffers)
+}
+
+// Scratch space outside the stream and recording directories, so neither the stream GC nor
+// the recording checks see the ring files
+fn base_dir() -> PathBuf {
+    std::env::temp_dir().join("tauri-onvif-viewer-prebuffer")
+}
+
+fn ring_dir(camera_id: i32) -> PathBuf {
+    base_dir().join(camera_id.to_string())
+}
+
+fn preroll_dir(recording_id: i64) -> PathBuf {
+    base_dir().join(format!("preroll_{}", recording_id))
+}
+
+async fn run_ring(app_handle: AppHandle, camera_id: i32) {
+    let state = app_handle.state::<AppState>();
+    println!("[Prebuffer] Buffering camera {}", camera_id);
+
+    loop {
+        let seconds = match crate::db::open(&state.db_path).map_err(|e| e.to_string())
+            .and_then(|conn| load_seconds(&conn, camera_id))
+        {
+            Ok(Some(seconds)) => seconds,
+            Ok(None) => break,
+            Err(e) => {
+                eprintln!("[Prebuffer] Failed to load settings of camera {}: {}", camera_id, e);
+                break;
+            }
+        };
+
+        if let Err(e) = record_ring(&state, camera_id, seconds).await {
+            eprintln!("[Prebuffer] Camera {}: {}", camera_id, e);
+            state.pipelines.error(camera_id, &format!("Pre-recording buffer: {}", e));
+        }
+
+        tokio::time::sleep(Duration::from_secs(RESTART_DELAY_SECS)).await;
+    }
+
+    println!("[Prebuffer] Ring buffer of camera {} finished", camera_id);
+}
+
+async fn record_ring(state: &AppState, camera_id: i32, seconds: i32) -> Result<(), String> {
+    let camera = crate::stream::load_camera(&state.db_path, camera_id)?;
+    // A local device can only be opened once, and the stream or recording needs it
+    if crate::stream::is_local_device(&camera.camera_type) {
+        return Err("UVC and Pi cameras can't be buffered".to_string());
+    }
+    let source = crate::stream::get_rtsp_url(&state.onvif_http, &camera).await?;
+
+    let dir = ring_dir(camera_id);
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    // One spare segment for the one being written
+    let wrap = (seconds + SEGMENT_SECS - 1) / SEGMENT_SECS + 1;
+
+    let mut cmd = tokio::process::Command::new("ffmpeg");
+    cmd.args(["-hide_banner", "-nostats", "-loglevel", "error"])
+        .args(crate::stream::network_input_args(&camera, &source))
+        .args(["-i", &source])
+        .args(["-map", "0:v", "-map", "0:a?", "-c", "copy"])
+        .args(["-f", "segment", "-segment_format", "mpegts", "-reset_timestamps", "1"])
+        .args(["-segment_time", &SEGMENT_SECS.to_string(), "-segment_wrap", &wrap.to_string()])
+        .args(state.pipelines.run_marker_args())
+        .arg(dir.join("ring_%03d.ts"))
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+
+    #[cfg(target_os = "windows")]
+    {
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        cmd.creation_flags(CREATE_NO_WINDOW);
+    }
+
+    let mut child = cmd.spawn().map_err(|e| format!("Failed to start ffmpeg: {}", e))?;
+    let _process = state.pipelines.adopt_tokio_process(&child);
+    let stderr = child.stderr.take().ok_or("Failed to capture ffmpeg output")?;
+    let mut lines = BufReader::new(stderr).lines();
+    let mut last_output = String::new();
+    while let Ok(Some(line)) = lines.next_line().await {
+        if !line.trim().is_empty() {
+            last_output = line;
+        }
+    }
+
+    let status = child.wait().await.map_err(|e| e.to_string())?;
+    Err(format!("FFmpeg exited with {}: {}", status, crate::redact::redact(last_output.trim())))
+}
+
+/// Keep the ring's last `seconds` for a recording that just started, before the ring
+/// overwrites them. Returns the number of segments kept.
+pub fn snapshot(conn: &Connection, camera_id: i32, recording_id: i64) -> Result<usize, String> {
+    let Some(seconds) = load_seconds(conn, camera_id)? else { return Ok(0) };
+    let Ok(entries) = fs::read_dir(ring_dir(camera_id)) else { return Ok(0) };
+
+    let since = SystemTime::now() - Duration::from_secs(seconds as u64);
+    let mut segments: Vec<(SystemTime, PathBuf)> = entries
+        .flatten()
+        .filter(|entry| entry.file_name().to_string_lossy().ends_with(".ts"))
+        .filter_map(|entry| Some((entry.metadata().ok()?.modified().ok()?, entry.path())))
+        // Anything written into the window; the newest one is still growing and is copied as is
+        .filter(|(modified, _)| *modified >= since)
+        .collect();
+    segments.sort();
+    if segments.is_empty() {
+        return Ok(0);
+    }
+
+    let target = preroll_dir(recording_id);
+    fs::create_dir_all(&target).map_err(|e| e.to_string())?;
+    for (index, (_, path)) in segments.iter().enumerate() {
+        fs::copy(path, target.join(format!("{:03}.ts", index))).map_err(|e| e.to_string())?;
+    }
+    println!("[Prebuffer] Kept {} pre-roll segment(s) of camera {} for recording {}", segments.len(), camera_id, recording_id);
+    Ok(segments.len())
+}
+
+/// Drop the pre-roll of a recording that is discarded
+pub fn discard(recording_id: i64) {
+    let _ = fs::remove_dir_all(preroll_dir(recording_id));
+}
+
+/// Put the recording's pre-roll in front of `recording`, which keeps its container (TS, MP4
+/// or Matroska). Returns the seconds added; a recording without pre-roll is left alone, and a
+/// failure leaves it as it was.
+pub async fn prepend(db_path: &str, recording_id: i64, recording: &Path) -> f64 {
+    let dir = preroll_dir(recording_id);
+    if !dir.exists() {
+        return 0.0;
+    }
+
+    let result = prepend_into(db_path, recording_id, &dir, recording).await;
+    discard(recording_id);
+    match result {
+        Ok(seconds) => {
+            println!("[Prebuffer] Added {:.1}s of pre-roll to recording {}", seconds, recording_id);
+            seconds
+        }
+        Err(e) => {
+            eprintln!("[Prebuffer] Pre-roll of recording {} not added: {}", recording_id, e);
+            0.0
+        }
+    }
+}
+
+async fn prepend_into(db_path: &str, recording_id: i64, dir: &Path, recording: &Path) -> Result<f64, String> {
+    let mut segments: Vec<PathBuf> = fs::read_dir(dir).map_err(|e| e.to_string())?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "ts"))
+        .collect();
+    segments.sort();
+
+    // The ring is the camera's own stream; encode it like the recording so the two concatenate
+    let (camera, preset) = {
+        let conn = crate::db::open(db_path).map_err(|e| e.to_string())?;
+        let camera_id: i32 = conn.query_row("SELECT camera_id FROM recordings WHERE id = ?1", [recording_id], |row| row.get(0))
+            .map_err(|e| e.to_string())?;
+        (crate::repository::CameraRepo::get(&conn, camera_id)?, crate::encoder::quality_preset_for(&conn, camera_id)?)
+    };
+    let selector = crate::stream::build_encoder_selector_from_path(db_path).await?.with_preset(preset);
+    let encoder = selector.select_encoder_for_recording().await;
+    let preroll = dir.join("preroll.ts");
+    let mut args = concat_input(&dir.join("segments.txt"), &segments)?;
+    args.extend(crate::stream::video_filter_args(&camera));
+    args.extend(encoder.args);
+    args.extend(selector.audio_args(false));
+    args.extend(["-f", "mpegts"].map(String::from));
+    args.push(preroll.to_string_lossy().to_string());
+    run_ffmpeg(&args)?;
+    let seconds = probe_duration(&preroll)?;
+
+    // Concatenating TS with TS avoids mixing bitstream formats of different containers
+    let body = if recording.extension().is_some_and(|ext| ext == "ts") {
+        recording.to_path_buf()
+    } else {
+        let body = dir.join("body.ts");
+        run_ffmpeg(&[
+            "-y".to_string(), "-i".to_string(), recording.to_string_lossy().to_string(),
+            "-map".to_string(), "0".to_string(), "-c".to_string(), "copy".to_string(),
+            "-f".to_string(), "mpegts".to_string(), body.to_string_lossy().to_string(),
+        ])?;
+        body
+    };
+
+    let extension = recording.extension().map(|ext| ext.to_string_lossy().to_string()).unwrap_or_default();
+    let combined = dir.join(format!("combined.{}", extension));
+    let mut args = concat_input(&dir.join("combined.txt"), &[preroll, body])?;
+    args.extend(["-map", "0", "-c", "copy"].map(String::from));
+    match extension.as_str() {
+        "mp4" => args.extend(["-movflags", "+faststart"].map(String::from)),
+        "mkv" => args.extend(["-f", "matroska"].map(String::from)),
+        _ => args.extend(["-f", "mpegts"].map(String::from)),
+    }
+    args.push(combined.to_string_lossy().to_string());
+    run_ffmpeg(&args)?;
+
+    // A rename across file systems fails; fall back to copying
+    if fs::rename(&combined, recording).is_err() {
+        fs::copy(&combined, recording).map_err(|e| e.to_string())?;
+    }
+    Ok(seconds)
+}
+
+// FFmpeg input reading `files` one after the other through a concat list written to `list`
+fn concat_input(list: &Path, files: &[PathBuf]) -> Result<Vec<String>, String> {
+    let entries: String = files.iter()
+        .map(|file| format!("file '{}'\n", file.to_string_lossy().replace('\'', "'\\''")))
+        .collect();
+    fs::write(list, entries).map_err(|e| e.to_string())?;
+    Ok(["-y", "-f", "concat", "-safe", "0", "-i"].iter()
+        .map(|arg| arg.to_string())
+        .chain(std::iter::once(list.to_string_lossy().to_string()))
+        .collect())
+}
+
+fn run_ffmpeg(args: &[String]) -> Result<(), String> {
+    let mut cmd = Command::new("ffmpeg");
+    cmd.args(["-hide_banner", "-loglevel", "error"]).args(args);
+
+    // Hide console window on Windows
+    #[cfg(target_os = "windows")]
+    {
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        cmd.creation_flags(CREATE_NO_WINDOW);
+    }
+
+    let output = cmd.output().map_err(|e| format!("Failed to run ffmpeg: {}", e))?;
+    if !output.status.success() {
+        return Err(format!("FFmpeg failed: {}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(())
+}
+
+fn probe_duration(path: &Path) -> Result<f64, String> {
+    let mut cmd = Command::new("ffprobe");
+    cmd.args(["-v", "error", "-show_entries", "format=duration", "-of", "csv=p=0"]).arg(path);
+
+    // Hide console window on Windows
+    #[cfg(target_os = "windows")]
+    {
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        cmd.creation_flags(CREATE_NO_WINDOW);
+    }
+
+    let output = cmd.output().map_err(|e| format!("Failed to run ffprobe: {}", e))?;
+    String::from_utf8_lossy(&output.stdout).trim().parse::<f64>()
+        .map_err(|_| "Can't read the pre-roll duration".to_string())
+}
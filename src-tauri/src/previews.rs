@@ -0,0 +1,161 @@
+use chrono::{DateTime, Utc};
+use rusqlite::Connection;
+use crate::ffmpeg::{CommandBuilder, InputSource};
+use std::path::Path;
+
+/// Formats of animated previews; WebP is smaller, GIF plays everywhere
+pub const PREVIEW_FORMATS: [&str; 2] = ["webp", "gif"];
+
+const PREVIEW_SECS: f64 = 3.0;
+const PREVIEW_WIDTH: u32 = 320;
+const PREVIEW_FPS: u32 = 10;
+// Event previews start this long before the event so the motion is seen coming in
+const EVENT_LEAD_SECS: f64 = 1.0;
+
+/// Create a looping preview of `PREVIEW_SECS` seconds from `offset_secs` into a recording, in
+/// the thumbnails directory (served under /recordings/thumbnails/). Falls back to GIF when
+/// FFmpeg can't encode WebP. Stores and returns the preview's name.
+pub fn create_preview(db_path: &str, recording_dir: &Path, recording_id: i32, offset_secs: f64, format: &str) -> Result<String, String> {
+    let conn = crate::db::open(db_path).map_err(|e| e.to_string())?;
+    let preview = render(&conn, recording_dir, recording_id, offset_secs, format)?;
+    conn.execute(
+        "UPDATE recordings SET preview = ?1 WHERE id = ?2",
+        rusqlite::params![preview, recording_id],
+    ).map_err(|e| e.to_string())?;
+    Ok(preview)
+}
+
+/// Create previews of the motion events a finished recording covers that have none yet;
+/// returns how many were created
+pub fn create_event_previews(db_path: &str, recording_dir: &Path, recording_id: i32) -> Result<usize, String> {
+    let conn = crate::db::open(db_path).map_err(|e| e.to_string())?;
+    let (camera_id, start, end): (i32, String, Option<String>) = conn.query_row(
+        "SELECT camera_id, start_time, end_time FROM recordings WHERE id = ?1 AND is_finished = 1 AND failure IS NULL",
+        [recording_id],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+    ).map_err(|e| e.to_string())?;
+    let Some(end) = end else { return Ok(0) };
+    let recording_start = parse_time(&start)?;
+
+    let events: Vec<(i64, String)> = {
+        let mut stmt = conn.prepare(
+            "SELECT id, start_time FROM events
+             WHERE camera_id = ?1 AND event_type = 'motion' AND preview IS NULL AND start_time >= ?2 AND start_time < ?3
+             ORDER BY start_time"
+        ).map_err(|e| e.to_string())?;
+        let rows = stmt.query_map(rusqlite::params![camera_id, start, end], |row| Ok((row.get(0)?, row.get(1)?)))
+            .map_err(|e| e.to_string())?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())?;
+        rows
+    };
+
+    let mut created = 0;
+    for (event_id, event_start) in events {
+        let offset = (parse_time(&event_start)? - recording_start).num_milliseconds() as f64 / 1000.0 - EVENT_LEAD_SECS;
+        match render(&conn, recording_dir, recording_id, offset.max(0.0), PREVIEW_FORMATS[0]) {
+            Ok(preview) => {
+                conn.execute("UPDATE events SET preview = ?1 WHERE id = ?2", rusqlite::params![preview, event_id])
+                    .map_err(|e| e.to_string())?;
+                created += 1;
+            }
+            Err(e) => eprintln!("[Preview] Preview of event {} failed: {}", event_id, e),
+        }
+    }
+    Ok(created)
+}
+
+// Write the preview unless it exists already; returns its name in the thumbnails directory
+fn render(conn: &Connection, recording_dir: &Path, recording_id: i32, offset_secs: f64, format: &str) -> Result<String, String> {
+    if !PREVIEW_FORMATS.contains(&format) {
+        return Err(format!("Unknown preview format '{}' (expected one of {})", format, PREVIEW_FORMATS.join(", ")));
+    }
+    if !offset_secs.is_finite() || offset_secs < 0.0 {
+        return Err("Preview offset must be zero or more seconds".to_string());
+    }
+    let video_path = crate::storage::recording_path(conn, recording_dir, recording_id)?;
+    let thumbnails_dir = recording_dir.join("thumbnails");
+    std::fs::create_dir_all(&thumbnails_dir).map_err(|e| format!("Failed to create thumbnails directory: {}", e))?;
+
+    let formats = if format == "gif" { vec!["gif"] } else { vec![format, "gif"] };
+    let mut last_error = String::new();
+    for format in formats {
+        let name = preview_name(&video_path, offset_secs, format);
+        let path = thumbnails_dir.join(&name);
+        if path.exists() {
+            return Ok(name);
+        }
+        match run_ffmpeg(&video_path, &path, offset_secs, format) {
+            Ok(()) => {
+                println!("[Preview] Created {} for recording {}", name, recording_id);
+                return Ok(name);
+            }
+            Err(e) => {
+                let _ = std::fs::remove_file(&path);
+                last_error = e;
+            }
+        }
+    }
+    Err(last_error)
+}
+
+// "<recording stem>_preview_<offset ms>.<format>"
+fn preview_name(video_path: &Path, offset_secs: f64, format: &str) -> String {
+    let stem = video_path.file_stem().map(|stem| stem.to_string_lossy().to_string()).unwrap_or_default();
+    format!("{}_preview_{}.{}", stem, (offset_secs * 1000.0).round() as i64, format)
+}
+
+fn run_ffmpeg(video_path: &Path, output_path: &Path, offset_secs: f64, format: &str) -> Result<(), String> {
+    let output = CommandBuilder::new()
+        .seek(offset_secs)
+        .args(["-t".to_string(), PREVIEW_SECS.to_string()])
+        .input(InputSource::File(video_path))
+        .args(encode_args(format))
+        .output(output_path)
+        .command()
+        .output().map_err(|e| format!("Failed to spawn FFmpeg for preview: {}", e))?;
+    if !output.status.success() || !output_path.exists() {
+        return Err(format!("FFmpeg preview generation failed: {}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(())
+}
+
+// Output options of a looping, silent preview
+fn encode_args(format: &str) -> Vec<String> {
+    let scale = format!("fps={},scale={}:-2:flags=lanczos", PREVIEW_FPS, PREVIEW_WIDTH);
+    let mut args = vec!["-an".to_string()];
+    if format == "gif" {
+        // A palette of the clip's own colours instead of the generic 256
+        args.extend(["-vf".to_string(), format!("{},split[a][b];[a]palettegen[p];[b][p]paletteuse", scale)]);
+    } else {
+        args.extend(["-vf".to_string(), scale]);
+        args.extend(["-c:v", "libwebp", "-quality", "60"].map(String::from));
+    }
+    args.extend(["-loop".to_string(), "0".to_string()]);
+    args
+}
+
+fn parse_time(value: &str) -> Result<DateTime<Utc>, String> {
+    DateTime::parse_from_rfc3339(value).map(|t| t.with_timezone(&Utc)).map_err(|e| format!("Invalid time {}: {}", value, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn previews_are_named_after_recording_and_offset() {
+        let video = Path::new("/recordings/camera_1_20240501_100000.mp4");
+        assert_eq!(preview_name(video, 12.5, "webp"), "camera_1_20240501_100000_preview_12500.webp");
+        assert_eq!(preview_name(video, 0.0, "gif"), "camera_1_20240501_100000_preview_0.gif");
+    }
+
+    #[test]
+    fn gif_previews_use_their_own_palette() {
+        let args = encode_args("gif");
+        let filter = &args[args.iter().position(|arg| arg == "-vf").unwrap() + 1];
+        assert!(filter.starts_with("fps=10,scale=320:-2") && filter.ends_with("paletteuse"));
+        assert!(!args.contains(&"libwebp".to_string()));
+        assert!(encode_args("webp").contains(&"libwebp".to_string()));
+    }
+}
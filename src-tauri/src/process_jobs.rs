@@ -0,0 +1,148 @@
+use std::process::Child;
+#[cfg(windows)]
+use std::collections::HashMap;
+#[cfg(windows)]
+use std::os::windows::io::{AsRawHandle, RawHandle};
+#[cfg(windows)]
+use std::sync::{Arc, Mutex};
+
+/// Windows Job Objects of the long-running FFmpeg processes, one per process and keyed by PID.
+/// Killing through the job ends the whole process tree (`Child::kill` only ends FFmpeg
+/// itself), and every job is kill-on-close, so whatever is left dies with the app when
+/// Windows closes its handles. Elsewhere this only forwards to `Child::kill`.
+#[derive(Clone, Default)]
+pub struct ProcessJobs {
+    #[cfg(windows)]
+    jobs: Arc<Mutex<HashMap<u32, job::Job>>>,
+}
+
+impl ProcessJobs {
+    /// Put a freshly spawned process (and the processes it starts) into its own job
+    pub fn adopt(&self, child: &Child) {
+        #[cfg(windows)]
+        self.adopt_handle(child.id(), child.as_raw_handle());
+        #[cfg(not(windows))]
+        let _ = child;
+    }
+
+    /// `adopt` for a process spawned through tokio
+    pub fn adopt_tokio(&self, child: &tokio::process::Child) {
+        #[cfg(windows)]
+        if let (Some(pid), Some(handle)) = (child.id(), child.raw_handle()) {
+            self.adopt_handle(pid, handle);
+        }
+        #[cfg(not(windows))]
+        let _ = child;
+    }
+
+    /// Close the job of a process that isn't killed through `kill`; kill-on-close ends
+    /// whatever is left of its tree
+    pub fn release(&self, pid: u32) {
+        #[cfg(windows)]
+        if let Ok(mut jobs) = self.jobs.lock() {
+            jobs.remove(&pid);
+        }
+        #[cfg(not(windows))]
+        let _ = pid;
+    }
+
+    #[cfg(windows)]
+    fn adopt_handle(&self, pid: u32, handle: RawHandle) {
+        let Ok(mut jobs) = self.jobs.lock() else { return };
+        // Jobs of processes that exited by themselves
+        jobs.retain(|_, job| !job.is_empty());
+
+        match job::Job::new().and_then(|job| job.assign(handle).map(|_| job)) {
+            Ok(job) => {
+                jobs.insert(pid, job);
+            }
+            Err(e) => eprintln!("[Process] Failed to put process {} into a job object: {}", pid, e),
+        }
+    }
+
+    /// Kill the process and everything it started
+    pub fn kill(&self, child: &mut Child) -> std::io::Result<()> {
+        #[cfg(windows)]
+        {
+            let job = self.jobs.lock().ok().and_then(|mut jobs| jobs.remove(&child.id()));
+            if let Some(job) = job {
+                job.terminate();
+            }
+        }
+        child.kill()
+    }
+}
+
+#[cfg(windows)]
+mod job {
+    use std::ffi::c_void;
+    use std::os::windows::io::RawHandle;
+    use windows::core::PCWSTR;
+    use windows::Win32::Foundation::{CloseHandle, HANDLE};
+    use windows::Win32::System::JobObjects::{
+        AssignProcessToJobObject, CreateJobObjectW, JobObjectBasicAccountingInformation,
+        JobObjectExtendedLimitInformation, QueryInformationJobObject, SetInformationJobObject,
+        TerminateJobObject, JOBOBJECT_BASIC_ACCOUNTING_INFORMATION,
+        JOBOBJECT_EXTENDED_LIMIT_INFORMATION, JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE,
+    };
+
+    pub struct Job(HANDLE);
+
+    // A job handle can be used and closed from any thread
+    unsafe impl Send for Job {}
+
+    impl Job {
+        pub fn new() -> Result<Job, String> {
+            unsafe {
+                let job = Job(CreateJobObjectW(None, PCWSTR::null()).map_err(|e| e.to_string())?);
+
+                let mut limits = JOBOBJECT_EXTENDED_LIMIT_INFORMATION::default();
+                limits.BasicLimitInformation.LimitFlags = JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE;
+                SetInformationJobObject(
+                    job.0,
+                    JobObjectExtendedLimitInformation,
+                    &limits as *const _ as *const c_void,
+                    std::mem::size_of::<JOBOBJECT_EXTENDED_LIMIT_INFORMATION>() as u32,
+                ).map_err(|e| e.to_string())?;
+
+                Ok(job)
+            }
+        }
+
+        pub fn assign(&self, process: RawHandle) -> Result<(), String> {
+            unsafe {
+                AssignProcessToJobObject(self.0, HANDLE(process))
+                    .map_err(|e| e.to_string())
+            }
+        }
+
+        pub fn terminate(&self) {
+            unsafe {
+                let _ = TerminateJobObject(self.0, 1);
+            }
+        }
+
+        // No process of the job is running any more
+        pub fn is_empty(&self) -> bool {
+            let mut accounting = JOBOBJECT_BASIC_ACCOUNTING_INFORMATION::default();
+            let queried = unsafe {
+                QueryInformationJobObject(
+                    self.0,
+                    JobObjectBasicAccountingInformation,
+                    &mut accounting as *mut _ as *mut c_void,
+                    std::mem::size_of::<JOBOBJECT_BASIC_ACCOUNTING_INFORMATION>() as u32,
+                    None,
+                )
+            };
+            queried.is_ok() && accounting.ActiveProcesses == 0
+        }
+    }
+
+    impl Drop for Job {
+        fn drop(&mut self) {
+            unsafe {
+                let _ = CloseHandle(self.0);
+            }
+        }
+    }
+}
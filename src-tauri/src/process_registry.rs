@@ -0,0 +1,153 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::{Arc, Mutex};
+
+#[cfg(target_os = "windows")]
+use std::os::windows::process::CommandExt;
+
+// Pipeline FFmpeg processes get "-metadata onvif_viewer_run=<run id>", so a PID from the state
+// file is only killed when it still is one of ours and the OS hasn't reused the number
+const RUN_MARKER_KEY: &str = "onvif_viewer_run";
+
+#[derive(Serialize, Deserialize, Default)]
+struct StateFile {
+    run_id: String,
+    pids: BTreeSet<u32>,
+}
+
+struct Registry {
+    path: PathBuf,
+    state: StateFile,
+}
+
+/// PIDs of the pipeline FFmpeg processes this run spawned, written to a state file so the next
+/// start can clean up after a crash (see `terminate_orphans`)
+#[derive(Clone, Default)]
+pub struct ProcessRegistry {
+    registry: Arc<Mutex<Option<Registry>>>,
+}
+
+impl ProcessRegistry {
+    /// Start a new run recorded in `path`; call `terminate_orphans` on the file first
+    pub fn start(path: PathBuf) -> Self {
+        let registry = Registry {
+            path,
+            state: StateFile { run_id: uuid::Uuid::new_v4().simple().to_string(), pids: BTreeSet::new() },
+        };
+        save(&registry);
+        ProcessRegistry { registry: Arc::new(Mutex::new(Some(registry))) }
+    }
+
+    /// FFmpeg output options tagging a process with this run's id
+    pub fn marker_args(&self) -> Vec<String> {
+        let registry = self.registry.lock().ok();
+        match registry.as_ref().and_then(|registry| registry.as_ref()) {
+            Some(registry) => vec!["-metadata".to_string(), format!("{}={}", RUN_MARKER_KEY, registry.state.run_id)],
+            None => Vec::new(),
+        }
+    }
+
+    pub fn add(&self, pid: u32) {
+        self.update(|pids| {
+            pids.insert(pid);
+        });
+    }
+
+    pub fn remove(&self, pid: u32) {
+        self.update(|pids| {
+            pids.remove(&pid);
+        });
+    }
+
+    fn update<F: FnOnce(&mut BTreeSet<u32>)>(&self, f: F) {
+        if let Ok(mut registry) = self.registry.lock() {
+            if let Some(registry) = registry.as_mut() {
+                f(&mut registry.state.pids);
+                save(registry);
+            }
+        }
+    }
+}
+
+fn save(registry: &Registry) {
+    let result = serde_json::to_string(&registry.state)
+        .map_err(|e| e.to_string())
+        .and_then(|json| std::fs::write(&registry.path, json).map_err(|e| e.to_string()));
+    if let Err(e) = result {
+        eprintln!("[Process] Failed to write {:?}: {}", registry.path, e);
+    }
+}
+
+/// Kill the FFmpeg processes a previous run left behind when it crashed; they would otherwise
+/// keep holding the cameras' RTSP sessions. Must run before any pipeline starts.
+pub fn terminate_orphans(path: &Path) {
+    let Ok(json) = std::fs::read_to_string(path) else { return };
+    let state: StateFile = match serde_json::from_str(&json) {
+        Ok(state) => state,
+        Err(e) => {
+            eprintln!("[Process] Ignoring unreadable {:?}: {}", path, e);
+            return;
+        }
+    };
+
+    let marker = format!("{}={}", RUN_MARKER_KEY, state.run_id);
+    for pid in state.pids {
+        let Some(command_line) = command_line(pid) else { continue };
+        if !command_line.contains(&marker) {
+            continue;
+        }
+
+        println!("[Process] Terminating FFmpeg {} left running by the previous session", pid);
+        if let Err(e) = kill_tree(pid) {
+            eprintln!("[Process] Failed to terminate orphaned FFmpeg {}: {}", pid, e);
+        }
+    }
+}
+
+// Command line of a running process, None if it doesn't exist
+fn command_line(pid: u32) -> Option<String> {
+    #[cfg(unix)]
+    let output = Command::new("ps")
+        .args(["-ww", "-o", "args=", "-p", &pid.to_string()])
+        .output()
+        .ok()?;
+
+    #[cfg(target_os = "windows")]
+    let output = {
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        Command::new("powershell")
+            .args([
+                "-NoProfile",
+                "-Command",
+                &format!("(Get-CimInstance Win32_Process -Filter 'ProcessId={}').CommandLine", pid),
+            ])
+            .creation_flags(CREATE_NO_WINDOW)
+            .output()
+            .ok()?
+    };
+
+    let command_line = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !command_line.is_empty()).then_some(command_line)
+}
+
+fn kill_tree(pid: u32) -> Result<(), String> {
+    #[cfg(unix)]
+    let status = Command::new("kill").args(["-9", &pid.to_string()]).status();
+
+    #[cfg(target_os = "windows")]
+    let status = {
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        Command::new("taskkill")
+            .args(["/PID", &pid.to_string(), "/T", "/F"])
+            .creation_flags(CREATE_NO_WINDOW)
+            .status()
+    };
+
+    match status {
+        Ok(status) if status.success() => Ok(()),
+        Ok(status) => Err(format!("exited with {}", status)),
+        Err(e) => Err(e.to_string()),
+    }
+}
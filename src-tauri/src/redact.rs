@@ -0,0 +1,97 @@
+use regex::Regex;
+use std::collections::VecDeque;
+use std::io::Read;
+use std::sync::{Arc, LazyLock, Mutex};
+
+// Lines of a child's stderr kept for error reports
+const STDERR_TAIL_LINES: usize = 20;
+
+/// The last lines a child wrote to stderr, already redacted
+pub type StderrTail = Arc<Mutex<VecDeque<String>>>;
+
+// Userinfo of any URL in the text ("rtsp://admin:secret@")
+static URL_USERINFO: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"(?i)\b([a-z][a-z0-9+.-]*://)[^/\s@'"]+@"#).unwrap());
+// Credentials passed as query or form parameters ("?user=admin&password=secret")
+static SECRET_PARAMS: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"(?i)\b(password|passwd|pwd|pass|token|secret|wssession)=[^&\s'"]*"#).unwrap());
+
+// HTTP authorization headers, e.g. in custom FFmpeg arguments ("-headers 'Authorization: Basic ...'")
+static AUTHORIZATION_HEADER: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"(?i)\b(authorization:\s*)[^\r\n'"]+"#).unwrap());
+
+/// Text with credentials masked: URL userinfo, password/token parameters and authorization
+/// headers. Anything that may contain a stream URL (URLs, FFmpeg arguments and output) goes
+/// through this before it is logged or returned as an error.
+pub fn redact(text: &str) -> String {
+    let text = URL_USERINFO.replace_all(text, "${1}***@");
+    let text = AUTHORIZATION_HEADER.replace_all(&text, "${1}***");
+    SECRET_PARAMS.replace_all(&text, "${1}=***").into_owned()
+}
+
+// Secrets of a SOAP message: the WS-Security UsernameToken and the users of
+// CreateUsers/SetUser requests
+static SOAP_SECRETS: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?is)(<(?:[\w.-]+:)?(?:Username|Password|Nonce)\b[^>]*>).*?(</(?:[\w.-]+:)?(?:Username|Password|Nonce)>)"#).unwrap()
+});
+
+/// SOAP message with credentials masked: UsernameToken and user elements as well as
+/// everything `redact` covers (e.g. stream URIs in GetStreamUri responses)
+pub fn redact_soap(text: &str) -> String {
+    let text = SOAP_SECRETS.replace_all(text, "${1}***${2}");
+    redact(&text)
+}
+
+/// Copy a child's stderr to ours with credentials masked. FFmpeg prints its input URL when it
+/// opens it and in most connection errors. The returned tail holds the last lines (without
+/// progress updates) so a failure can be reported with its cause.
+pub fn forward_stderr(child: &mut std::process::Child) -> StderrTail {
+    let tail: StderrTail = Arc::new(Mutex::new(VecDeque::new()));
+    let Some(mut stderr) = child.stderr.take() else { return tail };
+
+    let lines = tail.clone();
+    std::thread::spawn(move || {
+        let mut buffer = [0u8; 4096];
+        let mut line = Vec::new();
+        // Progress lines end with '\r', everything else with '\n'
+        while let Ok(len) = stderr.read(&mut buffer) {
+            if len == 0 {
+                break;
+            }
+            for &byte in &buffer[..len] {
+                if byte == b'\n' || byte == b'\r' {
+                    if !line.is_empty() {
+                        let text = redact(&String::from_utf8_lossy(&line));
+                        eprint!("{}{}", text, byte as char);
+                        if byte == b'\n' {
+                            keep_line(&lines, text);
+                        }
+                        line.clear();
+                    }
+                } else {
+                    line.push(byte);
+                }
+            }
+        }
+        if !line.is_empty() {
+            let text = redact(&String::from_utf8_lossy(&line));
+            eprintln!("{}", text);
+            keep_line(&lines, text);
+        }
+    });
+    tail
+}
+
+fn keep_line(tail: &StderrTail, line: String) {
+    if let Ok(mut lines) = tail.lock() {
+        if lines.len() == STDERR_TAIL_LINES {
+            lines.pop_front();
+        }
+        lines.push_back(line);
+    }
+}
+
+/// Snapshot of a stderr tail, oldest line first
+pub fn tail_lines(tail: &StderrTail) -> Vec<String> {
+    tail.lock().map(|lines| lines.iter().cloned().collect()).unwrap_or_default()
+}
@@ -0,0 +1,421 @@
+use crate::events::AppEvent;
+use crate::models::{ActivityReport, CameraActivity, DailyUsage, OfflineCamera, ReportFailure, StorageUsage};
+use crate::settings;
+use crate::AppState;
+use chrono::{DateTime, Datelike, Days, NaiveDate, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use rusqlite::{params, Connection};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+// How often the digest task checks whether a digest is due
+const DIGEST_CHECK_INTERVAL_SECS: u64 = 300;
+const WEBHOOK_TIMEOUT_SECS: u64 = 30;
+// Failures listed in a report; failure_count covers all of them
+const MAX_REPORT_FAILURES: usize = 50;
+// Incidents are only needed for reports, so old ones are dropped
+const INCIDENT_RETENTION_DAYS: u64 = 90;
+
+// Activity reports: recordings made, failures, cameras whose stream died and how fast storage
+// fills up over a time range. Failures and dropped streams only exist as events, so
+// spawn_incident_log keeps them in camera_incidents; the digest task sends the report of the
+// previous day or week over the event bus (app window, WebSocket clients, MQTT) and the
+// optional webhook.
+
+/// Build the report of recordings started in [from, to)
+pub fn generate_report(conn: &Connection, recording_dir: &Path, from: DateTime<Utc>, to: DateTime<Utc>) -> Result<ActivityReport, String> {
+    if to <= from {
+        return Err("Report end must be after its start".to_string());
+    }
+    let from_str = from.to_rfc3339();
+    let to_str = to.to_rfc3339();
+    let tz = settings::timezone();
+
+    // Recordings per camera and bytes per local day
+    let mut activity: HashMap<i32, CameraActivity> = HashMap::new();
+    let mut daily: BTreeMap<NaiveDate, i64> = BTreeMap::new();
+    let mut date = from.with_timezone(&tz).date_naive();
+    while local_midnight(date, tz).is_none_or(|midnight| midnight < to) {
+        daily.insert(date, 0);
+        date = date + Days::new(1);
+    }
+
+    let mut stmt = conn.prepare(
+        "SELECT camera_id, start_time, end_time, COALESCE(file_size, 0) FROM recordings
+         WHERE start_time >= ?1 AND start_time < ?2"
+    ).map_err(|e| e.to_string())?;
+    let rows = stmt.query_map(params![from_str, to_str], |row| {
+        Ok((row.get::<_, i32>(0)?, row.get::<_, String>(1)?, row.get::<_, Option<String>>(2)?, row.get::<_, i64>(3)?))
+    }).map_err(|e| e.to_string())?;
+
+    for row in rows {
+        let (camera_id, start, end, bytes) = row.map_err(|e| e.to_string())?;
+        let Ok(start) = DateTime::parse_from_rfc3339(&start) else { continue };
+        let secs = end
+            .and_then(|end| DateTime::parse_from_rfc3339(&end).ok())
+            .map(|end| (end - start).num_seconds().max(0))
+            .unwrap_or(0);
+
+        let entry = activity.entry(camera_id).or_insert_with(|| CameraActivity {
+            camera_id,
+            camera_name: String::new(),
+            recordings: 0,
+            recorded_bytes: 0,
+            recorded_secs: 0,
+            failures: 0,
+        });
+        entry.recordings += 1;
+        entry.recorded_bytes += bytes;
+        entry.recorded_secs += secs;
+        *daily.entry(start.with_timezone(&tz).date_naive()).or_insert(0) += bytes;
+    }
+
+    let names = camera_names(conn)?;
+    let failures = failures(conn, &from_str, &to_str, &names)?;
+    for failure in &failures {
+        activity.entry(failure.camera_id).or_insert_with(|| CameraActivity {
+            camera_id: failure.camera_id,
+            camera_name: String::new(),
+            recordings: 0,
+            recorded_bytes: 0,
+            recorded_secs: 0,
+            failures: 0,
+        }).failures += 1;
+    }
+
+    // Every active camera, archived ones only when they did something in the range
+    let mut cameras = Vec::new();
+    for entry in &names {
+        match activity.remove(&entry.id) {
+            Some(mut camera) => {
+                camera.camera_name = entry.name.clone();
+                cameras.push(camera);
+            }
+            None if !entry.archived => cameras.push(CameraActivity {
+                camera_id: entry.id,
+                camera_name: entry.name.clone(),
+                recordings: 0,
+                recorded_bytes: 0,
+                recorded_secs: 0,
+                failures: 0,
+            }),
+            None => {}
+        }
+    }
+
+    let recorded_bytes: i64 = cameras.iter().map(|camera| camera.recorded_bytes).sum();
+    let range_days = (to - from).num_seconds() as f64 / 86400.0;
+    let average_daily_bytes = recorded_bytes as f64 / range_days;
+
+    Ok(ActivityReport {
+        from,
+        to,
+        generated_at: Utc::now(),
+        recordings: cameras.iter().map(|camera| camera.recordings).sum(),
+        recorded_bytes,
+        recorded_secs: cameras.iter().map(|camera| camera.recorded_secs).sum(),
+        failure_count: failures.len() as i64,
+        failures: failures.into_iter().take(MAX_REPORT_FAILURES).collect(),
+        offline_cameras: offline_cameras(conn, &from_str, &to_str, &names)?,
+        daily_usage: daily.into_iter()
+            .map(|(date, bytes)| DailyUsage { date: date.format("%Y-%m-%d").to_string(), bytes })
+            .collect(),
+        storage: storage_usage(conn, recording_dir, average_daily_bytes)?,
+        cameras,
+    })
+}
+
+struct CameraName {
+    id: i32,
+    name: String,
+    archived: bool,
+}
+
+// Every camera in display order
+fn camera_names(conn: &Connection) -> Result<Vec<CameraName>, String> {
+    let mut stmt = conn.prepare(
+        "SELECT id, name, archived_at IS NOT NULL FROM cameras ORDER BY sort_order, id"
+    ).map_err(|e| e.to_string())?;
+    let names = stmt.query_map([], |row| Ok(CameraName { id: row.get(0)?, name: row.get(1)?, archived: row.get(2)? }))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    Ok(names)
+}
+
+fn camera_name(names: &[CameraName], camera_id: i32) -> Option<String> {
+    names.iter().find(|entry| entry.id == camera_id).map(|entry| entry.name.clone())
+}
+
+// Failed recordings and schedule runs that didn't record, most recent first. Schedules failing
+// to start also failed their recording, so only deferred and interrupted runs are added.
+fn failures(conn: &Connection, from: &str, to: &str, names: &[CameraName]) -> Result<Vec<ReportFailure>, String> {
+    let mut stmt = conn.prepare(
+        "SELECT camera_id, 'recording', detail, occurred_at FROM camera_incidents
+         WHERE kind = 'recording-failed' AND occurred_at >= ?1 AND occurred_at < ?2
+         UNION ALL
+         SELECT r.camera_id, 'schedule', s.name || ': ' || COALESCE(r.detail, r.status), r.scheduled_at
+         FROM schedule_runs r JOIN recording_schedules s ON s.id = r.schedule_id
+         WHERE r.status IN ('deferred', 'interrupted') AND r.scheduled_at >= ?1 AND r.scheduled_at < ?2
+         ORDER BY 4 DESC"
+    ).map_err(|e| e.to_string())?;
+    let rows = stmt.query_map(params![from, to], |row| {
+        Ok((row.get::<_, i32>(0)?, row.get::<_, String>(1)?, row.get::<_, Option<String>>(2)?, row.get::<_, String>(3)?))
+    }).map_err(|e| e.to_string())?;
+
+    let mut failures = Vec::new();
+    for row in rows {
+        let (camera_id, kind, detail, occurred_at) = row.map_err(|e| e.to_string())?;
+        failures.push(ReportFailure {
+            camera_id,
+            camera_name: camera_name(names, camera_id),
+            kind,
+            detail,
+            occurred_at: parse_time(&occurred_at),
+        });
+    }
+    Ok(failures)
+}
+
+// Cameras whose live stream died during the range, most incidents first
+fn offline_cameras(conn: &Connection, from: &str, to: &str, names: &[CameraName]) -> Result<Vec<OfflineCamera>, String> {
+    let mut stmt = conn.prepare(
+        "SELECT camera_id, COUNT(*), MAX(occurred_at),
+                (SELECT detail FROM camera_incidents last
+                 WHERE last.camera_id = i.camera_id AND last.kind = 'stream-died'
+                   AND last.occurred_at >= ?1 AND last.occurred_at < ?2
+                 ORDER BY last.occurred_at DESC LIMIT 1)
+         FROM camera_incidents i
+         WHERE kind = 'stream-died' AND occurred_at >= ?1 AND occurred_at < ?2
+         GROUP BY camera_id
+         ORDER BY COUNT(*) DESC, camera_id"
+    ).map_err(|e| e.to_string())?;
+    let rows = stmt.query_map(params![from, to], |row| {
+        Ok((row.get::<_, i32>(0)?, row.get::<_, i64>(1)?, row.get::<_, String>(2)?, row.get::<_, Option<String>>(3)?))
+    }).map_err(|e| e.to_string())?;
+
+    let mut cameras = Vec::new();
+    for row in rows {
+        let (camera_id, incidents, last, last_reason) = row.map_err(|e| e.to_string())?;
+        cameras.push(OfflineCamera {
+            camera_id,
+            camera_name: camera_name(names, camera_id),
+            incidents,
+            last_incident_at: parse_time(&last),
+            last_reason,
+        });
+    }
+    Ok(cameras)
+}
+
+// Free space of the recordings directory and the enabled storage locations, with the days
+// left at the given recording volume
+fn storage_usage(conn: &Connection, recording_dir: &Path, average_daily_bytes: f64) -> Result<Vec<StorageUsage>, String> {
+    let days_left = |free: Option<u64>| {
+        free.filter(|_| average_daily_bytes >= 1.0).map(|free| (free as f64 / average_daily_bytes) as i64)
+    };
+
+    let free_bytes = fs4::available_space(recording_dir).ok();
+    let mut usage = vec![StorageUsage {
+        name: "Recordings".to_string(),
+        path: recording_dir.to_string_lossy().to_string(),
+        free_bytes,
+        total_bytes: fs4::total_space(recording_dir).ok(),
+        days_left: days_left(free_bytes),
+    }];
+    let locations = crate::storage::list_locations(conn)?.into_iter().filter(|location| location.enabled).collect();
+    for location in crate::storage::with_status(locations) {
+        usage.push(StorageUsage {
+            name: location.name,
+            path: location.path,
+            free_bytes: location.free_bytes,
+            total_bytes: location.total_bytes,
+            days_left: days_left(location.free_bytes),
+        });
+    }
+    Ok(usage)
+}
+
+fn parse_time(text: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(text).map(|time| time.with_timezone(&Utc)).unwrap_or_else(|_| Utc::now())
+}
+
+/// One-line summary of a report, e.g. for chat or mail gateways behind the webhook
+pub fn summary(report: &ActivityReport) -> String {
+    let tz = settings::timezone();
+    format!(
+        "{} - {}: {} recordings ({:.1} GB, {}h {}m), {} failures, {} cameras offline",
+        report.from.with_timezone(&tz).format("%Y-%m-%d %H:%M"),
+        report.to.with_timezone(&tz).format("%Y-%m-%d %H:%M"),
+        report.recordings,
+        report.recorded_bytes as f64 / 1_073_741_824.0,
+        report.recorded_secs / 3600,
+        report.recorded_secs % 3600 / 60,
+        report.failure_count,
+        report.offline_cameras.len(),
+    )
+}
+
+/// Record recording failures and dropped live streams from the event bus so reports can
+/// list them later
+pub fn spawn_incident_log(app_handle: AppHandle) {
+    let state = app_handle.state::<AppState>().inner().clone();
+    let mut events = state.events.subscribe();
+
+    tauri::async_runtime::spawn(async move {
+        loop {
+            match events.recv().await {
+                Ok(text) => {
+                    if let Err(e) = log_incident(&state.db_path, &text) {
+                        eprintln!("[Report] Failed to record incident: {}", e);
+                    }
+                }
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                    eprintln!("[Report] Incident log fell behind, skipped {} events", skipped);
+                }
+                Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    });
+}
+
+// Store an event (as sent to WebSocket clients) if it is an incident
+fn log_incident(db_path: &str, text: &str) -> Result<(), String> {
+    let Ok(message) = serde_json::from_str::<Value>(text) else { return Ok(()) };
+    let payload = &message["payload"];
+    let (kind, detail) = match message["event"].as_str() {
+        Some("recording-failed") => ("recording-failed", payload["error"].as_str()),
+        Some("stream-died") => ("stream-died", payload["reason"].as_str()),
+        _ => return Ok(()),
+    };
+    let Some(camera_id) = payload["cameraId"].as_i64() else { return Ok(()) };
+
+    let conn = crate::db::open(db_path).map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT INTO camera_incidents (camera_id, kind, detail, occurred_at) VALUES (?1, ?2, ?3, ?4)",
+        params![camera_id, kind, detail, Utc::now().to_rfc3339()],
+    ).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Send the digest of the previous day or week (reports.digest) once its range has ended
+pub fn spawn_digest(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(Duration::from_secs(DIGEST_CHECK_INTERVAL_SECS)).await;
+
+            let state = app_handle.state::<AppState>().inner().clone();
+            if let Err(e) = send_due_digest(&state).await {
+                eprintln!("[Report] Digest failed: {}", e);
+            }
+        }
+    });
+}
+
+async fn send_due_digest(state: &AppState) -> Result<(), String> {
+    let (period, webhook_url, report) = {
+        let conn = crate::db::open(&state.db_path).map_err(|e| e.to_string())?;
+        conn.execute(
+            "DELETE FROM camera_incidents WHERE occurred_at < ?1",
+            [(Utc::now() - Days::new(INCIDENT_RETENTION_DAYS)).to_rfc3339()],
+        ).map_err(|e| e.to_string())?;
+
+        let period = settings::get_opt_string(&conn, settings::REPORTS_DIGEST)?.unwrap_or_default();
+        let Some((from, to)) = digest_range(&period, Utc::now(), settings::timezone()) else {
+            return Ok(());
+        };
+        let sent: bool = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM report_deliveries WHERE period = ?1 AND range_end = ?2)",
+            params![period, to.to_rfc3339()],
+            |row| row.get(0),
+        ).map_err(|e| e.to_string())?;
+        if sent {
+            return Ok(());
+        }
+
+        let report = generate_report(&conn, &state.recording_dir, from, to)?;
+        (period, settings::get_opt_string(&conn, settings::REPORTS_WEBHOOK_URL)?, report)
+    };
+
+    println!("[Report] Sending {} digest: {}", period, summary(&report));
+    state.events.emit(AppEvent::ReportReady { period: period.clone(), report: Box::new(report.clone()) });
+    let error = match &webhook_url {
+        Some(url) => post_webhook(url, &period, &report).await.err(),
+        None => None,
+    };
+    if let Some(e) = &error {
+        eprintln!("[Report] Failed to post digest to webhook: {}", e);
+    }
+
+    // Recorded even when the webhook failed, so a broken endpoint isn't retried every check
+    let conn = crate::db::open(&state.db_path).map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT OR IGNORE INTO report_deliveries (period, range_start, range_end, sent_at, error)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![period, report.from.to_rfc3339(), report.to.to_rfc3339(), Utc::now().to_rfc3339(), error],
+    ).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+async fn post_webhook(url: &str, period: &str, report: &ActivityReport) -> Result<(), String> {
+    let http = reqwest::Client::builder()
+        .timeout(Duration::from_secs(WEBHOOK_TIMEOUT_SECS))
+        .build()
+        .map_err(|e| e.to_string())?;
+    let response = http.post(url)
+        .json(&json!({ "period": period, "summary": summary(report), "report": report }))
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        return Err(format!("Webhook answered {}", response.status()));
+    }
+    Ok(())
+}
+
+// Range the latest digest of the period covers: the previous local day, or the previous week
+// from Monday, between local midnights. None when digests are off.
+fn digest_range(period: &str, now: DateTime<Utc>, tz: Tz) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+    let today = now.with_timezone(&tz).date_naive();
+    let (end, days) = match period {
+        "daily" => (today, 1),
+        "weekly" => (today - Days::new(today.weekday().num_days_from_monday() as u64), 7),
+        _ => return None,
+    };
+    Some((local_midnight(end - Days::new(days), tz)?, local_midnight(end, tz)?))
+}
+
+// Start of a local day; None in the rare zones whose DST change skips midnight
+fn local_midnight(date: NaiveDate, tz: Tz) -> Option<DateTime<Utc>> {
+    tz.from_local_datetime(&date.and_time(NaiveTime::MIN)).earliest().map(|time| time.with_timezone(&Utc))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn daily_digest_covers_the_previous_local_day() {
+        let now = Utc.with_ymd_and_hms(2026, 10, 15, 3, 0, 0).unwrap(); // 12:00 in Tokyo
+        let (from, to) = digest_range("daily", now, chrono_tz::Asia::Tokyo).unwrap();
+        assert_eq!(from, Utc.with_ymd_and_hms(2026, 10, 13, 15, 0, 0).unwrap());
+        assert_eq!(to, Utc.with_ymd_and_hms(2026, 10, 14, 15, 0, 0).unwrap());
+    }
+
+    #[test]
+    fn weekly_digest_ends_on_monday_and_follows_dst() {
+        // Thursday; the week before Monday 2026-11-02 contains the end of US daylight saving time
+        let now = Utc.with_ymd_and_hms(2026, 11, 5, 12, 0, 0).unwrap();
+        let (from, to) = digest_range("weekly", now, chrono_tz::America::New_York).unwrap();
+        assert_eq!(from, Utc.with_ymd_and_hms(2026, 10, 26, 4, 0, 0).unwrap());
+        assert_eq!(to, Utc.with_ymd_and_hms(2026, 11, 2, 5, 0, 0).unwrap());
+    }
+
+    #[test]
+    fn no_digest_when_off() {
+        assert!(digest_range("off", Utc::now(), chrono_tz::Asia::Tokyo).is_none());
+    }
+}
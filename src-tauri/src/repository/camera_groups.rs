@@ -0,0 +1,96 @@
+use crate::models::CameraGroup;
+use rusqlite::{Connection, OptionalExtension};
+
+/// Camera groups and their members
+pub struct CameraGroupRepo;
+
+impl CameraGroupRepo {
+    pub fn list(conn: &Connection) -> Result<Vec<CameraGroup>, String> {
+        let groups: Vec<(i64, String)> = {
+            let mut stmt = conn.prepare("SELECT id, name FROM camera_groups ORDER BY name").map_err(|e| e.to_string())?;
+            let groups = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+                .map_err(|e| e.to_string())?
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| e.to_string())?;
+            groups
+        };
+
+        groups.into_iter()
+            .map(|(id, name)| Ok(CameraGroup { id, name, camera_ids: Self::members(conn, id)? }))
+            .collect()
+    }
+
+    pub fn get(conn: &Connection, id: i64) -> Result<CameraGroup, String> {
+        let name: String = conn.query_row("SELECT name FROM camera_groups WHERE id = ?1", [id], |row| row.get(0))
+            .optional().map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("Camera group {} not found", id))?;
+        Ok(CameraGroup { id, name, camera_ids: Self::members(conn, id)? })
+    }
+
+    /// Cameras of the group in list order, archived ones left out
+    pub fn members(conn: &Connection, id: i64) -> Result<Vec<i32>, String> {
+        let mut stmt = conn.prepare(
+            "SELECT c.id FROM camera_group_members m
+             JOIN cameras c ON c.id = m.camera_id
+             WHERE m.group_id = ?1 AND c.archived_at IS NULL
+             ORDER BY c.sort_order, c.id"
+        ).map_err(|e| e.to_string())?;
+        let ids = stmt.query_map([id], |row| row.get(0))
+            .map_err(|e| e.to_string())?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())?;
+        Ok(ids)
+    }
+
+    /// Create the group (id None) or rename it, replacing its members
+    pub fn save(conn: &mut Connection, id: Option<i64>, name: &str, camera_ids: &[i32]) -> Result<i64, String> {
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+        let id = match id {
+            Some(id) => {
+                let updated = tx.execute("UPDATE camera_groups SET name = ?1 WHERE id = ?2", rusqlite::params![name, id])
+                    .map_err(|e| e.to_string())?;
+                if updated == 0 {
+                    return Err(format!("Camera group {} not found", id));
+                }
+                tx.execute("DELETE FROM camera_group_members WHERE group_id = ?1", [id]).map_err(|e| e.to_string())?;
+                id
+            }
+            None => {
+                tx.execute("INSERT INTO camera_groups (name) VALUES (?1)", [name]).map_err(|e| e.to_string())?;
+                tx.last_insert_rowid()
+            }
+        };
+        for camera_id in camera_ids {
+            tx.execute(
+                "INSERT OR IGNORE INTO camera_group_members (group_id, camera_id) VALUES (?1, ?2)",
+                rusqlite::params![id, camera_id],
+            ).map_err(|e| e.to_string())?;
+        }
+        tx.commit().map_err(|e| e.to_string())?;
+        Ok(id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::test_db::TestDb;
+
+    #[test]
+    fn members_follow_camera_order_and_skip_archived() {
+        let mut db = TestDb::new("group-members");
+        let gate = db.add_camera("Gate");
+        let yard = db.add_camera("Yard");
+        let old = db.add_camera("Old");
+        db.conn.execute("UPDATE cameras SET sort_order = 1 WHERE id = ?1", [yard]).unwrap();
+        db.conn.execute("UPDATE cameras SET sort_order = 2 WHERE id = ?1", [gate]).unwrap();
+        db.conn.execute("UPDATE cameras SET archived_at = '2024-05-01T00:00:00Z' WHERE id = ?1", [old]).unwrap();
+
+        let id = CameraGroupRepo::save(&mut db.conn, None, "Outdoor", &[gate, yard, old]).unwrap();
+        assert_eq!(CameraGroupRepo::members(&db.conn, id).unwrap(), vec![yard, gate]);
+
+        CameraGroupRepo::save(&mut db.conn, Some(id), "Front", &[gate]).unwrap();
+        let group = CameraGroupRepo::get(&db.conn, id).unwrap();
+        assert_eq!((group.name.as_str(), group.camera_ids), ("Front", vec![gate]));
+    }
+}
@@ -0,0 +1,141 @@
+use super::parse_time;
+use crate::models::{Camera, InputTuning, VideoFilters};
+use rusqlite::Connection;
+
+// Every column of a camera row, in the order `from_row` reads them
+const COLUMNS: &str = "id, name, type, host, port, user, pass, xaddr, stream_path,
+    device_path, device_id, device_index,
+    video_format, video_width, video_height, video_fps,
+    tls_verify, rtsp_transport, stream_timeout_secs, created_at, updated_at, tls_cert_pem,
+    sort_order, is_favorite, input_socket_timeout_ms, input_rw_timeout_ms, input_reconnect,
+    input_reconnect_delay_max_secs, input_probesize, input_analyzeduration_ms, input_user_agent,
+    extra_input_args, extra_output_args, quality_preset,
+    video_deinterlace, video_rotation, video_hflip, video_vflip, time_sync_strategy,
+    profile_token";
+
+/// Complete camera records, including the UVC/stream metadata
+pub struct CameraRepo;
+
+impl CameraRepo {
+    pub fn get(conn: &Connection, id: i32) -> Result<Camera, String> {
+        conn.query_row(&format!("SELECT {} FROM cameras WHERE id = ?1", COLUMNS), [id], Self::from_row)
+            .map_err(|e| format!("Camera not found: {}", e))
+    }
+
+    /// Cameras in list order; `archived` selects the archived ones instead of the active ones
+    pub fn list(conn: &Connection, archived: bool) -> Result<Vec<Camera>, String> {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM cameras WHERE archived_at IS {} ORDER BY sort_order, id",
+            COLUMNS,
+            if archived { "NOT NULL" } else { "NULL" }
+        )).map_err(|e| e.to_string())?;
+
+        let cameras = stmt.query_map([], Self::from_row)
+            .map_err(|e| e.to_string())?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())?;
+        Ok(cameras)
+    }
+
+    fn from_row(row: &rusqlite::Row) -> rusqlite::Result<Camera> {
+        Ok(Camera {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            camera_type: row.get(2)?,
+            host: row.get(3)?,
+            port: row.get(4)?,
+            user: row.get(5)?,
+            pass: row.get(6)?,
+            xaddr: row.get(7)?,
+            stream_path: row.get(8)?,
+            device_path: row.get(9)?,
+            device_id: row.get(10)?,
+            device_index: row.get(11)?,
+            video_format: row.get(12)?,
+            video_width: row.get(13)?,
+            video_height: row.get(14)?,
+            video_fps: row.get(15)?,
+            tls_verify: row.get(16)?,
+            rtsp_transport: row.get(17)?,
+            stream_timeout_secs: row.get(18)?,
+            created_at: parse_time(&row.get::<_, String>(19)?),
+            updated_at: parse_time(&row.get::<_, String>(20)?),
+            tls_cert_pem: row.get(21)?,
+            sort_order: row.get(22)?,
+            is_favorite: row.get(23)?,
+            input_tuning: InputTuning {
+                socket_timeout_ms: row.get(24)?,
+                rw_timeout_ms: row.get(25)?,
+                reconnect: row.get(26)?,
+                reconnect_delay_max_secs: row.get(27)?,
+                probesize: row.get(28)?,
+                analyzeduration_ms: row.get(29)?,
+                user_agent: row.get(30)?,
+            },
+            extra_input_args: parse_args(row.get(31)?),
+            extra_output_args: parse_args(row.get(32)?),
+            quality_preset: row.get(33)?,
+            video_filters: VideoFilters {
+                deinterlace: row.get(34)?,
+                rotation: row.get(35)?,
+                hflip: row.get(36)?,
+                vflip: row.get(37)?,
+            },
+            time_sync_strategy: row.get(38)?,
+            profile_token: row.get(39)?,
+        })
+    }
+}
+
+// Custom FFmpeg arguments are stored as a JSON array; NULL when never set
+fn parse_args(json: Option<String>) -> Vec<String> {
+    json.and_then(|json| serde_json::from_str(&json).ok()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::test_db::TestDb;
+
+    #[test]
+    fn get_returns_uvc_metadata() {
+        let db = TestDb::new("camera-get");
+        db.conn.execute(
+            "INSERT INTO cameras (name, type, host, port, device_path, video_format, video_width, video_height, video_fps)
+             VALUES ('Desk', 'uvc', 'localhost', 0, '/dev/video0', 'mjpeg', 1280, 720, 30)",
+            [],
+        ).unwrap();
+        let id = db.conn.last_insert_rowid() as i32;
+
+        let camera = CameraRepo::get(&db.conn, id).unwrap();
+        assert_eq!(camera.camera_type, "uvc");
+        assert_eq!(camera.device_path.as_deref(), Some("/dev/video0"));
+        assert_eq!(camera.video_format.as_deref(), Some("mjpeg"));
+        assert_eq!((camera.video_width, camera.video_height, camera.video_fps), (Some(1280), Some(720), Some(30)));
+        assert_eq!(camera.rtsp_transport, "tcp");
+    }
+
+    #[test]
+    fn get_fails_for_unknown_camera() {
+        let db = TestDb::new("camera-missing");
+        assert!(CameraRepo::get(&db.conn, 42).is_err());
+    }
+
+    #[test]
+    fn list_keeps_sort_order_and_separates_archived() {
+        let db = TestDb::new("camera-list");
+        let first = db.add_camera("First");
+        let second = db.add_camera("Second");
+        let archived = db.add_camera("Archived");
+        db.conn.execute("UPDATE cameras SET sort_order = 2 WHERE id = ?1", [first]).unwrap();
+        db.conn.execute("UPDATE cameras SET sort_order = 1, is_favorite = 1 WHERE id = ?1", [second]).unwrap();
+        db.conn.execute("UPDATE cameras SET archived_at = '2024-05-01T00:00:00Z' WHERE id = ?1", [archived]).unwrap();
+
+        let active = CameraRepo::list(&db.conn, false).unwrap();
+        assert_eq!(active.iter().map(|c| c.id).collect::<Vec<_>>(), vec![second, first]);
+        assert!(active[0].is_favorite);
+
+        let archived_list = CameraRepo::list(&db.conn, true).unwrap();
+        assert_eq!(archived_list.iter().map(|c| c.id).collect::<Vec<_>>(), vec![archived]);
+    }
+}
@@ -0,0 +1,69 @@
+// Typed queries for the tables several modules read. Each repo owns the column list of its
+// SELECT together with the row mapping, so a schema change only has to touch one place.
+mod camera_groups;
+mod cameras;
+mod recordings;
+mod schedules;
+
+pub use camera_groups::CameraGroupRepo;
+pub use cameras::CameraRepo;
+pub use recordings::RecordingRepo;
+pub use schedules::ScheduleRepo;
+
+use chrono::{DateTime, Utc};
+
+// Timestamps are stored as RFC 3339; rows written by SQLite defaults (CURRENT_TIMESTAMP) or
+// damaged values fall back to now
+fn parse_time(text: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(text)
+        .map(|time| time.with_timezone(&Utc))
+        .unwrap_or_else(|_| Utc::now())
+}
+
+#[cfg(test)]
+pub(crate) mod test_db {
+    use rusqlite::Connection;
+    use std::path::{Path, PathBuf};
+
+    /// A freshly initialized database in a temporary directory of its own, which also holds
+    /// the test's files. The directory is removed when dropped, also when the test panics.
+    pub struct TestDb {
+        dir: PathBuf,
+        pub conn: Connection,
+    }
+
+    impl TestDb {
+        pub fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!("onvif-viewer-test-{}-{}", name, std::process::id()));
+            let _ = std::fs::remove_dir_all(&dir);
+            std::fs::create_dir_all(&dir).unwrap();
+            let path = dir.join("app.db");
+            crate::db::init_db(&path).unwrap();
+            let conn = crate::db::open(&path).unwrap();
+            TestDb { dir, conn }
+        }
+
+        pub fn dir(&self) -> &Path {
+            &self.dir
+        }
+
+        /// Path of the database, as the modules that open their own connection take it
+        pub fn db_path(&self) -> String {
+            self.dir.join("app.db").to_string_lossy().to_string()
+        }
+
+        pub fn add_camera(&self, name: &str) -> i32 {
+            self.conn.execute(
+                "INSERT INTO cameras (name, type, host, port) VALUES (?1, 'rtsp', '10.0.0.5', 554)",
+                [name],
+            ).unwrap();
+            self.conn.last_insert_rowid() as i32
+        }
+    }
+
+    impl Drop for TestDb {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.dir);
+        }
+    }
+}
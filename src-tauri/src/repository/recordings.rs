@@ -0,0 +1,166 @@
+use super::parse_time;
+use crate::models::{Recording, RecordingFilter};
+use rusqlite::Connection;
+
+/// Recording rows as listed in the library
+pub struct RecordingRepo;
+
+impl RecordingRepo {
+    /// Recordings matching the filter, newest first
+    pub fn query(conn: &Connection, filter: RecordingFilter) -> Result<Vec<Recording>, String> {
+        let mut conditions: Vec<&str> = Vec::new();
+        let mut params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+
+        if let Some(camera_id) = filter.camera_id {
+            conditions.push("r.camera_id = ?");
+            params.push(Box::new(camera_id));
+        }
+        if let Some(trigger) = filter.trigger.filter(|t| !t.is_empty()) {
+            if trigger == "scheduled" {
+                conditions.push("r.trigger LIKE 'scheduled:%'");
+            } else {
+                conditions.push("r.trigger = ?");
+                params.push(Box::new(trigger));
+            }
+        }
+        if let Some(tag) = filter.tag.filter(|t| !t.is_empty()) {
+            conditions.push("EXISTS (SELECT 1 FROM json_each(r.tags) WHERE json_each.value = ?)");
+            params.push(Box::new(tag));
+        }
+        if let Some(notes) = filter.notes.filter(|n| !n.is_empty()) {
+            conditions.push("r.notes LIKE '%' || ? || '%'");
+            params.push(Box::new(notes));
+        }
+        if let Some(is_protected) = filter.is_protected {
+            conditions.push("r.is_protected = ?");
+            params.push(Box::new(is_protected));
+        }
+        if let Some(integrity) = filter.integrity.filter(|i| !i.is_empty()) {
+            conditions.push("r.integrity = ?");
+            params.push(Box::new(integrity));
+        }
+        if let Some(session_id) = filter.session_id {
+            conditions.push("r.session_id = ?");
+            params.push(Box::new(session_id));
+        }
+        if let Some(failed) = filter.failed {
+            conditions.push(if failed { "r.failure IS NOT NULL" } else { "r.failure IS NULL" });
+        }
+
+        let where_clause = if conditions.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", conditions.join(" AND "))
+        };
+
+        let mut stmt = conn.prepare(&format!(
+            "SELECT r.id, r.camera_id, r.filename, r.thumbnail, r.start_time, r.end_time, r.is_finished, c.name,
+                    r.trigger, r.tags, r.notes, r.is_protected, r.archive_location, r.archived_at,
+                    r.integrity, r.integrity_detail, r.session_id, r.file_size, r.failure, r.preview
+             FROM recordings r
+             LEFT JOIN cameras c ON r.camera_id = c.id
+             {}
+             ORDER BY r.start_time DESC",
+            where_clause
+        )).map_err(|e| e.to_string())?;
+
+        let recordings = stmt.query_map(rusqlite::params_from_iter(params.iter()), Self::from_row)
+            .map_err(|e| e.to_string())?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())?;
+        Ok(recordings)
+    }
+
+    fn from_row(row: &rusqlite::Row) -> rusqlite::Result<Recording> {
+        Ok(Recording {
+            id: row.get(0)?,
+            camera_id: row.get(1)?,
+            filename: row.get(2)?,
+            thumbnail: row.get(3)?,
+            start_time: parse_time(&row.get::<_, String>(4)?),
+            end_time: row.get::<_, Option<String>>(5)?.map(|t| parse_time(&t)),
+            is_finished: row.get(6)?,
+            trigger: row.get(8)?,
+            tags: serde_json::from_str(&row.get::<_, String>(9)?).unwrap_or_default(),
+            notes: row.get(10)?,
+            is_protected: row.get(11)?,
+            archive_location: row.get(12)?,
+            archived_at: row.get::<_, Option<String>>(13)?
+                .and_then(|t| chrono::DateTime::parse_from_rfc3339(&t).ok())
+                .map(|t| t.with_timezone(&chrono::Utc)),
+            integrity: row.get(14)?,
+            integrity_detail: row.get(15)?,
+            file_size: row.get(17)?,
+            session_id: row.get(16)?,
+            failure: row.get(18)?,
+            preview: row.get(19)?,
+            camera_name: row.get(7)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::test_db::TestDb;
+
+    fn add_recording(db: &TestDb, camera_id: i32, filename: &str, start_time: &str, trigger: &str, tags: &str) -> i32 {
+        db.conn.execute(
+            "INSERT INTO recordings (camera_id, filename, start_time, end_time, is_finished, trigger, tags, file_size)
+             VALUES (?1, ?2, ?3, ?3, 1, ?4, ?5, 1024)",
+            rusqlite::params![camera_id, filename, start_time, trigger, tags],
+        ).unwrap();
+        db.conn.last_insert_rowid() as i32
+    }
+
+    #[test]
+    fn query_lists_newest_first_with_camera_name() {
+        let db = TestDb::new("recording-order");
+        let camera_id = db.add_camera("Gate");
+        let older = add_recording(&db, camera_id, "a.mp4", "2024-05-01T10:00:00+00:00", "manual", "[]");
+        let newer = add_recording(&db, camera_id, "b.mp4", "2024-05-02T10:00:00+00:00", "manual", "[]");
+
+        let recordings = RecordingRepo::query(&db.conn, RecordingFilter::default()).unwrap();
+        assert_eq!(recordings.iter().map(|r| r.id).collect::<Vec<_>>(), vec![newer, older]);
+        assert_eq!(recordings[0].camera_name.as_deref(), Some("Gate"));
+        assert_eq!(recordings[0].file_size, Some(1024));
+    }
+
+    #[test]
+    fn query_filters_by_camera_tag_and_scheduled_trigger() {
+        let db = TestDb::new("recording-filter");
+        let gate = db.add_camera("Gate");
+        let yard = db.add_camera("Yard");
+        let tagged = add_recording(&db, gate, "a.mp4", "2024-05-01T10:00:00+00:00", "manual", r#"["delivery"]"#);
+        let scheduled = add_recording(&db, gate, "b.mp4", "2024-05-01T11:00:00+00:00", "scheduled:Night", "[]");
+        add_recording(&db, yard, "c.mp4", "2024-05-01T12:00:00+00:00", "manual", "[]");
+
+        let by_camera = RecordingRepo::query(&db.conn, RecordingFilter { camera_id: Some(gate), ..Default::default() }).unwrap();
+        assert_eq!(by_camera.len(), 2);
+
+        let by_tag = RecordingRepo::query(&db.conn, RecordingFilter { tag: Some("delivery".to_string()), ..Default::default() }).unwrap();
+        assert_eq!(by_tag.iter().map(|r| r.id).collect::<Vec<_>>(), vec![tagged]);
+        assert_eq!(by_tag[0].tags, vec!["delivery".to_string()]);
+
+        let by_trigger = RecordingRepo::query(&db.conn, RecordingFilter { trigger: Some("scheduled".to_string()), ..Default::default() }).unwrap();
+        assert_eq!(by_trigger.iter().map(|r| r.id).collect::<Vec<_>>(), vec![scheduled]);
+    }
+
+    #[test]
+    fn query_filters_failed_recordings() {
+        let db = TestDb::new("recording-failed");
+        let camera_id = db.add_camera("Gate");
+        let ok = add_recording(&db, camera_id, "a.mp4", "2024-05-01T10:00:00+00:00", "manual", "[]");
+        let failed = add_recording(&db, camera_id, "b.mp4", "2024-05-01T11:00:00+00:00", "manual", "[]");
+        db.conn.execute(
+            "UPDATE recordings SET failure = 'FFmpeg exited with exit status: 1 after 2s: 401 Unauthorized' WHERE id = ?1",
+            [failed],
+        ).unwrap();
+
+        let only_failed = RecordingRepo::query(&db.conn, RecordingFilter { failed: Some(true), ..Default::default() }).unwrap();
+        assert_eq!(only_failed.iter().map(|r| r.id).collect::<Vec<_>>(), vec![failed]);
+        assert!(only_failed[0].failure.as_deref().is_some_and(|f| f.contains("401")));
+        let without = RecordingRepo::query(&db.conn, RecordingFilter { failed: Some(false), ..Default::default() }).unwrap();
+        assert_eq!(without.iter().map(|r| r.id).collect::<Vec<_>>(), vec![ok]);
+    }
+}
@@ -0,0 +1,170 @@
+use super::parse_time;
+use crate::models::{RecordingSchedule, WeeklyBlock};
+use chrono::Utc;
+use rusqlite::Connection;
+
+const SELECT: &str = "SELECT s.id, s.camera_id, s.name, s.cron_expression, s.duration_minutes, s.fps, s.is_enabled,
+        s.created_at, s.updated_at, c.name as camera_name, s.schedule_type, s.weekly_blocks,
+        s.action, s.retention_days,
+        (SELECT schedules_enabled = 0 FROM scheduler_settings WHERE id = 1),
+        s.group_id, g.name
+    FROM recording_schedules s
+    LEFT JOIN cameras c ON s.camera_id = c.id
+    LEFT JOIN camera_groups g ON s.group_id = g.id";
+
+/// Recording and snapshot schedules, with their camera name and next run
+pub struct ScheduleRepo;
+
+impl ScheduleRepo {
+    pub fn get(conn: &Connection, id: i32) -> Result<RecordingSchedule, String> {
+        conn.query_row(&format!("{} WHERE s.id = ?1", SELECT), [id], Self::from_row)
+            .map_err(|e| e.to_string())
+    }
+
+    /// Every schedule, newest first
+    pub fn list(conn: &Connection) -> Result<Vec<RecordingSchedule>, String> {
+        Self::query(conn, &format!("{} ORDER BY s.created_at DESC", SELECT))
+    }
+
+    /// Schedules the scheduler has to register
+    pub fn list_enabled(conn: &Connection) -> Result<Vec<RecordingSchedule>, String> {
+        Self::query(conn, &format!("{} WHERE s.is_enabled = 1", SELECT))
+    }
+
+    fn query(conn: &Connection, sql: &str) -> Result<Vec<RecordingSchedule>, String> {
+        let mut stmt = conn.prepare(sql).map_err(|e| e.to_string())?;
+        let schedules = stmt.query_map([], Self::from_row)
+            .map_err(|e| e.to_string())?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())?;
+        Ok(schedules)
+    }
+
+    fn from_row(row: &rusqlite::Row) -> rusqlite::Result<RecordingSchedule> {
+        let cron_expression: String = row.get(3)?;
+        let is_enabled: bool = row.get(6)?;
+        let schedule_type: String = row.get(10)?;
+        let weekly_blocks: Option<Vec<WeeklyBlock>> = row.get::<_, Option<String>>(11)?
+            .and_then(|blocks| serde_json::from_str(&blocks).ok());
+
+        let next_run = match (&weekly_blocks, schedule_type.as_str()) {
+            (Some(blocks), "weekly") => crate::weekly_schedule::compile(blocks).ok().and_then(|triggers| {
+                triggers.iter().filter_map(|(cron, _)| next_run(cron, is_enabled)).min()
+            }),
+            _ => next_run(&cron_expression, is_enabled),
+        };
+
+        Ok(RecordingSchedule {
+            id: row.get(0)?,
+            camera_id: row.get(1)?,
+            group_id: row.get(15)?,
+            name: row.get(2)?,
+            cron_expression,
+            duration_minutes: row.get(4)?,
+            fps: row.get(5)?,
+            is_enabled,
+            created_at: parse_time(&row.get::<_, String>(7)?),
+            updated_at: parse_time(&row.get::<_, String>(8)?),
+            schedule_type,
+            weekly_blocks,
+            action: row.get(12)?,
+            retention_days: row.get(13)?,
+            camera_name: row.get(9)?,
+            group_name: row.get(16)?,
+            is_paused: row.get::<_, Option<bool>>(14)?.unwrap_or(false),
+            next_run,
+        })
+    }
+}
+
+// Next run time of a cron expression (None if disabled or no future runs)
+fn next_run(cron_expr: &str, is_enabled: bool) -> Option<String> {
+    use croner::Cron;
+
+    if !is_enabled {
+        return None;
+    }
+
+    // cron_expr is in 6-field format: "second minute hour day month dow"
+    let cron = Cron::new(cron_expr)
+        .with_seconds_optional()
+        .parse()
+        .ok()?;
+
+    // Schedules run in the configured time zone
+    let now = Utc::now().with_timezone(&crate::settings::timezone());
+
+    // Formatted as ISO 8601 with the zone's offset
+    cron.find_next_occurrence(&now, false)
+        .ok()
+        .map(|next| next.with_timezone(&crate::settings::timezone()).to_rfc3339())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::test_db::TestDb;
+
+    fn add_schedule(db: &TestDb, camera_id: i32, name: &str, is_enabled: bool) -> i32 {
+        db.conn.execute(
+            "INSERT INTO recording_schedules (camera_id, name, cron_expression, duration_minutes, fps, is_enabled)
+             VALUES (?1, ?2, '0 0 2 * * *', 30, 10, ?3)",
+            rusqlite::params![camera_id, name, is_enabled],
+        ).unwrap();
+        db.conn.last_insert_rowid() as i32
+    }
+
+    #[test]
+    fn get_includes_camera_name_and_next_run() {
+        let db = TestDb::new("schedule-get");
+        let camera_id = db.add_camera("Gate");
+        let id = add_schedule(&db, camera_id, "Nightly", true);
+
+        let schedule = ScheduleRepo::get(&db.conn, id).unwrap();
+        assert_eq!(schedule.name, "Nightly");
+        assert_eq!(schedule.camera_name.as_deref(), Some("Gate"));
+        assert_eq!(schedule.schedule_type, "cron");
+        assert_eq!(schedule.action, "record");
+        assert!(schedule.next_run.is_some());
+    }
+
+    #[test]
+    fn disabled_schedules_have_no_next_run_and_are_not_enabled() {
+        let db = TestDb::new("schedule-enabled");
+        let camera_id = db.add_camera("Gate");
+        let enabled = add_schedule(&db, camera_id, "On", true);
+        let disabled = add_schedule(&db, camera_id, "Off", false);
+
+        assert_eq!(ScheduleRepo::list(&db.conn).unwrap().len(), 2);
+        assert!(ScheduleRepo::get(&db.conn, disabled).unwrap().next_run.is_none());
+        let ids: Vec<i32> = ScheduleRepo::list_enabled(&db.conn).unwrap().iter().map(|s| s.id).collect();
+        assert_eq!(ids, vec![enabled]);
+    }
+
+    #[test]
+    fn group_schedules_have_the_group_name() {
+        let mut db = TestDb::new("schedule-group");
+        let camera_id = db.add_camera("Gate");
+        let group_id = crate::repository::CameraGroupRepo::save(&mut db.conn, None, "Outdoor", &[camera_id]).unwrap();
+        db.conn.execute(
+            "INSERT INTO recording_schedules (group_id, name, cron_expression, duration_minutes) VALUES (?1, 'Night', '0 0 2 * * *', 30)",
+            [group_id],
+        ).unwrap();
+
+        let schedule = ScheduleRepo::get(&db.conn, db.conn.last_insert_rowid() as i32).unwrap();
+        assert_eq!((schedule.camera_id, schedule.group_id), (None, Some(group_id)));
+        assert_eq!(schedule.group_name.as_deref(), Some("Outdoor"));
+        assert!(schedule.camera_name.is_none());
+    }
+
+    #[test]
+    fn schedules_report_the_global_pause() {
+        let db = TestDb::new("schedule-paused");
+        let camera_id = db.add_camera("Gate");
+        let id = add_schedule(&db, camera_id, "Nightly", true);
+        assert!(!ScheduleRepo::get(&db.conn, id).unwrap().is_paused);
+
+        crate::scheduler::set_schedules_enabled(&db.conn, false).unwrap();
+        assert!(ScheduleRepo::get(&db.conn, id).unwrap().is_paused);
+    }
+}
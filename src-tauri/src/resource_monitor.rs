@@ -0,0 +1,149 @@
+use crate::events::AppEvent;
+use crate::models::{ProcessUsage, ResourceUsage};
+use crate::settings;
+use crate::AppState;
+use chrono::Utc;
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
+use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System};
+use tauri::{AppHandle, Manager};
+
+// How often the interval setting is re-read while monitoring is off
+const DISABLED_CHECK_SECS: u64 = 30;
+// A resource at or above this load is listed in ResourceUsage::warnings
+const WARN_PERCENT: f32 = 90.0;
+
+struct Sampler {
+    system: System,
+    last_refresh: Option<Instant>,
+}
+
+// CPU usage is measured between two refreshes, so one System is kept for all samples
+static SAMPLER: LazyLock<Mutex<Sampler>> = LazyLock::new(|| {
+    Mutex::new(Sampler { system: System::new(), last_refresh: None })
+});
+
+/// Send a `resource-usage` event every `monitoring.resourceIntervalSecs` seconds (0 turns it off)
+pub fn spawn_resource_monitor(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut last_warnings = Vec::new();
+        loop {
+            let state = app_handle.state::<AppState>().inner().clone();
+            let interval = crate::db::open(&state.db_path)
+                .map_err(|e| e.to_string())
+                .and_then(|conn| settings::get_i32(&conn, settings::MONITORING_RESOURCE_INTERVAL))
+                .unwrap_or(0);
+            if interval <= 0 {
+                tokio::time::sleep(Duration::from_secs(DISABLED_CHECK_SECS)).await;
+                continue;
+            }
+
+            match sample(&state).await {
+                Ok(usage) => {
+                    // Logged when they change, not on every sample
+                    if usage.warnings != last_warnings {
+                        for warning in &usage.warnings {
+                            println!("[Resources] {}", warning);
+                        }
+                        last_warnings = usage.warnings.clone();
+                    }
+                    state.events.emit(AppEvent::ResourceUsage(Box::new(usage)));
+                }
+                Err(e) => eprintln!("[Resources] Sampling failed: {}", e),
+            }
+            tokio::time::sleep(Duration::from_secs(interval as u64)).await;
+        }
+    });
+}
+
+/// CPU and memory of the machine and of each pipeline FFmpeg, plus GPU load
+pub async fn sample(state: &AppState) -> Result<ResourceUsage, String> {
+    // (pid, kind, camera id) of every pipeline FFmpeg
+    let mut pids: Vec<(u32, String, Option<i32>)> = Vec::new();
+    for (kind, processes) in [("stream", &state.processes), ("recording", &state.recording_processes)] {
+        let processes = processes.lock().map_err(|e| e.to_string())?;
+        pids.extend(processes.iter().map(|(camera_id, child)| (child.id(), kind.to_string(), Some(*camera_id))));
+    }
+    pids.extend(
+        state.playback_processes.lock().map_err(|e| e.to_string())?
+            .values()
+            .map(|child| (child.id(), "playback".to_string(), None)),
+    );
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let gpus = crate::gpu_detector::sample_gpu_usage();
+        let mut sampler = SAMPLER.lock().map_err(|e| e.to_string())?;
+
+        // The first refresh only sets the baseline; CPU figures need some time between two
+        if sampler.last_refresh.is_none() {
+            refresh(&mut sampler.system, &pids);
+            std::thread::sleep(sysinfo::MINIMUM_CPU_UPDATE_INTERVAL);
+        } else if let Some(wait) = sampler.last_refresh
+            .and_then(|last| sysinfo::MINIMUM_CPU_UPDATE_INTERVAL.checked_sub(last.elapsed()))
+        {
+            std::thread::sleep(wait);
+        }
+        refresh(&mut sampler.system, &pids);
+        sampler.last_refresh = Some(Instant::now());
+
+        let system = &sampler.system;
+        let processes: Vec<ProcessUsage> = pids.into_iter()
+            .filter_map(|(pid, kind, camera_id)| {
+                let process = system.process(Pid::from_u32(pid))?;
+                Some(ProcessUsage { pid, kind, camera_id, cpu_percent: process.cpu_usage(), memory_bytes: process.memory() })
+            })
+            .collect();
+
+        let mut usage = ResourceUsage {
+            sampled_at: Utc::now(),
+            cpu_percent: system.global_cpu_usage(),
+            cpu_count: system.cpus().len(),
+            memory_used_bytes: system.used_memory(),
+            memory_total_bytes: system.total_memory(),
+            gpus,
+            ffmpeg_cpu_percent: processes.iter().map(|p| p.cpu_percent).sum(),
+            ffmpeg_memory_bytes: processes.iter().map(|p| p.memory_bytes).sum(),
+            processes,
+            warnings: Vec::new(),
+        };
+        usage.warnings = capacity_warnings(&usage);
+        Ok(usage)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+fn refresh(system: &mut System, pids: &[(u32, String, Option<i32>)]) {
+    let pids: Vec<Pid> = pids.iter().map(|(pid, _, _)| Pid::from_u32(*pid)).collect();
+    system.refresh_cpu_usage();
+    system.refresh_memory();
+    system.refresh_processes_specifics(
+        ProcessesToUpdate::Some(&pids),
+        true,
+        ProcessRefreshKind::nothing().with_cpu().with_memory(),
+    );
+}
+
+// Resources at the edge of their capacity, worded for the UI
+fn capacity_warnings(usage: &ResourceUsage) -> Vec<String> {
+    let mut warnings = Vec::new();
+    if usage.cpu_percent >= WARN_PERCENT {
+        warnings.push(format!("CPU at {:.0}%", usage.cpu_percent));
+    }
+    if usage.memory_total_bytes > 0 {
+        let percent = usage.memory_used_bytes as f32 * 100.0 / usage.memory_total_bytes as f32;
+        if percent >= WARN_PERCENT {
+            warnings.push(format!("Memory at {:.0}%", percent));
+        }
+    }
+    for gpu in &usage.gpus {
+        let label = format!("GPU {} ({})", gpu.index, gpu.name.as_deref().unwrap_or(&gpu.vendor));
+        if let Some(percent) = gpu.utilization_percent.filter(|percent| *percent >= WARN_PERCENT) {
+            warnings.push(format!("{} at {:.0}%", label, percent));
+        }
+        if let Some(percent) = gpu.encoder_percent.filter(|percent| *percent >= WARN_PERCENT) {
+            warnings.push(format!("{} encoder at {:.0}%", label, percent));
+        }
+    }
+    warnings
+}
@@ -0,0 +1,152 @@
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+
+// Minimal S3 client (AWS Signature V4) covering what the uploader needs:
+// PUT and DELETE of single objects on AWS S3, MinIO and other compatible stores.
+
+const SERVICE: &str = "s3";
+const HMAC_BLOCK_SIZE: usize = 64;
+
+pub struct S3Target {
+    pub endpoint: url::Url,
+    pub region: String,
+    pub bucket: String,
+    pub access_key: String,
+    pub secret_key: String,
+    pub path_style: bool,
+}
+
+impl S3Target {
+    pub fn new(
+        endpoint: &str,
+        region: &str,
+        bucket: &str,
+        access_key: &str,
+        secret_key: &str,
+        path_style: bool,
+    ) -> Result<Self, String> {
+        let endpoint = url::Url::parse(endpoint).map_err(|e| format!("Invalid S3 endpoint: {}", e))?;
+        if endpoint.host_str().is_none() {
+            return Err("S3 endpoint has no host".to_string());
+        }
+
+        Ok(Self {
+            endpoint,
+            region: if region.is_empty() { "us-east-1".to_string() } else { region.to_string() },
+            bucket: bucket.to_string(),
+            access_key: access_key.to_string(),
+            secret_key: secret_key.to_string(),
+            path_style,
+        })
+    }
+
+    /// URL of an object; each key segment is percent-encoded the way SigV4 expects
+    pub fn object_url(&self, key: &str) -> Result<url::Url, String> {
+        let encoded_key = key.split('/').map(|s| urlencoding::encode(s).into_owned()).collect::<Vec<_>>().join("/");
+        let mut url = self.endpoint.clone();
+
+        if self.path_style {
+            let base = url.path().trim_end_matches('/').to_string();
+            url.set_path(&format!("{}/{}/{}", base, self.bucket, encoded_key));
+        } else {
+            let host = format!("{}.{}", self.bucket, url.host_str().unwrap_or_default());
+            url.set_host(Some(&host)).map_err(|e| e.to_string())?;
+            url.set_path(&format!("/{}", encoded_key));
+        }
+
+        Ok(url)
+    }
+
+    /// Sign a request and return the headers to add to it.
+    /// `payload_sha256` is the hex SHA-256 of the body, which S3 checks against what it receives.
+    pub fn sign(
+        &self,
+        method: &str,
+        url: &url::Url,
+        payload_sha256: &str,
+        now: DateTime<Utc>,
+    ) -> Vec<(&'static str, String)> {
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+
+        let host = match url.port() {
+            Some(port) => format!("{}:{}", url.host_str().unwrap_or_default(), port),
+            None => url.host_str().unwrap_or_default().to_string(),
+        };
+
+        let signed_headers = "host;x-amz-content-sha256;x-amz-date";
+        let canonical_request = format!(
+            "{}\n{}\n{}\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\n{}\n{}",
+            method,
+            url.path(),
+            url.query().unwrap_or_default(),
+            host,
+            payload_sha256,
+            amz_date,
+            signed_headers,
+            payload_sha256,
+        );
+
+        let scope = format!("{}/{}/{}/aws4_request", date, self.region, SERVICE);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            amz_date,
+            scope,
+            crate::archive::to_hex(&Sha256::digest(canonical_request.as_bytes())),
+        );
+
+        let key = hmac_sha256(format!("AWS4{}", self.secret_key).as_bytes(), date.as_bytes());
+        let key = hmac_sha256(&key, self.region.as_bytes());
+        let key = hmac_sha256(&key, SERVICE.as_bytes());
+        let key = hmac_sha256(&key, b"aws4_request");
+        let signature = crate::archive::to_hex(&hmac_sha256(&key, string_to_sign.as_bytes()));
+
+        vec![
+            ("x-amz-date", amz_date),
+            ("x-amz-content-sha256", payload_sha256.to_string()),
+            (
+                "authorization",
+                format!(
+                    "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+                    self.access_key, scope, signed_headers, signature
+                ),
+            ),
+        ]
+    }
+
+    pub async fn delete_object(&self, http: &reqwest::Client, key: &str) -> Result<(), String> {
+        let url = self.object_url(key)?;
+        let empty_sha256 = crate::archive::to_hex(&Sha256::digest(b""));
+
+        let mut request = http.delete(url.clone());
+        for (name, value) in self.sign("DELETE", &url, &empty_sha256, Utc::now()) {
+            request = request.header(name, value);
+        }
+
+        let res = request.send().await.map_err(|e| format!("S3 delete failed: {}", e))?;
+        // Deleting a missing object is not an error
+        if !res.status().is_success() && res.status() != reqwest::StatusCode::NOT_FOUND {
+            return Err(format!("S3 delete failed with HTTP status {}", res.status()));
+        }
+
+        Ok(())
+    }
+}
+
+pub(crate) fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+    let mut block = [0u8; HMAC_BLOCK_SIZE];
+    if key.len() > HMAC_BLOCK_SIZE {
+        block[..32].copy_from_slice(&Sha256::digest(key));
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+
+    let mut inner = Sha256::new();
+    inner.update(block.map(|b| b ^ 0x36));
+    inner.update(message);
+
+    let mut outer = Sha256::new();
+    outer.update(block.map(|b| b ^ 0x5c));
+    outer.update(inner.finalize());
+    outer.finalize().into()
+}
@@ -1,13 +1,73 @@
 use tokio_cron_scheduler::{JobScheduler, Job};
-use crate::{AppState, models::RecordingSchedule};
+use crate::events::AppEvent;
+use crate::{AppState, models::{RecordingSchedule, RecordingMeta, RecordingTrigger, SchedulerSettings}};
 use std::sync::Arc;
 use std::collections::HashMap;
+use std::time::{Duration, Instant};
 use uuid::Uuid;
-use chrono_tz::Asia::Tokyo;
+use chrono::{DateTime, Utc};
+use rusqlite::Connection;
+
+// How often a queued schedule checks for a free recording slot
+const SLOT_POLL_INTERVAL_SECS: u64 = 5;
+// Interrupted runs with less of their window left than this are not resumed
+const MIN_RESUME_SECS: i64 = 60;
 
 pub struct SchedulerManager {
     scheduler: JobScheduler,
-    job_map: Arc<tokio::sync::Mutex<HashMap<i32, Uuid>>>, // schedule_id -> job_uuid
+    job_map: Arc<tokio::sync::Mutex<HashMap<i32, Vec<Uuid>>>>, // schedule_id -> job_uuids (one per weekly block)
+    // Serializes scheduled starts; holds the time of the last one for staggering
+    start_gate: Arc<tokio::sync::Mutex<Option<Instant>>>,
+}
+
+pub fn load_settings(conn: &Connection) -> Result<SchedulerSettings, String> {
+    conn.query_row(
+        "SELECT id, max_concurrent_recordings, max_defer_minutes, stagger_seconds, schedules_enabled
+         FROM scheduler_settings WHERE id = 1",
+        [],
+        |row| {
+            Ok(SchedulerSettings {
+                id: row.get(0)?,
+                maxConcurrentRecordings: row.get(1)?,
+                maxDeferMinutes: row.get(2)?,
+                staggerSeconds: row.get(3)?,
+                schedulesEnabled: row.get(4)?,
+            })
+        },
+    ).map_err(|e| e.to_string())
+}
+
+/// Pause or resume every schedule. Paused schedules stay registered but skip their runs;
+/// recordings already started by a schedule run to their planned end.
+pub fn set_schedules_enabled(conn: &Connection, enabled: bool) -> Result<(), String> {
+    conn.execute("UPDATE scheduler_settings SET schedules_enabled = ?1 WHERE id = 1", [enabled])
+        .map_err(|e| e.to_string())?;
+    println!("[Scheduler] Schedules {}", if enabled { "resumed" } else { "paused" });
+    Ok(())
+}
+
+// Cameras a schedule runs on: its camera, or the members of its group
+fn target_cameras(db_path: &str, camera_id: Option<i32>, group_id: Option<i64>) -> Result<Vec<i32>, String> {
+    match (camera_id, group_id) {
+        (Some(camera_id), _) => Ok(vec![camera_id]),
+        (None, Some(group_id)) => {
+            let conn = crate::db::open(db_path).map_err(|e| e.to_string())?;
+            let members = crate::repository::CameraGroupRepo::members(&conn, group_id)?;
+            if members.is_empty() {
+                return Err(format!("camera group {} is empty", group_id));
+            }
+            Ok(members)
+        }
+        (None, None) => Err("no camera or camera group set".to_string()),
+    }
+}
+
+// Checked each time a schedule fires, so the persisted flag also holds after a restart
+fn schedules_paused(db_path: &str) -> bool {
+    crate::db::open(db_path).map_err(|e| e.to_string())
+        .and_then(|conn| load_settings(&conn))
+        .map(|settings| !settings.schedulesEnabled)
+        .unwrap_or(false)
 }
 
 impl SchedulerManager {
@@ -23,78 +83,116 @@ impl SchedulerManager {
         Ok(Self {
             scheduler,
             job_map: Arc::new(tokio::sync::Mutex::new(HashMap::new())),
+            start_gate: Arc::new(tokio::sync::Mutex::new(None)),
         })
     }
 
     pub async fn add_schedule(
         &self,
         schedule: RecordingSchedule,
-        state: Arc<AppState>
-    ) -> Result<Uuid, String> {
+        state: AppState
+    ) -> Result<Vec<Uuid>, String> {
         let schedule_id = schedule.id;
         let camera_id = schedule.camera_id;
-        let duration = schedule.duration_minutes;
+        let group_id = schedule.group_id;
         let fps = schedule.fps;
-        let cron_expr = schedule.cron_expression.clone();
         let name = schedule.name.clone();
+        let is_snapshot = schedule.action == "snapshot";
+        let retention_days = schedule.retention_days;
 
-        println!("[Scheduler] Adding schedule '{}' (ID: {}) with cron: {}", name, schedule_id, cron_expr);
+        // Weekly schedules compile to one job per time block
+        let triggers = crate::weekly_schedule::triggers_for(&schedule)?;
+        let mut job_ids = Vec::new();
 
-        let job = Job::new_async_tz(cron_expr.as_str(), Tokyo, move |_uuid, _lock| {
-            let state_clone = state.clone();
-            let camera_id = camera_id;
-            let duration = duration;
-            let fps = fps;
+        for (cron_expr, duration) in triggers {
+            println!("[Scheduler] Adding schedule '{}' (ID: {}) with cron: {} ({} minutes)", name, schedule_id, cron_expr, duration);
+
+            let state = state.clone();
+            let start_gate = self.start_gate.clone();
             let name = name.clone();
 
-            Box::pin(async move {
-                println!("[Scheduler] Executing schedule '{}' for camera {}", name, camera_id);
-
-                // Start scheduled recording
-                if let Err(e) = start_scheduled_recording(
-                    state_clone.clone(),
-                    camera_id,
-                    duration,
-                    fps
-                ).await {
-                    eprintln!("[Scheduler] Failed to start recording for '{}': {}", name, e);
-                    return;
-                }
+            let job = Job::new_async_tz(cron_expr.as_str(), crate::settings::timezone(), move |_uuid, _lock| {
+                let state_clone = state.clone();
+                let start_gate = start_gate.clone();
+                let name = name.clone();
 
-                println!("[Scheduler] Recording started for '{}', will stop after {} minutes", name, duration);
+                Box::pin(async move {
+                    // Group schedules run on the group's current members, each camera on its own
+                    let camera_ids = match target_cameras(&state_clone.db_path, camera_id, group_id) {
+                        Ok(ids) => ids,
+                        Err(e) => {
+                            eprintln!("[Scheduler] Schedule '{}' has no cameras to run on: {}", name, e);
+                            return;
+                        }
+                    };
 
-                // Wait for duration and then stop
-                tokio::time::sleep(tokio::time::Duration::from_secs((duration * 60) as u64)).await;
+                    if schedules_paused(&state_clone.db_path) {
+                        println!("[Scheduler] Skipping schedule '{}': schedules are paused", name);
+                        let planned_stop = Utc::now() + chrono::Duration::minutes(duration as i64);
+                        for camera_id in camera_ids {
+                            let reason = "Schedules are paused".to_string();
+                            let run_id = record_run(&state_clone.db_path, schedule_id, camera_id, planned_stop, None).ok();
+                            update_run(&state_clone.db_path, run_id, "deferred", Some(&reason));
+                            state_clone.events.emit(AppEvent::ScheduleDeferred { schedule_id, camera_id, name: name.clone(), reason });
+                        }
+                        return;
+                    }
 
-                if let Err(e) = stop_scheduled_recording(state_clone.clone(), camera_id).await {
-                    eprintln!("[Scheduler] Failed to stop recording for '{}': {}", name, e);
-                } else {
-                    println!("[Scheduler] Recording completed for '{}'", name);
-                }
-            })
-        }).map_err(|e| format!("Failed to create job: {}", e))?;
+                    let planned_stop = Utc::now() + chrono::Duration::minutes(duration as i64);
+                    let mut runs = Vec::new();
+                    for camera_id in camera_ids {
+                        println!("[Scheduler] Executing schedule '{}' for camera {}", name, camera_id);
+                        state_clone.events.emit(AppEvent::ScheduleFired { schedule_id, camera_id, name: name.clone() });
+
+                        // Snapshot schedules take a single frame and don't use recording slots
+                        if is_snapshot {
+                            crate::snapshots::run_scheduled_capture(&state_clone, schedule_id, camera_id, retention_days, &name).await;
+                            continue;
+                        }
 
-        let job_id = job.guid();
+                        let run_id = match record_run(&state_clone.db_path, schedule_id, camera_id, planned_stop, None) {
+                            Ok(id) => Some(id),
+                            Err(e) => {
+                                eprintln!("[Scheduler] Failed to record run of '{}': {}", name, e);
+                                None
+                            }
+                        };
+                        runs.push((camera_id, run_id));
+                    }
 
-        self.scheduler.add(job).await
-            .map_err(|e| format!("Failed to add job to scheduler: {}", e))?;
+                    // The start gate still serializes the starts and applies the concurrency limit
+                    futures::future::join_all(runs.into_iter().map(|(camera_id, run_id)| {
+                        execute_run(state_clone.clone(), &start_gate, schedule_id, camera_id, &name, fps, planned_stop, run_id)
+                    })).await;
+                })
+            }).map_err(|e| format!("Failed to create job: {}", e))?;
+
+            let job_id = job.guid();
+
+            self.scheduler.add(job).await
+                .map_err(|e| format!("Failed to add job to scheduler: {}", e))?;
+
+            job_ids.push(job_id);
+        }
 
         // Store the mapping
         let mut map = self.job_map.lock().await;
-        map.insert(schedule_id, job_id);
+        map.insert(schedule_id, job_ids.clone());
 
-        println!("[Scheduler] Schedule added successfully: {} -> {}", schedule_id, job_id);
+        println!("[Scheduler] Schedule added successfully: {} -> {:?}", schedule_id, job_ids);
 
-        Ok(job_id)
+        Ok(job_ids)
     }
 
     pub async fn remove_schedule(&self, schedule_id: i32) -> Result<(), String> {
         let mut map = self.job_map.lock().await;
 
-        if let Some(job_id) = map.remove(&schedule_id) {
-            println!("[Scheduler] Removing schedule {} (job {})", schedule_id, job_id);
-            self.scheduler.remove(&job_id).await
-                .map_err(|e| format!("Failed to remove job from scheduler: {}", e))?;
+        if let Some(job_ids) = map.remove(&schedule_id) {
+            for job_id in job_ids {
+                println!("[Scheduler] Removing schedule {} (job {})", schedule_id, job_id);
+                self.scheduler.remove(&job_id).await
+                    .map_err(|e| format!("Failed to remove job from scheduler: {}", e))?;
+            }
             println!("[Scheduler] Schedule removed successfully");
             Ok(())
         } else {
@@ -102,31 +200,275 @@ impl SchedulerManager {
         }
     }
 
-    pub async fn get_job_id(&self, schedule_id: i32) -> Option<Uuid> {
+    pub async fn get_job_ids(&self, schedule_id: i32) -> Vec<Uuid> {
         let map = self.job_map.lock().await;
-        map.get(&schedule_id).copied()
+        map.get(&schedule_id).cloned().unwrap_or_default()
+    }
+
+    /// Handle runs left unfinished by the previous session (app closed or crashed mid-recording).
+    /// Any partial recording is finalized; if the schedule is still enabled and enough of its
+    /// window remains (and schedules aren't paused), recording resumes until the planned stop
+    /// time. Otherwise the run is marked interrupted.
+    pub async fn resume_interrupted_runs(&self, state: AppState) -> Result<(), String> {
+        let paused = schedules_paused(&state.db_path);
+        let runs = {
+            let conn = crate::db::open(&state.db_path).map_err(|e| e.to_string())?;
+            let mut stmt = conn.prepare(
+                "SELECT r.id, r.schedule_id, r.camera_id, r.status, r.planned_stop_at, s.name, s.fps, s.is_enabled
+                 FROM schedule_runs r
+                 LEFT JOIN recording_schedules s ON s.id = r.schedule_id
+                 WHERE r.status IN ('pending', 'queued', 'recording')"
+            ).map_err(|e| e.to_string())?;
+            let runs = stmt.query_map([], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, i32>(1)?,
+                    row.get::<_, i32>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, Option<String>>(4)?,
+                    row.get::<_, Option<String>>(5)?,
+                    row.get::<_, Option<i32>>(6)?,
+                    row.get::<_, Option<bool>>(7)?,
+                ))
+            }).map_err(|e| e.to_string())?
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| e.to_string())?;
+            runs
+        };
+
+        for (run_id, schedule_id, camera_id, status, planned_stop, name, fps, enabled) in runs {
+            let name = name.unwrap_or_else(|| format!("schedule {}", schedule_id));
+
+            if status == "recording" {
+                // Remux whatever the killed FFmpeg left behind into a normal recording
+                if let Err(e) = stop_scheduled_recording(state.clone(), camera_id).await {
+                    eprintln!("[Scheduler] Failed to finalize interrupted recording of '{}': {}", name, e);
+                }
+            }
+
+            let planned_stop = planned_stop
+                .and_then(|t| chrono::DateTime::parse_from_rfc3339(&t).ok())
+                .map(|t| t.with_timezone(&Utc));
+            let remaining = planned_stop.map(|stop| stop - Utc::now()).unwrap_or_else(chrono::Duration::zero);
+
+            if !enabled.unwrap_or(false) || paused || remaining < chrono::Duration::seconds(MIN_RESUME_SECS) {
+                let detail = if paused {
+                    "App restarted while schedules were paused"
+                } else if status == "recording" {
+                    "App restarted during the recording; partial recording was finalized"
+                } else {
+                    "App restarted before the recording started"
+                };
+                println!("[Scheduler] Run {} of '{}' interrupted: {}", run_id, name, detail);
+                update_run(&state.db_path, Some(run_id), "interrupted", Some(detail));
+                continue;
+            }
+
+            let Some(planned_stop) = planned_stop else { continue };
+            let minutes = (remaining.num_seconds() + 59) / 60;
+            println!("[Scheduler] Resuming '{}' for the remaining {} minutes", name, minutes);
+            update_run(
+                &state.db_path,
+                Some(run_id),
+                "interrupted",
+                Some(&format!("App restarted; resumed for the remaining {} minutes", minutes)),
+            );
+
+            let new_run_id = record_run(&state.db_path, schedule_id, camera_id, planned_stop, Some("Resumed after restart")).ok();
+            let state = state.clone();
+            let start_gate = self.start_gate.clone();
+            tauri::async_runtime::spawn(async move {
+                execute_run(state, &start_gate, schedule_id, camera_id, &name, fps, planned_stop, new_run_id).await;
+            });
+        }
+
+        Ok(())
+    }
+}
+
+// Start a run (waiting for a free slot if needed), record until `planned_stop`, then stop it
+#[allow(clippy::too_many_arguments)]
+async fn execute_run(
+    state: AppState,
+    start_gate: &tokio::sync::Mutex<Option<Instant>>,
+    schedule_id: i32,
+    camera_id: i32,
+    name: &str,
+    fps: Option<i32>,
+    planned_stop: DateTime<Utc>,
+    run_id: Option<i64>,
+) {
+    // Wait for a free slot if too many recordings are running
+    match acquire_slot_and_start(&state, start_gate, schedule_id, camera_id, fps, planned_stop, run_id).await {
+        Ok(SlotOutcome::Started) => {}
+        Ok(SlotOutcome::Deferred(reason)) => {
+            eprintln!("[Scheduler] Schedule '{}' deferred: {}", name, reason);
+            update_run(&state.db_path, run_id, "deferred", Some(&reason));
+            state.events.emit(AppEvent::ScheduleDeferred { schedule_id, camera_id, name: name.to_string(), reason });
+            return;
+        }
+        Err(e) => {
+            eprintln!("[Scheduler] Failed to start recording for '{}': {}", name, e);
+            update_run(&state.db_path, run_id, "failed", Some(&e));
+            return;
+        }
+    }
+
+    println!("[Scheduler] Recording started for '{}', will stop at {}", name, planned_stop.with_timezone(&crate::settings::timezone()));
+    state.active_scheduled_recordings.lock().await.insert((schedule_id, camera_id));
+
+    // Stop at the planned end, even if the start was delayed
+    let remaining = (planned_stop - Utc::now()).to_std().unwrap_or_default();
+    tokio::time::sleep(remaining).await;
+
+    state.active_scheduled_recordings.lock().await.remove(&(schedule_id, camera_id));
+
+    if let Err(e) = stop_scheduled_recording(state.clone(), camera_id).await {
+        eprintln!("[Scheduler] Failed to stop recording for '{}': {}", name, e);
+        update_run(&state.db_path, run_id, "failed", Some(&e));
+    } else {
+        println!("[Scheduler] Recording completed for '{}'", name);
+        update_run(&state.db_path, run_id, "completed", None);
+    }
+}
+
+enum SlotOutcome {
+    Started,
+    Deferred(String),
+}
+
+// Start the recording once fewer than max_concurrent_recordings are running.
+// Starts are serialized and spaced by stagger_seconds so encoders don't all spin up at once.
+// Gives up after max_defer_minutes (or at the planned stop, if sooner).
+async fn acquire_slot_and_start(
+    state: &AppState,
+    start_gate: &tokio::sync::Mutex<Option<Instant>>,
+    schedule_id: i32,
+    camera_id: i32,
+    fps: Option<i32>,
+    planned_stop: DateTime<Utc>,
+    run_id: Option<i64>,
+) -> Result<SlotOutcome, String> {
+    let fired_at = Instant::now();
+    let mut queued = false;
+
+    loop {
+        let settings = {
+            let conn = crate::db::open(&state.db_path).map_err(|e| e.to_string())?;
+            load_settings(&conn)?
+        };
+        let window = (planned_stop.signed_duration_since(Utc::now())).to_std().unwrap_or_default() + fired_at.elapsed();
+        let max_wait = Duration::from_secs((settings.maxDeferMinutes.max(0) * 60) as u64).min(window);
+
+        {
+            let mut last_start = start_gate.lock().await;
+            let active = state.recording_processes.lock().map_err(|e| e.to_string())?.len();
+
+            if settings.maxConcurrentRecordings.is_none_or(|max| active < max.max(1) as usize) {
+                let stagger = Duration::from_secs(settings.staggerSeconds.max(0) as u64);
+                if let Some(wait) = last_start.and_then(|t| stagger.checked_sub(t.elapsed())) {
+                    tokio::time::sleep(wait).await;
+                }
+
+                let result = start_scheduled_recording(state.clone(), schedule_id, camera_id, fps).await;
+                *last_start = Some(Instant::now());
+                result?;
+
+                let delay = fired_at.elapsed().as_secs();
+                let detail = (delay > 0).then(|| format!("Started {}s late", delay));
+                mark_run_started(&state.db_path, run_id, detail.as_deref());
+                return Ok(SlotOutcome::Started);
+            }
+
+            if fired_at.elapsed() >= max_wait {
+                return Ok(SlotOutcome::Deferred(format!(
+                    "{} recordings already running (limit {}), no slot freed within {} minutes",
+                    active,
+                    settings.maxConcurrentRecordings.unwrap_or_default(),
+                    max_wait.as_secs() / 60
+                )));
+            }
+
+            if !queued {
+                println!(
+                    "[Scheduler] Schedule {} queued: {} recordings running (limit {})",
+                    schedule_id, active, settings.maxConcurrentRecordings.unwrap_or_default()
+                );
+                update_run(&state.db_path, run_id, "queued", None);
+                queued = true;
+            }
+        }
+
+        tokio::time::sleep(Duration::from_secs(SLOT_POLL_INTERVAL_SECS)).await;
+    }
+}
+
+// Schedule history (schedule_runs table)
+fn record_run(
+    db_path: &str,
+    schedule_id: i32,
+    camera_id: i32,
+    planned_stop: DateTime<Utc>,
+    detail: Option<&str>,
+) -> Result<i64, String> {
+    let conn = crate::db::open(db_path).map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT INTO schedule_runs (schedule_id, camera_id, scheduled_at, planned_stop_at, status, detail)
+         VALUES (?1, ?2, ?3, ?4, 'pending', ?5)",
+        rusqlite::params![schedule_id, camera_id, Utc::now().to_rfc3339(), planned_stop.to_rfc3339(), detail],
+    ).map_err(|e| e.to_string())?;
+    Ok(conn.last_insert_rowid())
+}
+
+fn mark_run_started(db_path: &str, run_id: Option<i64>, detail: Option<&str>) {
+    let Some(run_id) = run_id else { return };
+    let result = crate::db::open(db_path).and_then(|conn| {
+        conn.execute(
+            "UPDATE schedule_runs SET status = 'recording', started_at = ?1, detail = ?2 WHERE id = ?3",
+            rusqlite::params![Utc::now().to_rfc3339(), detail, run_id],
+        )
+    });
+    if let Err(e) = result {
+        eprintln!("[Scheduler] Failed to update schedule run {}: {}", run_id, e);
+    }
+}
+
+fn update_run(db_path: &str, run_id: Option<i64>, status: &str, detail: Option<&str>) {
+    let Some(run_id) = run_id else { return };
+    let finished = matches!(status, "completed" | "deferred" | "failed" | "interrupted");
+    let result = crate::db::open(db_path).and_then(|conn| {
+        conn.execute(
+            "UPDATE schedule_runs SET status = ?1, detail = COALESCE(?2, detail),
+                    finished_at = CASE WHEN ?3 THEN ?4 ELSE finished_at END
+             WHERE id = ?5",
+            rusqlite::params![status, detail, finished, Utc::now().to_rfc3339(), run_id],
+        )
+    });
+    if let Err(e) = result {
+        eprintln!("[Scheduler] Failed to update schedule run {}: {}", run_id, e);
     }
 }
 
 // Helper function to start scheduled recording
 async fn start_scheduled_recording(
-    state: Arc<AppState>,
+    state: AppState,
+    schedule_id: i32,
     camera_id: i32,
-    _duration_minutes: i32,
     fps: Option<i32>
 ) -> Result<(), String> {
     // Directly call the stream function with state components
     crate::stream::start_recording_with_options_direct(
         &state,
         camera_id,
-        fps
-    ).await
+        fps,
+        RecordingMeta::new(RecordingTrigger::Scheduled(schedule_id))
+    ).await.map(|_| ())
 }
 
 // Helper function to stop scheduled recording
 async fn stop_scheduled_recording(
-    state: Arc<AppState>,
+    state: AppState,
     camera_id: i32
 ) -> Result<(), String> {
-    crate::stream::stop_recording_direct(&state, camera_id, Some(&state.app_handle)).await
+    crate::stream::stop_recording_direct(&state, camera_id).await
 }
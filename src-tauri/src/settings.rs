@@ -0,0 +1,628 @@
+use chrono::Utc;
+use chrono_tz::Tz;
+use rusqlite::{Connection, OptionalExtension};
+use serde_json::{Map, Value};
+use std::sync::RwLock;
+
+pub const SERVER_PORT: &str = "server.port";
+pub const SERVER_LAN_MODE: &str = "server.lanMode";
+pub const SERVER_DOWNLOAD_LIMIT: &str = "server.downloadLimitKbps";
+pub const TIMEZONE: &str = "general.timezone";
+pub const HLS_SEGMENT_SECS: &str = "hls.segmentSecs";
+pub const HLS_LIST_SIZE: &str = "hls.listSize";
+pub const HLS_DELETE_THRESHOLD: &str = "hls.deleteThreshold";
+pub const HLS_SEGMENT_DIR: &str = "hls.segmentDir";
+pub const HLS_TIMESHIFT_MINUTES: &str = "hls.timeshiftMinutes";
+pub const HLS_STALL_SEGMENTS: &str = "hls.stallSegments";
+pub const HLS_RENDITION_HEIGHTS: &str = "hls.renditionHeights";
+pub const CLOUD_RETENTION_DAYS: &str = "cloudUpload.retentionDays";
+pub const AUTH_REQUIRED: &str = "auth.required";
+pub const ENCODER_LOAD_BUDGET: &str = "encoder.loadBudget";
+pub const RECORDINGS_PURGE_MISSING: &str = "recordings.purgeMissing";
+pub const REPORTS_DIGEST: &str = "reports.digest";
+pub const REPORTS_WEBHOOK_URL: &str = "reports.webhookUrl";
+pub const MONITORING_RESOURCE_INTERVAL: &str = "monitoring.resourceIntervalSecs";
+pub const ENCODER_QUALITY_PRESET: &str = "encoder.qualityPreset";
+pub const ENCODER_GPU_SESSION_LIMIT: &str = "encoder.gpuSessionLimit";
+pub const TRIGGERS_TOKEN: &str = "triggers.token";
+pub const TRIGGERS_ALLOW_REMOTE: &str = "triggers.allowRemote";
+pub const TRIGGERS_REMOTE_PORT: &str = "triggers.remotePort";
+pub const TRIGGERS_DEFAULT_RECORD_MINUTES: &str = "triggers.defaultRecordMinutes";
+pub const MQTT_ENABLED: &str = "mqtt.enabled";
+pub const MQTT_HOST: &str = "mqtt.host";
+pub const MQTT_PORT: &str = "mqtt.port";
+pub const MQTT_USERNAME: &str = "mqtt.username";
+pub const MQTT_PASSWORD: &str = "mqtt.password";
+pub const MQTT_CLIENT_ID: &str = "mqtt.clientId";
+pub const MQTT_DISCOVERY_PREFIX: &str = "mqtt.discoveryPrefix";
+pub const MQTT_BASE_TOPIC: &str = "mqtt.baseTopic";
+
+enum Kind {
+    Bool { default: bool },
+    Int { min: i64, max: i64, default: i64 },
+    // Positive whole number, or null when unset; 0 is stored as null too
+    OptionalCount,
+    // IANA name such as "Asia/Tokyo"
+    Timezone { default: &'static str },
+    // Absolute directory the app can write to, or null
+    OptionalDir,
+    // One of a fixed set of strings
+    Choice { options: &'static [&'static str], default: &'static str },
+    // http(s) URL, or null
+    OptionalUrl,
+    // Distinct whole numbers, stored largest first; empty by default
+    IntList { min: i64, max: i64, max_len: usize },
+    // Trimmed text, or null when empty
+    OptionalText,
+    // Trimmed, non-empty text
+    Text { default: &'static str },
+    // MQTT topic prefix: non-empty and without wildcards
+    Topic { default: &'static str },
+    // Password: like OptionalText, but never listed by `load_all`
+    OptionalSecret,
+    // Random token generated by `init`, at least 16 characters; never listed by `load_all`
+    Token,
+}
+
+struct Definition {
+    key: &'static str,
+    label: &'static str,
+    kind: Kind,
+    // Read once at startup
+    restart_required: bool,
+}
+
+const DEFINITIONS: &[Definition] = &[
+    Definition {
+        key: SERVER_PORT,
+        label: "Server port",
+        kind: Kind::Int { min: 1024, max: 65535, default: 3333 },
+        restart_required: true,
+    },
+    Definition {
+        key: SERVER_LAN_MODE,
+        label: "Serve streams to the local network",
+        // Other hosts then need signed tokens for the HLS streams (see stream_tokens)
+        kind: Kind::Bool { default: false },
+        restart_required: true,
+    },
+    Definition {
+        key: SERVER_DOWNLOAD_LIMIT,
+        label: "Download limit",
+        // Per-download bandwidth cap of /recordings/{id}/download in KB/s
+        kind: Kind::OptionalCount,
+        restart_required: false,
+    },
+    Definition {
+        key: TIMEZONE,
+        label: "Time zone",
+        kind: Kind::Timezone { default: "Asia/Tokyo" },
+        // Cron schedules are registered with the zone they were created in
+        restart_required: true,
+    },
+    Definition {
+        key: HLS_SEGMENT_SECS,
+        label: "Segment duration",
+        kind: Kind::Int { min: crate::hls::SEGMENT_SECS_RANGE.0 as i64, max: crate::hls::SEGMENT_SECS_RANGE.1 as i64, default: 2 },
+        restart_required: false,
+    },
+    Definition {
+        key: HLS_LIST_SIZE,
+        label: "Playlist size",
+        kind: Kind::Int { min: crate::hls::LIST_SIZE_RANGE.0 as i64, max: crate::hls::LIST_SIZE_RANGE.1 as i64, default: 15 },
+        restart_required: false,
+    },
+    Definition {
+        key: HLS_DELETE_THRESHOLD,
+        label: "Delete threshold",
+        kind: Kind::Int { min: crate::hls::DELETE_THRESHOLD_RANGE.0 as i64, max: crate::hls::DELETE_THRESHOLD_RANGE.1 as i64, default: 3 },
+        restart_required: false,
+    },
+    Definition {
+        key: HLS_SEGMENT_DIR,
+        label: "Segment location",
+        kind: Kind::OptionalDir,
+        restart_required: false,
+    },
+    Definition {
+        key: HLS_TIMESHIFT_MINUTES,
+        label: "Timeshift window",
+        kind: Kind::Int { min: crate::hls::TIMESHIFT_MINUTES_RANGE.0 as i64, max: crate::hls::TIMESHIFT_MINUTES_RANGE.1 as i64, default: 0 },
+        restart_required: false,
+    },
+    Definition {
+        key: HLS_STALL_SEGMENTS,
+        label: "Stall threshold",
+        // Segment durations without a new segment before a live stream is restarted, 0 for never
+        kind: Kind::Int { min: 0, max: 30, default: 5 },
+        restart_required: false,
+    },
+    Definition {
+        key: HLS_RENDITION_HEIGHTS,
+        label: "Extra renditions",
+        // Heights of lower-resolution variants next to the source one, for adaptive playback
+        // through master.m3u8; empty for a single rendition
+        kind: Kind::IntList { min: 144, max: 1080, max_len: 3 },
+        restart_required: false,
+    },
+    Definition {
+        key: CLOUD_RETENTION_DAYS,
+        label: "Upload retention days",
+        kind: Kind::OptionalCount,
+        restart_required: false,
+    },
+    Definition {
+        key: AUTH_REQUIRED,
+        label: "Require login",
+        kind: Kind::Bool { default: false },
+        restart_required: false,
+    },
+    Definition {
+        key: ENCODER_LOAD_BUDGET,
+        label: "Encoding load budget",
+        // Megapixels per second all streams and recordings may encode together; lower-priority
+        // live streams are downgraded above it (see budget). 0 for no budget.
+        kind: Kind::Int { min: 0, max: 100_000, default: 0 },
+        restart_required: false,
+    },
+    Definition {
+        key: RECORDINGS_PURGE_MISSING,
+        label: "Remove recordings whose files were deleted",
+        kind: Kind::Bool { default: false },
+        restart_required: false,
+    },
+    Definition {
+        key: REPORTS_DIGEST,
+        label: "Activity digest",
+        // Sent for the previous day or week, see report::spawn_digest
+        kind: Kind::Choice { options: &["off", "daily", "weekly"], default: "off" },
+        restart_required: false,
+    },
+    Definition {
+        key: REPORTS_WEBHOOK_URL,
+        label: "Digest webhook",
+        kind: Kind::OptionalUrl,
+        restart_required: false,
+    },
+    Definition {
+        key: ENCODER_QUALITY_PRESET,
+        label: "Quality preset",
+        // Cameras without their own preset; "custom" uses the individual encoder and HLS settings
+        kind: Kind::Choice { options: &crate::encoder::QUALITY_PRESET_NAMES, default: "custom" },
+        restart_required: false,
+    },
+    Definition {
+        key: ENCODER_GPU_SESSION_LIMIT,
+        label: "GPU encoder sessions",
+        // Concurrent GPU encodes (NVENC allows 8 on consumer cards); extra renditions beyond it
+        // are encoded on the CPU. 0 for no limit.
+        kind: Kind::Int { min: 0, max: 64, default: 8 },
+        restart_required: false,
+    },
+    Definition {
+        key: MONITORING_RESOURCE_INTERVAL,
+        label: "Resource usage interval",
+        // Seconds between resource-usage events, 0 for off
+        kind: Kind::Int { min: 0, max: 300, default: 10 },
+        restart_required: false,
+    },
+    Definition {
+        key: TRIGGERS_TOKEN,
+        label: "Trigger token",
+        // Accepted by /api/triggers across launches
+        kind: Kind::Token,
+        restart_required: false,
+    },
+    Definition {
+        key: TRIGGERS_ALLOW_REMOTE,
+        label: "Accept remote triggers",
+        // The trigger listener then also binds all interfaces
+        kind: Kind::Bool { default: false },
+        restart_required: true,
+    },
+    Definition {
+        key: TRIGGERS_REMOTE_PORT,
+        label: "Remote trigger port",
+        kind: Kind::Int { min: 1024, max: 65535, default: 3334 },
+        restart_required: true,
+    },
+    Definition {
+        key: TRIGGERS_DEFAULT_RECORD_MINUTES,
+        label: "Default recording duration",
+        // Minutes recorded when a trigger doesn't give a duration
+        kind: Kind::Int { min: 1, max: 24 * 60, default: 5 },
+        restart_required: false,
+    },
+    Definition {
+        key: MQTT_ENABLED,
+        label: "MQTT",
+        kind: Kind::Bool { default: false },
+        restart_required: false,
+    },
+    Definition {
+        key: MQTT_HOST,
+        label: "MQTT broker",
+        kind: Kind::OptionalText,
+        restart_required: false,
+    },
+    Definition {
+        key: MQTT_PORT,
+        label: "MQTT port",
+        kind: Kind::Int { min: 1, max: 65535, default: 1883 },
+        restart_required: false,
+    },
+    Definition {
+        key: MQTT_USERNAME,
+        label: "MQTT user name",
+        kind: Kind::OptionalText,
+        restart_required: false,
+    },
+    Definition {
+        key: MQTT_PASSWORD,
+        label: "MQTT password",
+        kind: Kind::OptionalSecret,
+        restart_required: false,
+    },
+    Definition {
+        key: MQTT_CLIENT_ID,
+        label: "MQTT client ID",
+        kind: Kind::Text { default: "onvif-viewer" },
+        restart_required: false,
+    },
+    Definition {
+        key: MQTT_DISCOVERY_PREFIX,
+        label: "Discovery prefix",
+        // Home Assistant's MQTT discovery prefix
+        kind: Kind::Topic { default: "homeassistant" },
+        restart_required: false,
+    },
+    Definition {
+        key: MQTT_BASE_TOPIC,
+        label: "Base topic",
+        // State and command topics live under <base topic>/<camera id>/
+        kind: Kind::Topic { default: "onvif_viewer" },
+        restart_required: false,
+    },
+];
+
+// Shortest trigger token accepted when one is set by hand
+const MIN_TOKEN_LEN: usize = 16;
+
+// Zone used for file names, schedules and log output; kept in memory since formatting code has
+// no database connection at hand
+static TIMEZONE_CACHE: RwLock<Tz> = RwLock::new(chrono_tz::Asia::Tokyo);
+
+fn definition(key: &str) -> Result<&'static Definition, String> {
+    DEFINITIONS.iter().find(|d| d.key == key).ok_or_else(|| format!("Unknown setting: {}", key))
+}
+
+impl Definition {
+    fn default_value(&self) -> Value {
+        match self.kind {
+            Kind::Bool { default } => Value::from(default),
+            Kind::Int { default, .. } => Value::from(default),
+            Kind::Timezone { default } => Value::from(default),
+            Kind::Choice { default, .. } => Value::from(default),
+            Kind::Text { default } | Kind::Topic { default } => Value::from(default),
+            Kind::OptionalCount | Kind::OptionalDir | Kind::OptionalUrl | Kind::OptionalText | Kind::OptionalSecret | Kind::Token => Value::Null,
+            Kind::IntList { .. } => Value::Array(Vec::new()),
+        }
+    }
+
+    fn is_secret(&self) -> bool {
+        matches!(self.kind, Kind::OptionalSecret | Kind::Token)
+    }
+
+    // Checked and normalized value to store
+    fn validate(&self, value: &Value) -> Result<Value, String> {
+        match self.kind {
+            Kind::Bool { .. } => value.as_bool().map(Value::from).ok_or_else(|| format!("{} must be true or false", self.label)),
+            Kind::Int { min, max, .. } => {
+                let number = value.as_i64().ok_or_else(|| format!("{} must be a whole number", self.label))?;
+                if !(min..=max).contains(&number) {
+                    return Err(format!("{} must be between {} and {}", self.label, min, max));
+                }
+                Ok(Value::from(number))
+            }
+            Kind::OptionalCount => match value {
+                Value::Null => Ok(Value::Null),
+                _ => match value.as_i64() {
+                    Some(count) if count < 0 => Err(format!("{} must not be negative", self.label)),
+                    Some(0) => Ok(Value::Null),
+                    Some(count) => Ok(Value::from(count)),
+                    None => Err(format!("{} must be a whole number", self.label)),
+                },
+            },
+            Kind::Timezone { .. } => {
+                let name = value.as_str().map(str::trim).ok_or_else(|| format!("{} must be a string", self.label))?;
+                name.parse::<Tz>().map_err(|_| format!("Unknown time zone: {}", name))?;
+                Ok(Value::from(name))
+            }
+            Kind::OptionalDir => match value {
+                Value::Null => Ok(Value::Null),
+                Value::String(dir) if dir.trim().is_empty() => Ok(Value::Null),
+                Value::String(dir) => {
+                    crate::hls::check_segment_dir(dir.trim())?;
+                    Ok(Value::from(dir.trim()))
+                }
+                _ => Err(format!("{} must be a path", self.label)),
+            },
+            Kind::Choice { options, .. } => match value.as_str() {
+                Some(choice) if options.contains(&choice) => Ok(Value::from(choice)),
+                _ => Err(format!("{} must be one of {}", self.label, options.join(", "))),
+            },
+            Kind::OptionalUrl => match value {
+                Value::Null => Ok(Value::Null),
+                Value::String(text) if text.trim().is_empty() => Ok(Value::Null),
+                Value::String(text) => match url::Url::parse(text.trim()) {
+                    Ok(parsed) if matches!(parsed.scheme(), "http" | "https") => Ok(Value::from(text.trim())),
+                    _ => Err(format!("{} must be an http or https URL", self.label)),
+                },
+                _ => Err(format!("{} must be a URL", self.label)),
+            },
+            Kind::IntList { min, max, max_len } => {
+                let items = value.as_array().ok_or_else(|| format!("{} must be a list of numbers", self.label))?;
+                let mut numbers = items.iter()
+                    .map(|item| match item.as_i64() {
+                        Some(number) if (min..=max).contains(&number) => Ok(number),
+                        _ => Err(format!("{} must be whole numbers between {} and {}", self.label, min, max)),
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                numbers.sort_unstable_by(|a, b| b.cmp(a));
+                numbers.dedup();
+                if numbers.len() > max_len {
+                    return Err(format!("{} can have at most {} entries", self.label, max_len));
+                }
+                Ok(Value::from(numbers))
+            }
+            Kind::OptionalText | Kind::OptionalSecret => match value {
+                Value::Null => Ok(Value::Null),
+                Value::String(text) if text.trim().is_empty() => Ok(Value::Null),
+                Value::String(text) => Ok(Value::from(text.trim())),
+                _ => Err(format!("{} must be text", self.label)),
+            },
+            Kind::Text { .. } => match value.as_str().map(str::trim) {
+                Some(text) if !text.is_empty() => Ok(Value::from(text)),
+                _ => Err(format!("{} must not be empty", self.label)),
+            },
+            Kind::Topic { .. } => match value.as_str().map(str::trim) {
+                Some(topic) if !topic.is_empty() && !topic.contains(['+', '#']) => Ok(Value::from(topic)),
+                _ => Err(format!("{} must not be empty or contain MQTT wildcards", self.label)),
+            },
+            Kind::Token => match value.as_str().map(str::trim) {
+                Some(token) if token.len() >= MIN_TOKEN_LEN && !token.contains(char::is_whitespace) => Ok(Value::from(token)),
+                _ => Err(format!("{} must be at least {} characters without spaces", self.label, MIN_TOKEN_LEN)),
+            },
+        }
+    }
+
+    // A stored value is only re-checked cheaply (a removed segment directory shouldn't hide
+    // the setting); anything of the wrong shape falls back to the default
+    fn validate_stored(&self, value: Value) -> Value {
+        let valid = match self.kind {
+            Kind::OptionalDir => value.is_null() || value.is_string(),
+            _ => self.validate(&value).is_ok(),
+        };
+        if valid {
+            value
+        } else {
+            match self.is_secret() {
+                true => eprintln!("[Settings] Ignoring invalid stored value of {}", self.key),
+                false => eprintln!("[Settings] Ignoring invalid stored value of {}: {}", self.key, value),
+            }
+            self.default_value()
+        }
+    }
+}
+
+/// Copy settings that older versions kept in their own tables, generate the trigger token if
+/// there is none, then load the cached time zone. Runs once at startup after `db::init_db`.
+pub fn init(conn: &Connection) -> Result<(), String> {
+    let legacy = [
+        (SERVER_DOWNLOAD_LIMIT, "SELECT download_limit_kbps FROM server_settings WHERE id = 1"),
+        (TRIGGERS_TOKEN, "SELECT token FROM trigger_settings WHERE id = 1"),
+        (TRIGGERS_ALLOW_REMOTE, "SELECT allow_remote FROM trigger_settings WHERE id = 1"),
+        (TRIGGERS_REMOTE_PORT, "SELECT remote_port FROM trigger_settings WHERE id = 1"),
+        (TRIGGERS_DEFAULT_RECORD_MINUTES, "SELECT default_record_minutes FROM trigger_settings WHERE id = 1"),
+        (MQTT_ENABLED, "SELECT enabled FROM mqtt_settings WHERE id = 1"),
+        (MQTT_HOST, "SELECT host FROM mqtt_settings WHERE id = 1"),
+        (MQTT_PORT, "SELECT port FROM mqtt_settings WHERE id = 1"),
+        (MQTT_USERNAME, "SELECT username FROM mqtt_settings WHERE id = 1"),
+        (MQTT_PASSWORD, "SELECT password FROM mqtt_settings WHERE id = 1"),
+        (MQTT_CLIENT_ID, "SELECT client_id FROM mqtt_settings WHERE id = 1"),
+        (MQTT_DISCOVERY_PREFIX, "SELECT discovery_prefix FROM mqtt_settings WHERE id = 1"),
+        (MQTT_BASE_TOPIC, "SELECT base_topic FROM mqtt_settings WHERE id = 1"),
+        (HLS_SEGMENT_SECS, "SELECT segment_secs FROM hls_settings WHERE id = 1"),
+        (HLS_LIST_SIZE, "SELECT list_size FROM hls_settings WHERE id = 1"),
+        (HLS_DELETE_THRESHOLD, "SELECT delete_threshold FROM hls_settings WHERE id = 1"),
+        (HLS_SEGMENT_DIR, "SELECT segment_dir FROM hls_settings WHERE id = 1"),
+        (HLS_TIMESHIFT_MINUTES, "SELECT timeshift_minutes FROM hls_settings WHERE id = 1"),
+        (CLOUD_RETENTION_DAYS, "SELECT retention_days FROM cloud_upload_settings WHERE id = 1"),
+    ];
+    for (key, query) in legacy {
+        if stored(conn, key)?.is_some() {
+            continue;
+        }
+        let value: Option<rusqlite::types::Value> = conn.query_row(query, [], |row| row.get(0))
+            .optional()
+            .map_err(|e| e.to_string())?;
+        let value = match value {
+            // SQLite booleans are integers
+            Some(rusqlite::types::Value::Integer(number)) if matches!(definition(key)?.kind, Kind::Bool { .. }) => Value::from(number != 0),
+            Some(rusqlite::types::Value::Integer(number)) => Value::from(number),
+            Some(rusqlite::types::Value::Text(text)) => Value::from(text),
+            _ => continue,
+        };
+        write(conn, key, &value)?;
+        match definition(key)?.is_secret() {
+            true => println!("[Settings] Migrated {}", key),
+            false => println!("[Settings] Migrated {} = {}", key, value),
+        }
+    }
+
+    if stored(conn, TRIGGERS_TOKEN)?.is_none() {
+        write(conn, TRIGGERS_TOKEN, &Value::from(new_token()))?;
+    }
+
+    set_cached_timezone(&get(conn, TIMEZONE)?);
+    Ok(())
+}
+
+// Stored JSON value of a key, None if never set
+fn stored(conn: &Connection, key: &str) -> Result<Option<Value>, String> {
+    let text: Option<String> = conn.query_row("SELECT value FROM app_settings WHERE key = ?1", [key], |row| row.get(0))
+        .optional()
+        .map_err(|e| e.to_string())?;
+    Ok(text.and_then(|text| serde_json::from_str(&text).ok()))
+}
+
+fn write(conn: &Connection, key: &str, value: &Value) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO app_settings (key, value, updated_at) VALUES (?1, ?2, ?3)
+         ON CONFLICT(key) DO UPDATE SET value = excluded.value, updated_at = excluded.updated_at",
+        rusqlite::params![key, value.to_string(), Utc::now().to_rfc3339()],
+    ).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Current value of a setting, the default when unset (or no longer valid)
+pub fn get(conn: &Connection, key: &str) -> Result<Value, String> {
+    let definition = definition(key)?;
+    match stored(conn, key)? {
+        Some(value) => Ok(definition.validate_stored(value)),
+        None => Ok(definition.default_value()),
+    }
+}
+
+pub fn get_bool(conn: &Connection, key: &str) -> Result<bool, String> {
+    get(conn, key)?.as_bool().ok_or_else(|| format!("{} is not true or false", key))
+}
+
+pub fn get_i32(conn: &Connection, key: &str) -> Result<i32, String> {
+    get(conn, key)?.as_i64().map(|number| number as i32).ok_or_else(|| format!("{} is not a number", key))
+}
+
+pub fn get_opt_i32(conn: &Connection, key: &str) -> Result<Option<i32>, String> {
+    Ok(get(conn, key)?.as_i64().map(|number| number as i32))
+}
+
+pub fn get_opt_string(conn: &Connection, key: &str) -> Result<Option<String>, String> {
+    Ok(get(conn, key)?.as_str().map(str::to_string))
+}
+
+pub fn get_i32_list(conn: &Connection, key: &str) -> Result<Vec<i32>, String> {
+    let value = get(conn, key)?;
+    let items = value.as_array().ok_or_else(|| format!("{} is not a list", key))?;
+    Ok(items.iter().filter_map(|item| item.as_i64()).map(|number| number as i32).collect())
+}
+
+/// Random value for the trigger token
+pub fn new_token() -> String {
+    uuid::Uuid::new_v4().simple().to_string()
+}
+
+/// Every setting keyed by name, defaults filled in. Secrets (the MQTT password and the trigger
+/// token) are left out; viewers can read this list, and those are only read by admin commands.
+pub fn load_all(conn: &Connection) -> Result<Map<String, Value>, String> {
+    DEFINITIONS.iter()
+        .filter(|definition| !definition.is_secret())
+        .map(|definition| Ok((definition.key.to_string(), get(conn, definition.key)?)))
+        .collect()
+}
+
+/// Result of `update`: the keys whose value changed
+pub struct SettingsChange {
+    pub keys: Vec<String>,
+    pub restart_required: bool,
+}
+
+/// Validate every change first, then store them together
+pub fn update(conn: &mut Connection, changes: &Map<String, Value>) -> Result<SettingsChange, String> {
+    let mut validated = Vec::new();
+    for (key, value) in changes {
+        let definition = definition(key)?;
+        let value = definition.validate(value)?;
+        if get(conn, key)? != value {
+            validated.push((definition, value));
+        }
+    }
+
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    for (definition, value) in &validated {
+        write(&tx, definition.key, value)?;
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+
+    for (definition, value) in &validated {
+        match definition.is_secret() {
+            true => println!("[Settings] {} changed", definition.key),
+            false => println!("[Settings] {} = {}", definition.key, value),
+        }
+        if definition.key == TIMEZONE {
+            set_cached_timezone(value);
+        }
+    }
+    Ok(SettingsChange {
+        keys: validated.iter().map(|(definition, _)| definition.key.to_string()).collect(),
+        restart_required: validated.iter().any(|(definition, _)| definition.restart_required),
+    })
+}
+
+fn set_cached_timezone(value: &Value) {
+    let Some(tz) = value.as_str().and_then(|name| name.parse::<Tz>().ok()) else { return };
+    if let Ok(mut cached) = TIMEZONE_CACHE.write() {
+        *cached = tz;
+    }
+}
+
+/// The configured time zone (Asia/Tokyo unless changed)
+pub fn timezone() -> Tz {
+    TIMEZONE_CACHE.read().map(|tz| *tz).unwrap_or(chrono_tz::Asia::Tokyo)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::test_db::TestDb;
+
+    #[test]
+    fn legacy_table_values_are_copied_once() {
+        let db = TestDb::new("settings-migration");
+        db.conn.execute_batch(
+            "UPDATE server_settings SET download_limit_kbps = 2048;
+             UPDATE trigger_settings SET allow_remote = 1, remote_port = 4000;
+             UPDATE mqtt_settings SET enabled = 1, host = 'broker.local', password = 'secret', base_topic = 'cams';",
+        ).unwrap();
+
+        init(&db.conn).unwrap();
+        assert_eq!(get_opt_i32(&db.conn, SERVER_DOWNLOAD_LIMIT).unwrap(), Some(2048));
+        assert!(get_bool(&db.conn, TRIGGERS_ALLOW_REMOTE).unwrap());
+        assert_eq!(get_i32(&db.conn, TRIGGERS_REMOTE_PORT).unwrap(), 4000);
+        assert!(get_bool(&db.conn, MQTT_ENABLED).unwrap());
+        assert_eq!(get_opt_string(&db.conn, MQTT_HOST).unwrap().as_deref(), Some("broker.local"));
+        assert_eq!(get_opt_string(&db.conn, MQTT_BASE_TOPIC).unwrap().as_deref(), Some("cams"));
+        assert_eq!(get_opt_string(&db.conn, MQTT_CLIENT_ID).unwrap().as_deref(), Some("onvif-viewer"));
+
+        // Later changes to the old tables are ignored
+        db.conn.execute("UPDATE mqtt_settings SET host = 'other.local'", []).unwrap();
+        init(&db.conn).unwrap();
+        assert_eq!(get_opt_string(&db.conn, MQTT_HOST).unwrap().as_deref(), Some("broker.local"));
+    }
+
+    #[test]
+    fn secrets_are_not_listed() {
+        let db = TestDb::new("settings-secrets");
+        db.conn.execute("UPDATE mqtt_settings SET password = 'secret'", []).unwrap();
+        db.conn.execute("DELETE FROM trigger_settings", []).unwrap();
+
+        init(&db.conn).unwrap();
+        let token = get_opt_string(&db.conn, TRIGGERS_TOKEN).unwrap().expect("a token is generated");
+        assert!(token.len() >= MIN_TOKEN_LEN);
+        assert_eq!(get_opt_string(&db.conn, MQTT_PASSWORD).unwrap().as_deref(), Some("secret"));
+
+        let all = load_all(&db.conn).unwrap();
+        assert!(all.contains_key(MQTT_HOST));
+        assert!(!all.contains_key(MQTT_PASSWORD));
+        assert!(!all.contains_key(TRIGGERS_TOKEN));
+    }
+}
@@ -0,0 +1,478 @@
+use crate::models::{Camera, CapturedFrame, Snapshot, VideoFilters};
+use crate::onvif::OnvifHttpClient;
+use crate::events::AppEvent;
+use crate::AppState;
+use chrono::{DateTime, Utc};
+use rusqlite::Connection;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+// Subdirectory of the recordings dir holding snapshot images (served under /recordings/snapshots)
+const SNAPSHOT_DIR: &str = "snapshots";
+// A camera that doesn't deliver a frame within this time fails the capture
+const CAPTURE_TIMEOUT_SECS: u64 = 20;
+// Width of the thumbnails shown in the discovery list
+const THUMBNAIL_WIDTH: u32 = 320;
+
+/// Cron expression for "every N minutes". Only intervals that divide an hour or a day evenly
+/// can be expressed; anything else needs an explicit cron expression.
+pub fn interval_to_cron(minutes: i32) -> Result<String, String> {
+    match minutes {
+        1..=59 if 60 % minutes == 0 => Ok(format!("0 */{} * * * *", minutes)),
+        60 => Ok("0 0 * * * *".to_string()),
+        m if m > 60 && m % 60 == 0 && 24 % (m / 60) == 0 => Ok(format!("0 0 */{} * * *", m / 60)),
+        _ => Err(format!(
+            "An interval of {} minutes doesn't divide an hour or a day evenly; use a cron expression instead",
+            minutes
+        )),
+    }
+}
+
+// FFmpeg input arguments for a single frame, mirroring the recording pipeline's inputs
+fn input_args(camera: &Camera, source: &str) -> Vec<String> {
+    let mut args = Vec::new();
+
+    match camera.camera_type.as_str() {
+        "uvc" => {
+            #[cfg(target_os = "linux")]
+            {
+                if let Some(ref format) = camera.video_format {
+                    args.extend(["-input_format".to_string(), format.clone()]);
+                }
+                if let (Some(width), Some(height)) = (camera.video_width, camera.video_height) {
+                    args.extend(["-video_size".to_string(), format!("{}x{}", width, height)]);
+                }
+                args.extend(["-f".to_string(), "v4l2".to_string(), "-i".to_string(), source.to_string()]);
+            }
+
+            #[cfg(target_os = "windows")]
+            {
+                args.extend(["-f".to_string(), "dshow".to_string(), "-i".to_string(), format!("video={}", source)]);
+            }
+
+            #[cfg(target_os = "macos")]
+            {
+                args.extend(["-f".to_string(), "avfoundation".to_string(), "-i".to_string(), source.to_string()]);
+            }
+        }
+        "rpicam" => args.extend(crate::plugins::rpi_camera_plugin::input_args(camera)),
+        _ => {
+            args.extend(crate::stream::network_input_args(camera, source));
+            args.extend(["-i".to_string(), source.to_string()]);
+        }
+    }
+
+    args
+}
+
+// Let FFmpeg decode a single frame from the camera's stream into output_path
+async fn grab_frame(onvif_http: &OnvifHttpClient, camera: &Camera, output_path: &Path) -> Result<(), String> {
+    let source = crate::stream::get_rtsp_url(onvif_http, camera).await?;
+
+    let mut cmd = tokio::process::Command::new("ffmpeg");
+    cmd.arg("-n")
+        .args(input_args(camera, &source))
+        .args(crate::stream::video_filter_args(camera))
+        .args(["-frames:v", "1", "-q:v", "2"])
+        .arg(output_path)
+        .kill_on_drop(true);
+
+    #[cfg(target_os = "windows")]
+    {
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        cmd.creation_flags(CREATE_NO_WINDOW);
+    }
+
+    if camera.camera_type == "rpicam" {
+        cmd.stdin(crate::plugins::rpi_camera_plugin::start_source(camera)?);
+    }
+
+    let output = tokio::time::timeout(Duration::from_secs(CAPTURE_TIMEOUT_SECS), cmd.output())
+        .await
+        .map_err(|_| format!("Camera {} didn't deliver a frame within {} seconds", camera.id, CAPTURE_TIMEOUT_SECS))?
+        .map_err(|e| format!("Failed to run ffmpeg: {}", e))?;
+
+    if !output.status.success() || !output_path.exists() {
+        let _ = std::fs::remove_file(output_path);
+        return Err(format!("Snapshot capture failed: {}", crate::redact::redact(&String::from_utf8_lossy(&output.stderr))));
+    }
+
+    Ok(())
+}
+
+/// Scale a JPEG down to thumbnail size and return it as a data:image/jpeg URL
+pub async fn thumbnail_data_url(jpeg: &[u8]) -> Option<String> {
+    use base64::prelude::*;
+    use tokio::io::AsyncWriteExt;
+
+    let mut cmd = tokio::process::Command::new("ffmpeg");
+    cmd.args(["-f", "image2pipe", "-i", "pipe:0"])
+        .args(["-vf", &format!("scale={}:-2", THUMBNAIL_WIDTH)])
+        .args(["-frames:v", "1", "-q:v", "5", "-f", "image2", "-c:v", "mjpeg", "pipe:1"])
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::null())
+        .kill_on_drop(true);
+
+    #[cfg(target_os = "windows")]
+    {
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        cmd.creation_flags(CREATE_NO_WINDOW);
+    }
+
+    let mut child = cmd.spawn().ok()?;
+    let mut stdin = child.stdin.take()?;
+    let input = jpeg.to_vec();
+    // Feed the image while FFmpeg writes its output so neither pipe fills up
+    tauri::async_runtime::spawn(async move {
+        let _ = stdin.write_all(&input).await;
+    });
+
+    let output = tokio::time::timeout(Duration::from_secs(CAPTURE_TIMEOUT_SECS), child.wait_with_output())
+        .await
+        .ok()?
+        .ok()?;
+    if !output.status.success() || output.stdout.is_empty() {
+        return None;
+    }
+    Some(format!("data:image/jpeg;base64,{}", BASE64_STANDARD.encode(&output.stdout)))
+}
+
+// Library path of a new still, named by its capture time to the millisecond with a counter
+// added when that is taken, so captures never replace each other
+fn new_library_filename(recording_dir: &Path, camera_id: i32, captured_at: DateTime<Utc>, suffix: &str, extension: &str) -> String {
+    let stem = format!(
+        "{}/{}/{}{}",
+        SNAPSHOT_DIR,
+        camera_id,
+        captured_at.with_timezone(&crate::settings::timezone()).format("%Y%m%d_%H%M%S_%3f"),
+        suffix
+    );
+    let mut filename = format!("{}.{}", stem, extension);
+    let mut counter = 2;
+    while recording_dir.join(&filename).exists() {
+        filename = format!("{}_{}.{}", stem, counter, extension);
+        counter += 1;
+    }
+    filename
+}
+
+/// Grab one frame from the camera and add it to the snapshots library
+pub async fn capture_snapshot(
+    db_path: &str,
+    onvif_http: &OnvifHttpClient,
+    recording_dir: &Path,
+    camera_id: i32,
+    schedule_id: Option<i32>,
+) -> Result<Snapshot, String> {
+    let camera = crate::stream::load_camera(db_path, camera_id)?;
+
+    let captured_at = Utc::now();
+    let camera_dir = recording_dir.join(SNAPSHOT_DIR).join(camera_id.to_string());
+    std::fs::create_dir_all(&camera_dir).map_err(|e| e.to_string())?;
+
+    let filename = new_library_filename(recording_dir, camera_id, captured_at, "", "jpg");
+    let output_path = recording_dir.join(&filename);
+
+    // Vendor snapshot URLs give a full-resolution still without opening the stream; with video
+    // filters the frame is decoded instead so that the still matches the stream
+    let vendor_image = match camera.camera_type.as_str() {
+        _ if camera.video_filters != VideoFilters::default() => None,
+        "hikvision" => Some(crate::plugins::hikvision_plugin::snapshot(&onvif_http.for_camera(&camera), &camera).await?),
+        "dahua" => Some(crate::plugins::dahua_plugin::snapshot(&onvif_http.for_camera(&camera), &camera).await?),
+        "axis" => Some(crate::plugins::axis_plugin::snapshot(&onvif_http.for_camera(&camera), &camera).await?),
+        _ => None,
+    };
+    match vendor_image {
+        Some(image) => {
+            use std::io::Write;
+            std::fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&output_path)
+                .and_then(|mut file| file.write_all(&image))
+                .map_err(|e| e.to_string())?
+        }
+        None => grab_frame(onvif_http, &camera, &output_path).await?,
+    }
+
+    let snapshot = add_to_library(db_path, &camera, schedule_id, filename, &output_path, captured_at)?;
+    println!("[Snapshot] Captured {} from camera {}", snapshot.filename, camera_id);
+    Ok(snapshot)
+}
+
+// Register an image written to `path` (recordings dir + `filename`) in the snapshots library
+fn add_to_library(
+    db_path: &str,
+    camera: &Camera,
+    schedule_id: Option<i32>,
+    filename: String,
+    path: &Path,
+    captured_at: DateTime<Utc>,
+) -> Result<Snapshot, String> {
+    let file_size = std::fs::metadata(path).ok().map(|m| m.len() as i64);
+
+    let conn = crate::db::open(db_path).map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT INTO snapshots (camera_id, schedule_id, filename, captured_at, file_size)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        rusqlite::params![camera.id, schedule_id, filename, captured_at.to_rfc3339(), file_size],
+    ).map_err(|e| e.to_string())?;
+
+    Ok(Snapshot {
+        id: conn.last_insert_rowid(),
+        camera_id: camera.id,
+        schedule_id,
+        filename,
+        captured_at,
+        file_size,
+        camera_name: Some(camera.name.clone()),
+    })
+}
+
+/// Still of what the camera shows right now. While its live stream runs, the last frame of the
+/// newest HLS segment is used, so the camera isn't asked for another connection; otherwise a
+/// frame is decoded from its stream. The image goes to `dest` (PNG or JPEG by extension) or,
+/// without one, to the snapshots library in `format` ("jpeg" unless "png").
+pub async fn capture_frame(
+    state: &AppState,
+    camera_id: i32,
+    dest: Option<&Path>,
+    format: Option<&str>,
+) -> Result<CapturedFrame, String> {
+    let camera = crate::stream::load_camera(&state.db_path, camera_id)?;
+    let captured_at = Utc::now();
+
+    let (output_path, library_filename) = match dest {
+        Some(dest) => {
+            let extension = dest.extension().and_then(|e| e.to_str()).map(str::to_lowercase);
+            if !matches!(extension.as_deref(), Some("png" | "jpg" | "jpeg")) {
+                return Err("The frame can only be saved as .png, .jpg or .jpeg".to_string());
+            }
+            // The save dialog already asked before replacing the file; FFmpeg never overwrites
+            if dest.exists() {
+                std::fs::remove_file(dest).map_err(|e| e.to_string())?;
+            }
+            (dest.to_path_buf(), None)
+        }
+        None => {
+            let extension = match format.unwrap_or("jpeg") {
+                "jpeg" | "jpg" => "jpg",
+                "png" => "png",
+                other => return Err(format!("Unknown image format '{}' (expected jpeg or png)", other)),
+            };
+            let camera_dir = state.recording_dir.join(SNAPSHOT_DIR).join(camera_id.to_string());
+            std::fs::create_dir_all(&camera_dir).map_err(|e| e.to_string())?;
+            let filename = new_library_filename(&state.recording_dir, camera_id, captured_at, "_frame", extension);
+            (state.recording_dir.join(&filename), Some(filename))
+        }
+    };
+
+    let from_live_stream = match latest_live_segment(state, camera_id) {
+        Some(segment) => match last_frame_of(&segment, &output_path).await {
+            Ok(()) => true,
+            Err(e) => {
+                println!("[Snapshot] No frame from the live stream of camera {}, asking the camera: {}", camera_id, e);
+                grab_frame(&state.onvif_http, &camera, &output_path).await?;
+                false
+            }
+        },
+        None => {
+            grab_frame(&state.onvif_http, &camera, &output_path).await?;
+            false
+        }
+    };
+
+    let snapshot = match library_filename {
+        Some(filename) => Some(add_to_library(&state.db_path, &camera, None, filename, &output_path, captured_at)?),
+        None => None,
+    };
+    println!(
+        "[Snapshot] Saved frame of camera {} to {:?} (from {})",
+        camera_id, output_path, if from_live_stream { "live stream" } else { "camera" }
+    );
+
+    Ok(CapturedFrame {
+        path: output_path.to_string_lossy().to_string(),
+        from_live_stream,
+        snapshot,
+    })
+}
+
+// Newest segment the camera's running HLS stream has finished (DASH-only streams have none)
+fn latest_live_segment(state: &AppState, camera_id: i32) -> Option<PathBuf> {
+    if !state.processes.lock().ok()?.contains_key(&camera_id) {
+        return None;
+    }
+    let stream_dir = state.stream_dir.join(camera_id.to_string());
+    let playlist = std::fs::read_to_string(stream_dir.join(crate::hls::PLAYLIST_NAME)).ok()?;
+    let segment = last_segment_name(&playlist)?;
+    let dir = state.pipelines.segment_dir(camera_id).unwrap_or(stream_dir);
+    Some(dir.join(segment)).filter(|path| path.exists())
+}
+
+// File name of the last segment a media playlist lists
+fn last_segment_name(playlist: &str) -> Option<&str> {
+    playlist.lines()
+        .map(str::trim)
+        .rfind(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter(|name| !name.contains(['/', '\\']) && !name.contains(".."))
+}
+
+// Decode the segment's last second and keep overwriting output_path, leaving its final frame
+async fn last_frame_of(segment: &Path, output_path: &Path) -> Result<(), String> {
+    let mut cmd = tokio::process::Command::new("ffmpeg");
+    cmd.args(["-n", "-sseof", "-1", "-i"])
+        .arg(segment)
+        .args(["-update", "1", "-q:v", "2"])
+        .arg(output_path)
+        .kill_on_drop(true);
+
+    #[cfg(target_os = "windows")]
+    {
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        cmd.creation_flags(CREATE_NO_WINDOW);
+    }
+
+    let output = tokio::time::timeout(Duration::from_secs(CAPTURE_TIMEOUT_SECS), cmd.output())
+        .await
+        .map_err(|_| "Extracting the frame took too long".to_string())?
+        .map_err(|e| format!("Failed to run ffmpeg: {}", e))?;
+
+    if !output.status.success() || !output_path.exists() {
+        let _ = std::fs::remove_file(output_path);
+        return Err(crate::redact::redact(&String::from_utf8_lossy(&output.stderr)));
+    }
+    Ok(())
+}
+
+/// Scheduler job body for snapshot schedules: capture, then apply the schedule's retention
+pub async fn run_scheduled_capture(
+    state: &AppState,
+    schedule_id: i32,
+    camera_id: i32,
+    retention_days: Option<i32>,
+    name: &str,
+) {
+    match capture_snapshot(&state.db_path, &state.onvif_http, &state.recording_dir, camera_id, Some(schedule_id)).await {
+        Ok(snapshot) => state.events.emit(AppEvent::SnapshotCaptured(snapshot)),
+        Err(e) => eprintln!("[Snapshot] Schedule '{}' failed to capture: {}", name, e),
+    }
+
+    if let Some(days) = retention_days.filter(|d| *d > 0) {
+        if let Err(e) = prune_schedule_snapshots(&state.db_path, &state.recording_dir, schedule_id, days) {
+            eprintln!("[Snapshot] Failed to apply retention for '{}': {}", name, e);
+        }
+    }
+}
+
+/// Delete a schedule's snapshots older than `retention_days`
+pub fn prune_schedule_snapshots(
+    db_path: &str,
+    recording_dir: &Path,
+    schedule_id: i32,
+    retention_days: i32,
+) -> Result<usize, String> {
+    let cutoff = (Utc::now() - chrono::Duration::days(retention_days as i64)).to_rfc3339();
+    let conn = crate::db::open(db_path).map_err(|e| e.to_string())?;
+
+    let expired = {
+        let mut stmt = conn.prepare(
+            "SELECT id, filename FROM snapshots WHERE schedule_id = ?1 AND captured_at < ?2"
+        ).map_err(|e| e.to_string())?;
+        let rows = stmt.query_map(rusqlite::params![schedule_id, cutoff], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+        }).map_err(|e| e.to_string())?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())?;
+        rows
+    };
+
+    for (id, filename) in &expired {
+        remove_snapshot_file(recording_dir, filename);
+        conn.execute("DELETE FROM snapshots WHERE id = ?1", [id]).map_err(|e| e.to_string())?;
+    }
+
+    if !expired.is_empty() {
+        println!("[Snapshot] Removed {} snapshots of schedule {} older than {} days", expired.len(), schedule_id, retention_days);
+    }
+
+    Ok(expired.len())
+}
+
+fn remove_snapshot_file(recording_dir: &Path, filename: &str) {
+    let path = recording_dir.join(filename);
+    if let Err(e) = std::fs::remove_file(&path) {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            eprintln!("[Snapshot] Failed to delete {:?}: {}", path, e);
+        }
+    }
+}
+
+/// Snapshots newest first, optionally filtered by camera and/or schedule
+pub fn list_snapshots(
+    conn: &Connection,
+    camera_id: Option<i32>,
+    schedule_id: Option<i32>,
+    limit: u32,
+) -> Result<Vec<Snapshot>, String> {
+    let mut stmt = conn.prepare(
+        "SELECT s.id, s.camera_id, s.schedule_id, s.filename, s.captured_at, s.file_size, c.name
+         FROM snapshots s
+         LEFT JOIN cameras c ON s.camera_id = c.id
+         WHERE (?1 IS NULL OR s.camera_id = ?1) AND (?2 IS NULL OR s.schedule_id = ?2)
+         ORDER BY s.captured_at DESC
+         LIMIT ?3"
+    ).map_err(|e| e.to_string())?;
+
+    let snapshots = stmt.query_map(rusqlite::params![camera_id, schedule_id, limit], |row| {
+        Ok(Snapshot {
+            id: row.get(0)?,
+            camera_id: row.get(1)?,
+            schedule_id: row.get(2)?,
+            filename: row.get(3)?,
+            captured_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(4)?)
+                .map(|t| t.with_timezone(&Utc))
+                .unwrap_or_else(|_| Utc::now()),
+            file_size: row.get(5)?,
+            camera_name: row.get(6)?,
+        })
+    }).map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok(snapshots)
+}
+
+pub fn delete_snapshot(db_path: &str, recording_dir: &Path, id: i64) -> Result<(), String> {
+    let conn = crate::db::open(db_path).map_err(|e| e.to_string())?;
+    let filename: String = conn.query_row(
+        "SELECT filename FROM snapshots WHERE id = ?1", [id], |row| row.get(0),
+    ).map_err(|e| format!("Snapshot not found: {}", e))?;
+
+    remove_snapshot_file(recording_dir, &filename);
+    conn.execute("DELETE FROM snapshots WHERE id = ?1", [id]).map_err(|e| e.to_string())?;
+
+    println!("[Snapshot] Deleted snapshot {} ({})", id, filename);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn captures_in_the_same_millisecond_get_their_own_file() {
+        let dir = std::env::temp_dir().join(format!("onvif-viewer-snapshots-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join(SNAPSHOT_DIR).join("3")).unwrap();
+        let captured_at = Utc::now();
+
+        let first = new_library_filename(&dir, 3, captured_at, "", "jpg");
+        assert!(first.starts_with("snapshots/3/") && first.ends_with(".jpg"));
+        std::fs::write(dir.join(&first), b"jpeg").unwrap();
+        let second = new_library_filename(&dir, 3, captured_at, "", "jpg");
+        assert_eq!(second, first.replace(".jpg", "_2.jpg"));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,170 @@
+use crate::models::{NewStorageLocation, StorageLocation};
+use rusqlite::{Connection, OptionalExtension};
+use std::path::{Path, PathBuf};
+
+// The built-in recordings directory is only used first while it keeps this much free
+const DEFAULT_MIN_FREE_MB: i64 = 1024;
+
+// Recording storage locations: extra directories (a second disk, a NAS mount, ...) recordings
+// can be written to besides the built-in recordings directory. A recording row keeps its
+// filename relative to the root of its location (recordings.location_id, NULL for the
+// built-in directory), so a location can be remounted elsewhere by changing its path.
+// Thumbnails and snapshots stay in the built-in directory.
+
+/// Configured locations, in failover order
+pub fn list_locations(conn: &Connection) -> Result<Vec<StorageLocation>, String> {
+    let mut stmt = conn.prepare(
+        "SELECT id, name, path, priority, min_free_mb, enabled FROM storage_locations ORDER BY priority, id"
+    ).map_err(|e| e.to_string())?;
+
+    let locations = stmt.query_map([], |row| {
+        Ok(StorageLocation {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            path: row.get(2)?,
+            priority: row.get(3)?,
+            min_free_mb: row.get(4)?,
+            enabled: row.get(5)?,
+            available: false,
+            free_bytes: None,
+            total_bytes: None,
+        })
+    }).map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    Ok(locations)
+}
+
+/// Fill in whether each location can take recordings now and its free space
+pub fn with_status(mut locations: Vec<StorageLocation>) -> Vec<StorageLocation> {
+    for location in &mut locations {
+        let root = Path::new(&location.path);
+        location.available = check_usable(root, location.min_free_mb).is_ok();
+        location.free_bytes = fs4::available_space(root).ok();
+        location.total_bytes = fs4::total_space(root).ok();
+    }
+    locations
+}
+
+/// Check a location before it is saved
+pub fn validate(location: &NewStorageLocation) -> Result<(), String> {
+    if location.name.trim().is_empty() {
+        return Err("Storage location name must not be empty".to_string());
+    }
+    if location.min_free_mb < 0 {
+        return Err("Minimum free space must not be negative".to_string());
+    }
+    let root = Path::new(&location.path);
+    if !root.is_absolute() {
+        return Err("Storage location path must be absolute".to_string());
+    }
+    if !root.is_dir() {
+        return Err(format!("{} is not an existing directory", location.path));
+    }
+    Ok(())
+}
+
+/// Root directory of a location; None is the built-in recordings directory
+pub fn location_root(conn: &Connection, recording_dir: &Path, location_id: Option<i64>) -> Result<PathBuf, String> {
+    let Some(location_id) = location_id else {
+        return Ok(recording_dir.to_path_buf());
+    };
+
+    let path: Option<String> = conn.query_row(
+        "SELECT path FROM storage_locations WHERE id = ?1",
+        [location_id],
+        |row| row.get(0),
+    ).optional().map_err(|e| e.to_string())?;
+    path.map(PathBuf::from).ok_or_else(|| format!("Storage location {} not found", location_id))
+}
+
+/// Absolute path of a recording file stored under a location
+pub fn resolve(conn: &Connection, recording_dir: &Path, location_id: Option<i64>, filename: &str) -> Result<PathBuf, String> {
+    Ok(location_root(conn, recording_dir, location_id)?.join(filename))
+}
+
+/// Absolute path of a recording's file
+pub fn recording_path(conn: &Connection, recording_dir: &Path, recording_id: i32) -> Result<PathBuf, String> {
+    let (filename, location_id): (String, Option<i64>) = conn.query_row(
+        "SELECT filename, location_id FROM recordings WHERE id = ?1",
+        [recording_id],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    ).map_err(|e| e.to_string())?;
+    resolve(conn, recording_dir, location_id, &filename)
+}
+
+/// Location assigned to a camera, None for the built-in recordings directory
+pub fn camera_location(conn: &Connection, camera_id: i32) -> Result<Option<i64>, String> {
+    conn.query_row(
+        "SELECT location_id FROM camera_storage WHERE camera_id = ?1",
+        [camera_id],
+        |row| row.get(0),
+    ).optional().map_err(|e| e.to_string())
+}
+
+/// Where a new recording of the camera goes: its assigned location, or the next usable one
+/// when that is full or unavailable (unmounted, read-only). Cameras without an assignment
+/// start with the built-in directory, which is also the last resort.
+pub fn choose_location(conn: &Connection, recording_dir: &Path, camera_id: i32) -> Result<(Option<i64>, PathBuf), String> {
+    let assigned = camera_location(conn, camera_id)?;
+    let locations: Vec<StorageLocation> = list_locations(conn)?.into_iter()
+        .filter(|location| location.enabled || Some(location.id) == assigned)
+        .collect();
+
+    let builtin = (None, recording_dir.to_path_buf(), DEFAULT_MIN_FREE_MB);
+    let mut candidates: Vec<(Option<i64>, PathBuf, i64)> = Vec::new();
+    if assigned.is_none() {
+        candidates.push(builtin.clone());
+    }
+    // The assigned location first, then the others in priority order
+    let (first, rest): (Vec<_>, Vec<_>) = locations.into_iter().partition(|location| Some(location.id) == assigned);
+    for location in first.into_iter().chain(rest) {
+        candidates.push((Some(location.id), PathBuf::from(location.path), location.min_free_mb));
+    }
+    if assigned.is_some() {
+        candidates.push(builtin);
+    }
+
+    for (index, (location_id, root, min_free_mb)) in candidates.iter().enumerate() {
+        match check_usable(root, *min_free_mb) {
+            Ok(()) => {
+                if index > 0 {
+                    println!("[Storage] Camera {} records to {:?} (failover)", camera_id, root);
+                }
+                return Ok((*location_id, root.clone()));
+            }
+            Err(e) => eprintln!("[Storage] Skipping {:?} for camera {}: {}", root, camera_id, e),
+        }
+    }
+
+    // Nothing has room; the built-in directory fails the way it always did
+    eprintln!("[Storage] No usable storage location for camera {}, using {:?}", camera_id, recording_dir);
+    Ok((None, recording_dir.to_path_buf()))
+}
+
+// A location must exist (it isn't created, so an unmounted drive isn't mistaken for an empty
+// directory), be writable and have the minimum free space
+fn check_usable(root: &Path, min_free_mb: i64) -> Result<(), String> {
+    if !root.is_dir() {
+        return Err("directory is unavailable".to_string());
+    }
+
+    let probe = root.join(".write_test");
+    std::fs::write(&probe, b"").map_err(|e| format!("not writable: {}", e))?;
+    let _ = std::fs::remove_file(&probe);
+
+    let available = fs4::available_space(root).map_err(|e| e.to_string())?;
+    if available < (min_free_mb.max(0) as u64) * 1024 * 1024 {
+        return Err(format!("only {} MB free", available / (1024 * 1024)));
+    }
+    Ok(())
+}
+
+/// URL of a recording file on the local server, with the server token it needs
+pub fn recording_url(port: u16, token: &str, location_id: Option<i64>, filename: &str) -> String {
+    let path = match location_id {
+        Some(location_id) => format!("locations/{}/{}", location_id, urlencoding::encode(filename)),
+        None => urlencoding::encode(filename).into_owned(),
+    };
+    format!("http://localhost:{}/recordings/{}?token={}", port, path, urlencoding::encode(token))
+}
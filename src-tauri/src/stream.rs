@@ -1,24 +1,27 @@
-use crate::models::{Camera, EncoderSettings};
+use crate::models::{Camera, EncoderSettings, RecordingGap, RecordingMeta, RecordingTrigger, StreamProbe, VideoFilters, Watermark};
 use crate::AppState;
 use crate::gpu_detector::detect_gpu_capabilities;
 use crate::encoder::EncoderSelector;
-use std::process::{Command, Stdio, Child};
-use std::sync::{Arc, Mutex};
+use crate::onvif::OnvifHttpClient;
+use crate::thumbnails::ThumbnailQueue;
+use crate::events::{AppEvent, EventBus};
+use crate::pipeline::{LiveEncoder, PipelineTracker};
+use crate::redact::StderrTail;
+use crate::ffmpeg::{CommandBuilder, InputSource};
+use serde::Serialize;
+use std::process::{Stdio, Child};
+use std::sync::{Arc, LazyLock, Mutex};
 use std::collections::HashMap;
-use tauri::{State, Emitter};
+use std::time::{Duration, Instant};
+use tauri::{Manager, State};
 use std::fs;
-use std::path::PathBuf;
-use rusqlite::Connection;
+use std::path::{Path, PathBuf};
+use rusqlite::{Connection, OptionalExtension};
 use chrono::{Utc, DateTime};
-use chrono_tz::Asia::Tokyo;
-
-// Windows-specific imports for hiding console window
-#[cfg(target_os = "windows")]
-use std::os::windows::process::CommandExt;
 
 // Helper to get DB connection inside stream module
 fn get_conn(state: &State<AppState>) -> Result<Connection, String> {
-    Connection::open(&state.db_path).map_err(|e| e.to_string())
+    crate::db::open(&state.db_path).map_err(|e| e.to_string())
 }
 
 // Get encoder settings from database
@@ -51,157 +54,326 @@ async fn build_encoder_selector(state: &State<'_, AppState>) -> Result<EncoderSe
     Ok(EncoderSelector::new(capabilities, settings))
 }
 
-pub async fn start_stream(state: State<'_, AppState>, camera: Camera) -> Result<String, String> {
+// Time FFmpeg gets to write the first HLS segment unless the camera has its own setting
+pub const DEFAULT_STREAM_START_TIMEOUT_SECS: i32 = 20;
+pub const MIN_STREAM_START_TIMEOUT_SECS: i32 = 5;
+pub const MAX_STREAM_START_TIMEOUT_SECS: i32 = 300;
+const READY_POLL_MS: u64 = 250;
+
+/// Why a stream didn't come up. `reason` is "timeout" (no segment in time), "exited" (FFmpeg
+/// quit, e.g. bad credentials or an unreachable camera), "encoder_failed" (the GPU encoder
+/// broke, see `fall_back_to_cpu`), "stopped" (stopped while starting) or "failed" (FFmpeg
+/// couldn't be started); `stderr` holds FFmpeg's last output lines.
+#[derive(Debug, Serialize)]
+pub struct StreamStartError {
+    pub reason: String,
+    pub message: String,
+    pub stderr: Vec<String>,
+}
+
+impl From<String> for StreamStartError {
+    fn from(message: String) -> Self {
+        StreamStartError { reason: "failed".to_string(), message, stderr: Vec::new() }
+    }
+}
+
+impl std::fmt::Display for StreamStartError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)?;
+        if let Some(last) = self.stderr.last() {
+            write!(f, " ({})", last)?;
+        }
+        Ok(())
+    }
+}
+
+/// Start the camera's HLS stream and wait until the playlist has a segment, so callers only
+/// get the playlist path once there is something to play. A GPU encoder that fails while the
+/// stream starts is given up for the camera and the stream started again on the CPU.
+pub async fn start_stream(state: State<'_, AppState>, camera: Camera) -> Result<&'static str, StreamStartError> {
+    match spawn_stream(&state, &camera).await {
+        Err(e) if e.reason == "encoder_failed" => spawn_stream(&state, &camera).await,
+        result => result,
+    }
+}
+
+async fn spawn_stream(state: &State<'_, AppState>, camera: &Camera) -> Result<&'static str, StreamStartError> {
     let id = camera.id;
 
     // Check if already running
     {
-        let processes = state.processes.lock().map_err(|e| e.to_string())?;
-        if processes.contains_key(&id) {
-            return Ok(format!("streams/{}/index.m3u8", id));
+        let mut processes = state.processes.lock().map_err(|e| e.to_string())?;
+        if let Some(child) = processes.get_mut(&id) {
+            match child.try_wait() {
+                Ok(Some(status)) => {
+                    // FFmpeg exited on its own (e.g. the camera dropped the connection); start a new one
+                    println!("[Stream] FFmpeg for camera {} had exited ({}), restarting", id, status);
+                    processes.remove(&id);
+                    let reason = format!("FFmpeg exited with {}", status);
+                    state.pipelines.error(id, &reason);
+                    state.pipelines.camera_offline(&state.events, id, &reason);
+                    state.events.emit(AppEvent::StreamDied { camera_id: id, reason });
+                }
+                _ => {
+                    let packaging = crate::hls::packaging_for(&get_conn(state)?, id)?;
+                    let adaptive = state.stream_dir.join(id.to_string()).join(crate::hls::MASTER_PLAYLIST_NAME).exists();
+                    return Ok(crate::hls::stream_file(&packaging, adaptive));
+                }
+            }
         }
     }
 
     let stream_dir = state.stream_dir.join(id.to_string());
-    if stream_dir.exists() {
-        fs::remove_dir_all(&stream_dir).map_err(|e| e.to_string())?;
-    }
-    fs::create_dir_all(&stream_dir).map_err(|e| e.to_string())?;
 
-    let rtsp_url = get_rtsp_url(&camera).await?;
+    let rtsp_url = get_rtsp_url(&state.onvif_http, camera).await?;
 
-    let output_file = stream_dir.join("index.m3u8");
-    let segment_filename = stream_dir.join("segment_%03d.ts");
-
-    println!("[Stream] Starting FFmpeg for camera {}: {}", id, rtsp_url);
+    let (hls, packaging) = {
+        let conn = get_conn(state)?;
+        (crate::hls::params_for(&conn, id)?, crate::hls::packaging_for(&conn, id)?)
+    };
+    // Segments may go elsewhere (e.g. tmpfs); the playlist always stays in the stream directory,
+    // and DASH segments next to their manifest
+    let segment_dir = hls.segment_dir.as_ref()
+        .filter(|_| packaging != "dash")
+        .map(|dir| dir.join(id.to_string()));
+    let output_file = stream_dir.join(crate::hls::PLAYLIST_NAME);
+    let manifest_file = stream_dir.join(crate::hls::MANIFEST_NAME);
+    let segment_filename = segment_dir.as_ref().unwrap_or(&stream_dir).join("segment_%03d.ts");
+
+    println!("[Stream] Starting FFmpeg for camera {}: {}", id, crate::redact::redact(&rtsp_url));
+
+    // A stream downgraded by the load budget is encoded at a lower frame rate
+    let downgrade = state.pipelines.stream_downgrade(id);
+    let downgraded_fps = crate::budget::downgraded_fps(camera.video_fps, downgrade);
+    let fps = downgraded_fps.or(camera.video_fps);
+    if downgrade > 0 {
+        println!("[Stream] Camera {} is downgraded to level {} by the load budget", id, downgrade);
+    }
 
     // Get encoder configuration with camera FPS
-    let encoder_selector = build_encoder_selector(&state).await?;
-    let encoder_config = encoder_selector.select_encoder_for_streaming(camera.video_fps).await;
-
-    println!("[Stream] Using encoder: {} (GPU: {}) with FPS: {:?}", encoder_config.codec, encoder_config.is_gpu, camera.video_fps);
-
-    // Build FFmpeg command
-    let mut args = vec!["-y".to_string()];
+    let preset = crate::encoder::quality_preset_for(&get_conn(state)?, id)?;
+    let encoder_selector = build_encoder_selector(state).await?.with_preset(preset);
+    let mut encoder_config = encoder_selector.select_encoder_for_streaming(fps).await;
+    let can_fall_back = encoder_selector.settings.encoderMode != "GpuOnly";
+    if encoder_config.is_gpu && can_fall_back && state.pipelines.cpu_fallback(id).is_some() {
+        println!("[Stream] Camera {} was degraded to the CPU encoder, not using {}", id, encoder_config.codec);
+        encoder_config = encoder_selector.build_cpu_config_streaming(fps);
+    }
 
-    // Add input format and device arguments based on camera type
-    match camera.camera_type.as_str() {
-        "uvc" => {
-            // UVC camera - use device input with detected settings
-            #[cfg(target_os = "linux")]
-            {
-                args.extend_from_slice(&[
-                    "-err_detect".to_string(), "ignore_err".to_string(),  // Ignore MJPEG decode errors (APP field issues)
-                    "-fflags".to_string(), "nobuffer+genpts".to_string(),  // Minimize buffering + generate timestamps
-                    "-flags".to_string(), "low_delay".to_string(),   // Low delay mode
-                    "-avoid_negative_ts".to_string(), "make_zero".to_string(),  // Handle timestamp issues
-                ]);
-
-                // Use detected video format if available
-                if let Some(ref format) = camera.video_format {
-                    args.extend_from_slice(&[
-                        "-input_format".to_string(), format.clone(),
-                    ]);
-                }
+    println!("[Stream] Using encoder: {} (GPU: {}) with FPS: {:?}", encoder_config.codec, encoder_config.is_gpu, fps);
 
-                // Use detected resolution if available
-                if let (Some(width), Some(height)) = (camera.video_width, camera.video_height) {
-                    args.extend_from_slice(&[
-                        "-video_size".to_string(), format!("{}x{}", width, height),
-                    ]);
-                }
+    if camera.camera_type == "uvc" {
+        println!("[Stream] UVC input: format={:?}, size={:?}x{:?}, fps={:?}",
+            camera.video_format, camera.video_width, camera.video_height, camera.video_fps);
+    }
 
-                // Use detected FPS if available
-                if let Some(fps) = camera.video_fps {
-                    args.extend_from_slice(&[
-                        "-framerate".to_string(), fps.to_string(),
-                    ]);
-                }
+    let encoder_codec = encoder_config.codec.clone();
+    let encoder_is_gpu = encoder_config.is_gpu;
 
-                args.extend_from_slice(&[
-                    "-f".to_string(), "v4l2".to_string(),
-                    "-i".to_string(), rtsp_url.clone(),
-                ]);
+    // Lower renditions for adaptive playback; DASH packagings keep a single one
+    let renditions = if packaging == "hls" { hls.renditions.clone() } else { Vec::new() };
+    let gpu_renditions = match encoder_is_gpu && !renditions.is_empty() {
+        true => free_gpu_sessions(state, id)?.saturating_sub(1).min(renditions.len()),
+        false => 0,
+    };
+    let cpu_config = (encoder_is_gpu && gpu_renditions < renditions.len())
+        .then(|| encoder_selector.build_cpu_config_streaming(fps));
+    let mut rendition_args = Vec::new();
+    let mut variants = Vec::new();
+    for (index, height) in renditions.iter().enumerate() {
+        let kbps = crate::encoder::rendition_kbps(*height);
+        let config = match &cpu_config {
+            Some(cpu_config) if index >= gpu_renditions => cpu_config,
+            _ => &encoder_config,
+        };
+        let dir = stream_dir.join(crate::hls::rendition_dir_name(*height));
+        let scale = format!("scale=-2:{}", height);
+        let chain = match video_filter_chain(&camera.video_filters) {
+            Some(chain) => format!("{},{}", chain, scale),
+            None => scale,
+        };
+        rendition_args.extend(["-vf".to_string(), chain]);
+        rendition_args.extend(crate::encoder::rendition_config(config, kbps).args);
+        rendition_args.push("-an".to_string());
+        rendition_args.extend(crate::hls::output_args(&hls, &dir.join("segment_%03d.ts"), &dir.join(crate::hls::PLAYLIST_NAME)));
+        variants.push((*height, kbps));
+    }
+    if !variants.is_empty() {
+        println!(
+            "[Stream] Camera {} renditions: {} ({} on the GPU)",
+            id,
+            renditions.iter().map(|height| format!("{}p", height)).collect::<Vec<_>>().join(", "),
+            gpu_renditions
+        );
+    }
+    let master_playlist = (!variants.is_empty()).then(|| {
+        crate::hls::master_playlist(crate::encoder::config_kbps(&encoder_config).unwrap_or(4000), &variants)
+    });
 
-                println!("[Stream] UVC input: format={:?}, size={:?}x{:?}, fps={:?}",
-                    camera.video_format, camera.video_width, camera.video_height, camera.video_fps);
-            }
+    // Spawn FFmpeg
+    let mut cmd = CommandBuilder::new()
+        .low_latency()
+        .input(InputSource::camera(camera, &rtsp_url))
+        .frame_rate(downgraded_fps)
+        .filters_with(&camera.video_filters, crate::budget::downgraded_scale(downgrade))
+        .encoder(&encoder_config)
+        .args(state.pipelines.run_marker_args())
+        .args(encoder_selector.audio_args(true))
+        .args(custom_output_args(camera))
+        .args(crate::hls::packaged_output_args(&packaging, &hls, &segment_filename, &output_file, &manifest_file))
+        .args(rendition_args)
+        .command();
+    cmd.stdout(Stdio::null())
+        .stderr(Stdio::piped());
+
+    if camera.camera_type == "rpicam" {
+        cmd.stdin(crate::plugins::rpi_camera_plugin::start_source(camera)?);
+    }
 
-            #[cfg(target_os = "windows")]
-            {
-                args.extend_from_slice(&[
-                    "-fflags".to_string(), "nobuffer".to_string(),
-                    "-flags".to_string(), "low_delay".to_string(),
-                    "-f".to_string(), "dshow".to_string(),
-                    "-i".to_string(), format!("video={}", rtsp_url),
-                ]);
-                // TODO: Add format/resolution/fps detection for Windows
+    // Reset the directory and register FFmpeg under the lock, so neither the stream GC nor a
+    // concurrent stop_stream can delete the fresh directory before its process is known
+    let stderr = {
+        let mut processes = state.processes.lock().map_err(|e| e.to_string())?;
+        for dir in std::iter::once(&stream_dir).chain(segment_dir.as_ref()) {
+            if dir.exists() {
+                fs::remove_dir_all(dir).map_err(|e| e.to_string())?;
             }
+            fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        // Lower renditions keep their segments next to their playlist
+        for height in &renditions {
+            fs::create_dir_all(stream_dir.join(crate::hls::rendition_dir_name(*height))).map_err(|e| e.to_string())?;
+        }
+        if let Some(master_playlist) = &master_playlist {
+            fs::write(stream_dir.join(crate::hls::MASTER_PLAYLIST_NAME), master_playlist).map_err(|e| e.to_string())?;
+        }
+        state.pipelines.set_segment_dir(id, segment_dir.clone());
+
+        let mut child = cmd.spawn()
+            .map_err(|e| format!("Failed to start ffmpeg: {}", e))?;
+        let stderr = crate::redact::forward_stderr(&mut child);
+        state.pipelines.adopt_process(&child);
+        state.pipelines.set_live_encoder(id, LiveEncoder {
+            codec: encoder_codec.clone(),
+            is_gpu: encoder_is_gpu,
+            can_fall_back,
+            stderr: stderr.clone(),
+            gpu_sessions: if encoder_is_gpu { 1 + gpu_renditions } else { 0 },
+        });
+        processes.insert(id, child);
+        stderr
+    };
 
-            #[cfg(target_os = "macos")]
-            {
-                args.extend_from_slice(&[
-                    "-fflags".to_string(), "nobuffer".to_string(),
-                    "-flags".to_string(), "low_delay".to_string(),
-                    "-f".to_string(), "avfoundation".to_string(),
-                    "-i".to_string(), rtsp_url.clone(),
-                ]);
-                // TODO: Add format/resolution/fps detection for macOS
+    let timeout_secs = camera.stream_timeout_secs.unwrap_or(DEFAULT_STREAM_START_TIMEOUT_SECS);
+    // A DASH manifest is usable once its timeline lists a segment
+    let (ready_file, ready_marker) = if packaging == "dash" { (&manifest_file, "<S ") } else { (&output_file, "#EXTINF") };
+    if let Err(mut e) = wait_until_ready(state, id, ready_file, ready_marker, timeout_secs, &stderr).await {
+        // FFmpeg quit right away because of the GPU encoder: the caller retries on the CPU
+        if encoder_is_gpu && can_fall_back && e.reason == "exited" {
+            if let Some(line) = crate::encoder::encoder_failure(&e.stderr, &encoder_codec) {
+                fall_back_to_cpu(state, id, &encoder_codec, line);
+                e.reason = "encoder_failed".to_string();
             }
         }
-        _ => {
-            // ONVIF/RTSP camera - use RTSP input
-            args.extend_from_slice(&[
-                "-fflags".to_string(), "nobuffer".to_string(),
-                "-rtsp_transport".to_string(), "tcp".to_string(),
-                "-i".to_string(), rtsp_url.clone(),
-            ]);
-        }
+        return Err(e);
     }
+    println!("[Stream] Stream of camera {} is ready ({})", id, packaging);
 
-    // Add encoder-specific arguments
-    args.extend(encoder_config.args);
+    let stream_file = crate::hls::stream_file(&packaging, master_playlist.is_some());
+    state.pipelines.stream_started(id, stream_file, &encoder_codec, encoder_is_gpu);
+    state.events.emit(AppEvent::StreamStarted { camera_id: id });
+    state.pipelines.camera_online(&state.events, id);
 
-    // Add common streaming arguments
-    args.extend_from_slice(&[
-        "-an".to_string(), // Disable audio for stability/latency
-        "-f".to_string(), "hls".to_string(),
-        "-hls_time".to_string(), "2".to_string(),
-        "-hls_list_size".to_string(), "15".to_string(),
-        "-hls_delete_threshold".to_string(), "3".to_string(),
-        "-hls_flags".to_string(), "delete_segments+omit_endlist+program_date_time".to_string(),
-        "-hls_segment_type".to_string(), "mpegts".to_string(),
-        "-hls_segment_filename".to_string(), segment_filename.to_str().unwrap().to_string(),
-        output_file.to_str().unwrap().to_string(),
-    ]);
-
-    // Spawn FFmpeg
-    let mut cmd = Command::new("ffmpeg");
-    cmd.args(&args)
-        .stdout(Stdio::null())
-        .stderr(Stdio::inherit());
+    Ok(stream_file)
+}
 
-    // Hide console window on Windows
-    #[cfg(target_os = "windows")]
-    {
-        const CREATE_NO_WINDOW: u32 = 0x08000000;
-        cmd.creation_flags(CREATE_NO_WINDOW);
+// GPU encoder sessions left for a new stream of the camera under encoder.gpuSessionLimit
+fn free_gpu_sessions(state: &AppState, camera_id: i32) -> Result<usize, String> {
+    let limit = crate::settings::get_i32(&crate::db::open(&state.db_path).map_err(|e| e.to_string())?, crate::settings::ENCODER_GPU_SESSION_LIMIT)?;
+    if limit == 0 {
+        return Ok(usize::MAX);
     }
+    let streaming: Vec<i32> = state.processes.lock().map_err(|e| e.to_string())?
+        .keys()
+        .filter(|other| **other != camera_id)
+        .copied()
+        .collect();
+    let recording: Vec<i32> = state.recording_processes.lock().map_err(|e| e.to_string())?.keys().copied().collect();
+    Ok((limit as usize).saturating_sub(state.pipelines.gpu_sessions(&streaming, &recording)))
+}
 
-    let child = cmd.spawn()
-        .map_err(|e| format!("Failed to start ffmpeg: {}", e))?;
+/// Stop using the GPU encoder for the camera's stream after it failed with `detail`; the next
+/// start of the stream uses the CPU encoder
+pub(crate) fn fall_back_to_cpu(state: &AppState, camera_id: i32, encoder: &str, detail: &str) {
+    let reason = format!("{} failed: {}", encoder, detail);
+    println!("[Stream] Camera {} degraded to the CPU encoder ({})", camera_id, crate::redact::redact(&reason));
+    state.pipelines.fall_back_to_cpu(camera_id, &reason);
+    state.events.emit(AppEvent::EncoderFallback {
+        camera_id,
+        encoder: encoder.to_string(),
+        reason: crate::redact::redact(&reason),
+    });
+}
 
-    // Save process
-    {
-        let mut processes = state.processes.lock().map_err(|e| e.to_string())?;
-        processes.insert(id, child);
-    }
+// Poll until FFmpeg has listed a segment in the playlist (`marker` shows up). Fails as soon as
+// FFmpeg exits; on timeout FFmpeg is killed so a stream that never came up doesn't linger.
+async fn wait_until_ready(state: &AppState, id: i32, playlist: &Path, marker: &str, timeout_secs: i32, stderr: &StderrTail) -> Result<(), StreamStartError> {
+    let deadline = Instant::now() + Duration::from_secs(timeout_secs.max(1) as u64);
+
+    loop {
+        if fs::read_to_string(playlist).is_ok_and(|text| text.contains(marker)) {
+            return Ok(());
+        }
 
-    Ok(format!("streams/{}/index.m3u8", id))
+        let exited = {
+            let mut processes = state.processes.lock().map_err(|e| e.to_string())?;
+            let Some(child) = processes.get_mut(&id) else {
+                return Err(StreamStartError {
+                    reason: "stopped".to_string(),
+                    message: "The stream was stopped while starting".to_string(),
+                    stderr: crate::redact::tail_lines(stderr),
+                });
+            };
+            match child.try_wait() {
+                Ok(Some(status)) => {
+                    processes.remove(&id);
+                    Some(status)
+                }
+                _ if Instant::now() >= deadline => {
+                    if let Some(mut child) = processes.remove(&id) {
+                        let _ = state.pipelines.kill_process(&mut child);
+                        let _ = child.wait();
+                    }
+                    println!("[Stream] Camera {} produced no stream within {}s, stopped FFmpeg", id, timeout_secs);
+                    return Err(StreamStartError {
+                        reason: "timeout".to_string(),
+                        message: format!("No video from the camera within {} seconds", timeout_secs),
+                        stderr: crate::redact::tail_lines(stderr),
+                    });
+                }
+                _ => None,
+            }
+        };
+
+        if let Some(status) = exited {
+            // Let the stderr reader catch FFmpeg's last words
+            tokio::time::sleep(Duration::from_millis(READY_POLL_MS)).await;
+            println!("[Stream] FFmpeg for camera {} exited during startup ({})", id, status);
+            return Err(StreamStartError {
+                reason: "exited".to_string(),
+                message: format!("FFmpeg exited with {} before the stream started", status),
+                stderr: crate::redact::tail_lines(stderr),
+            });
+        }
+
+        tokio::time::sleep(Duration::from_millis(READY_POLL_MS)).await;
+    }
 }
 
 pub async fn stop_stream(state: State<'_, AppState>, id: i32) -> Result<(), String> {
     println!("[Stream] Stopping stream for camera {}", id);
+    state.pipelines.stream_stopped(id);
+    state.stream_tokens.revoke_camera(id);
 
     // Stop streaming process
     {
@@ -213,8 +385,8 @@ pub async fn stop_stream(state: State<'_, AppState>, id: i32) -> Result<(), Stri
             // Get PID before killing (for double-check)
             let pid = child.id();
 
-            // Try to kill the process
-            if let Err(e) = child.kill() {
+            // Try to kill the process (and on Windows its whole job)
+            if let Err(e) = state.pipelines.kill_process(&mut child) {
                 eprintln!("[Stream] Warning: Failed to kill FFmpeg process: {}", e);
             }
 
@@ -248,242 +420,579 @@ pub async fn stop_stream(state: State<'_, AppState>, id: i32) -> Result<(), Stri
 
         if let Some(mut child) = recording_processes.remove(&id) {
             println!("[Stream] Stopping active recording for camera {}", id);
-            let _ = child.kill();
+            let _ = state.pipelines.kill_process(&mut child);
             let _ = child.wait();
 
             // Clean up recording database entry
             // Note: This is a simplified cleanup - the recording will be marked as unfinished
             // A full implementation might want to finalize the recording properly
-            if let Ok(conn) = Connection::open(&state.db_path) {
+            if let Ok(conn) = crate::db::open(&state.db_path) {
                 let _ = conn.execute(
                     "DELETE FROM recordings WHERE camera_id = ?1 AND is_finished = 0",
                     [id]
                 );
+                let _ = prune_recording_sessions(&conn, id);
                 println!("[Stream] Cleaned up unfinished recording for camera {}", id);
             }
         }
     }
 
-    let stream_dir = state.stream_dir.join(id.to_string());
-    if stream_dir.exists() {
-        // Optional: clean up files after stop? Reference does it.
-        // fs::remove_dir_all(&stream_dir).map_err(|e| e.to_string())?;
-    }
+    crate::stream_cleanup::remove_stream_dir(&state, id)?;
 
     Ok(())
 }
 
 pub async fn start_recording(state: State<'_, AppState>, camera: Camera) -> Result<(), String> {
-    start_recording_with_options(state, camera.id, None).await
+    start_recording_with_options(state, camera.id, None, RecordingMeta::new(RecordingTrigger::Manual)).await?;
+    Ok(())
 }
 
+/// Start a recording and return its id
 pub async fn start_recording_with_options(
     state: State<'_, AppState>,
     camera_id: i32,
-    fps: Option<i32>
-) -> Result<(), String> {
+    fps: Option<i32>,
+    meta: RecordingMeta
+) -> Result<i64, String> {
     start_recording_internal(
         &state.db_path,
+        &state.onvif_http,
         &state.recording_processes,
         &state.recording_dir,
+        &state.events,
+        &state.pipelines,
         camera_id,
-        fps
-    ).await
+        fps,
+        meta
+    ).await.inspect_err(|e| state.pipelines.error(camera_id, e))
 }
 
-// Internal implementation shared by both Tauri commands and scheduler
-async fn start_recording_internal(
-    db_path: &str,
-    recording_processes: &Arc<Mutex<HashMap<i32, Child>>>,
-    recording_dir: &PathBuf,
-    camera_id: i32,
-    fps: Option<i32>
-) -> Result<(), String> {
-    let id = camera_id;
+/// RTSP transports a camera can use: TCP interleaved (default), UDP, UDP multicast and
+/// RTSP tunnelled over HTTP
+pub const RTSP_TRANSPORTS: [&str; 4] = ["tcp", "udp", "multicast", "http"];
+
+/// FFmpeg input options for a network camera's `source`: its RTSP transport and input tuning.
+/// RTSP-only options are left out for HTTP(S) sources and HTTP reconnects for RTSP ones, since
+/// FFmpeg refuses options the input doesn't consume.
+pub(crate) fn network_input_args(camera: &Camera, source: &str) -> Vec<String> {
+    let tuning = &camera.input_tuning;
+    let is_http = source.starts_with("http://") || source.starts_with("https://");
+    let mut args = Vec::new();
+
+    if !is_http {
+        let transport = match camera.rtsp_transport.as_str() {
+            "udp" => "udp",
+            "multicast" => "udp_multicast",
+            "http" => "http",
+            _ => "tcp",
+        };
+        args.extend(["-rtsp_transport".to_string(), transport.to_string()]);
+        if let Some(ms) = tuning.socket_timeout_ms {
+            args.extend([rtsp_socket_timeout_option().to_string(), (ms as i64 * 1000).to_string()]);
+        }
+    } else if tuning.reconnect {
+        args.extend(["-reconnect", "1", "-reconnect_streamed", "1", "-reconnect_on_network_error", "1"].map(String::from));
+        if let Some(secs) = tuning.reconnect_delay_max_secs {
+            args.extend(["-reconnect_delay_max".to_string(), secs.to_string()]);
+        }
+    }
 
-    // Check if already recording
+    if let Some(ms) = tuning.rw_timeout_ms {
+        args.extend(["-rw_timeout".to_string(), (ms as i64 * 1000).to_string()]);
+    }
+    if let Some(bytes) = tuning.probesize {
+        args.extend(["-probesize".to_string(), bytes.to_string()]);
+    }
+    if let Some(ms) = tuning.analyzeduration_ms {
+        args.extend(["-analyzeduration".to_string(), (ms * 1000).to_string()]);
+    }
+    if let Some(user_agent) = &tuning.user_agent {
+        args.extend(["-user_agent".to_string(), user_agent.clone()]);
+    }
+    // Custom arguments come last so they can override the generated ones
+    args.extend(camera.extra_input_args.iter().cloned());
+    args
+}
+
+// How long ffprobe may take to open a stream and read its parameters
+const PROBE_TIMEOUT_SECS: u64 = 15;
+
+/// Open an RTSP or HTTP stream with ffprobe and read its codecs, resolution and frame rate
+pub async fn probe_stream(source: &str) -> Result<StreamProbe, String> {
+    let mut cmd = tokio::process::Command::new("ffprobe");
+    cmd.args(["-v", "error"]);
+    if !(source.starts_with("http://") || source.starts_with("https://")) {
+        cmd.args(["-rtsp_transport", "tcp"]);
+    }
+    cmd.args(["-show_entries", "stream=codec_type,codec_name,width,height,avg_frame_rate", "-of", "json"])
+        .arg(source)
+        .kill_on_drop(true);
+
+    #[cfg(target_os = "windows")]
     {
-        let processes = recording_processes.lock().map_err(|e| e.to_string())?;
-        if processes.contains_key(&id) {
-             return Err("Recording is already in progress".to_string());
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        cmd.creation_flags(CREATE_NO_WINDOW);
+    }
+
+    let output = tokio::time::timeout(Duration::from_secs(PROBE_TIMEOUT_SECS), cmd.output())
+        .await
+        .map_err(|_| format!("The stream didn't answer within {} seconds", PROBE_TIMEOUT_SECS))?
+        .map_err(|e| format!("Failed to run ffprobe: {}", e))?;
+    if !output.status.success() {
+        return Err(format!("ffprobe failed: {}", crate::redact::redact(String::from_utf8_lossy(&output.stderr).trim())));
+    }
+
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).map_err(|e| e.to_string())?;
+    let probe = parse_stream_probe(&json);
+    if probe.video_codec.is_none() {
+        return Err("The stream has no video".to_string());
+    }
+    Ok(probe)
+}
+
+// StreamProbe of ffprobe's `-show_entries stream=... -of json` output; the first video and
+// audio streams count
+fn parse_stream_probe(json: &serde_json::Value) -> StreamProbe {
+    let streams = json["streams"].as_array().map(Vec::as_slice).unwrap_or_default();
+    let video = streams.iter().find(|s| s["codec_type"] == "video");
+    let audio = streams.iter().find(|s| s["codec_type"] == "audio");
+    let dimension = |key: &str| video.and_then(|s| s[key].as_i64()).filter(|v| *v > 0).map(|v| v as i32);
+    // "25/1", "30000/1001"; "0/0" when the stream doesn't say
+    let fps = video.and_then(|s| s["avg_frame_rate"].as_str()).and_then(|rate| {
+        let (num, den) = rate.split_once('/')?;
+        let (num, den) = (num.parse::<f64>().ok()?, den.parse::<f64>().ok()?);
+        (num > 0.0 && den > 0.0).then(|| (num / den).round() as i32)
+    });
+    StreamProbe {
+        video_codec: video.and_then(|s| s["codec_name"].as_str()).map(String::from),
+        audio_codec: audio.and_then(|s| s["codec_name"].as_str()).map(String::from),
+        width: dimension("width"),
+        height: dimension("height"),
+        fps,
+    }
+}
+
+// Options custom FFmpeg arguments may not use: the app owns inputs, outputs and overwriting,
+// and these read or write files of their own
+const FORBIDDEN_FFMPEG_OPTIONS: [&str; 11] = [
+    "-i", "-y", "-n", "-filter_script", "-filter_complex_script", "-attach", "-dump_attachment",
+    "-report", "-progress", "-vstats_file", "-passlogfile",
+];
+const MAX_EXTRA_FFMPEG_ARGS: usize = 32;
+const MAX_EXTRA_FFMPEG_ARG_LEN: usize = 512;
+
+/// Check custom FFmpeg arguments of a camera (one argument per element, as FFmpeg receives them)
+pub fn check_extra_ffmpeg_args(args: &[String]) -> Result<(), String> {
+    if args.len() > MAX_EXTRA_FFMPEG_ARGS {
+        return Err(format!("At most {} custom FFmpeg arguments are allowed", MAX_EXTRA_FFMPEG_ARGS));
+    }
+    if args.first().is_some_and(|first| !first.starts_with('-')) {
+        return Err(format!("Custom FFmpeg arguments must start with an option, not '{}'", crate::redact::redact(&args[0])));
+    }
+    for arg in args {
+        if arg.is_empty() || arg.len() > MAX_EXTRA_FFMPEG_ARG_LEN || arg.chars().any(char::is_control) {
+            return Err(format!(
+                "Custom FFmpeg arguments must be single lines of 1 to {} characters",
+                MAX_EXTRA_FFMPEG_ARG_LEN
+            ));
+        }
+        // "-f:v" is the option "-f" for a stream specifier
+        let option = arg.split(':').next().unwrap_or(arg);
+        if FORBIDDEN_FFMPEG_OPTIONS.contains(&option) {
+            return Err(format!("'{}' can't be used in custom FFmpeg arguments", option));
         }
     }
+    Ok(())
+}
 
-    // Get camera info
-    let camera = {
-        let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
-        let mut stmt = conn.prepare(
-            "SELECT id, name, type, host, port, user, pass, xaddr, stream_path,
-                    device_path, device_id, device_index,
-                    video_format, video_width, video_height, video_fps,
-                    created_at, updated_at
-             FROM cameras WHERE id = ?1"
-        ).map_err(|e| e.to_string())?;
+// The camera's custom output arguments, logged (redacted) since they are a common cause of failures
+fn custom_output_args(camera: &Camera) -> Vec<String> {
+    if !camera.extra_input_args.is_empty() || !camera.extra_output_args.is_empty() {
+        println!(
+            "[Stream] Camera {} uses custom FFmpeg arguments: input [{}], output [{}]",
+            camera.id,
+            crate::redact::redact(&camera.extra_input_args.join(" ")),
+            crate::redact::redact(&camera.extra_output_args.join(" "))
+        );
+    }
+    camera.extra_output_args.clone()
+}
 
-        stmt.query_row([id], |row| {
-            let created_at_str: String = row.get(16)?;
-            let updated_at_str: String = row.get(17)?;
-
-            Ok(Camera {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                camera_type: row.get(2)?,
-                host: row.get(3)?,
-                port: row.get(4)?,
-                user: row.get(5)?,
-                pass: row.get(6)?,
-                xaddr: row.get(7)?,
-                stream_path: row.get(8)?,
-                device_path: row.get(9)?,
-                device_id: row.get(10)?,
-                device_index: row.get(11)?,
-                video_format: row.get(12)?,
-                video_width: row.get(13)?,
-                video_height: row.get(14)?,
-                video_fps: row.get(15)?,
-                created_at: DateTime::parse_from_rfc3339(&created_at_str)
-                    .unwrap_or(Utc::now().into())
-                    .with_timezone(&Utc),
-                updated_at: DateTime::parse_from_rfc3339(&updated_at_str)
-                    .unwrap_or(Utc::now().into())
-                    .with_timezone(&Utc),
-            })
-        }).map_err(|e| format!("Camera not found: {}", e))?
-    };
+/// FFmpeg filter chain of a camera's video filters, None when it has none.
+/// Deinterlacing comes first since rotating mixes up the fields.
+pub(crate) fn video_filter_chain(filters: &VideoFilters) -> Option<String> {
+    let mut chain = Vec::new();
+    if filters.deinterlace {
+        chain.push("yadif");
+    }
+    match filters.rotation {
+        90 => chain.push("transpose=clock"),
+        180 => chain.extend(["hflip", "vflip"]),
+        270 => chain.push("transpose=cclock"),
+        _ => {}
+    }
+    if filters.hflip {
+        chain.push("hflip");
+    }
+    if filters.vflip {
+        chain.push("vflip");
+    }
+    (!chain.is_empty()).then(|| chain.join(","))
+}
 
-    // Get the rtsp url
-    let rtsp_url = get_rtsp_url(&camera).await?;
+/// `-vf` arguments of the camera's video filters, the same for the live stream, recordings,
+/// the pre-recording buffer and snapshots
+pub fn video_filter_args(camera: &Camera) -> Vec<String> {
+    match video_filter_chain(&camera.video_filters) {
+        Some(chain) => vec!["-vf".to_string(), chain],
+        None => Vec::new(),
+    }
+}
 
-    let temp_filename = format!("temp_rec_{}.ts", id);
-    let temp_file_path = recording_dir.join(&temp_filename);
+// Major version of the installed FFmpeg, None if unknown (e.g. a git build)
+static FFMPEG_MAJOR_VERSION: LazyLock<Option<u32>> = LazyLock::new(|| {
+    let mut cmd = crate::ffmpeg::command();
+    cmd.arg("-version");
+    let output = cmd.output().ok()?;
+    parse_ffmpeg_major_version(&String::from_utf8_lossy(&output.stdout))
+});
+
+// "ffmpeg version 6.1.1-3ubuntu5 Copyright ..." or "ffmpeg version n7.0 ..."
+fn parse_ffmpeg_major_version(output: &str) -> Option<u32> {
+    let version = output.split_whitespace().nth(2)?;
+    version.trim_start_matches('n').split('.').next()?.parse().ok()
+}
 
-    println!("[Recording] Starting FFmpeg for camera {}: {}", id, rtsp_url);
-    if let Some(target_fps) = fps {
-        println!("[Recording] Target FPS: {}", target_fps);
+// FFmpeg 5 renamed the RTSP socket timeout -stimeout to -timeout, which had meant a listen timeout
+fn rtsp_socket_timeout_option() -> &'static str {
+    match *FFMPEG_MAJOR_VERSION {
+        Some(major) if major < 5 => "-stimeout",
+        _ => "-timeout",
     }
+}
 
-    // Get encoder configuration
-    let encoder_selector = build_encoder_selector_from_path(db_path).await?;
-    let encoder_config = encoder_selector.select_encoder_for_recording().await;
+/// Containers a camera can record to: MP4 (captured as TS, remuxed with faststart when the
+/// recording stops), fragmented MP4 and Matroska (both written directly)
+pub const RECORDING_CONTAINERS: [&str; 3] = ["mp4", "fmp4", "mkv"];
+
+// Unfinished "mp4" recordings are named temp_rec_<camera id>.ts until they are remuxed
+const TEMP_RECORDING_PREFIX: &str = "temp_rec_";
+
+/// Container the camera records to, "mp4" unless set in camera_recording_formats
+pub fn load_recording_container(conn: &Connection, camera_id: i32) -> Result<String, String> {
+    let container: Option<String> = conn.query_row(
+        "SELECT container FROM camera_recording_formats WHERE camera_id = ?1",
+        [camera_id],
+        |row| row.get(0),
+    ).optional().map_err(|e| e.to_string())?;
+    Ok(container.unwrap_or_else(|| "mp4".to_string()))
+}
 
-    println!("[Recording] Using encoder: {} (GPU: {})", encoder_config.codec, encoder_config.is_gpu);
+// Final file name of a recording, stamped in the configured time zone
+fn recording_filename(camera_id: i32, container: &str, start_time: DateTime<Utc>) -> String {
+    let extension = if container == "mkv" { "mkv" } else { "mp4" };
+    let start_time = start_time.with_timezone(&crate::settings::timezone());
+    format!("rec_{}_{}.{}", camera_id, start_time.format("%Y%m%d_%H%M%S"), extension)
+}
 
-    // Build FFmpeg command
-    let mut args = vec!["-y".to_string()];
+/// Copy a recording into a regular MP4 with the index at the front, which players can start
+/// before the whole file has downloaded
+pub fn remux_faststart(source: &Path, target: &Path) -> Result<(), String> {
+    let output = CommandBuilder::new()
+        .input(InputSource::File(source))
+        .args(["-c", "copy", "-movflags", "+faststart"])
+        .output(target)
+        .command()
+        .output()
+        .map_err(|e| format!("Failed to remux recording: {}", e))?;
 
-    // Add input format and device arguments based on camera type
-    match camera.camera_type.as_str() {
-        "uvc" => {
-            // UVC camera - use device input with detected settings
-            #[cfg(target_os = "linux")]
-            {
-                // Error handling flags for robust MJPEG decoding
-                args.extend_from_slice(&[
-                    "-err_detect".to_string(), "ignore_err".to_string(),  // Ignore MJPEG decode errors
-                    "-fflags".to_string(), "+genpts".to_string(),         // Generate timestamps
-                    "-avoid_negative_ts".to_string(), "make_zero".to_string(),  // Handle timestamp issues
-                ]);
-
-                // Use detected video format if available
-                if let Some(ref format) = camera.video_format {
-                    args.extend_from_slice(&[
-                        "-input_format".to_string(), format.clone(),
-                    ]);
-                }
+    if !output.status.success() {
+        let _ = fs::remove_file(target);
+        return Err(format!("FFmpeg remux failed: {}", String::from_utf8_lossy(&output.stderr)));
+    }
+    Ok(())
+}
 
-                // Use detected resolution if available
-                if let (Some(width), Some(height)) = (camera.video_width, camera.video_height) {
-                    args.extend_from_slice(&[
-                        "-video_size".to_string(), format!("{}x{}", width, height),
-                    ]);
-                }
+// Height of each pane of a side-by-side export
+const COMPARISON_HEIGHT: u32 = 720;
 
-                // Use detected FPS if available
-                if let Some(fps) = camera.video_fps {
-                    args.extend_from_slice(&[
-                        "-framerate".to_string(), fps.to_string(),
-                    ]);
-                }
+/// Join the segments of a recording session, oldest first, into one file without re-encoding;
+/// an MP4 target gets its index at the front
+pub fn concat_recordings(sources: &[PathBuf], target: &Path) -> Result<(), String> {
+    let list_path = std::env::temp_dir().join(format!("concat_{}.txt", uuid::Uuid::new_v4()));
+    let list: String = sources.iter()
+        .map(|source| format!("file '{}'\n", source.to_string_lossy().replace('\'', "'\\''")))
+        .collect();
+    fs::write(&list_path, list).map_err(|e| e.to_string())?;
 
-                args.extend_from_slice(&[
-                    "-f".to_string(), "v4l2".to_string(),
-                    "-i".to_string(), rtsp_url.clone(),
-                ]);
+    let output = concat_command(CommandBuilder::new(), &list_path, target).command().output();
+    let _ = fs::remove_file(&list_path);
+    let output = output.map_err(|e| format!("Failed to join recording segments: {}", e))?;
 
-                println!("[Recording] UVC input: format={:?}, size={:?}x{:?}, fps={:?}",
-                    camera.video_format, camera.video_width, camera.video_height, camera.video_fps);
-            }
+    if !output.status.success() {
+        let _ = fs::remove_file(target);
+        return Err(format!("FFmpeg concat failed: {}", String::from_utf8_lossy(&output.stderr)));
+    }
+    Ok(())
+}
 
-            #[cfg(target_os = "windows")]
-            {
-                args.extend_from_slice(&[
-                    "-f".to_string(), "dshow".to_string(),
-                    "-i".to_string(), format!("video={}", rtsp_url),
-                ]);
-                // TODO: Add format/resolution/fps detection for Windows
-            }
+// FFmpeg arguments of concat_recordings; `list_path` holds the concat demuxer's file list
+fn concat_command(builder: CommandBuilder, list_path: &Path, target: &Path) -> CommandBuilder {
+    builder.input(InputSource::ConcatList(list_path))
+        .args(["-map", "0", "-c", "copy"])
+        .faststart(target)
+        .output(target)
+}
 
-            #[cfg(target_os = "macos")]
-            {
-                args.extend_from_slice(&[
-                    "-f".to_string(), "avfoundation".to_string(),
-                    "-i".to_string(), rtsp_url.clone(),
-                ]);
-                // TODO: Add format/resolution/fps detection for macOS
-            }
+/// Re-encode a recording (or a joined session) with a watermark burned in; audio is copied as is
+pub fn export_watermarked(source: &Path, target: &Path, watermark: &Watermark) -> Result<(), String> {
+    let output = watermark_command(CommandBuilder::new(), source, target, watermark)
+        .command()
+        .output()
+        .map_err(|e| format!("Failed to watermark recording: {}", e))?;
+
+    if !output.status.success() {
+        let _ = fs::remove_file(target);
+        return Err(format!("FFmpeg watermark failed: {}", String::from_utf8_lossy(&output.stderr)));
+    }
+    Ok(())
+}
+
+// FFmpeg arguments of export_watermarked
+fn watermark_command(builder: CommandBuilder, source: &Path, target: &Path, watermark: &Watermark) -> CommandBuilder {
+    builder.input(InputSource::File(source))
+        .args(crate::watermark::input_args(watermark))
+        .filter_complex(crate::watermark::filter(watermark, "0:v", 1, "out"))
+        .args(["-map", "[out]", "-map", "0:a?", "-c:a", "copy"])
+        .args(["-c:v", "libx264", "-preset", "veryfast", "-crf", "20", "-pix_fmt", "yuv420p"])
+        .faststart(target)
+        .output(target)
+}
+
+/// Seek into a recording and black padding in front of it so that it shows `from`.
+/// Returns (seek_secs, pad_secs); a recording that starts after `from` is padded instead.
+pub(crate) fn comparison_timing(start: DateTime<Utc>, from: DateTime<Utc>) -> (f64, f64) {
+    let offset = (from - start).num_milliseconds() as f64 / 1000.0;
+    if offset >= 0.0 { (offset, 0.0) } else { (0.0, -offset) }
+}
+
+/// Render two recordings side by side, each aligned to the same wall-clock start. `inputs` are
+/// (file, seek_secs, pad_secs) from `comparison_timing`; both are scaled to a common height.
+pub fn export_side_by_side(
+    inputs: &[(PathBuf, f64, f64); 2],
+    duration_secs: f64,
+    watermark: Option<&Watermark>,
+    target: &Path,
+) -> Result<(), String> {
+    let output = side_by_side_command(CommandBuilder::new(), inputs, duration_secs, watermark, target)
+        .command()
+        .output()
+        .map_err(|e| format!("Failed to export comparison: {}", e))?;
+
+    if !output.status.success() {
+        let _ = fs::remove_file(target);
+        return Err(format!("FFmpeg comparison export failed: {}", String::from_utf8_lossy(&output.stderr)));
+    }
+    Ok(())
+}
+
+// FFmpeg arguments of export_side_by_side
+fn side_by_side_command(
+    mut builder: CommandBuilder,
+    inputs: &[(PathBuf, f64, f64); 2],
+    duration_secs: f64,
+    watermark: Option<&Watermark>,
+    target: &Path,
+) -> CommandBuilder {
+    for (source, seek, _) in inputs {
+        builder = builder.seek(*seek).input(InputSource::File(source));
+    }
+
+    let pane = |index: usize, pad: f64| {
+        format!(
+            "[{index}:v]tpad=start_duration={pad:.3}:color=black,scale=-2:{COMPARISON_HEIGHT},setsar=1[v{index}]"
+        )
+    };
+    let mut filter = format!(
+        "{};{};[v0][v1]hstack=inputs=2",
+        pane(0, inputs[0].2),
+        pane(1, inputs[1].2)
+    );
+    // One watermark over the whole frame, not one per pane
+    match watermark {
+        Some(watermark) => {
+            builder = builder.args(crate::watermark::input_args(watermark));
+            filter.push_str("[stacked];");
+            filter.push_str(&crate::watermark::filter(watermark, "stacked", 2, "out"));
         }
-        _ => {
-            // ONVIF/RTSP camera - use RTSP input
-            args.extend_from_slice(&[
-                "-rtsp_transport".to_string(), "tcp".to_string(),
-                "-i".to_string(), rtsp_url.clone(),
-            ]);
+        None => filter.push_str("[out]"),
+    }
+    builder.filter_complex(filter)
+        .args(["-map", "[out]", "-an"])
+        .duration(duration_secs)
+        .args(["-c:v", "libx264", "-preset", "veryfast", "-crf", "23", "-pix_fmt", "yuv420p"])
+        .args(["-movflags", "+faststart"])
+        .output(target)
+}
+
+/// MIME type of a recording file
+pub fn recording_content_type(filename: &str) -> &'static str {
+    match Path::new(filename).extension().and_then(|e| e.to_str()) {
+        Some("mp4") => "video/mp4",
+        Some("mkv") => "video/x-matroska",
+        _ => "application/octet-stream",
+    }
+}
+
+// Load a camera row by id (used where only the id is known, e.g. scheduled jobs)
+pub(crate) fn load_camera(db_path: &str, camera_id: i32) -> Result<Camera, String> {
+    let conn = crate::db::open(db_path).map_err(|e| e.to_string())?;
+    crate::repository::CameraRepo::get(&conn, camera_id)
+}
+
+// Internal implementation shared by both Tauri commands and scheduler
+#[allow(clippy::too_many_arguments)]
+async fn start_recording_internal(
+    db_path: &str,
+    onvif_http: &OnvifHttpClient,
+    recording_processes: &Arc<Mutex<HashMap<i32, Child>>>,
+    recording_dir: &PathBuf,
+    events: &EventBus,
+    pipelines: &PipelineTracker,
+    camera_id: i32,
+    fps: Option<i32>,
+    meta: RecordingMeta
+) -> Result<i64, String> {
+    // Check if already recording
+    {
+        let processes = recording_processes.lock().map_err(|e| e.to_string())?;
+        if processes.contains_key(&camera_id) {
+             return Err("Recording is already in progress".to_string());
+        }
+    }
+
+    match spawn_recording(db_path, onvif_http, recording_processes, recording_dir, pipelines, camera_id, fps, meta).await {
+        Ok(recording_id) => {
+            events.emit(AppEvent::RecordingStarted { camera_id, recording_id });
+            pipelines.camera_online(events, camera_id);
+            Ok(recording_id)
+        }
+        Err(e) => {
+            events.emit(AppEvent::RecordingFailed { camera_id, error: e.clone() });
+            Err(e)
         }
     }
+}
+
+// Start FFmpeg and register the recording; returns the new recording id
+#[allow(clippy::too_many_arguments)]
+async fn spawn_recording(
+    db_path: &str,
+    onvif_http: &OnvifHttpClient,
+    recording_processes: &Arc<Mutex<HashMap<i32, Child>>>,
+    recording_dir: &PathBuf,
+    pipelines: &PipelineTracker,
+    camera_id: i32,
+    fps: Option<i32>,
+    meta: RecordingMeta
+) -> Result<i64, String> {
+    let id = camera_id;
+
+    // Get camera info
+    let camera = load_camera(db_path, id)?;
+
+    // Get the rtsp url
+    let rtsp_url = get_rtsp_url(onvif_http, &camera).await?;
 
-    // Add FPS filter if specified
+    let (container, (location_id, location_root)) = {
+        let conn = crate::db::open(db_path).map_err(|e| e.to_string())?;
+        (load_recording_container(&conn, id)?, crate::storage::choose_location(&conn, recording_dir, id)?)
+    };
+    // "mp4" is captured to a temporary TS and remuxed when the recording stops; the other
+    // containers are written under their final name and survive a crash as they are
+    let temp_filename = match container.as_str() {
+        "mp4" => format!("{}{}.ts", TEMP_RECORDING_PREFIX, id),
+        _ => recording_filename(id, &container, Utc::now()),
+    };
+    let temp_file_path = location_root.join(&temp_filename);
+
+    println!("[Recording] Starting FFmpeg for camera {}: {}", id, crate::redact::redact(&rtsp_url));
     if let Some(target_fps) = fps {
-        args.extend_from_slice(&[
-            "-r".to_string(),
-            target_fps.to_string(),
-        ]);
+        println!("[Recording] Target FPS: {}", target_fps);
     }
 
-    // Add encoder-specific arguments
-    args.extend(encoder_config.args);
+    // Get encoder configuration
+    let preset = crate::encoder::quality_preset_for(&crate::db::open(db_path).map_err(|e| e.to_string())?, id)?;
+    let encoder_selector = build_encoder_selector_from_path(db_path).await?.with_preset(preset);
+    let mut encoder_config = encoder_selector.select_encoder_for_recording().await;
+    // The GPU encoder already broke on this camera's live stream
+    if encoder_config.is_gpu && encoder_selector.settings.encoderMode != "GpuOnly" && pipelines.cpu_fallback(id).is_some() {
+        encoder_config = encoder_selector.build_cpu_config_recording();
+    }
 
-    // Add audio and output format
-    args.extend_from_slice(&[
-        "-c:a".to_string(), "aac".to_string(),
-        "-f".to_string(), "mpegts".to_string(),
-        temp_file_path.to_str().unwrap().to_string(),
-    ]);
+    println!("[Recording] Using encoder: {} (GPU: {})", encoder_config.codec, encoder_config.is_gpu);
+    let encoder_config_is_gpu = encoder_config.is_gpu;
 
-    // Spawn FFmpeg for recording
-    let mut cmd = Command::new("ffmpeg");
-    cmd.args(&args)
-        .stdout(Stdio::null())
-        .stderr(Stdio::inherit());
+    if camera.camera_type == "uvc" {
+        println!("[Recording] UVC input: format={:?}, size={:?}x{:?}, fps={:?}",
+            camera.video_format, camera.video_width, camera.video_height, camera.video_fps);
+    }
 
-    // Hide console window on Windows
-    #[cfg(target_os = "windows")]
-    {
-        const CREATE_NO_WINDOW: u32 = 0x08000000;
-        cmd.creation_flags(CREATE_NO_WINDOW);
+    // Spawn FFmpeg for recording
+    let mut cmd = CommandBuilder::new()
+        .input(InputSource::camera(&camera, &rtsp_url))
+        .frame_rate(fps)
+        .filters(&camera.video_filters)
+        .encoder(&encoder_config)
+        .args(pipelines.run_marker_args())
+        .args(encoder_selector.audio_args(false))
+        .container(&container)
+        .args(custom_output_args(&camera))
+        .output(&temp_file_path)
+        .command();
+    cmd.stdout(Stdio::null())
+        .stderr(Stdio::piped());
+
+    if camera.camera_type == "rpicam" {
+        cmd.stdin(crate::plugins::rpi_camera_plugin::start_source(&camera)?);
     }
 
-    let child = cmd.spawn()
+    let mut child = cmd.spawn()
         .map_err(|e| format!("Failed to start recording ffmpeg: {}", e))?;
+    let stderr = crate::redact::forward_stderr(&mut child);
+    pipelines.adopt_process(&child);
 
     // FFmpeg started successfully - now insert DB record in transaction
-    {
-        let mut conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    let recording_id = {
+        let mut conn = crate::db::open(db_path).map_err(|e| e.to_string())?;
         let tx = conn.transaction().map_err(|e| e.to_string())?;
 
+        // A new recording (not a resumed segment) ends any session left paused on this camera
+        // and starts a session of its own
+        let started_at = Utc::now().to_rfc3339();
+        let session_id = match meta.session_id {
+            Some(session_id) => {
+                tx.execute("UPDATE recording_sessions SET ended_at = NULL WHERE id = ?1", [session_id])
+                    .map_err(|e| e.to_string())?;
+                session_id
+            }
+            None => {
+                discard_paused_session(&tx, id)?;
+                tx.execute(
+                    "INSERT INTO recording_sessions (camera_id, trigger, started_at) VALUES (?1, ?2, ?3)",
+                    (id, meta.trigger.to_string(), &started_at),
+                ).map_err(|e| e.to_string())?;
+                tx.last_insert_rowid()
+            }
+        };
+
+        let tags = serde_json::to_string(&meta.tags).map_err(|e| e.to_string())?;
         tx.execute(
-            "INSERT INTO recordings (camera_id, filename, start_time, is_finished) VALUES (?1, ?2, ?3, ?4)",
-            (id, &temp_filename, Utc::now().to_rfc3339(), false),
+            "INSERT INTO recordings (camera_id, filename, start_time, is_finished, trigger, tags, notes, session_id, location_id)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            (id, &temp_filename, &started_at, false, meta.trigger.to_string(), tags, &meta.notes, session_id, location_id),
         ).map_err(|e| e.to_string())?;
+        let recording_id = tx.last_insert_rowid();
 
         tx.commit().map_err(|e| {
             eprintln!("[Recording] Failed to commit transaction");
@@ -491,29 +1000,40 @@ async fn start_recording_internal(
         })?;
 
         println!("[Recording] Recording registered in database successfully");
-    }
+
+        // Keep the seconds before the start from the pre-recording buffer, if the camera has one
+        if meta.session_id.is_none() {
+            if let Err(e) = crate::prebuffer::snapshot(&conn, id, recording_id) {
+                eprintln!("[Recording] Failed to keep pre-roll of recording {}: {}", recording_id, e);
+            }
+        }
+        recording_id
+    };
 
     // Save process
     {
         let mut processes = recording_processes.lock().map_err(|e| e.to_string())?;
         processes.insert(id, child);
     }
+    pipelines.recording_spawned(id, stderr);
+    pipelines.set_recording_gpu(id, encoder_config_is_gpu);
 
-    Ok(())
+    Ok(recording_id)
 }
 
 pub async fn stop_recording(
     state: State<'_, AppState>,
-    app_handle: tauri::AppHandle,
     id: i32
 ) -> Result<(), String> {
     stop_recording_internal(
         &state.db_path,
         &state.recording_processes,
         &state.recording_dir,
-        id,
-        Some(&app_handle)
-    ).await
+        &state.thumbnail_queue,
+        &state.events,
+        &state.pipelines,
+        id
+    ).await.inspect_err(|e| state.pipelines.error(id, e))
 }
 
 // Internal implementation shared by both Tauri commands and scheduler
@@ -521,8 +1041,10 @@ async fn stop_recording_internal(
     db_path: &str,
     recording_processes: &Arc<Mutex<HashMap<i32, Child>>>,
     recording_dir: &PathBuf,
-    camera_id: i32,
-    app_handle: Option<&tauri::AppHandle>
+    thumbnails: &ThumbnailQueue,
+    events: &EventBus,
+    pipelines: &PipelineTracker,
+    camera_id: i32
 ) -> Result<(), String> {
     let id = camera_id;
 
@@ -530,7 +1052,7 @@ async fn stop_recording_internal(
     let process_was_running = {
         let mut processes = recording_processes.lock().map_err(|e| e.to_string())?;
         if let Some(mut child) = processes.remove(&id) {
-            if let Err(e) = child.kill() {
+            if let Err(e) = pipelines.kill_process(&mut child) {
                 eprintln!("[Recording] Warning: Failed to kill process: {}", e);
             }
 
@@ -551,93 +1073,89 @@ async fn stop_recording_internal(
         }
     };
 
-    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    let recording_info: Option<(i32, String, String, i64, Option<i64>)> = {
+        let conn = crate::db::open(db_path).map_err(|e| e.to_string())?;
 
-    // Find the active recording for this camera
-    let mut stmt = conn.prepare("SELECT id, filename, start_time FROM recordings WHERE camera_id = ?1 AND is_finished = 0 ORDER BY start_time DESC LIMIT 1").map_err(|e| e.to_string())?;
+        // Stopping a paused recording ends its session at the pause
+        discard_paused_session(&conn, id)?;
 
-    let recording_info: Option<(i32, String, String)> = stmt.query_row([id], |row| {
-        Ok((row.get(0)?, row.get(1)?, row.get(2)?))
-    }).ok();
+        // Find the active recording for this camera
+        let mut stmt = conn.prepare("SELECT id, filename, start_time, session_id, location_id FROM recordings WHERE camera_id = ?1 AND is_finished = 0 ORDER BY start_time DESC LIMIT 1").map_err(|e| e.to_string())?;
 
-    if let Some((rec_id, temp_filename, start_time_str)) = recording_info {
-        let temp_path = recording_dir.join(&temp_filename);
+        stmt.query_row([id], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?))
+        }).ok()
+    };
+
+    if let Some((rec_id, temp_filename, start_time_str, session_id, location_id)) = recording_info {
+        let location_root = {
+            let conn = crate::db::open(db_path).map_err(|e| e.to_string())?;
+            crate::storage::location_root(&conn, recording_dir, location_id)?
+        };
+        let temp_path = location_root.join(&temp_filename);
+        // The pre-roll goes in front while the file still has its capture container
+        let preroll_secs = if temp_path.exists() {
+            crate::prebuffer::prepend(db_path, rec_id as i64, &temp_path).await
+        } else {
+            0.0
+        };
+        let conn = crate::db::open(db_path).map_err(|e| e.to_string())?;
 
         if temp_path.exists() {
-             // Generate final filename using JST timezone
              let start_time = DateTime::parse_from_rfc3339(&start_time_str)
                  .map_err(|e| format!("Invalid start_time: {}", e))?
-                 .with_timezone(&Tokyo);
-             let final_filename = format!("rec_{}_{}.mp4", id, start_time.format("%Y%m%d_%H%M%S"));
-             let final_path = recording_dir.join(&final_filename);
-
-             println!("[Recording] Converting {} to {}", temp_filename, final_filename);
-
-             // Convert TS to MP4 (remux)
-             let mut cmd = Command::new("ffmpeg");
-             cmd.args([
-                    "-y",
-                    "-i", temp_path.to_str().unwrap(),
-                    "-c", "copy",
-                    "-movflags", "+faststart",
-                    final_path.to_str().unwrap()
-                ]);
-
-             // Hide console window on Windows
-             #[cfg(target_os = "windows")]
-             {
-                 const CREATE_NO_WINDOW: u32 = 0x08000000;
-                 cmd.creation_flags(CREATE_NO_WINDOW);
-             }
-
-             let output = cmd.output()
-                .map_err(|e| format!("Failed to remux recording: {}", e))?;
-
-             if !output.status.success() {
-                 return Err(format!("FFmpeg remux failed: {}", String::from_utf8_lossy(&output.stderr)));
-             }
-
-             // Remove temp file
-             let _ = fs::remove_file(&temp_path);
-
-             // Generate thumbnail
-             let thumbnail_filename = final_filename.replace(".mp4", ".jpg");
-             let thumbnail_path = recording_dir.join("thumbnails").join(&thumbnail_filename);
-
-             // Ensure thumbnails directory exists
-             if let Some(parent) = thumbnail_path.parent() {
-                 fs::create_dir_all(parent).map_err(|e| format!("Failed to create thumbnails directory: {}", e))?;
-             }
-
-             // Try to generate thumbnail (non-fatal if it fails)
-             let thumbnail_result = generate_thumbnail(&final_path, &thumbnail_path);
-             let thumbnail_db_value = match thumbnail_result {
-                 Ok(_) => Some(thumbnail_filename),
-                 Err(e) => {
-                     eprintln!("[Thumbnail] Warning: Failed to generate thumbnail: {}", e);
-                     None
+                 .with_timezone(&Utc);
+
+             // Direct recordings are already in their final container
+             let final_filename = if temp_filename.starts_with(TEMP_RECORDING_PREFIX) {
+                 let final_filename = recording_filename(id, "mp4", start_time);
+                 let final_path = location_root.join(&final_filename);
+
+                 println!("[Recording] Converting {} to {}", temp_filename, final_filename);
+
+                 // Convert TS to MP4 (remux)
+                 if let Err(error) = remux_faststart(&temp_path, &final_path) {
+                     events.emit(AppEvent::RecordingFailed { camera_id, error: error.clone() });
+                     return Err(error);
                  }
+
+                 // Remove temp file
+                 let _ = fs::remove_file(&temp_path);
+                 final_filename
+             } else {
+                 temp_filename.clone()
              };
+             let final_path = location_root.join(&final_filename);
 
-             // Update DB
+             // Size is stored so integrity checks can detect truncated or replaced files
+             let file_size = fs::metadata(&final_path).ok().map(|m| m.len() as i64);
+
+             // Update DB; a pre-roll moves the start back by its length
+             let start_time = start_time - chrono::Duration::milliseconds((preroll_secs * 1000.0) as i64);
+             let end_time = Utc::now().to_rfc3339();
+             conn.execute(
+                "UPDATE recordings SET is_finished = 1, filename = ?1, start_time = ?2, end_time = ?3, file_size = ?4 WHERE id = ?5",
+                (&final_filename, start_time.to_rfc3339(), &end_time, file_size, rec_id)
+             ).map_err(|e| e.to_string())?;
              conn.execute(
-                "UPDATE recordings SET is_finished = 1, filename = ?1, thumbnail = ?2, end_time = ?3 WHERE id = ?4",
-                (&final_filename, thumbnail_db_value, Utc::now().to_rfc3339(), rec_id)
+                "UPDATE recording_sessions SET ended_at = ?1,
+                     started_at = MIN(started_at, (SELECT MIN(start_time) FROM recordings WHERE session_id = ?2))
+                 WHERE id = ?2",
+                rusqlite::params![end_time, session_id],
              ).map_err(|e| e.to_string())?;
 
              println!("[Recording] Recording saved: {}", final_filename);
 
+             // The thumbnail follows in the background, then the upload
+             thumbnails.enqueue(rec_id, camera_id);
+
              // Emit event to frontend to update recording list
-             if let Some(app) = app_handle {
-                 if let Err(e) = app.emit("recording-completed", camera_id) {
-                     eprintln!("[Event] Warning: Failed to emit recording-completed event: {}", e);
-                 } else {
-                     println!("[Event] Emitted recording-completed event for camera {}", camera_id);
-                 }
-             }
+             events.emit(AppEvent::RecordingCompleted { camera_id, recording_id: rec_id });
         } else {
             // Temp file missing - clean up DB entry
+            crate::prebuffer::discard(rec_id as i64);
             conn.execute("DELETE FROM recordings WHERE id = ?1", [rec_id]).map_err(|e| e.to_string())?;
+            prune_recording_sessions(&conn, id)?;
             println!("[Recording] Warning: Recording temp file not found, cleaned up DB entry");
         }
     } else {
@@ -654,12 +1172,156 @@ async fn stop_recording_internal(
     Ok(())
 }
 
-async fn get_rtsp_url(camera: &Camera) -> Result<String, String> {
+// Drop the open gap of a paused session, leaving its segments as they are
+fn discard_paused_session(conn: &Connection, camera_id: i32) -> Result<(), String> {
+    let discarded = conn.execute(
+        "DELETE FROM recording_gaps WHERE camera_id = ?1 AND resumed_at IS NULL",
+        [camera_id],
+    ).map_err(|e| e.to_string())?;
+
+    if discarded > 0 {
+        println!("[Recording] Ended paused recording session of camera {}", camera_id);
+    }
+    Ok(())
+}
+
+/// Pause a recording: finalize the current segment and open a gap until it is resumed.
+/// Returns the session id shared by all segments of the recording.
+pub async fn pause_recording(state: &AppState, camera_id: i32) -> Result<i64, String> {
+    if !state.recording_processes.lock().map_err(|e| e.to_string())?.contains_key(&camera_id) {
+        return Err("No recording in progress".to_string());
+    }
+
+    let (recording_id, session_id) = {
+        let conn = crate::db::open(&state.db_path).map_err(|e| e.to_string())?;
+        let (recording_id, session_id, trigger): (i32, i64, String) = conn.query_row(
+            "SELECT id, session_id, trigger FROM recordings
+             WHERE camera_id = ?1 AND is_finished = 0 ORDER BY start_time DESC LIMIT 1",
+            [camera_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        ).map_err(|_| "No recording in progress".to_string())?;
+
+        // The scheduler owns the start and stop of scheduled recordings
+        if trigger.starts_with("scheduled") {
+            return Err("Scheduled recordings can't be paused".to_string());
+        }
+        (recording_id, session_id)
+    };
+
+    // What is left of a max duration carries over to the resumed segment
+    let paused_at = Utc::now();
+    let remaining_secs = state.pipelines.recording_stops_at(camera_id, recording_id)
+        .map(|stop_at| (stop_at - paused_at).num_seconds().max(0));
+
+    stop_recording_direct(state, camera_id).await?;
+
+    let conn = crate::db::open(&state.db_path).map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT INTO recording_gaps (session_id, camera_id, paused_at, remaining_secs) VALUES (?1, ?2, ?3, ?4)",
+        rusqlite::params![session_id, camera_id, paused_at.to_rfc3339(), remaining_secs],
+    ).map_err(|e| e.to_string())?;
+
+    println!("[Recording] Paused recording session {} of camera {}", session_id, camera_id);
+    state.events.emit(AppEvent::RecordingPaused { camera_id, session_id });
+    Ok(session_id)
+}
+
+/// Resume a paused recording as a new segment of the same session; returns the segment's recording id
+pub async fn resume_recording(state: &AppState, camera_id: i32) -> Result<i64, String> {
+    let (gap_id, session_id, remaining_secs, meta) = {
+        let conn = crate::db::open(&state.db_path).map_err(|e| e.to_string())?;
+        let (gap_id, session_id, remaining_secs): (i64, i64, Option<i64>) = conn.query_row(
+            "SELECT id, session_id, remaining_secs FROM recording_gaps WHERE camera_id = ?1 AND resumed_at IS NULL",
+            [camera_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        ).map_err(|_| "Recording is not paused".to_string())?;
+
+        // The new segment carries over the session's trigger, tags and notes
+        let (trigger, tags, notes): (String, String, Option<String>) = conn.query_row(
+            "SELECT trigger, tags, notes FROM recordings WHERE session_id = ?1 ORDER BY start_time DESC LIMIT 1",
+            [session_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        ).map_err(|e| format!("Recording session {} not found: {}", session_id, e))?;
+
+        let meta = RecordingMeta {
+            trigger: trigger.parse()?,
+            tags: serde_json::from_str(&tags).unwrap_or_default(),
+            notes,
+            session_id: Some(session_id),
+        };
+        (gap_id, session_id, remaining_secs, meta)
+    };
+
+    let recording_id = start_recording_with_options_direct(state, camera_id, None, meta).await?;
+    if let Some(secs) = remaining_secs {
+        schedule_auto_stop_secs(state, camera_id, recording_id, secs);
+    }
+
+    let conn = crate::db::open(&state.db_path).map_err(|e| e.to_string())?;
+    conn.execute(
+        "UPDATE recording_gaps SET resumed_at = ?1 WHERE id = ?2",
+        rusqlite::params![Utc::now().to_rfc3339(), gap_id],
+    ).map_err(|e| e.to_string())?;
+
+    println!("[Recording] Resumed recording session {} of camera {} as recording {}", session_id, camera_id, recording_id);
+    state.events.emit(AppEvent::RecordingResumed { camera_id, session_id, recording_id });
+    Ok(recording_id)
+}
+
+/// Drop the camera's recording sessions that have no segments left, with their pauses
+pub(crate) fn prune_recording_sessions(conn: &Connection, camera_id: i32) -> Result<(), String> {
+    conn.execute(
+        "DELETE FROM recording_gaps WHERE camera_id = ?1
+         AND NOT EXISTS (SELECT 1 FROM recordings r WHERE r.session_id = recording_gaps.session_id)",
+        [camera_id],
+    ).map_err(|e| e.to_string())?;
+    conn.execute(
+        "DELETE FROM recording_sessions WHERE camera_id = ?1
+         AND NOT EXISTS (SELECT 1 FROM recordings r WHERE r.session_id = recording_sessions.id)",
+        [camera_id],
+    ).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Pauses of a recording session, oldest first
+pub fn list_recording_gaps(conn: &Connection, session_id: i64) -> Result<Vec<RecordingGap>, String> {
+    let mut stmt = conn.prepare(
+        "SELECT id, session_id, camera_id, paused_at, resumed_at FROM recording_gaps
+         WHERE session_id = ?1 ORDER BY paused_at"
+    ).map_err(|e| e.to_string())?;
+
+    let parse = |t: String| DateTime::parse_from_rfc3339(&t).map(|t| t.with_timezone(&Utc));
+    let gaps = stmt.query_map([session_id], |row| {
+        Ok(RecordingGap {
+            id: row.get(0)?,
+            session_id: row.get(1)?,
+            camera_id: row.get(2)?,
+            paused_at: parse(row.get(3)?).unwrap_or_else(|_| Utc::now()),
+            resumed_at: row.get::<_, Option<String>>(4)?.and_then(|t| parse(t).ok()),
+        })
+    }).map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok(gaps)
+}
+
+/// Local capture devices (UVC, Raspberry Pi CSI) can only be opened by one process at a time
+pub(crate) fn is_local_device(camera_type: &str) -> bool {
+    matches!(camera_type, "uvc" | "rpicam")
+}
+
+pub(crate) async fn get_rtsp_url(onvif_http: &OnvifHttpClient, camera: &Camera) -> Result<String, String> {
     match camera.camera_type.as_str() {
         "onvif" => {
             // Use ONVIF protocol to get the stream URI
-            crate::onvif::get_onvif_stream_url(&camera).await
+            crate::onvif::get_onvif_stream_url(onvif_http, camera).await
         }
+        "hikvision" => crate::plugins::hikvision_plugin::stream_url(&onvif_http.for_camera(camera), camera).await,
+        "dahua" => crate::plugins::dahua_plugin::stream_url(&onvif_http.for_camera(camera), camera).await,
+        "axis" => crate::plugins::axis_plugin::stream_url(&onvif_http.for_camera(camera), camera).await,
+        // Not a URL: the FFmpeg input is piped from rpicam-vid
+        "rpicam" => Ok(format!("rpicam:{}", camera.device_index.unwrap_or(0))),
         "uvc" => {
             // For UVC cameras, return device path (not RTSP URL)
             // This will be used as FFmpeg input device
@@ -688,97 +1350,108 @@ async fn get_rtsp_url(camera: &Camera) -> Result<String, String> {
             }
         }
         _ => {
-            // RTSP Camera
-            let base_url = if let Some(path) = &camera.stream_path {
-                format!("rtsp://{}:{}{}", camera.host, camera.port, path)
+            // RTSP Camera: stream_path is a path on the camera, or a complete URL for cameras
+            // whose stream lives elsewhere
+            let path = camera.stream_path.as_deref().unwrap_or("/");
+            let base_url = if path.contains("://") {
+                path.to_string()
             } else {
-                // Default fallback for RTSP if no path
-                format!("rtsp://{}:{}/", camera.host, camera.port)
+                crate::stream_url::rtsp_url(&camera.host, camera.port as u16, path)?
             };
 
-            if let (Some(user), Some(pass)) = (&camera.user, &camera.pass) {
-                if !user.is_empty() {
-                    Ok(base_url.replace("rtsp://", &format!("rtsp://{}:{}@", user, urlencoding::encode(pass))))
-                } else {
-                    Ok(base_url)
-                }
-            } else {
-                Ok(base_url)
-            }
+            crate::stream_url::with_credentials(
+                &base_url,
+                camera.user.as_deref().unwrap_or(""),
+                camera.pass.as_deref().unwrap_or(""),
+            )
         }
     }
 }
 
-// Generate thumbnail from video file using FFmpeg
-fn generate_thumbnail(video_path: &PathBuf, thumbnail_path: &PathBuf) -> Result<(), String> {
-    println!("[Thumbnail] Generating thumbnail from {:?} to {:?}", video_path, thumbnail_path);
-
-    // FFmpeg command: extract frame at 2 seconds, scale to 320px width, high quality
-    let mut cmd = Command::new("ffmpeg");
-    cmd.args([
-            "-y",
-            "-ss", "00:00:02",
-            "-i", video_path.to_str().unwrap(),
-            "-vframes", "1",
-            "-vf", "scale=320:-1",
-            "-q:v", "2",
-            thumbnail_path.to_str().unwrap()
-        ]);
-
-    // Hide console window on Windows
-    #[cfg(target_os = "windows")]
-    {
-        const CREATE_NO_WINDOW: u32 = 0x08000000;
-        cmd.creation_flags(CREATE_NO_WINDOW);
-    }
-
-    let output = cmd.output()
-        .map_err(|e| format!("Failed to spawn FFmpeg for thumbnail: {}", e))?;
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        println!("[Thumbnail] FFmpeg failed: {}", stderr);
-        return Err(format!("FFmpeg thumbnail generation failed: {}", stderr));
-    }
-
-    println!("[Thumbnail] Successfully generated thumbnail");
-    Ok(())
-}
-
 // Direct versions of functions for scheduler (no State wrapper needed)
 pub async fn start_recording_with_options_direct(
     state: &AppState,
     camera_id: i32,
-    fps: Option<i32>
-) -> Result<(), String> {
+    fps: Option<i32>,
+    meta: RecordingMeta
+) -> Result<i64, String> {
     start_recording_internal(
         &state.db_path,
+        &state.onvif_http,
         &state.recording_processes,
         &state.recording_dir,
+        &state.events,
+        &state.pipelines,
         camera_id,
-        fps
-    ).await
+        fps,
+        meta
+    ).await.inspect_err(|e| state.pipelines.error(camera_id, e))
 }
 
 pub async fn stop_recording_direct(
     state: &AppState,
-    id: i32,
-    app_handle: Option<&tauri::AppHandle>
+    id: i32
 ) -> Result<(), String> {
     stop_recording_internal(
         &state.db_path,
         &state.recording_processes,
         &state.recording_dir,
-        id,
-        app_handle
-    ).await
+        &state.thumbnail_queue,
+        &state.events,
+        &state.pipelines,
+        id
+    ).await.inspect_err(|e| state.pipelines.error(id, e))
+}
+
+pub fn load_default_max_duration(conn: &Connection) -> Result<Option<i32>, String> {
+    conn.query_row(
+        "SELECT default_max_duration_minutes FROM recording_settings WHERE id = 1",
+        [],
+        |row| row.get(0),
+    ).map_err(|e| e.to_string())
+}
+
+/// Stop and finalize a manual recording once it has run for `minutes`. Does nothing if the
+/// recording was stopped earlier, even when the camera has started a new one since.
+pub fn schedule_auto_stop(state: &AppState, camera_id: i32, recording_id: i64, minutes: i32) {
+    schedule_auto_stop_secs(state, camera_id, recording_id, minutes as i64 * 60);
+}
+
+// schedule_auto_stop with the run time in seconds, for resumed sessions that used part of it
+fn schedule_auto_stop_secs(state: &AppState, camera_id: i32, recording_id: i64, secs: i64) {
+    let secs = secs.max(0);
+    let stop_at = Utc::now() + chrono::Duration::seconds(secs);
+    state.pipelines.recording_stop_scheduled(camera_id, recording_id as i32, stop_at);
+    println!("[Recording] Recording {} of camera {} will stop after {} seconds", recording_id, camera_id, secs);
+
+    let app_handle = state.app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(std::time::Duration::from_secs(secs as u64)).await;
+
+        let state = app_handle.state::<AppState>();
+        let still_running = crate::db::open(&state.db_path)
+            .and_then(|conn| conn.query_row(
+                "SELECT COUNT(*) FROM recordings WHERE id = ?1 AND camera_id = ?2 AND is_finished = 0",
+                rusqlite::params![recording_id, camera_id],
+                |row| row.get::<_, i64>(0),
+            ))
+            .is_ok_and(|count| count > 0);
+        if !still_running {
+            return;
+        }
+
+        println!("[Recording] Max duration reached for recording {}, stopping", recording_id);
+        if let Err(e) = stop_recording_direct(&state, camera_id).await {
+            eprintln!("[Recording] Failed to stop recording {} after max duration: {}", recording_id, e);
+        }
+    });
 }
 
 // Helper function to build encoder selector from db_path
-async fn build_encoder_selector_from_path(db_path: &str) -> Result<EncoderSelector, String> {
+pub(crate) async fn build_encoder_selector_from_path(db_path: &str) -> Result<EncoderSelector, String> {
     let capabilities = detect_gpu_capabilities().await?;
 
-    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    let conn = crate::db::open(db_path).map_err(|e| e.to_string())?;
 
     let mut stmt = conn.prepare(
         "SELECT id, encoder_mode, gpu_encoder, cpu_encoder, preset, quality FROM encoder_settings WHERE id = 1"
@@ -798,3 +1471,156 @@ async fn build_encoder_selector_from_path(db_path: &str) -> Result<EncoderSelect
     Ok(EncoderSelector::new(capabilities, settings))
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ffmpeg::Platform;
+    use crate::models::InputTuning;
+
+    fn camera(tuning: InputTuning) -> Camera {
+        let mut camera: Camera = serde_json::from_value(serde_json::json!({
+            "id": 1, "name": "Gate", "type": "rtsp", "host": "10.0.0.5", "port": 554,
+            "tls_verify": false, "rtsp_transport": "udp", "sort_order": 0, "is_favorite": false,
+            "created_at": "2024-05-01T00:00:00Z", "updated_at": "2024-05-01T00:00:00Z",
+        })).unwrap();
+        camera.input_tuning = tuning;
+        camera
+    }
+
+    #[test]
+    fn video_filters_deinterlace_before_rotating() {
+        assert_eq!(video_filter_chain(&VideoFilters::default()), None);
+        let filters = VideoFilters { deinterlace: true, rotation: 90, hflip: true, vflip: false };
+        assert_eq!(video_filter_chain(&filters).as_deref(), Some("yadif,transpose=clock,hflip"));
+        let filters = VideoFilters { rotation: 180, ..Default::default() };
+        assert_eq!(video_filter_chain(&filters).as_deref(), Some("hflip,vflip"));
+    }
+
+    #[test]
+    fn comparison_timing_seeks_or_pads_to_the_range_start() {
+        let at = |secs: i64| DateTime::from_timestamp(1_700_000_000 + secs, 0).unwrap();
+        // Started before the range: skip ahead
+        assert_eq!(comparison_timing(at(0), at(90)), (90.0, 0.0));
+        // Started inside the range: black until it begins
+        assert_eq!(comparison_timing(at(12), at(10)), (0.0, 2.0));
+        assert_eq!(comparison_timing(at(10), at(10)), (0.0, 0.0));
+    }
+
+    #[test]
+    fn export_commands_per_platform() {
+        let watermark = Watermark {
+            text: None,
+            image_path: Some("logo.png".to_string()),
+            position: "top_right".to_string(),
+            opacity: 0.5,
+            font_size: None,
+        };
+        let overlay = |input, image_input| crate::watermark::filter(&watermark, input, image_input, "out");
+        for platform in [Platform::Linux, Platform::Windows, Platform::MacOs] {
+            let builder = || CommandBuilder::for_platform(platform);
+
+            assert_eq!(concat_command(builder(), Path::new("list.txt"), Path::new("session.mp4")).build(), [
+                "-y", "-f", "concat", "-safe", "0", "-i", "list.txt", "-map", "0", "-c", "copy",
+                "-movflags", "+faststart", "session.mp4",
+            ]);
+            assert_eq!(concat_command(builder(), Path::new("list.txt"), Path::new("session.mkv")).build(), [
+                "-y", "-f", "concat", "-safe", "0", "-i", "list.txt", "-map", "0", "-c", "copy", "session.mkv",
+            ]);
+
+            let watermarked = watermark_command(builder(), Path::new("rec_1.mkv"), Path::new("export.mkv"), &watermark);
+            assert_eq!(watermarked.build(), [
+                "-y", "-i", "rec_1.mkv", "-loop", "1", "-i", "logo.png", "-filter_complex", &overlay("0:v", 1),
+                "-map", "[out]", "-map", "0:a?", "-c:a", "copy",
+                "-c:v", "libx264", "-preset", "veryfast", "-crf", "20", "-pix_fmt", "yuv420p", "export.mkv",
+            ]);
+
+            let inputs = [(PathBuf::from("a.mp4"), 90.0, 0.0), (PathBuf::from("b.mp4"), 0.0, 2.5)];
+            let panes = "[0:v]tpad=start_duration=0.000:color=black,scale=-2:720,setsar=1[v0];\
+                         [1:v]tpad=start_duration=2.500:color=black,scale=-2:720,setsar=1[v1];[v0][v1]hstack=inputs=2";
+            let tail = [
+                "-map", "[out]", "-an", "-t", "60.000",
+                "-c:v", "libx264", "-preset", "veryfast", "-crf", "23", "-pix_fmt", "yuv420p",
+                "-movflags", "+faststart", "both.mp4",
+            ];
+            let plain = side_by_side_command(builder(), &inputs, 60.0, None, Path::new("both.mp4")).build();
+            assert_eq!(plain[..10], ["-y", "-ss", "90.000", "-i", "a.mp4", "-ss", "0.000", "-i", "b.mp4", "-filter_complex"]);
+            assert_eq!(plain[10], format!("{}[out]", panes));
+            assert_eq!(plain[11..], tail);
+
+            let marked = side_by_side_command(builder(), &inputs, 60.0, Some(&watermark), Path::new("both.mp4")).build();
+            assert_eq!(marked[9..14], ["-loop", "1", "-i", "logo.png", "-filter_complex"]);
+            assert_eq!(marked[14], format!("{}[stacked];{}", panes, overlay("stacked", 2)));
+            assert_eq!(marked[15..], tail);
+        }
+    }
+
+    #[test]
+    fn stream_probe_reads_the_first_video_and_audio_streams() {
+        let json = serde_json::json!({"streams": [
+            {"codec_type": "video", "codec_name": "h264", "width": 1920, "height": 1080, "avg_frame_rate": "30000/1001"},
+            {"codec_type": "audio", "codec_name": "pcm_mulaw", "avg_frame_rate": "0/0"},
+            {"codec_type": "video", "codec_name": "mjpeg", "width": 640, "height": 360, "avg_frame_rate": "5/1"},
+        ]});
+        assert_eq!(parse_stream_probe(&json), StreamProbe {
+            video_codec: Some("h264".to_string()),
+            audio_codec: Some("pcm_mulaw".to_string()),
+            width: Some(1920),
+            height: Some(1080),
+            fps: Some(30),
+        });
+        let json = serde_json::json!({"streams": [{"codec_type": "video", "codec_name": "hevc", "avg_frame_rate": "0/0"}]});
+        assert_eq!(parse_stream_probe(&json).fps, None);
+        assert_eq!(parse_stream_probe(&serde_json::json!({})), StreamProbe::default());
+    }
+
+    #[test]
+    fn input_args_match_the_source_protocol() {
+        let tuning = InputTuning {
+            rw_timeout_ms: Some(15_000),
+            reconnect: true,
+            reconnect_delay_max_secs: Some(30),
+            probesize: Some(500_000),
+            user_agent: Some("Mozilla/5.0".to_string()),
+            ..Default::default()
+        };
+        let camera = camera(tuning);
+
+        let rtsp = network_input_args(&camera, "rtsp://10.0.0.5/live");
+        assert_eq!(rtsp[..2], ["-rtsp_transport", "udp"]);
+        assert!(!rtsp.contains(&"-reconnect".to_string()));
+        assert!(rtsp.windows(2).any(|pair| pair == ["-rw_timeout", "15000000"]));
+        assert!(rtsp.windows(2).any(|pair| pair == ["-user_agent", "Mozilla/5.0"]));
+
+        let http = network_input_args(&camera, "http://10.0.0.5/video.mjpg");
+        assert!(!http.contains(&"-rtsp_transport".to_string()));
+        assert!(http.windows(2).any(|pair| pair == ["-reconnect_delay_max", "30"]));
+        assert!(http.windows(2).any(|pair| pair == ["-probesize", "500000"]));
+    }
+
+    #[test]
+    fn untuned_camera_only_selects_the_transport() {
+        assert_eq!(network_input_args(&camera(InputTuning::default()), "rtsp://10.0.0.5/live"), ["-rtsp_transport", "udp"]);
+    }
+
+    #[test]
+    fn custom_args_are_checked_and_appended_to_the_input() {
+        let args = |list: &[&str]| list.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+        assert!(check_extra_ffmpeg_args(&args(&["-fflags", "+igndts", "-err_detect", "ignore_err"])).is_ok());
+        assert!(check_extra_ffmpeg_args(&args(&["igndts"])).is_err());
+        assert!(check_extra_ffmpeg_args(&args(&["-i", "/etc/passwd"])).is_err());
+        assert!(check_extra_ffmpeg_args(&args(&["-progress:v", "file.txt"])).is_err());
+        assert!(check_extra_ffmpeg_args(&args(&["-metadata", "title=a\nb"])).is_err());
+
+        let mut camera = camera(InputTuning::default());
+        camera.extra_input_args = args(&["-rtsp_transport", "tcp"]);
+        assert_eq!(network_input_args(&camera, "rtsp://10.0.0.5/live"), ["-rtsp_transport", "udp", "-rtsp_transport", "tcp"]);
+    }
+
+    #[test]
+    fn parses_ffmpeg_major_version() {
+        assert_eq!(parse_ffmpeg_major_version("ffmpeg version 6.1.1-3ubuntu5 Copyright (c) 2000-2023"), Some(6));
+        assert_eq!(parse_ffmpeg_major_version("ffmpeg version n4.4.2 Copyright"), Some(4));
+        assert_eq!(parse_ffmpeg_major_version("ffmpeg version N-113684-gb9e8a3d Copyright"), None);
+    }
+}
@@ -0,0 +1,244 @@
+use crate::models::PipelineCleanup;
+use crate::AppState;
+use rusqlite::Connection;
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::Path;
+use std::process::Child;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+use tauri::{AppHandle, Manager};
+
+const GC_INTERVAL_SECS: u64 = 60;
+// A directory without a process is only removed once it has been left alone this long,
+// which covers a pipeline between stop and restart
+const ORPHAN_GRACE_SECS: u64 = 60;
+// Segments of a live stream that the playlist no longer lists and that are older than this
+// were left behind by a killed FFmpeg
+const STALE_SEGMENT_SECS: u64 = 120;
+
+/// Periodically remove stream and playback directories that no FFmpeg process owns any more,
+/// and segments a live stream's playlist has dropped
+pub fn spawn_stream_gc(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(Duration::from_secs(GC_INTERVAL_SECS)).await;
+
+            let app_handle = app_handle.clone();
+            let result = tauri::async_runtime::spawn_blocking(move || {
+                collect_garbage(&app_handle.state::<AppState>())
+            }).await;
+            match result {
+                Ok(Ok(removed)) if removed > 0 => println!("[StreamGC] Removed {} stale stream item(s)", removed),
+                Ok(Ok(_)) => {}
+                Ok(Err(e)) => eprintln!("[StreamGC] Cleanup failed: {}", e),
+                Err(e) => eprintln!("[StreamGC] Cleanup task failed: {}", e),
+            }
+        }
+    });
+}
+
+/// Remove the live stream directory of a camera, unless a new FFmpeg has already taken it
+/// over (a restart racing with the stop)
+pub fn remove_stream_dir(state: &AppState, camera_id: i32) -> Result<(), String> {
+    let processes = state.processes.lock().map_err(|e| e.to_string())?;
+    if processes.contains_key(&camera_id) {
+        return Ok(());
+    }
+
+    let stream_dir = state.stream_dir.join(camera_id.to_string());
+    if stream_dir.exists() {
+        fs::remove_dir_all(&stream_dir).map_err(|e| e.to_string())?;
+    }
+    remove_segment_dir(state, camera_id)
+}
+
+/// Escape hatch for a wedged pipeline: kill the camera's stream and recording FFmpeg, remove its
+/// stream directory and drop its unfinished recording rows, without the checks and finalizing
+/// the stop commands do. With no camera, every camera that has anything of that left is cleaned.
+pub fn force_cleanup(state: &AppState, camera_id: Option<i32>) -> Result<Vec<PipelineCleanup>, String> {
+    let conn = crate::db::open(&state.db_path).map_err(|e| e.to_string())?;
+    let camera_ids = match camera_id {
+        Some(camera_id) => BTreeSet::from([camera_id]),
+        None => {
+            let mut ids = BTreeSet::new();
+            ids.extend(state.processes.lock().map_err(|e| e.to_string())?.keys().copied());
+            ids.extend(state.recording_processes.lock().map_err(|e| e.to_string())?.keys().copied());
+            ids.extend(
+                fs::read_dir(&state.stream_dir).into_iter().flatten().flatten()
+                    .filter_map(|entry| entry.file_name().to_string_lossy().parse::<i32>().ok()),
+            );
+            let mut stmt = conn.prepare("SELECT DISTINCT camera_id FROM recordings WHERE is_finished = 0")
+                .map_err(|e| e.to_string())?;
+            let unfinished = stmt.query_map([], |row| row.get::<_, i32>(0))
+                .map_err(|e| e.to_string())?
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| e.to_string())?;
+            ids.extend(unfinished);
+            ids
+        }
+    };
+
+    Ok(camera_ids.into_iter().map(|camera_id| cleanup_camera(state, &conn, camera_id)).collect())
+}
+
+// Carries on past failures so one stuck part doesn't keep the rest in place
+fn cleanup_camera(state: &AppState, conn: &Connection, camera_id: i32) -> PipelineCleanup {
+    let mut cleanup = PipelineCleanup {
+        cameraId: camera_id,
+        streamKilled: false,
+        recordingKilled: false,
+        streamDirRemoved: false,
+        recordingsRemoved: Vec::new(),
+        errors: Vec::new(),
+    };
+
+    state.pipelines.stream_stopped(camera_id);
+    state.stream_tokens.revoke_camera(camera_id);
+    match kill_pipeline_process(state, &state.processes, camera_id) {
+        Ok(killed) => cleanup.streamKilled = killed,
+        Err(e) => cleanup.errors.push(format!("Stream FFmpeg: {}", e)),
+    }
+    match kill_pipeline_process(state, &state.recording_processes, camera_id) {
+        Ok(killed) => cleanup.recordingKilled = killed,
+        Err(e) => cleanup.errors.push(format!("Recording FFmpeg: {}", e)),
+    }
+
+    let stream_dir = state.stream_dir.join(camera_id.to_string());
+    let had_stream_dir = stream_dir.exists();
+    match remove_stream_dir(state, camera_id) {
+        Ok(()) => cleanup.streamDirRemoved = had_stream_dir && !stream_dir.exists(),
+        Err(e) => cleanup.errors.push(format!("Stream directory: {}", e)),
+    }
+
+    match remove_unfinished_recordings(conn, camera_id) {
+        Ok(ids) => cleanup.recordingsRemoved = ids,
+        Err(e) => cleanup.errors.push(format!("Unfinished recordings: {}", e)),
+    }
+
+    println!(
+        "[Cleanup] Camera {}: stream killed {}, recording killed {}, stream directory removed {}, {} unfinished recording(s) dropped{}",
+        camera_id, cleanup.streamKilled, cleanup.recordingKilled, cleanup.streamDirRemoved,
+        cleanup.recordingsRemoved.len(),
+        if cleanup.errors.is_empty() { String::new() } else { format!(", errors: {}", cleanup.errors.join("; ")) },
+    );
+    cleanup
+}
+
+// Kill and reap the camera's process in `processes`; false if it had none
+fn kill_pipeline_process(state: &AppState, processes: &Mutex<HashMap<i32, Child>>, camera_id: i32) -> Result<bool, String> {
+    let child = processes.lock().map_err(|e| e.to_string())?.remove(&camera_id);
+    let Some(mut child) = child else { return Ok(false) };
+
+    // Fails when the process has already exited, which wait() then reaps
+    if let Err(e) = state.pipelines.kill_process(&mut child) {
+        eprintln!("[Cleanup] Warning: Failed to kill FFmpeg {}: {}", child.id(), e);
+    }
+    child.wait().map_err(|e| e.to_string())?;
+    Ok(true)
+}
+
+// Rows of recordings that never finished, with an open pause and emptied sessions; the
+// files stay on disk
+fn remove_unfinished_recordings(conn: &Connection, camera_id: i32) -> Result<Vec<i32>, String> {
+    let mut stmt = conn.prepare("SELECT id FROM recordings WHERE camera_id = ?1 AND is_finished = 0")
+        .map_err(|e| e.to_string())?;
+    let ids = stmt.query_map([camera_id], |row| row.get::<_, i32>(0))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    conn.execute("DELETE FROM recordings WHERE camera_id = ?1 AND is_finished = 0", [camera_id])
+        .map_err(|e| e.to_string())?;
+    conn.execute("DELETE FROM recording_gaps WHERE camera_id = ?1 AND resumed_at IS NULL", [camera_id])
+        .map_err(|e| e.to_string())?;
+    crate::stream::prune_recording_sessions(conn, camera_id)?;
+    Ok(ids)
+}
+
+// Segments kept outside the stream directory (configurable HLS segment location)
+fn remove_segment_dir(state: &AppState, camera_id: i32) -> Result<(), String> {
+    if let Some(segment_dir) = state.pipelines.segment_dir(camera_id).filter(|dir| dir.exists()) {
+        fs::remove_dir_all(&segment_dir).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+fn collect_garbage(state: &AppState) -> Result<usize, String> {
+    let mut removed = 0;
+
+    for entry in fs::read_dir(&state.stream_dir).map_err(|e| e.to_string())?.flatten() {
+        let path = entry.path();
+        if !path.is_dir() {
+            continue;
+        }
+        let name = entry.file_name().to_string_lossy().to_string();
+
+        if name == "playback" {
+            removed += collect_playback_sessions(state, &path)?;
+            continue;
+        }
+        let Ok(camera_id) = name.parse::<i32>() else { continue };
+
+        // Held while deleting: start_stream resets the directory and registers its FFmpeg
+        // under the same lock
+        let processes = state.processes.lock().map_err(|e| e.to_string())?;
+        if processes.contains_key(&camera_id) {
+            drop(processes);
+            let segment_dir = state.pipelines.segment_dir(camera_id).unwrap_or_else(|| path.clone());
+            removed += remove_stale_segments(&path, &segment_dir);
+        } else if idle_for(&path) >= Duration::from_secs(ORPHAN_GRACE_SECS) {
+            println!("[StreamGC] Removing orphaned stream directory of camera {}", camera_id);
+            fs::remove_dir_all(&path).map_err(|e| e.to_string())?;
+            remove_segment_dir(state, camera_id)?;
+            removed += 1;
+        }
+    }
+
+    Ok(removed)
+}
+
+// Transcode sessions the player never stopped (e.g. the window was closed)
+fn collect_playback_sessions(state: &AppState, playback_dir: &Path) -> Result<usize, String> {
+    let mut removed = 0;
+
+    for entry in fs::read_dir(playback_dir).map_err(|e| e.to_string())?.flatten() {
+        let session_id = entry.file_name().to_string_lossy().to_string();
+        let sessions = state.playback_processes.lock().map_err(|e| e.to_string())?;
+        if !sessions.contains_key(&session_id) && idle_for(&entry.path()) >= Duration::from_secs(ORPHAN_GRACE_SECS) {
+            println!("[StreamGC] Removing orphaned playback session {}", session_id);
+            fs::remove_dir_all(entry.path()).map_err(|e| e.to_string())?;
+            removed += 1;
+        }
+    }
+
+    Ok(removed)
+}
+
+// Segments the playlist no longer references; FFmpeg's delete_segments only cleans up after
+// itself while it runs
+fn remove_stale_segments(stream_dir: &Path, segment_dir: &Path) -> usize {
+    let Ok(playlist) = fs::read_to_string(stream_dir.join("index.m3u8")) else { return 0 };
+    let Ok(entries) = fs::read_dir(segment_dir) else { return 0 };
+
+    let mut removed = 0;
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if !name.ends_with(".ts") || playlist.contains(&name) {
+            continue;
+        }
+        if idle_for(&entry.path()) >= Duration::from_secs(STALE_SEGMENT_SECS) && fs::remove_file(entry.path()).is_ok() {
+            removed += 1;
+        }
+    }
+    removed
+}
+
+// Time since the path was last written; zero when unknown so nothing is deleted by mistake
+fn idle_for(path: &Path) -> Duration {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+        .unwrap_or(Duration::ZERO)
+}
@@ -0,0 +1,300 @@
+use axum::body::Body;
+use axum::extract::{Request, State};
+use axum::http::{header, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use chrono::Utc;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+// A stream URL handed to a player stays valid this long; a leaked one stops working after it
+const PLAYLIST_TOKEN_SECS: i64 = 12 * 60 * 60;
+// Segment URIs are re-signed on every playlist fetch, so they only need to outlive a refresh
+const SEGMENT_TOKEN_SECS: i64 = 120;
+const MAX_PLAYLIST_BYTES: usize = 4 * 1024 * 1024;
+// A player session nothing was fetched through for this long is dropped; players fetch the
+// playlist every few seconds
+const SESSION_IDLE_SECS: u64 = 10 * 60;
+// Open player sessions per camera; the least recently used one makes room for a new player
+const MAX_SESSIONS_PER_CAMERA: usize = 8;
+
+struct Session {
+    camera_id: i32,
+    // The camera's one session for status views, kept apart from the players' so revoking a
+    // player doesn't break the status URL; never ages out
+    status: bool,
+    last_used: Instant,
+}
+
+/// Signed, expiring tokens for the /streams HLS files (stored in AppState). Enabled with LAN
+/// mode, where the server is reachable from other hosts: every playlist and segment request
+/// then needs an `st` token for its stream directory. Playlists are rewritten as they are
+/// served so each segment URI carries a fresh short-lived token; DASH manifests get the same
+/// treatment for their segment templates. The signing key is random
+/// per launch, so tokens never survive a restart.
+///
+/// Live streams are also only reachable through random session ids, "streams/<session>/...",
+/// resolved to the camera's stream directory here; camera ids never appear in stream URLs.
+/// Sessions end with the stream, when revoked one by one, or once idle for a while.
+#[derive(Clone)]
+pub struct StreamTokens {
+    enabled: bool,
+    secret: [u8; 32],
+    sessions: Arc<Mutex<HashMap<String, Session>>>,
+}
+
+impl StreamTokens {
+    pub fn new(enabled: bool) -> Self {
+        Self { enabled, secret: rand::random(), sessions: Arc::default() }
+    }
+
+    /// New session for a player of the camera's live stream
+    pub fn open_session(&self, camera_id: i32) -> String {
+        let session = uuid::Uuid::new_v4().simple().to_string();
+        if let Ok(mut sessions) = self.sessions.lock() {
+            let idle = Duration::from_secs(SESSION_IDLE_SECS);
+            sessions.retain(|_, session| session.status || session.last_used.elapsed() < idle);
+
+            loop {
+                let players = sessions.iter().filter(|(_, session)| session.camera_id == camera_id && !session.status);
+                if players.clone().count() < MAX_SESSIONS_PER_CAMERA {
+                    break;
+                }
+                let Some(oldest) = players.min_by_key(|(_, session)| session.last_used).map(|(id, _)| id.clone()) else { break };
+                sessions.remove(&oldest);
+            }
+
+            sessions.insert(session.clone(), Session { camera_id, status: false, last_used: Instant::now() });
+        }
+        session
+    }
+
+    /// The camera's session for status views that link the stream, opened on first use
+    pub fn status_session(&self, camera_id: i32) -> String {
+        let Ok(mut sessions) = self.sessions.lock() else {
+            return uuid::Uuid::new_v4().simple().to_string();
+        };
+        let existing = sessions.iter()
+            .find(|(_, session)| session.camera_id == camera_id && session.status)
+            .map(|(id, _)| id.clone());
+        existing.unwrap_or_else(|| {
+            let id = uuid::Uuid::new_v4().simple().to_string();
+            sessions.insert(id.clone(), Session { camera_id, status: true, last_used: Instant::now() });
+            id
+        })
+    }
+
+    /// End one session; its URLs stop working right away. False if it wasn't open.
+    pub fn revoke_session(&self, session: &str) -> bool {
+        self.sessions.lock().is_ok_and(|mut sessions| sessions.remove(session).is_some())
+    }
+
+    /// End every session of the camera, e.g. when its stream stops
+    pub fn revoke_camera(&self, camera_id: i32) {
+        if let Ok(mut sessions) = self.sessions.lock() {
+            sessions.retain(|_, session| session.camera_id != camera_id);
+        }
+    }
+
+    fn camera_of(&self, session: &str) -> Option<i32> {
+        let mut sessions = self.sessions.lock().ok()?;
+        let session = sessions.get_mut(session)?;
+        session.last_used = Instant::now();
+        Some(session.camera_id)
+    }
+
+    /// URL of a file of a live stream session, e.g. "index.m3u8"
+    pub fn session_url(&self, port: u16, session: &str, file: &str) -> String {
+        self.url(port, &format!("streams/{}/{}", session, file))
+    }
+
+    /// Full URL of a file under the server root, e.g. "streams/3/index.m3u8", with a
+    /// playlist token when tokens are required
+    pub fn url(&self, port: u16, path: &str) -> String {
+        let url = format!("http://localhost:{}/{}", port, path);
+        let scope = path.strip_prefix("streams/").and_then(|rest| rest.rsplit_once('/')).map(|(dir, _)| dir);
+        match scope.filter(|_| self.enabled) {
+            Some(scope) => format!("{}?st={}", url, self.sign(scope, Utc::now().timestamp() + PLAYLIST_TOKEN_SECS)),
+            None => url,
+        }
+    }
+
+    // "<expiry>.<hex HMAC of scope and expiry>"
+    fn sign(&self, scope: &str, expires: i64) -> String {
+        let mac = crate::s3::hmac_sha256(&self.secret, format!("{}:{}", scope, expires).as_bytes());
+        format!("{}.{}", expires, mac.iter().map(|b| format!("{:02x}", b)).collect::<String>())
+    }
+
+    fn verify(&self, scope: &str, token: &str) -> bool {
+        let Some(expires) = token.split_once('.').and_then(|(expires, _)| expires.parse::<i64>().ok()) else {
+            return false;
+        };
+        let expected = self.sign(scope, expires);
+        // Constant time, so the MAC can't be guessed byte by byte
+        let matches = expected.len() == token.len()
+            && expected.bytes().zip(token.bytes()).fold(0u8, |diff, (a, b)| diff | (a ^ b)) == 0;
+        matches && expires >= Utc::now().timestamp()
+    }
+}
+
+/// Middleware of the /streams routes resolving live stream sessions, checking the token and
+/// signing segment URIs in playlists
+pub async fn guard(State(tokens): State<StreamTokens>, mut request: Request, next: Next) -> Response {
+    let path = request.uri().path().to_string();
+    // "/streams/<session>/index.m3u8" or "/streams/playback/<session>/segment_001.ts"
+    let Some((scope, file)) = path.strip_prefix("/streams/").and_then(|rest| rest.rsplit_once('/')) else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+
+    // Serve a live session from its camera's directory; raw camera ids aren't reachable
+    let (session, rest) = scope.split_once('/').unwrap_or((scope, ""));
+    if session != "playback" {
+        let Some(camera_id) = tokens.camera_of(session) else {
+            return StatusCode::NOT_FOUND.into_response();
+        };
+        let subdir = if rest.is_empty() { String::new() } else { format!("{}/", rest) };
+        let query = request.uri().query().map(|query| format!("?{}", query)).unwrap_or_default();
+        match format!("/streams/{}/{}{}{}", camera_id, subdir, file, query).parse() {
+            Ok(uri) => *request.uri_mut() = uri,
+            Err(_) => return StatusCode::BAD_REQUEST.into_response(),
+        }
+    }
+
+    if !tokens.enabled {
+        return next.run(request).await;
+    }
+
+    let token = request.uri().query()
+        .and_then(|query| url::form_urlencoded::parse(query.as_bytes()).find(|(key, _)| key == "st"))
+        .map(|(_, value)| value.into_owned())
+        .unwrap_or_default();
+    // A camera's token also covers the rendition subdirectories its master playlist points to
+    let mut scopes = std::iter::successors(Some(scope), |scope| scope.rsplit_once('/').map(|(parent, _)| parent));
+    if !scopes.any(|scope| tokens.verify(scope, &token)) {
+        return StatusCode::FORBIDDEN.into_response();
+    }
+
+    let response = next.run(request).await;
+    let is_manifest = file.ends_with(".mpd");
+    if !(file.ends_with(".m3u8") || is_manifest) || !response.status().is_success() {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let Ok(bytes) = axum::body::to_bytes(body, MAX_PLAYLIST_BYTES).await else {
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    };
+    let segment_token = tokens.sign(scope, Utc::now().timestamp() + SEGMENT_TOKEN_SECS);
+    let text = String::from_utf8_lossy(&bytes);
+    let playlist = if is_manifest {
+        sign_manifest(&text, &segment_token)
+    } else {
+        sign_playlist(&text, &token, &segment_token)
+    };
+    parts.headers.remove(header::CONTENT_LENGTH);
+    parts.headers.insert(header::CACHE_CONTROL, header::HeaderValue::from_static("no-store"));
+    Response::from_parts(parts, Body::from(playlist))
+}
+
+// Append a token to every URI of a playlist: nested playlists keep the token the player
+// already has (it refetches them for as long as it plays), everything else gets the
+// short-lived segment token
+fn sign_playlist(playlist: &str, playlist_token: &str, segment_token: &str) -> String {
+    let sign = |uri: &str| {
+        let token = if uri.split('?').next().unwrap_or(uri).ends_with(".m3u8") { playlist_token } else { segment_token };
+        let separator = if uri.contains('?') { '&' } else { '?' };
+        format!("{}{}st={}", uri, separator, token)
+    };
+
+    playlist.lines()
+        .map(|line| {
+            let trimmed = line.trim();
+            if trimmed.is_empty() {
+                line.to_string()
+            } else if !trimmed.starts_with('#') {
+                sign(trimmed)
+            } else if let Some((before, rest)) = line.split_once("URI=\"") {
+                // #EXT-X-MAP, #EXT-X-MEDIA and friends
+                match rest.split_once('"') {
+                    Some((uri, after)) => format!("{}URI=\"{}\"{}", before, sign(uri), after),
+                    None => line.to_string(),
+                }
+            } else {
+                line.to_string()
+            }
+        })
+        .map(|line| line + "\n")
+        .collect()
+}
+
+// Append the segment token to the segment templates of a DASH manifest, which players refetch
+// as it updates
+fn sign_manifest(manifest: &str, segment_token: &str) -> String {
+    ["initialization=\"", "media=\""].iter().fold(manifest.to_string(), |manifest, attribute| {
+        let mut signed = String::with_capacity(manifest.len());
+        let mut rest = manifest.as_str();
+        while let Some(start) = rest.find(attribute) {
+            let value_start = start + attribute.len();
+            let Some(length) = rest[value_start..].find('"') else { break };
+            let uri = &rest[value_start..value_start + length];
+            let separator = if uri.contains('?') { "&amp;" } else { "?" };
+            signed.push_str(&rest[..value_start + length]);
+            signed.push_str(&format!("{}st={}", separator, segment_token));
+            rest = &rest[value_start + length..];
+        }
+        signed.push_str(rest);
+        signed
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn player_sessions_are_capped_per_camera() {
+        let tokens = StreamTokens::new(true);
+        let first = tokens.open_session(1);
+        let second = tokens.open_session(1);
+        let other_camera = tokens.open_session(2);
+        // Fetching through the first session makes the second the least recently used
+        assert_eq!(tokens.camera_of(&first), Some(1));
+        for _ in 0..MAX_SESSIONS_PER_CAMERA - 1 {
+            tokens.open_session(1);
+        }
+
+        assert_eq!(tokens.camera_of(&second), None);
+        assert_eq!(tokens.camera_of(&first), Some(1));
+        assert_eq!(tokens.camera_of(&other_camera), Some(2));
+    }
+
+    #[test]
+    fn idle_player_sessions_age_out() {
+        let tokens = StreamTokens::new(true);
+        let idle = tokens.open_session(1);
+        let status = tokens.status_session(1);
+        // Not representable this soon after boot
+        let Some(long_ago) = Instant::now().checked_sub(Duration::from_secs(SESSION_IDLE_SECS + 1)) else { return };
+        for session in tokens.sessions.lock().unwrap().values_mut() {
+            session.last_used = long_ago;
+        }
+
+        tokens.open_session(1);
+        assert_eq!(tokens.camera_of(&idle), None);
+        assert_eq!(tokens.camera_of(&status), Some(1));
+    }
+
+    #[test]
+    fn status_views_have_their_own_session() {
+        let tokens = StreamTokens::new(true);
+        let player = tokens.open_session(1);
+        let status = tokens.status_session(1);
+        assert_ne!(status, player);
+        assert_eq!(tokens.status_session(1), status);
+
+        assert!(tokens.revoke_session(&player));
+        assert_eq!(tokens.status_session(1), status);
+        assert_eq!(tokens.camera_of(&status), Some(1));
+    }
+}
@@ -0,0 +1,153 @@
+use crate::events::AppEvent;
+use crate::AppState;
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant, SystemTime};
+use tauri::{AppHandle, Manager};
+
+const CHECK_INTERVAL_SECS: u64 = 5;
+
+/// Periodically check that every live stream is still writing segments. FFmpeg can stay alive
+/// while a stalled camera sends nothing, which freezes the player without any error; such a
+/// stream is restarted once its newest segment is older than `hls.stallSegments` segment
+/// durations.
+pub fn spawn_stream_watchdog(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(Duration::from_secs(CHECK_INTERVAL_SECS)).await;
+
+            let state = app_handle.state::<AppState>();
+            for (camera_id, encoder, detail) in failed_gpu_streams(&state) {
+                crate::stream::fall_back_to_cpu(&state, camera_id, &encoder, &detail);
+                if let Err(e) = restart_stream(&app_handle, camera_id).await {
+                    eprintln!("[Watchdog] Restarting the stream of camera {} on the CPU failed: {}", camera_id, e);
+                }
+            }
+
+            let stalled = match stalled_streams(&state) {
+                Ok(stalled) => stalled,
+                Err(e) => {
+                    eprintln!("[Watchdog] Check failed: {}", e);
+                    continue;
+                }
+            };
+            for (camera_id, stale_secs) in stalled {
+                println!("[Watchdog] Stream of camera {} has written no segment for {}s, restarting", camera_id, stale_secs);
+                state.events.emit(AppEvent::StreamDegraded { camera_id, stale_secs });
+                if let Err(e) = restart_stream(&app_handle, camera_id).await {
+                    eprintln!("[Watchdog] Restarting the stream of camera {} failed: {}", camera_id, e);
+                }
+            }
+        }
+    });
+}
+
+// Live streams whose FFmpeg exited because the GPU encoder broke (driver reset, session limit),
+// with the encoder and the stderr line that shows it
+fn failed_gpu_streams(state: &AppState) -> Vec<(i32, String, String)> {
+    let Ok(mut processes) = state.processes.lock() else { return Vec::new() };
+    processes.iter_mut()
+        .filter_map(|(camera_id, child)| {
+            if !matches!(child.try_wait(), Ok(Some(_))) {
+                return None;
+            }
+            let encoder = state.pipelines.live_encoder(*camera_id)?;
+            if !encoder.is_gpu || !encoder.can_fall_back {
+                return None;
+            }
+            let stderr = crate::redact::tail_lines(&encoder.stderr);
+            let line = crate::encoder::encoder_failure(&stderr, &encoder.codec)?.to_string();
+            Some((*camera_id, encoder.codec, line))
+        })
+        .collect()
+}
+
+// Cameras whose stream stopped producing segments, with the age of their newest segment
+fn stalled_streams(state: &AppState) -> Result<Vec<(i32, u64)>, String> {
+    let conn = crate::db::open(&state.db_path).map_err(|e| e.to_string())?;
+    let stall_segments = crate::settings::get_i32(&conn, crate::settings::HLS_STALL_SEGMENTS)?;
+    if stall_segments == 0 {
+        return Ok(Vec::new());
+    }
+
+    let camera_ids: Vec<i32> = state.processes.lock().map_err(|e| e.to_string())?.keys().copied().collect();
+    let mut stalled = Vec::new();
+    for camera_id in camera_ids {
+        // No segment yet: still starting, which start_stream times out on its own
+        let Some(newest) = newest_segment(state, camera_id) else { continue };
+        let age = SystemTime::now().duration_since(newest).unwrap_or(Duration::ZERO);
+
+        let segment_secs = crate::hls::params_for(&conn, camera_id)?.segment_secs as u64;
+        let limit = Duration::from_secs(segment_secs * stall_segments as u64);
+        if age > limit {
+            stalled.push((camera_id, age.as_secs()));
+        }
+    }
+    Ok(stalled)
+}
+
+// Modification time of the live stream's newest segment
+fn newest_segment(state: &AppState, camera_id: i32) -> Option<SystemTime> {
+    let segment_dir = state.pipelines.segment_dir(camera_id)
+        .unwrap_or_else(|| state.stream_dir.join(camera_id.to_string()));
+    newest_segment_in(&segment_dir)
+}
+
+fn newest_segment_in(dir: &Path) -> Option<SystemTime> {
+    fs::read_dir(dir).ok()?
+        .flatten()
+        .filter(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            name.ends_with(".ts") || name.ends_with(".m4s")
+        })
+        .filter_map(|entry| entry.metadata().and_then(|metadata| metadata.modified()).ok())
+        .max()
+}
+
+/// Replace the live FFmpeg of a camera; a recording of the camera runs separately and is left
+/// alone. Returns false if the stream had been stopped in the meantime.
+pub async fn restart_stream(app_handle: &AppHandle, camera_id: i32) -> Result<bool, String> {
+    let state = app_handle.state::<AppState>();
+    {
+        let mut processes = state.processes.lock().map_err(|e| e.to_string())?;
+        let Some(mut child) = processes.remove(&camera_id) else { return Ok(false) };
+        let _ = state.pipelines.kill_process(&mut child);
+        let _ = child.wait();
+    }
+
+    let camera = crate::stream::load_camera(&state.db_path, camera_id)?;
+    if let Err(e) = crate::stream::start_stream(app_handle.state::<AppState>(), camera).await {
+        state.pipelines.error(camera_id, &e.message);
+        state.pipelines.camera_offline(&state.events, camera_id, &e.message);
+        state.events.emit(AppEvent::StreamDied { camera_id, reason: e.message.clone() });
+        return Err(e.message);
+    }
+    Ok(true)
+}
+
+/// After the camera's clock was changed at `since`, wait for its live stream to write a new
+/// segment. Cameras that keep the RTSP session going across the change need nothing; a
+/// stream that stalls is restarted. Returns whether it was restarted.
+pub async fn recover_after_clock_change(app_handle: &AppHandle, camera_id: i32, since: SystemTime) -> Result<bool, String> {
+    let segment_secs = {
+        let state = app_handle.state::<AppState>();
+        let conn = crate::db::open(&state.db_path).map_err(|e| e.to_string())?;
+        crate::hls::params_for(&conn, camera_id)?.segment_secs as u64
+    };
+    // A couple of segments' worth, since the first one may have been cut before the change
+    let deadline = Instant::now() + Duration::from_secs(segment_secs * 3);
+
+    while Instant::now() < deadline {
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        let state = app_handle.state::<AppState>();
+        if !state.processes.lock().map_err(|e| e.to_string())?.contains_key(&camera_id) {
+            return Ok(false);
+        }
+        if newest_segment(&state, camera_id).is_some_and(|newest| newest > since) {
+            return Ok(false);
+        }
+    }
+
+    println!("[Watchdog] Stream of camera {} stalled after its clock changed, restarting", camera_id);
+    restart_stream(app_handle, camera_id).await
+}
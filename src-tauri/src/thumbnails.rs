@@ -0,0 +1,125 @@
+use crate::cloud_upload::UploadQueue;
+use crate::events::{AppEvent, EventBus};
+use crate::ffmpeg::{CommandBuilder, InputSource};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+// A recording without a thumbnail after this many tries is left without one
+const MAX_ATTEMPTS: u32 = 3;
+const RETRY_DELAY_SECS: u64 = 15;
+
+struct ThumbnailJob {
+    recording_id: i32,
+    camera_id: i32,
+    attempt: u32,
+}
+
+/// Thumbnail generation for finished recordings (stored in AppState), so stopping a recording
+/// doesn't wait for FFmpeg. Jobs run one at a time on a background task; a finished thumbnail
+/// is announced with `recording-updated`, and the recording is handed to the upload queue
+/// afterwards so uploads can include it.
+#[derive(Clone)]
+pub struct ThumbnailQueue {
+    sender: mpsc::UnboundedSender<ThumbnailJob>,
+}
+
+impl ThumbnailQueue {
+    /// Create the queue and start its worker task
+    pub fn start(db_path: String, recording_dir: PathBuf, events: EventBus, upload_queue: UploadQueue) -> Self {
+        let (sender, mut receiver) = mpsc::unbounded_channel::<ThumbnailJob>();
+        let retry_sender = sender.clone();
+
+        tauri::async_runtime::spawn(async move {
+            while let Some(job) = receiver.recv().await {
+                let (db_path, recording_dir) = (db_path.clone(), recording_dir.clone());
+                let recording_id = job.recording_id;
+                let result = tauri::async_runtime::spawn_blocking(move || {
+                    create_thumbnail(&db_path, &recording_dir, recording_id)?;
+                    // Motion events inside the recording get their previews along with it
+                    if let Err(e) = crate::previews::create_event_previews(&db_path, &recording_dir, recording_id) {
+                        eprintln!("[Preview] Event previews of recording {} failed: {}", recording_id, e);
+                    }
+                    Ok(())
+                }).await.unwrap_or_else(|e| Err(e.to_string()));
+
+                match result {
+                    Ok(()) => {
+                        events.emit(AppEvent::RecordingUpdated { camera_id: job.camera_id, recording_id });
+                    }
+                    Err(e) if job.attempt < MAX_ATTEMPTS => {
+                        eprintln!("[Thumbnail] Attempt {} for recording {} failed, retrying: {}", job.attempt, recording_id, e);
+                        let sender = retry_sender.clone();
+                        tauri::async_runtime::spawn(async move {
+                            tokio::time::sleep(Duration::from_secs(RETRY_DELAY_SECS)).await;
+                            let _ = sender.send(ThumbnailJob { attempt: job.attempt + 1, ..job });
+                        });
+                        continue;
+                    }
+                    Err(e) => eprintln!("[Thumbnail] Giving up on recording {}: {}", recording_id, e),
+                }
+
+                upload_queue.enqueue_finished(recording_id, job.camera_id);
+            }
+        });
+
+        Self { sender }
+    }
+
+    /// Queue a just-finalized recording
+    pub fn enqueue(&self, recording_id: i32, camera_id: i32) {
+        if let Err(e) = self.sender.send(ThumbnailJob { recording_id, camera_id, attempt: 1 }) {
+            eprintln!("[Thumbnail] Failed to queue recording {}: {}", recording_id, e);
+        }
+    }
+}
+
+/// Generate the thumbnail of a recording and store its name
+pub fn create_thumbnail(db_path: &str, recording_dir: &Path, recording_id: i32) -> Result<(), String> {
+    let conn = crate::db::open(db_path).map_err(|e| e.to_string())?;
+    let filename: String = conn.query_row(
+        "SELECT filename FROM recordings WHERE id = ?1",
+        [recording_id],
+        |row| row.get(0),
+    ).map_err(|e| e.to_string())?;
+
+    let thumbnail_filename = Path::new(&filename).with_extension("jpg").to_string_lossy().to_string();
+    let thumbnail_path = recording_dir.join("thumbnails").join(&thumbnail_filename);
+    if let Some(parent) = thumbnail_path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| format!("Failed to create thumbnails directory: {}", e))?;
+    }
+
+    generate_thumbnail(&crate::storage::recording_path(&conn, recording_dir, recording_id)?, &thumbnail_path)?;
+
+    // The recording may have been deleted meanwhile
+    conn.execute(
+        "UPDATE recordings SET thumbnail = ?1 WHERE id = ?2",
+        rusqlite::params![thumbnail_filename, recording_id],
+    ).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// Generate thumbnail from video file using FFmpeg
+fn generate_thumbnail(video_path: &Path, thumbnail_path: &Path) -> Result<(), String> {
+    println!("[Thumbnail] Generating thumbnail from {:?} to {:?}", video_path, thumbnail_path);
+
+    // FFmpeg command: extract frame at 2 seconds, scale to 320px width, high quality
+    let output = CommandBuilder::new()
+        .seek(2.0)
+        .input(InputSource::File(video_path))
+        .args(["-vframes", "1"])
+        .video_filter("scale=320:-1")
+        .args(["-q:v", "2"])
+        .output(thumbnail_path)
+        .command()
+        .output()
+        .map_err(|e| format!("Failed to spawn FFmpeg for thumbnail: {}", e))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("FFmpeg thumbnail generation failed: {}", stderr));
+    }
+
+    println!("[Thumbnail] Successfully generated thumbnail");
+    Ok(())
+}
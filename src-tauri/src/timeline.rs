@@ -0,0 +1,234 @@
+use crate::models::{CameraEvent, RecordingTimeline, TimelineMarker, TimelineRange};
+use chrono::{DateTime, Duration, Utc};
+use rusqlite::Connection;
+use std::path::Path;
+
+// Recordings started by an event begin a moment after it; they still count as covering it
+const EVENT_RECORDING_LEAD_SECS: i64 = 10;
+
+// Recordings separated by less than this are shown as one continuous block
+// (covers the short gap while FFmpeg restarts between recordings)
+const MERGE_GAP_SECS: i64 = 2;
+
+fn parse_time(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value).ok().map(|t| t.with_timezone(&Utc))
+}
+
+/// Record a camera event (shown as a timeline marker) starting now; returns its id
+pub fn insert_event(conn: &Connection, camera_id: i32, event_type: &str, details: serde_json::Value) -> Result<i64, String> {
+    conn.execute(
+        "INSERT INTO events (camera_id, event_type, start_time, details) VALUES (?1, ?2, ?3, ?4)",
+        rusqlite::params![camera_id, event_type, Utc::now().to_rfc3339(), details.to_string()],
+    ).map_err(|e| e.to_string())?;
+    Ok(conn.last_insert_rowid())
+}
+
+pub fn end_event(conn: &Connection, event_id: i64) -> Result<(), String> {
+    conn.execute(
+        "UPDATE events SET end_time = ?1 WHERE id = ?2",
+        rusqlite::params![Utc::now().to_rfc3339(), event_id],
+    ).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Events newest first, each linked to the recording covering its start
+pub fn list_events(
+    conn: &Connection,
+    camera_id: Option<i32>,
+    event_type: Option<&str>,
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+    limit: u32,
+) -> Result<Vec<CameraEvent>, String> {
+    let mut stmt = conn.prepare(
+        "SELECT e.id, e.camera_id, c.name, e.event_type, e.start_time, e.end_time, e.details, e.preview
+         FROM events e
+         LEFT JOIN cameras c ON e.camera_id = c.id
+         WHERE (?1 IS NULL OR e.camera_id = ?1) AND (?2 IS NULL OR e.event_type = ?2)
+           AND (?3 IS NULL OR e.start_time >= ?3) AND (?4 IS NULL OR e.start_time < ?4)
+         ORDER BY e.start_time DESC
+         LIMIT ?5"
+    ).map_err(|e| e.to_string())?;
+
+    let rows = stmt.query_map(
+        rusqlite::params![camera_id, event_type, from.map(|t| t.to_rfc3339()), to.map(|t| t.to_rfc3339()), limit],
+        |row| Ok((
+            row.get::<_, i64>(0)?,
+            row.get::<_, i32>(1)?,
+            row.get::<_, Option<String>>(2)?,
+            row.get::<_, String>(3)?,
+            row.get::<_, String>(4)?,
+            row.get::<_, Option<String>>(5)?,
+            row.get::<_, Option<String>>(6)?,
+            row.get::<_, Option<String>>(7)?,
+        )),
+    ).map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    // Latest recording of the camera that started before (or just after) the event and
+    // hadn't ended yet; uses the (camera_id, start_time) index
+    let mut recording_stmt = conn.prepare(
+        "SELECT id, start_time, session_id FROM recordings
+         WHERE camera_id = ?1 AND start_time <= ?2 AND (end_time IS NULL OR end_time >= ?3)
+         ORDER BY start_time DESC
+         LIMIT 1"
+    ).map_err(|e| e.to_string())?;
+
+    let mut events = Vec::with_capacity(rows.len());
+    for (id, camera_id, camera_name, event_type, start, end, details, preview) in rows {
+        let Some(start_time) = parse_time(&start) else { continue };
+        let latest_start = start_time + Duration::seconds(EVENT_RECORDING_LEAD_SECS);
+
+        let recording = recording_stmt.query_row(
+            rusqlite::params![camera_id, latest_start.to_rfc3339(), start_time.to_rfc3339()],
+            |row| Ok((row.get::<_, i32>(0)?, row.get::<_, String>(1)?, row.get::<_, Option<i64>>(2)?)),
+        ).ok();
+
+        let offset = recording.as_ref()
+            .and_then(|(_, recording_start, _)| parse_time(recording_start))
+            .map(|recording_start| ((start_time - recording_start).num_milliseconds().max(0)) as f64 / 1000.0);
+
+        events.push(CameraEvent {
+            id,
+            camera_id,
+            camera_name,
+            event_type,
+            start_time,
+            end_time: end.as_deref().and_then(parse_time),
+            details: details.and_then(|d| serde_json::from_str(&d).ok()),
+            recording_id: recording.as_ref().map(|(id, _, _)| *id),
+            recording_session_id: recording.and_then(|(_, _, session_id)| session_id),
+            recording_offset_secs: offset,
+            preview,
+        });
+    }
+
+    Ok(events)
+}
+
+/// Build the covered/uncovered ranges and event markers for one camera between `from` and `to`.
+/// Both queries use the (camera_id, start_time) indexes.
+pub fn build_timeline(
+    conn: &Connection,
+    recording_dir: &Path,
+    camera_id: i32,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> Result<RecordingTimeline, String> {
+    if to <= from {
+        return Err("Timeline end must be after its start".to_string());
+    }
+
+    let now = Utc::now();
+    let from_str = from.to_rfc3339();
+    let to_str = to.to_rfc3339();
+
+    // Recordings overlapping the window; unfinished ones, whether still running or cut off by a
+    // crash, run until their file was last written
+    let mut stmt = conn.prepare(
+        "SELECT id, start_time, end_time, filename, location_id FROM recordings
+         WHERE camera_id = ?1 AND start_time < ?2 AND (end_time IS NULL OR end_time > ?3)
+         ORDER BY start_time"
+    ).map_err(|e| e.to_string())?;
+
+    let rows = stmt.query_map(rusqlite::params![camera_id, to_str, from_str], |row| {
+        Ok((
+            row.get::<_, i32>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, Option<String>>(2)?,
+            row.get::<_, String>(3)?,
+            row.get::<_, Option<i64>>(4)?,
+        ))
+    }).map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let mut spans: Vec<(DateTime<Utc>, DateTime<Utc>, i32)> = Vec::new();
+    for (id, start, end, filename, location_id) in rows {
+        let Some(start) = parse_time(&start) else { continue };
+        let end = match end.as_deref().and_then(parse_time) {
+            Some(end) => end,
+            None => {
+                // Without a file there is nothing to play back
+                let Some(written) = last_written(conn, recording_dir, location_id, &filename) else { continue };
+                written.min(now)
+            }
+        };
+
+        let start = start.max(from);
+        let end = end.min(to);
+        if end > start {
+            spans.push((start, end, id));
+        }
+    }
+
+    let ranges = merge_ranges(spans, from, to);
+
+    let mut stmt = conn.prepare(
+        "SELECT id, event_type, start_time, end_time FROM events
+         WHERE camera_id = ?1 AND start_time >= ?2 AND start_time < ?3
+         ORDER BY start_time"
+    ).map_err(|e| e.to_string())?;
+
+    let markers = stmt.query_map(rusqlite::params![camera_id, from_str, to_str], |row| {
+        Ok((
+            row.get::<_, i64>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, String>(2)?,
+            row.get::<_, Option<String>>(3)?,
+        ))
+    }).map_err(|e| e.to_string())?
+        .filter_map(|row| row.ok())
+        .filter_map(|(id, event_type, start, end)| {
+            Some(TimelineMarker {
+                id,
+                event_type,
+                time: parse_time(&start)?,
+                end_time: end.as_deref().and_then(parse_time),
+            })
+        })
+        .collect();
+
+    Ok(RecordingTimeline { camera_id, from, to, ranges, markers })
+}
+
+// When an unfinished recording's file was last written to
+fn last_written(conn: &Connection, recording_dir: &Path, location_id: Option<i64>, filename: &str) -> Option<DateTime<Utc>> {
+    let path = crate::storage::resolve(conn, recording_dir, location_id, filename).ok()?;
+    std::fs::metadata(path).and_then(|m| m.modified()).ok().map(DateTime::<Utc>::from)
+}
+
+// Merge sorted recording spans and fill the holes between them with uncovered ranges
+fn merge_ranges(
+    spans: Vec<(DateTime<Utc>, DateTime<Utc>, i32)>,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> Vec<TimelineRange> {
+    let mut covered: Vec<TimelineRange> = Vec::new();
+
+    for (start, end, id) in spans {
+        match covered.last_mut() {
+            Some(last) if start <= last.end + Duration::seconds(MERGE_GAP_SECS) => {
+                last.end = last.end.max(end);
+                last.recording_ids.push(id);
+            }
+            _ => covered.push(TimelineRange { start, end, covered: true, recording_ids: vec![id] }),
+        }
+    }
+
+    let mut ranges = Vec::new();
+    let mut cursor = from;
+    for range in covered {
+        if range.start > cursor {
+            ranges.push(TimelineRange { start: cursor, end: range.start, covered: false, recording_ids: Vec::new() });
+        }
+        cursor = range.end;
+        ranges.push(range);
+    }
+    if cursor < to {
+        ranges.push(TimelineRange { start: cursor, end: to, covered: false, recording_ids: Vec::new() });
+    }
+
+    ranges
+}
@@ -0,0 +1,203 @@
+use crate::events::AppEvent;
+use crate::models::{RecordingMeta, RecordingTrigger, TriggerRequest, TriggerResult, TriggerSettings};
+use crate::AppState;
+use axum::extract::{Path, Query, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use rusqlite::Connection;
+use std::collections::HashMap;
+use tauri::{AppHandle, Manager};
+
+// Longest reason kept in the event details
+const MAX_REASON_LEN: usize = 200;
+
+pub fn load_settings(conn: &Connection) -> Result<TriggerSettings, String> {
+    use crate::settings::{self, TRIGGERS_ALLOW_REMOTE, TRIGGERS_DEFAULT_RECORD_MINUTES, TRIGGERS_REMOTE_PORT, TRIGGERS_TOKEN};
+
+    Ok(TriggerSettings {
+        id: 1,
+        token: settings::get_opt_string(conn, TRIGGERS_TOKEN)?.ok_or("No trigger token configured")?,
+        allowRemote: settings::get_bool(conn, TRIGGERS_ALLOW_REMOTE)?,
+        remotePort: settings::get_i32(conn, TRIGGERS_REMOTE_PORT)? as u16,
+        defaultRecordMinutes: settings::get_i32(conn, TRIGGERS_DEFAULT_RECORD_MINUTES)?,
+    })
+}
+
+/// Handle an external trigger: store it as a "trigger" event, then start a recording
+/// (stopped after the requested or default duration) or capture a snapshot
+pub async fn fire(state: &AppState, camera_id: i32, request: TriggerRequest, source: &str) -> Result<TriggerResult, String> {
+    let action = request.action.as_deref().unwrap_or("record").to_string();
+    if action != "record" && action != "snapshot" {
+        return Err(format!("Unknown trigger action '{}' (expected \"record\" or \"snapshot\")", action));
+    }
+    if request.durationMinutes.is_some_and(|minutes| minutes <= 0) {
+        return Err("Duration must be at least one minute".to_string());
+    }
+
+    let reason: String = request.reason.as_deref().unwrap_or("").trim().chars().take(MAX_REASON_LEN).collect();
+    let camera = crate::stream::load_camera(&state.db_path, camera_id)?;
+
+    let (event_id, settings) = {
+        let conn = crate::db::open(&state.db_path).map_err(|e| e.to_string())?;
+        let event_id = crate::timeline::insert_event(&conn, camera_id, "trigger", serde_json::json!({
+            "reason": reason,
+            "action": action,
+            "source": source,
+        }))?;
+        (event_id, load_settings(&conn)?)
+    };
+
+    println!("[Trigger] {} trigger for camera {} from {}: {}", action, camera_id, source, reason);
+    state.events.emit(AppEvent::TriggerReceived {
+        camera_id,
+        event_id,
+        action: action.clone(),
+        reason: reason.clone(),
+    });
+
+    let mut result = TriggerResult {
+        eventId: event_id,
+        action: action.clone(),
+        recordingId: None,
+        alreadyRecording: false,
+        snapshot: None,
+    };
+
+    if action == "snapshot" {
+        let snapshot = crate::snapshots::capture_snapshot(
+            &state.db_path, &state.onvif_http, &state.recording_dir, camera_id, None
+        ).await?;
+        state.events.emit(AppEvent::SnapshotCaptured(snapshot.clone()));
+        result.snapshot = Some(snapshot);
+        return Ok(result);
+    }
+
+    // A running recording already covers the trigger; leave its duration alone
+    if state.recording_processes.lock().map_err(|e| e.to_string())?.contains_key(&camera_id) {
+        result.alreadyRecording = true;
+        return Ok(result);
+    }
+
+    // A local device can only be opened once, so its live stream has to make way
+    if crate::stream::is_local_device(&camera.camera_type) && state.processes.lock().map_err(|e| e.to_string())?.contains_key(&camera_id) {
+        return Err("Local camera is streaming; stop the stream before triggering a recording".to_string());
+    }
+
+    let meta = RecordingMeta {
+        trigger: RecordingTrigger::External,
+        tags: Vec::new(),
+        notes: (!reason.is_empty()).then(|| reason.clone()),
+        session_id: None,
+    };
+    let recording_id = crate::stream::start_recording_with_options_direct(state, camera_id, None, meta).await?;
+    let minutes = request.durationMinutes.unwrap_or(settings.defaultRecordMinutes);
+    crate::stream::schedule_auto_stop(state, camera_id, recording_id, minutes);
+
+    result.recordingId = Some(recording_id);
+    Ok(result)
+}
+
+/// `/api/triggers/{camera_id}` for alarm panels, doorbells and automations. Accepts the
+/// persistent trigger token from app_settings (or the per-launch server token, or the
+/// session token of an operator) as `Authorization: Bearer` or `?token=`; parameters come as
+/// a JSON body or in the query string, since simple devices can often only send a GET.
+pub fn router(app_handle: AppHandle) -> Router {
+    Router::new()
+        .route("/api/triggers/:camera_id", get(trigger_from_query).post(trigger_from_body))
+        .with_state(app_handle)
+}
+
+/// Serve only the trigger endpoint on all interfaces when remote triggers are enabled,
+/// so other machines can reach it without exposing streams and recordings.
+/// Changes to allowRemote and remotePort take effect on the next launch.
+pub fn spawn_remote_listener(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let settings = {
+            let state = app_handle.state::<AppState>();
+            match crate::db::open(&state.db_path).map_err(|e| e.to_string()).and_then(|conn| load_settings(&conn)) {
+                Ok(settings) => settings,
+                Err(e) => {
+                    eprintln!("[Trigger] Failed to load trigger settings: {}", e);
+                    return;
+                }
+            }
+        };
+        if !settings.allowRemote {
+            return;
+        }
+
+        let addr = std::net::SocketAddr::from(([0, 0, 0, 0], settings.remotePort));
+        let listener = match tokio::net::TcpListener::bind(&addr).await {
+            Ok(listener) => listener,
+            Err(e) => {
+                eprintln!("[Trigger] Failed to listen on {}: {}", addr, e);
+                return;
+            }
+        };
+
+        println!("[Trigger] Accepting remote triggers on {}", addr);
+        if let Err(e) = axum::serve(listener, router(app_handle)).await {
+            eprintln!("[Trigger] Remote trigger listener stopped: {}", e);
+        }
+    });
+}
+
+async fn trigger_from_query(
+    State(app_handle): State<AppHandle>,
+    Path(camera_id): Path<i32>,
+    Query(query): Query<HashMap<String, String>>,
+    headers: HeaderMap,
+) -> Response {
+    let request = request_from_query(&query);
+    handle(app_handle, camera_id, &query, &headers, request).await
+}
+
+async fn trigger_from_body(
+    State(app_handle): State<AppHandle>,
+    Path(camera_id): Path<i32>,
+    Query(query): Query<HashMap<String, String>>,
+    headers: HeaderMap,
+    body: Option<Json<TriggerRequest>>,
+) -> Response {
+    let request = body.map(|Json(request)| request).unwrap_or_else(|| request_from_query(&query));
+    handle(app_handle, camera_id, &query, &headers, request).await
+}
+
+fn request_from_query(query: &HashMap<String, String>) -> TriggerRequest {
+    TriggerRequest {
+        action: query.get("action").cloned(),
+        reason: query.get("reason").cloned(),
+        durationMinutes: query.get("durationMinutes").and_then(|minutes| minutes.parse().ok()),
+    }
+}
+
+async fn handle(
+    app_handle: AppHandle,
+    camera_id: i32,
+    query: &HashMap<String, String>,
+    headers: &HeaderMap,
+    request: TriggerRequest,
+) -> Response {
+    let state = app_handle.state::<AppState>();
+
+    // Read the token on every request so a regenerated token applies immediately
+    let trigger_token = match crate::db::open(&state.db_path).map_err(|e| e.to_string()).and_then(|conn| load_settings(&conn)) {
+        Ok(settings) => settings.token,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+    if !crate::download::is_authorized(&trigger_token, headers, query)
+        && !crate::auth::authorize_request(&state.db_path, &state.server_token, headers, query, crate::auth::Role::Operator)
+    {
+        return (StatusCode::UNAUTHORIZED, "Missing or invalid token").into_response();
+    }
+
+    match fire(&state, camera_id, request, "http").await {
+        Ok(result) => Json(result).into_response(),
+        Err(e) => {
+            eprintln!("[Trigger] Trigger for camera {} failed: {}", camera_id, e);
+            (StatusCode::BAD_REQUEST, e).into_response()
+        }
+    }
+}
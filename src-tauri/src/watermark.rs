@@ -0,0 +1,156 @@
+use crate::models::Watermark;
+use std::path::Path;
+
+pub const WATERMARK_POSITIONS: [&str; 5] = ["top_left", "top_right", "bottom_left", "bottom_right", "center"];
+
+// Distance of the watermark from the frame edges, in pixels
+const MARGIN: u32 = 16;
+
+/// Reject a watermark FFmpeg can't draw before an export starts
+pub fn check(watermark: &Watermark) -> Result<(), String> {
+    let text = watermark.text.as_deref().map(str::trim).filter(|text| !text.is_empty());
+    match (text, watermark.image_path.as_deref()) {
+        (Some(_), Some(_)) => return Err("A watermark is either text or an image, not both".to_string()),
+        (None, None) => return Err("A watermark needs text or an image".to_string()),
+        (Some(text), None) if text.contains('\n') => {
+            return Err("Watermark text must be a single line".to_string());
+        }
+        (None, Some(path)) => {
+            let path = Path::new(path);
+            if !path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("png")) {
+                return Err("Watermark image must be a PNG file".to_string());
+            }
+            if !path.is_file() {
+                return Err(format!("Watermark image {} not found", path.display()));
+            }
+        }
+        _ => {}
+    }
+    if !WATERMARK_POSITIONS.contains(&watermark.position.as_str()) {
+        return Err(format!(
+            "Unknown watermark position '{}', expected one of {}",
+            watermark.position,
+            WATERMARK_POSITIONS.join(", ")
+        ));
+    }
+    if !(0.0..=1.0).contains(&watermark.opacity) {
+        return Err("Watermark opacity must be between 0 and 1".to_string());
+    }
+    if watermark.font_size.is_some_and(|size| !(8..=200).contains(&size)) {
+        return Err("Watermark font size must be between 8 and 200".to_string());
+    }
+    Ok(())
+}
+
+/// Extra FFmpeg input of an image watermark, to be added after the video inputs
+pub fn input_args(watermark: &Watermark) -> Vec<String> {
+    match watermark.image_path.as_deref() {
+        Some(path) => vec!["-loop".to_string(), "1".to_string(), "-i".to_string(), path.to_string()],
+        None => Vec::new(),
+    }
+}
+
+/// Filter graph chain drawing the watermark onto the `input` pad and naming the result `output`.
+/// `image_input` is the index of the input added by `input_args`.
+pub fn filter(watermark: &Watermark, input: &str, image_input: usize, output: &str) -> String {
+    let opacity = watermark.opacity;
+    match watermark.text.as_deref() {
+        Some(text) => {
+            let (x, y) = position(&watermark.position, "text_w", "text_h");
+            let font_size = watermark.font_size
+                .map(|size| size.to_string())
+                .unwrap_or_else(|| "h/24".to_string());
+            format!(
+                "[{input}]drawtext=text={}:expansion=none:fontsize={font_size}:fontcolor=white@{opacity}\
+                 :box=1:boxcolor=black@{:.2}:boxborderw=8:x={x}:y={y}[{output}]",
+                quote(&escape_text(text.trim())),
+                opacity * 0.5
+            )
+        }
+        None => {
+            let (x, y) = position(&watermark.position, "overlay_w", "overlay_h");
+            format!(
+                "[{image_input}:v]format=rgba,colorchannelmixer=aa={opacity}[watermark];\
+                 [{input}][watermark]overlay=x={x}:y={y}:shortest=1[{output}]"
+            )
+        }
+    }
+}
+
+// x and y expressions of a watermark `width` x `height` in the named corner
+fn position(position: &str, width: &str, height: &str) -> (String, String) {
+    let left = MARGIN.to_string();
+    let right = format!("main_w-{width}-{MARGIN}");
+    let top = MARGIN.to_string();
+    let bottom = format!("main_h-{height}-{MARGIN}");
+    match position {
+        "top_left" => (left, top),
+        "top_right" => (right, top),
+        "bottom_left" => (left, bottom),
+        "center" => (format!("(main_w-{width})/2"), format!("(main_h-{height})/2")),
+        _ => (right, bottom),
+    }
+}
+
+// drawtext's own escaping of an option value
+fn escape_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | ':' | '\'') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+// Quote a value for the filter graph parser, which keeps everything between quotes as is
+fn quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(text: &str, position: &str) -> Watermark {
+        Watermark {
+            text: Some(text.to_string()),
+            image_path: None,
+            position: position.to_string(),
+            opacity: 0.6,
+            font_size: None,
+        }
+    }
+
+    #[test]
+    fn text_is_escaped_for_drawtext_and_the_filter_graph() {
+        let filter = filter(&text("CONFIDENTIAL: it's ours", "top_left"), "0:v", 1, "out");
+        assert!(filter.starts_with("[0:v]drawtext=text='CONFIDENTIAL\\: it\\'\\''s ours':expansion=none"));
+        assert!(filter.contains(":x=16:y=16[out]"));
+        assert!(filter.contains("fontcolor=white@0.6"));
+    }
+
+    #[test]
+    fn image_is_overlaid_from_its_own_input() {
+        let mut watermark = text("", "bottom_right");
+        watermark.text = None;
+        watermark.image_path = Some("/tmp/logo.png".to_string());
+        assert_eq!(input_args(&watermark), ["-loop", "1", "-i", "/tmp/logo.png"]);
+        assert_eq!(
+            filter(&watermark, "stacked", 2, "out"),
+            "[2:v]format=rgba,colorchannelmixer=aa=0.6[watermark];\
+             [stacked][watermark]overlay=x=main_w-overlay_w-16:y=main_h-overlay_h-16:shortest=1[out]"
+        );
+    }
+
+    #[test]
+    fn rejects_incomplete_watermarks() {
+        assert!(check(&text("  ", "top_left")).is_err());
+        assert!(check(&text("Shop", "middle")).is_err());
+        let mut watermark = text("Shop", "center");
+        watermark.opacity = 1.5;
+        assert!(check(&watermark).is_err());
+        assert!(check(&text("Shop", "center")).is_ok());
+    }
+}
@@ -0,0 +1,142 @@
+use crate::models::{RecordingSchedule, WeeklyBlock};
+use chrono::{NaiveTime, Timelike};
+
+// Cron day names indexed by ISO weekday - 1 (names avoid the 0/1-based Sunday ambiguity
+// between cron parsers)
+const DAY_NAMES: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+const MINUTES_PER_DAY: i32 = 24 * 60;
+const MINUTES_PER_WEEK: i32 = 7 * MINUTES_PER_DAY;
+
+fn parse_time(value: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(value.trim(), "%H:%M")
+        .map_err(|_| format!("Invalid time '{}' (expected HH:MM)", value))
+}
+
+// Length of a block in minutes; ending at or before the start runs into the next day
+fn duration(start: NaiveTime, end: NaiveTime) -> i32 {
+    let minutes = (end - start).num_minutes() as i32;
+    if minutes > 0 { minutes } else { minutes + MINUTES_PER_DAY }
+}
+
+/// Check days are ISO weekdays (1 = Monday .. 7 = Sunday), times are HH:MM and no two blocks
+/// overlap, since a camera can only run one scheduled recording at a time
+pub fn validate_blocks(blocks: &[WeeklyBlock]) -> Result<(), String> {
+    if blocks.is_empty() {
+        return Err("A weekly schedule needs at least one time block".to_string());
+    }
+
+    for block in blocks {
+        if block.days.is_empty() {
+            return Err(format!("Time block {}-{} has no days", block.start, block.end));
+        }
+        if let Some(day) = block.days.iter().find(|d| !(1..=7).contains(*d)) {
+            return Err(format!("Invalid day {} (expected 1 = Monday .. 7 = Sunday)", day));
+        }
+        if parse_time(&block.start)? == parse_time(&block.end)? {
+            return Err(format!("Time block {}-{} is empty", block.start, block.end));
+        }
+    }
+
+    // [start, end) minutes of the week of every block and day; Sunday night blocks wrap to Monday
+    let mut ranges = Vec::new();
+    for block in blocks {
+        let start = parse_time(&block.start)?;
+        let length = duration(start, parse_time(&block.end)?);
+        let mut days = block.days.clone();
+        days.sort_unstable();
+        days.dedup();
+        for day in days {
+            let from = (day as i32 - 1) * MINUTES_PER_DAY + (start.hour() * 60 + start.minute()) as i32;
+            let to = from + length;
+            ranges.push((from, to.min(MINUTES_PER_WEEK), block));
+            if to > MINUTES_PER_WEEK {
+                ranges.push((0, to - MINUTES_PER_WEEK, block));
+            }
+        }
+    }
+    ranges.sort_by_key(|(from, _, _)| *from);
+    if let Some(pair) = ranges.windows(2).find(|pair| pair[1].0 < pair[0].1) {
+        let (a, b) = (pair[0].2, pair[1].2);
+        return Err(format!("Time blocks {}-{} and {}-{} overlap", a.start, a.end, b.start, b.end));
+    }
+
+    Ok(())
+}
+
+/// Compile blocks into (6-field cron expression, duration in minutes) pairs for the scheduler.
+/// A block ending at or before its start runs past midnight into the next day.
+pub fn compile(blocks: &[WeeklyBlock]) -> Result<Vec<(String, i32)>, String> {
+    validate_blocks(blocks)?;
+
+    let mut triggers = Vec::new();
+    for block in blocks {
+        let start = parse_time(&block.start)?;
+        let duration = duration(start, parse_time(&block.end)?);
+
+        let mut days = block.days.clone();
+        days.sort_unstable();
+        days.dedup();
+        let day_list = days.iter()
+            .map(|d| DAY_NAMES[(*d - 1) as usize])
+            .collect::<Vec<_>>()
+            .join(",");
+
+        triggers.push((
+            format!("0 {} {} * * {}", start.minute(), start.hour(), day_list),
+            duration,
+        ));
+    }
+
+    Ok(triggers)
+}
+
+/// Scheduler jobs for a schedule: one per weekly block, or its single cron expression
+pub fn triggers_for(schedule: &RecordingSchedule) -> Result<Vec<(String, i32)>, String> {
+    match &schedule.weekly_blocks {
+        Some(blocks) if schedule.schedule_type == "weekly" => compile(blocks),
+        _ => Ok(vec![(schedule.cron_expression.clone(), schedule.duration_minutes)]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(days: &[u8], start: &str, end: &str) -> WeeklyBlock {
+        WeeklyBlock { days: days.to_vec(), start: start.to_string(), end: end.to_string() }
+    }
+
+    #[test]
+    fn blocks_compile_to_cron_triggers() {
+        let triggers = compile(&[block(&[5, 1, 1], "09:30", "17:00")]).unwrap();
+        assert_eq!(triggers, [("0 30 9 * * Mon,Fri".to_string(), 450)]);
+    }
+
+    #[test]
+    fn overnight_blocks_run_into_the_next_day() {
+        let triggers = compile(&[block(&[7], "22:00", "06:00"), block(&[1], "08:00", "08:30")]).unwrap();
+        // Sunday night ends Monday 06:00, before the Monday block starts
+        assert_eq!(triggers, [("0 0 22 * * Sun".to_string(), 480), ("0 0 8 * * Mon".to_string(), 30)]);
+    }
+
+    #[test]
+    fn overlapping_blocks_are_rejected() {
+        assert!(validate_blocks(&[block(&[1, 2], "08:00", "12:00"), block(&[2], "11:00", "13:00")]).is_err());
+        // Only overlapping through midnight, and through Sunday into Monday
+        assert!(validate_blocks(&[block(&[3], "22:00", "02:00"), block(&[4], "01:00", "03:00")]).is_err());
+        assert!(validate_blocks(&[block(&[7], "23:00", "01:00"), block(&[1], "00:30", "02:00")]).is_err());
+        // Back to back is fine
+        assert!(validate_blocks(&[block(&[1], "08:00", "12:00"), block(&[1], "12:00", "13:00")]).is_ok());
+    }
+
+    #[test]
+    fn invalid_blocks_are_rejected() {
+        assert!(validate_blocks(&[]).is_err());
+        assert!(validate_blocks(&[block(&[], "08:00", "09:00")]).is_err());
+        assert!(validate_blocks(&[block(&[0], "08:00", "09:00")]).is_err());
+        assert!(validate_blocks(&[block(&[8], "08:00", "09:00")]).is_err());
+        assert!(validate_blocks(&[block(&[1], "8am", "09:00")]).is_err());
+        assert!(validate_blocks(&[block(&[1], "24:00", "09:00")]).is_err());
+        assert!(validate_blocks(&[block(&[1], "09:00", "09:00")]).is_err());
+    }
+}
@@ -1,6 +1,7 @@
 import { useState, useEffect, useRef, useCallback } from 'react';
-import { AppBar, Toolbar, Typography, Container, CssBaseline, CircularProgress, Alert, Button, Modal, Paper, IconButton } from '@mui/material';
+import { AppBar, Toolbar, Typography, Container, CssBaseline, CircularProgress, Alert, Button, Modal, Paper, IconButton, Snackbar } from '@mui/material';
 import SettingsIcon from '@mui/icons-material/Settings';
+import AssessmentIcon from '@mui/icons-material/Assessment';
 import { listen } from '@tauri-apps/api/event';
 import CameraList from './components/CameraList';
 import VideoPlayer from './components/VideoPlayer';
@@ -10,8 +11,12 @@ import DiscoverCamerasModal from './components/DiscoverCamerasModal';
 import PTZControls from './components/PTZControls';
 import EncoderSettings from './components/EncoderSettings';
 import ScheduleRecording from './components/ScheduleRecording';
-import { getCameras, startStream, stopStream, startRecording, stopRecording, checkPTZCapabilities } from './services/api';
-import type { Camera } from './services/api';
+import ActivityReport from './components/ActivityReport';
+import CameraImportExport from './components/CameraImportExport';
+import LoginDialog from './components/LoginDialog';
+import BackendHealthBanner from './components/BackendHealthBanner';
+import { getCameras, loadServerPort, getAuthStatus, logout, startStream, isStreamStartError, stopStream, startRecording, stopRecording, checkPTZCapabilities, startRecordingSessionPlayback, stopPlaybackSession, captureFrame, getSnapshotUrl } from './services/api';
+import type { AppUser, Camera, PlaybackSession, RecordingSession, ScheduleDeferredEvent, RecordingCompletedEvent, RecordingUpdatedEvent, LibraryChangedEvent, ActivityReport as ActivityReportType, ReportReadyEvent, EncoderFallbackEvent, RecordingFailedEvent } from './services/api';
 
 // Style for the modal (keeping MUI sx for complex overlay centering if tailwind is tricky, but Tailwind is better)
 // Tailwind: absolute top-1/2 left-1/2 -translate-x-1/2 -translate-y-1/2 w-[80vw] bg-white border-2 border-black shadow-xl p-4
@@ -63,13 +68,25 @@ function App() {
   const [activeCameras, setActiveCameras] = useState<Map<number, ActiveCameraState>>(new Map());
 
   const [isPlaybackModalOpen, setIsPlaybackModalOpen] = useState(false);
-  const [playingRecordingUrl, setPlayingRecordingUrl] = useState<string | null>(null);
+  const [playbackSession, setPlaybackSession] = useState<PlaybackSession | null>(null);
 
   const [isAddCameraModalOpen, setIsAddCameraModalOpen] = useState(false);
   const [isDiscoverModalOpen, setIsDiscoverModalOpen] = useState(false);
+  const [isImportExportOpen, setIsImportExportOpen] = useState(false);
   const [isEncoderSettingsOpen, setIsEncoderSettingsOpen] = useState(false);
+  const [isReportOpen, setIsReportOpen] = useState(false);
+  // Latest daily/weekly digest, shown in the report dialog from its notice
+  const [digestReport, setDigestReport] = useState<ActivityReportType | null>(null);
+  const [digestNotice, setDigestNotice] = useState<string | null>(null);
 
   const [recordingListVersion, setRecordingListVersion] = useState(0);
+  const [scheduleNotice, setScheduleNotice] = useState<string | null>(null);
+  const [encoderNotice, setEncoderNotice] = useState<EncoderFallbackEvent | null>(null);
+  const [recordingFailure, setRecordingFailure] = useState<RecordingFailedEvent | null>(null);
+  const [frameNotice, setFrameNotice] = useState<{ message: string; error: boolean } | null>(null);
+  // Set while login is required and nobody is logged in; the camera view is hidden until then
+  const [needsLogin, setNeedsLogin] = useState(false);
+  const [currentUser, setCurrentUser] = useState<AppUser | null>(null);
 
   const stateRef = useRef({ activeCameras });
   useEffect(() => {
@@ -100,18 +117,45 @@ function App() {
     }
   }, []);
 
-  useEffect(() => {
+  const restoreSession = useCallback(() => {
     const savedCameraIds = sessionStorage.getItem(SESSION_STORAGE_KEY);
     const idsToRestore = savedCameraIds ? JSON.parse(savedCameraIds) : undefined;
     fetchCameras(idsToRestore);
   }, [fetchCameras]);
 
+  useEffect(() => {
+    loadServerPort()
+      .catch(err => console.error('Failed to get the server port', err))
+      .then(() => getAuthStatus())
+      .then(status => {
+        setCurrentUser(status.user);
+        if (status.required && !status.user) {
+          setNeedsLogin(true);
+        } else {
+          restoreSession();
+        }
+      })
+      .catch(err => {
+        console.error('Failed to get the login status', err);
+        restoreSession();
+      });
+  }, [restoreSession]);
+
+  const handleLogout = async () => {
+    await logout().catch(err => console.error('Failed to log out', err));
+    setCurrentUser(null);
+    const status = await getAuthStatus().catch(() => null);
+    if (status?.required) {
+      setNeedsLogin(true);
+    }
+  };
+
   // Listen for recording-completed events from backend
   useEffect(() => {
     const setupListener = async () => {
-      const unlisten = await listen('recording-completed', (event) => {
-        const cameraId = event.payload as number;
-        console.log(`[Event] Recording completed for camera ${cameraId}`);
+      const unlisten = await listen<RecordingCompletedEvent>('recording-completed', (event) => {
+        const { cameraId, recordingId } = event.payload;
+        console.log(`[Event] Recording ${recordingId} completed for camera ${cameraId}`);
 
         // Update recording list
         setRecordingListVersion(v => v + 1);
@@ -133,6 +177,85 @@ function App() {
     };
   }, []);
 
+  // A recording whose FFmpeg died right after starting (bad credentials, wrong path)
+  useEffect(() => {
+    const unlisten = listen<RecordingFailedEvent>('recording-failed', (event) => {
+      const { cameraId } = event.payload;
+      console.warn('[Event] Recording failed', event.payload);
+      setRecordingFailure(event.payload);
+      setActiveCameras(prev => {
+        const cameraState = prev.get(cameraId);
+        if (!cameraState) return prev;
+        const newMap = new Map(prev);
+        newMap.set(cameraId, { ...cameraState, recordingStatus: 'idle' });
+        return newMap;
+      });
+      setRecordingListVersion(v => v + 1);
+    });
+
+    return () => {
+      unlisten.then(fn => fn());
+    };
+  }, []);
+
+  // Thumbnails are generated after the recording completes
+  useEffect(() => {
+    const unlisten = listen<RecordingUpdatedEvent>('recording-updated', () => {
+      setRecordingListVersion(v => v + 1);
+    });
+
+    return () => {
+      unlisten.then(fn => fn());
+    };
+  }, []);
+
+  // Recording files deleted outside the app
+  useEffect(() => {
+    const unlisten = listen<LibraryChangedEvent>('library-changed', () => {
+      setRecordingListVersion(v => v + 1);
+    });
+
+    return () => {
+      unlisten.then(fn => fn());
+    };
+  }, []);
+
+  // Daily or weekly activity digest
+  useEffect(() => {
+    const unlisten = listen<ReportReadyEvent>('report-ready', (event) => {
+      const { period, report } = event.payload;
+      setDigestReport(report);
+      setDigestNotice(`${period === 'daily' ? 'Daily' : 'Weekly'} report: ${report.recordings} recordings, ${report.failure_count} failures`);
+    });
+
+    return () => {
+      unlisten.then(fn => fn());
+    };
+  }, []);
+
+  // Notify when a schedule couldn't run because too many recordings were active
+  useEffect(() => {
+    const unlisten = listen<ScheduleDeferredEvent>('schedule-deferred', (event) => {
+      console.warn('[Event] Schedule deferred', event.payload);
+      setScheduleNotice(`Schedule "${event.payload.name}" did not run: ${event.payload.reason}`);
+    });
+
+    return () => {
+      unlisten.then(fn => fn());
+    };
+  }, []);
+
+  // Notify when a stream's GPU encoder failed and it was restarted on the CPU
+  useEffect(() => {
+    const unlisten = listen<EncoderFallbackEvent>('encoder-fallback', (event) => {
+      console.warn('[Event] Encoder fallback', event.payload);
+      setEncoderNotice(event.payload);
+    });
+
+    return () => {
+      unlisten.then(fn => fn());
+    };
+  }, []);
 
   useEffect(() => {
     const handleCleanup = async (isUnloading = false) => {
@@ -264,9 +387,11 @@ function App() {
 
           console.error('Failed to start or poll for stream:', error);
 
-          // Tauri invoke returns the error as a string directly in the catch block
+          // start_stream rejects with a StreamStartError; FFmpeg's last line usually names the cause
 
-          const errorMessage = error instanceof Error ? error.message : String(error);
+          const errorMessage = isStreamStartError(error)
+            ? [error.message, error.stderr[error.stderr.length - 1]].filter(Boolean).join(': ')
+            : error instanceof Error ? error.message : String(error);
 
     
 
@@ -321,21 +446,43 @@ function App() {
     }
   };
 
-  const handlePlayRecording = (filename: string) => {
-    // In Tauri, we might need a custom protocol to serve files or localhost server.
-    // Assuming the backend serves recordings at a specific URL.
-    // We will use a placeholder or assume backend returns full URL or we construct it.
-    // For now, let's assume Rust serves it at http://localhost:PORT/recordings/filename
-    // I'll make this dynamic later.
-    // Actually, `VideoPlayer` might expect a URL.
-    const url = `http://localhost:3333/recordings/${filename}`; // TODO: Fix this port assumption
-    setPlayingRecordingUrl(url);
-    setIsPlaybackModalOpen(true);
+  // Still of the live view, kept in the snapshots library; copying also puts it on the clipboard
+  const handleCaptureFrame = async (cameraId: number, copy: boolean) => {
+    try {
+      const frame = await captureFrame(cameraId, undefined, copy ? 'png' : 'jpeg');
+      if (copy && frame.snapshot) {
+        const image = await (await fetch(await getSnapshotUrl(frame.snapshot))).blob();
+        await navigator.clipboard.write([new ClipboardItem({ 'image/png': image })]);
+        setFrameNotice({ message: 'Frame copied to the clipboard', error: false });
+      } else {
+        setFrameNotice({ message: `Frame saved to ${frame.path}`, error: false });
+      }
+    } catch (error) {
+      setFrameNotice({ message: `Failed to capture frame: ${error}`, error: true });
+    }
+  };
+
+  const handlePlayRecording = async (recording: RecordingSession) => {
+    try {
+      // The backend serves compatible files directly and transcodes the rest (and sessions of
+      // several segments) to HLS
+      const session = await startRecordingSessionPlayback(recording.id);
+      setPlaybackSession(session);
+      setIsPlaybackModalOpen(true);
+    } catch (error) {
+      console.error('Failed to start playback:', error);
+      alert(`Failed to play recording: ${error}`);
+    }
   };
 
   const handleClosePlaybackModal = () => {
+    if (playbackSession?.sessionId) {
+      stopPlaybackSession(playbackSession.sessionId).catch(error =>
+        console.error('Failed to stop playback session:', error)
+      );
+    }
     setIsPlaybackModalOpen(false);
-    setPlayingRecordingUrl(null);
+    setPlaybackSession(null);
   };
 
   const handleCameraAdded = () => {
@@ -367,6 +514,16 @@ function App() {
           <Typography variant="h6" component="div" className="flex-grow font-semibold">
             ONVIF Camera Viewer (Tauri)
           </Typography>
+          <IconButton
+            color="inherit"
+            onClick={() => {
+              setDigestReport(null);
+              setIsReportOpen(true);
+            }}
+            title="Activity Report"
+          >
+            <AssessmentIcon />
+          </IconButton>
           <IconButton
             color="inherit"
             onClick={() => setIsEncoderSettingsOpen(true)}
@@ -374,8 +531,15 @@ function App() {
           >
             <SettingsIcon />
           </IconButton>
+          {currentUser && (
+            <Button color="inherit" onClick={handleLogout} title={`Logged in as ${currentUser.username} (${currentUser.role})`}>
+              Log out
+            </Button>
+          )}
         </Toolbar>
       </AppBar>
+      <BackendHealthBanner />
+      {!needsLogin && (
       <main>
         <Container maxWidth="xl" className="py-8">
           <div className="flex justify-between items-center mb-6">
@@ -383,6 +547,9 @@ function App() {
               Cameras
             </Typography>
             <div className="flex gap-4">
+              <Button variant="outlined" onClick={() => setIsImportExportOpen(true)} className="bg-white">
+                Import / Export
+              </Button>
               <Button variant="outlined" onClick={() => setIsDiscoverModalOpen(true)} className="bg-white">
                 Discover Cameras
               </Button>
@@ -399,6 +566,8 @@ function App() {
             activeCameraIds={Array.from(activeCameras.keys())}
             onSelectCamera={handleSelectCamera}
             onCameraDeleted={handleCameraDeleted}
+            onCameraRestored={handleCameraAdded}
+            onCamerasChanged={setCameras}
           />
 
           {activeCameras.size > 0 && (
@@ -407,7 +576,9 @@ function App() {
                 Live Streams ({activeCameras.size}/{MAX_CAMERAS})
               </Typography>
               <div className="grid grid-cols-1 md:grid-cols-2 gap-6">
-                {Array.from(activeCameras.entries()).map(([cameraId, cameraState]) => (
+                {Array.from(activeCameras.entries())
+                  .sort(([a], [b]) => cameras.findIndex(c => c.id === a) - cameras.findIndex(c => c.id === b))
+                  .map(([cameraId, cameraState]) => (
                   <div
                     key={cameraId}
                     className="bg-white p-4 rounded-lg shadow-md border border-gray-200"
@@ -457,6 +628,12 @@ function App() {
                               Stop Recording
                             </Button>
                           )}
+                          <Button size="small" variant="outlined" onClick={() => handleCaptureFrame(cameraId, false)}>
+                            Save Frame
+                          </Button>
+                          <Button size="small" variant="outlined" onClick={() => handleCaptureFrame(cameraId, true)}>
+                            Copy Frame
+                          </Button>
                           {cameraState.recordingStatus === 'recording' && (
                             <div className="flex items-center gap-2 animate-pulse">
                               <div className="w-3 h-3 rounded-full bg-red-600"></div>
@@ -490,6 +667,15 @@ function App() {
 
         </Container>
       </main>
+      )}
+      <LoginDialog
+        open={needsLogin}
+        onLoggedIn={(session) => {
+          setCurrentUser(session.user);
+          setNeedsLogin(false);
+          restoreSession();
+        }}
+      />
       <AddCameraModal
         open={isAddCameraModalOpen}
         onClose={() => setIsAddCameraModalOpen(false)}
@@ -501,21 +687,93 @@ function App() {
         onCameraAdded={handleCameraAdded}
         registeredCameras={cameras}
       />
+      <CameraImportExport
+        open={isImportExportOpen}
+        onClose={() => setIsImportExportOpen(false)}
+        onCamerasImported={handleCameraAdded}
+      />
       <Modal
         open={isPlaybackModalOpen}
         onClose={handleClosePlaybackModal}
         aria-labelledby="recording-playback-modal"
       >
         <div className={modalClassName}>
-          {playingRecordingUrl && (
-            <video src={playingRecordingUrl} controls autoPlay className="w-full max-h-[70vh] bg-black" />
-          )}
+          {playbackSession && (playbackSession.transcoded ? (
+            <VideoPlayer streamUrl={playbackSession.url} />
+          ) : (
+            <video src={playbackSession.url} controls autoPlay className="w-full max-h-[70vh] bg-black" />
+          ))}
         </div>
       </Modal>
       <EncoderSettings
         open={isEncoderSettingsOpen}
         onClose={() => setIsEncoderSettingsOpen(false)}
       />
+      <ActivityReport
+        open={isReportOpen}
+        onClose={() => setIsReportOpen(false)}
+        digest={digestReport}
+      />
+      <Snackbar
+        open={digestNotice !== null}
+        autoHideDuration={15000}
+        onClose={() => setDigestNotice(null)}
+        anchorOrigin={{ vertical: 'bottom', horizontal: 'left' }}
+      >
+        <Alert
+          severity="info"
+          onClose={() => setDigestNotice(null)}
+          action={
+            <Button color="inherit" size="small" onClick={() => { setDigestNotice(null); setIsReportOpen(true); }}>
+              View
+            </Button>
+          }
+        >
+          {digestNotice}
+        </Alert>
+      </Snackbar>
+      <Snackbar
+        open={scheduleNotice !== null}
+        autoHideDuration={10000}
+        onClose={() => setScheduleNotice(null)}
+        anchorOrigin={{ vertical: 'bottom', horizontal: 'right' }}
+      >
+        <Alert severity="warning" onClose={() => setScheduleNotice(null)}>
+          {scheduleNotice}
+        </Alert>
+      </Snackbar>
+      <Snackbar
+        open={encoderNotice !== null}
+        autoHideDuration={10000}
+        onClose={() => setEncoderNotice(null)}
+        anchorOrigin={{ vertical: 'bottom', horizontal: 'right' }}
+      >
+        <Alert severity="warning" onClose={() => setEncoderNotice(null)}>
+          {encoderNotice && `${cameras.find((c) => c.id === encoderNotice.cameraId)?.name ?? `Camera ${encoderNotice.cameraId}`}: ` +
+            `${encoderNotice.encoder} failed, streaming with the CPU encoder`}
+        </Alert>
+      </Snackbar>
+      <Snackbar
+        open={recordingFailure !== null}
+        autoHideDuration={15000}
+        onClose={() => setRecordingFailure(null)}
+        anchorOrigin={{ vertical: 'bottom', horizontal: 'right' }}
+      >
+        <Alert severity="error" onClose={() => setRecordingFailure(null)} sx={{ whiteSpace: 'pre-line' }}>
+          {recordingFailure && `${cameras.find((c) => c.id === recordingFailure.cameraId)?.name ?? `Camera ${recordingFailure.cameraId}`}: ` +
+            `recording failed. ${recordingFailure.error}`}
+        </Alert>
+      </Snackbar>
+      <Snackbar
+        open={frameNotice !== null}
+        autoHideDuration={5000}
+        onClose={() => setFrameNotice(null)}
+        anchorOrigin={{ vertical: 'bottom', horizontal: 'center' }}
+      >
+        <Alert severity={frameNotice?.error ? 'error' : 'success'} onClose={() => setFrameNotice(null)}>
+          {frameNotice?.message}
+        </Alert>
+      </Snackbar>
     </div>
   );
 }
@@ -0,0 +1,239 @@
+import { useEffect, useState } from 'react';
+import {
+  Dialog,
+  DialogTitle,
+  DialogContent,
+  DialogActions,
+  Button,
+  FormControl,
+  InputLabel,
+  Select,
+  MenuItem,
+  TextField,
+  Alert,
+  CircularProgress,
+  Box,
+  Typography,
+  Table,
+  TableBody,
+  TableCell,
+  TableHead,
+  TableRow,
+} from '@mui/material';
+import {
+  generateReport,
+  getAppSettings,
+  updateAppSettings,
+  type ActivityReport as ActivityReportType,
+  type AppSettings,
+} from '../services/api';
+
+interface ActivityReportProps {
+  open: boolean;
+  onClose: () => void;
+  // Digest received while the app was open; shown instead of generating one
+  digest?: ActivityReportType | null;
+}
+
+const RANGES = [
+  { label: 'Last 24 hours', hours: 24 },
+  { label: 'Last 7 days', hours: 24 * 7 },
+  { label: 'Last 30 days', hours: 24 * 30 },
+];
+
+const formatBytes = (bytes: number) => {
+  if (bytes >= 1024 ** 3) return `${(bytes / 1024 ** 3).toFixed(1)} GB`;
+  return `${(bytes / 1024 ** 2).toFixed(1)} MB`;
+};
+
+const formatHours = (secs: number) => `${Math.floor(secs / 3600)}h ${Math.floor((secs % 3600) / 60)}m`;
+
+export default function ActivityReport({ open, onClose, digest }: ActivityReportProps) {
+  const [hours, setHours] = useState(24);
+  const [report, setReport] = useState<ActivityReportType | null>(null);
+  const [loading, setLoading] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+  const [digestPeriod, setDigestPeriod] = useState<AppSettings['reports.digest']>('off');
+  const [webhookUrl, setWebhookUrl] = useState('');
+  const [successMessage, setSuccessMessage] = useState<string | null>(null);
+
+  useEffect(() => {
+    if (!open) return;
+    setSuccessMessage(null);
+    getAppSettings()
+      .then((settings) => {
+        setDigestPeriod(settings['reports.digest']);
+        setWebhookUrl(settings['reports.webhookUrl'] ?? '');
+      })
+      .catch((err) => setError(`Failed to load settings: ${err}`));
+    if (digest) {
+      setReport(digest);
+    } else {
+      loadReport(hours);
+    }
+  }, [open, digest]);
+
+  const loadReport = async (rangeHours: number) => {
+    setLoading(true);
+    setError(null);
+    try {
+      const to = new Date();
+      const from = new Date(to.getTime() - rangeHours * 3600 * 1000);
+      setReport(await generateReport(from, to));
+    } catch (err: any) {
+      setError(`Failed to generate report: ${err}`);
+    } finally {
+      setLoading(false);
+    }
+  };
+
+  const handleSaveDigest = async () => {
+    setError(null);
+    setSuccessMessage(null);
+    try {
+      await updateAppSettings({ 'reports.digest': digestPeriod, 'reports.webhookUrl': webhookUrl.trim() || null });
+      setSuccessMessage('Digest settings saved');
+    } catch (err: any) {
+      setError(`Failed to save digest settings: ${err}`);
+    }
+  };
+
+  return (
+    <Dialog open={open} onClose={onClose} maxWidth="md" fullWidth>
+      <DialogTitle>Activity Report</DialogTitle>
+      <DialogContent>
+        {error && <Alert severity="error" sx={{ mb: 2 }}>{error}</Alert>}
+        {successMessage && <Alert severity="success" sx={{ mb: 2 }}>{successMessage}</Alert>}
+
+        <Box display="flex" gap={2} alignItems="center" mt={1}>
+          <FormControl size="small" sx={{ minWidth: 180 }}>
+            <InputLabel>Range</InputLabel>
+            <Select
+              value={hours}
+              label="Range"
+              onChange={(e) => {
+                const value = e.target.value as number;
+                setHours(value);
+                loadReport(value);
+              }}
+            >
+              {RANGES.map((range) => (
+                <MenuItem key={range.hours} value={range.hours}>{range.label}</MenuItem>
+              ))}
+            </Select>
+          </FormControl>
+          {loading && <CircularProgress size={24} />}
+        </Box>
+
+        {report && (
+          <Box mt={2}>
+            <Typography variant="body2" color="text.secondary">
+              {new Date(report.from).toLocaleString()} - {new Date(report.to).toLocaleString()}
+            </Typography>
+            <Typography variant="h6" mt={1}>
+              {report.recordings} recordings, {formatBytes(report.recorded_bytes)}, {formatHours(report.recorded_secs)}
+            </Typography>
+            <Typography color={report.failure_count > 0 ? 'error' : 'text.secondary'}>
+              {report.failure_count} failures, {report.offline_cameras.length} cameras with dropped streams
+            </Typography>
+
+            <Table size="small" sx={{ mt: 2 }}>
+              <TableHead>
+                <TableRow>
+                  <TableCell>Camera</TableCell>
+                  <TableCell align="right">Recordings</TableCell>
+                  <TableCell align="right">Size</TableCell>
+                  <TableCell align="right">Duration</TableCell>
+                  <TableCell align="right">Failures</TableCell>
+                </TableRow>
+              </TableHead>
+              <TableBody>
+                {report.cameras.map((camera) => (
+                  <TableRow key={camera.camera_id}>
+                    <TableCell>{camera.camera_name}</TableCell>
+                    <TableCell align="right">{camera.recordings}</TableCell>
+                    <TableCell align="right">{formatBytes(camera.recorded_bytes)}</TableCell>
+                    <TableCell align="right">{formatHours(camera.recorded_secs)}</TableCell>
+                    <TableCell align="right">{camera.failures}</TableCell>
+                  </TableRow>
+                ))}
+              </TableBody>
+            </Table>
+
+            {report.offline_cameras.length > 0 && (
+              <Box mt={2}>
+                <Typography variant="subtitle2">Dropped streams</Typography>
+                {report.offline_cameras.map((camera) => (
+                  <Typography key={camera.camera_id} variant="body2">
+                    {camera.camera_name ?? `Camera ${camera.camera_id}`}: {camera.incidents} times, last{' '}
+                    {new Date(camera.last_incident_at).toLocaleString()}
+                    {camera.last_reason ? ` (${camera.last_reason})` : ''}
+                  </Typography>
+                ))}
+              </Box>
+            )}
+
+            {report.failures.length > 0 && (
+              <Box mt={2}>
+                <Typography variant="subtitle2">Failures</Typography>
+                {report.failures.map((failure, index) => (
+                  <Typography key={index} variant="body2">
+                    {new Date(failure.occurred_at).toLocaleString()} {failure.camera_name ?? `Camera ${failure.camera_id}`}
+                    {' '}({failure.kind}): {failure.detail ?? 'unknown error'}
+                  </Typography>
+                ))}
+              </Box>
+            )}
+
+            <Box mt={2}>
+              <Typography variant="subtitle2">Recorded per day</Typography>
+              <Typography variant="body2">
+                {report.daily_usage.map((day) => `${day.date}: ${formatBytes(day.bytes)}`).join(', ')}
+              </Typography>
+            </Box>
+
+            <Box mt={2}>
+              <Typography variant="subtitle2">Storage</Typography>
+              {report.storage.map((storage) => (
+                <Typography key={storage.path} variant="body2">
+                  {storage.name} ({storage.path}):{' '}
+                  {storage.free_bytes != null && storage.total_bytes != null
+                    ? `${formatBytes(storage.free_bytes)} free of ${formatBytes(storage.total_bytes)}`
+                    : 'unavailable'}
+                  {storage.days_left != null ? `, about ${storage.days_left} days left` : ''}
+                </Typography>
+              ))}
+            </Box>
+          </Box>
+        )}
+
+        <Typography variant="subtitle2" mt={3}>Digest</Typography>
+        <Box display="flex" gap={2} alignItems="center" mt={1}>
+          <FormControl size="small" sx={{ minWidth: 140 }}>
+            <InputLabel>Send</InputLabel>
+            <Select
+              value={digestPeriod}
+              label="Send"
+              onChange={(e) => setDigestPeriod(e.target.value as AppSettings['reports.digest'])}
+            >
+              <MenuItem value="off">Off</MenuItem>
+              <MenuItem value="daily">Daily</MenuItem>
+              <MenuItem value="weekly">Weekly</MenuItem>
+            </Select>
+          </FormControl>
+          <TextField
+            size="small"
+            fullWidth
+            label="Webhook URL (optional)"
+            value={webhookUrl}
+            onChange={(e) => setWebhookUrl(e.target.value)}
+          />
+          <Button variant="outlined" onClick={handleSaveDigest}>Save</Button>
+        </Box>
+      </DialogContent>
+      <DialogActions>
+        <Button onClick={onClose}>Close</Button>
+      </DialogActions>
+    </Dialog>
+  );
+}
@@ -0,0 +1,52 @@
+import { useEffect, useRef, useState } from 'react';
+import { Alert } from '@mui/material';
+import { listen } from '@tauri-apps/api/event';
+import { ping, type BackendHeartbeat } from '../services/api';
+
+// Heartbeats that may be missed before the backend is pinged directly
+const MISSED_BEATS = 3;
+const PING_TIMEOUT_MS = 5000;
+const DEFAULT_INTERVAL_SECS = 5;
+
+// Banner shown while the backend reports problems or stops answering, so failing commands
+// have a visible cause
+export default function BackendHealthBanner() {
+  const [beat, setBeat] = useState<BackendHeartbeat | null>(null);
+  const [unresponsive, setUnresponsive] = useState(false);
+  const lastBeatAt = useRef(Date.now());
+  const intervalSecs = useRef(DEFAULT_INTERVAL_SECS);
+
+  useEffect(() => {
+    const received = (heartbeat: BackendHeartbeat) => {
+      lastBeatAt.current = Date.now();
+      intervalSecs.current = heartbeat.intervalSecs;
+      setBeat(heartbeat);
+      setUnresponsive(false);
+    };
+    const unlisten = listen<BackendHeartbeat>('backend-heartbeat', (event) => received(event.payload));
+
+    const timer = setInterval(async () => {
+      if (Date.now() - lastBeatAt.current < MISSED_BEATS * intervalSecs.current * 1000) return;
+      try {
+        const timeout = new Promise<never>((_, reject) => setTimeout(() => reject(new Error('timeout')), PING_TIMEOUT_MS));
+        received(await Promise.race([ping(), timeout]));
+      } catch (err) {
+        console.warn('[Heartbeat] Backend not responding:', err);
+        setUnresponsive(true);
+      }
+    }, DEFAULT_INTERVAL_SECS * 1000);
+
+    return () => {
+      clearInterval(timer);
+      unlisten.then(fn => fn());
+    };
+  }, []);
+
+  if (unresponsive) {
+    return <Alert severity="error" square>The backend is not responding. Actions may time out until it recovers.</Alert>;
+  }
+  if (beat && beat.problems.length > 0) {
+    return <Alert severity="warning" square>Backend degraded: {beat.problems.join('; ')}</Alert>;
+  }
+  return null;
+}
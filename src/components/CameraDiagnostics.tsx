@@ -0,0 +1,122 @@
+import { useEffect, useState } from 'react';
+import {
+  Dialog,
+  DialogTitle,
+  DialogContent,
+  DialogActions,
+  Button,
+  Alert,
+  CircularProgress,
+  Box,
+  Typography,
+  Table,
+  TableBody,
+  TableCell,
+  TableHead,
+  TableRow,
+  Chip,
+} from '@mui/material';
+import { runCameraDiagnostics, type Camera, type CameraDiagnostics as CameraDiagnosticsType } from '../services/api';
+
+interface CameraDiagnosticsProps {
+  camera: Camera | null;
+  onClose: () => void;
+}
+
+const STATUS_COLORS = { pass: 'success', fail: 'error', skipped: 'default' } as const;
+
+// ONVIF self-test of one camera: tells a camera problem from an app problem
+export default function CameraDiagnostics({ camera, onClose }: CameraDiagnosticsProps) {
+  const [result, setResult] = useState<CameraDiagnosticsType | null>(null);
+  const [running, setRunning] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+  const [expanded, setExpanded] = useState<string | null>(null);
+
+  const run = async (id: number) => {
+    setRunning(true);
+    setError(null);
+    setResult(null);
+    setExpanded(null);
+    try {
+      setResult(await runCameraDiagnostics(id));
+    } catch (err: any) {
+      setError(String(err));
+    } finally {
+      setRunning(false);
+    }
+  };
+
+  useEffect(() => {
+    if (camera) run(camera.id);
+  }, [camera]);
+
+  return (
+    <Dialog open={camera !== null} onClose={onClose} maxWidth="md" fullWidth>
+      <DialogTitle>ONVIF Diagnostics{camera ? `: ${camera.name}` : ''}</DialogTitle>
+      <DialogContent>
+        {error && <Alert severity="error" sx={{ mb: 2 }}>{error}</Alert>}
+        {running && (
+          <Box display="flex" gap={2} alignItems="center" mt={1}>
+            <CircularProgress size={24} />
+            <Typography>Testing the camera's ONVIF operations...</Typography>
+          </Box>
+        )}
+
+        {result && (
+          <Box mt={1}>
+            <Alert severity={result.failed > 0 ? 'warning' : 'success'}>
+              {result.passed} passed, {result.failed} failed, {result.skipped} skipped. {result.conclusion}
+            </Alert>
+            <Table size="small" sx={{ mt: 2 }}>
+              <TableHead>
+                <TableRow>
+                  <TableCell>Operation</TableCell>
+                  <TableCell>Result</TableCell>
+                  <TableCell>Details</TableCell>
+                  <TableCell align="right">Time</TableCell>
+                </TableRow>
+              </TableHead>
+              <TableBody>
+                {result.checks.map((check) => (
+                  <TableRow
+                    key={check.operation}
+                    hover={check.fault !== null}
+                    onClick={() => check.fault && setExpanded(expanded === check.operation ? null : check.operation)}
+                    sx={{ cursor: check.fault ? 'pointer' : 'default', verticalAlign: 'top' }}
+                  >
+                    <TableCell>{check.operation}</TableCell>
+                    <TableCell>
+                      <Chip label={check.status} size="small" color={STATUS_COLORS[check.status]} />
+                    </TableCell>
+                    <TableCell sx={{ wordBreak: 'break-all' }}>
+                      {check.detail}
+                      {check.fault && (
+                        <Typography variant="body2" color="text.secondary">
+                          Fault {[check.fault.code, ...check.fault.subcodes].filter(Boolean).join('/')}
+                          {check.fault.reason ? `: ${check.fault.reason}` : ''}
+                          {expanded === check.operation ? '' : ' (click for the raw response)'}
+                        </Typography>
+                      )}
+                      {check.fault && expanded === check.operation && (
+                        <Box component="pre" sx={{ fontSize: 12, whiteSpace: 'pre-wrap', mt: 1 }}>
+                          {check.fault.raw}
+                        </Box>
+                      )}
+                    </TableCell>
+                    <TableCell align="right">
+                      {check.duration_ms != null ? `${check.duration_ms} ms` : ''}
+                    </TableCell>
+                  </TableRow>
+                ))}
+              </TableBody>
+            </Table>
+          </Box>
+        )}
+      </DialogContent>
+      <DialogActions>
+        <Button onClick={() => camera && run(camera.id)} disabled={running || !camera}>Run again</Button>
+        <Button onClick={onClose}>Close</Button>
+      </DialogActions>
+    </Dialog>
+  );
+}
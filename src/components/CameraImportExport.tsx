@@ -0,0 +1,148 @@
+import { useEffect, useState } from 'react';
+import {
+  Dialog,
+  DialogTitle,
+  DialogContent,
+  DialogActions,
+  Button,
+  TextField,
+  Alert,
+  Box,
+  Typography,
+  FormControlLabel,
+  Checkbox,
+} from '@mui/material';
+import { importCameras, exportCameras, type CameraImport } from '../services/api';
+
+interface CameraImportExportProps {
+  open: boolean;
+  onClose: () => void;
+  onCamerasImported: () => void;
+}
+
+// Migrate cameras from Frigate or Shinobi, or move this app's camera list elsewhere
+export default function CameraImportExport({ open, onClose, onCamerasImported }: CameraImportExportProps) {
+  const [importPath, setImportPath] = useState('');
+  const [preview, setPreview] = useState<CameraImport | null>(null);
+  const [exportPath, setExportPath] = useState('');
+  const [includeCredentials, setIncludeCredentials] = useState(false);
+  const [busy, setBusy] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+  const [successMessage, setSuccessMessage] = useState<string | null>(null);
+
+  useEffect(() => {
+    if (open) {
+      setPreview(null);
+      setError(null);
+      setSuccessMessage(null);
+    }
+  }, [open]);
+
+  const run = async (action: () => Promise<void>) => {
+    setBusy(true);
+    setError(null);
+    setSuccessMessage(null);
+    try {
+      await action();
+    } catch (err: any) {
+      setError(String(err));
+    } finally {
+      setBusy(false);
+    }
+  };
+
+  const handlePreview = () => run(async () => {
+    setPreview(await importCameras(importPath.trim(), true));
+  });
+
+  const handleImport = () => run(async () => {
+    const result = await importCameras(importPath.trim(), false);
+    setPreview(null);
+    setSuccessMessage(`Imported ${result.created.length} cameras from ${result.source}` +
+      (result.skipped.length > 0 ? `, skipped ${result.skipped.length}` : ''));
+    onCamerasImported();
+  });
+
+  const handleExport = () => run(async () => {
+    const count = await exportCameras(exportPath.trim(), includeCredentials);
+    setSuccessMessage(`Exported ${count} cameras to ${exportPath.trim()}`);
+  });
+
+  return (
+    <Dialog open={open} onClose={onClose} maxWidth="sm" fullWidth>
+      <DialogTitle>Import / Export Cameras</DialogTitle>
+      <DialogContent>
+        {error && <Alert severity="error" sx={{ mb: 2 }}>{error}</Alert>}
+        {successMessage && <Alert severity="success" sx={{ mb: 2 }}>{successMessage}</Alert>}
+
+        <Typography variant="subtitle2" mt={1}>Import</Typography>
+        <Typography variant="body2" color="text.secondary">
+          Frigate config (config.yml), Shinobi monitor export (JSON) or a camera list exported here
+        </Typography>
+        <Box display="flex" gap={1} alignItems="center" mt={1}>
+          <TextField
+            size="small"
+            fullWidth
+            label="File path"
+            value={importPath}
+            onChange={(e) => {
+              setImportPath(e.target.value);
+              setPreview(null);
+            }}
+          />
+          <Button variant="outlined" onClick={handlePreview} disabled={busy || !importPath.trim()}>
+            Preview
+          </Button>
+        </Box>
+
+        {preview && (
+          <Box mt={2}>
+            <Typography variant="body2">
+              {preview.cameras.length} cameras from {preview.source}:
+            </Typography>
+            {preview.cameras.map((camera) => (
+              <Typography key={camera.name} variant="body2" color="text.secondary">
+                {camera.name}: {camera.type} {camera.host}:{camera.port}{camera.stream_path ?? ''}
+                {camera.user ? ` (user ${camera.user})` : ''}
+              </Typography>
+            ))}
+            {preview.skipped.map((skipped) => (
+              <Typography key={`skipped-${skipped.name}`} variant="body2" color="warning.main">
+                Skipped {skipped.name}: {skipped.reason}
+              </Typography>
+            ))}
+            <Button
+              variant="contained"
+              sx={{ mt: 1 }}
+              onClick={handleImport}
+              disabled={busy || preview.cameras.length === 0}
+            >
+              Import {preview.cameras.length} cameras
+            </Button>
+          </Box>
+        )}
+
+        <Typography variant="subtitle2" mt={3}>Export</Typography>
+        <Box display="flex" gap={1} alignItems="center" mt={1}>
+          <TextField
+            size="small"
+            fullWidth
+            label="File path (.yaml or .json)"
+            value={exportPath}
+            onChange={(e) => setExportPath(e.target.value)}
+          />
+          <Button variant="outlined" onClick={handleExport} disabled={busy || !exportPath.trim()}>
+            Export
+          </Button>
+        </Box>
+        <FormControlLabel
+          control={<Checkbox checked={includeCredentials} onChange={(e) => setIncludeCredentials(e.target.checked)} />}
+          label="Include passwords"
+        />
+      </DialogContent>
+      <DialogActions>
+        <Button onClick={onClose}>Close</Button>
+      </DialogActions>
+    </Dialog>
+  );
+}
@@ -1,12 +1,19 @@
-import React, { useState } from 'react';
+import React, { useCallback, useEffect, useState } from 'react';
 import type { Camera } from '../services/api';
-import { deleteCamera, syncCameraTime } from '../services/api';
-import { List, ListItem, ListItemText, Button, CircularProgress, Alert, Box, Stack, IconButton, Snackbar, Chip } from '@mui/material';
+import { deleteCamera, getArchivedCameras, restoreCamera, syncCameraTime, isAppMessage, reorderCameras, setFavorite } from '../services/api';
+import { localizeMessage } from '../services/messages';
+import { List, ListItem, ListItemText, Button, CircularProgress, Alert, Box, Stack, IconButton, Snackbar, Chip, Dialog, DialogTitle, DialogContent, DialogContentText, DialogActions, Typography } from '@mui/material';
 import DeleteIcon from '@mui/icons-material/Delete';
 import SyncIcon from '@mui/icons-material/Sync';
 import VideocamIcon from '@mui/icons-material/Videocam';
 import CableIcon from '@mui/icons-material/Cable';
 import UsbIcon from '@mui/icons-material/Usb';
+import StarIcon from '@mui/icons-material/Star';
+import StarBorderIcon from '@mui/icons-material/StarBorder';
+import ArrowUpwardIcon from '@mui/icons-material/ArrowUpward';
+import ArrowDownwardIcon from '@mui/icons-material/ArrowDownward';
+import FactCheckIcon from '@mui/icons-material/FactCheck';
+import CameraDiagnostics from './CameraDiagnostics';
 
 
 interface CameraListProps {
@@ -16,22 +23,96 @@ interface CameraListProps {
   activeCameraIds: number[];
   onSelectCamera: (camera: Camera) => void;
   onCameraDeleted: (id: number) => void; // Callback to refresh the list
+  onCameraRestored: () => void; // An archived camera is back in the list
+  onCamerasChanged: (cameras: Camera[]) => void; // Order or favorites changed
 }
 
-const CameraList: React.FC<CameraListProps> = ({ cameras, loading, error, activeCameraIds, onSelectCamera, onCameraDeleted }) => {
+const CameraList: React.FC<CameraListProps> = ({ cameras, loading, error, activeCameraIds, onSelectCamera, onCameraDeleted, onCameraRestored, onCamerasChanged }) => {
   const [syncingCameraId, setSyncingCameraId] = useState<number | null>(null);
+  const [diagnosedCamera, setDiagnosedCamera] = useState<Camera | null>(null);
+  const [deletingCamera, setDeletingCamera] = useState<Camera | null>(null);
+  const [deleteError, setDeleteError] = useState<string | null>(null);
+  const [deleteBusy, setDeleteBusy] = useState(false);
+  const [archivedCameras, setArchivedCameras] = useState<Camera[]>([]);
   const [snackbarOpen, setSnackbarOpen] = useState(false);
   const [snackbarMessage, setSnackbarMessage] = useState('');
 
-  const handleDelete = async (id: number) => {
-    if (window.confirm('Are you sure you want to delete this camera?')) {
-      try {
-        await deleteCamera(id);
-        onCameraDeleted(id); // Notify parent to refresh
-      } catch (err) {
-        console.error('Failed to delete camera', err);
-        alert('Failed to delete camera. See console for details.');
+  const loadArchivedCameras = useCallback(async () => {
+    try {
+      setArchivedCameras(await getArchivedCameras());
+    } catch (err) {
+      console.error('Failed to load archived cameras', err);
+    }
+  }, []);
+
+  useEffect(() => {
+    loadArchivedCameras();
+  }, [loadArchivedCameras]);
+
+  const openDeleteDialog = (camera: Camera) => {
+    setDeleteError(null);
+    setDeletingCamera(camera);
+  };
+
+  // Archiving keeps the camera's recordings browsable; deleting removes them too, except protected ones
+  const handleDelete = async (archive: boolean) => {
+    if (!deletingCamera) {
+      return;
+    }
+    const id = deletingCamera.id;
+    setDeleteBusy(true);
+    setDeleteError(null);
+    try {
+      await deleteCamera(id, { archive, deleteRecordings: !archive });
+      setDeletingCamera(null);
+      onCameraDeleted(id); // Notify parent to refresh
+      if (archive) {
+        loadArchivedCameras();
       }
+    } catch (err) {
+      console.error('Failed to delete camera', err);
+      setDeleteError(String(err));
+    } finally {
+      setDeleteBusy(false);
+    }
+  };
+
+  const handleRestore = async (camera: Camera) => {
+    try {
+      await restoreCamera(camera.id);
+      setArchivedCameras(prev => prev.filter(c => c.id !== camera.id));
+      onCameraRestored();
+      setSnackbarMessage(`Restored ${camera.name}`);
+      setSnackbarOpen(true);
+    } catch (err) {
+      console.error('Failed to restore camera', err);
+      setSnackbarMessage(`Error: ${String(err)}`);
+      setSnackbarOpen(true);
+    }
+  };
+
+  // Order and favorites are stored in the database, so the layout is the same on every machine
+  const handleMove = async (index: number, offset: number) => {
+    const target = index + offset;
+    if (target < 0 || target >= cameras.length) {
+      return;
+    }
+    const reordered = [...cameras];
+    [reordered[index], reordered[target]] = [reordered[target], reordered[index]];
+    try {
+      await reorderCameras(reordered.map(camera => camera.id));
+      onCamerasChanged(reordered.map((camera, position) => ({ ...camera, sort_order: position + 1 })));
+    } catch (err) {
+      console.error('Failed to reorder cameras', err);
+    }
+  };
+
+  const handleToggleFavorite = async (camera: Camera) => {
+    try {
+      await setFavorite(camera.id, !camera.is_favorite);
+      onCamerasChanged(cameras.map(c => (c.id === camera.id ? { ...c, is_favorite: !camera.is_favorite } : c)));
+    } catch (err) {
+      console.error('Failed to update favorite', err);
     }
   };
 
@@ -39,11 +120,14 @@ const CameraList: React.FC<CameraListProps> = ({ cameras, loading, error, active
     setSyncingCameraId(id);
     try {
       const result = await syncCameraTime(id);
-      setSnackbarMessage(result.message || 'Camera time synchronized successfully');
+      const restarted = result.streamRestarted
+        ? ` ${localizeMessage({ code: 'time_sync.stream_restarted', params: {}, message: 'The live stream stalled after the clock change and was restarted' })}`
+        : '';
+      setSnackbarMessage(localizeMessage(result) + restarted);
       setSnackbarOpen(true);
     } catch (err: any) {
       console.error('Failed to sync camera time', err);
-      const errorMessage = err.response?.data?.error || err.message || 'Failed to sync camera time';
+      const errorMessage = isAppMessage(err) ? localizeMessage(err) : String(err);
       setSnackbarMessage(`Error: ${errorMessage}`);
       setSnackbarOpen(true);
     } finally {
@@ -71,7 +155,7 @@ const CameraList: React.FC<CameraListProps> = ({ cameras, loading, error, active
             <ListItemText primary="No cameras found. Click 'Add Camera' to get started." />
           </ListItem>
         ) : (
-          cameras.map((camera) => {
+          cameras.map((camera, index) => {
             const isActive = activeCameraIds.includes(camera.id);
             const isOnvif = camera.type === 'onvif';
             const isUvc = camera.type === 'uvc';
@@ -91,6 +175,30 @@ const CameraList: React.FC<CameraListProps> = ({ cameras, loading, error, active
                 key={camera.id}
                 secondaryAction={
                   <Stack direction="row" spacing={1}>
+                    <IconButton
+                      aria-label="move up"
+                      onClick={() => handleMove(index, -1)}
+                      disabled={index === 0}
+                      title="Move up"
+                    >
+                      <ArrowUpwardIcon />
+                    </IconButton>
+                    <IconButton
+                      aria-label="move down"
+                      onClick={() => handleMove(index, 1)}
+                      disabled={index === cameras.length - 1}
+                      title="Move down"
+                    >
+                      <ArrowDownwardIcon />
+                    </IconButton>
+                    <IconButton
+                      aria-label="favorite"
+                      onClick={() => handleToggleFavorite(camera)}
+                      color={camera.is_favorite ? 'warning' : 'default'}
+                      title={camera.is_favorite ? 'Remove from favorites' : 'Add to favorites'}
+                    >
+                      {camera.is_favorite ? <StarIcon /> : <StarBorderIcon />}
+                    </IconButton>
                     <Button
                       variant="contained"
                       color={isActive ? "secondary" : "primary"}
@@ -114,10 +222,20 @@ const CameraList: React.FC<CameraListProps> = ({ cameras, loading, error, active
                         )}
                       </IconButton>
                     )}
+                    {isOnvif && (
+                      <IconButton
+                        edge="end"
+                        aria-label="diagnostics"
+                        onClick={() => setDiagnosedCamera(camera)}
+                        title="Test the camera's ONVIF operations"
+                      >
+                        <FactCheckIcon />
+                      </IconButton>
+                    )}
                     <IconButton
                       edge="end"
                       aria-label="delete"
-                      onClick={() => handleDelete(camera.id)}
+                      onClick={() => openDeleteDialog(camera)}
                       color="error"
                     >
                       <DeleteIcon />
@@ -142,12 +260,48 @@ const CameraList: React.FC<CameraListProps> = ({ cameras, loading, error, active
           })
         )}
       </List>
+      {archivedCameras.length > 0 && (
+        <>
+          <Typography variant="subtitle1" sx={{ mt: 2 }}>Archived cameras</Typography>
+          <List dense>
+            {archivedCameras.map(camera => (
+              <ListItem
+                key={camera.id}
+                secondaryAction={
+                  <Button size="small" onClick={() => handleRestore(camera)}>Restore</Button>
+                }
+              >
+                <ListItemText
+                  primary={camera.name}
+                  secondary={camera.archived_at ? `Archived ${new Date(camera.archived_at).toLocaleString()}` : undefined}
+                />
+              </ListItem>
+            ))}
+          </List>
+        </>
+      )}
+      <Dialog open={deletingCamera !== null} onClose={() => !deleteBusy && setDeletingCamera(null)} maxWidth="xs" fullWidth>
+        <DialogTitle>Delete {deletingCamera?.name}?</DialogTitle>
+        <DialogContent>
+          {deleteError && <Alert severity="error" sx={{ mb: 2 }}>{deleteError}</Alert>}
+          <DialogContentText>
+            Archive the camera to keep its recordings available; it can be restored later.
+            Deleting removes its recordings and snapshots as well. Protected recordings are never deleted.
+          </DialogContentText>
+        </DialogContent>
+        <DialogActions>
+          <Button onClick={() => setDeletingCamera(null)} disabled={deleteBusy}>Cancel</Button>
+          <Button onClick={() => handleDelete(true)} disabled={deleteBusy}>Archive</Button>
+          <Button onClick={() => handleDelete(false)} disabled={deleteBusy} color="error">Delete with recordings</Button>
+        </DialogActions>
+      </Dialog>
       <Snackbar
         open={snackbarOpen}
         autoHideDuration={4000}
         onClose={handleCloseSnackbar}
         message={snackbarMessage}
       />
+      <CameraDiagnostics camera={diagnosedCamera} onClose={() => setDiagnosedCamera(null)} />
     </Box>
   );
 };
@@ -1,4 +1,4 @@
-import React, { useState } from 'react';
+import React, { useEffect, useRef, useState } from 'react';
 import {
   Modal,
   Box,
@@ -14,8 +14,25 @@ import {
   DialogTitle,
   DialogContent,
   DialogActions,
+  FormControlLabel,
+  Checkbox,
 } from '@mui/material';
-import { discoverCameras, addCamera, type DiscoveredDevice, type Camera } from '../services/api';
+import { listen, type UnlistenFn } from '@tauri-apps/api/event';
+import {
+  startDiscovery,
+  cancelDiscovery,
+  probeOnvifDevice,
+  addCamera,
+  addCameraChannels,
+  type DiscoveredDevice,
+  type ProbedDevice,
+  type DeviceDiscoveredEvent,
+  type DiscoveryFinishedEvent,
+  type Camera,
+} from '../services/api';
+
+// Channels found by a manual probe; discovered devices aren't queried until they are added
+const channelCount = (device: DiscoveredDevice) => (device as ProbedDevice).channels?.length ?? 0;
 
 const modalStyle = {
   position: 'absolute' as 'absolute',
@@ -32,6 +49,19 @@ const modalStyle = {
   overflow: 'auto',
 };
 
+// Discovery entries are one per device: local devices by their device path/id/index,
+// network devices by host
+const isSameDevice = (a: DiscoveredDevice, b: DiscoveredDevice) => {
+  const isLocal = (d: DiscoveredDevice) => !d.host || d.host === 'localhost';
+  if (isLocal(a) || isLocal(b)) {
+    return a.camera_type === b.camera_type
+      && a.device_path === b.device_path
+      && a.device_id === b.device_id
+      && a.device_index === b.device_index;
+  }
+  return a.host.toLowerCase() === b.host.toLowerCase();
+};
+
 interface DiscoverCamerasModalProps {
   open: boolean;
   onClose: () => void;
@@ -41,37 +71,103 @@ interface DiscoverCamerasModalProps {
 
 const DiscoverCamerasModal: React.FC<DiscoverCamerasModalProps> = ({ open, onClose, onCameraAdded, registeredCameras }) => {
   const [isDiscovering, setIsDiscovering] = useState(false);
+  const [isCancelling, setIsCancelling] = useState(false);
   const [devices, setDevices] = useState<DiscoveredDevice[]>([]);
   const [error, setError] = useState<string | null>(null);
   const [selectedDevice, setSelectedDevice] = useState<DiscoveredDevice | null>(null);
   const [isAddDialogOpen, setIsAddDialogOpen] = useState(false);
   const [credentials, setCredentials] = useState({ user: '', pass: '' });
   const [isAdding, setIsAdding] = useState(false);
+  const [addAllChannels, setAddAllChannels] = useState(false);
   const [addError, setAddError] = useState<string | null>(null);
+  const [probeTarget, setProbeTarget] = useState({ host: '', port: '80', user: '', pass: '' });
+  const [isProbing, setIsProbing] = useState(false);
+  const [probeError, setProbeError] = useState<string | null>(null);
+
+  const scanIdRef = useRef<string | null>(null);
+
+  // Subscribe to discovery events for as long as the modal is mounted
+  useEffect(() => {
+    const unlisteners: Promise<UnlistenFn>[] = [
+      listen<DeviceDiscoveredEvent>('device-discovered', (event) => {
+        if (event.payload.scanId !== scanIdRef.current) return;
+        const camera = event.payload.camera;
+        // Updates for a device already listed replace its entry
+        setDevices(prev => {
+          const index = prev.findIndex(d => isSameDevice(d, camera));
+          if (index === -1) return [...prev, camera];
+          const next = [...prev];
+          next[index] = camera;
+          return next;
+        });
+      }),
+      listen<DiscoveryFinishedEvent>('discovery-finished', (event) => {
+        if (event.payload.scanId !== scanIdRef.current) return;
+        scanIdRef.current = null;
+        setIsDiscovering(false);
+        setIsCancelling(false);
+        if (event.payload.found === 0 && !event.payload.cancelled) {
+          setError('No ONVIF cameras found on the network. If you know the camera IP address, use "Add Camera" instead.');
+        }
+      }),
+    ];
+
+    return () => {
+      unlisteners.forEach(p => p.then(unlisten => unlisten()));
+    };
+  }, []);
 
   const handleDiscover = async () => {
     setIsDiscovering(true);
+    setIsCancelling(false);
     setError(null);
     setDevices([]);
 
     try {
-      const discoveredDevices = await discoverCameras();
-      setDevices(discoveredDevices);
-      if (discoveredDevices.length === 0) {
-        setError('No ONVIF cameras found on the network. If you know the camera IP address, use "Add Camera" instead.');
-      }
+      scanIdRef.current = await startDiscovery();
     } catch (err: any) {
       console.error('Discovery error:', err);
       const errorMsg = err.response?.data?.message || err.message || 'Failed to discover cameras. Please check the backend server.';
       setError(errorMsg);
-    } finally {
       setIsDiscovering(false);
     }
   };
 
+  const handleCancelDiscovery = async () => {
+    if (!scanIdRef.current) return;
+    setIsCancelling(true);
+    try {
+      await cancelDiscovery(scanIdRef.current);
+    } catch (err) {
+      // The scan may have finished in the meantime
+      console.warn('Cancel discovery error:', err);
+    }
+  };
+
+  const handleProbe = async () => {
+    setIsProbing(true);
+    setProbeError(null);
+
+    try {
+      const device = await probeOnvifDevice(
+        probeTarget.host,
+        parseInt(probeTarget.port, 10) || 80,
+        probeTarget.user || undefined,
+        probeTarget.pass || undefined
+      );
+      setDevices(prev => [device, ...prev.filter(d => d.host !== device.host || d.port !== device.port)]);
+    } catch (err: any) {
+      console.error('Probe error:', err);
+      setProbeError(typeof err === 'string' ? err : err.message || 'Failed to probe device.');
+    } finally {
+      setIsProbing(false);
+    }
+  };
+
   const handleSelectDevice = (device: DiscoveredDevice) => {
     setSelectedDevice(device);
-    setCredentials({ user: '', pass: '' });
+    setCredentials({ user: device.user || '', pass: device.pass || '' });
+    setAddAllChannels(channelCount(device) > 1);
     setAddError(null);
     setIsAddDialogOpen(true);
   };
@@ -90,6 +186,7 @@ const DiscoverCamerasModal: React.FC<DiscoverCamerasModalProps> = ({ open, onClo
         port: selectedDevice.port,
         user: credentials.user || undefined,
         pass: credentials.pass || undefined,
+        xaddr: selectedDevice.xaddr || undefined,
       };
 
       // Add type-specific fields
@@ -106,7 +203,11 @@ const DiscoverCamerasModal: React.FC<DiscoverCamerasModalProps> = ({ open, onClo
       console.log('[DiscoverCamerasModal] Adding camera:', JSON.stringify(newCamera, null, 2));
       console.log('[DiscoverCamerasModal] Selected device:', JSON.stringify(selectedDevice, null, 2));
 
-      await addCamera(newCamera);
+      if (selectedDevice.camera_type === 'onvif' && addAllChannels) {
+        await addCameraChannels(newCamera);
+      } else {
+        await addCamera(newCamera);
+      }
 
       // Success
       setIsAddDialogOpen(false);
@@ -130,6 +231,10 @@ const DiscoverCamerasModal: React.FC<DiscoverCamerasModalProps> = ({ open, onClo
   };
 
   const handleModalClose = () => {
+    if (scanIdRef.current) {
+      cancelDiscovery(scanIdRef.current).catch(() => {});
+      scanIdRef.current = null;
+    }
     setDevices([]);
     setError(null);
     setIsDiscovering(false);
@@ -150,24 +255,36 @@ const DiscoverCamerasModal: React.FC<DiscoverCamerasModalProps> = ({ open, onClo
             This scan uses unicast probes to detect cameras that don't respond to multicast discovery.
           </Alert>
 
-          <Button
-            variant="contained"
-            onClick={handleDiscover}
-            disabled={isDiscovering}
-            fullWidth
-            sx={{ mb: 2 }}
-          >
-            {isDiscovering ? 'Scanning Network...' : 'Start Discovery'}
-          </Button>
+          {isDiscovering ? (
+            <Button
+              variant="outlined"
+              color="warning"
+              onClick={handleCancelDiscovery}
+              disabled={isCancelling}
+              fullWidth
+              sx={{ mb: 2 }}
+            >
+              {isCancelling ? 'Stopping...' : 'Stop Discovery'}
+            </Button>
+          ) : (
+            <Button
+              variant="contained"
+              onClick={handleDiscover}
+              fullWidth
+              sx={{ mb: 2 }}
+            >
+              Start Discovery
+            </Button>
+          )}
 
           {isDiscovering && (
             <Box sx={{ display: 'flex', flexDirection: 'column', alignItems: 'center', my: 2 }}>
               <CircularProgress />
               <Typography variant="body2" sx={{ mt: 2 }}>
-                Scanning network... This may take 2-3 minutes.
+                Scanning network... Cameras appear below as they respond.
               </Typography>
               <Typography variant="caption" color="text.secondary">
-                Please be patient while we probe each IP address.
+                You can stop the scan at any time.
               </Typography>
             </Box>
           )}
@@ -178,6 +295,57 @@ const DiscoverCamerasModal: React.FC<DiscoverCamerasModalProps> = ({ open, onClo
             </Alert>
           )}
 
+          <Typography variant="subtitle2" sx={{ mt: 2, mb: 1 }}>
+            Camera on another network? Probe it by address:
+          </Typography>
+          <Box sx={{ display: 'flex', gap: 1, mb: 1 }}>
+            <TextField
+              label="IP / Hostname"
+              size="small"
+              value={probeTarget.host}
+              onChange={(e) => setProbeTarget({ ...probeTarget, host: e.target.value })}
+              disabled={isProbing}
+              sx={{ flex: 2 }}
+            />
+            <TextField
+              label="Port"
+              size="small"
+              value={probeTarget.port}
+              onChange={(e) => setProbeTarget({ ...probeTarget, port: e.target.value })}
+              disabled={isProbing}
+              sx={{ flex: 1 }}
+            />
+            <TextField
+              label="Username"
+              size="small"
+              value={probeTarget.user}
+              onChange={(e) => setProbeTarget({ ...probeTarget, user: e.target.value })}
+              disabled={isProbing}
+              sx={{ flex: 1 }}
+            />
+            <TextField
+              label="Password"
+              type="password"
+              size="small"
+              value={probeTarget.pass}
+              onChange={(e) => setProbeTarget({ ...probeTarget, pass: e.target.value })}
+              disabled={isProbing}
+              sx={{ flex: 1 }}
+            />
+            <Button
+              variant="outlined"
+              onClick={handleProbe}
+              disabled={isProbing || !probeTarget.host.trim()}
+            >
+              {isProbing ? <CircularProgress size={20} /> : 'Probe'}
+            </Button>
+          </Box>
+          {probeError && (
+            <Alert severity="error" sx={{ mb: 2 }}>
+              {probeError}
+            </Alert>
+          )}
+
           {devices.length > 0 && (
             <>
               <Typography variant="subtitle1" sx={{ mt: 2, mb: 1 }}>
@@ -185,7 +353,8 @@ const DiscoverCamerasModal: React.FC<DiscoverCamerasModalProps> = ({ open, onClo
               </Typography>
               <List sx={{ bgcolor: 'background.paper', border: '1px solid #ccc', borderRadius: 1 }}>
                 {devices.map((device, index) => {
-                  const isRegistered = registeredCameras.some(c => c.host === device.host && c.name === device.name);
+                  const isRegistered = device.existing_camera_id != null
+                    || registeredCameras.some(c => c.host === device.host && c.name === device.name);
                   const displayInfo = device.camera_type === 'uvc'
                     ? `${device.device_path || device.device_id || `Index: ${device.device_index}`}`
                     : `${device.host}:${device.port}`;
@@ -204,9 +373,17 @@ const DiscoverCamerasModal: React.FC<DiscoverCamerasModalProps> = ({ open, onClo
                         )
                       }
                     >
+                      {device.thumbnail && (
+                        <Box
+                          component="img"
+                          src={device.thumbnail}
+                          alt=""
+                          sx={{ width: 96, height: 54, objectFit: 'cover', borderRadius: 1, mr: 2, flexShrink: 0 }}
+                        />
+                      )}
                       <ListItemText
-                        primary={`${device.name} [${device.camera_type.toUpperCase()}]`}
-                        secondary={displayInfo}
+                        primary={`${device.name} [${(device.sources && device.sources.length > 0 ? device.sources : [device.camera_type]).map(s => s.toUpperCase()).join(' / ')}]`}
+                        secondary={device.mac ? `${displayInfo} · ${device.mac}` : displayInfo}
                       />
                     </ListItem>
                   );
@@ -261,6 +438,18 @@ const DiscoverCamerasModal: React.FC<DiscoverCamerasModalProps> = ({ open, onClo
                     onChange={(e) => setCredentials({ ...credentials, pass: e.target.value })}
                     disabled={isAdding}
                   />
+                  <FormControlLabel
+                    control={
+                      <Checkbox
+                        checked={addAllChannels}
+                        onChange={(e) => setAddAllChannels(e.target.checked)}
+                        disabled={isAdding}
+                      />
+                    }
+                    label={channelCount(selectedDevice) > 1
+                      ? `Add all ${channelCount(selectedDevice)} channels as separate cameras`
+                      : 'Add each video channel as a separate camera (NVRs, encoders)'}
+                  />
                 </>
               )}
 
@@ -16,14 +16,28 @@ import {
   Typography,
   Chip,
 } from '@mui/material';
+import { listen } from '@tauri-apps/api/event';
 import {
   detectGpu,
   getEncoderSettings,
+  getResourceUsage,
+  getLoadBudget,
+  getAppSettings,
+  getQualityPresets,
+  updateAppSettings,
   updateEncoderSettings,
   GpuCapabilities,
   EncoderSettings as EncoderSettingsType,
+  QualityPreset,
+  QualityPresetName,
+  ResourceUsage,
+  LoadBudgetStatus,
 } from '../services/api';
 
+const formatMegabytes = (bytes: number) => `${Math.round(bytes / 1024 ** 2)} MB`;
+
+const percent = (value: number | null) => (value === null ? '-' : `${Math.round(value)}%`);
+
 interface EncoderSettingsProps {
   open: boolean;
   onClose: () => void;
@@ -36,6 +50,11 @@ export default function EncoderSettings({ open, onClose }: EncoderSettingsProps)
   const [settings, setSettings] = useState<EncoderSettingsType | null>(null);
   const [error, setError] = useState<string | null>(null);
   const [successMessage, setSuccessMessage] = useState<string | null>(null);
+  const [usage, setUsage] = useState<ResourceUsage | null>(null);
+  const [qualityPresets, setQualityPresets] = useState<QualityPreset[]>([]);
+  const [qualityPreset, setQualityPreset] = useState<QualityPresetName>('custom');
+  const [loadBudget, setLoadBudget] = useState<number>(0);
+  const [budgetStatus, setBudgetStatus] = useState<LoadBudgetStatus | null>(null);
 
   useEffect(() => {
     if (open) {
@@ -43,16 +62,35 @@ export default function EncoderSettings({ open, onClose }: EncoderSettingsProps)
     }
   }, [open]);
 
+  // 負荷は開いている間だけ resource-usage イベントで更新する
+  useEffect(() => {
+    if (!open) return;
+    getResourceUsage().then(setUsage).catch((err) => console.error('Failed to get resource usage:', err));
+    const unlisten = listen<ResourceUsage>('resource-usage', (event) => {
+      setUsage(event.payload);
+      getLoadBudget().then(setBudgetStatus).catch((err) => console.error('Failed to get load budget:', err));
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, [open]);
+
   const loadData = async () => {
     setLoading(true);
     setError(null);
     try {
-      const [caps, sets] = await Promise.all([
+      const [caps, sets, presets, appSettings] = await Promise.all([
         detectGpu(),
         getEncoderSettings(),
+        getQualityPresets(),
+        getAppSettings(),
       ]);
       setCapabilities(caps);
       setSettings(sets);
+      setQualityPresets(presets);
+      setQualityPreset(appSettings['encoder.qualityPreset']);
+      setLoadBudget(appSettings['encoder.loadBudget']);
+      getLoadBudget().then(setBudgetStatus).catch((err) => console.error('Failed to get load budget:', err));
     } catch (err: any) {
       setError(`Failed to load settings: ${err}`);
     } finally {
@@ -75,6 +113,7 @@ export default function EncoderSettings({ open, onClose }: EncoderSettingsProps)
         preset: settings.preset,
         quality: settings.quality,
       });
+      await updateAppSettings({ 'encoder.qualityPreset': qualityPreset, 'encoder.loadBudget': loadBudget });
       setSettings(updated);
       setSuccessMessage('設定を保存しました');
 
@@ -148,8 +187,77 @@ export default function EncoderSettings({ open, onClose }: EncoderSettingsProps)
           )}
         </Box>
 
+        {/* リソース使用状況 */}
+        {usage && (
+          <Box mb={3}>
+            <Typography variant="subtitle2" gutterBottom>
+              リソース使用状況
+            </Typography>
+            {usage.warnings.map((warning) => (
+              <Alert key={warning} severity="warning" sx={{ mb: 1 }}>
+                {warning}
+              </Alert>
+            ))}
+            <Typography variant="body2">
+              CPU {percent(usage.cpu_percent)} ({usage.cpu_count}コア) / メモリ {formatMegabytes(usage.memory_used_bytes)} / {formatMegabytes(usage.memory_total_bytes)}
+            </Typography>
+            {usage.gpus.map((gpu) => (
+              <Typography key={gpu.index} variant="body2">
+                GPU {gpu.index} ({gpu.name ?? gpu.vendor}): 使用率 {percent(gpu.utilization_percent)}
+                , エンコーダー {percent(gpu.encoder_percent)}
+                {gpu.decoder_percent !== null && `, デコーダー ${percent(gpu.decoder_percent)}`}
+                {gpu.temperature_c !== null && `, ${Math.round(gpu.temperature_c)}°C`}
+              </Typography>
+            ))}
+            <Typography variant="caption" color="text.secondary">
+              FFmpeg {usage.processes.length}プロセス: CPU {percent(usage.ffmpeg_cpu_percent)} (1コア=100%), メモリ {formatMegabytes(usage.ffmpeg_memory_bytes)}
+            </Typography>
+          </Box>
+        )}
+
         {settings && (
           <>
+            {/* 品質プリセット: 選択中はビットレート・GOP・プリセット・品質・音声を上書きする */}
+            <FormControl fullWidth margin="normal">
+              <InputLabel>品質プリセット</InputLabel>
+              <Select
+                value={qualityPreset}
+                label="品質プリセット"
+                onChange={(e) => setQualityPreset(e.target.value as QualityPresetName)}
+              >
+                <MenuItem value="custom">カスタム (以下の設定を使用)</MenuItem>
+                {qualityPresets.map((preset) => (
+                  <MenuItem key={preset.name} value={preset.name}>
+                    {preset.label}
+                  </MenuItem>
+                ))}
+              </Select>
+            </FormControl>
+            {qualityPresets
+              .filter((preset) => preset.name === qualityPreset)
+              .map((preset) => (
+                <Typography key={preset.name} variant="caption" color="text.secondary">
+                  {preset.description} (セグメント {preset.segment_secs}秒, ライブ {preset.stream_kbps} kbps, 録画 {preset.record_kbps} kbps)
+                </Typography>
+              ))}
+
+            {/* 負荷バジェット: 超えると優先度の低いライブ配信からフレームレート・解像度を下げる */}
+            <TextField
+              fullWidth
+              margin="normal"
+              type="number"
+              label="エンコード負荷バジェット (Mpx/秒, 0で無制限)"
+              value={loadBudget}
+              onChange={(e) => setLoadBudget(Math.max(0, Number(e.target.value) || 0))}
+              inputProps={{ min: 0, max: 100000 }}
+              helperText={
+                budgetStatus
+                  ? `現在の負荷 ${budgetStatus.load.toFixed(1)} Mpx/秒` +
+                    `, 品質を下げた配信 ${budgetStatus.cameras.filter((camera) => camera.streamDowngrade > 0).length}件`
+                  : undefined
+              }
+            />
+
             {/* エンコーダーモード選択 */}
             <FormControl fullWidth margin="normal">
               <InputLabel>エンコーダーモード</InputLabel>
@@ -0,0 +1,79 @@
+import React, { useState } from 'react';
+import { Modal, Box, Typography, TextField, Button, CircularProgress, Alert } from '@mui/material';
+import { login, type AuthSession } from '../services/api';
+
+const modalStyle = {
+  position: 'absolute' as 'absolute',
+  top: '50%',
+  left: '50%',
+  transform: 'translate(-50%, -50%)',
+  width: 360,
+  bgcolor: 'background.paper',
+  border: '2px solid #000',
+  boxShadow: 24,
+  p: 4,
+};
+
+interface LoginDialogProps {
+  open: boolean;
+  onLoggedIn: (session: AuthSession) => void;
+}
+
+// Shown instead of the camera view while the app requires login
+const LoginDialog: React.FC<LoginDialogProps> = ({ open, onLoggedIn }) => {
+  const [username, setUsername] = useState('');
+  const [password, setPassword] = useState('');
+  const [loading, setLoading] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+
+  const handleSubmit = async (event: React.FormEvent) => {
+    event.preventDefault();
+    setError(null);
+    setLoading(true);
+    try {
+      const session = await login(username, password);
+      setPassword('');
+      onLoggedIn(session);
+    } catch (err) {
+      setError(String(err));
+    } finally {
+      setLoading(false);
+    }
+  };
+
+  return (
+    <Modal open={open}>
+      <Box sx={modalStyle} component="form" onSubmit={handleSubmit}>
+        <Typography variant="h6" component="h2" gutterBottom>
+          Log in
+        </Typography>
+        {error && <Alert severity="error" sx={{ mb: 2 }}>{error}</Alert>}
+        <TextField
+          label="Username"
+          value={username}
+          onChange={(e) => setUsername(e.target.value)}
+          fullWidth
+          margin="normal"
+          autoFocus
+          required
+        />
+        <TextField
+          label="Password"
+          type="password"
+          value={password}
+          onChange={(e) => setPassword(e.target.value)}
+          fullWidth
+          margin="normal"
+          required
+        />
+        <Box sx={{ mt: 2, display: 'flex', justifyContent: 'flex-end' }}>
+          <Button type="submit" variant="contained" disabled={loading || !username || !password}>
+            {loading ? <CircularProgress size={24} /> : 'Log in'}
+          </Button>
+        </Box>
+      </Box>
+    </Modal>
+  );
+};
+
+export default LoginDialog;
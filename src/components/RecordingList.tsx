@@ -1,27 +1,49 @@
 import React, { useEffect, useState } from 'react';
-import { getRecordings, deleteRecording, type Recording } from '../services/api';
+import { openUrl } from '@tauri-apps/plugin-opener';
+import { getRecordingSessions, deleteRecordingSession, setRecordingProtected, repairRecording, createPreview, getRecordingDownloadUrl, getRecordingFileUrl, getServerToken, type RecordingSession } from '../services/api';
 import {
     Box, Card, CardMedia, CardContent, CardActions,
     Button, CircularProgress, Alert, Typography, IconButton
 } from '@mui/material';
 import DeleteIcon from '@mui/icons-material/Delete';
+import DownloadIcon from '@mui/icons-material/Download';
+import GifBoxIcon from '@mui/icons-material/GifBox';
 import PlayArrowIcon from '@mui/icons-material/PlayArrow';
+import LockIcon from '@mui/icons-material/Lock';
+import LockOpenIcon from '@mui/icons-material/LockOpen';
 
 interface RecordingListProps {
     listVersion: number;
-    onPlayRecording: (filename: string) => void;
+    onPlayRecording: (recording: RecordingSession) => void;
 }
 
+const formatDuration = (secs: number) => {
+    const total = Math.round(secs);
+    const h = Math.floor(total / 3600);
+    const m = Math.floor((total % 3600) / 60);
+    const s = total % 60;
+    return h > 0 ? `${h}h ${m}m` : m > 0 ? `${m}m ${s}s` : `${s}s`;
+};
+
+// One card per recording session; a paused and resumed recording shows as one entry
 const RecordingList: React.FC<RecordingListProps> = ({ listVersion, onPlayRecording }) => {
-    const [recordings, setRecordings] = useState<Recording[]>([]);
+    const [recordings, setRecordings] = useState<RecordingSession[]>([]);
     const [loading, setLoading] = useState<boolean>(true);
     const [error, setError] = useState<string | null>(null);
+    const [hoveredId, setHoveredId] = useState<number | null>(null);
+    const [serverToken, setServerToken] = useState<string | null>(null);
+
+    useEffect(() => {
+        getServerToken()
+            .then(setServerToken)
+            .catch(err => console.error('[RecordingList] Failed to get the server token:', err));
+    }, []);
 
     const fetchRecordings = async () => {
         try {
             if (import.meta.env.DEV) console.log('[RecordingList] Fetching recordings...');
             setLoading(true);
-            const data = await getRecordings();
+            const data = await getRecordingSessions();
             if (import.meta.env.DEV) console.log(`[RecordingList] Fetched ${data.length} recordings:`, data);
             setRecordings(data);
             setError(null);
@@ -38,10 +60,14 @@ const RecordingList: React.FC<RecordingListProps> = ({ listVersion, onPlayRecord
         fetchRecordings();
     }, [listVersion]);
 
-    const handleDelete = async (id: number, filename: string) => {
-        if (window.confirm(`Are you sure you want to delete recording "${filename}"?`)) {
+    const handleDelete = async (rec: RecordingSession) => {
+        const name = rec.segments[0]?.filename ?? `#${rec.id}`;
+        const message = rec.is_protected
+            ? `Recording "${name}" is protected. Delete it anyway?`
+            : `Are you sure you want to delete recording "${name}"${rec.segment_count > 1 ? ` and its ${rec.segment_count} segments` : ''}?`;
+        if (window.confirm(message)) {
             try {
-                await deleteRecording(id);
+                await deleteRecordingSession(rec.id, rec.is_protected);
                 // Refresh the recordings list
                 await fetchRecordings();
             } catch (err) {
@@ -51,6 +77,51 @@ const RecordingList: React.FC<RecordingListProps> = ({ listVersion, onPlayRecord
         }
     };
 
+    const handleToggleProtected = async (rec: RecordingSession) => {
+        try {
+            await Promise.all(rec.segments.map(segment => setRecordingProtected(segment.id, !rec.is_protected)));
+            setRecordings(prev => prev.map(r => r.id === rec.id ? { ...r, is_protected: !rec.is_protected } : r));
+        } catch (err) {
+            console.error('Failed to update recording protection', err);
+            alert('Failed to update recording protection. See console for details.');
+        }
+    };
+
+    // Sessions of several segments are downloaded segment by segment
+    const handleDownload = async (rec: RecordingSession) => {
+        try {
+            // Let the system browser handle the download and save dialog
+            for (const segment of rec.segments) {
+                await openUrl(await getRecordingDownloadUrl(segment.id));
+            }
+        } catch (err) {
+            console.error('Failed to download recording', err);
+            alert('Failed to download recording. See console for details.');
+        }
+    };
+
+    // The preview shows up on hover once the job is done and the list is refreshed
+    const handleCreatePreview = async (rec: RecordingSession) => {
+        try {
+            await createPreview(rec.segments[0].id);
+        } catch (err) {
+            console.error('Failed to create preview', err);
+            alert(`Failed to create preview: ${err}`);
+        }
+    };
+
+    const handleRepair = async (rec: RecordingSession) => {
+        try {
+            for (const segment of rec.segments.filter(s => s.integrity === 'corrupt')) {
+                await repairRecording(segment.id);
+            }
+            await fetchRecordings();
+        } catch (err) {
+            console.error('Failed to repair recording', err);
+            alert(`Failed to repair recording: ${err}`);
+        }
+    };
+
     if (loading) {
         return <CircularProgress />;
     }
@@ -59,8 +130,6 @@ const RecordingList: React.FC<RecordingListProps> = ({ listVersion, onPlayRecord
         return <Alert severity="error">{error}</Alert>;
     }
 
-    const BACKEND_URL = 'http://localhost:3333';
-
     return (
         <Box sx={{ mt: 4 }}>
             <Typography variant="h4" component="h2" gutterBottom>
@@ -77,53 +146,126 @@ const RecordingList: React.FC<RecordingListProps> = ({ listVersion, onPlayRecord
                         mt: 2,
                     }}
                 >
-                    {recordings.map((rec) => (
-                        <Card key={rec.id} sx={{ display: 'flex', flexDirection: 'column' }}>
+                    {recordings.map((rec) => {
+                        const first = rec.segments[0];
+                        const integrity = rec.segments.some(s => s.integrity === 'corrupt') ? 'corrupt'
+                            : rec.segments.some(s => s.integrity === 'missing') ? 'missing' : null;
+                        return (
+                        <Card
+                            key={rec.id}
+                            sx={{ display: 'flex', flexDirection: 'column' }}
+                            onMouseEnter={() => setHoveredId(rec.id)}
+                            onMouseLeave={() => setHoveredId(null)}
+                        >
                             <CardMedia
                                 component="img"
                                 height="180"
                                 image={
-                                    rec.thumbnail
-                                        ? `${BACKEND_URL}/recordings/thumbnails/${rec.thumbnail}`
+                                    serverToken && hoveredId === rec.id && first?.preview
+                                        ? getRecordingFileUrl(`thumbnails/${first.preview}`, serverToken)
+                                        : serverToken && first?.thumbnail
+                                        ? getRecordingFileUrl(`thumbnails/${first.thumbnail}`, serverToken)
                                         : 'data:image/svg+xml,%3Csvg xmlns="http://www.w3.org/2000/svg" width="320" height="180"%3E%3Crect fill="%23ddd" width="320" height="180"/%3E%3Ctext fill="%23999" x="50%25" y="50%25" dominant-baseline="middle" text-anchor="middle" font-family="sans-serif" font-size="18"%3ENo Thumbnail%3C/text%3E%3C/svg%3E'
                                 }
-                                alt={rec.filename}
+                                alt={first?.filename}
                                 sx={{ objectFit: 'cover' }}
                             />
                             <CardContent sx={{ flexGrow: 1, pb: 1 }}>
                                 <Typography variant="h6" component="div" noWrap title={rec.camera_name}>
                                     {rec.camera_name}
                                 </Typography>
-                                <Typography variant="body2" color="text.secondary" noWrap title={rec.filename}>
-                                    {rec.filename}
+                                <Typography variant="body2" color="text.secondary" noWrap title={first?.filename}>
+                                    {first?.filename}
                                 </Typography>
                                 <Typography variant="caption" color="text.secondary" display="block">
-                                    Start: {new Date(rec.start_time).toLocaleString()}
+                                    Start: {new Date(rec.started_at).toLocaleString()}
                                 </Typography>
                                 <Typography variant="caption" color="text.secondary" display="block">
-                                    End: {new Date(rec.end_time).toLocaleString()}
+                                    End: {rec.ended_at ? new Date(rec.ended_at).toLocaleString() : 'Recording'}
                                 </Typography>
+                                <Typography variant="caption" color="text.secondary" display="block">
+                                    {formatDuration(rec.total_duration_secs)}
+                                    {rec.segment_count > 1 && ` in ${rec.segment_count} segments`}
+                                </Typography>
+                                {rec.segments.some(s => s.failure) && (
+                                    <Typography
+                                        variant="caption"
+                                        color="error"
+                                        display="block"
+                                        title={rec.segments.map(s => s.failure).filter(Boolean).join('\n')}
+                                    >
+                                        Recording failed: nothing was captured
+                                    </Typography>
+                                )}
+                                {integrity && (
+                                    <Typography
+                                        variant="caption"
+                                        color="error"
+                                        display="block"
+                                        title={rec.segments.map(s => s.integrity_detail).filter(Boolean).join('\n') || undefined}
+                                    >
+                                        {integrity === 'missing' ? 'File missing' : 'File damaged'}
+                                        {integrity === 'corrupt' && (
+                                            <Button size="small" color="error" onClick={() => handleRepair(rec)} sx={{ ml: 1, py: 0 }}>
+                                                Repair
+                                            </Button>
+                                        )}
+                                    </Typography>
+                                )}
                             </CardContent>
                             <CardActions sx={{ justifyContent: 'space-between', pt: 0 }}>
                                 <Button
                                     size="small"
                                     variant="contained"
                                     startIcon={<PlayArrowIcon />}
-                                    onClick={() => onPlayRecording(rec.filename)}
+                                    onClick={() => onPlayRecording(rec)}
+                                    disabled={rec.segments.every(s => s.failure)}
                                 >
                                     Play
                                 </Button>
-                                <IconButton
-                                    size="small"
-                                    aria-label="delete"
-                                    onClick={() => handleDelete(rec.id, rec.filename)}
-                                    color="error"
-                                >
-                                    <DeleteIcon />
-                                </IconButton>
+                                <Box>
+                                    <IconButton
+                                        size="small"
+                                        aria-label="download"
+                                        title="Download"
+                                        onClick={() => handleDownload(rec)}
+                                        disabled={rec.segments.every(s => s.failure)}
+                                    >
+                                        <DownloadIcon />
+                                    </IconButton>
+                                    {!first?.preview && (
+                                        <IconButton
+                                            size="small"
+                                            aria-label="create preview"
+                                            title="Create animated preview"
+                                            onClick={() => handleCreatePreview(rec)}
+                                            disabled={!rec.ended_at || rec.segments.every(s => s.failure)}
+                                        >
+                                            <GifBoxIcon />
+                                        </IconButton>
+                                    )}
+                                    <IconButton
+                                        size="small"
+                                        aria-label={rec.is_protected ? 'unprotect' : 'protect'}
+                                        title={rec.is_protected ? 'Protected from automatic cleanup' : 'Protect from automatic cleanup'}
+                                        onClick={() => handleToggleProtected(rec)}
+                                        color={rec.is_protected ? 'warning' : 'default'}
+                                    >
+                                        {rec.is_protected ? <LockIcon /> : <LockOpenIcon />}
+                                    </IconButton>
+                                    <IconButton
+                                        size="small"
+                                        aria-label="delete"
+                                        onClick={() => handleDelete(rec)}
+                                        color="error"
+                                    >
+                                        <DeleteIcon />
+                                    </IconButton>
+                                </Box>
                             </CardActions>
                         </Card>
-                    ))}
+                        );
+                    })}
                 </Box>
             )}
         </Box>
@@ -21,24 +21,38 @@ import {
   MenuItem,
   FormControlLabel,
   Checkbox,
+  ListSubheader,
 } from '@mui/material';
 import DeleteIcon from '@mui/icons-material/Delete';
 import EditIcon from '@mui/icons-material/Edit';
 import AddIcon from '@mui/icons-material/Add';
 import ScheduleIcon from '@mui/icons-material/Schedule';
+import PauseIcon from '@mui/icons-material/Pause';
+import PlayArrowIcon from '@mui/icons-material/PlayArrow';
 import {
   getRecordingSchedules,
   addRecordingSchedule,
   updateRecordingSchedule,
+  addSnapshotSchedule,
+  updateSnapshotSchedule,
   deleteRecordingSchedule,
   toggleSchedule,
   getCameras,
   getRecordingCameras,
+  getAppSettings,
   stopRecording,
+  pauseSchedules,
+  resumeSchedules,
+  getCameraGroups,
+  addCameraGroup,
+  deleteCameraGroup,
+  type CameraGroup,
   type RecordingSchedule,
   type Camera,
   type NewRecordingSchedule,
   type UpdateRecordingSchedule,
+  type ScheduleAction,
+  type WeeklyBlock,
 } from '../services/api';
 import CronExpressionBuilder from './CronExpressionBuilder';
 
@@ -49,18 +63,26 @@ interface ScheduleRecordingProps {
 export default function ScheduleRecording({ onScheduleChanged }: ScheduleRecordingProps) {
   const [schedules, setSchedules] = useState<RecordingSchedule[]>([]);
   const [cameras, setCameras] = useState<Camera[]>([]);
+  const [groups, setGroups] = useState<CameraGroup[]>([]);
+  const [isGroupDialogOpen, setIsGroupDialogOpen] = useState(false);
+  const [groupName, setGroupName] = useState('');
+  const [groupCameraIds, setGroupCameraIds] = useState<number[]>([]);
   const [loading, setLoading] = useState(true);
   const [error, setError] = useState<string | null>(null);
   const [isDialogOpen, setIsDialogOpen] = useState(false);
   const [editingSchedule, setEditingSchedule] = useState<RecordingSchedule | null>(null);
   const [recordingCameraIds, setRecordingCameraIds] = useState<number[]>([]);
+  // Zone the schedules run in (general.timezone)
+  const [timeZone, setTimeZone] = useState('Asia/Tokyo');
 
-  // Form state
-  const [selectedCameraId, setSelectedCameraId] = useState<number | ''>('');
+  // Form state; the target is "camera:<id>" or "group:<id>"
+  const [selectedTarget, setSelectedTarget] = useState('');
   const [scheduleName, setScheduleName] = useState('');
   const [cronExpression, setCronExpression] = useState('0 9 * * *');
   const [durationMinutes, setDurationMinutes] = useState(30);
   const [fps, setFps] = useState<number | ''>('');
+  const [action, setAction] = useState<ScheduleAction>('record');
+  const [retentionDays, setRetentionDays] = useState<number | ''>('');
   const [isEnabled, setIsEnabled] = useState(true);
   const [formError, setFormError] = useState<string | null>(null);
 
@@ -84,12 +106,16 @@ export default function ScheduleRecording({ onScheduleChanged }: ScheduleRecordi
     setLoading(true);
     setError(null);
     try {
-      const [schedulesData, camerasData] = await Promise.all([
+      const [schedulesData, camerasData, groupsData, appSettings] = await Promise.all([
         getRecordingSchedules(),
         getCameras(),
+        getCameraGroups(),
+        getAppSettings(),
       ]);
       setSchedules(schedulesData);
       setCameras(camerasData);
+      setGroups(groupsData);
+      setTimeZone(appSettings['general.timezone']);
     } catch (err: any) {
       setError(`Failed to load data: ${err}`);
       console.error('Failed to load schedules:', err);
@@ -101,11 +127,13 @@ export default function ScheduleRecording({ onScheduleChanged }: ScheduleRecordi
   const handleOpenDialog = (schedule?: RecordingSchedule) => {
     if (schedule) {
       setEditingSchedule(schedule);
-      setSelectedCameraId(schedule.camera_id);
+      setSelectedTarget(schedule.group_id != null ? `group:${schedule.group_id}` : `camera:${schedule.camera_id}`);
       setScheduleName(schedule.name);
       setCronExpression(schedule.cron_expression);
       setDurationMinutes(schedule.duration_minutes);
       setFps(schedule.fps ?? '');
+      setAction(schedule.action);
+      setRetentionDays(schedule.retention_days ?? '');
       setIsEnabled(schedule.is_enabled);
     } else {
       resetForm();
@@ -115,11 +143,13 @@ export default function ScheduleRecording({ onScheduleChanged }: ScheduleRecordi
 
   const resetForm = () => {
     setEditingSchedule(null);
-    setSelectedCameraId('');
+    setSelectedTarget('');
     setScheduleName('');
     setCronExpression('0 9 * * *');
     setDurationMinutes(30);
     setFps('');
+    setAction('record');
+    setRetentionDays('');
     setIsEnabled(true);
     setFormError(null);
   };
@@ -130,8 +160,12 @@ export default function ScheduleRecording({ onScheduleChanged }: ScheduleRecordi
   };
 
   const validateForm = (): boolean => {
-    if (!selectedCameraId) {
-      setFormError('Please select a camera');
+    if (!selectedTarget) {
+      setFormError('Please select a camera or camera group');
+      return false;
+    }
+    if (action === 'snapshot' && selectedTarget.startsWith('group:')) {
+      setFormError('Snapshot schedules need a single camera');
       return false;
     }
     if (!scheduleName.trim()) {
@@ -142,7 +176,7 @@ export default function ScheduleRecording({ onScheduleChanged }: ScheduleRecordi
       setFormError('Please enter a cron expression');
       return false;
     }
-    if (durationMinutes <= 0) {
+    if (action === 'record' && durationMinutes <= 0) {
       setFormError('Duration must be greater than 0');
       return false;
     }
@@ -154,12 +188,43 @@ export default function ScheduleRecording({ onScheduleChanged }: ScheduleRecordi
     return true;
   };
 
+  const targetFields = () => {
+    const [kind, id] = selectedTarget.split(':');
+    return kind === 'group'
+      ? { camera_id: null, group_id: Number(id) }
+      : { camera_id: Number(id), group_id: null };
+  };
+
   const handleSave = async () => {
     if (!validateForm()) return;
 
     try {
+      if (action === 'snapshot') {
+        const retention = retentionDays === '' ? 0 : retentionDays;
+        if (editingSchedule) {
+          await updateSnapshotSchedule(editingSchedule.id, {
+            name: scheduleName.trim(),
+            cron_expression: cronExpression.trim(),
+            retention_days: retention,
+            is_enabled: isEnabled,
+          });
+        } else {
+          await addSnapshotSchedule({
+            camera_id: targetFields().camera_id as number,
+            name: scheduleName.trim(),
+            cron_expression: cronExpression.trim(),
+            retention_days: retention || null,
+            is_enabled: isEnabled,
+          });
+        }
+        handleCloseDialog();
+        await loadData();
+        onScheduleChanged?.();
+        return;
+      }
+
       const scheduleData: NewRecordingSchedule = {
-        camera_id: selectedCameraId as number,
+        ...targetFields(),
         name: scheduleName.trim(),
         cron_expression: cronExpression.trim(),
         duration_minutes: durationMinutes,
@@ -215,6 +280,29 @@ export default function ScheduleRecording({ onScheduleChanged }: ScheduleRecordi
     }
   };
 
+  const handleAddGroup = async () => {
+    try {
+      await addCameraGroup({ name: groupName.trim(), camera_ids: groupCameraIds });
+      setGroupName('');
+      setGroupCameraIds([]);
+      await loadData();
+    } catch (err: any) {
+      setError(`Failed to add camera group: ${err}`);
+    }
+  };
+
+  const handleDeleteGroup = async (group: CameraGroup) => {
+    if (!window.confirm(`Delete camera group "${group.name}"?`)) {
+      return;
+    }
+    try {
+      await deleteCameraGroup(group.id);
+      await loadData();
+    } catch (err: any) {
+      setError(`Failed to delete camera group: ${err}`);
+    }
+  };
+
   const handleStopRecording = async (cameraId: number, scheduleName: string) => {
     if (!window.confirm(`Stop recording for "${scheduleName}"?`)) {
       return;
@@ -255,6 +343,30 @@ export default function ScheduleRecording({ onScheduleChanged }: ScheduleRecordi
     return cron;
   };
 
+  const DAY_LABELS = ['Mon', 'Tue', 'Wed', 'Thu', 'Fri', 'Sat', 'Sun'];
+
+  // e.g. "Mon, Tue, Wed 09:00-18:00; Sat 10:00-12:00"
+  const formatWeeklyBlocks = (blocks: WeeklyBlock[]): string =>
+    blocks
+      .map((block) => `${[...block.days].sort((a, b) => a - b).map((d) => DAY_LABELS[d - 1]).join(', ')} ${block.start}-${block.end}`)
+      .join('; ');
+
+  // The pause flag is global, so every schedule reports the same value
+  const schedulesPaused = schedules.some((schedule) => schedule.is_paused);
+
+  const handleTogglePause = async () => {
+    try {
+      if (schedulesPaused) {
+        await resumeSchedules();
+      } else {
+        await pauseSchedules();
+      }
+      await loadData();
+    } catch (err) {
+      setError(`Failed to ${schedulesPaused ? 'resume' : 'pause'} schedules: ${err}`);
+    }
+  };
+
   if (loading) {
     return (
       <Box display="flex" justifyContent="center" alignItems="center" minHeight={200}>
@@ -270,16 +382,36 @@ export default function ScheduleRecording({ onScheduleChanged }: ScheduleRecordi
           <ScheduleIcon sx={{ mr: 1, verticalAlign: 'middle' }} />
           Recording Schedules
         </Typography>
-        <Button
-          variant="contained"
-          startIcon={<AddIcon />}
-          onClick={() => handleOpenDialog()}
-          className="bg-blue-600"
-        >
-          Add Schedule
-        </Button>
+        <Box display="flex" gap={1}>
+          <Button variant="outlined" onClick={() => setIsGroupDialogOpen(true)}>
+            Camera Groups
+          </Button>
+          {schedules.length > 0 && (
+            <Button
+              variant="outlined"
+              startIcon={schedulesPaused ? <PlayArrowIcon /> : <PauseIcon />}
+              onClick={handleTogglePause}
+            >
+              {schedulesPaused ? 'Resume All' : 'Pause All'}
+            </Button>
+          )}
+          <Button
+            variant="contained"
+            startIcon={<AddIcon />}
+            onClick={() => handleOpenDialog()}
+            className="bg-blue-600"
+          >
+            Add Schedule
+          </Button>
+        </Box>
       </Box>
 
+      {schedulesPaused && (
+        <Alert severity="warning" sx={{ mb: 2 }}>
+          All schedules are paused. Their runs are skipped until you resume them.
+        </Alert>
+      )}
+
       {error && (
         <Alert severity="error" sx={{ mb: 2 }}>
           {error}
@@ -299,7 +431,7 @@ export default function ScheduleRecording({ onScheduleChanged }: ScheduleRecordi
           }}
         >
           {schedules.map((schedule) => {
-            const isRecording = recordingCameraIds.includes(schedule.camera_id);
+            const isRecording = schedule.camera_id != null && recordingCameraIds.includes(schedule.camera_id);
 
             return (
               <Card key={schedule.id} className="shadow-md">
@@ -311,7 +443,11 @@ export default function ScheduleRecording({ onScheduleChanged }: ScheduleRecordi
                       </Typography>
                       <Box display="flex" gap={1} alignItems="center" flexWrap="wrap">
                         <Chip
-                          label={schedule.camera_name || getCameraName(schedule.camera_id)}
+                          label={
+                            schedule.group_id != null
+                              ? `Group: ${schedule.group_name ?? schedule.group_id}`
+                              : schedule.camera_name || getCameraName(schedule.camera_id as number)
+                          }
                           size="small"
                           color="primary"
                           variant="outlined"
@@ -321,7 +457,7 @@ export default function ScheduleRecording({ onScheduleChanged }: ScheduleRecordi
                           label={
                             schedule.next_run
                               ? `Next: ${new Date(schedule.next_run).toLocaleString('ja-JP', {
-                                  timeZone: 'Asia/Tokyo',
+                                  timeZone,
                                   year: 'numeric',
                                   month: '2-digit',
                                   day: '2-digit',
@@ -334,6 +470,9 @@ export default function ScheduleRecording({ onScheduleChanged }: ScheduleRecordi
                           color={schedule.next_run ? "success" : "default"}
                           variant={schedule.next_run ? "filled" : "outlined"}
                         />
+                        {schedule.is_paused && schedule.is_enabled && (
+                          <Chip label="Paused" size="small" color="warning" />
+                        )}
                         {/* Recording Status */}
                         {isRecording && (
                           <Chip
@@ -357,13 +496,31 @@ export default function ScheduleRecording({ onScheduleChanged }: ScheduleRecordi
                     />
                   </Box>
 
-                <Typography variant="body2" color="text.secondary" sx={{ mb: 0.5 }}>
-                  <strong>Schedule:</strong> {formatCronDescription(schedule.cron_expression)}
-                </Typography>
-
-                <Typography variant="body2" color="text.secondary" sx={{ mb: 0.5 }}>
-                  <strong>Duration:</strong> {schedule.duration_minutes} minutes
-                </Typography>
+                {schedule.action === 'snapshot' ? (
+                  <>
+                    <Typography variant="body2" color="text.secondary" sx={{ mb: 0.5 }}>
+                      <strong>Snapshot:</strong> {formatCronDescription(schedule.cron_expression)}
+                    </Typography>
+
+                    <Typography variant="body2" color="text.secondary" sx={{ mb: 0.5 }}>
+                      <strong>Keep:</strong> {schedule.retention_days ? `${schedule.retention_days} days` : 'forever'}
+                    </Typography>
+                  </>
+                ) : schedule.schedule_type === 'weekly' && schedule.weekly_blocks ? (
+                  <Typography variant="body2" color="text.secondary" sx={{ mb: 0.5 }}>
+                    <strong>Weekly:</strong> {formatWeeklyBlocks(schedule.weekly_blocks)}
+                  </Typography>
+                ) : (
+                  <>
+                    <Typography variant="body2" color="text.secondary" sx={{ mb: 0.5 }}>
+                      <strong>Schedule:</strong> {formatCronDescription(schedule.cron_expression)}
+                    </Typography>
+
+                    <Typography variant="body2" color="text.secondary" sx={{ mb: 0.5 }}>
+                      <strong>Duration:</strong> {schedule.duration_minutes} minutes
+                    </Typography>
+                  </>
+                )}
 
                 {schedule.fps && (
                   <Typography variant="body2" color="text.secondary" sx={{ mb: 0.5 }}>
@@ -377,7 +534,7 @@ export default function ScheduleRecording({ onScheduleChanged }: ScheduleRecordi
                       size="small"
                       variant="contained"
                       color="error"
-                      onClick={() => handleStopRecording(schedule.camera_id, schedule.name)}
+                      onClick={() => handleStopRecording(schedule.camera_id as number, schedule.name)}
                     >
                       Stop Recording
                     </Button>
@@ -410,7 +567,7 @@ export default function ScheduleRecording({ onScheduleChanged }: ScheduleRecordi
       {/* Add/Edit Schedule Dialog */}
       <Dialog open={isDialogOpen} onClose={handleCloseDialog} maxWidth="sm" fullWidth>
         <DialogTitle>
-          {editingSchedule ? 'Edit Recording Schedule' : 'Add Recording Schedule'}
+          {editingSchedule ? 'Edit Schedule' : 'Add Schedule'}
         </DialogTitle>
         <DialogContent>
           {formError && (
@@ -422,16 +579,22 @@ export default function ScheduleRecording({ onScheduleChanged }: ScheduleRecordi
           <FormControl fullWidth sx={{ mt: 2 }}>
             <InputLabel>Camera</InputLabel>
             <Select
-              value={selectedCameraId}
-              onChange={(e) => setSelectedCameraId(e.target.value as number)}
+              value={selectedTarget}
+              onChange={(e) => setSelectedTarget(e.target.value as string)}
               label="Camera"
               disabled={!!editingSchedule}
             >
               {cameras.map((camera) => (
-                <MenuItem key={camera.id} value={camera.id}>
+                <MenuItem key={`camera:${camera.id}`} value={`camera:${camera.id}`}>
                   {camera.name} ({camera.type})
                 </MenuItem>
               ))}
+              {groups.length > 0 && <ListSubheader>Camera groups</ListSubheader>}
+              {groups.map((group) => (
+                <MenuItem key={`group:${group.id}`} value={`group:${group.id}`}>
+                  {group.name} ({group.camera_ids.length} cameras)
+                </MenuItem>
+              ))}
             </Select>
           </FormControl>
 
@@ -444,6 +607,19 @@ export default function ScheduleRecording({ onScheduleChanged }: ScheduleRecordi
             placeholder="e.g., Daily Morning Recording"
           />
 
+          <FormControl fullWidth sx={{ mt: 1 }}>
+            <InputLabel>Action</InputLabel>
+            <Select
+              value={action}
+              onChange={(e) => setAction(e.target.value as ScheduleAction)}
+              label="Action"
+              disabled={!!editingSchedule}
+            >
+              <MenuItem value="record">Record video</MenuItem>
+              <MenuItem value="snapshot">Capture snapshot</MenuItem>
+            </Select>
+          </FormControl>
+
           <Box mt={2}>
             <Typography variant="subtitle2" gutterBottom>
               Schedule (Cron Expression)
@@ -454,26 +630,41 @@ export default function ScheduleRecording({ onScheduleChanged }: ScheduleRecordi
             />
           </Box>
 
-          <TextField
-            fullWidth
-            type="number"
-            label="Duration (minutes)"
-            value={durationMinutes}
-            onChange={(e) => setDurationMinutes(parseInt(e.target.value) || 0)}
-            margin="normal"
-            inputProps={{ min: 1 }}
-          />
+          {action === 'snapshot' ? (
+            <TextField
+              fullWidth
+              type="number"
+              label="Keep snapshots for (days)"
+              value={retentionDays}
+              onChange={(e) => setRetentionDays(e.target.value ? parseInt(e.target.value) : '')}
+              margin="normal"
+              inputProps={{ min: 0 }}
+              placeholder="Leave empty to keep forever"
+            />
+          ) : (
+            <>
+              <TextField
+                fullWidth
+                type="number"
+                label="Duration (minutes)"
+                value={durationMinutes}
+                onChange={(e) => setDurationMinutes(parseInt(e.target.value) || 0)}
+                margin="normal"
+                inputProps={{ min: 1 }}
+              />
 
-          <TextField
-            fullWidth
-            type="number"
-            label="FPS (optional)"
-            value={fps}
-            onChange={(e) => setFps(e.target.value ? parseInt(e.target.value) : '')}
-            margin="normal"
-            inputProps={{ min: 1 }}
-            placeholder="Leave empty for camera default"
-          />
+              <TextField
+                fullWidth
+                type="number"
+                label="FPS (optional)"
+                value={fps}
+                onChange={(e) => setFps(e.target.value ? parseInt(e.target.value) : '')}
+                margin="normal"
+                inputProps={{ min: 1 }}
+                placeholder="Leave empty for camera default"
+              />
+            </>
+          )}
 
           <FormControlLabel
             control={
@@ -493,6 +684,51 @@ export default function ScheduleRecording({ onScheduleChanged }: ScheduleRecordi
           </Button>
         </DialogActions>
       </Dialog>
+
+      {/* Camera groups: a schedule targeting a group records every camera in it */}
+      <Dialog open={isGroupDialogOpen} onClose={() => setIsGroupDialogOpen(false)} maxWidth="sm" fullWidth>
+        <DialogTitle>Camera Groups</DialogTitle>
+        <DialogContent>
+          {groups.map((group) => (
+            <Box key={group.id} display="flex" justifyContent="space-between" alignItems="center" mt={1}>
+              <Typography>
+                {group.name}: {group.camera_ids.map((id) => getCameraName(id)).join(', ') || 'no cameras'}
+              </Typography>
+              <IconButton size="small" color="error" onClick={() => handleDeleteGroup(group)} title="Delete group">
+                <DeleteIcon fontSize="small" />
+              </IconButton>
+            </Box>
+          ))}
+          <TextField
+            fullWidth
+            label="New group name"
+            value={groupName}
+            onChange={(e) => setGroupName(e.target.value)}
+            margin="normal"
+          />
+          <FormControl fullWidth>
+            <InputLabel>Cameras</InputLabel>
+            <Select
+              multiple
+              value={groupCameraIds}
+              onChange={(e) => setGroupCameraIds(e.target.value as number[])}
+              label="Cameras"
+            >
+              {cameras.map((camera) => (
+                <MenuItem key={camera.id} value={camera.id}>
+                  {camera.name}
+                </MenuItem>
+              ))}
+            </Select>
+          </FormControl>
+        </DialogContent>
+        <DialogActions>
+          <Button onClick={() => setIsGroupDialogOpen(false)}>Close</Button>
+          <Button onClick={handleAddGroup} variant="contained" disabled={!groupName.trim()}>
+            Add Group
+          </Button>
+        </DialogActions>
+      </Dialog>
     </Box>
   );
 }
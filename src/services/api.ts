@@ -3,121 +3,1709 @@ import { invoke } from '@tauri-apps/api/core';
 export interface Camera {
   id: number;
   name: string;
-  type: 'onvif' | 'rtsp' | 'uvc';
+  type: 'onvif' | 'rtsp' | 'uvc' | 'rpicam' | 'hikvision' | 'dahua' | 'axis';
   host: string;
   port: number;
   xaddr?: string | null;  // ONVIF only
   stream_path?: string | null;  // RTSP only
   device_path?: string | null;  // UVC: /dev/video0
   device_id?: string | null;    // UVC: Windows device ID
+  device_index?: number | null; // UVC: macOS device index, Pi: libcamera camera index
+  video_format?: string | null; // UVC: mjpeg/yuyv
+  video_width?: number | null;  // UVC/Pi: 1280
+  video_height?: number | null; // UVC/Pi: 720
+  video_fps?: number | null;    // UVC/Pi: 30
+  tls_verify?: boolean;         // ONVIF/vendor plugins: verify HTTPS certificate
+  tls_cert_pem?: string | null; // pinned certificate; only it is trusted when set
+  rtsp_transport: RtspTransport;
+  stream_timeout_secs?: number | null; // null: app default (20s)
+  input_tuning: InputTuning;
+  extra_input_args: string[];  // advanced: custom FFmpeg arguments before the input
+  extra_output_args: string[]; // and before the live stream/recording output
+  quality_preset?: QualityPresetName | null; // null: the encoder.qualityPreset setting
+  video_filters: VideoFilters;
+  profile_token?: string | null; // ONVIF profile (channel) streamed; null: the first profile
+  time_sync_strategy?: TimeSyncStrategy | null; // null: "utc"
+  sort_order: number;     // position in the camera list and grid
+  is_favorite: boolean;
+}
+
+// How FFmpeg receives a network camera's RTSP stream; "http" tunnels RTSP over HTTP
+export type RtspTransport = 'tcp' | 'udp' | 'multicast' | 'http';
+
+// FFmpeg input options for cameras whose firmware drops or stalls streams; null keeps FFmpeg's default
+export interface InputTuning {
+  socket_timeout_ms: number | null;        // RTSP socket I/O timeout, 1000-600000
+  rw_timeout_ms: number | null;            // any single read or write, 1000-600000
+  reconnect: boolean;                      // HTTP(S) sources only
+  reconnect_delay_max_secs: number | null; // 1-600
+  probesize: number | null;                // bytes, 32 to 50 MB
+  analyzeduration_ms: number | null;       // 0-60000
+  user_agent: string | null;
+}
+
+// Corrections for interlaced or rotated video; applied to the live stream, recordings and snapshots
+export interface VideoFilters {
+  deinterlace: boolean;
+  rotation: 0 | 90 | 180 | 270; // clockwise
+  hflip: boolean;               // after rotating
+  vflip: boolean;
+}
+
+export type NewCamera = {
+  name: string;
+  type: 'onvif' | 'rtsp' | 'uvc' | 'rpicam' | 'hikvision' | 'dahua' | 'axis';
+  host: string;
+  port: number;
+  user?: string;
+  pass?: string;
+  xaddr?: string;
+  stream_path?: string;
+  device_path?: string;
+  device_id?: string;
+  device_index?: number;
+  video_format?: string;
+  video_width?: number;
+  video_height?: number;
+  video_fps?: number;
+  tls_verify?: boolean;
+  tls_cert_pem?: string;
+  rtsp_transport?: RtspTransport; // default "tcp"
+  stream_timeout_secs?: number;   // 5-300
+  profile_token?: string;         // ONVIF: channel of a multichannel device
+};
+
+export const getCameras = async (): Promise<Camera[]> => {
+  return await invoke('get_cameras');
+};
+
+export const addCamera = async (camera: NewCamera): Promise<Camera> => {
+  return await invoke('add_camera', { camera });
+};
+
+// One camera per channel of a multichannel ONVIF device (encoder, NVR), named
+// "<name> - <profile name>"; profileTokens picks channels, null adds all of them
+export const addCameraChannels = async (camera: NewCamera, profileTokens: string[] | null = null): Promise<Camera[]> => {
+  return await invoke('add_camera_channels', { camera, profileTokens });
+};
+
+// What ffprobe reported for a camera's stream
+export interface StreamProbe {
+  video_codec: string | null;
+  audio_codec: string | null;
+  width: number | null;
+  height: number | null;
+  fps: number | null;
+}
+
+// Creates an RTSP camera from its stream URL, filling in resolution and frame rate by probing
+// the stream; fails without saving anything when the stream doesn't answer
+export const addCameraFromUrl = async (
+  rtspUrl: string,
+  name: string | null = null,
+): Promise<{ camera: Camera; probe: StreamProbe }> => {
+  return await invoke('add_camera_from_url', { rtspUrl, name });
+};
+
+// Cameras read from a Frigate config, a Shinobi monitor export or an exported camera list
+export interface CameraImport {
+  source: 'frigate' | 'shinobi' | 'generic';
+  cameras: NewCamera[];                      // created, or on a dry run to be created
+  skipped: { name: string; reason: string }[];
+  created: number[];                         // ids of the new cameras, empty on a dry run
+}
+
+export const importCameras = async (path: string, dryRun: boolean): Promise<CameraImport> => {
+  return await invoke('import_cameras', { path, dryRun });
+};
+
+// YAML for .yaml/.yml paths, JSON otherwise; resolves to the number of cameras written
+export const exportCameras = async (path: string, includeCredentials: boolean): Promise<number> => {
+  return await invoke('export_cameras', { path, includeCredentials });
+};
+
+export interface DeleteCameraOptions {
+  archive?: boolean;          // hide the camera but keep it (and its recordings) browsable
+  deleteRecordings?: boolean; // remove its recordings and snapshots too
+}
+
+// Without options a camera that has recordings can't be deleted
+export const deleteCamera = async (id: number, options?: DeleteCameraOptions): Promise<void> => {
+  await invoke('delete_camera', { id, options });
+};
+
+export const getArchivedCameras = async (): Promise<Camera[]> => {
+  return await invoke('get_archived_cameras');
+};
+
+export const restoreCamera = async (id: number): Promise<void> => {
+  await invoke('restore_camera', { id });
+};
+
+// Cameras left out of ids keep their relative order after the listed ones
+export const reorderCameras = async (ids: number[]): Promise<void> => {
+  await invoke('reorder_cameras', { ids });
+};
+
+export const setFavorite = async (id: number, favorite: boolean): Promise<void> => {
+  await invoke('set_favorite', { id, favorite });
+};
+
+// Named set of cameras a schedule can target
+export interface CameraGroup {
+  id: number;
+  name: string;
+  camera_ids: number[];
+}
+
+export interface NewCameraGroup {
+  name: string;
+  camera_ids: number[];
+}
+
+export const getCameraGroups = async (): Promise<CameraGroup[]> => {
+  return await invoke('get_camera_groups');
+};
+
+export const addCameraGroup = async (group: NewCameraGroup): Promise<CameraGroup> => {
+  return await invoke('add_camera_group', { group });
+};
+
+export const updateCameraGroup = async (id: number, group: NewCameraGroup): Promise<CameraGroup> => {
+  return await invoke('update_camera_group', { id, group });
+};
+
+// Fails while a schedule targets the group
+export const deleteCameraGroup = async (id: number): Promise<void> => {
+  await invoke('delete_camera_group', { id });
+};
+
+export interface DiscoveredDevice {
+  name: string;
+  host: string;
+  port: number;
+  camera_type: string;  // "onvif" or "uvc"
+  user?: string | null;
+  pass?: string | null;
+  device_path?: string | null;  // UVC: /dev/video0
+  device_id?: string | null;    // UVC: Windows device ID
   device_index?: number | null; // UVC: macOS device index
   video_format?: string | null; // UVC: mjpeg/yuyv
   video_width?: number | null;  // UVC: 1280
   video_height?: number | null; // UVC: 720
   video_fps?: number | null;    // UVC: 30
+  xaddr?: string | null;        // ONVIF: device service URL
+  mac?: string | null;          // "ac:cc:8e:12:34:56" when the device advertises it
+  serial?: string | null;
+  sources?: string[];           // plugins that found the device (one entry per device)
+  thumbnail?: string | null;    // data:image/jpeg URL when the device allows anonymous snapshots
+  existing_camera_id?: number | null; // camera already added for this device
+}
+
+export interface ProbedDevice extends DiscoveredDevice {
+  xaddr: string;
+  manufacturer: string;
+  model: string;
+  firmware_version: string;
+  serial_number: string;
+  hardware_id: string;
+  has_ptz: boolean;
+  channels: OnvifChannel[]; // more than one for multichannel encoders and NVRs
+}
+
+// Video input of an ONVIF device and the profile streaming it
+export interface OnvifChannel {
+  profile_token: string;
+  profile_name: string;
+  source_token: string | null;
+  width: number | null;
+  height: number | null;
+}
+
+// Queries an ONVIF device directly by address (for cameras WS-Discovery can't reach).
+// A null port tries the HTTP ports of the ONVIF discovery settings (or the common ONVIF ports).
+export const probeOnvifDevice = async (
+  host: string,
+  port: number | null,
+  user?: string,
+  pass?: string
+): Promise<ProbedDevice> => {
+  return await invoke('probe_onvif_device', { host, port, user, pass });
+};
+
+// Queries a Hikvision camera over ISAPI (when its ONVIF service is disabled or broken)
+export const probeHikvisionDevice = async (
+  host: string,
+  port: number,
+  user?: string,
+  pass?: string,
+  tlsVerify?: boolean
+): Promise<ProbedDevice> => {
+  return await invoke('probe_hikvision_device', { host, port, user, pass, tlsVerify });
+};
+
+// Queries a Dahua or Amcrest camera over its CGI API
+export const probeDahuaDevice = async (
+  host: string,
+  port: number,
+  user?: string,
+  pass?: string,
+  tlsVerify?: boolean
+): Promise<ProbedDevice> => {
+  return await invoke('probe_dahua_device', { host, port, user, pass, tlsVerify });
+};
+
+// Queries an Axis camera over VAPIX (when its ONVIF users are restricted or missing)
+export const probeAxisDevice = async (
+  host: string,
+  port: number,
+  user?: string,
+  pass?: string,
+  tlsVerify?: boolean
+): Promise<ProbedDevice> => {
+  return await invoke('probe_axis_device', { host, port, user, pass, tlsVerify });
+};
+
+// VAPIX parameters of an Axis camera, keyed by full name ("root.Brand.ProdNbr")
+export const getAxisParameters = async (id: number, group: string): Promise<Record<string, string>> => {
+  return await invoke('get_axis_parameters', { id, group });
+};
+
+export interface RpiCameraMode {
+  width: number;
+  height: number;
+  max_fps: number;
+}
+
+export interface RpiCameraInfo {
+  index: number;
+  sensor: string; // e.g. "imx708"
+  path: string;
+  modes: RpiCameraMode[];
+}
+
+// Raspberry Pi CSI cameras and their sensor modes
+export const listRpiCameras = async (): Promise<RpiCameraInfo[]> => {
+  return await invoke('list_rpi_cameras');
+};
+
+// Capture resolution and frame rate of a UVC or Pi camera (applies on the next stream start)
+export const setCameraVideoMode = async (id: number, width: number, height: number, fps: number): Promise<void> => {
+  return await invoke('set_camera_video_mode', { id, width, height, fps });
+};
+
+// Applies the next time the camera's stream or a recording starts
+export const setCameraRtspTransport = async (id: number, transport: RtspTransport): Promise<void> => {
+  return await invoke('set_camera_rtsp_transport', { id, transport });
+};
+
+// Applies the next time the camera's stream or a recording starts
+export const setCameraInputTuning = async (id: number, tuning: InputTuning): Promise<void> => {
+  return await invoke('set_camera_input_tuning', { id, tuning });
+};
+
+// Applies the next time the camera's stream or a recording starts
+export const setCameraVideoFilters = async (id: number, filters: VideoFilters): Promise<void> => {
+  return await invoke('set_camera_video_filters', { id, filters });
+};
+
+// Advanced: one FFmpeg argument per element (e.g. ['-fflags', '+igndts']), at most 32 each.
+// Options that add inputs or outputs or write files (-i, -y, -report, ...) are rejected.
+export const setCameraFfmpegArgs = async (id: number, inputArgs: string[], outputArgs: string[]): Promise<void> => {
+  return await invoke('set_camera_ffmpeg_args', { id, inputArgs, outputArgs });
+};
+
+// "custom" uses the individual encoder and HLS settings
+export type QualityPresetName = 'custom' | 'low_latency' | 'balanced' | 'archive';
+
+export interface QualityPreset {
+  name: Exclude<QualityPresetName, 'custom'>;
+  label: string;
+  description: string;
+  segment_secs: number; // a camera's own HLS segment length still wins
+  gop_secs: number;
+  cpu_preset: string;
+  quality: number;      // CRF, or CQ/QP of GPU encoders
+  stream_kbps: number;
+  record_kbps: number;
+  stream_audio: boolean;
+  record_audio: boolean;
+}
+
+export const getQualityPresets = async (): Promise<QualityPreset[]> => {
+  return await invoke('get_quality_presets');
+};
+
+// null follows the encoder.qualityPreset setting; applies the next time the stream or a recording starts
+export const setCameraQualityPreset = async (id: number, preset: QualityPresetName | null): Promise<void> => {
+  return await invoke('set_camera_quality_preset', { id, preset });
+};
+
+// How long startStream waits for video (5-300 seconds); null restores the default
+export const setCameraStreamTimeout = async (id: number, seconds: number | null): Promise<void> => {
+  return await invoke('set_camera_stream_timeout', { id, seconds });
+};
+
+// Which live streams the encoder.loadBudget setting downgrades first ("low") and last ("high")
+export type CameraPriority = 'low' | 'normal' | 'high';
+
+// null restores "normal"; applies at the next budget check (every 15 seconds)
+export const setCameraPriority = async (id: number, priority: CameraPriority | null): Promise<void> => {
+  return await invoke('set_camera_priority', { id, priority });
+};
+
+export interface TlsCertificate {
+  pem: string;
+  sha256: string; // colon-separated hex fingerprint
+}
+
+// Certificate the camera's HTTPS endpoint presents, to show the user before pinning it
+export const getCameraCertificate = async (id: number): Promise<TlsCertificate> => {
+  return await invoke('get_camera_certificate', { id });
+};
+
+// Verify the camera's certificate against the system roots, or pin certPem so only it is accepted
+export const setCameraTls = async (id: number, verify: boolean, certPem: string | null): Promise<void> => {
+  return await invoke('set_camera_tls', { id, verify, certPem });
+};
+
+// Full SOAP requests and responses of an ONVIF camera, credentials masked; for bug reports
+export interface OnvifTrace {
+  enabled: boolean;
+  trace: string; // empty until a request was traced
+}
+
+export const setOnvifTrace = async (id: number, enabled: boolean): Promise<void> => {
+  return await invoke('set_onvif_trace', { id, enabled });
+};
+
+export const getOnvifTrace = async (cameraId: number): Promise<OnvifTrace> => {
+  return await invoke('get_onvif_trace', { cameraId });
+};
+
+// ONVIF self-test: which of the camera's main operations work, with the raw faults of those that don't
+export interface DiagnosticFault {
+  code: string;
+  subcodes: string[];
+  reason: string;
+  raw: string; // response body, credentials masked and truncated
+}
+
+export interface DiagnosticCheck {
+  operation: string; // e.g. "GetProfiles", or "Snapshot" for the image download
+  status: 'pass' | 'fail' | 'skipped';
+  detail: string | null;
+  http_status: number | null;
+  fault: DiagnosticFault | null;
+  duration_ms: number | null;
+}
+
+export interface CameraDiagnostics {
+  camera_id: number;
+  camera_name: string;
+  ran_at: string;
+  checks: DiagnosticCheck[];
+  passed: number;
+  failed: number;
+  skipped: number;
+  conclusion: string;
+}
+
+// Sets the camera clock unless it uses NTP
+export const runCameraDiagnostics = async (id: number): Promise<CameraDiagnostics> => {
+  return await invoke('run_camera_diagnostics', { id });
+};
+
+// Plugin settings follow the plugin's schema (a JSON Schema subset: type, properties,
+// required, items, enum, minimum/maximum, minLength/maxLength, pattern, default)
+export interface PluginConfig {
+  pluginType: string;
+  schema: Record<string, unknown> | null; // null when the plugin has no settings
+  config: Record<string, unknown>;         // stored values with schema defaults filled in
+}
+
+export const getPluginConfig = async (pluginType: string): Promise<PluginConfig> => {
+  return await invoke('get_plugin_config', { pluginType });
+};
+
+// Validated by the plugin before it is stored; takes effect immediately
+export const setPluginConfig = async (pluginType: string, config: Record<string, unknown>): Promise<PluginConfig> => {
+  return await invoke('set_plugin_config', { pluginType, config });
+};
+
+export const discoverCameras = async (): Promise<DiscoveredDevice[]> => {
+  // Scans for ONVIF, UVC, Raspberry Pi CSI and Axis (SSDP/Bonjour) cameras
+  return await invoke('discover_cameras');
+};
+
+export interface DeviceDiscoveredEvent {
+  scanId: string;
+  camera: DiscoveredDevice;
+}
+
+export interface DiscoveryFinishedEvent {
+  scanId: string;
+  found: number;
+  cancelled: boolean;
+}
+
+// Starts a background scan; results arrive as `device-discovered` events
+// followed by a single `discovery-finished` event for the returned scan id.
+// A device is sent again (replacing the earlier entry) when another plugin finds it
+// or its thumbnail arrives, which may be after `discovery-finished`.
+export const startDiscovery = async (): Promise<string> => {
+  return await invoke('start_discovery');
+};
+
+export const cancelDiscovery = async (scanId: string): Promise<void> => {
+  await invoke('cancel_discovery', { scanId });
+};
+
+// Message the frontend can localize by code (see services/messages.ts); message is the English
+// text. Commands returning one reject with it as well, plain errors under the code "error".
+export interface AppMessage {
+  code: string; // e.g. "time_sync.verified"
+  params: Record<string, unknown>;
+  message: string;
+}
+
+export const isAppMessage = (value: unknown): value is AppMessage =>
+  typeof value === 'object' && value !== null && 'code' in value && 'message' in value;
+
+// Error startStream rejects with when the stream doesn't come up
+export interface StreamStartError {
+  reason: 'timeout' | 'exited' | 'stopped' | 'failed';
+  message: string;
+  stderr: string[]; // FFmpeg's last output lines, credentials masked
+}
+
+export const isStreamStartError = (error: unknown): error is StreamStartError =>
+  typeof error === 'object' && error !== null && 'reason' in error && 'stderr' in error;
+
+// Resolves once the playlist has a segment, so the URL is ready to play. streamUrl is the HLS
+// playlist, or the DASH manifest for "dash" packaging; dashUrl is set whenever DASH is produced.
+// Both point into a new session ("/streams/<session>/..."), revocable with revokeStreamSession.
+export const startStream = async (id: number): Promise<{ streamUrl: string; dashUrl: string | null }> => {
+  return await invoke('start_stream', { id });
+};
+
+export const stopStream = async (id: number): Promise<{ success: boolean }> => {
+  return await invoke('stop_stream', { id });
+};
+
+// session: the id after "/streams/" in a stream URL
+export const revokeStreamSession = async (session: string): Promise<void> => {
+  return await invoke('revoke_stream_session', { session });
+};
+
+// maxDurationMinutes: stop automatically after this long (0 for no limit,
+// omitted to use the default from getRecordingSettings)
+export const startRecording = async (
+  id: number,
+  tags?: string[],
+  notes?: string,
+  maxDurationMinutes?: number
+): Promise<{ success: boolean }> => {
+  return await invoke('start_recording', { id, tags, notes, maxDurationMinutes });
+};
+
+export const stopRecording = async (id: number): Promise<{ success: boolean }> => {
+  return await invoke('stop_recording', { id });
+};
+
+// Finalizes the current segment; resolves to the session id
+export const pauseRecording = async (cameraId: number): Promise<number> => {
+  return await invoke('pause_recording', { cameraId });
+};
+
+// Starts a new segment of the paused session; resolves to its recording id
+export const resumeRecording = async (cameraId: number): Promise<number> => {
+  return await invoke('resume_recording', { cameraId });
+};
+
+export interface RecordingGap {
+  id: number;
+  session_id: number;
+  camera_id: number;
+  paused_at: string;
+  resumed_at: string | null; // null while still paused
+}
+
+export const getRecordingGaps = async (sessionId: number): Promise<RecordingGap[]> => {
+  return await invoke('get_recording_gaps', { sessionId });
+};
+
+export interface Recording {
+  id: number;
+  filename: string;
+  start_time: string;
+  end_time: string;
+  camera_name: string;
+  thumbnail: string | null;
+  trigger: string; // "manual" | "scheduled:<id>" | "motion" | "audio" | "continuous" | "external"
+  tags: string[];
+  notes: string | null;
+  is_protected: boolean; // skipped by automatic cleanup; deleting requires force
+  archive_location: string | null;
+  archived_at: string | null;
+  integrity: 'ok' | 'missing' | 'corrupt' | null; // null until first checked
+  integrity_detail: string | null;
+  file_size: number | null; // bytes, once finished
+  session_id: number | null; // shared by the segments of a paused and resumed recording
+  failure: string | null; // why FFmpeg died right after starting; failed recordings have no file
+  preview: string | null; // animated preview under /recordings/thumbnails/, made on request
+}
+
+// A recording as it was started, with one segment per pause/resume
+export interface RecordingSession {
+  id: number;
+  camera_id: number;
+  camera_name: string | null;
+  trigger: string;
+  started_at: string;
+  ended_at: string | null; // null while recording or paused
+  segment_count: number;
+  total_duration_secs: number;
+  total_size: number;
+  is_protected: boolean; // any segment protected
+  segments: Recording[]; // oldest first
+}
+
+export interface RecordingFilter {
+  camera_id?: number;
+  trigger?: string; // "scheduled" matches recordings from any schedule
+  tag?: string;
+  notes?: string;
+  is_protected?: boolean;
+  integrity?: 'ok' | 'missing' | 'corrupt';
+  session_id?: number;
+  failed?: boolean; // true for failed recordings only, false to leave them out
+}
+
+export const getRecordings = async (filter?: RecordingFilter): Promise<Recording[]> => {
+  return await invoke('get_recordings', { filter });
+};
+
+// Newest first; the filter selects segments, sessions list the matching ones
+export const getRecordingSessions = async (filter?: RecordingFilter): Promise<RecordingSession[]> => {
+  return await invoke('get_recording_sessions', { filter });
+};
+
+// Fails without deleting anything if a segment is protected (unless forced) or still recording
+export const deleteRecordingSession = async (sessionId: number, force?: boolean): Promise<void> => {
+  await invoke('delete_recording_session', { sessionId, force });
+};
+
+// Burned into exported clips only; the recordings themselves are left alone
+export interface Watermark {
+  text?: string | null;       // either text
+  image_path?: string | null; // or a PNG logo, drawn at its own size
+  position: 'top_left' | 'top_right' | 'bottom_left' | 'bottom_right' | 'center';
+  opacity: number;            // 0-1
+  font_size?: number | null;  // 8-200; default scales with the video height
+}
+
+// Joins the segments into one file named after the first; resolves to its path
+export const exportRecordingSession = async (
+  sessionId: number,
+  destDir: string,
+  faststart?: boolean,
+  watermark?: Watermark
+): Promise<string> => {
+  return await invoke('export_recording_session', { sessionId, destDir, faststart, watermark });
+};
+
+// Both recordings side by side over the same wall-clock range (ISO times); resolves to the file's path
+export const exportComparison = async (
+  recordingA: number,
+  recordingB: number,
+  from: string,
+  to: string,
+  destDir: string,
+  watermark?: Watermark
+): Promise<string> => {
+  return await invoke('export_comparison', { recordingA, recordingB, from, to, destDir, watermark });
+};
+
+export const updateRecordingMeta = async (
+  id: number,
+  updates: { tags?: string[]; notes?: string }
+): Promise<void> => {
+  await invoke('update_recording_meta', { id, updates });
+};
+
+export const deleteRecording = async (id: number, force?: boolean): Promise<void> => {
+  await invoke('delete_recording', { id, force });
+};
+
+export interface RecordingIssue {
+  id: number;
+  filename: string;
+  status: 'missing' | 'corrupt';
+  detail: string | null;
+}
+
+export interface VerifyReport {
+  checked: number;
+  ok: number;
+  missing: number;
+  corrupt: number;
+  issues: RecordingIssue[];
+}
+
+// deep (default true) also reads each file with ffprobe; false only checks existence and size
+export const verifyRecordings = async (deep?: boolean): Promise<VerifyReport> => {
+  return await invoke('verify_recordings', { deep });
+};
+
+export const repairRecording = async (id: number): Promise<void> => {
+  return await invoke('repair_recording', { id });
+};
+
+// Port of the running local HTTP server (the server.port setting as of app start)
+let serverPort = 3333;
+
+export const loadServerPort = async (): Promise<number> => {
+  serverPort = await invoke('get_server_port');
+  return serverPort;
+};
+
+export const getServerBaseUrl = (): string => `http://localhost:${serverPort}`;
+
+// Token for authenticated endpoints of the local HTTP server (changes every launch)
+export const getServerToken = async (): Promise<string> => {
+  return await invoke('get_server_token');
+};
+
+// Files under /recordings (recordings, snapshots, thumbnails) need the server token, since in
+// LAN mode the server is reachable from other hosts
+export const getRecordingFileUrl = (path: string, token: string): string => {
+  return `${getServerBaseUrl()}/recordings/${path}?token=${encodeURIComponent(token)}`;
+};
+
+// Served with a "<camera>_<timestamp>.mp4" filename; supports Range requests
+export const getRecordingDownloadUrl = async (id: number): Promise<string> => {
+  return getRecordingFileUrl(`${id}/download`, await getServerToken());
+};
+
+// ============= App Events =============
+// Backend events arrive as Tauri events (listen(name, ...)) and, for other clients,
+// over the /events WebSocket as { event, payload } messages.
+
+export interface RecordingStartedEvent {
+  cameraId: number;
+  recordingId: number;
+}
+
+// Payload of the "recording-completed" event
+export interface RecordingCompletedEvent {
+  cameraId: number;
+  recordingId: number;
+}
+
+// Payload of the "recording-progress" event, sent every few seconds while a recording runs
+export interface RecordingProgressEvent {
+  cameraId: number;
+  recordingId: number;
+  elapsedSecs: number;
+  bytes: number; // size of the output file so far
+}
+
+// Payload of the "recording-updated" event, sent once a finished recording's thumbnail is ready
+export interface RecordingUpdatedEvent {
+  cameraId: number;
+  recordingId: number;
+}
+
+export interface RecordingFailedEvent {
+  cameraId: number;
+  error: string;
+}
+
+export interface RecordingPausedEvent {
+  cameraId: number;
+  sessionId: number;
+}
+
+export interface RecordingResumedEvent {
+  cameraId: number;
+  sessionId: number;
+  recordingId: number;
+}
+
+// Sound rose above a camera's audio alert threshold
+export interface AudioDetectedEvent {
+  cameraId: number;
+  eventId: number; // timeline event (event_type "audio")
+  thresholdDb: number;
+}
+
+// FFmpeg motion detection saw a frame change more than the camera's sensitivity allows
+export interface MotionDetectedEvent {
+  cameraId: number;
+  eventId: number; // timeline event (event_type "motion")
+  score: number; // scene change score, 0..1
+}
+
+// Object detection found objects of the camera's configured classes
+export interface ObjectDetectedEvent {
+  cameraId: number;
+  eventId: number; // timeline event (event_type "object")
+  labels: string[]; // categories: "person" | "vehicle" | "animal"
+  image: string; // frame path relative to the recordings server, boxes in the .json next to it
+}
+
+// An external system (or triggerCamera) triggered a recording or snapshot
+export interface TriggerReceivedEvent {
+  cameraId: number;
+  eventId: number; // timeline event (event_type "trigger")
+  action: 'record' | 'snapshot';
+  reason: string;
+}
+
+// Camera-side alarm from a vendor event stream (camera-side motion arrives as motion-detected)
+export interface CameraAlarmEvent {
+  cameraId: number;
+  eventId: number; // timeline event (event_type is the kind)
+  kind: 'line_crossing' | 'intrusion' | 'tampering' | 'io' | 'video_loss' | string;
+}
+
+export interface StreamDiedEvent {
+  cameraId: number;
+  reason: string;
+}
+
+// A live stream stopped writing HLS segments and is being restarted
+export interface StreamDegradedEvent {
+  cameraId: number;
+  staleSecs: number; // age of the newest segment
+}
+
+export interface ScheduleFiredEvent {
+  scheduleId: number;
+  cameraId: number;
+  name: string;
+}
+
+export interface DiskLowEvent {
+  path: string;
+  availableBytes: number;
+  totalBytes: number;
+}
+
+// Payload of the "library-changed" event: recordings whose files were deleted outside the
+// app (flagged missing or purged) or came back
+export interface LibraryChangedEvent {
+  missing: number[];
+  restored: number[];
+  purged: number[];
+}
+
+// Payload of the "report-ready" event: the daily or weekly digest (reports.digest setting)
+export interface ReportReadyEvent {
+  period: 'daily' | 'weekly';
+  report: ActivityReport;
+}
+
+// update_app_settings changed these keys; restartRequired when one only applies at startup
+export interface SettingsChangedEvent {
+  keys: string[];
+  restartRequired: boolean;
+}
+
+export interface AppEventMessage {
+  event: string; // Tauri event name, e.g. "recording-completed"
+  payload: unknown;
+}
+
+export const getEventsSocketUrl = async (): Promise<string> => {
+  const token = await getServerToken();
+  return `ws://localhost:${serverPort}/events?token=${encodeURIComponent(token)}`;
+};
+
+// Generic settings store; the typed *Settings commands cover the same keys
+export interface AppSettings {
+  'server.port': number;                   // 1024-65535, applies after restart
+  'server.lanMode': boolean;               // serve to the network with signed stream URLs; applies after restart
+  'general.timezone': string;              // IANA name, e.g. "Asia/Tokyo"; applies to schedules after restart
+  'hls.segmentSecs': number;
+  'hls.listSize': number;
+  'hls.deleteThreshold': number;
+  'hls.segmentDir': string | null;
+  'hls.timeshiftMinutes': number;
+  'hls.stallSegments': number;
+  'hls.renditionHeights': number[];        // lower renditions (e.g. [480]); streams then play from master.m3u8
+  'cloudUpload.retentionDays': number | null;
+  'auth.required': boolean;                // needs an admin user before it can be turned on
+  'encoder.loadBudget': number;            // Mpx/s all streams and recordings may encode, 0 for no budget
+  'recordings.purgeMissing': boolean;      // drop rows whose files were deleted instead of flagging them
+  'reports.digest': 'off' | 'daily' | 'weekly'; // activity digest of the previous day or week
+  'reports.webhookUrl': string | null;     // the digest is also POSTed here as JSON
+  'monitoring.resourceIntervalSecs': number; // resource-usage event interval, 0 for off
+  'encoder.qualityPreset': QualityPresetName; // for cameras without their own preset
+  'encoder.gpuSessionLimit': number;       // concurrent GPU encodes, 0 for no limit; renditions past it use the CPU
+  'server.downloadLimitKbps': number | null; // per recording download, null for no limit
+  'triggers.allowRemote': boolean;         // applies after restart
+  'triggers.remotePort': number;           // 1024-65535, applies after restart
+  'triggers.defaultRecordMinutes': number;
+  'mqtt.enabled': boolean;
+  'mqtt.host': string | null;
+  'mqtt.port': number;
+  'mqtt.username': string | null;
+  'mqtt.clientId': string;
+  'mqtt.discoveryPrefix': string;          // no + or # wildcards
+  'mqtt.baseTopic': string;
+  // The trigger token and MQTT password are only available through their typed commands
+}
+
+export const getAppSettings = async (): Promise<AppSettings> => {
+  return await invoke('get_app_settings');
+};
+
+// All changes are validated before any is stored
+export const updateAppSettings = async (changes: Partial<AppSettings>): Promise<AppSettings> => {
+  return await invoke('update_app_settings', { changes });
+};
+
+// ============= App Users =============
+// Only enforced while the auth.required setting is on
+
+export type Role = 'viewer' | 'operator' | 'admin';
+
+export interface AppUser {
+  id: number;
+  username: string;
+  role: Role;
+  created_at: string;
+  last_login_at: string | null;
+}
+
+export interface NewAppUser {
+  username: string;
+  password: string; // at least 8 characters
+  role: Role;
+}
+
+export interface UpdateAppUser {
+  role?: Role;
+  password?: string; // also ends the user's sessions
+}
+
+// The token also works for the HTTP API ("Authorization: Bearer <token>" or ?token=)
+export interface AuthSession {
+  token: string;
+  user: AppUser;
+  expires_at: string;
+}
+
+export interface AuthStatus {
+  required: boolean;
+  has_admin: boolean;
+  user: AppUser | null;
+}
+
+export const login = async (username: string, password: string): Promise<AuthSession> => {
+  return await invoke('login', { username, password });
+};
+
+export const logout = async (): Promise<void> => {
+  return await invoke('logout');
+};
+
+export const getAuthStatus = async (): Promise<AuthStatus> => {
+  return await invoke('get_auth_status');
+};
+
+export const changePassword = async (currentPassword: string, newPassword: string): Promise<void> => {
+  return await invoke('change_password', { currentPassword, newPassword });
+};
+
+export const getAppUsers = async (): Promise<AppUser[]> => {
+  return await invoke('get_app_users');
+};
+
+export const addAppUser = async (user: NewAppUser): Promise<AppUser> => {
+  return await invoke('add_app_user', { user });
+};
+
+export const updateAppUser = async (id: number, changes: UpdateAppUser): Promise<AppUser> => {
+  return await invoke('update_app_user', { id, changes });
+};
+
+export const deleteAppUser = async (id: number): Promise<void> => {
+  return await invoke('delete_app_user', { id });
+};
+
+export interface ServerSettings {
+  id: number;
+  downloadLimitKbps: number | null; // null for unlimited
+}
+
+export interface UpdateServerSettings {
+  downloadLimitKbps?: number; // 0 removes the limit
+}
+
+export const getServerSettings = async (): Promise<ServerSettings> => {
+  return await invoke('get_server_settings');
+};
+
+export const updateServerSettings = async (settings: UpdateServerSettings): Promise<ServerSettings> => {
+  return await invoke('update_server_settings', { settings });
+};
+
+// Live stream HLS output; changes apply the next time a stream starts
+export interface HlsSettings {
+  id: number;
+  segmentSecs: number;       // 1-10
+  listSize: number;          // 3-1800 segments in the playlist (the rewind window)
+  deleteThreshold: number;   // 1-100 unlisted segments kept before deletion
+  segmentDir: string | null; // e.g. a tmpfs/RAM disk path, null for the app data dir
+  timeshiftMinutes: number;  // 0-60 minutes of live rewind, 0 for off
+  stallSegments: number;     // 0-30 segment durations without output before a restart, 0 for off
+}
+
+export interface UpdateHlsSettings {
+  segmentSecs?: number;
+  listSize?: number;
+  deleteThreshold?: number;
+  segmentDir?: string; // "" goes back to the app data dir
+  timeshiftMinutes?: number;
+  stallSegments?: number;
+}
+
+export const getHlsSettings = async (): Promise<HlsSettings> => {
+  return await invoke('get_hls_settings');
+};
+
+export const updateHlsSettings = async (settings: UpdateHlsSettings): Promise<HlsSettings> => {
+  return await invoke('update_hls_settings', { settings });
+};
+
+// Per-camera overrides; null fields follow HlsSettings
+export interface CameraHlsSettings {
+  camera_id: number;
+  segment_secs: number | null;
+  list_size: number | null;
+  delete_threshold: number | null;
+  segment_dir: string | null;
+  timeshift_minutes: number | null;
+  packaging: StreamPackaging | null; // null: "hls"
+}
+
+// "dash" alone only suits external players: the app's player and stream-based detection use HLS
+export type StreamPackaging = 'hls' | 'dash' | 'both';
+
+export const getCameraHlsSettings = async (cameraId: number): Promise<CameraHlsSettings | null> => {
+  return await invoke('get_camera_hls_settings', { cameraId });
+};
+
+// All fields null removes the camera's overrides
+export const setCameraHlsSettings = async (settings: CameraHlsSettings): Promise<void> => {
+  return await invoke('set_camera_hls_settings', { settings });
+};
+
+// Live rewind: the stream playlist keeps the last timeshiftMinutes of video, with
+// EXT-X-PROGRAM-DATE-TIME on every segment for mapping player time to wall-clock time
+export interface TimeshiftInfo {
+  cameraId: number;
+  live: boolean;
+  windowSecs: number;         // configured window
+  availableSecs: number;      // video currently in the playlist
+  segmentCount: number;
+  oldestTime: string | null;  // earliest point the player can seek to
+  newestTime: string | null;  // live edge
+  streamUrl: string | null;
+}
+
+export const getTimeshiftInfo = async (cameraId: number): Promise<TimeshiftInfo> => {
+  return await invoke('get_timeshift_info', { cameraId });
+};
+
+export interface RecordingSettings {
+  id: number;
+  defaultMaxDurationMinutes: number | null; // null for no limit
+}
+
+export interface UpdateRecordingSettings {
+  defaultMaxDurationMinutes?: number; // 0 removes the limit
+}
+
+export const getRecordingSettings = async (): Promise<RecordingSettings> => {
+  return await invoke('get_recording_settings');
+};
+
+export const updateRecordingSettings = async (settings: UpdateRecordingSettings): Promise<RecordingSettings> => {
+  return await invoke('update_recording_settings', { settings });
+};
+
+// External triggers: POST (JSON body) or GET (query string) to
+// http://<host>:<port>/api/triggers/<cameraId> with "Authorization: Bearer <token>" or ?token=<token>.
+// The server port (server.port setting) on this machine, or remotePort from other machines when allowRemote is set.
+export interface TriggerSettings {
+  id: number;
+  token: string;
+  allowRemote: boolean; // takes effect on the next launch
+  remotePort: number;
+  defaultRecordMinutes: number;
+}
+
+export interface UpdateTriggerSettings {
+  allowRemote?: boolean;
+  remotePort?: number;
+  defaultRecordMinutes?: number;
+  regenerateToken?: boolean;
+}
+
+export interface TriggerResult {
+  eventId: number;
+  action: 'record' | 'snapshot';
+  recordingId: number | null;
+  alreadyRecording: boolean; // a recording was already running, so none was started
+  snapshot: Snapshot | null;
+}
+
+export const triggerCamera = async (
+  cameraId: number,
+  action: 'record' | 'snapshot' = 'record',
+  reason?: string,
+  durationMinutes?: number
+): Promise<TriggerResult> => {
+  return await invoke('trigger_camera', { cameraId, action, reason, durationMinutes });
+};
+
+export const getTriggerSettings = async (): Promise<TriggerSettings> => {
+  return await invoke('get_trigger_settings');
+};
+
+export const updateTriggerSettings = async (settings: UpdateTriggerSettings): Promise<TriggerSettings> => {
+  return await invoke('update_trigger_settings', { settings });
+};
+
+// Home Assistant MQTT discovery: each camera appears as a device with a snapshot camera,
+// a motion sensor, a recording switch and a snapshot button
+export interface MqttSettings {
+  id: number;
+  enabled: boolean;
+  host: string | null;
+  port: number;
+  username: string | null;
+  password: string | null;
+  clientId: string;
+  discoveryPrefix: string; // "homeassistant" unless changed in Home Assistant
+  baseTopic: string; // state and commands under <baseTopic>/<cameraId>/...
+  connected: boolean;
+}
+
+export interface UpdateMqttSettings {
+  enabled?: boolean;
+  host?: string; // an empty host, username or password clears it
+  port?: number;
+  username?: string;
+  password?: string;
+  clientId?: string;
+  discoveryPrefix?: string;
+  baseTopic?: string;
+}
+
+export const getMqttSettings = async (): Promise<MqttSettings> => {
+  return await invoke('get_mqtt_settings');
+};
+
+// Saving reconnects to the broker
+export const updateMqttSettings = async (settings: UpdateMqttSettings): Promise<MqttSettings> => {
+  return await invoke('update_mqtt_settings', { settings });
+};
+
+export interface BatchResult {
+  succeeded: number[];
+  failed: { id: number; error: string }[];
+}
+
+// Emitted as `recordings-batch-progress` after each item of a batch operation
+export interface RecordingsBatchProgress {
+  operation: 'delete' | 'export';
+  id: number;
+  completed: number;
+  total: number;
+  error: string | null;
+}
+
+export const deleteRecordings = async (ids: number[], force?: boolean): Promise<BatchResult> => {
+  return await invoke('delete_recordings', { ids, force });
+};
+
+// faststart remuxes each recording into a regular MP4 with the index up front (fragmented MP4
+// and MKV recordings otherwise keep their container). A watermark re-encodes each export.
+export const exportRecordings = async (
+  ids: number[],
+  destDir: string,
+  faststart?: boolean,
+  watermark?: Watermark
+): Promise<BatchResult> => {
+  return await invoke('export_recordings', { ids, destDir, faststart, watermark });
+};
+
+export const setRecordingProtected = async (id: number, isProtected: boolean): Promise<void> => {
+  await invoke('set_recording_protected', { id, protected: isProtected });
+};
+
+export interface PlaybackSession {
+  sessionId: string | null; // null when the file is served directly
+  url: string;
+  transcoded: boolean;      // true: url is an HLS playlist
+  startOffset: number;      // seconds into the recording where the stream starts
+  duration: number | null;
+}
+
+export const startPlaybackSession = async (recordingId: number, startSecs?: number): Promise<PlaybackSession> => {
+  return await invoke('start_playback_session', { recordingId, startSecs });
+};
+
+// Plays all segments of a recording session back to back
+export const startRecordingSessionPlayback = async (sessionId: number, startSecs?: number): Promise<PlaybackSession> => {
+  return await invoke('start_recording_session_playback', { sessionId, startSecs });
+};
+
+export const stopPlaybackSession = async (sessionId: string): Promise<void> => {
+  await invoke('stop_playback_session', { sessionId });
+};
+
+export interface TimelineRange {
+  start: string;
+  end: string;
+  covered: boolean;
+  recording_ids: number[];
+}
+
+export interface TimelineMarker {
+  id: number;
+  event_type: string; // e.g. "motion"
+  time: string;
+  end_time: string | null;
+}
+
+export interface RecordingTimeline {
+  camera_id: number;
+  from: string;
+  to: string;
+  ranges: TimelineRange[]; // consecutive ranges spanning the whole window
+  markers: TimelineMarker[];
+}
+
+export const getRecordingTimeline = async (cameraId: number, from: Date, to: Date): Promise<RecordingTimeline> => {
+  return await invoke('get_recording_timeline', {
+    cameraId,
+    from: from.toISOString(),
+    to: to.toISOString(),
+  });
+};
+
+// ============= Activity Reports =============
+
+export interface CameraActivity {
+  camera_id: number;
+  camera_name: string;
+  recordings: number;
+  recorded_bytes: number;
+  recorded_secs: number;
+  failures: number;
+}
+
+export interface ReportFailure {
+  camera_id: number;
+  camera_name: string | null;
+  kind: 'recording' | 'schedule';
+  detail: string | null;
+  occurred_at: string;
+}
+
+// Camera whose live stream died during the range
+export interface OfflineCamera {
+  camera_id: number;
+  camera_name: string | null;
+  incidents: number;
+  last_incident_at: string;
+  last_reason: string | null;
+}
+
+export interface StorageUsage {
+  name: string;
+  path: string;
+  free_bytes: number | null;
+  total_bytes: number | null;
+  days_left: number | null; // at the range's average daily recording volume
+}
+
+export interface ActivityReport {
+  from: string;
+  to: string;
+  generated_at: string;
+  recordings: number;
+  recorded_bytes: number;
+  recorded_secs: number;
+  cameras: CameraActivity[];
+  failure_count: number;
+  failures: ReportFailure[]; // most recent first, capped; failure_count covers all
+  offline_cameras: OfflineCamera[];
+  daily_usage: { date: string; bytes: number }[]; // bytes recorded per local day
+  storage: StorageUsage[];
+}
+
+// Covers recordings started in [from, to)
+export const generateReport = async (from: Date, to: Date): Promise<ActivityReport> => {
+  return await invoke('generate_report', {
+    range: { from: from.toISOString(), to: to.toISOString() },
+  });
+};
+
+// Event with the footage covering it; play it with
+// startPlaybackSession(recording_id, recording_offset_secs)
+export interface CameraEvent {
+  id: number;
+  camera_id: number;
+  camera_name: string | null;
+  event_type: string; // "motion" | "audio" | "object" | ...
+  start_time: string;
+  end_time: string | null;
+  details: Record<string, unknown> | null;
+  recording_id: number | null; // null when nothing was recorded
+  recording_session_id: number | null;
+  recording_offset_secs: number | null;
+  preview: string | null; // animated clip of a motion event under /recordings/thumbnails/
+}
+
+export interface EventQuery {
+  cameraId?: number;
+  eventType?: string;
+  from?: Date;
+  to?: Date;
+  limit?: number; // default 200, at most 1000
+}
+
+export const getEvents = async (query: EventQuery = {}): Promise<CameraEvent[]> => {
+  return await invoke('get_events', {
+    cameraId: query.cameraId,
+    eventType: query.eventType,
+    from: query.from?.toISOString(),
+    to: query.to?.toISOString(),
+    limit: query.limit,
+  });
+};
+
+export interface ArchiveSettings {
+  id: number;
+  targetType: 'local' | 'webdav'; // "local" also covers mounted SMB/NFS shares
+  target: string | null;
+  webdavUser: string | null;
+  webdavPass: string | null;
+  autoArchive: boolean;
+}
+
+export interface UpdateArchiveSettings {
+  targetType?: 'local' | 'webdav';
+  target?: string;
+  webdavUser?: string;
+  webdavPass?: string;
+  autoArchive?: boolean;
+}
+
+// Queue entry; also the payload of the "archive-progress" event
+export interface ArchiveJob {
+  recording_id: number;
+  filename: string;
+  status: 'queued' | 'transferring' | 'verifying' | 'done' | 'failed';
+  bytes_done: number;
+  bytes_total: number;
+  location: string | null;
+  error: string | null;
+}
+
+export const archiveRecording = async (id: number): Promise<void> => {
+  return await invoke('archive_recording', { id });
+};
+
+export const getArchiveQueue = async (): Promise<ArchiveJob[]> => {
+  return await invoke('get_archive_queue');
+};
+
+export const getArchiveSettings = async (): Promise<ArchiveSettings> => {
+  return await invoke('get_archive_settings');
+};
+
+export const updateArchiveSettings = async (settings: UpdateArchiveSettings): Promise<ArchiveSettings> => {
+  return await invoke('update_archive_settings', { settings });
+};
+
+export interface CloudUploadSettings {
+  id: number;
+  enabled: boolean; // default for cameras without their own policy
+  endpoint: string | null; // e.g. https://s3.amazonaws.com or http://minio:9000
+  region: string;
+  bucket: string | null;
+  accessKey: string | null;
+  secretKey: string | null;
+  prefix: string;
+  pathStyle: boolean;
+  includeThumbnails: boolean;
+  retentionDays: number | null;
+  bandwidthLimitKbps: number | null;
+  maxRetries: number;
+}
+
+export interface UpdateCloudUploadSettings {
+  enabled?: boolean;
+  endpoint?: string;
+  region?: string;
+  bucket?: string;
+  accessKey?: string;
+  secretKey?: string;
+  prefix?: string;
+  pathStyle?: boolean;
+  includeThumbnails?: boolean;
+  retentionDays?: number; // 0 keeps uploads forever
+  bandwidthLimitKbps?: number; // 0 removes the limit
+  maxRetries?: number;
+}
+
+export interface AudioMonitorSettings {
+  cameraId: number;
+  enabled: boolean;
+  thresholdDb: number; // alert when the level rises above this (dBFS, e.g. -20)
+  cooldownSecs: number; // minimum time between alerts
+  recordOnAlert: boolean;
+  recordMinutes: number; // length of recordings started by an alert
+  running: boolean;
+}
+
+export interface UpdateAudioMonitorSettings {
+  enabled?: boolean;
+  thresholdDb?: number;
+  cooldownSecs?: number;
+  recordOnAlert?: boolean;
+  recordMinutes?: number;
+}
+
+export const getAudioMonitor = async (cameraId: number): Promise<AudioMonitorSettings> => {
+  return await invoke('get_audio_monitor', { cameraId });
+};
+
+// Network cameras only; the camera's stream must carry audio
+export const setAudioMonitor = async (
+  cameraId: number,
+  settings: UpdateAudioMonitorSettings
+): Promise<AudioMonitorSettings> => {
+  return await invoke('set_audio_monitor', { cameraId, settings });
+};
+
+// Region analysed for motion, as fractions of the frame width and height
+export interface MotionRoi {
+  x: number;
+  y: number;
+  width: number;
+  height: number;
 }
 
-export type NewCamera = {
-  name: string;
-  type: 'onvif' | 'rtsp' | 'uvc';
-  host: string;
-  port: number;
-  user?: string;
-  pass?: string;
-  xaddr?: string;
-  stream_path?: string;
-  device_path?: string;
-  device_id?: string;
-  device_index?: number;
-  video_format?: string;
-  video_width?: number;
-  video_height?: number;
-  video_fps?: number;
+export interface MotionDetectorSettings {
+  cameraId: number;
+  enabled: boolean;
+  sensitivity: number; // 1..100, higher reacts to smaller changes
+  roi: MotionRoi | null; // null for the whole frame
+  cooldownSecs: number; // minimum time between motion events
+  recordOnMotion: boolean; // not available for UVC cameras
+  recordMinutes: number; // length of recordings started by motion
+  running: boolean;
+}
+
+export interface UpdateMotionDetectorSettings {
+  enabled?: boolean;
+  sensitivity?: number;
+  roi?: MotionRoi; // { x: 0, y: 0, width: 1, height: 1 } removes the region
+  cooldownSecs?: number;
+  recordOnMotion?: boolean;
+  recordMinutes?: number;
+}
+
+export const getMotionDetector = async (cameraId: number): Promise<MotionDetectorSettings> => {
+  return await invoke('get_motion_detector', { cameraId });
 };
 
-export const getCameras = async (): Promise<Camera[]> => {
-  return await invoke('get_cameras');
+// UVC cameras are analysed from their live stream, so it has to be running
+export const setMotionDetector = async (
+  cameraId: number,
+  settings: UpdateMotionDetectorSettings
+): Promise<MotionDetectorSettings> => {
+  return await invoke('set_motion_detector', { cameraId, settings });
 };
 
-export const addCamera = async (camera: NewCamera): Promise<Camera> => {
-  return await invoke('add_camera', { camera });
+export interface AnalyticsSettings {
+  id: number;
+  backend: 'http' | 'onnx';
+  modelPath: string | null; // ONNX model file (onnx backend)
+  endpointUrl: string | null; // e.g. http://localhost:32168/v1/vision/detection (http backend)
+  sampleFps: number; // frames analysed per second of a live stream
+  availableBackends: string[]; // "onnx" only in builds with the onnx feature
+}
+
+export interface UpdateAnalyticsSettings {
+  backend?: 'http' | 'onnx';
+  modelPath?: string; // empty string clears
+  endpointUrl?: string; // empty string clears
+  sampleFps?: number; // 0.1..2
+}
+
+export const getAnalyticsSettings = async (): Promise<AnalyticsSettings> => {
+  return await invoke('get_analytics_settings');
 };
 
-export const deleteCamera = async (id: number): Promise<void> => {
-  await invoke('delete_camera', { id });
+export const updateAnalyticsSettings = async (settings: UpdateAnalyticsSettings): Promise<AnalyticsSettings> => {
+  return await invoke('update_analytics_settings', { settings });
 };
 
-export interface DiscoveredDevice {
-  name: string;
-  host: string;
-  port: number;
-  camera_type: string;  // "onvif" or "uvc"
-  user?: string | null;
-  pass?: string | null;
-  device_path?: string | null;  // UVC: /dev/video0
-  device_id?: string | null;    // UVC: Windows device ID
-  device_index?: number | null; // UVC: macOS device index
-  video_format?: string | null; // UVC: mjpeg/yuyv
-  video_width?: number | null;  // UVC: 1280
-  video_height?: number | null; // UVC: 720
-  video_fps?: number | null;    // UVC: 30
+export type ObjectClass = 'person' | 'vehicle' | 'animal';
+
+export interface ObjectDetectorSettings {
+  cameraId: number;
+  enabled: boolean;
+  minConfidence: number; // 0..1
+  classes: ObjectClass[];
+  cooldownSecs: number; // minimum time between object events
+  running: boolean;
 }
 
-export const discoverCameras = async (): Promise<DiscoveredDevice[]> => {
-  // Scans for both ONVIF and UVC cameras
-  return await invoke('discover_cameras');
+export interface UpdateObjectDetectorSettings {
+  enabled?: boolean;
+  minConfidence?: number;
+  classes?: ObjectClass[];
+  cooldownSecs?: number;
+}
+
+export const getObjectDetector = async (cameraId: number): Promise<ObjectDetectorSettings> => {
+  return await invoke('get_object_detector', { cameraId });
 };
 
-export const startStream = async (id: number): Promise<{ streamUrl: string }> => {
-  return await invoke('start_stream', { id });
+// Frames are sampled from the camera's live stream, so detection only runs while it streams
+export const setObjectDetector = async (
+  cameraId: number,
+  settings: UpdateObjectDetectorSettings
+): Promise<ObjectDetectorSettings> => {
+  return await invoke('set_object_detector', { cameraId, settings });
 };
 
-export const stopStream = async (id: number): Promise<{ success: boolean }> => {
-  return await invoke('stop_stream', { id });
+export interface CameraUploadPolicy {
+  camera_id: number;
+  enabled: boolean;
+}
+
+// Queue entry; also the payload of the "upload-progress" event
+export interface UploadJob {
+  recording_id: number;
+  filename: string;
+  status: 'queued' | 'uploading' | 'retrying' | 'done' | 'failed';
+  attempts: number;
+  bytes_done: number;
+  bytes_total: number;
+  key: string | null;
+  next_retry_at: string | null;
+  error: string | null;
+}
+
+export const uploadRecording = async (id: number): Promise<void> => {
+  return await invoke('upload_recording', { id });
 };
 
-export const startRecording = async (id: number): Promise<{ success: boolean }> => {
-  return await invoke('start_recording', { id });
+export const getUploadQueue = async (): Promise<UploadJob[]> => {
+  return await invoke('get_upload_queue');
 };
 
-export const stopRecording = async (id: number): Promise<{ success: boolean }> => {
-  return await invoke('stop_recording', { id });
+// Background jobs. verify_recordings takes { deep?: boolean }; regenerate_thumbnails takes
+// { ids?: number[] } and defaults to every finished recording without a thumbnail;
+// create_preview is submitted through createPreview.
+export type JobKind = 'verify_recordings' | 'regenerate_thumbnails' | 'create_preview';
+
+// Job row; also the payload of the "job-progress" event
+export interface BackgroundJob {
+  id: number;
+  kind: JobKind;
+  params: unknown;
+  status: 'queued' | 'running' | 'done' | 'failed' | 'cancelled';
+  progress: number; // 0-1
+  message: string | null; // summary once done
+  error: string | null;
+  created_at: string;
+  started_at: string | null;
+  finished_at: string | null;
+}
+
+export const submitJob = async (kind: JobKind, params?: Record<string, unknown>): Promise<BackgroundJob> => {
+  return await invoke('submit_job', { kind, params });
 };
 
-export interface Recording {
+// Queues a 3 second looping preview from offset seconds into the recording; the recording's
+// preview field is set once the job is done. format is 'webp' (default) or 'gif'.
+export const createPreview = async (recordingId: number, offset = 0, format?: 'webp' | 'gif'): Promise<BackgroundJob> => {
+  return await invoke('create_preview', { recordingId, offset, format });
+};
+
+export const getJobs = async (limit?: number): Promise<BackgroundJob[]> => {
+  return await invoke('get_jobs', { limit });
+};
+
+// A running job stops at its next step
+export const cancelJob = async (id: number): Promise<void> => {
+  return await invoke('cancel_job', { id });
+};
+
+export const getCloudUploadSettings = async (): Promise<CloudUploadSettings> => {
+  return await invoke('get_cloud_upload_settings');
+};
+
+export const updateCloudUploadSettings = async (settings: UpdateCloudUploadSettings): Promise<CloudUploadSettings> => {
+  return await invoke('update_cloud_upload_settings', { settings });
+};
+
+export const getCameraUploadPolicies = async (): Promise<CameraUploadPolicy[]> => {
+  return await invoke('get_camera_upload_policies');
+};
+
+// Pass null to make the camera follow the global setting
+export const setCameraUploadPolicy = async (cameraId: number, enabled: boolean | null): Promise<void> => {
+  return await invoke('set_camera_upload_policy', { cameraId, enabled });
+};
+
+// "mp4" records to TS and remuxes to a faststart MP4 on stop; "fmp4" and "mkv" are written
+// directly and stay playable if the app crashes mid-recording
+export type RecordingContainer = 'mp4' | 'fmp4' | 'mkv';
+
+export interface CameraRecordingFormat {
+  camera_id: number;
+  container: RecordingContainer;
+}
+
+// Cameras without an entry record to "mp4"
+export const getCameraRecordingFormats = async (): Promise<CameraRecordingFormat[]> => {
+  return await invoke('get_camera_recording_formats');
+};
+
+// Applies the next time a recording starts; null goes back to "mp4"
+export const setCameraRecordingFormat = async (cameraId: number, container: RecordingContainer | null): Promise<void> => {
+  return await invoke('set_camera_recording_format', { cameraId, container });
+};
+
+// Recording directory besides the built-in one, tried in priority order when a camera's own
+// location is full or unavailable
+export interface StorageLocation {
   id: number;
-  filename: string;
-  start_time: string;
-  end_time: string;
-  camera_name: string;
-  thumbnail: string | null;
+  name: string;
+  path: string;
+  priority: number; // lower first
+  min_free_mb: number;
+  enabled: boolean;
+  available: boolean; // takes recordings now
+  free_bytes: number | null;
+  total_bytes: number | null;
+}
+
+export type NewStorageLocation = Pick<StorageLocation, 'name' | 'path' | 'priority' | 'min_free_mb' | 'enabled'>;
+
+export const getStorageLocations = async (): Promise<StorageLocation[]> => {
+  return await invoke('get_storage_locations');
+};
+
+// The directory must already exist; resolves to the new location's id
+export const addStorageLocation = async (location: NewStorageLocation): Promise<number> => {
+  return await invoke('add_storage_location', { location });
+};
+
+export const updateStorageLocation = async (id: number, location: NewStorageLocation): Promise<void> => {
+  await invoke('update_storage_location', { id, location });
+};
+
+// Fails while recordings are stored there
+export const deleteStorageLocation = async (id: number): Promise<void> => {
+  await invoke('delete_storage_location', { id });
+};
+
+export interface CameraStorage {
+  camera_id: number;
+  location_id: number;
+}
+
+// Cameras without an entry record to the built-in recordings directory
+export const getCameraStorage = async (): Promise<CameraStorage[]> => {
+  return await invoke('get_camera_storage');
+};
+
+// Applies the next time a recording starts; null goes back to the built-in directory
+export const setCameraStorage = async (cameraId: number, locationId: number | null): Promise<void> => {
+  await invoke('set_camera_storage', { cameraId, locationId });
+};
+
+// Seconds kept before each recording of the camera (5 to 120)
+export interface CameraPrebuffer {
+  camera_id: number;
+  seconds: number;
 }
 
-export const getRecordings = async (): Promise<Recording[]> => {
-  return await invoke('get_recordings');
+export const getCameraPrebuffers = async (): Promise<CameraPrebuffer[]> => {
+  return await invoke('get_camera_prebuffers');
 };
 
-export const deleteRecording = async (id: number): Promise<void> => {
-  await invoke('delete_recording', { id });
+// Network cameras only; null turns the buffer off
+export const setCameraPrebuffer = async (cameraId: number, seconds: number | null): Promise<void> => {
+  return await invoke('set_camera_prebuffer', { cameraId, seconds });
 };
 
 export interface CameraTimeInfo {
   cameraTime: any;
   serverTime: string;
+  timeZone: string | null; // POSIX TZ configured on the camera, e.g. "CET-1CEST,M3.5.0,M10.5.0/3"
+  daylightSavings: boolean;
 }
 
 export const getCameraTime = async (id: number): Promise<CameraTimeInfo> => {
   return await invoke('get_camera_time', { id });
 };
 
-export interface TimeSyncResult {
+export interface CameraClockStatus {
+  cameraId: number;
+  cameraName: string;
+  reachable: boolean;
+  cameraTime: string | null; // RFC 3339, UTC
+  driftSecs: number | null; // camera minus server; positive when the camera is ahead
+  error: string | null;
+}
+
+export interface CameraClockReport {
+  serverTime: string;
+  cameras: CameraClockStatus[];
+  reachableCount: number;
+  unreachableCount: number;
+  driftedCount: number; // reachable cameras off by 5 seconds or more
+  maxDriftSecs: number | null;
+}
+
+// Clocks of all cameras with time support, queried concurrently
+export const getAllCameraTimes = async (): Promise<CameraClockReport> => {
+  return await invoke('get_all_camera_times');
+};
+
+export interface TimeSyncResult extends AppMessage {
   success: boolean;
   beforeTime: any;
   serverTime: string;
-  message: string;
+  timeZone: string | null; // POSIX TZ the camera was given; null for UTC
+  streamRestarted: boolean; // the live stream stalled after the clock change and was restarted
   error?: string;
 }
 
+// How time sync sets an ONVIF camera's time zone: UTC, the camera's own, or this app's
+// general.timezone. The clock itself is always set to the same instant.
+export type TimeSyncStrategy = 'utc' | 'camera' | 'host';
+
+export const setCameraTimeSyncStrategy = async (id: number, strategy: TimeSyncStrategy | null): Promise<void> => {
+  return await invoke('set_camera_time_sync_strategy', { id, strategy });
+};
+
 export const syncCameraTime = async (id: number): Promise<TimeSyncResult> => {
   return await invoke('sync_camera_time', { id });
 };
@@ -137,9 +1725,8 @@ export interface PTZMovement {
   timeout?: number;
 }
 
-export interface PTZResult {
+export interface PTZResult extends AppMessage {
   success: boolean;
-  message: string;
 }
 
 export const checkPTZCapabilities = async (id: number): Promise<PTZCapabilities> => {
@@ -154,6 +1741,76 @@ export const stopPTZ = async (id: number): Promise<PTZResult> => {
   return await invoke('stop_ptz', { id });
 };
 
+export interface OnvifUser {
+  username: string;
+  user_level: 'Administrator' | 'Operator' | 'User' | string;
+}
+
+export interface OnvifUserChange {
+  username: string;
+  password?: string | null;
+  user_level: 'Administrator' | 'Operator' | 'User';
+  use_for_app?: boolean; // switch the app's stored credentials to this account
+}
+
+export const getCameraUsers = async (id: number): Promise<OnvifUser[]> => {
+  return await invoke('get_camera_users', { id });
+};
+
+export const createCameraUser = async (id: number, user: OnvifUserChange): Promise<Camera> => {
+  return await invoke('create_camera_user', { id, user });
+};
+
+export const updateCameraUser = async (id: number, user: OnvifUserChange): Promise<Camera> => {
+  return await invoke('update_camera_user', { id, user });
+};
+
+export const deleteCameraUser = async (id: number, username: string): Promise<void> => {
+  await invoke('delete_camera_user', { id, username });
+};
+
+export interface NetworkInterface {
+  token: string;
+  name: string | null;
+  hw_address: string | null;
+  enabled: boolean;
+  dhcp: boolean;
+  ipv4_address: string | null; // DHCP lease or first manual address
+  prefix_length: number | null;
+}
+
+export interface NetworkConfig {
+  interfaces: NetworkInterface[];
+  gateways: string[];
+  dns_from_dhcp: boolean;
+  dns_servers: string[];
+}
+
+export interface NetworkChange {
+  interface_token: string;
+  dhcp: boolean;
+  ipv4_address?: string; // required with prefix_length when dhcp is false
+  prefix_length?: number;
+  gateway?: string;
+  dns_from_dhcp?: boolean; // DNS is unchanged when this and dns_servers are omitted
+  dns_servers?: string[];
+}
+
+export interface NetworkChangeResult extends AppMessage {
+  camera: Camera; // host and xaddr already point at a new static address
+  rebooted: boolean;
+  reachable: boolean;
+}
+
+export const getCameraNetwork = async (id: number): Promise<NetworkConfig> => {
+  return await invoke('get_camera_network', { id });
+};
+
+// Resolves once the camera answers at its new address or the re-check times out (up to ~3 minutes)
+export const setCameraNetwork = async (id: number, change: NetworkChange): Promise<NetworkChangeResult> => {
+  return await invoke('set_camera_network', { id, change });
+};
+
 export interface CameraCapabilities {
   streaming: boolean;
   recording: boolean;
@@ -182,6 +1839,77 @@ export const detectGpu = async (): Promise<GpuCapabilities> => {
   return await invoke('detect_gpu');
 };
 
+// Fields the vendor tool doesn't report are null; Intel reports its video engine as encoder_percent
+export interface GpuUsage {
+  index: number;
+  vendor: 'NVIDIA' | 'Intel' | 'AMD';
+  name: string | null;
+  utilization_percent: number | null;
+  encoder_percent: number | null;
+  decoder_percent: number | null;
+  memory_used_bytes: number | null;
+  memory_total_bytes: number | null;
+  temperature_c: number | null;
+}
+
+export interface ProcessUsage {
+  pid: number;
+  kind: 'stream' | 'recording' | 'playback';
+  camera_id: number | null;
+  cpu_percent: number; // 100 per fully used core
+  memory_bytes: number;
+}
+
+// Also the payload of the "resource-usage" event (every monitoring.resourceIntervalSecs)
+export interface ResourceUsage {
+  sampled_at: string;
+  cpu_percent: number; // all cores, 0-100
+  cpu_count: number;
+  memory_used_bytes: number;
+  memory_total_bytes: number;
+  gpus: GpuUsage[];
+  processes: ProcessUsage[];
+  ffmpeg_cpu_percent: number;
+  ffmpeg_memory_bytes: number;
+  warnings: string[]; // resources at 90% or more
+}
+
+export const getResourceUsage = async (): Promise<ResourceUsage> => {
+  return await invoke('get_resource_usage');
+};
+
+// Result of exportDiagnostics
+export interface DiagnosticsBundle {
+  path: string;
+  files: string[]; // names inside the zip
+  bytes: number;
+}
+
+// Zips recent incidents and events, settings (secrets masked), the database schema, GPU detection,
+// the FFmpeg version and a camera summary into destPath for a bug report
+export const exportDiagnostics = async (destPath: string): Promise<DiagnosticsBundle> => {
+  return await invoke('export_diagnostics', { destPath });
+};
+
+// Payload of ping() and the "backend-heartbeat" event, sent every intervalSecs seconds
+export interface BackendHeartbeat {
+  sentAt: string;
+  intervalSecs: number;
+  uptimeSecs: number;
+  activeStreams: number;
+  activeRecordings: number;
+  activePlaybacks: number;
+  dbOk: boolean;
+  dbError: string | null;
+  ffmpegAvailable: boolean;
+  problems: string[]; // empty while the backend is healthy
+}
+
+// Works without a login, so the UI can tell a dead backend from a signed-out one
+export const ping = async (): Promise<BackendHeartbeat> => {
+  return await invoke('ping');
+};
+
 export interface EncoderSettings {
   id: number;
   encoderMode: 'Auto' | 'GpuOnly' | 'CpuOnly';
@@ -211,7 +1939,8 @@ export const updateEncoderSettings = async (settings: UpdateEncoderSettings): Pr
 
 export interface RecordingSchedule {
   id: number;
-  camera_id: number;
+  camera_id: number | null; // null for group schedules
+  group_id: number | null;
   name: string;
   cron_expression: string;
   duration_minutes: number;
@@ -219,12 +1948,45 @@ export interface RecordingSchedule {
   is_enabled: boolean;
   created_at: string;
   updated_at: string;
+  schedule_type: 'cron' | 'weekly';
+  weekly_blocks: WeeklyBlock[] | null; // set for weekly schedules
+  action: ScheduleAction;
+  retention_days: number | null; // snapshot schedules: captures older than this are deleted
   camera_name: string | null;
+  group_name: string | null;
+  is_paused: boolean; // all schedules are paused; runs are skipped until resumed
   next_run: string | null; // "active" if will run, null if disabled/no future runs
 }
 
+// "snapshot" schedules capture a still image on each trigger instead of recording
+export type ScheduleAction = 'record' | 'snapshot';
+
+// Time range recorded on each of the given days, e.g. Mon-Fri 09:00-18:00
+export interface WeeklyBlock {
+  days: number[]; // ISO weekdays: 1 = Monday .. 7 = Sunday
+  start: string; // "HH:MM"
+  end: string; // "HH:MM"; at or before start means it ends the next day
+}
+
+export interface NewWeeklySchedule {
+  camera_id?: number | null; // set one of camera_id and group_id
+  group_id?: number | null;
+  name: string;
+  blocks: WeeklyBlock[];
+  fps: number | null;
+  is_enabled: boolean;
+}
+
+export interface UpdateWeeklySchedule {
+  name?: string;
+  blocks?: WeeklyBlock[]; // also converts a cron schedule into a weekly one
+  fps?: number;
+  is_enabled?: boolean;
+}
+
 export interface NewRecordingSchedule {
-  camera_id: number;
+  camera_id?: number | null; // set one of camera_id and group_id
+  group_id?: number | null;  // runs on every camera of the group, each tracked separately
   name: string;
   cron_expression: string;
   duration_minutes: number;
@@ -256,10 +2018,217 @@ export const deleteRecordingSchedule = async (id: number): Promise<void> => {
   await invoke('delete_recording_schedule', { id });
 };
 
+export const addWeeklySchedule = async (schedule: NewWeeklySchedule): Promise<RecordingSchedule> => {
+  return await invoke('add_weekly_schedule', { schedule });
+};
+
+export const updateWeeklySchedule = async (id: number, updates: UpdateWeeklySchedule): Promise<RecordingSchedule> => {
+  return await invoke('update_weekly_schedule', { id, updates });
+};
+
+export interface NewSnapshotSchedule {
+  camera_id: number;
+  name: string;
+  cron_expression?: string; // either a cron expression ...
+  interval_minutes?: number; // ... or an interval dividing an hour or a day evenly
+  retention_days?: number | null;
+  is_enabled: boolean;
+}
+
+export interface UpdateSnapshotSchedule {
+  name?: string;
+  cron_expression?: string;
+  interval_minutes?: number;
+  retention_days?: number; // 0 keeps snapshots forever
+  is_enabled?: boolean;
+}
+
+export interface Snapshot {
+  id: number;
+  camera_id: number;
+  schedule_id: number | null; // null for manual captures
+  filename: string; // relative to the recordings dir
+  captured_at: string;
+  file_size: number | null;
+  camera_name: string | null;
+}
+
+export const addSnapshotSchedule = async (schedule: NewSnapshotSchedule): Promise<RecordingSchedule> => {
+  return await invoke('add_snapshot_schedule', { schedule });
+};
+
+export const updateSnapshotSchedule = async (id: number, updates: UpdateSnapshotSchedule): Promise<RecordingSchedule> => {
+  return await invoke('update_snapshot_schedule', { id, updates });
+};
+
+export const captureSnapshot = async (cameraId: number): Promise<Snapshot> => {
+  return await invoke('capture_snapshot', { cameraId });
+};
+
+// Current frame, from the running live stream when possible
+export interface CapturedFrame {
+  path: string;
+  from_live_stream: boolean;
+  snapshot: Snapshot | null; // library entry when no dest was given
+}
+
+// dest: .png/.jpg path to write to; without it the frame goes to the snapshots library as format
+export const captureFrame = async (
+  cameraId: number,
+  dest?: string,
+  format: 'jpeg' | 'png' = 'jpeg',
+): Promise<CapturedFrame> => {
+  return await invoke('capture_frame', { cameraId, dest, format });
+};
+
+export const getSnapshots = async (cameraId?: number, scheduleId?: number, limit?: number): Promise<Snapshot[]> => {
+  return await invoke('get_snapshots', { cameraId, scheduleId, limit });
+};
+
+export const deleteSnapshot = async (id: number): Promise<void> => {
+  await invoke('delete_snapshot', { id });
+};
+
+// Snapshot images are served next to recordings by the local HTTP server
+export const getSnapshotUrl = async (snapshot: Snapshot): Promise<string> => {
+  return getRecordingFileUrl(snapshot.filename, await getServerToken());
+};
+
 export const toggleSchedule = async (id: number, enabled: boolean): Promise<RecordingSchedule> => {
   return await invoke('toggle_schedule', { id, enabled });
 };
 
 export const getRecordingCameras = async (): Promise<number[]> => {
   return await invoke('get_recording_cameras');
-};
\ No newline at end of file
+};
+
+// Live state of a camera's streaming and recording processes
+export interface PipelineStatus {
+  cameraId: number;
+  streaming: boolean;
+  streamUrl: string | null;
+  streamStartedAt: string | null;
+  streamUptimeSecs: number | null;
+  streamEncoder: string | null;
+  streamGpu: boolean | null;
+  streamCpuFallback: string | null; // set once the GPU encoder failed and the stream was degraded to the CPU
+  streamDowngrade: number; // load budget: 0 full quality, 1 half frame rate, 2 also half resolution
+  streamRestartCount: number; // starts after the first one in this session
+  recording: boolean;
+  recordingId: number | null;
+  recordingStartedAt: string | null;
+  recordingElapsedSecs: number | null;
+  recordingFileSize: number | null; // bytes written so far
+  recordingPaused: boolean;
+  recordingStopsAt: string | null; // set when a manual recording has a max duration
+  lastError: string | null;
+  lastErrorAt: string | null;
+}
+
+export const getPipelineStatus = async (): Promise<PipelineStatus[]> => {
+  return await invoke('get_pipeline_status');
+};
+
+// Encoding load in megapixels per second against the encoder.loadBudget setting
+export interface LoadBudgetStatus {
+  budget: number; // 0 for no budget
+  load: number;
+  cameras: CameraLoad[]; // cameras streaming or recording
+}
+
+export interface CameraLoad {
+  cameraId: number;
+  priority: CameraPriority;
+  streamLoad: number | null;    // null when not streaming
+  recordingLoad: number | null; // null when not recording; recordings are never downgraded
+  streamDowngrade: number;
+}
+
+export const getLoadBudget = async (): Promise<LoadBudgetStatus> => {
+  return await invoke('get_load_budget');
+};
+
+// Payload of the "stream-downgraded" event: the stream restarts at the new level (0 when restored)
+export interface StreamDowngradedEvent {
+  cameraId: number;
+  level: number;
+}
+
+export interface PipelineCleanup {
+  cameraId: number;
+  streamKilled: boolean;
+  recordingKilled: boolean;
+  streamDirRemoved: boolean;
+  recordingsRemoved: number[]; // unfinished recording rows; their files are kept
+  errors: string[];
+}
+
+// For a pipeline stopStream/stopRecording can't stop: kills its FFmpeg and cleans up after it.
+// Without a camera id every camera with a process, stream directory or unfinished recording.
+export const forceCleanup = async (cameraId?: number): Promise<PipelineCleanup[]> => {
+  return await invoke('force_cleanup', { cameraId: cameraId ?? null });
+};
+
+// Payload of the "encoder-fallback" event: the stream was restarted with the CPU encoder
+// (until the encoder settings are changed)
+export interface EncoderFallbackEvent {
+  cameraId: number;
+  encoder: string;
+  reason: string;
+}
+
+export interface SchedulerSettings {
+  id: number;
+  maxConcurrentRecordings: number | null; // null for unlimited
+  maxDeferMinutes: number; // how long a schedule may wait for a free slot
+  staggerSeconds: number; // minimum gap between scheduled starts
+  schedulesEnabled: boolean; // false while all schedules are paused
+}
+
+export interface UpdateSchedulerSettings {
+  maxConcurrentRecordings?: number; // 0 removes the limit
+  maxDeferMinutes?: number;
+  staggerSeconds?: number;
+  schedulesEnabled?: boolean;
+}
+
+export interface ScheduleRun {
+  id: number;
+  schedule_id: number;
+  camera_id: number;
+  scheduled_at: string;
+  started_at: string | null;
+  finished_at: string | null;
+  planned_stop_at: string | null;
+  status: 'pending' | 'queued' | 'recording' | 'completed' | 'deferred' | 'failed' | 'interrupted';
+  detail: string | null;
+}
+
+// Payload of the "schedule-deferred" event
+export interface ScheduleDeferredEvent {
+  scheduleId: number;
+  cameraId: number;
+  name: string;
+  reason: string;
+}
+
+export const getSchedulerSettings = async (): Promise<SchedulerSettings> => {
+  return await invoke('get_scheduler_settings');
+};
+
+export const updateSchedulerSettings = async (settings: UpdateSchedulerSettings): Promise<SchedulerSettings> => {
+  return await invoke('update_scheduler_settings', { settings });
+};
+
+// Paused schedules skip their runs (also after a restart) until resumed
+export const pauseSchedules = async (): Promise<SchedulerSettings> => {
+  return await invoke('pause_schedules');
+};
+
+export const resumeSchedules = async (): Promise<SchedulerSettings> => {
+  return await invoke('resume_schedules');
+};
+
+export const getScheduleHistory = async (scheduleId?: number, limit?: number): Promise<ScheduleRun[]> => {
+  return await invoke('get_schedule_history', { scheduleId, limit });
+};
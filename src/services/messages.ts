@@ -0,0 +1,30 @@
+import type { AppMessage } from './api';
+
+type Params = Record<string, unknown>;
+
+// Japanese texts of backend message codes; codes missing here fall back to the English
+// message rendered by the backend (src-tauri/src/messages.rs)
+const ja: Record<string, (p: Params) => string> = {
+  'camera.not_found': () => 'カメラが見つかりません',
+  'camera.not_onvif': () => 'ONVIF カメラではありません',
+  'time_sync.unsupported': (p) => `${p.cameraType} カメラは時刻同期に対応していません`,
+  'time_sync.invalid_camera_time': () => 'カメラの時刻形式が不正です',
+  'time_sync.verified': (p) => `カメラの時刻を同期しました（${p.adjustedSecs}秒調整、確認済み）`,
+  'time_sync.not_applied': (p) => `カメラの時刻が正しく設定されていない可能性があります（同期前の差: ${p.beforeDiffSecs}秒、同期後の差: ${p.afterDiffSecs}秒）`,
+  'time_sync.already_synced': (p) => `カメラの時刻はすでに同期されています（差: ${p.diffSecs}秒）`,
+  'time_sync.unverified': (p) => `時刻設定を送信しました（${p.adjustedSecs}秒調整、確認できませんでした）`,
+  'time_sync.stream_restarted': () => '時刻変更後にライブ映像が停止したため再起動しました',
+  'ptz.moving': () => '移動中',
+  'ptz.stopped': () => '停止しました',
+  'network.reachable': (p) => `カメラは ${p.host} で応答しています`,
+  'network.dhcp_moved': () => 'カメラが以前のアドレスで応答しません。DHCP でアドレスが変わった可能性があります。検出でカメラを探してホストを更新してください',
+  'network.unreachable': (p) => `カメラが ${p.timeoutSecs} 秒以内に ${p.host} で応答しませんでした。アドレス、プレフィックス長、ゲートウェイを確認してください`,
+};
+
+const useJapanese = () => navigator.language.toLowerCase().startsWith('ja');
+
+// Text of a backend message in the UI language
+export const localizeMessage = (message: AppMessage): string => {
+  const translate = useJapanese() ? ja[message.code] : undefined;
+  return translate ? translate((message.params ?? {}) as Params) : message.message;
+};